@@ -16,12 +16,14 @@ pub mod hash;
 mod header;
 mod macros;
 pub mod prelude;
+pub mod protocol;
 pub mod receipt;
 pub mod signature;
 pub mod state_update;
 pub mod test_utils;
 pub mod transaction;
 pub mod trie;
+pub mod typed_data;
 
 pub use signature::BlockCommitmentSignature;
 pub use state_update::StateUpdate;
@@ -35,6 +37,58 @@ impl ContractAddress {
     /// It is used by starknet to store values for smart contracts to access
     /// using syscalls. For example the block hash.
     pub const ONE: ContractAddress = contract_address!("0x1");
+
+    /// Produces a "0x" prefixed hex string of 64 digits whose letters encode a checksum in their
+    /// case, similar to Ethereum's EIP-55.
+    ///
+    /// A letter is upper case if the matching nibble of the Starknet Keccak of the address'
+    /// minimal big-endian bytes is 8 or more. This matches `getChecksumAddress` of starknet.js.
+    pub fn to_checksum_hex_str(&self) -> String {
+        let bytes = self.0.as_be_bytes();
+        // Zero is hashed as a single byte.
+        let first = bytes
+            .iter()
+            .position(|b| *b != 0)
+            .unwrap_or(bytes.len() - 1);
+        let hash = pathfinder_crypto::hash::starknet_keccak(&bytes[first..]).to_be_bytes();
+
+        let mut digits = format!("{:x}", self.0).into_bytes();
+        for (i, digit) in digits.iter_mut().enumerate() {
+            let nibble = if i % 2 == 0 {
+                hash[i / 2] >> 4
+            } else {
+                hash[i / 2] & 0x0f
+            };
+            if nibble >= 8 {
+                digit.make_ascii_uppercase();
+            }
+        }
+
+        // Unwrap is safe as the buffer contains hex digits only.
+        format!("0x{}", String::from_utf8(digits).unwrap())
+    }
+
+    /// Parses an address from a hex string, verifying its checksum as produced by
+    /// [ContractAddress::to_checksum_hex_str].
+    ///
+    /// Strings whose letters are all lower case or all upper case carry no checksum and are
+    /// accepted as is. Leading zeros may be omitted.
+    pub fn from_checksum_hex_str(hex_str: &str) -> anyhow::Result<Self> {
+        let felt = Felt::from_hex_str(hex_str)?;
+        let address = Self::new(felt).context("Address has more than 251 bits")?;
+
+        let digits = hex_str.strip_prefix("0x").unwrap_or(hex_str);
+        let has_lower = digits.bytes().any(|x| x.is_ascii_lowercase());
+        let has_upper = digits.bytes().any(|x| x.is_ascii_uppercase());
+        if has_lower && has_upper {
+            anyhow::ensure!(
+                address.to_checksum_hex_str().ends_with(digits),
+                "Invalid address checksum"
+            );
+        }
+
+        Ok(address)
+    }
 }
 
 // Bytecode and entry point list of a class
@@ -54,10 +108,15 @@ impl EntryPoint {
     ///
     /// See: <https://docs.starknet.io/documentation/architecture_and_concepts/Smart_Contracts/contract-classes/>
     pub fn hashed(input: &[u8]) -> Self {
-        use sha3::Digest;
-        EntryPoint(truncated_keccak(<[u8; 32]>::from(sha3::Keccak256::digest(
-            input,
-        ))))
+        EntryPoint(pathfinder_crypto::hash::starknet_keccak(input))
+    }
+
+    /// Returns the selector of the entry point with the given name.
+    ///
+    /// Unlike [EntryPoint::hashed], this maps the names of Cairo 0 default entry points to the
+    /// zero selector.
+    pub fn from_name(name: &str) -> Self {
+        EntryPoint(pathfinder_crypto::hash::selector_from_name(name))
     }
 
     /// The constructor [EntryPoint], defined as the truncated keccak of b"constructor".
@@ -94,16 +153,11 @@ impl StateCommitment {
 
 impl StorageAddress {
     pub fn from_name(input: &[u8]) -> Self {
-        use sha3::Digest;
-        Self(truncated_keccak(<[u8; 32]>::from(sha3::Keccak256::digest(
-            input,
-        ))))
+        Self(pathfinder_crypto::hash::starknet_keccak(input))
     }
 
     pub fn from_map_name_and_key(name: &[u8], key: Felt) -> Self {
-        use sha3::Digest;
-
-        let intermediate = truncated_keccak(<[u8; 32]>::from(sha3::Keccak256::digest(name)));
+        let intermediate = pathfinder_crypto::hash::starknet_keccak(name);
         let value = pathfinder_crypto::hash::pedersen_hash(intermediate, key);
 
         let value = primitive_types::U256::from_big_endian(value.as_be_bytes());
@@ -577,14 +631,9 @@ where
     }
 }
 
-/// See:
-/// <https://github.com/starkware-libs/cairo-lang/blob/64a7f6aed9757d3d8d6c28bd972df73272b0cb0a/src/starkware/starknet/public/abi.py#L21-L26>
-pub fn truncated_keccak(mut plain: [u8; 32]) -> Felt {
-    // python code masks with (2**250 - 1) which starts 0x03 and is followed by 31 0xff in be
-    // truncation is needed not to overflow the field element.
-    plain[0] &= 0x03;
-    Felt::from_be_bytes(plain).expect("cannot overflow: smaller than modulus")
-}
+pub use pathfinder_crypto::hash::truncated_keccak;
+
+const CONTRACT_CLASS_HASH_VERSION: pathfinder_crypto::Felt = felt_bytes!(b"CONTRACT_CLASS_LEAF_V0");
 
 /// Calculate class commitment tree leaf hash value.
 ///
@@ -592,8 +641,6 @@ pub fn truncated_keccak(mut plain: [u8; 32]) -> Felt {
 pub fn calculate_class_commitment_leaf_hash(
     compiled_class_hash: CasmHash,
 ) -> ClassCommitmentLeafHash {
-    const CONTRACT_CLASS_HASH_VERSION: pathfinder_crypto::Felt =
-        felt_bytes!(b"CONTRACT_CLASS_LEAF_V0");
     ClassCommitmentLeafHash(
         pathfinder_crypto::hash::poseidon_hash(
             CONTRACT_CLASS_HASH_VERSION.into(),
@@ -603,10 +650,57 @@ pub fn calculate_class_commitment_leaf_hash(
     )
 }
 
+/// Same as [calculate_class_commitment_leaf_hash], for many classes at once.
+///
+/// The hashes are computed in batches, which is considerably faster when hashing the classes of
+/// many blocks.
+pub fn calculate_class_commitment_leaf_hashes(
+    compiled_class_hashes: &[CasmHash],
+) -> Vec<ClassCommitmentLeafHash> {
+    use pathfinder_crypto::hash::{poseidon_permute_many, PoseidonState};
+    use pathfinder_crypto::MontFelt;
+
+    // The same states as computed by `poseidon_hash`.
+    let mut states = compiled_class_hashes
+        .iter()
+        .map(|casm| {
+            [
+                CONTRACT_CLASS_HASH_VERSION.into(),
+                casm.0.into(),
+                MontFelt::TWO,
+            ]
+        })
+        .collect::<Vec<PoseidonState>>();
+    poseidon_permute_many(&mut states);
+
+    states
+        .into_iter()
+        .map(|state| ClassCommitmentLeafHash(state[0].into()))
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{felt, CallParam, ClassHash, ContractAddress, ContractAddressSalt};
 
+    #[test]
+    fn class_commitment_leaf_hashes() {
+        use crate::{calculate_class_commitment_leaf_hash, calculate_class_commitment_leaf_hashes};
+
+        let casm_hashes = (0..100u64)
+            .map(|i| crate::CasmHash(i.into()))
+            .collect::<Vec<_>>();
+        let expected = casm_hashes
+            .iter()
+            .map(|casm| calculate_class_commitment_leaf_hash(*casm))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            calculate_class_commitment_leaf_hashes(&casm_hashes),
+            expected
+        );
+    }
+
     #[test]
     fn constructor_entry_point() {
         use crate::truncated_keccak;
@@ -618,6 +712,19 @@ mod tests {
         let expected = EntryPoint(truncated_keccak(<[u8; 32]>::from(keccak.finalize())));
 
         assert_eq!(EntryPoint::CONSTRUCTOR, expected);
+        assert_eq!(EntryPoint::hashed(b"constructor"), expected);
+    }
+
+    #[test]
+    fn default_entry_point_selectors() {
+        use crate::EntryPoint;
+
+        assert_eq!(EntryPoint::from_name("__default__"), EntryPoint::ZERO);
+        assert_eq!(EntryPoint::from_name("__l1_default__"), EntryPoint::ZERO);
+        assert_eq!(
+            EntryPoint::from_name("transfer"),
+            EntryPoint::hashed(b"transfer")
+        );
     }
 
     mod starknet_version {
@@ -688,4 +795,60 @@ mod tests {
         );
         assert_eq!(actual_contract_address, expected_contract_address);
     }
+
+    mod checksum {
+        use super::*;
+
+        // Computed by starknet.js' `getChecksumAddress`.
+        const CHECKSUM: &str = "0x02Fd23d9182193775423497fc0c472E156C57C69E4089A1967fb288A2d84e914";
+
+        fn address() -> ContractAddress {
+            ContractAddress(felt!(
+                "0x2fd23d9182193775423497fc0c472e156c57c69e4089a1967fb288a2d84e914"
+            ))
+        }
+
+        #[test]
+        fn to_checksum_hex_str() {
+            assert_eq!(address().to_checksum_hex_str(), CHECKSUM);
+            assert_eq!(
+                ContractAddress::ZERO.to_checksum_hex_str(),
+                format!("0x{}", "0".repeat(64))
+            );
+        }
+
+        #[test]
+        fn from_checksum_hex_str() {
+            assert_eq!(
+                ContractAddress::from_checksum_hex_str(CHECKSUM).unwrap(),
+                address()
+            );
+            // Without leading zeros.
+            assert_eq!(
+                ContractAddress::from_checksum_hex_str(&CHECKSUM.replace("0x0", "0x")).unwrap(),
+                address()
+            );
+        }
+
+        #[test]
+        fn without_checksum() {
+            let lower = CHECKSUM.to_lowercase();
+            let upper = format!("0x{}", CHECKSUM[2..].to_uppercase());
+
+            assert_eq!(
+                ContractAddress::from_checksum_hex_str(&lower).unwrap(),
+                address()
+            );
+            assert_eq!(
+                ContractAddress::from_checksum_hex_str(&upper).unwrap(),
+                address()
+            );
+        }
+
+        #[test]
+        fn invalid_checksum() {
+            let invalid = CHECKSUM.replace("Fd23", "fD23");
+            ContractAddress::from_checksum_hex_str(&invalid).unwrap_err();
+        }
+    }
 }
@@ -0,0 +1,58 @@
+//! Starknet protocol changes which depend on the version of a block.
+//!
+//! Hashing, verifying or re-executing a historical block must use the rules that were in
+//! effect when it was produced. Instead of comparing versions wherever such a rule is
+//! applied, each change is registered here along with the version that introduced it.
+use crate::StarknetVersion;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProtocolChange {
+    /// The transaction commitment includes the signatures of all transactions, instead of
+    /// only those of invoke transactions.
+    TransactionCommitmentSignatures,
+    /// Blocks can be re-executed locally, e.g. to trace them. Traces of older blocks
+    /// have to be fetched from the feeder gateway.
+    LocalExecution,
+    /// Execution uses blockifier's latest versioned constants instead of those of 0.13.0.
+    LatestVersionedConstants,
+}
+
+impl ProtocolChange {
+    /// The Starknet version which introduced this change.
+    pub const fn introduced_in(self) -> semver::Version {
+        match self {
+            ProtocolChange::TransactionCommitmentSignatures => semver::Version::new(0, 11, 1),
+            ProtocolChange::LocalExecution => semver::Version::new(0, 13, 0),
+            ProtocolChange::LatestVersionedConstants => semver::Version::new(0, 13, 1),
+        }
+    }
+}
+
+impl StarknetVersion {
+    /// Returns true if blocks of this version follow the rules introduced by `change`.
+    ///
+    /// Blocks without a version predate versioning, and therefore all registered changes.
+    pub fn supports(&self, change: ProtocolChange) -> anyhow::Result<bool> {
+        Ok(self
+            .parse_as_semver()?
+            .is_some_and(|version| version >= change.introduced_in()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn supports() {
+        let change = ProtocolChange::LocalExecution;
+
+        assert!(!StarknetVersion::default().supports(change).unwrap());
+        assert!(!StarknetVersion::new(0, 12, 3).supports(change).unwrap());
+        assert!(StarknetVersion::new(0, 13, 0).supports(change).unwrap());
+        assert!(StarknetVersion::new(0, 13, 1).supports(change).unwrap());
+        assert!(StarknetVersion::from("0.13.0.1".to_owned())
+            .supports(change)
+            .unwrap());
+    }
+}
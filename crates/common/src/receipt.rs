@@ -1,3 +1,5 @@
+use primitive_types::H256;
+
 use crate::prelude::*;
 
 #[derive(Clone, Default, Debug, PartialEq, Eq)]
@@ -31,6 +33,33 @@ pub struct L2ToL1Message {
     pub to_address: EthereumAddress,
 }
 
+impl L2ToL1Message {
+    /// The hash under which this message is registered by the Starknet core contract on L1,
+    /// and which must be supplied when consuming it.
+    pub fn calculate_message_hash(&self) -> H256 {
+        use sha3::{Digest, Keccak256};
+
+        let mut hash = Keccak256::new();
+
+        hash.update(self.from_address.0.as_be_bytes());
+        // Pad the ethereum address to 32 bytes to match a felt.
+        hash.update([0u8; 12]);
+        hash.update(self.to_address.0.as_bytes());
+
+        // Pad the u64 to 32 bytes to match a felt.
+        hash.update([0u8; 24]);
+        hash.update((self.payload.len() as u64).to_be_bytes());
+
+        for elem in &self.payload {
+            hash.update(elem.0.as_be_bytes());
+        }
+
+        let hash = <[u8; 32]>::from(hash.finalize());
+
+        hash.into()
+    }
+}
+
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub struct ExecutionResources {
     pub builtins: BuiltinCounters,
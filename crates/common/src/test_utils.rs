@@ -104,10 +104,10 @@ pub mod metrics {
         }
 
         fn register_gauge(&self, _: &Key) -> Gauge {
-            unimplemented!()
+            Gauge::noop()
         }
         fn register_histogram(&self, _: &Key) -> Histogram {
-            unimplemented!()
+            Histogram::noop()
         }
     }
 
@@ -60,23 +60,8 @@ pub enum TransactionVariant {
 impl TransactionVariant {
     #[must_use = "Should act on verification result"]
     fn verify_hash(&self, chain_id: ChainId, expected: TransactionHash) -> bool {
-        if expected == self.calculate_hash(chain_id, false) {
-            return true;
-        }
-
-        // Some transaction variants had a different hash calculation in ancient times.
-        if Some(expected) == self.calculate_legacy_hash(chain_id) {
-            return true;
-        }
-
-        // L1 Handlers had a specific hash calculation for Starknet v0.7 blocks.
-        if let Self::L1Handler(l1_handler) = self {
-            if expected == l1_handler.calculate_v07_hash(chain_id) {
-                return true;
-            }
-        }
-
-        false
+        expected == compute_transaction_hash(self, chain_id)
+            || compute_legacy_transaction_hashes(self, chain_id).contains(&expected)
     }
 
     pub fn calculate_hash(&self, chain_id: ChainId, query_only: bool) -> TransactionHash {
@@ -94,18 +79,41 @@ impl TransactionVariant {
             TransactionVariant::L1Handler(tx) => tx.calculate_hash(chain_id),
         }
     }
+}
 
-    /// Some variants had a different hash calculations for blocks around Starknet v0.8
-    /// and earlier. The hash excluded the transaction version and nonce.
-    fn calculate_legacy_hash(&self, chain_id: ChainId) -> Option<TransactionHash> {
-        let hash = match self {
-            TransactionVariant::Deploy(tx) => tx.calculate_legacy_hash(chain_id),
-            TransactionVariant::InvokeV0(tx) => tx.calculate_legacy_hash(chain_id),
-            TransactionVariant::L1Handler(tx) => tx.calculate_legacy_hash(chain_id),
-            _ => return None,
-        };
+/// Computes the hash of the transaction, as it is included in a block.
+///
+/// This is the hash of every transaction type and version as specified by the current protocol,
+/// and is what clients sign. Transactions from blocks of Starknet v0.8 and earlier may instead
+/// have one of the hashes returned by [compute_legacy_transaction_hashes].
+pub fn compute_transaction_hash(
+    transaction: &TransactionVariant,
+    chain_id: ChainId,
+) -> TransactionHash {
+    transaction.calculate_hash(chain_id, false)
+}
 
-        Some(hash)
+/// Computes the hashes which the transaction may have had in ancient blocks instead of the one
+/// returned by [compute_transaction_hash].
+///
+/// These are
+/// - the hash excluding the transaction version and nonce, used by deploy, invoke v0 and
+///   L1 handler transactions in blocks around Starknet v0.8 and earlier, and
+/// - the hash which L1 handlers had in Starknet v0.7 blocks.
+///
+/// The list is empty for all other transactions.
+pub fn compute_legacy_transaction_hashes(
+    transaction: &TransactionVariant,
+    chain_id: ChainId,
+) -> Vec<TransactionHash> {
+    match transaction {
+        TransactionVariant::Deploy(tx) => vec![tx.calculate_legacy_hash(chain_id)],
+        TransactionVariant::InvokeV0(tx) => vec![tx.calculate_legacy_hash(chain_id)],
+        TransactionVariant::L1Handler(tx) => vec![
+            tx.calculate_legacy_hash(chain_id),
+            tx.calculate_v07_hash(chain_id),
+        ],
+        _ => Vec::new(),
     }
 }
 
@@ -804,6 +812,43 @@ mod tests {
         assert!(transaction.verify_hash(chain_id));
     }
 
+    #[rstest::rstest]
+    #[test]
+    #[case::declare_v0(declare_v0(), ChainId::GOERLI_INTEGRATION)]
+    #[case::declare_v1(declare_v1(), ChainId::SEPOLIA_TESTNET)]
+    #[case::declare_v2(declare_v2(), ChainId::SEPOLIA_TESTNET)]
+    #[case::declare_v3(declare_v3(), ChainId::GOERLI_INTEGRATION)]
+    #[case::deploy(deploy(), ChainId::GOERLI_TESTNET)]
+    #[case::deploy_account_v1(deploy_account_v1(), ChainId::MAINNET)]
+    #[case::deploy_account_v3(deploy_account_v3(), ChainId::GOERLI_INTEGRATION)]
+    #[case::invoke_v0(invoke_v0(), ChainId::GOERLI_TESTNET)]
+    #[case::invoke_v1(invoke_v1(), ChainId::MAINNET)]
+    #[case::invoke_v3(invoke_v3(), ChainId::SEPOLIA_TESTNET)]
+    #[case::l1_handler(l1_handler(), ChainId::MAINNET)]
+    fn compute_hash(#[case] transaction: Transaction, #[case] chain_id: ChainId) {
+        assert_eq!(
+            compute_transaction_hash(&transaction.variant, chain_id),
+            transaction.hash
+        );
+    }
+
+    #[rstest::rstest]
+    #[test]
+    #[case::deploy_legacy(deploy_legacy(), ChainId::GOERLI_TESTNET)]
+    #[case::invoke_v0_legacy(invoke_v0_legacy(), ChainId::GOERLI_TESTNET)]
+    #[case::l1_handler_v07(l1_handler_v07(), ChainId::MAINNET)]
+    #[case::l1_handler_legacy(l1_handler_legacy(), ChainId::GOERLI_TESTNET)]
+    fn compute_legacy_hash(#[case] transaction: Transaction, #[case] chain_id: ChainId) {
+        assert_ne!(
+            compute_transaction_hash(&transaction.variant, chain_id),
+            transaction.hash
+        );
+        assert!(
+            compute_legacy_transaction_hashes(&transaction.variant, chain_id)
+                .contains(&transaction.hash)
+        );
+    }
+
     fn declare_v0() -> Transaction {
         Transaction {
             hash: transaction_hash!(
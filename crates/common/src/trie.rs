@@ -1,3 +1,6 @@
+use std::collections::HashMap;
+
+use anyhow::Context;
 use bitvec::prelude::Msb0;
 use bitvec::vec::BitVec;
 use pathfinder_crypto::Felt;
@@ -29,3 +32,90 @@ impl TrieNode {
         }
     }
 }
+
+/// A node of a Starknet patricia-merkle trie as committed by the merkle-tree crate.
+///
+/// Children which are already stored are referred to by their index, while new children are
+/// referred to by their hash.
+#[derive(Clone, Debug)]
+pub enum Node {
+    Binary {
+        left: Child,
+        right: Child,
+    },
+    Edge {
+        child: Child,
+        path: BitVec<u8, Msb0>,
+    },
+    LeafBinary,
+    LeafEdge {
+        path: BitVec<u8, Msb0>,
+    },
+}
+
+/// A child of a [Node].
+#[derive(Clone, Debug)]
+pub enum Child {
+    Id(u64),
+    Hash(Felt),
+}
+
+/// A [Node] as stored, with all children referred to by their index.
+#[derive(Clone, Debug, PartialEq)]
+pub enum StoredNode {
+    Binary { left: u64, right: u64 },
+    Edge { child: u64, path: BitVec<u8, Msb0> },
+    LeafBinary,
+    LeafEdge { path: BitVec<u8, Msb0> },
+}
+
+impl StoredNode {
+    /// Returns the indices of the node's stored children. Leaves are stored in-line
+    /// and are therefore not included.
+    pub fn children(&self) -> impl Iterator<Item = u64> {
+        let (first, second) = match self {
+            Self::Binary { left, right } => (Some(*left), Some(*right)),
+            Self::Edge { child, .. } => (Some(*child), None),
+            Self::LeafBinary | Self::LeafEdge { .. } => (None, None),
+        };
+
+        first.into_iter().chain(second)
+    }
+}
+
+impl Node {
+    /// Converts the node to its stored form, using `indices` for the children which are only
+    /// known by their hash.
+    pub fn as_stored(&self, indices: &HashMap<Felt, u64>) -> anyhow::Result<StoredNode> {
+        let node = match self {
+            Node::Binary { left, right } => {
+                let left = match left {
+                    Child::Id(id) => *id,
+                    Child::Hash(hash) => *indices.get(hash).context("Left child index missing")?,
+                };
+
+                let right = match right {
+                    Child::Id(id) => *id,
+                    Child::Hash(hash) => *indices.get(hash).context("Right child index missing")?,
+                };
+
+                StoredNode::Binary { left, right }
+            }
+            Node::Edge { child, path } => {
+                let child = match child {
+                    Child::Id(id) => id,
+                    Child::Hash(hash) => indices.get(hash).context("Child index missing")?,
+                };
+
+                StoredNode::Edge {
+                    child: *child,
+                    path: path.clone(),
+                }
+            }
+            Node::LeafEdge { path } => StoredNode::LeafEdge { path: path.clone() },
+            Node::LeafBinary => StoredNode::LeafBinary,
+        };
+
+        Ok(node)
+    }
+}
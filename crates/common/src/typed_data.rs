@@ -0,0 +1,777 @@
+//! Hashing of off-chain typed data as specified by
+//! [SNIP-12](https://github.com/starknet-io/SNIPs/blob/main/SNIPS/snip-12.md).
+//!
+//! Both the legacy revision 0, which uses the Pedersen hash, and revision 1, which uses the
+//! Poseidon hash and adds enums, strings and preset types, are supported. Where the specification
+//! is ambiguous this follows the reference implementation in starknet.js.
+use std::collections::{BTreeSet, HashMap};
+
+use anyhow::Context;
+use num_bigint::BigUint;
+use pathfinder_crypto::hash::{poseidon_hash, poseidon_hash_many, starknet_keccak, HashChain};
+use pathfinder_crypto::{Felt, MontFelt};
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::ContractAddress;
+
+/// A typed data message, as signed by wallets.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct TypedData {
+    pub types: HashMap<String, Vec<TypeMember>>,
+    #[serde(rename = "primaryType")]
+    pub primary_type: String,
+    pub domain: Value,
+    pub message: Value,
+}
+
+/// A member of a struct type, or a variant of an enum type.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct TypeMember {
+    pub name: String,
+    pub r#type: String,
+    /// The type of the leaves of a `merkletree`, or the enum type of an `enum`.
+    #[serde(default)]
+    pub contains: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Revision {
+    /// The legacy revision, hashed with Pedersen.
+    V0,
+    /// Hashed with Poseidon.
+    V1,
+}
+
+impl Revision {
+    fn domain_type(self) -> &'static str {
+        match self {
+            Revision::V0 => "StarkNetDomain",
+            Revision::V1 => "StarknetDomain",
+        }
+    }
+
+    fn hash_many(self, values: &[Felt]) -> Felt {
+        match self {
+            Revision::V0 => {
+                let mut chain = HashChain::default();
+                for value in values {
+                    chain.update(*value);
+                }
+                chain.finalize()
+            }
+            Revision::V1 => {
+                let values = values
+                    .iter()
+                    .copied()
+                    .map(MontFelt::from)
+                    .collect::<Vec<_>>();
+                poseidon_hash_many(&values).into()
+            }
+        }
+    }
+
+    fn hash_pair(self, a: Felt, b: Felt) -> Felt {
+        match self {
+            Revision::V0 => pathfinder_crypto::hash::pedersen_hash(a, b),
+            Revision::V1 => poseidon_hash(a.into(), b.into()).into(),
+        }
+    }
+}
+
+impl TypedData {
+    /// The revision is given by the `revision` field of the domain, which defaults to 0.
+    pub fn revision(&self) -> anyhow::Result<Revision> {
+        let revision = match self.domain.get("revision") {
+            None => Revision::V0,
+            Some(revision) => match felt_from_value(revision)? {
+                x if x == Felt::ZERO => Revision::V0,
+                x if x == Felt::from_u64(1) => Revision::V1,
+                other => anyhow::bail!("Unsupported revision {other}"),
+            },
+        };
+
+        anyhow::ensure!(
+            self.types.contains_key(revision.domain_type()),
+            "The {} type is missing",
+            revision.domain_type()
+        );
+
+        Ok(revision)
+    }
+
+    /// Computes the hash which `account` signs for this message.
+    pub fn message_hash(&self, account: ContractAddress) -> anyhow::Result<Felt> {
+        let encoder = Encoder::new(self)?;
+
+        let domain = encoder
+            .struct_hash(encoder.revision.domain_type(), &self.domain)
+            .context("Hashing domain")?;
+        let message = encoder
+            .struct_hash(&self.primary_type, &self.message)
+            .context("Hashing message")?;
+
+        Ok(encoder.revision.hash_many(&[
+            short_string("StarkNet Message")?,
+            domain,
+            account.0,
+            message,
+        ]))
+    }
+
+    /// Encodes the type along with the types it references, which is what the type hash is
+    /// computed of.
+    pub fn encode_type(&self, name: &str) -> anyhow::Result<String> {
+        Encoder::new(self)?.encode_type(name)
+    }
+
+    pub fn type_hash(&self, name: &str) -> anyhow::Result<Felt> {
+        Encoder::new(self)?.type_hash(name)
+    }
+}
+
+/// The types revision 1 defines implicitly.
+fn preset_types() -> HashMap<String, Vec<TypeMember>> {
+    let member = |name: &str, r#type: &str| TypeMember {
+        name: name.to_owned(),
+        r#type: r#type.to_owned(),
+        contains: None,
+    };
+
+    HashMap::from([
+        (
+            "u256".to_owned(),
+            vec![member("low", "u128"), member("high", "u128")],
+        ),
+        (
+            "TokenAmount".to_owned(),
+            vec![
+                member("token_address", "ContractAddress"),
+                member("amount", "u256"),
+            ],
+        ),
+        (
+            "NftId".to_owned(),
+            vec![
+                member("collection_address", "ContractAddress"),
+                member("token_id", "u256"),
+            ],
+        ),
+    ])
+}
+
+struct Encoder {
+    revision: Revision,
+    types: HashMap<String, Vec<TypeMember>>,
+}
+
+impl Encoder {
+    fn new(typed_data: &TypedData) -> anyhow::Result<Self> {
+        let revision = typed_data.revision()?;
+        let mut types = typed_data.types.clone();
+        if revision == Revision::V1 {
+            types.extend(preset_types());
+        }
+
+        Ok(Self { revision, types })
+    }
+
+    fn members(&self, name: &str) -> anyhow::Result<&[TypeMember]> {
+        self.types
+            .get(name)
+            .map(Vec::as_slice)
+            .with_context(|| format!("Type {name} is not defined"))
+    }
+
+    /// The names of the types a member of the given type refers to.
+    fn referenced_types<'a>(&self, member: &'a TypeMember) -> Vec<&'a str> {
+        let r#type = member.r#type.as_str();
+        if let Some(element) = r#type.strip_suffix('*') {
+            return vec![element];
+        }
+
+        if self.revision == Revision::V1 {
+            if r#type == "enum" {
+                return member.contains.as_deref().into_iter().collect();
+            }
+            if let Some(tuple) = tuple_types(r#type) {
+                return tuple.map(|x| x.strip_suffix('*').unwrap_or(x)).collect();
+            }
+        }
+
+        vec![r#type]
+    }
+
+    fn encode_type(&self, name: &str) -> anyhow::Result<String> {
+        // The primary type comes first, followed by the types it references in alphabetical
+        // order.
+        let mut dependencies = BTreeSet::new();
+        let mut pending = vec![name];
+        while let Some(current) = pending.pop() {
+            for member in self.members(current)? {
+                for referenced in self.referenced_types(member) {
+                    if referenced != name
+                        && self.types.contains_key(referenced)
+                        && dependencies.insert(referenced)
+                    {
+                        pending.push(referenced);
+                    }
+                }
+            }
+        }
+
+        let escape = |x: &str| match self.revision {
+            Revision::V0 => x.to_owned(),
+            Revision::V1 => format!("\"{x}\""),
+        };
+
+        let mut encoded = String::new();
+        for r#type in std::iter::once(name).chain(dependencies) {
+            let members = self
+                .members(r#type)?
+                .iter()
+                .map(|member| {
+                    let target = match (self.revision, member.r#type.as_str()) {
+                        (Revision::V1, "enum") => member.contains.as_deref().unwrap_or_default(),
+                        (_, other) => other,
+                    };
+                    let target = match tuple_types(target) {
+                        Some(tuple) => {
+                            let tuple = tuple
+                                .map(|x| {
+                                    if x.is_empty() {
+                                        String::new()
+                                    } else {
+                                        escape(x)
+                                    }
+                                })
+                                .collect::<Vec<_>>();
+                            format!("({})", tuple.join(","))
+                        }
+                        None => escape(target),
+                    };
+                    format!("{}:{target}", escape(&member.name))
+                })
+                .collect::<Vec<_>>();
+
+            encoded.push_str(&format!("{}({})", escape(r#type), members.join(",")));
+        }
+
+        Ok(encoded)
+    }
+
+    fn type_hash(&self, name: &str) -> anyhow::Result<Felt> {
+        Ok(starknet_keccak(self.encode_type(name)?.as_bytes()))
+    }
+
+    fn struct_hash(&self, name: &str, data: &Value) -> anyhow::Result<Felt> {
+        let data = data
+            .as_object()
+            .with_context(|| format!("Value of type {name} is not an object"))?;
+
+        let mut values = vec![self.type_hash(name)?];
+        for member in self.members(name)? {
+            let value = data
+                .get(&member.name)
+                .filter(|x| !x.is_null())
+                .with_context(|| format!("Missing value for {name}.{}", member.name))?;
+            let value = self
+                .encode_value(&member.r#type, member.contains.as_deref(), value)
+                .with_context(|| format!("Encoding {name}.{}", member.name))?;
+            values.push(value);
+        }
+
+        Ok(self.revision.hash_many(&values))
+    }
+
+    fn encode_value(
+        &self,
+        r#type: &str,
+        contains: Option<&str>,
+        value: &Value,
+    ) -> anyhow::Result<Felt> {
+        if self.types.contains_key(r#type) {
+            return self.struct_hash(r#type, value);
+        }
+
+        if let Some(element) = r#type.strip_suffix('*') {
+            let values = value
+                .as_array()
+                .with_context(|| format!("Value of type {} is not an array", r#type))?
+                .iter()
+                .map(|x| self.encode_value(element, None, x))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            return Ok(self.revision.hash_many(&values));
+        }
+
+        match (self.revision, r#type) {
+            (Revision::V1, "enum") => {
+                let contains = contains.context("Enum does not specify the enum type")?;
+                self.encode_enum(contains, value)
+            }
+            (_, "merkletree") => {
+                let contains = contains.context("Merkle tree does not specify the leaf type")?;
+                anyhow::ensure!(
+                    !contains.ends_with('*'),
+                    "Merkle tree leaves cannot be arrays"
+                );
+                let leaves = value
+                    .as_array()
+                    .context("Value of merkle tree is not an array")?
+                    .iter()
+                    .map(|x| self.encode_value(contains, None, x))
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+                self.merkle_root(leaves)
+            }
+            (_, "selector") => {
+                let selector = value.as_str().context("Selector is not a string")?;
+                match selector.strip_prefix("0x") {
+                    Some(hex) if hex.chars().all(|x| x.is_ascii_hexdigit()) => {
+                        Felt::from_hex_str(selector).context("Parsing selector")
+                    }
+                    _ => Ok(starknet_keccak(selector.as_bytes())),
+                }
+            }
+            (Revision::V1, "string") => {
+                let string = value.as_str().context("Value of string is not a string")?;
+                self.byte_array_hash(string)
+            }
+            (Revision::V1, "i128") => i128_from_value(value),
+            (Revision::V1, "u128" | "timestamp") => {
+                let felt = felt_from_value(value)?;
+                anyhow::ensure!(
+                    TryInto::<u128>::try_into(felt).is_ok(),
+                    "Value {felt} of type {} is out of range",
+                    r#type
+                );
+                Ok(felt)
+            }
+            (Revision::V1, "bool") => {
+                let felt = felt_from_value(value)?;
+                anyhow::ensure!(
+                    felt == Felt::ZERO || felt == Felt::from_u64(1),
+                    "Value {felt} of type bool is out of range"
+                );
+                Ok(felt)
+            }
+            (Revision::V1, "felt" | "shortstring" | "ClassHash" | "ContractAddress") => {
+                felt_from_value(value)
+            }
+            (Revision::V1, other) => anyhow::bail!("Unsupported type {other}"),
+            // Revision 0 does not check types, and encodes any other value as a felt.
+            (Revision::V0, _) => felt_from_value(value),
+        }
+    }
+
+    /// Encodes an enum value `{"Variant": [values...]}` as the hash of the index of the variant
+    /// followed by its encoded values.
+    fn encode_enum(&self, enum_type: &str, value: &Value) -> anyhow::Result<Felt> {
+        let mut entries = value
+            .as_object()
+            .context("Value of enum is not an object")?
+            .iter();
+        let (variant, values) = match (entries.next(), entries.next()) {
+            (Some(entry), None) => entry,
+            _ => anyhow::bail!("Value of enum must have exactly one variant"),
+        };
+
+        let (index, variant_type) = self
+            .members(enum_type)?
+            .iter()
+            .enumerate()
+            .find(|(_, x)| &x.name == variant)
+            .with_context(|| format!("Variant {variant} is not part of {enum_type}"))?;
+        let types = tuple_types(&variant_type.r#type)
+            .with_context(|| format!("Variant {variant} is not a tuple"))?
+            .filter(|x| !x.is_empty())
+            .collect::<Vec<_>>();
+
+        let values = values
+            .as_array()
+            .with_context(|| format!("Values of variant {variant} are not an array"))?;
+        anyhow::ensure!(
+            values.len() == types.len(),
+            "Variant {variant} has {} values instead of {}",
+            values.len(),
+            types.len()
+        );
+
+        let mut encoded = vec![Felt::from_u64(index as u64)];
+        for (r#type, value) in types.into_iter().zip(values) {
+            encoded.push(self.encode_value(r#type, None, value)?);
+        }
+
+        Ok(self.revision.hash_many(&encoded))
+    }
+
+    /// Computes the root of a merkle tree whose pairs of nodes are hashed in ascending order. An
+    /// odd node is paired with zero.
+    fn merkle_root(&self, mut nodes: Vec<Felt>) -> anyhow::Result<Felt> {
+        anyhow::ensure!(!nodes.is_empty(), "Merkle tree has no leaves");
+
+        while nodes.len() > 1 {
+            nodes = nodes
+                .chunks(2)
+                .map(|pair| {
+                    let a = pair[0];
+                    let b = pair.get(1).copied().unwrap_or_default();
+                    self.revision.hash_pair(a.min(b), a.max(b))
+                })
+                .collect();
+        }
+
+        Ok(nodes[0])
+    }
+
+    /// Hashes the serialization of the string as a Cairo `ByteArray`.
+    fn byte_array_hash(&self, string: &str) -> anyhow::Result<Felt> {
+        let mut words = string.as_bytes().chunks(31).collect::<Vec<_>>();
+        let pending = if words.last().is_some_and(|x| x.len() < 31) {
+            words.pop().expect("Checked above")
+        } else {
+            &[]
+        };
+
+        let mut values = vec![Felt::from_u64(words.len() as u64)];
+        for word in words {
+            values.push(Felt::from_be_slice(word).context("Word fits into a felt")?);
+        }
+        values.push(Felt::from_be_slice(pending).context("Word fits into a felt")?);
+        values.push(Felt::from_u64(pending.len() as u64));
+
+        Ok(self.revision.hash_many(&values))
+    }
+}
+
+/// Splits a tuple type `(a,b)` into its element types.
+fn tuple_types(r#type: &str) -> Option<impl Iterator<Item = &str>> {
+    r#type
+        .strip_prefix('(')
+        .and_then(|x| x.strip_suffix(')'))
+        .map(|x| x.split(','))
+}
+
+/// Parses a felt from a number, a boolean, a hex or decimal string, or a short string.
+fn felt_from_value(value: &Value) -> anyhow::Result<Felt> {
+    match value {
+        Value::Bool(x) => Ok(Felt::from_u64(*x as u64)),
+        Value::Number(x) => felt_from_decimal(&x.to_string()),
+        Value::String(x) => {
+            if x.starts_with("0x") || x.starts_with("0X") {
+                Felt::from_hex_str(x).with_context(|| format!("Parsing {x} as hex"))
+            } else if !x.is_empty() && x.chars().all(|x| x.is_ascii_digit()) {
+                felt_from_decimal(x)
+            } else {
+                short_string(x)
+            }
+        }
+        other => anyhow::bail!("Cannot encode {other} as a felt"),
+    }
+}
+
+fn felt_from_decimal(decimal: &str) -> anyhow::Result<Felt> {
+    let value = BigUint::parse_bytes(decimal.as_bytes(), 10)
+        .with_context(|| format!("Parsing {decimal} as decimal"))?;
+    Felt::from_be_slice(&value.to_bytes_be()).with_context(|| format!("{decimal} is too large"))
+}
+
+/// Encodes an ASCII string of at most 31 characters as a felt.
+fn short_string(string: &str) -> anyhow::Result<Felt> {
+    anyhow::ensure!(string.is_ascii(), "Short string {string} is not ASCII");
+    anyhow::ensure!(
+        string.len() <= 31,
+        "Short string {string} is longer than 31 characters"
+    );
+    Ok(Felt::from_be_slice(string.as_bytes()).expect("31 bytes fit into a felt"))
+}
+
+/// Parses an `i128`, whose negative values are encoded as their field element counterparts.
+fn i128_from_value(value: &Value) -> anyhow::Result<Felt> {
+    let negative = match value {
+        Value::Number(x) => x
+            .to_string()
+            .strip_prefix('-')
+            .map(|x| Value::String(x.to_owned())),
+        Value::String(x) => x.strip_prefix('-').map(|x| Value::String(x.to_owned())),
+        _ => None,
+    };
+
+    let (magnitude, limit) = match &negative {
+        Some(magnitude) => (felt_from_value(magnitude)?, 1u128 << 127),
+        None => (felt_from_value(value)?, i128::MAX as u128),
+    };
+    let in_range = TryInto::<u128>::try_into(magnitude).is_ok_and(|x| x <= limit);
+    anyhow::ensure!(in_range, "Value {value} of type i128 is out of range");
+
+    Ok(match negative {
+        Some(_) => Felt::ZERO - magnitude,
+        None => magnitude,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macro_prelude::*;
+    use serde_json::json;
+
+    const ACCOUNT: ContractAddress =
+        contract_address!("0xcd2a3d9f938e13cd947ec05abc7fe734df8dd826");
+
+    fn mail() -> TypedData {
+        serde_json::from_value(json!({
+            "types": {
+                "StarkNetDomain": [
+                    { "name": "name", "type": "felt" },
+                    { "name": "version", "type": "felt" },
+                    { "name": "chainId", "type": "felt" }
+                ],
+                "Person": [
+                    { "name": "name", "type": "felt" },
+                    { "name": "wallet", "type": "felt" }
+                ],
+                "Mail": [
+                    { "name": "from", "type": "Person" },
+                    { "name": "to", "type": "Person" },
+                    { "name": "contents", "type": "felt" }
+                ]
+            },
+            "primaryType": "Mail",
+            "domain": { "name": "StarkNet Mail", "version": "1", "chainId": 1 },
+            "message": {
+                "from": { "name": "Cow", "wallet": "0xCD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826" },
+                "to": { "name": "Bob", "wallet": "0xbBbBBBBbbBBBbbbBbbBbbbbBBbBbbbbBbBbbBBbB" },
+                "contents": "Hello, Bob!"
+            }
+        }))
+        .unwrap()
+    }
+
+    fn transfer() -> TypedData {
+        serde_json::from_value(json!({
+            "types": {
+                "StarknetDomain": [
+                    { "name": "name", "type": "shortstring" },
+                    { "name": "version", "type": "shortstring" },
+                    { "name": "chainId", "type": "shortstring" },
+                    { "name": "revision", "type": "shortstring" }
+                ],
+                "Transfer": [
+                    { "name": "recipient", "type": "ContractAddress" },
+                    { "name": "amount", "type": "TokenAmount" },
+                    { "name": "memo", "type": "string" },
+                    { "name": "action", "type": "enum", "contains": "Action" },
+                    { "name": "selector", "type": "selector" },
+                    { "name": "delta", "type": "i128" },
+                    { "name": "allowed", "type": "merkletree", "contains": "Leaf" },
+                    { "name": "tags", "type": "shortstring*" },
+                    { "name": "expiry", "type": "timestamp" },
+                    { "name": "urgent", "type": "bool" }
+                ],
+                "Leaf": [
+                    { "name": "value", "type": "felt" }
+                ],
+                "Action": [
+                    { "name": "Pay", "type": "()" },
+                    { "name": "Split", "type": "(u128,ContractAddress*)" }
+                ]
+            },
+            "primaryType": "Transfer",
+            "domain": { "name": "Pathfinder", "version": "1", "chainId": "SN_MAIN", "revision": "1" },
+            "message": {
+                "recipient": "0x1234",
+                "amount": {
+                    "token_address": "0x49d36570d4e46f48e99674bd3fcc84644ddd6b96f7c741b1562b82f9e004dc7",
+                    "amount": { "low": "1000", "high": 0 }
+                },
+                "memo": "A memo which is longer than thirty-one bytes",
+                "action": { "Split": [7, ["0x1", "0x2"]] },
+                "selector": "transfer",
+                "delta": -5,
+                "allowed": [{ "value": "0x1" }, { "value": "0x2" }, { "value": "0x3" }],
+                "tags": ["a", "b"],
+                "expiry": 1700000000,
+                "urgent": true
+            }
+        }))
+        .unwrap()
+    }
+
+    mod revision_0 {
+        use super::*;
+
+        #[test]
+        fn encode_type() {
+            assert_eq!(
+                mail().encode_type("Mail").unwrap(),
+                "Mail(from:Person,to:Person,contents:felt)Person(name:felt,wallet:felt)"
+            );
+        }
+
+        #[test]
+        fn type_hashes() {
+            let mail = mail();
+            assert_eq!(
+                mail.type_hash("StarkNetDomain").unwrap(),
+                felt!("0x1bfc207425a47a5dfa1a50a4f5241203f50624ca5fdf5e18755765416b8e288")
+            );
+            assert_eq!(
+                mail.type_hash("Person").unwrap(),
+                felt!("0x2896dbe4b96a67110f454c01e5336edc5bbc3635537efd690f122f4809cc855")
+            );
+            assert_eq!(
+                mail.type_hash("Mail").unwrap(),
+                felt!("0x13d89452df9512bf750f539ba3001b945576243288137ddb6c788457d4b2f79")
+            );
+        }
+
+        #[test]
+        fn message_hash() {
+            // Matches the starknet.js test suite.
+            assert_eq!(
+                mail().message_hash(ACCOUNT).unwrap(),
+                felt!("0x6fcff244f63e38b9d88b9e3378d44757710d1b244282b435cb472053c8d78d0")
+            );
+        }
+
+        #[test]
+        fn merkle_tree_and_selectors() {
+            let typed_data: TypedData = serde_json::from_value(json!({
+                "types": {
+                    "StarkNetDomain": [
+                        { "name": "name", "type": "felt" },
+                        { "name": "version", "type": "felt" },
+                        { "name": "chainId", "type": "felt" }
+                    ],
+                    "Session": [
+                        { "name": "key", "type": "felt" },
+                        { "name": "expires", "type": "felt" },
+                        { "name": "root", "type": "merkletree", "contains": "Policy" },
+                        { "name": "calls", "type": "selector*" }
+                    ],
+                    "Policy": [
+                        { "name": "contractAddress", "type": "felt" },
+                        { "name": "selector", "type": "selector" }
+                    ]
+                },
+                "primaryType": "Session",
+                "domain": { "name": "StarkNet Mail", "version": "1", "chainId": "SN_GOERLI" },
+                "message": {
+                    "key": "0x1",
+                    "expires": 1000,
+                    "root": [
+                        { "contractAddress": "0x1", "selector": "transfer" },
+                        {
+                            "contractAddress": "0x2",
+                            "selector": "0x83afd3f4caedc6eebf44246fe54e38c95e3179a5ec9ea81740eca5b482d12e"
+                        },
+                        { "contractAddress": "0x3", "selector": "approve" }
+                    ],
+                    "calls": ["transfer", "balanceOf"]
+                }
+            }))
+            .unwrap();
+
+            // Merkle tree leaf types are not part of the encoded type.
+            assert_eq!(
+                typed_data.encode_type("Session").unwrap(),
+                "Session(key:felt,expires:felt,root:merkletree,calls:selector*)"
+            );
+            assert_eq!(
+                typed_data.message_hash(ACCOUNT).unwrap(),
+                felt!("0x39209a11b4cbee873030e449501407481a4cd532f60bb3351f9608568a05382")
+            );
+        }
+    }
+
+    mod revision_1 {
+        use super::*;
+
+        #[test]
+        fn domain_type_hash() {
+            assert_eq!(
+                transfer().type_hash("StarknetDomain").unwrap(),
+                felt!("0x1ff2f602e42168014d405a94f75e8a93d640751d71d16311266e140d8b0a210")
+            );
+        }
+
+        #[test]
+        fn encode_type() {
+            assert_eq!(
+                transfer().encode_type("Transfer").unwrap(),
+                concat!(
+                    r#""Transfer"("recipient":"ContractAddress","amount":"TokenAmount","memo":"string","#,
+                    r#""action":"Action","selector":"selector","delta":"i128","allowed":"merkletree","#,
+                    r#""tags":"shortstring*","expiry":"timestamp","urgent":"bool")"#,
+                    r#""Action"("Pay":(),"Split":("u128","ContractAddress*"))"#,
+                    r#""TokenAmount"("token_address":"ContractAddress","amount":"u256")"#,
+                    r#""u256"("low":"u128","high":"u128")"#,
+                )
+            );
+        }
+
+        #[test]
+        fn message_hash() {
+            assert_eq!(
+                transfer().message_hash(ACCOUNT).unwrap(),
+                felt!("0x4d0512c84aefcb54b5304616ac562b9c0a95ef9fc84c27393340589b95db636")
+            );
+        }
+
+        #[test]
+        fn u128_out_of_range() {
+            let mut typed_data = transfer();
+            typed_data.message["expiry"] = json!("0x100000000000000000000000000000000");
+
+            typed_data.message_hash(ACCOUNT).unwrap_err();
+        }
+
+        #[test]
+        fn unknown_enum_variant() {
+            let mut typed_data = transfer();
+            typed_data.message["action"] = json!({ "Refund": [] });
+
+            typed_data.message_hash(ACCOUNT).unwrap_err();
+        }
+
+        #[test]
+        fn missing_value() {
+            let mut typed_data = transfer();
+            typed_data.message.as_object_mut().unwrap().remove("memo");
+
+            typed_data.message_hash(ACCOUNT).unwrap_err();
+        }
+    }
+
+    #[test]
+    fn missing_domain_type() {
+        let mut typed_data = transfer();
+        typed_data.types.remove("StarknetDomain");
+
+        typed_data.revision().unwrap_err();
+    }
+
+    #[test]
+    fn unsupported_revision() {
+        let mut typed_data = transfer();
+        typed_data.domain["revision"] = json!("2");
+
+        typed_data.revision().unwrap_err();
+    }
+
+    #[test]
+    fn i128_encoding() {
+        assert_eq!(
+            i128_from_value(&json!(-1)).unwrap(),
+            Felt::ZERO - Felt::from_u64(1)
+        );
+        assert_eq!(
+            i128_from_value(&json!("-170141183460469231731687303715884105728")).unwrap(),
+            Felt::ZERO - Felt::from_u128(1 << 127)
+        );
+        i128_from_value(&json!("170141183460469231731687303715884105728")).unwrap_err();
+        i128_from_value(&json!("-170141183460469231731687303715884105729")).unwrap_err();
+    }
+}
@@ -1,8 +1,11 @@
 use ::pathfinder_crypto::hash::poseidon::poseidon_hash;
-use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use criterion::{
+    black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput,
+};
 use pathfinder_crypto::algebra::curve::{ProjectivePoint, CURVE_G};
 use pathfinder_crypto::algebra::field::{CurveOrderMontFelt, Felt, MontFelt};
 use pathfinder_crypto::hash::pedersen::pedersen_hash;
+use pathfinder_crypto::hash::{poseidon_hash_many, poseidon_permute_many, HashChain};
 use pathfinder_crypto::signature::{ecdsa_sign, ecdsa_sign_k, ecdsa_verify_partial, get_pk};
 
 pub fn criterion_benchmark(c: &mut Criterion) {
@@ -11,6 +14,7 @@ pub fn criterion_benchmark(c: &mut Criterion) {
 
     // Bench hash functions
     bench_hash(c);
+    bench_hash_throughput(c);
 
     // Bench signatures
     bench_signature(c);
@@ -64,6 +68,63 @@ pub fn bench_hash(c: &mut Criterion) {
     grp_hash.finish();
 }
 
+/// Hashes sequences of elements, as done for class hashes and commitments.
+///
+/// Inputs are generated from a fixed seed so that results are comparable across runs.
+pub fn bench_hash_throughput(c: &mut Criterion) {
+    use rand::SeedableRng;
+
+    let rng = &mut rand::rngs::StdRng::seed_from_u64(0);
+
+    let mut grp_hash = c.benchmark_group("hash_throughput");
+    for len in [16, 256, 4096] {
+        grp_hash.throughput(Throughput::Elements(len as u64));
+
+        let felts = (0..len).map(|_| Felt::random(rng)).collect::<Vec<_>>();
+        grp_hash.bench_with_input(
+            BenchmarkId::new("pedersen_chain", len),
+            &felts,
+            |b, felts| {
+                b.iter(|| {
+                    let chain = felts.iter().fold(HashChain::default(), |chain, felt| {
+                        chain.chain_update(*felt)
+                    });
+                    black_box(chain.finalize())
+                })
+            },
+        );
+
+        let felts = (0..len).map(|_| MontFelt::random(rng)).collect::<Vec<_>>();
+        grp_hash.bench_with_input(
+            BenchmarkId::new("poseidon_hash_many", len),
+            &felts,
+            |b, felts| b.iter(|| black_box(poseidon_hash_many(felts))),
+        );
+
+        let states = (0..len)
+            .map(|_| {
+                [
+                    MontFelt::random(rng),
+                    MontFelt::random(rng),
+                    MontFelt::random(rng),
+                ]
+            })
+            .collect::<Vec<_>>();
+        grp_hash.bench_with_input(
+            BenchmarkId::new("poseidon_permute_many", len),
+            &states,
+            |b, states| {
+                b.iter_batched_ref(
+                    || states.clone(),
+                    |states| poseidon_permute_many(black_box(states)),
+                    BatchSize::SmallInput,
+                )
+            },
+        );
+    }
+    grp_hash.finish();
+}
+
 pub fn bench_signature(c: &mut Criterion) {
     pub fn rand_251bit_felt() -> Felt {
         let rng = &mut rand::thread_rng();
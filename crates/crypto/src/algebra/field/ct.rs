@@ -0,0 +1,333 @@
+//! Constant-time Montgomery arithmetic, which replaces `ark_ff`'s for [MontFelt](super::MontFelt)
+//! and [CurveOrderMontFelt](super::CurveOrderMontFelt) with the `constant-time` feature.
+//!
+//! `ark_ff` branches on the values it operates on, e.g. to decide whether to subtract the modulus
+//! after an addition or multiplication, and in its binary extended Euclidean inversion. Here those
+//! branches are replaced by masks, and elements are inverted by raising them to the power `p - 2`,
+//! where only the public exponent determines the sequence of operations.
+//!
+//! Only the field arithmetic is covered. Comparisons, conversions and square roots, as well as
+//! the elliptic curve point arithmetic built on top of the fields, still take data-dependent time.
+
+// The limb-wise loops read like the textbook algorithms.
+#![allow(clippy::needless_range_loop)]
+
+use ark_ff::{BigInt, Fp256, MontBackend, MontConfig};
+
+type Fp<C> = Fp256<MontBackend<C, 4>>;
+type Limbs = [u64; 4];
+
+/// Returns `a + b + carry` and the carry out.
+#[inline(always)]
+fn adc(a: u64, b: u64, carry: u64) -> (u64, u64) {
+    let t = a as u128 + b as u128 + carry as u128;
+    (t as u64, (t >> 64) as u64)
+}
+
+/// Returns `a - b - borrow` and the borrow out.
+#[inline(always)]
+fn sbb(a: u64, b: u64, borrow: u64) -> (u64, u64) {
+    let t = (a as u128).wrapping_sub(b as u128 + borrow as u128);
+    (t as u64, (t >> 127) as u64)
+}
+
+/// Returns `a + b * c + carry` and the carry out.
+#[inline(always)]
+fn mac(a: u64, b: u64, c: u64, carry: u64) -> (u64, u64) {
+    let t = a as u128 + (b as u128) * (c as u128) + carry as u128;
+    (t as u64, (t >> 64) as u64)
+}
+
+/// Returns `a` where `mask` is all ones, and `b` where it is all zeros.
+#[inline(always)]
+fn select(mask: u64, a: &Limbs, b: &Limbs) -> Limbs {
+    std::array::from_fn(|i| (a[i] & mask) | (b[i] & !mask))
+}
+
+/// Reduces `carry * 2^256 + a`, which must be less than `2p`, modulo `p`.
+#[inline(always)]
+fn reduce(a: &Limbs, carry: u64, p: &Limbs) -> Limbs {
+    let mut reduced = [0; 4];
+    let mut borrow = 0;
+    for i in 0..4 {
+        (reduced[i], borrow) = sbb(a[i], p[i], borrow);
+    }
+    // Whether `a` was already less than `p`.
+    let (_, borrow) = sbb(carry, 0, borrow);
+
+    select(borrow.wrapping_neg(), a, &reduced)
+}
+
+#[inline(always)]
+fn limbs<C: MontConfig<4>>(x: Fp<C>) -> Limbs {
+    x.0 .0
+}
+
+#[inline(always)]
+fn from_limbs<C: MontConfig<4>>(x: Limbs) -> Fp<C> {
+    Fp::new_unchecked(BigInt(x))
+}
+
+pub(crate) fn add<C: MontConfig<4>>(a: Fp<C>, b: Fp<C>) -> Fp<C> {
+    let (a, b) = (limbs(a), limbs(b));
+
+    let mut sum = [0; 4];
+    let mut carry = 0;
+    for i in 0..4 {
+        (sum[i], carry) = adc(a[i], b[i], carry);
+    }
+
+    from_limbs(reduce(&sum, carry, &C::MODULUS.0))
+}
+
+pub(crate) fn sub<C: MontConfig<4>>(a: Fp<C>, b: Fp<C>) -> Fp<C> {
+    let (a, b) = (limbs(a), limbs(b));
+    let p = C::MODULUS.0;
+
+    let mut diff = [0; 4];
+    let mut borrow = 0;
+    for i in 0..4 {
+        (diff[i], borrow) = sbb(a[i], b[i], borrow);
+    }
+
+    // Adds the modulus back if the subtraction wrapped around.
+    let mask = borrow.wrapping_neg();
+    let mut carry = 0;
+    for i in 0..4 {
+        (diff[i], carry) = adc(diff[i], p[i] & mask, carry);
+    }
+
+    from_limbs(diff)
+}
+
+pub(crate) fn neg<C: MontConfig<4>>(a: Fp<C>) -> Fp<C> {
+    sub(from_limbs([0; 4]), a)
+}
+
+pub(crate) fn double<C: MontConfig<4>>(a: Fp<C>) -> Fp<C> {
+    add(a, a)
+}
+
+/// Montgomery multiplication using the coarsely integrated operand scanning method.
+pub(crate) fn mul<C: MontConfig<4>>(a: Fp<C>, b: Fp<C>) -> Fp<C> {
+    let (a, b) = (limbs(a), limbs(b));
+    let p = C::MODULUS.0;
+
+    let mut t = [0; 6];
+    for i in 0..4 {
+        let mut carry = 0;
+        for j in 0..4 {
+            (t[j], carry) = mac(t[j], a[j], b[i], carry);
+        }
+        (t[4], t[5]) = adc(t[4], carry, 0);
+
+        let m = t[0].wrapping_mul(C::INV);
+        let (_, mut carry) = mac(t[0], m, p[0], 0);
+        for j in 1..4 {
+            (t[j - 1], carry) = mac(t[j], m, p[j], carry);
+        }
+        (t[3], carry) = adc(t[4], carry, 0);
+        t[4] = t[5] + carry;
+    }
+
+    from_limbs(reduce(&[t[0], t[1], t[2], t[3]], t[4], &p))
+}
+
+pub(crate) fn square<C: MontConfig<4>>(a: Fp<C>) -> Fp<C> {
+    mul(a, a)
+}
+
+pub(crate) fn inverse<C: MontConfig<4>>(a: Fp<C>) -> Option<Fp<C>> {
+    let p = C::MODULUS.0;
+
+    // By Fermat's little theorem, `a^(p - 2)` is the inverse of `a`, or zero if `a` is zero.
+    let two = [2, 0, 0, 0];
+    let mut exponent = [0; 4];
+    let mut borrow = 0;
+    for i in 0..4 {
+        (exponent[i], borrow) = sbb(p[i], two[i], borrow);
+    }
+
+    let mut result = from_limbs(C::R.0);
+    for bit in (0..256).rev() {
+        result = square(result);
+        // The exponent is public, so branching on it leaks nothing about `a`.
+        if (exponent[bit / 64] >> (bit % 64)) & 1 == 1 {
+            result = mul(result, a);
+        }
+    }
+
+    let is_zero = limbs(a).iter().fold(0, |acc, limb| acc | limb) == 0;
+    (!is_zero).then_some(result)
+}
+
+pub(crate) fn div<C: MontConfig<4>>(a: Fp<C>, b: Fp<C>) -> Fp<C> {
+    mul(a, inverse(b).expect("division by zero"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algebra::field::curveorder::Fr;
+    use crate::algebra::field::Fq;
+    use ark_ff::{Field, UniformRand, Zero};
+
+    #[test]
+    fn matches_ark_ff() {
+        let rng = &mut rand::thread_rng();
+        let mut fq_edge_cases = vec![Fq::zero(), Fq::from(1u64), -Fq::from(1u64)];
+        let mut fr_edge_cases = vec![Fr::zero(), Fr::from(1u64), -Fr::from(1u64)];
+        fq_edge_cases.extend((0..100).map(|_| Fq::rand(rng)));
+        fr_edge_cases.extend((0..100).map(|_| Fr::rand(rng)));
+
+        for &a in &fq_edge_cases {
+            assert_eq!(neg(a), -a);
+            assert_eq!(double(a), a.double());
+            assert_eq!(square(a), a.square());
+            assert_eq!(inverse(a), a.inverse());
+            for &b in &fq_edge_cases {
+                assert_eq!(add(a, b), a + b);
+                assert_eq!(sub(a, b), a - b);
+                assert_eq!(mul(a, b), a * b);
+            }
+        }
+
+        for &a in &fr_edge_cases {
+            assert_eq!(neg(a), -a);
+            assert_eq!(inverse(a), a.inverse());
+            for &b in &fr_edge_cases {
+                assert_eq!(add(a, b), a + b);
+                assert_eq!(sub(a, b), a - b);
+                assert_eq!(mul(a, b), a * b);
+            }
+        }
+    }
+
+    /// Timing leakage tests in the style of [dudect](https://eprint.iacr.org/2016/1123.pdf).
+    ///
+    /// The operations are timed on two classes of inputs, one fixed and one random, interleaved
+    /// in random order. Welch's t-test then checks whether the timings of the classes differ.
+    ///
+    /// These are sensitive to noise from other processes, so they are only meaningful in release
+    /// mode on an otherwise idle machine:
+    ///
+    /// `cargo test --release -p pathfinder-crypto --features constant-time -- --ignored dudect`
+    mod dudect {
+        use std::hint::black_box;
+        use std::time::Instant;
+
+        use rand::Rng;
+
+        use super::*;
+
+        const SAMPLES: usize = 200_000;
+        /// Operations per sample, so that a sample takes longer than the timer's resolution.
+        const BATCH: usize = 16;
+        /// dudect considers larger values evidence of a leak.
+        const THRESHOLD: f64 = 10.0;
+
+        /// Running mean and variance of one class of samples.
+        #[derive(Default)]
+        struct Stats {
+            n: f64,
+            mean: f64,
+            m2: f64,
+        }
+
+        impl Stats {
+            fn push(&mut self, x: f64) {
+                self.n += 1.0;
+                let delta = x - self.mean;
+                self.mean += delta / self.n;
+                self.m2 += delta * (x - self.mean);
+            }
+
+            fn variance(&self) -> f64 {
+                self.m2 / (self.n - 1.0)
+            }
+        }
+
+        /// Returns Welch's t-statistic for the timings of `op` with `fixed` inputs versus random
+        /// inputs.
+        fn t_statistic<T: Copy>(
+            fixed: (T, T),
+            random: impl Fn(&mut rand::rngs::ThreadRng) -> T,
+            op: impl Fn(T, T) -> T,
+        ) -> f64 {
+            let rng = &mut rand::thread_rng();
+
+            let mut timings = (0..SAMPLES)
+                .map(|_| {
+                    let class = rng.gen::<bool>();
+                    let inputs = if class {
+                        fixed
+                    } else {
+                        (random(rng), random(rng))
+                    };
+
+                    let start = Instant::now();
+                    for _ in 0..BATCH {
+                        black_box(op(black_box(inputs.0), black_box(inputs.1)));
+                    }
+                    (class, start.elapsed().as_nanos() as f64)
+                })
+                .collect::<Vec<_>>();
+
+            // Drops the slowest samples, which are dominated by interrupts and context switches.
+            let mut sorted = timings.iter().map(|(_, t)| *t).collect::<Vec<_>>();
+            sorted.sort_by(f64::total_cmp);
+            let cutoff = sorted[sorted.len() * 9 / 10];
+            timings.retain(|(_, t)| *t <= cutoff);
+
+            let (mut fixed, mut random) = (Stats::default(), Stats::default());
+            for (class, t) in timings {
+                if class {
+                    fixed.push(t)
+                } else {
+                    random.push(t)
+                }
+            }
+
+            (fixed.mean - random.mean)
+                / (fixed.variance() / fixed.n + random.variance() / random.n).sqrt()
+        }
+
+        fn assert_constant_time<T: Copy>(
+            name: &str,
+            fixed: (T, T),
+            random: impl Fn(&mut rand::rngs::ThreadRng) -> T,
+            op: impl Fn(T, T) -> T,
+        ) {
+            let t = t_statistic(fixed, random, op);
+            assert!(t.abs() < THRESHOLD, "{name} leaks timing: t = {t}");
+        }
+
+        #[test]
+        #[ignore = "timing measurement"]
+        fn dudect_montfelt() {
+            let zero = Fq::zero();
+            let random = |rng: &mut rand::rngs::ThreadRng| Fq::rand(rng);
+
+            assert_constant_time("add", (zero, zero), random, add);
+            assert_constant_time("sub", (zero, zero), random, sub);
+            assert_constant_time("mul", (zero, zero), random, mul);
+            assert_constant_time("inverse", (zero, zero), random, |a, _| {
+                inverse(a).unwrap_or_default()
+            });
+        }
+
+        #[test]
+        #[ignore = "timing measurement"]
+        fn dudect_curve_order_montfelt() {
+            let zero = Fr::zero();
+            let random = |rng: &mut rand::rngs::ThreadRng| Fr::rand(rng);
+
+            assert_constant_time("add", (zero, zero), random, add);
+            assert_constant_time("sub", (zero, zero), random, sub);
+            assert_constant_time("mul", (zero, zero), random, mul);
+            assert_constant_time("inverse", (zero, zero), random, |a, _| {
+                inverse(a).unwrap_or_default()
+            });
+        }
+    }
+}
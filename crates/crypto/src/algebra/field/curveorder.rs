@@ -1,5 +1,4 @@
 use crate::algebra::curve::CURVE_ORDER;
-use crate::algebra::field::derive::{derive_op, derive_op_assign};
 use crate::algebra::field::{Felt, MontFelt};
 use ark_ff::fields::{Fp256, MontBackend};
 use ark_ff::{BigInt, BigInteger, Field, MontConfig, PrimeField, UniformRand};
@@ -81,12 +80,20 @@ impl CurveOrderMontFelt {
 
     /// Compute the square of a field element
     pub fn square(&self) -> Self {
-        CurveOrderMontFelt(self.0.square())
+        #[cfg(feature = "constant-time")]
+        let square = super::ct::square(self.0);
+        #[cfg(not(feature = "constant-time"))]
+        let square = self.0.square();
+        CurveOrderMontFelt(square)
     }
 
     /// Compute inverse of a field element
     pub fn inverse(&self) -> Option<Self> {
-        self.0.inverse().map(CurveOrderMontFelt)
+        #[cfg(feature = "constant-time")]
+        let inverse = super::ct::inverse(self.0);
+        #[cfg(not(feature = "constant-time"))]
+        let inverse = self.0.inverse();
+        inverse.map(CurveOrderMontFelt)
     }
 
     /// Compute square root of an element.
@@ -140,16 +147,39 @@ impl PartialOrd for CurveOrderMontFelt {
 impl std::ops::Neg for CurveOrderMontFelt {
     type Output = Self;
     fn neg(self) -> Self::Output {
-        CurveOrderMontFelt(-self.0)
+        #[cfg(feature = "constant-time")]
+        let neg = super::ct::neg(self.0);
+        #[cfg(not(feature = "constant-time"))]
+        let neg = -self.0;
+        CurveOrderMontFelt(neg)
     }
 }
 
-derive_op!(CurveOrderMontFelt, Add, add, +);
-derive_op!(CurveOrderMontFelt, Sub, sub, -);
-derive_op!(CurveOrderMontFelt, Mul, mul, *);
-derive_op!(CurveOrderMontFelt, Div, div, /);
-derive_op_assign!(CurveOrderMontFelt, AddAssign, add_assign, +=);
-derive_op_assign!(CurveOrderMontFelt, SubAssign, sub_assign, -=);
+#[cfg(not(feature = "constant-time"))]
+mod ops {
+    use super::*;
+    use crate::algebra::field::derive::{derive_op, derive_op_assign};
+
+    derive_op!(CurveOrderMontFelt, Add, add, +);
+    derive_op!(CurveOrderMontFelt, Sub, sub, -);
+    derive_op!(CurveOrderMontFelt, Mul, mul, *);
+    derive_op!(CurveOrderMontFelt, Div, div, /);
+    derive_op_assign!(CurveOrderMontFelt, AddAssign, add_assign, +=);
+    derive_op_assign!(CurveOrderMontFelt, SubAssign, sub_assign, -=);
+}
+
+#[cfg(feature = "constant-time")]
+mod ops {
+    use super::*;
+    use crate::algebra::field::derive::{derive_ct_op, derive_ct_op_assign};
+
+    derive_ct_op!(CurveOrderMontFelt, Add, add);
+    derive_ct_op!(CurveOrderMontFelt, Sub, sub);
+    derive_ct_op!(CurveOrderMontFelt, Mul, mul);
+    derive_ct_op!(CurveOrderMontFelt, Div, div);
+    derive_ct_op_assign!(CurveOrderMontFelt, AddAssign, add_assign, add);
+    derive_ct_op_assign!(CurveOrderMontFelt, SubAssign, sub_assign, sub);
+}
 
 #[cfg(test)]
 mod tests {
@@ -69,3 +69,81 @@ macro_rules! derive_op_assign {
     };
 }
 pub(crate) use derive_op_assign;
+
+/// Same as [derive_op], but using the [constant-time arithmetic](super::ct).
+#[cfg(feature = "constant-time")]
+macro_rules! derive_ct_op {
+    ($type:ident, $iface:ident, $fun:ident) => {
+        impl std::ops::$iface<Self> for $type {
+            type Output = $type;
+            #[inline(always)]
+            fn $fun(self, rhs: Self) -> Self::Output {
+                $type($crate::algebra::field::ct::$fun(self.0, rhs.0))
+            }
+        }
+        impl std::ops::$iface<&Self> for $type {
+            type Output = $type;
+            #[inline(always)]
+            fn $fun(self, rhs: &Self) -> Self::Output {
+                $type($crate::algebra::field::ct::$fun(self.0, rhs.0))
+            }
+        }
+        impl std::ops::$iface<&mut Self> for $type {
+            type Output = $type;
+            #[inline(always)]
+            fn $fun(self, rhs: &mut Self) -> Self::Output {
+                $type($crate::algebra::field::ct::$fun(self.0, rhs.0))
+            }
+        }
+        impl std::ops::$iface<$type> for &$type {
+            type Output = $type;
+            #[inline(always)]
+            fn $fun(self, rhs: $type) -> Self::Output {
+                $type($crate::algebra::field::ct::$fun(self.0, rhs.0))
+            }
+        }
+        impl std::ops::$iface<&$type> for &$type {
+            type Output = $type;
+            #[inline(always)]
+            fn $fun(self, rhs: &$type) -> Self::Output {
+                $type($crate::algebra::field::ct::$fun(self.0, rhs.0))
+            }
+        }
+        impl std::ops::$iface<&mut $type> for &$type {
+            type Output = $type;
+            #[inline(always)]
+            fn $fun(self, rhs: &mut $type) -> Self::Output {
+                $type($crate::algebra::field::ct::$fun(self.0, rhs.0))
+            }
+        }
+    };
+}
+#[cfg(feature = "constant-time")]
+pub(crate) use derive_ct_op;
+
+/// Same as [derive_op_assign], but using `$op` from the [constant-time arithmetic](super::ct).
+#[cfg(feature = "constant-time")]
+macro_rules! derive_ct_op_assign {
+    ($type:ident, $iface:ident, $fun:ident, $op:ident) => {
+        impl std::ops::$iface<Self> for $type {
+            #[inline(always)]
+            fn $fun(&mut self, rhs: Self) {
+                self.0 = $crate::algebra::field::ct::$op(self.0, rhs.0);
+            }
+        }
+        impl std::ops::$iface<&Self> for $type {
+            #[inline(always)]
+            fn $fun(&mut self, rhs: &Self) {
+                self.0 = $crate::algebra::field::ct::$op(self.0, rhs.0);
+            }
+        }
+        impl std::ops::$iface<&mut Self> for $type {
+            #[inline(always)]
+            fn $fun(&mut self, rhs: &mut Self) {
+                self.0 = $crate::algebra::field::ct::$op(self.0, rhs.0);
+            }
+        }
+    };
+}
+#[cfg(feature = "constant-time")]
+pub(crate) use derive_ct_op_assign;
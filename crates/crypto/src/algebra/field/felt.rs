@@ -2,7 +2,6 @@ use std::borrow::Cow;
 use std::error::Error;
 
 use bitvec::{order::Msb0, slice::BitSlice, view::BitView};
-use fake::Dummy;
 
 use crate::algebra::field::montfelt::MontFelt;
 
@@ -46,7 +45,8 @@ impl Default for Felt {
     }
 }
 
-impl<T> Dummy<T> for Felt {
+#[cfg(feature = "fake")]
+impl<T> fake::Dummy<T> for Felt {
     fn dummy_with_rng<R: rand::Rng + ?Sized>(_: &T, rng: &mut R) -> Self {
         let mut bytes = [0u8; 32];
         rng.fill_bytes(&mut bytes);
@@ -433,6 +433,13 @@ impl Felt {
         // Unwrap is safe as the buffer contains valid utf8
         String::from_utf8(buf).unwrap().into()
     }
+
+    /// Produces a "0x" prefixed hex string of exactly 64 lower case digits from a [Felt].
+    ///
+    /// Unlike [Felt::to_hex_str], leading zeros are kept so that the string has a fixed width.
+    pub fn to_padded_hex_str(&self) -> String {
+        format!("0x{self:x}")
+    }
 }
 
 /// Error returned by [Felt::from_hex_str] indicating an invalid hex string.
@@ -763,6 +770,26 @@ mod tests {
             let mut buf = [0u8; 65];
             Felt::ZERO.as_hex_str(&mut buf);
         }
+
+        #[test]
+        fn padded() {
+            assert_eq!(
+                Felt::ZERO.to_padded_hex_str(),
+                format!("0x{}", "0".repeat(64))
+            );
+
+            let hash = Felt::from_hex_str(ODD).unwrap();
+            assert_eq!(
+                hash.to_padded_hex_str(),
+                "0x00000000000000000000000000000000000000000000000001234567890abcde"
+            );
+
+            let hash = Felt::from_hex_str(MAX).unwrap();
+            assert_eq!(
+                hash.to_padded_hex_str(),
+                "0x0800000000000011000000000000000000000000000000000000000000000000"
+            );
+        }
     }
 
     mod has_more_than_251_bits {
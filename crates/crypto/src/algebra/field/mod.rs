@@ -1,7 +1,10 @@
+#[cfg(feature = "constant-time")]
+mod ct;
 mod curveorder;
 mod derive;
 mod felt;
 mod montfelt;
+#[cfg(feature = "serde")]
 mod serde;
 
 pub(crate) use montfelt::montfelt_dec;
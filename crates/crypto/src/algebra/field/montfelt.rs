@@ -1,4 +1,3 @@
-use crate::algebra::field::derive::*;
 use crate::algebra::field::{CurveOrderMontFelt, Felt};
 use ark_ff::fields::{Fp256, MontBackend};
 use ark_ff::{BigInt, BigInteger, Field, MontConfig, PrimeField, UniformRand};
@@ -80,17 +79,29 @@ impl MontFelt {
 
     /// Computes the double of a field element
     pub fn double(&self) -> Self {
-        MontFelt(self.0.double())
+        #[cfg(feature = "constant-time")]
+        let double = super::ct::double(self.0);
+        #[cfg(not(feature = "constant-time"))]
+        let double = self.0.double();
+        MontFelt(double)
     }
 
     /// Compute the square of a field element
     pub fn square(&self) -> Self {
-        MontFelt(self.0.square())
+        #[cfg(feature = "constant-time")]
+        let square = super::ct::square(self.0);
+        #[cfg(not(feature = "constant-time"))]
+        let square = self.0.square();
+        MontFelt(square)
     }
 
     /// Compute inverse of a field element
     pub fn inverse(&self) -> Option<Self> {
-        self.0.inverse().map(MontFelt)
+        #[cfg(feature = "constant-time")]
+        let inverse = super::ct::inverse(self.0);
+        #[cfg(not(feature = "constant-time"))]
+        let inverse = self.0.inverse();
+        inverse.map(MontFelt)
     }
 
     /// Compute square root of an element.
@@ -135,13 +146,36 @@ impl PartialOrd for MontFelt {
 impl std::ops::Neg for MontFelt {
     type Output = Self;
     fn neg(self) -> Self::Output {
-        MontFelt(-self.0)
+        #[cfg(feature = "constant-time")]
+        let neg = super::ct::neg(self.0);
+        #[cfg(not(feature = "constant-time"))]
+        let neg = -self.0;
+        MontFelt(neg)
     }
 }
 
-derive_op!(MontFelt, Add, add, +);
-derive_op!(MontFelt, Sub, sub, -);
-derive_op!(MontFelt, Mul, mul, *);
-derive_op!(MontFelt, Div, div, /);
-derive_op_assign!(MontFelt, AddAssign, add_assign, +=);
-derive_op_assign!(MontFelt, SubAssign, sub_assign, -=);
+#[cfg(not(feature = "constant-time"))]
+mod ops {
+    use super::*;
+    use crate::algebra::field::derive::{derive_op, derive_op_assign};
+
+    derive_op!(MontFelt, Add, add, +);
+    derive_op!(MontFelt, Sub, sub, -);
+    derive_op!(MontFelt, Mul, mul, *);
+    derive_op!(MontFelt, Div, div, /);
+    derive_op_assign!(MontFelt, AddAssign, add_assign, +=);
+    derive_op_assign!(MontFelt, SubAssign, sub_assign, -=);
+}
+
+#[cfg(feature = "constant-time")]
+mod ops {
+    use super::*;
+    use crate::algebra::field::derive::{derive_ct_op, derive_ct_op_assign};
+
+    derive_ct_op!(MontFelt, Add, add);
+    derive_ct_op!(MontFelt, Sub, sub);
+    derive_ct_op!(MontFelt, Mul, mul);
+    derive_ct_op!(MontFelt, Div, div);
+    derive_ct_op_assign!(MontFelt, AddAssign, add_assign, add);
+    derive_ct_op_assign!(MontFelt, SubAssign, sub_assign, sub);
+}
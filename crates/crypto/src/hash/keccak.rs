@@ -0,0 +1,94 @@
+use sha3::{Digest, Keccak256};
+
+use crate::Felt;
+
+/// The entry point names whose selector is zero instead of their Starknet Keccak.
+///
+/// See:
+/// <https://github.com/starkware-libs/cairo-lang/blob/64a7f6aed9757d3d8d6c28bd972df73272b0cb0a/src/starkware/starknet/public/abi.py>
+const DEFAULT_ENTRY_POINT_NAMES: [&str; 2] = ["__default__", "__l1_default__"];
+
+/// Computes the Starknet Keccak of the data, which is its Keccak256 digest truncated to 250 bits
+/// so that it fits into a field element.
+pub fn starknet_keccak(data: &[u8]) -> Felt {
+    truncated_keccak(Keccak256::digest(data).into())
+}
+
+/// Truncates a Keccak256 digest to 250 bits.
+///
+/// See:
+/// <https://github.com/starkware-libs/cairo-lang/blob/64a7f6aed9757d3d8d6c28bd972df73272b0cb0a/src/starkware/starknet/public/abi.py#L21-L26>
+pub fn truncated_keccak(mut digest: [u8; 32]) -> Felt {
+    // python code masks with (2**250 - 1) which starts 0x03 and is followed by 31 0xff in be
+    // truncation is needed not to overflow the field element.
+    digest[0] &= 0x03;
+    Felt::from_be_bytes(digest).expect("cannot overflow: smaller than modulus")
+}
+
+/// Computes the selector of the entry point with the given name.
+///
+/// This is the Starknet Keccak of the name, except for the default entry points of Cairo 0
+/// classes whose selector is zero.
+pub fn selector_from_name(name: &str) -> Felt {
+    if DEFAULT_ENTRY_POINT_NAMES.contains(&name) {
+        Felt::ZERO
+    } else {
+        starknet_keccak(name.as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn starknet_keccak_test_vectors() {
+        // The expected values are the selectors computed by cairo-lang's `get_selector_from_name`.
+        let vectors = [
+            (
+                "",
+                "0x1d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470",
+            ),
+            (
+                "constructor",
+                "0x28ffe4ff0f226a9107253e17a904099aa4f63a02a5621de0576e5aa71bc5194",
+            ),
+            (
+                "balanceOf",
+                "0x2e4263afad30923c891518314c3c95dbe830a16874e8abc5777a9a20b54c76e",
+            ),
+            (
+                "transfer",
+                "0x83afd3f4caedc6eebf44246fe54e38c95e3179a5ec9ea81740eca5b482d12e",
+            ),
+        ];
+
+        for (input, expected) in vectors {
+            let expected = Felt::from_hex_str(expected).unwrap();
+            assert_eq!(starknet_keccak(input.as_bytes()), expected, "{input}");
+        }
+    }
+
+    #[test]
+    fn truncated_keccak_clears_the_top_six_bits() {
+        let truncated = truncated_keccak([0xff; 32]);
+
+        let mut expected = [0xff; 32];
+        expected[0] = 0x03;
+        assert_eq!(truncated, Felt::from_be_bytes(expected).unwrap());
+    }
+
+    #[test]
+    fn selector_from_name_of_default_entry_points_is_zero() {
+        assert_eq!(selector_from_name("__default__"), Felt::ZERO);
+        assert_eq!(selector_from_name("__l1_default__"), Felt::ZERO);
+    }
+
+    #[test]
+    fn selector_from_name_is_starknet_keccak() {
+        assert_eq!(
+            selector_from_name("balanceOf"),
+            starknet_keccak(b"balanceOf")
+        );
+    }
+}
@@ -1,8 +1,14 @@
+/// Starknet Keccak hash function and entry point selectors.
+pub mod keccak;
+
 /// Pedersen hash function.
 pub mod pedersen;
 
 /// Poseidon hash function.
 pub mod poseidon;
 
+pub use keccak::{selector_from_name, starknet_keccak, truncated_keccak};
 pub use pedersen::{pedersen_hash, HashChain};
-pub use poseidon::{poseidon_hash, poseidon_hash_many, PoseidonHasher};
+pub use poseidon::{
+    poseidon_hash, poseidon_hash_many, poseidon_permute_many, PoseidonHasher, PoseidonState,
+};
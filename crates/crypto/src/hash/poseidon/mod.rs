@@ -3,4 +3,4 @@ mod hash;
 mod permutation;
 
 pub use hash::{poseidon_hash, poseidon_hash_many, PoseidonHasher};
-pub use permutation::{permute, PoseidonState};
+pub use permutation::{permute, poseidon_permute_many, PoseidonState};
@@ -71,6 +71,53 @@ pub fn permute(state: &mut PoseidonState) {
     }
 }
 
+/// The number of states which [poseidon_permute_many] takes through the rounds together.
+const BATCH_SIZE: usize = 64;
+
+/// Poseidon permutation of many independent states.
+///
+/// Equivalent to calling [permute] on each state. The states are split into batches which go
+/// through the rounds together, so that the field operations within a round are independent of
+/// each other. `states` is a contiguous buffer of three Montgomery form field elements per state,
+/// which is also the layout expected by a GPU implementation.
+///
+/// With the `rayon` feature, the batches are permuted in parallel.
+pub fn poseidon_permute_many(states: &mut [PoseidonState]) {
+    #[cfg(feature = "rayon")]
+    {
+        use rayon::prelude::*;
+        states.par_chunks_mut(BATCH_SIZE).for_each(permute_batch);
+    }
+
+    #[cfg(not(feature = "rayon"))]
+    states.chunks_mut(BATCH_SIZE).for_each(permute_batch);
+}
+
+/// Same as [permute], but round by round for all `states`.
+fn permute_batch(states: &mut [PoseidonState]) {
+    let mut idx = 0;
+
+    // Full rounds
+    for _ in 0..(FULL_ROUNDS / 2) {
+        states.iter_mut().for_each(|state| full_round(state, idx));
+        idx += 3;
+    }
+
+    // Partial rounds
+    for _ in 0..PARTIAL_ROUNDS {
+        states
+            .iter_mut()
+            .for_each(|state| partial_round(state, idx));
+        idx += 1;
+    }
+
+    // Full rounds
+    for _ in 0..(FULL_ROUNDS / 2) {
+        states.iter_mut().for_each(|state| full_round(state, idx));
+        idx += 3;
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::algebra::field::{montfelt_dec, MontFelt};
@@ -95,4 +142,27 @@ mod tests {
         permute(&mut state);
         assert_eq!(state, test_result);
     }
+
+    #[test]
+    fn permute_many() {
+        let rng = &mut rand::thread_rng();
+
+        // Spans several batches, the last of which is incomplete.
+        let states = (0..3 * BATCH_SIZE + 5)
+            .map(|_| {
+                [
+                    MontFelt::random(rng),
+                    MontFelt::random(rng),
+                    MontFelt::random(rng),
+                ]
+            })
+            .collect::<Vec<PoseidonState>>();
+
+        let mut expected = states.clone();
+        expected.iter_mut().for_each(permute);
+
+        let mut batched = states;
+        poseidon_permute_many(&mut batched);
+        assert_eq!(batched, expected);
+    }
 }
@@ -3,12 +3,14 @@
 /// Contains algebra such as finite fields and elliptic curves.
 pub mod algebra;
 
-/// Contains hash functions such as Pedersen and Poseidon.
+/// Contains hash functions such as Keccak, Pedersen and Poseidon.
 pub mod hash;
 
 /// Contains signature functions such as ECDSA.
 pub mod signature;
 
+pub use bitvec;
+
 pub use algebra::{
     AffinePoint, CurveOrderMontFelt, Felt, HexParseError, MontFelt, OverflowError, ProjectivePoint,
 };
@@ -1,4 +1,4 @@
-use pathfinder_common::{BlockHash, BlockNumber, EthereumChain, StateCommitment};
+use pathfinder_common::{BlockHash, BlockNumber, EthereumChain, GasPrice, StateCommitment};
 use pathfinder_crypto::Felt;
 use primitive_types::{H160, H256, U256};
 
@@ -23,10 +23,24 @@ pub struct EthereumStateUpdate {
     pub block_hash: BlockHash,
 }
 
+/// The gas prices of an Ethereum block, which the Starknet sequencer's L1 gas prices are
+/// derived from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct L1GasPrices {
+    /// The number of the Ethereum block.
+    pub block_number: u64,
+    /// The base fee per gas, in wei.
+    pub base_fee: GasPrice,
+    /// The base fee per blob gas, in wei, or [None] for blocks preceding the Cancun upgrade.
+    pub blob_base_fee: Option<GasPrice>,
+}
+
 #[async_trait::async_trait]
 pub trait EthereumApi {
     async fn get_starknet_state(&self, address: &H160) -> anyhow::Result<EthereumStateUpdate>;
     async fn get_chain(&self) -> anyhow::Result<EthereumChain>;
+    /// Returns the gas prices of the latest Ethereum block.
+    async fn get_gas_prices(&self) -> anyhow::Result<L1GasPrices>;
 }
 
 #[derive(Clone, Debug)]
@@ -147,6 +161,34 @@ impl EthereumApi for EthereumClient {
             x => EthereumChain::Other(x),
         })
     }
+
+    async fn get_gas_prices(&self) -> anyhow::Result<L1GasPrices> {
+        // The fee history includes the blob base fee, which blocks only contain indirectly as
+        // their excess blob gas, and whose computation changes between forks. Both lists also
+        // contain the fees of the next block.
+        let history = self
+            .call_ethereum(serde_json::json!({
+                "jsonrpc": "2.0",
+                "method": "eth_feeHistory",
+                "params": ["0x1", "latest", []],
+                "id": 0
+            }))
+            .await?;
+
+        let blob_base_fee = match &history["baseFeePerBlobGas"][0] {
+            serde_json::Value::Null => None,
+            // Zero before the Cancun upgrade.
+            value => {
+                Some(get_u256(value).and_then(get_gas_price)?).filter(|fee| *fee != GasPrice::ZERO)
+            }
+        };
+
+        Ok(L1GasPrices {
+            block_number: get_u256(&history["oldestBlock"])?.as_u64(),
+            base_fee: get_u256(&history["baseFeePerGas"][0]).and_then(get_gas_price)?,
+            blob_base_fee,
+        })
+    }
 }
 
 fn encode_ethereum_call_data(signature: &[u8]) -> String {
@@ -183,6 +225,11 @@ fn get_number(value: U256) -> anyhow::Result<BlockNumber> {
     BlockNumber::new(value).ok_or(anyhow::anyhow!("Failed to read u64 from U256"))
 }
 
+fn get_gas_price(value: U256) -> anyhow::Result<GasPrice> {
+    anyhow::ensure!(value <= U256::from(u128::MAX), "Gas price exceeds u128");
+    Ok(GasPrice(value.as_u128()))
+}
+
 fn lpad64(value: &str) -> String {
     let input = value.strip_prefix("0x").unwrap_or(value);
     let prefix = if value.starts_with("0x") { "0x" } else { "" };
@@ -311,6 +358,63 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_get_gas_prices() -> anyhow::Result<()> {
+        let server = MockServer::start_async().await;
+
+        let mock = server.mock(|when, then| {
+            when.path("/")
+                .method(POST)
+                .header("Content-type", "application/json")
+                .body(r#"{"id":0,"jsonrpc":"2.0","method":"eth_feeHistory","params":["0x1","latest",[]]}"#);
+            then.status(200)
+                .header("Content-type", "application/json")
+                .body(r#"{"jsonrpc":"2.0","id":0,"result":{"oldestBlock":"0x1312d00","baseFeePerGas":["0x3b9aca00","0x3b9aca01"],"gasUsedRatio":[0.5],"baseFeePerBlobGas":["0x1","0x2"],"blobGasUsedRatio":[0.5]}}"#);
+        });
+
+        let url = Url::parse(&server.url("/"))?;
+        let eth = EthereumClient::new(url)?;
+        let prices = eth.get_gas_prices().await?;
+
+        mock.assert();
+        assert_eq!(
+            prices,
+            L1GasPrices {
+                block_number: 20_000_000,
+                base_fee: GasPrice(1_000_000_000),
+                blob_base_fee: Some(GasPrice(1)),
+            }
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_gas_prices_before_cancun() -> anyhow::Result<()> {
+        let server = MockServer::start_async().await;
+
+        let mock = server.mock(|when, then| {
+            when.path("/").method(POST);
+            then.status(200)
+                .header("Content-type", "application/json")
+                .body(r#"{"jsonrpc":"2.0","id":0,"result":{"oldestBlock":"0x10","baseFeePerGas":["0x7","0x8"],"gasUsedRatio":[0.5]}}"#);
+        });
+
+        let url = Url::parse(&server.url("/"))?;
+        let eth = EthereumClient::new(url)?;
+        let prices = eth.get_gas_prices().await?;
+
+        mock.assert();
+        assert_eq!(
+            prices,
+            L1GasPrices {
+                block_number: 16,
+                base_fee: GasPrice(7),
+                blob_base_fee: None,
+            }
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_h256() {
         assert!(H256::from_str(
@@ -1,13 +1,16 @@
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
 use blockifier::{
-    context::TransactionContext,
+    context::{BlockContext, TransactionContext},
     execution::entry_point::{CallEntryPoint, EntryPointExecutionContext},
+    state::{cached_state::CachedState, state_api::State},
     transaction::objects::{DeprecatedTransactionInfo, TransactionInfo},
     versioned_constants::VersionedConstants,
 };
+use cached::{Cached, SizedCache};
 use cairo_vm::vm::runners::cairo_runner::ExecutionResources;
-use pathfinder_common::{CallParam, CallResultValue, ContractAddress, EntryPoint};
+use pathfinder_common::{BlockHash, CallParam, CallResultValue, ContractAddress, EntryPoint};
+use pathfinder_crypto::Felt;
 use starknet_api::core::PatriciaKey;
 
 use super::{
@@ -16,6 +19,60 @@ use super::{
     felt::{IntoFelt, IntoStarkFelt},
 };
 
+/// Caches the results of calls made at a canonical block.
+///
+/// Results are keyed by block hash, so a reorg can never cause the result of a call at a
+/// different block to be returned. Entries of reorged blocks are simply evicted over time.
+#[derive(Debug, Clone)]
+pub struct CallCache(Arc<Mutex<SizedCache<CallCacheKey, Vec<CallResultValue>>>>);
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CallCacheKey {
+    block_hash: BlockHash,
+    contract_address: ContractAddress,
+    entry_point_selector: EntryPoint,
+    /// Calldata can be arbitrarily long, so only its hash is kept.
+    calldata_hash: Felt,
+}
+
+impl CallCacheKey {
+    pub fn new(
+        block_hash: BlockHash,
+        contract_address: ContractAddress,
+        entry_point_selector: EntryPoint,
+        calldata: &[CallParam],
+    ) -> Self {
+        let calldata = calldata
+            .iter()
+            .map(|param| pathfinder_crypto::MontFelt::from(param.0))
+            .collect::<Vec<_>>();
+        let calldata_hash = pathfinder_crypto::hash::poseidon_hash_many(&calldata).into();
+
+        Self {
+            block_hash,
+            contract_address,
+            entry_point_selector,
+            calldata_hash,
+        }
+    }
+}
+
+impl Default for CallCache {
+    fn default() -> Self {
+        Self(Arc::new(Mutex::new(SizedCache::with_size(1024))))
+    }
+}
+
+impl CallCache {
+    pub fn get(&self, key: &CallCacheKey) -> Option<Vec<CallResultValue>> {
+        self.0.lock().unwrap().cache_get(key).cloned()
+    }
+
+    pub fn insert(&self, key: CallCacheKey, result: Vec<CallResultValue>) {
+        self.0.lock().unwrap().cache_set(key, result);
+    }
+}
+
 pub fn call(
     mut execution_state: ExecutionState<'_>,
     contract_address: ContractAddress,
@@ -24,6 +81,61 @@ pub fn call(
 ) -> Result<Vec<CallResultValue>, CallError> {
     let (mut state, block_context) = execution_state.starknet_state()?;
 
+    execute(
+        &mut state,
+        block_context,
+        contract_address,
+        entry_point_selector,
+        calldata,
+    )
+}
+
+/// A call made as part of a [multicall].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Call {
+    pub contract_address: ContractAddress,
+    pub entry_point_selector: EntryPoint,
+    pub calldata: Vec<CallParam>,
+}
+
+/// Executes the calls one after the other against the same state.
+///
+/// State read by a call is cached for the calls following it. Each call starts from the original
+/// state though, as any state changes are discarded once it returns. The results are returned in
+/// the order of the calls, and the failure of a call does not affect the others.
+pub fn multicall(
+    mut execution_state: ExecutionState<'_>,
+    calls: Vec<Call>,
+) -> anyhow::Result<Vec<Result<Vec<CallResultValue>, CallError>>> {
+    let (mut state, block_context) = execution_state.starknet_state()?;
+
+    let results = calls
+        .into_iter()
+        .map(|call| {
+            let mut call_state = CachedState::<_>::create_transactional(&mut state);
+            let result = execute(
+                &mut call_state,
+                block_context.clone(),
+                call.contract_address,
+                call.entry_point_selector,
+                call.calldata,
+            );
+            call_state.abort();
+
+            result
+        })
+        .collect();
+
+    Ok(results)
+}
+
+fn execute(
+    state: &mut dyn State,
+    block_context: BlockContext,
+    contract_address: ContractAddress,
+    entry_point_selector: EntryPoint,
+    calldata: Vec<CallParam>,
+) -> Result<Vec<CallResultValue>, CallError> {
     let contract_address = starknet_api::core::ContractAddress(PatriciaKey::try_from(
         contract_address.0.into_starkfelt(),
     )?);
@@ -53,7 +165,7 @@ pub fn call(
         false,
     )?;
 
-    let call_info = call_entry_point.execute(&mut state, &mut resources, &mut context)?;
+    let call_info = call_entry_point.execute(state, &mut resources, &mut context)?;
 
     let result = call_info
         .execution
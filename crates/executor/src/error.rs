@@ -5,11 +5,52 @@ use blockifier::{
     state::errors::StateError,
     transaction::errors::TransactionExecutionError as BlockifierTransactionExecutionError,
 };
+use cairo_vm::vm::errors::{cairo_run_errors::CairoRunError, vm_errors::VirtualMachineError};
+
+/// The message of [VirtualMachineError::UnfinishedExecution], which ends up in the revert reasons
+/// of transactions running out of Cairo steps.
+const STEP_LIMIT_MESSAGE: &str = "RunResources has no remaining steps";
+
+/// Whether the transaction failed because it ran out of Cairo steps.
+fn transaction_step_limit_exceeded(error: &BlockifierTransactionExecutionError) -> bool {
+    use BlockifierTransactionExecutionError::*;
+    match error {
+        ContractConstructorExecutionFailed(e) | ExecutionError(e) | ValidateTransactionError(e) => {
+            step_limit_exceeded(e)
+        }
+        _ => false,
+    }
+}
+
+/// Whether the entry point failed because it ran out of Cairo steps, which the VM reports as
+/// [VirtualMachineError::UnfinishedExecution].
+///
+/// Nested calls running out of steps reach their caller as hint errors, which the blockifier
+/// only keeps as messages. These are reported as contract errors.
+fn step_limit_exceeded(error: &BlockifierEntryPointExecutionError) -> bool {
+    let run_error = match error {
+        BlockifierEntryPointExecutionError::CairoRunError(e)
+        | BlockifierEntryPointExecutionError::VirtualMachineExecutionErrorWithTrace {
+            source: e,
+            ..
+        } => e,
+        _ => return false,
+    };
+    let vm_error = match run_error {
+        CairoRunError::VirtualMachine(e) => e,
+        CairoRunError::VmException(e) => &e.inner_exc,
+        _ => return false,
+    };
+
+    matches!(vm_error, VirtualMachineError::UnfinishedExecution)
+}
 
 #[derive(Debug)]
 pub enum CallError {
     ContractNotFound,
     InvalidMessageSelector,
+    /// Execution exceeded the step limit of the call.
+    StepLimitExceeded,
     ContractError(anyhow::Error),
     Internal(anyhow::Error),
     Custom(anyhow::Error),
@@ -18,6 +59,9 @@ pub enum CallError {
 impl From<BlockifierTransactionExecutionError> for CallError {
     fn from(value: BlockifierTransactionExecutionError) -> Self {
         use BlockifierTransactionExecutionError::*;
+        if transaction_step_limit_exceeded(&value) {
+            return Self::StepLimitExceeded;
+        }
         match value {
             ContractConstructorExecutionFailed(e)
             | ExecutionError(e)
@@ -37,6 +81,9 @@ impl From<BlockifierTransactionExecutionError> for CallError {
 
 impl From<BlockifierEntryPointExecutionError> for CallError {
     fn from(e: BlockifierEntryPointExecutionError) -> Self {
+        if step_limit_exceeded(&e) {
+            return Self::StepLimitExceeded;
+        }
         match e {
             BlockifierEntryPointExecutionError::PreExecutionError(
                 PreExecutionError::EntryPointNotFound(_),
@@ -76,6 +123,10 @@ pub enum TransactionExecutionError {
         transaction_index: usize,
         error: String,
     },
+    /// Execution of the transaction exceeded the step limit.
+    StepLimitExceeded {
+        transaction_index: usize,
+    },
     Internal(anyhow::Error),
     Custom(anyhow::Error),
 }
@@ -103,17 +154,101 @@ impl From<anyhow::Error> for TransactionExecutionError {
 
 impl TransactionExecutionError {
     pub fn new(transaction_index: usize, error: BlockifierTransactionExecutionError) -> Self {
+        if transaction_step_limit_exceeded(&error) {
+            return Self::StepLimitExceeded { transaction_index };
+        }
         Self::ExecutionError {
             transaction_index,
             error: error.to_string(),
         }
     }
+
+    /// Creates an error from the revert reason of a transaction.
+    ///
+    /// The blockifier only reports the message of the VM error in revert reasons, so running out
+    /// of steps is recognized by the message of [VirtualMachineError::UnfinishedExecution].
+    pub(crate) fn reverted(transaction_index: usize, revert_error: String) -> Self {
+        if revert_error.contains(STEP_LIMIT_MESSAGE) {
+            return Self::StepLimitExceeded { transaction_index };
+        }
+        Self::ExecutionError {
+            transaction_index,
+            error: revert_error,
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn step_limit_in_revert_reason() {
+        let revert_error = format!("Error in the called contract: {STEP_LIMIT_MESSAGE}");
+        let err = TransactionExecutionError::reverted(3, revert_error);
+        assert!(matches!(
+            err,
+            TransactionExecutionError::StepLimitExceeded {
+                transaction_index: 3
+            }
+        ));
+
+        let err = TransactionExecutionError::reverted(3, "Assertion failed".to_owned());
+        assert!(matches!(
+            err,
+            TransactionExecutionError::ExecutionError { .. }
+        ));
+    }
+
+    fn out_of_steps() -> BlockifierEntryPointExecutionError {
+        BlockifierEntryPointExecutionError::CairoRunError(CairoRunError::VirtualMachine(
+            VirtualMachineError::UnfinishedExecution,
+        ))
+    }
+
+    #[test]
+    fn step_limit_message_matches_vm_error() {
+        assert!(VirtualMachineError::UnfinishedExecution
+            .to_string()
+            .contains(STEP_LIMIT_MESSAGE));
+    }
+
+    #[test]
+    fn step_limit_in_entry_point_error() {
+        let err = CallError::from(out_of_steps());
+        assert!(matches!(err, CallError::StepLimitExceeded));
+
+        let err = CallError::from(BlockifierEntryPointExecutionError::RecursionDepthExceeded);
+        assert!(matches!(err, CallError::ContractError(_)));
+    }
+
+    #[test]
+    fn step_limit_in_transaction_error() {
+        let err = TransactionExecutionError::new(
+            2,
+            BlockifierTransactionExecutionError::ExecutionError(out_of_steps()),
+        );
+        assert!(matches!(
+            err,
+            TransactionExecutionError::StepLimitExceeded {
+                transaction_index: 2
+            }
+        ));
+
+        let err = CallError::from(
+            BlockifierTransactionExecutionError::ValidateTransactionError(out_of_steps()),
+        );
+        assert!(matches!(err, CallError::StepLimitExceeded));
+    }
+
+    #[test]
+    fn other_vm_errors_are_not_step_limit() {
+        let err = BlockifierEntryPointExecutionError::CairoRunError(CairoRunError::VirtualMachine(
+            VirtualMachineError::NoImm,
+        ));
+        assert!(matches!(CallError::from(err), CallError::ContractError(_)));
+    }
+
     mod transaction_errors_are_mapped_correctly {
         //! Some variants in the blockifier are opaque and omit the inner error's data. We've patched this manually
         //! and this tests ensures we don't accidentally stutter once the blockifier fixes this.
@@ -58,10 +58,10 @@ pub fn estimate(
             Ok(tx_info) => {
                 if let Some(revert_error) = tx_info.revert_error {
                     tracing::debug!(%revert_error, "Transaction reverted");
-                    return Err(TransactionExecutionError::ExecutionError {
-                        transaction_index: transaction_idx,
-                        error: revert_error,
-                    });
+                    return Err(TransactionExecutionError::reverted(
+                        transaction_idx,
+                        revert_error,
+                    ));
                 }
 
                 tracing::trace!(actual_fee=%tx_info.actual_fee.0, actual_resources=?tx_info.actual_resources, "Transaction estimation finished");
@@ -1,5 +1,6 @@
 use std::sync::Arc;
 
+use super::fork::ForkedState;
 use super::pending::PendingStateReader;
 use super::state_reader::PathfinderStateReader;
 use crate::IntoStarkFelt;
@@ -22,6 +23,7 @@ pub const STRK_FEE_TOKEN_ADDRESS: ContractAddress =
     contract_address!("0x04718f5a0fc34cc1af16a1cdee98ffb20c31f5cd61d6ab07201858f4287c938d");
 
 mod versioned_constants {
+    use pathfinder_common::protocol::ProtocolChange;
     use pathfinder_common::StarknetVersion;
 
     use super::VersionedConstants;
@@ -29,9 +31,6 @@ mod versioned_constants {
     const BLOCKIFIER_VERSIONED_CONSTANTS_JSON_0_13_0: &[u8] =
         include_bytes!("../resources/versioned_constants_13_0.json");
 
-    const STARKNET_VERSION_MATCHING_LATEST_BLOCKIFIER: semver::Version =
-        semver::Version::new(0, 13, 1);
-
     lazy_static::lazy_static! {
         pub static ref BLOCKIFIER_VERSIONED_CONSTANTS_0_13_0: VersionedConstants =
             serde_json::from_slice(BLOCKIFIER_VERSIONED_CONSTANTS_JSON_0_13_0).unwrap();
@@ -40,19 +39,12 @@ mod versioned_constants {
     pub(super) fn for_version(
         version: &StarknetVersion,
     ) -> anyhow::Result<&'static VersionedConstants> {
-        let versioned_constants = match version.parse_as_semver()? {
-            Some(version) => {
-                // Right now we only properly support two versions: 0.13.0 and 0.13.1.
-                // We use 0.13.0 for all blocks _before_ 0.13.1.
-                if version < STARKNET_VERSION_MATCHING_LATEST_BLOCKIFIER {
-                    &BLOCKIFIER_VERSIONED_CONSTANTS_0_13_0
-                } else {
-                    VersionedConstants::latest_constants()
-                }
-            }
-            // The default for blocks that don't have a version number.
-            // This is supposed to be the _oldest_ version we support.
-            None => &BLOCKIFIER_VERSIONED_CONSTANTS_0_13_0,
+        // Right now we only properly support two versions: 0.13.0 and 0.13.1.
+        // We use 0.13.0 for all blocks _before_ 0.13.1, including those without a version.
+        let versioned_constants = if version.supports(ProtocolChange::LatestVersionedConstants)? {
+            VersionedConstants::latest_constants()
+        } else {
+            &BLOCKIFIER_VERSIONED_CONSTANTS_0_13_0
         };
 
         Ok(versioned_constants)
@@ -65,14 +57,17 @@ pub struct ExecutionState<'tx> {
     pub header: BlockHeader,
     execute_on_parent_state: bool,
     pending_state: Option<Arc<StateUpdate>>,
+    state_overrides: Option<Arc<StateUpdate>>,
     allow_use_kzg_data: bool,
+    max_steps: Option<u32>,
+    fork: Option<Arc<dyn ForkedState>>,
 }
 
 impl<'tx> ExecutionState<'tx> {
     pub(super) fn starknet_state(
         &mut self,
     ) -> anyhow::Result<(
-        CachedState<PendingStateReader<PathfinderStateReader<'_>>>,
+        CachedState<PendingStateReader<PendingStateReader<PathfinderStateReader<'_>>>>,
         BlockContext,
     )> {
         let block_number = if self.execute_on_parent_state {
@@ -85,9 +80,13 @@ impl<'tx> ExecutionState<'tx> {
             self.transaction,
             block_number,
             self.pending_state.is_some(),
+            self.fork.as_deref(),
         );
         let pending_state_reader = PendingStateReader::new(raw_reader, self.pending_state.clone());
-        let mut cached_state = CachedState::new(pending_state_reader, GlobalContractCache::new(16));
+        let overridden_state_reader =
+            PendingStateReader::new(pending_state_reader, self.state_overrides.clone());
+        let mut cached_state =
+            CachedState::new(overridden_state_reader, GlobalContractCache::new(16));
 
         let chain_info = self.chain_info()?;
         let block_info = self.block_info()?;
@@ -97,10 +96,17 @@ impl<'tx> ExecutionState<'tx> {
         let old_block_number_and_hash = if self.header.number.get() >= 10 {
             let block_number_whose_hash_becomes_available =
                 pathfinder_common::BlockNumber::new_or_panic(self.header.number.get() - 10);
-            let block_hash = self
+            let block_hash = match self
                 .transaction
                 .block_hash(block_number_whose_hash_becomes_available.into())?
-                .context("Getting historical block hash")?;
+            {
+                Some(block_hash) => Some(block_hash),
+                None => match &self.fork {
+                    Some(fork) => fork.block_hash(block_number_whose_hash_becomes_available)?,
+                    None => None,
+                },
+            }
+            .context("Getting historical block hash")?;
 
             tracing::trace!(%block_number_whose_hash_becomes_available, %block_hash, "Setting historical block hash");
 
@@ -114,14 +120,22 @@ impl<'tx> ExecutionState<'tx> {
             None
         };
 
-        let versioned_constants = versioned_constants::for_version(&self.header.starknet_version)?;
+        let mut versioned_constants =
+            versioned_constants::for_version(&self.header.starknet_version)?.to_owned();
+
+        if let Some(max_steps) = self.max_steps {
+            versioned_constants.invoke_tx_max_n_steps =
+                versioned_constants.invoke_tx_max_n_steps.min(max_steps);
+            versioned_constants.validate_max_n_steps =
+                versioned_constants.validate_max_n_steps.min(max_steps);
+        }
 
         let block_context = pre_process_block(
             &mut cached_state,
             old_block_number_and_hash,
             block_info,
             chain_info,
-            versioned_constants.to_owned(),
+            versioned_constants,
         )?;
 
         Ok((cached_state, block_context))
@@ -213,8 +227,11 @@ impl<'tx> ExecutionState<'tx> {
             chain_id,
             header,
             pending_state,
+            state_overrides: None,
             execute_on_parent_state: true,
             allow_use_kzg_data: true,
+            max_steps: None,
+            fork: None,
         }
     }
 
@@ -230,10 +247,37 @@ impl<'tx> ExecutionState<'tx> {
             chain_id,
             header,
             pending_state,
+            state_overrides: None,
             execute_on_parent_state: false,
             allow_use_kzg_data: l1_blob_data_availability == L1BlobDataAvailability::Enabled,
+            max_steps: None,
+            fork: None,
         }
     }
+
+    /// Applies `state_overrides` on top of the block's (and pending) state.
+    ///
+    /// Only storage values, nonces and contract class hashes are taken from the overrides. Any
+    /// classes they reference must already be declared.
+    pub fn with_state_overrides(mut self, state_overrides: StateUpdate) -> Self {
+        self.state_overrides = Some(Arc::new(state_overrides));
+        self
+    }
+
+    /// Limits the number of Cairo steps each transaction or call may execute.
+    ///
+    /// The limit can only lower the step limits of the Starknet version being executed,
+    /// which apply regardless.
+    pub fn with_max_steps(mut self, max_steps: Option<u32>) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
+
+    /// Falls through to the state of `fork` for anything which is not in the database.
+    pub fn with_fork(mut self, fork: Option<Arc<dyn ForkedState>>) -> Self {
+        self.fork = fork;
+        self
+    }
 }
 
 #[derive(Copy, Clone, PartialEq)]
@@ -0,0 +1,41 @@
+use pathfinder_common::{
+    BlockHash, BlockNumber, CasmHash, ClassHash, ContractAddress, ContractNonce, StorageAddress,
+    StorageValue,
+};
+
+/// State of another network at the block it was forked from.
+///
+/// The execution state falls through to the fork for anything it cannot find in the local
+/// database, which only holds the blocks produced on top of the fork.
+pub trait ForkedState: Send + Sync {
+    /// The block the state is forked from.
+    fn block_number(&self) -> BlockNumber;
+
+    /// Hash of a block at or before the fork block.
+    fn block_hash(&self, block: BlockNumber) -> anyhow::Result<Option<BlockHash>>;
+
+    fn storage_value(
+        &self,
+        contract_address: ContractAddress,
+        key: StorageAddress,
+    ) -> anyhow::Result<Option<StorageValue>>;
+
+    fn contract_nonce(
+        &self,
+        contract_address: ContractAddress,
+    ) -> anyhow::Result<Option<ContractNonce>>;
+
+    fn contract_class_hash(
+        &self,
+        contract_address: ContractAddress,
+    ) -> anyhow::Result<Option<ClassHash>>;
+
+    /// The class definition in the gateway's format, of Cairo 0 and Sierra classes alike. Execution
+    /// only uses it for classes without a compiled definition, which are Cairo 0 classes.
+    fn class_definition(&self, class_hash: ClassHash) -> anyhow::Result<Option<Vec<u8>>>;
+
+    /// The compiled definition of a Sierra class.
+    fn casm_definition(&self, class_hash: ClassHash) -> anyhow::Result<Option<Vec<u8>>>;
+
+    fn casm_hash(&self, class_hash: ClassHash) -> anyhow::Result<Option<CasmHash>>;
+}
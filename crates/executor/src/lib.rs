@@ -4,6 +4,7 @@ pub(crate) mod error;
 pub(crate) mod estimate;
 pub(crate) mod execution_state;
 pub(crate) mod felt;
+pub(crate) mod fork;
 pub(crate) mod lru_cache;
 pub(crate) mod pending;
 pub(crate) mod simulate;
@@ -11,7 +12,7 @@ pub(crate) mod state_reader;
 pub(crate) mod transaction;
 pub mod types;
 
-pub use call::call;
+pub use call::{call, multicall, Call, CallCache, CallCacheKey};
 pub use class::{parse_casm_definition, parse_deprecated_class_definition};
 pub use error::{CallError, TransactionExecutionError};
 pub use estimate::estimate;
@@ -19,6 +20,7 @@ pub use execution_state::{
     ExecutionState, L1BlobDataAvailability, ETH_FEE_TOKEN_ADDRESS, STRK_FEE_TOKEN_ADDRESS,
 };
 pub use felt::{IntoFelt, IntoStarkFelt};
+pub use fork::ForkedState;
 pub use simulate::{simulate, trace, TraceCache};
 
 // re-export blockifier transaction type since it's exposed on our API
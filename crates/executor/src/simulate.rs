@@ -179,10 +179,7 @@ pub fn trace(
         let mut tx_state = CachedState::<_>::create_transactional(&mut state);
         let tx_info = tx
             .execute(&mut tx_state, &block_context, charge_fee, validate)
-            .map_err(|e| TransactionExecutionError::ExecutionError {
-                transaction_index: transaction_idx,
-                error: e.to_string(),
-            })?;
+            .map_err(|e| TransactionExecutionError::new(transaction_idx, e))?;
         let state_diff = to_state_diff(&mut tx_state, tx_declared_deprecated_class_hash)?;
         tx_state.commit();
 
@@ -3,6 +3,7 @@ use pathfinder_common::{BlockNumber, ClassHash, StorageAddress, StorageValue};
 use pathfinder_crypto::Felt;
 use starknet_api::{hash::StarkFelt, StarknetApiError};
 
+use crate::fork::ForkedState;
 use crate::lru_cache::GLOBAL_CACHE;
 
 use super::felt::{IntoFelt, IntoStarkFelt};
@@ -14,6 +15,9 @@ pub(super) struct PathfinderStateReader<'tx> {
     // This flag makes it possible to find these classes -- essentially makes the state
     // reader look up classes which are not declared at a canonical block yet.
     ignore_block_number_for_classes: bool,
+    // State of the network the local chain was forked from, if any. Consulted for anything
+    // which is not found in the database.
+    fork: Option<&'tx dyn ForkedState>,
 }
 
 impl<'tx> PathfinderStateReader<'tx> {
@@ -21,11 +25,13 @@ impl<'tx> PathfinderStateReader<'tx> {
         transaction: &'tx pathfinder_storage::Transaction<'tx>,
         block_number: Option<BlockNumber>,
         ignore_block_number_for_classes: bool,
+        fork: Option<&'tx dyn ForkedState>,
     ) -> Self {
         Self {
             transaction,
             block_number,
             ignore_block_number_for_classes,
+            fork,
         }
     }
 
@@ -60,9 +66,18 @@ impl<'tx> PathfinderStateReader<'tx> {
                 .casm_definition_at_with_block_number(block_id, pathfinder_class_hash)
         };
 
-        if let Some((definition_block_number, casm_definition)) =
-            casm_definition.map_err(map_anyhow_to_state_err)?
-        {
+        let casm_definition = match casm_definition.map_err(map_anyhow_to_state_err)? {
+            Some(casm_definition) => Some(casm_definition),
+            None => match self.fork {
+                Some(fork) => fork
+                    .casm_definition(pathfinder_class_hash)
+                    .map_err(map_anyhow_to_state_err)?
+                    .map(|definition| (Some(fork.block_number()), definition)),
+                None => None,
+            },
+        };
+
+        if let Some((definition_block_number, casm_definition)) = casm_definition {
             let casm_definition = String::from_utf8(casm_definition).map_err(|error| {
                 StateError::StateReadError(format!(
                     "Class definition is not valid UTF-8: {}",
@@ -93,9 +108,18 @@ impl<'tx> PathfinderStateReader<'tx> {
                 })
         };
 
-        if let Some((definition_block_number, definition)) =
-            definition.map_err(map_anyhow_to_state_err)?
-        {
+        let definition = match definition.map_err(map_anyhow_to_state_err)? {
+            Some(definition) => Some(definition),
+            None => match self.fork {
+                Some(fork) => fork
+                    .class_definition(pathfinder_class_hash)
+                    .map_err(map_anyhow_to_state_err)?
+                    .map(|definition| (Some(fork.block_number()), definition)),
+                None => None,
+            },
+        };
+
+        if let Some((definition_block_number, definition)) = definition {
             let definition = String::from_utf8(definition).map_err(|error| {
                 StateError::StateReadError(format!(
                     "Class definition is not valid UTF-8: {}",
@@ -150,8 +174,15 @@ impl StateReader for PathfinderStateReader<'_> {
         let storage_val = self
             .transaction
             .storage_value(block_id, pathfinder_contract_address, storage_key)
-            .map_err(map_anyhow_to_state_err)?
-            .unwrap_or(StorageValue(Felt::ZERO));
+            .map_err(map_anyhow_to_state_err)?;
+
+        let storage_val = match (storage_val, self.fork) {
+            (None, Some(fork)) => fork
+                .storage_value(pathfinder_contract_address, storage_key)
+                .map_err(map_anyhow_to_state_err)?,
+            (storage_val, _) => storage_val,
+        }
+        .unwrap_or(StorageValue(Felt::ZERO));
 
         tracing::trace!(storage_value=%storage_val, "Got storage value");
 
@@ -180,8 +211,15 @@ impl StateReader for PathfinderStateReader<'_> {
         let nonce = self
             .transaction
             .contract_nonce(pathfinder_contract_address, block_id)
-            .map_err(map_anyhow_to_state_err)?
-            .unwrap_or(pathfinder_common::ContractNonce::ZERO);
+            .map_err(map_anyhow_to_state_err)?;
+
+        let nonce = match (nonce, self.fork) {
+            (None, Some(fork)) => fork
+                .contract_nonce(pathfinder_contract_address)
+                .map_err(map_anyhow_to_state_err)?,
+            (nonce, _) => nonce,
+        }
+        .unwrap_or(pathfinder_common::ContractNonce::ZERO);
 
         Ok(starknet_api::core::Nonce(nonce.0.into_starkfelt()))
     }
@@ -208,6 +246,13 @@ impl StateReader for PathfinderStateReader<'_> {
             .contract_class_hash(block_id, pathfinder_contract_address)
             .map_err(map_anyhow_to_state_err)?;
 
+        let class_hash = match (class_hash, self.fork) {
+            (None, Some(fork)) => fork
+                .contract_class_hash(pathfinder_contract_address)
+                .map_err(map_anyhow_to_state_err)?,
+            (class_hash, _) => class_hash,
+        };
+
         let Some(class_hash) = class_hash else {
             return Ok(starknet_api::core::ClassHash(
                 ClassHash::ZERO.0.into_starkfelt(),
@@ -268,7 +313,14 @@ impl StateReader for PathfinderStateReader<'_> {
             self.transaction.casm_hash_at(block_id, class_hash)
         };
 
-        let casm_hash = casm_hash.map_err(map_anyhow_to_state_err)?.ok_or_else(|| {
+        let casm_hash = match (casm_hash.map_err(map_anyhow_to_state_err)?, self.fork) {
+            (None, Some(fork)) => fork
+                .casm_hash(class_hash)
+                .map_err(map_anyhow_to_state_err)?,
+            (casm_hash, _) => casm_hash,
+        };
+
+        let casm_hash = casm_hash.ok_or_else(|| {
             StateError::StateReadError("Error getting compiled class hash".to_owned())
         })?;
 
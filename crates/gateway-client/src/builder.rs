@@ -9,9 +9,11 @@
 //!   2. [Method](stage::Method) where you select the REST API method.
 //!   3. [Params](stage::Params) where you select the retry behavior.
 //!   4. [Final](stage::Final) where you select the REST operation type, which is then executed.
+use crate::circuit_breaker::CircuitBreaker;
 use crate::metrics::{with_metrics, BlockTag, RequestMetadata};
 use pathfinder_common::{BlockId, ClassHash, TransactionHash};
 use starknet_gateway_types::error::SequencerError;
+use starknet_gateway_types::unknown_fields::unknown_fields;
 
 const X_THROTTLING_BYPASS: &str = "X-Throttling-Bypass";
 
@@ -20,6 +22,8 @@ pub struct Request<'a, S: RequestState> {
     state: S,
     url: reqwest::Url,
     api_key: Option<String>,
+    headers: &'a reqwest::header::HeaderMap,
+    circuit_breaker: &'a CircuitBreaker,
     client: &'a reqwest::Client,
 }
 
@@ -71,11 +75,15 @@ impl<'a> Request<'a, stage::Init> {
         client: &'a reqwest::Client,
         url: reqwest::Url,
         api_key: Option<String>,
+        headers: &'a reqwest::header::HeaderMap,
+        circuit_breaker: &'a CircuitBreaker,
     ) -> Request<'a, stage::Method> {
         Request {
             url,
             client,
             api_key,
+            headers,
+            circuit_breaker,
             state: stage::Method,
         }
     }
@@ -153,6 +161,8 @@ impl<'a> Request<'a, stage::Method> {
             url: self.url,
             client: self.client,
             api_key: self.api_key,
+            headers: self.headers,
+            circuit_breaker: self.circuit_breaker,
             state: stage::Params {
                 meta: RequestMetadata::new(method),
             },
@@ -211,6 +221,8 @@ impl<'a> Request<'a, stage::Params> {
             url: self.url,
             client: self.client,
             api_key: self.api_key,
+            headers: self.headers,
+            circuit_breaker: self.circuit_breaker,
             state: stage::Final {
                 meta: self.state.meta,
                 retry,
@@ -228,12 +240,13 @@ impl<'a> Request<'a, stage::Final> {
         async fn send_request<T: serde::de::DeserializeOwned>(
             url: reqwest::Url,
             api_key: Option<String>,
+            headers: &reqwest::header::HeaderMap,
             client: &reqwest::Client,
             meta: RequestMetadata,
         ) -> Result<T, SequencerError> {
             with_metrics(meta, async move {
                 tracing::trace!(%url, "Fetching data from feeder gateway");
-                let request = client.get(url);
+                let request = client.get(url).headers(headers.clone());
                 let request = match api_key {
                     Some(api_key) => request.header(X_THROTTLING_BYPASS, api_key),
                     None => request,
@@ -245,13 +258,31 @@ impl<'a> Request<'a, stage::Final> {
         }
 
         match self.state.retry {
-            false => send_request(self.url, self.api_key, self.client, self.state.meta).await,
+            false => {
+                self.circuit_breaker
+                    .call(send_request(
+                        self.url,
+                        self.api_key,
+                        self.headers,
+                        self.client,
+                        self.state.meta,
+                    ))
+                    .await
+            }
             true => {
                 retry0(
                     || async {
                         let url = self.url.clone();
                         let api_key = self.api_key.clone();
-                        send_request(url, api_key, self.client, self.state.meta).await
+                        self.circuit_breaker
+                            .call(send_request(
+                                url,
+                                api_key,
+                                self.headers,
+                                self.client,
+                                self.state.meta,
+                            ))
+                            .await
                     },
                     retry_condition,
                 )
@@ -260,17 +291,41 @@ impl<'a> Request<'a, stage::Final> {
         }
     }
 
+    /// Same as [get](Self::get), but also logs the fields of the reply which `T` ignored because
+    /// it does not know about them.
+    pub async fn get_logging_unknown_fields<T>(self) -> Result<T, SequencerError>
+    where
+        T: serde::de::DeserializeOwned + serde::Serialize,
+    {
+        let method = self.state.meta.method;
+        let reply: WithRaw<T> = self.get().await?;
+
+        match serde_json::to_value(&reply.value) {
+            Ok(known) => {
+                for field in unknown_fields(&reply.raw, &known) {
+                    tracing::info!(%method, %field, "Ignoring unknown field in feeder gateway reply");
+                }
+            }
+            Err(error) => {
+                tracing::debug!(%method, %error, "Failed to serialize reply to find unknown fields")
+            }
+        }
+
+        Ok(reply.value)
+    }
+
     /// Sends the Sequencer request as a REST `GET` operation and returns the response's bytes.
     pub async fn get_as_bytes(self) -> Result<bytes::Bytes, SequencerError> {
         async fn get_as_bytes_inner(
             url: reqwest::Url,
             api_key: Option<String>,
+            headers: &reqwest::header::HeaderMap,
             client: &reqwest::Client,
             meta: RequestMetadata,
         ) -> Result<bytes::Bytes, SequencerError> {
             with_metrics(meta, async {
                 tracing::trace!(%url, "Fetching binary data from feeder gateway");
-                let request = client.get(url);
+                let request = client.get(url).headers(headers.clone());
                 let request = match api_key {
                     Some(api_key) => request.header(X_THROTTLING_BYPASS, api_key),
                     None => request,
@@ -284,13 +339,31 @@ impl<'a> Request<'a, stage::Final> {
         }
 
         match self.state.retry {
-            false => get_as_bytes_inner(self.url, self.api_key, self.client, self.state.meta).await,
+            false => {
+                self.circuit_breaker
+                    .call(get_as_bytes_inner(
+                        self.url,
+                        self.api_key,
+                        self.headers,
+                        self.client,
+                        self.state.meta,
+                    ))
+                    .await
+            }
             true => {
                 retry0(
                     || async {
                         let url = self.url.clone();
                         let api_key = self.api_key.clone();
-                        get_as_bytes_inner(url, api_key, self.client, self.state.meta).await
+                        self.circuit_breaker
+                            .call(get_as_bytes_inner(
+                                url,
+                                api_key,
+                                self.headers,
+                                self.client,
+                                self.state.meta,
+                            ))
+                            .await
                     },
                     retry_condition,
                 )
@@ -315,6 +388,7 @@ impl<'a> Request<'a, stage::Final> {
         async fn post_with_json_inner<T, J>(
             url: reqwest::Url,
             api_key: Option<String>,
+            headers: &reqwest::header::HeaderMap,
             client: &reqwest::Client,
             meta: RequestMetadata,
             json: &J,
@@ -325,7 +399,7 @@ impl<'a> Request<'a, stage::Final> {
             J: serde::Serialize + ?Sized,
         {
             with_metrics(meta, async {
-                let request = client.post(url);
+                let request = client.post(url).headers(headers.clone());
                 let request = match api_key {
                     Some(api_key) => request.header(X_THROTTLING_BYPASS, api_key),
                     None => request,
@@ -342,15 +416,17 @@ impl<'a> Request<'a, stage::Final> {
 
         match self.state.retry {
             false => {
-                post_with_json_inner(
-                    self.url,
-                    self.api_key,
-                    self.client,
-                    self.state.meta,
-                    json,
-                    timeout,
-                )
-                .await
+                self.circuit_breaker
+                    .call(post_with_json_inner(
+                        self.url,
+                        self.api_key,
+                        self.headers,
+                        self.client,
+                        self.state.meta,
+                        json,
+                        timeout,
+                    ))
+                    .await
             }
             true => {
                 retry0(
@@ -358,15 +434,17 @@ impl<'a> Request<'a, stage::Final> {
                         tracing::trace!(url=%self.url, "Posting data to gateway");
                         let url = self.url.clone();
                         let api_key = self.api_key.clone();
-                        post_with_json_inner(
-                            url,
-                            api_key,
-                            self.client,
-                            self.state.meta,
-                            json,
-                            timeout,
-                        )
-                        .await
+                        self.circuit_breaker
+                            .call(post_with_json_inner(
+                                url,
+                                api_key,
+                                self.headers,
+                                self.client,
+                                self.state.meta,
+                                json,
+                                timeout,
+                            ))
+                            .await
                     },
                     retry_condition,
                 )
@@ -386,6 +464,23 @@ where
     Ok(response)
 }
 
+/// A reply parsed as `T` along with the raw JSON it was parsed from.
+struct WithRaw<T> {
+    value: T,
+    raw: serde_json::Value,
+}
+
+impl<'de, T: serde::de::DeserializeOwned> serde::Deserialize<'de> for WithRaw<T> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let raw = <serde_json::Value as serde::Deserialize>::deserialize(deserializer)?;
+        let value = T::deserialize(&raw).map_err(serde::de::Error::custom)?;
+        Ok(Self { value, raw })
+    }
+}
+
 /// Helper function which allows skipping deserialization when required.
 async fn parse_raw(response: reqwest::Response) -> Result<reqwest::Response, SequencerError> {
     use starknet_gateway_types::error::StarknetError;
@@ -761,4 +856,77 @@ mod tests {
             Ok(())
         }
     }
+
+    mod headers_are_set_when_configured {
+        use crate::{test_utils::GATEWAY_TIMEOUT, Client};
+        use httpmock::{prelude::*, Mock};
+        use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+        use serde_json::json;
+
+        async fn setup_with_headers(server: &MockServer) -> (Mock<'_>, Client) {
+            let mock = server.mock(|when, then| {
+                when.any_request()
+                    .header("X-Provider-Key", "secret")
+                    .header("X-Other", "value");
+                then.status(200).json_body(json!({}));
+            });
+
+            let mut headers = HeaderMap::new();
+            headers.insert(
+                HeaderName::from_static("x-provider-key"),
+                HeaderValue::from_static("secret"),
+            );
+            headers.insert(
+                HeaderName::from_static("x-other"),
+                HeaderValue::from_static("value"),
+            );
+
+            let client = Client::with_base_url(server.base_url().parse().unwrap(), GATEWAY_TIMEOUT)
+                .unwrap()
+                .with_headers(headers);
+
+            (mock, client)
+        }
+
+        #[tokio::test]
+        async fn get() -> anyhow::Result<()> {
+            let server = MockServer::start_async().await;
+            let (mock, client) = setup_with_headers(&server).await;
+
+            let _: serde_json::Value = client
+                .feeder_gateway_request()
+                .with_method("")
+                .with_retry(false)
+                .get()
+                .await?;
+
+            let _: bytes::Bytes = client
+                .feeder_gateway_request()
+                .with_method("")
+                .with_retry(false)
+                .get_as_bytes()
+                .await?;
+
+            mock.assert_hits(2);
+
+            Ok(())
+        }
+
+        #[tokio::test]
+        async fn post_with_json() -> anyhow::Result<()> {
+            let server = MockServer::start_async().await;
+            let (mock, client) = setup_with_headers(&server).await;
+
+            let _: serde_json::Value = client
+                .gateway_request()
+                .with_method("")
+                .with_retry(false)
+                .post_with_json(&json!({}), None)
+                .await?;
+
+            mock.assert_hits(1);
+
+            Ok(())
+        }
+    }
 }
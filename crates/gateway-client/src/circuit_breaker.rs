@@ -0,0 +1,203 @@
+//! A circuit breaker which backs off from a consistently failing gateway.
+//!
+//! After [FAILURE_THRESHOLD] consecutive failed requests the circuit opens and all
+//! requests wait until it closes again, instead of retrying in a tight loop. Once the
+//! wait is over the next request acts as a probe: if it fails as well the circuit opens
+//! again for twice as long (up to [MAX_OPEN_DURATION]), if it succeeds the circuit is
+//! closed.
+//!
+//! The feeder gateway and the gateway have separate circuit breakers, so that a failing gateway
+//! does not hold back syncing and a failing feeder gateway does not hold back submissions.
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use starknet_gateway_types::error::SequencerError;
+use tokio::time::Instant;
+
+/// The number of consecutive failures after which the circuit opens.
+const FAILURE_THRESHOLD: u32 = 10;
+const MIN_OPEN_DURATION: Duration = Duration::from_secs(5);
+const MAX_OPEN_DURATION: Duration = Duration::from_secs(120);
+
+const METRIC_OPEN: &str = "gateway_circuit_breaker_open";
+
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    /// The gateway guarded by this breaker, used to label logs and metrics.
+    gateway: &'static str,
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+    open_duration: Option<Duration>,
+}
+
+impl CircuitBreaker {
+    pub fn new(gateway: &'static str) -> Self {
+        Self {
+            gateway,
+            state: Default::default(),
+        }
+    }
+
+    /// Sends the request once the circuit allows it and records its outcome.
+    pub async fn call<T>(
+        &self,
+        request: impl Future<Output = Result<T, SequencerError>>,
+    ) -> Result<T, SequencerError> {
+        self.ready().await;
+        let result = request.await;
+        self.record(&result);
+        result
+    }
+
+    /// Waits until requests may be sent, i.e. until the circuit is no longer open.
+    async fn ready(&self) {
+        let open_until = self.state.lock().unwrap().open_until;
+
+        if let Some(open_until) = open_until {
+            tokio::time::sleep_until(open_until).await;
+        }
+    }
+
+    /// Returns `true` if requests are currently being held back.
+    #[cfg(test)]
+    fn is_open(&self) -> bool {
+        self.state
+            .lock()
+            .unwrap()
+            .open_until
+            .is_some_and(|open_until| open_until > Instant::now())
+    }
+
+    /// Records the outcome of a request.
+    fn record<T>(&self, result: &Result<T, SequencerError>) {
+        let mut state = self.state.lock().unwrap();
+
+        match result {
+            Err(e) if is_gateway_failure(e) => {
+                state.consecutive_failures = state.consecutive_failures.saturating_add(1);
+
+                let now = Instant::now();
+                let is_open = state.open_until.is_some_and(|until| until > now);
+
+                if state.consecutive_failures >= FAILURE_THRESHOLD && !is_open {
+                    let duration = match state.open_duration {
+                        Some(previous) => (previous * 2).min(MAX_OPEN_DURATION),
+                        None => MIN_OPEN_DURATION,
+                    };
+
+                    tracing::warn!(
+                        gateway=%self.gateway,
+                        failures=%state.consecutive_failures,
+                        "Gateway is failing consistently, backing off for {:?}",
+                        duration
+                    );
+
+                    state.open_until = Some(now + duration);
+                    state.open_duration = Some(duration);
+                    metrics::gauge!(METRIC_OPEN, 1.0, "gateway" => self.gateway);
+                }
+            }
+            _ => {
+                if state.open_duration.is_some() {
+                    tracing::info!(gateway=%self.gateway, "Gateway has recovered");
+                    metrics::gauge!(METRIC_OPEN, 0.0, "gateway" => self.gateway);
+                }
+
+                *state = State::default();
+            }
+        }
+    }
+}
+
+/// Whether the error indicates that the gateway itself is unavailable or overloaded, as
+/// opposed to rejecting a particular request.
+fn is_gateway_failure(e: &SequencerError) -> bool {
+    use reqwest::StatusCode;
+
+    match e {
+        SequencerError::ReqwestError(e) => match e.status() {
+            Some(status) => {
+                status == StatusCode::TOO_MANY_REQUESTS
+                    || status == StatusCode::BAD_GATEWAY
+                    || status == StatusCode::SERVICE_UNAVAILABLE
+                    || status == StatusCode::GATEWAY_TIMEOUT
+            }
+            None => !e.is_decode(),
+        },
+        SequencerError::StarknetError(_) | SequencerError::InvalidStarknetErrorVariant => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn failure() -> Result<(), SequencerError> {
+        // Nothing listens on this port, so this fails with a connection error.
+        let error = reqwest::get("http://127.0.0.1:1").await.unwrap_err();
+        Err(SequencerError::ReqwestError(error))
+    }
+
+    #[tokio::test]
+    async fn opens_after_consecutive_failures() {
+        let breaker = CircuitBreaker::new("feeder_gateway");
+        let failure = failure().await;
+
+        for _ in 1..FAILURE_THRESHOLD {
+            breaker.record(&failure);
+        }
+        assert!(!breaker.is_open());
+
+        breaker.record(&failure);
+        assert!(breaker.is_open());
+
+        breaker.record(&Ok(()));
+        assert!(!breaker.is_open());
+    }
+
+    #[tokio::test]
+    async fn backs_off_exponentially() {
+        let breaker = CircuitBreaker::new("feeder_gateway");
+        let failure = failure().await;
+
+        tokio::time::pause();
+
+        for _ in 0..FAILURE_THRESHOLD {
+            breaker.record(&failure);
+        }
+
+        let start = Instant::now();
+        breaker.ready().await;
+        assert_eq!(start.elapsed().as_secs(), MIN_OPEN_DURATION.as_secs());
+
+        // The probe fails and the circuit opens for twice as long.
+        breaker.record(&failure);
+        assert!(breaker.is_open());
+
+        let start = Instant::now();
+        breaker.ready().await;
+        assert_eq!(start.elapsed().as_secs(), 2 * MIN_OPEN_DURATION.as_secs());
+    }
+
+    #[tokio::test]
+    async fn ignores_starknet_errors() {
+        use starknet_gateway_types::error::{KnownStarknetErrorCode, StarknetError};
+
+        let breaker = CircuitBreaker::new("feeder_gateway");
+        let error = Err::<(), _>(SequencerError::StarknetError(StarknetError {
+            code: KnownStarknetErrorCode::BlockNotFound.into(),
+            message: String::new(),
+        }));
+
+        for _ in 0..FAILURE_THRESHOLD {
+            breaker.record(&error);
+        }
+        assert!(!breaker.is_open());
+    }
+}
@@ -4,9 +4,12 @@ use reqwest::Url;
 use starknet_gateway_types::reply::PendingBlock;
 use starknet_gateway_types::trace::{BlockTrace, TransactionTrace};
 use starknet_gateway_types::{error::SequencerError, reply, request};
-use std::{fmt::Debug, result::Result, time::Duration};
+use std::{fmt::Debug, result::Result, sync::Arc, time::Duration};
+
+use circuit_breaker::CircuitBreaker;
 
 mod builder;
+mod circuit_breaker;
 mod metrics;
 
 #[allow(unused_variables)]
@@ -207,6 +210,15 @@ pub struct Client {
     retry: bool,
     /// Api key added to each request as a value for 'X-Throttling-Bypass' header.
     api_key: Option<String>,
+    /// Additional headers added to each request.
+    headers: reqwest::header::HeaderMap,
+    /// Shared by all clones so that they back off together from a failing feeder gateway.
+    feeder_gateway_circuit_breaker: Arc<CircuitBreaker>,
+    /// Shared by all clones so that they back off together from a failing gateway.
+    gateway_circuit_breaker: Arc<CircuitBreaker>,
+    /// Whether fields of block and state update replies which are unknown to pathfinder are
+    /// logged.
+    log_unknown_fields: bool,
 }
 
 impl Client {
@@ -271,6 +283,10 @@ impl Client {
             feeder_gateway,
             retry: true,
             api_key: None,
+            headers: Default::default(),
+            feeder_gateway_circuit_breaker: Arc::new(CircuitBreaker::new("feeder_gateway")),
+            gateway_circuit_breaker: Arc::new(CircuitBreaker::new("gateway")),
+            log_unknown_fields: false,
         })
     }
 
@@ -280,6 +296,30 @@ impl Client {
         self
     }
 
+    /// Sets additional headers to be sent with each request, e.g. keys issued by
+    /// infrastructure providers to raise rate limits.
+    pub fn with_headers(mut self, headers: reqwest::header::HeaderMap) -> Self {
+        self.headers = headers;
+        self
+    }
+
+    /// Logs the fields of block and state update replies which are ignored because they are
+    /// unknown to pathfinder, e.g. because they were introduced by a newer gateway version.
+    pub fn with_unknown_field_logging(mut self, enabled: bool) -> Self {
+        self.log_unknown_fields = enabled;
+        self
+    }
+
+    /// Gives this client its own circuit breakers instead of sharing the ones of the client it
+    /// was cloned from, so that its failures do not hold back the requests of the other clients.
+    pub fn with_own_circuit_breakers(self) -> Self {
+        Self {
+            feeder_gateway_circuit_breaker: Arc::new(CircuitBreaker::new("feeder_gateway")),
+            gateway_circuit_breaker: Arc::new(CircuitBreaker::new("gateway")),
+            ..self
+        }
+    }
+
     /// Use this method to disable retry logic for all __non write__ requests when testing.
     pub fn disable_retry_for_tests(self) -> Self {
         Self {
@@ -289,7 +329,13 @@ impl Client {
     }
 
     fn gateway_request(&self) -> builder::Request<'_, builder::stage::Method> {
-        builder::Request::builder(&self.inner, self.gateway.clone(), self.api_key.clone())
+        builder::Request::builder(
+            &self.inner,
+            self.gateway.clone(),
+            self.api_key.clone(),
+            &self.headers,
+            &self.gateway_circuit_breaker,
+        )
     }
 
     fn feeder_gateway_request(&self) -> builder::Request<'_, builder::stage::Method> {
@@ -297,6 +343,8 @@ impl Client {
             &self.inner,
             self.feeder_gateway.clone(),
             self.api_key.clone(),
+            &self.headers,
+            &self.feeder_gateway_circuit_breaker,
         )
     }
 }
@@ -305,20 +353,22 @@ impl Client {
 impl GatewayApi for Client {
     #[tracing::instrument(skip(self))]
     async fn pending_block(&self) -> Result<(PendingBlock, StateUpdate), SequencerError> {
-        #[derive(Clone, Debug, serde::Deserialize)]
+        #[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
         struct Dto {
             pub block: PendingBlock,
             pub state_update: starknet_gateway_types::reply::StateUpdate,
         }
 
-        let result: Dto = self
+        let request = self
             .feeder_gateway_request()
             .get_state_update()
             .with_block(BlockId::Pending)
             .add_param("includeBlock", "true")
-            .with_retry(self.retry)
-            .get()
-            .await?;
+            .with_retry(self.retry);
+        let result: Dto = match self.log_unknown_fields {
+            true => request.get_logging_unknown_fields().await?,
+            false => request.get().await?,
+        };
 
         Ok((result.block, result.state_update.into()))
     }
@@ -401,20 +451,22 @@ impl GatewayApi for Client {
         &self,
         block: BlockNumber,
     ) -> Result<(reply::Block, StateUpdate), SequencerError> {
-        #[derive(serde::Deserialize)]
+        #[derive(serde::Deserialize, serde::Serialize)]
         struct Dto {
             block: reply::Block,
             state_update: reply::StateUpdate,
         }
 
-        let result: Dto = self
+        let request = self
             .feeder_gateway_request()
             .get_state_update()
             .with_block(block)
             .add_param("includeBlock", "true")
-            .with_retry(self.retry)
-            .get()
-            .await?;
+            .with_retry(self.retry);
+        let result: Dto = match self.log_unknown_fields {
+            true => request.get_logging_unknown_fields().await?,
+            false => request.get().await?,
+        };
         Ok((result.block, result.state_update.into()))
     }
 
@@ -700,6 +752,204 @@ pub mod test_utils {
         .disable_retry_for_tests();
         (Some(server_handle), client)
     }
+
+    /// Gateway responses captured by [record] and served by [replay].
+    ///
+    /// Responses are keyed by the requested url path and query, and are stored in the order in
+    /// which they were received.
+    #[derive(Default, serde::Serialize, serde::Deserialize)]
+    struct Cassette(std::collections::BTreeMap<String, Vec<RecordedResponse>>);
+
+    #[derive(Clone, serde::Serialize, serde::Deserialize)]
+    struct RecordedResponse {
+        status: u16,
+        body: String,
+    }
+
+    impl Cassette {
+        fn load(path: &std::path::Path) -> anyhow::Result<Self> {
+            use anyhow::Context;
+
+            let file = std::fs::File::open(path)
+                .with_context(|| format!("Opening cassette {}", path.display()))?;
+            serde_json::from_reader(std::io::BufReader::new(file)).context("Parsing cassette")
+        }
+
+        fn save(&self, path: &std::path::Path) -> anyhow::Result<()> {
+            use anyhow::Context;
+
+            let json = serde_json::to_vec_pretty(self).context("Serializing cassette")?;
+            std::fs::write(path, json)
+                .with_context(|| format!("Writing cassette {}", path.display()))
+        }
+    }
+
+    /// # Usage
+    ///
+    /// Use to initialize a [Client] test case against recorded gateway responses.
+    ///
+    /// 1. if `SEQUENCER_TESTS_RECORD` environment variable is set:
+    ///    - [records](record) the responses of the gateway at `upstream` into `cassette`
+    ///
+    /// 2. otherwise:
+    ///    - [replays](replay) the responses previously recorded into `cassette`
+    ///
+    pub fn setup_with_cassette(
+        upstream: reqwest::Url,
+        cassette: impl Into<std::path::PathBuf>,
+    ) -> (tokio::task::JoinHandle<()>, Client) {
+        let cassette = cassette.into();
+        if std::env::var_os("SEQUENCER_TESTS_RECORD").is_some() {
+            record(upstream, cassette)
+        } else {
+            replay(&cassette)
+        }
+    }
+
+    /// Creates a [Client] which connects to a local proxy of the gateway at `upstream`.
+    ///
+    /// Every response is forwarded to the client as is, and written to the `cassette` file so
+    /// that it can later be served by [replay]. Any existing cassette is overwritten.
+    ///
+    /// Only `GET` requests are supported.
+    pub fn record(
+        upstream: reqwest::Url,
+        cassette: std::path::PathBuf,
+    ) -> (tokio::task::JoinHandle<()>, Client) {
+        use std::sync::{Arc, Mutex};
+        use warp::Filter;
+
+        let recorded = Arc::new(Mutex::new(Cassette::default()));
+        let upstream_client = reqwest::Client::builder()
+            .timeout(GATEWAY_TIMEOUT)
+            .user_agent(pathfinder_common::consts::USER_AGENT)
+            .build()
+            .unwrap();
+
+        let opt_query_raw = warp::query::raw()
+            .map(Some)
+            .or_else(|_| async { Ok::<(Option<String>,), std::convert::Infallible>((None,)) });
+        let path = warp::get()
+            .and(warp::path::full())
+            .and(opt_query_raw)
+            .map(
+                |full_path: warp::path::FullPath, raw_query: Option<String>| match raw_query {
+                    Some(some_raw_query) => {
+                        format!("{}?{}", full_path.as_str(), some_raw_query.as_str())
+                    }
+                    None => full_path.as_str().to_owned(),
+                },
+            )
+            .and_then(move |actual_full_path_and_query: String| {
+                let upstream = upstream.clone();
+                let upstream_client = upstream_client.clone();
+                let recorded = recorded.clone();
+                let cassette = cassette.clone();
+                async move {
+                    let url = upstream
+                        .join(&actual_full_path_and_query)
+                        .expect("Valid url path and query");
+                    let response = upstream_client
+                        .get(url)
+                        .send()
+                        .await
+                        .expect("Upstream gateway request");
+                    let status = response.status().as_u16();
+                    let body = response.text().await.expect("Upstream gateway response");
+
+                    let mut recorded = recorded.lock().unwrap();
+                    recorded
+                        .0
+                        .entry(actual_full_path_and_query)
+                        .or_default()
+                        .push(RecordedResponse {
+                            status,
+                            body: body.clone(),
+                        });
+                    recorded.save(&cassette).expect("Saving cassette");
+
+                    Ok::<_, warp::Rejection>(
+                        http::response::Builder::new().status(status).body(body),
+                    )
+                }
+            });
+
+        let (addr, serve_fut) = warp::serve(path).bind_ephemeral(([127, 0, 0, 1], 0));
+        let server_handle = tokio::spawn(serve_fut);
+        let client = Client::with_base_url(
+            reqwest::Url::parse(&format!("http://{addr}")).unwrap(),
+            GATEWAY_TIMEOUT,
+        )
+        .unwrap()
+        .disable_retry_for_tests();
+        (server_handle, client)
+    }
+
+    /// Creates a [Client] which connects to a local mock server serving the responses
+    /// captured by [record].
+    ///
+    /// Responses for a particular path & query are served in the order in which they were
+    /// recorded, with the last one being repeated once the others are exhausted.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cassette cannot be read, or if the client queries a path & query for
+    /// which no response was recorded.
+    pub fn replay(cassette: &std::path::Path) -> (tokio::task::JoinHandle<()>, Client) {
+        use std::collections::VecDeque;
+        use std::sync::{Arc, Mutex};
+        use warp::Filter;
+
+        let cassette = Cassette::load(cassette).unwrap();
+        let responses = cassette
+            .0
+            .into_iter()
+            .map(|(path, responses)| (path, responses.into_iter().collect::<VecDeque<_>>()))
+            .collect::<std::collections::HashMap<_, _>>();
+        let responses = Arc::new(Mutex::new(responses));
+
+        let opt_query_raw = warp::query::raw()
+            .map(Some)
+            .or_else(|_| async { Ok::<(Option<String>,), std::convert::Infallible>((None,)) });
+        let path = warp::any().and(warp::path::full()).and(opt_query_raw).map(
+            move |full_path: warp::path::FullPath, raw_query: Option<String>| {
+                let actual_full_path_and_query = match raw_query {
+                    Some(some_raw_query) => {
+                        format!("{}?{}", full_path.as_str(), some_raw_query.as_str())
+                    }
+                    None => full_path.as_str().to_owned(),
+                };
+
+                let mut responses = responses.lock().unwrap();
+                let Some(recorded) = responses.get_mut(&actual_full_path_and_query) else {
+                    panic!(
+                        "No response recorded for url path and query {actual_full_path_and_query}"
+                    );
+                };
+
+                let response = if recorded.len() > 1 {
+                    recorded.pop_front()
+                } else {
+                    recorded.front().cloned()
+                }
+                .expect("At least one recorded response");
+
+                http::response::Builder::new()
+                    .status(response.status)
+                    .body(response.body)
+            },
+        );
+
+        let (addr, serve_fut) = warp::serve(path).bind_ephemeral(([127, 0, 0, 1], 0));
+        let server_handle = tokio::spawn(serve_fut);
+        let client = Client::with_base_url(
+            reqwest::Url::parse(&format!("http://{addr}")).unwrap(),
+            GATEWAY_TIMEOUT,
+        )
+        .unwrap()
+        .disable_retry_for_tests();
+        (server_handle, client)
+    }
 }
 
 #[cfg(test)]
@@ -1407,4 +1657,45 @@ mod tests {
                 .unwrap();
         }
     }
+
+    mod cassette {
+        use super::*;
+        use httpmock::prelude::*;
+
+        const REPLY: &str = r#"{
+            "block_hash": "0x1234",
+            "block_number": 9703
+        }"#;
+
+        #[test_log::test(tokio::test)]
+        async fn record_and_replay() {
+            let upstream = MockServer::start_async().await;
+            let mock = upstream.mock(|when, then| {
+                when.method(GET)
+                    .path("/feeder_gateway/get_block")
+                    .query_param("blockNumber", "9703")
+                    .query_param("headerOnly", "true");
+                then.status(200).body(REPLY);
+            });
+
+            let dir = tempfile::TempDir::new().unwrap();
+            let cassette = dir.path().join("cassette.json");
+            let block = BlockId::Number(BlockNumber::new_or_panic(9703));
+
+            let (_jh, client) = record(upstream.base_url().parse().unwrap(), cassette.clone());
+            let recorded = client.block_header(block).await.unwrap();
+            mock.assert_hits(1);
+
+            // Replaying must not hit the upstream gateway.
+            let (_jh, client) = replay(&cassette);
+            let replayed = client.block_header(block).await.unwrap();
+            mock.assert_hits(1);
+
+            assert_eq!(recorded, replayed);
+            assert_eq!(
+                recorded,
+                (BlockNumber::new_or_panic(9703), block_hash!("0x1234"))
+            );
+        }
+    }
 }
@@ -9,6 +9,7 @@ use pathfinder_common::BlockId;
 const METRIC_REQUESTS: &str = "gateway_requests_total";
 const METRIC_FAILED_REQUESTS: &str = "gateway_requests_failed_total";
 const METRICS: [&str; 2] = [METRIC_REQUESTS, METRIC_FAILED_REQUESTS];
+const METRIC_REQUEST_DURATION: &str = "gateway_request_duration_seconds";
 const TAG_LATEST: &str = "latest";
 const TAG_PENDING: &str = "pending";
 const TAGS: &[&str] = &[TAG_LATEST, TAG_PENDING];
@@ -143,7 +144,11 @@ pub async fn with_metrics<T>(
 
     increment(METRIC_REQUESTS, meta);
 
-    f.await.map_err(|e| {
+    let started = std::time::Instant::now();
+    let result = f.await;
+    metrics::histogram!(METRIC_REQUEST_DURATION, started.elapsed().as_secs_f64(), "method" => meta.method);
+
+    result.map_err(|e| {
         increment(METRIC_FAILED_REQUESTS, meta);
 
         match &e {
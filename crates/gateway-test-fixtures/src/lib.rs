@@ -187,6 +187,18 @@ pub mod v0_13_1 {
     }
 }
 
+/// Replies as a newer gateway version might serve them, with fields and status values which are
+/// unknown to pathfinder.
+pub mod future {
+    pub mod state_update_with_block {
+        /// [super::super::v0_13_1::state_update_with_block::SEPOLIA_INTEGRATION_NUMBER_9703] with
+        /// new fields and a new block status.
+        pub const SEPOLIA_INTEGRATION_NUMBER_9703_UNKNOWN_FIELDS: &str = str_fixture!(
+            "future/state_update_with_block/sepolia_integration_9703_unknown_fields.json"
+        );
+    }
+}
+
 pub mod add_transaction {
     pub const INVOKE_CONTRACT_WITH_SIGNATURE: &str =
         str_fixture!("add-transaction/invoke-contract-with-signature.json");
@@ -2,7 +2,7 @@ use crate::request::contract::EntryPointType;
 use anyhow::{Context, Error, Result};
 use pathfinder_common::{felt_bytes, ClassHash};
 use pathfinder_crypto::{
-    hash::{HashChain, PoseidonHasher},
+    hash::{starknet_keccak, truncated_keccak, HashChain, PoseidonHasher},
     Felt,
 };
 use serde::Serialize;
@@ -411,11 +411,7 @@ fn compute_sierra_class_hash(
         })
         .for_each(|x| hash.write(x.finish()));
 
-    let abi_truncated_keccak = {
-        let mut keccak = sha3::Keccak256::default();
-        keccak.update(contract_definition.abi.as_bytes());
-        truncated_keccak(<[u8; 32]>::from(keccak.finalize()))
-    };
+    let abi_truncated_keccak = starknet_keccak(contract_definition.abi.as_bytes());
     hash.write(abi_truncated_keccak.into());
 
     let program_hash = {
@@ -433,15 +429,6 @@ fn compute_sierra_class_hash(
     Ok(ClassHash(hash.finish().into()))
 }
 
-/// See:
-/// <https://github.com/starkware-libs/cairo-lang/blob/64a7f6aed9757d3d8d6c28bd972df73272b0cb0a/src/starkware/starknet/public/abi.py#L21-L26>
-pub(crate) fn truncated_keccak(mut plain: [u8; 32]) -> Felt {
-    // python code masks with (2**250 - 1) which starts 0x03 and is followed by 31 0xff in be
-    // truncation is needed not to overflow the field element.
-    plain[0] &= 0x03;
-    Felt::from_be_bytes(plain).expect("cannot overflow: smaller than modulus")
-}
-
 /// `std::io::Write` adapter for Keccak256; we don't need the serialized version in
 /// compute_class_hash, but we need the truncated_keccak hash.
 ///
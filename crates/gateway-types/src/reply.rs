@@ -1,4 +1,8 @@
 //! Structures used for deserializing replies from Starkware's sequencer REST API.
+//!
+//! Unknown fields are ignored so that replies from a newer gateway version can still be parsed,
+//! see [unknown_fields](crate::unknown_fields) for reporting them. Transactions are the exception
+//! as their fields are used to tell apart their versions.
 use pathfinder_common::{
     BlockCommitmentSignatureElem, BlockHash, BlockNumber, BlockTimestamp, ContractAddress,
     EthereumAddress, EventCommitment, GasPrice, SequencerAddress, StarknetVersion, StateCommitment,
@@ -15,7 +19,6 @@ pub use transaction::DataAvailabilityMode;
 /// Used to deserialize replies to Starknet block requests.
 #[serde_as]
 #[derive(Clone, Debug, Deserialize, PartialEq, Eq, serde::Serialize)]
-#[serde(deny_unknown_fields)]
 pub struct Block {
     pub block_hash: BlockHash,
     pub block_number: BlockNumber,
@@ -89,8 +92,7 @@ impl Block {
 }
 
 #[serde_as]
-#[derive(Clone, Default, Debug, Deserialize, PartialEq, Eq)]
-#[cfg_attr(test, derive(serde::Serialize))]
+#[derive(Clone, Default, Debug, Deserialize, PartialEq, Eq, serde::Serialize)]
 pub struct PendingBlock {
     /// Excluded in blocks prior to Starknet 0.9.
     ///
@@ -178,7 +180,6 @@ impl From<pathfinder_common::L1DataAvailabilityMode> for L1DataAvailabilityMode
 
 #[serde_as]
 #[derive(Copy, Clone, Debug, Deserialize, PartialEq, Eq, serde::Serialize)]
-#[serde(deny_unknown_fields)]
 pub struct GasPrices {
     #[serde_as(as = "GasPriceAsHexStr")]
     pub price_in_wei: GasPrice,
@@ -188,7 +189,6 @@ pub struct GasPrices {
 
 /// Block and transaction status values.
 #[derive(Copy, Clone, Default, Debug, Deserialize, PartialEq, Eq, serde::Serialize)]
-#[serde(deny_unknown_fields)]
 pub enum Status {
     #[serde(rename = "NOT_RECEIVED")]
     NotReceived,
@@ -207,6 +207,9 @@ pub enum Status {
     Reverted,
     #[serde(rename = "ABORTED")]
     Aborted,
+    /// A status introduced by a newer gateway version.
+    #[serde(rename = "UNKNOWN", other)]
+    Unknown,
 }
 
 impl std::fmt::Display for Status {
@@ -220,6 +223,7 @@ impl std::fmt::Display for Status {
             Status::AcceptedOnL2 => write!(f, "ACCEPTED_ON_L2"),
             Status::Reverted => write!(f, "REVERTED"),
             Status::Aborted => write!(f, "ABORTED"),
+            Status::Unknown => write!(f, "UNKNOWN"),
         }
     }
 }
@@ -233,7 +237,6 @@ pub mod call {
     /// Describes problems encountered during some of call failures .
     #[serde_as]
     #[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
-    #[serde(deny_unknown_fields)]
     pub struct Problems {
         #[serde_as(as = "HashMap<_, _>")]
         pub calldata: HashMap<u64, Vec<String>>,
@@ -333,7 +336,6 @@ pub(crate) mod transaction {
 
     /// Represents execution resources for L2 transaction.
     #[derive(Copy, Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
-    #[serde(deny_unknown_fields)]
     pub struct ExecutionResources {
         pub builtin_instance_counter: BuiltinCounters,
         pub n_steps: u64,
@@ -364,7 +366,6 @@ pub(crate) mod transaction {
     }
 
     #[derive(Copy, Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
-    #[serde(deny_unknown_fields)]
     pub struct ExecutionDataAvailability {
         pub l1_gas: u128,
         pub l1_data_gas: u128,
@@ -494,7 +495,6 @@ pub(crate) mod transaction {
     /// Represents deserialized L1 to L2 message.
     #[serde_as]
     #[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
-    #[serde(deny_unknown_fields)]
     pub struct L1ToL2Message {
         #[serde_as(as = "EthereumAddressAsHexStr")]
         pub from_address: EthereumAddress,
@@ -522,7 +522,6 @@ pub(crate) mod transaction {
     /// Represents deserialized L2 to L1 message.
     #[serde_as]
     #[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq, Dummy)]
-    #[serde(deny_unknown_fields)]
     pub struct L2ToL1Message {
         pub from_address: ContractAddress,
         #[serde_as(as = "Vec<L2ToL1MessagePayloadElemAsDecimalStr>")]
@@ -573,7 +572,6 @@ pub(crate) mod transaction {
 
     /// Represents deserialized L2 transaction receipt data.
     #[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
-    #[serde(deny_unknown_fields)]
     pub struct Receipt {
         #[serde(default)]
         pub actual_fee: Option<Fee>,
@@ -2021,7 +2019,6 @@ pub(crate) mod transaction {
 
     /// Describes L2 transaction failure details.
     #[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
-    #[serde(deny_unknown_fields)]
     pub struct Failure {
         pub code: String,
         pub error_message: String,
@@ -2030,7 +2027,6 @@ pub(crate) mod transaction {
 
 /// Used to deserialize replies to StarkNet state update requests.
 #[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
-#[serde(deny_unknown_fields)]
 pub struct StateUpdate {
     /// Gets default value for pending state updates.
     #[serde(default)]
@@ -2121,7 +2117,6 @@ pub mod state_update {
     /// L2 state diff.
     #[serde_as]
     #[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq, Default)]
-    #[serde(deny_unknown_fields)]
     pub struct StateDiff {
         #[serde_as(as = "HashMap<_, Vec<_>>")]
         pub storage_diffs: HashMap<ContractAddress, Vec<StorageDiff>>,
@@ -2134,7 +2129,6 @@ pub mod state_update {
 
     /// L2 storage diff.
     #[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord)]
-    #[serde(deny_unknown_fields)]
     pub struct StorageDiff {
         pub key: StorageAddress,
         pub value: StorageValue,
@@ -2142,7 +2136,6 @@ pub mod state_update {
 
     /// L2 contract data within state diff.
     #[derive(Copy, Clone, Debug, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord)]
-    #[serde(deny_unknown_fields)]
     pub struct DeployedContract {
         pub address: ContractAddress,
         /// `class_hash` is the field name from cairo 0.9.0 onwards
@@ -2153,7 +2146,6 @@ pub mod state_update {
 
     /// Describes a newly declared class. Maps Sierra class hash to a Casm hash.
     #[derive(Copy, Clone, Debug, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord)]
-    #[serde(deny_unknown_fields)]
     pub struct DeclaredSierraClass {
         pub class_hash: SierraHash,
         pub compiled_class_hash: CasmHash,
@@ -2161,7 +2153,6 @@ pub mod state_update {
 
     /// Describes a newly replaced class. Maps contract address to a new class.
     #[derive(Copy, Clone, Debug, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord)]
-    #[serde(deny_unknown_fields)]
     pub struct ReplacedClass {
         pub address: ContractAddress,
         pub class_hash: ClassHash,
@@ -2214,7 +2205,6 @@ pub mod add_transaction {
 
     /// API response for an INVOKE_FUNCTION transaction
     #[derive(Clone, Debug, serde::Deserialize, PartialEq, Eq)]
-    #[serde(deny_unknown_fields)]
     pub struct InvokeResponse {
         pub code: String, // TRANSACTION_RECEIVED
         pub transaction_hash: TransactionHash,
@@ -2222,7 +2212,6 @@ pub mod add_transaction {
 
     /// API response for a DECLARE transaction
     #[derive(Clone, Debug, serde::Deserialize, PartialEq, Eq)]
-    #[serde(deny_unknown_fields)]
     pub struct DeclareResponse {
         pub code: String, // TRANSACTION_RECEIVED
         pub transaction_hash: TransactionHash,
@@ -2231,7 +2220,6 @@ pub mod add_transaction {
 
     /// API response for a DEPLOY transaction
     #[derive(Clone, Debug, serde::Deserialize, PartialEq, Eq)]
-    #[serde(deny_unknown_fields)]
     pub struct DeployResponse {
         pub code: String, // TRANSACTION_RECEIVED
         pub transaction_hash: TransactionHash,
@@ -2385,6 +2373,58 @@ mod tests {
         }
     }
 
+    /// Replies from a newer gateway version must still be parsed, ignoring the fields and
+    /// status values which pathfinder does not know about.
+    mod forward_compatibility {
+        use super::super::{Block, StateUpdate, Status};
+        use starknet_gateway_test_fixtures::*;
+
+        #[derive(serde::Deserialize, serde::Serialize)]
+        struct StateUpdateWithBlock {
+            block: Block,
+            state_update: StateUpdate,
+        }
+
+        #[test]
+        fn unknown_fields_and_status() {
+            let expected = serde_json::from_str::<StateUpdateWithBlock>(
+                v0_13_1::state_update_with_block::SEPOLIA_INTEGRATION_NUMBER_9703,
+            )
+            .unwrap();
+
+            let mut parsed = serde_json::from_str::<StateUpdateWithBlock>(
+                future::state_update_with_block::SEPOLIA_INTEGRATION_NUMBER_9703_UNKNOWN_FIELDS,
+            )
+            .unwrap();
+
+            assert_eq!(parsed.block.status, Status::Unknown);
+            parsed.block.status = expected.block.status;
+            assert_eq!(parsed.block, expected.block);
+            assert_eq!(parsed.state_update, expected.state_update);
+        }
+
+        #[test]
+        fn unknown_fields_are_found() {
+            let json =
+                future::state_update_with_block::SEPOLIA_INTEGRATION_NUMBER_9703_UNKNOWN_FIELDS;
+            let raw = serde_json::from_str::<serde_json::Value>(json).unwrap();
+            let parsed = serde_json::from_str::<StateUpdateWithBlock>(json).unwrap();
+            let known = serde_json::to_value(parsed).unwrap();
+
+            let unknown = crate::unknown_fields::unknown_fields(&raw, &known);
+
+            assert_eq!(
+                unknown,
+                vec![
+                    "block.l1_gas_price.price_in_new_unit",
+                    "block.new_block_field",
+                    "block.transaction_receipts[0].new_receipt_field",
+                    "state_update.state_diff.new_state_diff_field",
+                ]
+            );
+        }
+    }
+
     #[test]
     fn from_state_update() {
         use pathfinder_common::macro_prelude::*;
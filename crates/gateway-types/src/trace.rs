@@ -5,7 +5,6 @@ use serde::Deserialize;
 use crate::reply::transaction::ExecutionResources;
 
 #[derive(Debug, Deserialize)]
-#[serde(deny_unknown_fields)]
 pub struct TransactionTrace {
     pub revert_error: Option<String>,
     pub validate_invocation: Option<FunctionInvocation>,
@@ -18,7 +17,6 @@ pub struct TransactionTrace {
 }
 
 #[derive(Debug, Deserialize)]
-#[serde(deny_unknown_fields)]
 pub struct BlockTrace {
     pub traces: Vec<TransactionTrace>,
 }
@@ -32,7 +30,6 @@ pub enum CallType {
 }
 
 #[derive(Debug, Deserialize, Eq, PartialEq)]
-#[serde(deny_unknown_fields)]
 pub struct Event {
     pub order: i64,
     pub data: Vec<Felt>,
@@ -41,7 +38,6 @@ pub struct Event {
 
 #[serde_with::skip_serializing_none]
 #[derive(Debug, Deserialize)]
-#[serde(deny_unknown_fields)]
 pub struct FunctionInvocation {
     pub calldata: Vec<Felt>,
     pub contract_address: ContractAddress,
@@ -77,7 +73,6 @@ pub enum EntryPointType {
 }
 
 #[derive(Debug, Deserialize, Eq, PartialEq)]
-#[serde(deny_unknown_fields)]
 pub struct MsgToL1 {
     pub order: usize,
     pub payload: Vec<Felt>,
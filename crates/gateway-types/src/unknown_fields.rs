@@ -0,0 +1,86 @@
+//! Detection of reply fields which pathfinder does not know about.
+//!
+//! Most reply types ignore unknown fields so that a new gateway field does not break sync. This
+//! module finds such fields by comparing the raw reply against the re-serialized parsed reply, so
+//! that they can be reported.
+use serde_json::Value;
+
+/// Names of fields which are parsed through a serde alias, and are therefore serialized under a
+/// different name.
+const ALIASED_FIELDS: [&str; 4] = [
+    "contract_address",
+    "contract_hash",
+    "gas_price",
+    "state_root",
+];
+
+/// Returns the paths of the fields which are in `raw` but not in `known`.
+///
+/// `known` is expected to be the re-serialized form of what was parsed from `raw`. Fields with a
+/// `null` value are skipped since optional fields are usually not serialized when absent, as are
+/// fields which are known under an alias.
+pub fn unknown_fields(raw: &Value, known: &Value) -> Vec<String> {
+    let mut paths = Vec::new();
+    collect(raw, known, String::new(), &mut paths);
+    paths
+}
+
+fn collect(raw: &Value, known: &Value, path: String, paths: &mut Vec<String>) {
+    match (raw, known) {
+        (Value::Object(raw), Value::Object(known)) => {
+            for (key, raw) in raw {
+                let path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{path}.{key}")
+                };
+
+                match known.get(key) {
+                    Some(known) => collect(raw, known, path, paths),
+                    None if raw.is_null() || ALIASED_FIELDS.contains(&key.as_str()) => {}
+                    None => paths.push(path),
+                }
+            }
+        }
+        (Value::Array(raw), Value::Array(known)) => {
+            for (i, (raw, known)) in raw.iter().zip(known).enumerate() {
+                collect(raw, known, format!("{path}[{i}]"), paths);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn nested_objects_and_arrays() {
+        let raw = json!({
+            "a": 1,
+            "new": 2,
+            "b": { "c": 3, "new": 4 },
+            "d": [{ "e": 5 }, { "e": 6, "new": 7 }],
+        });
+        let known = json!({
+            "a": 1,
+            "b": { "c": 3 },
+            "d": [{ "e": 5 }, { "e": 6 }],
+        });
+
+        assert_eq!(
+            unknown_fields(&raw, &known),
+            vec!["b.new", "d[1].new", "new"]
+        );
+    }
+
+    #[test]
+    fn null_and_aliased_fields_are_skipped() {
+        let raw = json!({ "a": 1, "optional": null, "state_root": "0x1" });
+        let known = json!({ "a": 1, "state_commitment": "0x1" });
+
+        assert!(unknown_fields(&raw, &known).is_empty());
+    }
+}
@@ -0,0 +1,81 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use pathfinder_common::{ContractAddress, ContractStateHash, StorageAddress, StorageValue};
+use pathfinder_crypto::Felt;
+use pathfinder_merkle_tree::{ContractsStorageTree, StorageCommitmentTree};
+use pathfinder_storage::Storage;
+use rand::SeedableRng;
+
+pub fn criterion_benchmark(c: &mut Criterion) {
+    // Bench trie commits
+    bench_commit(c);
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
+
+/// Generates `n` random 251-bit felts from a fixed seed, so that results are comparable
+/// across runs.
+fn felts(n: usize, seed: u64) -> Vec<Felt> {
+    let rng = &mut rand::rngs::StdRng::seed_from_u64(seed);
+
+    let mut out = Vec::with_capacity(n);
+    while out.len() < n {
+        let felt = Felt::random(rng);
+        if !felt.has_more_than_251_bits() {
+            out.push(felt);
+        }
+    }
+
+    out
+}
+
+/// Sets `n` leaves of an empty trie and calculates the new root and nodes.
+pub fn bench_commit(c: &mut Criterion) {
+    let storage = Storage::in_memory().unwrap();
+    let mut connection = storage.connection().unwrap();
+    let tx = connection.transaction().unwrap();
+
+    let mut grp_commit = c.benchmark_group("trie_commit");
+    for n in [10, 100, 1000] {
+        grp_commit.throughput(Throughput::Elements(n as u64));
+
+        let updates = felts(n, 0)
+            .into_iter()
+            .zip(felts(n, 1))
+            .map(|(key, value)| (ContractAddress::new_or_panic(key), ContractStateHash(value)))
+            .collect::<Vec<_>>();
+        grp_commit.bench_with_input(
+            BenchmarkId::new("storage_commitment", n),
+            &updates,
+            |b, updates| {
+                b.iter(|| {
+                    let mut tree = StorageCommitmentTree::empty(&tx);
+                    for (contract, state_hash) in updates {
+                        tree.set(*contract, *state_hash).unwrap();
+                    }
+                    black_box(tree.commit().unwrap())
+                })
+            },
+        );
+
+        let updates = felts(n, 2)
+            .into_iter()
+            .zip(felts(n, 3))
+            .map(|(key, value)| (StorageAddress::new_or_panic(key), StorageValue(value)))
+            .collect::<Vec<_>>();
+        grp_commit.bench_with_input(
+            BenchmarkId::new("contract_storage", n),
+            &updates,
+            |b, updates| {
+                b.iter(|| {
+                    let mut tree = ContractsStorageTree::empty(&tx, ContractAddress::ONE);
+                    for (address, value) in updates {
+                        tree.set(*address, *value).unwrap();
+                    }
+                    black_box(tree.commit().unwrap())
+                })
+            },
+        );
+    }
+    grp_commit.finish();
+}
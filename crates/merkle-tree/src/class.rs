@@ -1,48 +1,51 @@
 use std::collections::HashMap;
 
 use anyhow::Context;
-use pathfinder_common::{
-    BlockNumber, ClassCommitment, ClassCommitmentLeafHash, ClassHash, SierraHash,
-};
+use pathfinder_common::{BlockNumber, ClassCommitment, ClassCommitmentLeafHash, SierraHash};
 use pathfinder_crypto::Felt;
-use pathfinder_storage::{Node, Transaction};
 
-use crate::tree::MerkleTree;
+use crate::storage::{AtBlock, TrieStorage};
+use crate::tree::{LeafDiff, MerkleTree};
 use pathfinder_common::hash::PoseidonHash;
+use pathfinder_common::trie::{Node, TrieNode};
 
 /// A [Patricia Merkle tree](MerkleTree) used to calculate commitments to Starknet's Sierra classes.
 ///
 /// It maps a class's [SierraHash] to its [ClassCommitmentLeafHash]
 ///
-/// Tree data is persisted by a sqlite table 'tree_class'.
-pub struct ClassCommitmentTree<'tx> {
+/// Tree data is persisted by a sqlite table 'tree_class', or by any other [TrieStorage].
+pub struct ClassCommitmentTree<S> {
     tree: MerkleTree<PoseidonHash, 251>,
-    storage: ClassStorage<'tx>,
+    storage: S,
+    block: Option<BlockNumber>,
 }
 
-impl<'tx> ClassCommitmentTree<'tx> {
-    pub fn empty(tx: &'tx Transaction<'tx>) -> Self {
-        let storage = ClassStorage { tx, block: None };
+impl<S: TrieStorage> ClassCommitmentTree<S> {
+    pub fn empty_with(storage: S) -> Self {
         let tree = MerkleTree::empty();
 
-        Self { tree, storage }
+        Self {
+            tree,
+            storage,
+            block: None,
+        }
     }
 
-    pub fn load(tx: &'tx Transaction<'tx>, block: BlockNumber) -> anyhow::Result<Self> {
-        let root = tx
-            .class_root_index(block)
+    pub fn load_with(storage: S, block: BlockNumber) -> anyhow::Result<Self> {
+        let root = storage
+            .root_index(block)
             .context("Querying class root index")?;
         let Some(root) = root else {
-            return Ok(Self::empty(tx));
+            return Ok(Self::empty_with(storage));
         };
 
-        let storage = ClassStorage {
-            tx,
-            block: Some(block),
-        };
         let tree = MerkleTree::new(root);
 
-        Ok(Self { tree, storage })
+        Ok(Self {
+            tree,
+            storage,
+            block: Some(block),
+        })
     }
 
     pub fn with_verify_hashes(mut self, verify_hashes: bool) -> Self {
@@ -57,59 +60,99 @@ impl<'tx> ClassCommitmentTree<'tx> {
     /// for details.
     pub fn set(&mut self, class: SierraHash, value: ClassCommitmentLeafHash) -> anyhow::Result<()> {
         let key = class.view_bits().to_owned();
-        self.tree.set(&self.storage, key, value.0)
+        self.tree.set(&self.at_block(), key, value.0)
     }
 
     /// Commits the changes and calculates the new node hashes. Returns the new commitment and
     /// any potentially newly created nodes.
     pub fn commit(self) -> anyhow::Result<(ClassCommitment, HashMap<Felt, Node>)> {
-        let update = self.tree.commit(&self.storage)?;
+        let update = self.tree.commit(&self.at_block())?;
 
         let commitment = ClassCommitment(update.root);
         Ok((commitment, update.nodes))
     }
-}
 
-struct ClassStorage<'tx> {
-    tx: &'tx Transaction<'tx>,
-    block: Option<BlockNumber>,
-}
+    /// Same as [ClassCommitmentTree::diff], using `storage`.
+    pub fn diff_with(
+        storage: &S,
+        from: BlockNumber,
+        to: BlockNumber,
+        limit: usize,
+    ) -> anyhow::Result<Vec<LeafDiff>> {
+        let root_a = storage
+            .root_index(from)
+            .context("Querying class root index")?;
+        let root_b = storage
+            .root_index(to)
+            .context("Querying class root index")?;
 
-impl crate::storage::Storage for ClassStorage<'_> {
-    fn get(&self, index: u64) -> anyhow::Result<Option<pathfinder_storage::StoredNode>> {
-        self.tx.class_trie_node(index)
-    }
+        let storage_a = AtBlock {
+            storage,
+            block: Some(from),
+        };
+        let storage_b = AtBlock {
+            storage,
+            block: Some(to),
+        };
 
-    fn hash(&self, index: u64) -> anyhow::Result<Option<Felt>> {
-        self.tx.class_trie_node_hash(index)
+        MerkleTree::<PoseidonHash, 251>::diff(root_a, &storage_a, root_b, &storage_b, limit)
     }
 
-    fn leaf(
-        &self,
-        path: &bitvec::slice::BitSlice<u8, bitvec::prelude::Msb0>,
-    ) -> anyhow::Result<Option<Felt>> {
-        assert!(path.len() == 251);
+    /// Same as [ClassCommitmentTree::get_proof], using `storage`.
+    pub fn get_proof_with(
+        storage: &S,
+        block: BlockNumber,
+        class: SierraHash,
+    ) -> anyhow::Result<Vec<TrieNode>> {
+        let root = storage
+            .root_index(block)
+            .context("Querying class root index")?;
 
-        let Some(block) = self.block else {
-            return Ok(None);
+        let Some(root) = root else {
+            return Ok(Vec::new());
+        };
+
+        let storage = AtBlock {
+            storage,
+            block: Some(block),
         };
 
-        let sierra = ClassHash(Felt::from_bits(path).context("Mapping path to sierra hash")?);
+        MerkleTree::<PoseidonHash, 251>::get_proof(root, &storage, class.view_bits())
+    }
+
+    /// Same as [ClassCommitmentTree::get_range], using `storage`.
+    pub fn get_range_with(
+        storage: &S,
+        block: BlockNumber,
+        start: SierraHash,
+        limit: usize,
+    ) -> anyhow::Result<Vec<(SierraHash, ClassCommitmentLeafHash)>> {
+        let root = storage
+            .root_index(block)
+            .context("Querying class root index")?;
+
+        let Some(root) = root else {
+            return Ok(Vec::new());
+        };
 
-        let casm = self
-            .tx
-            .casm_hash_at(block.into(), sierra)
-            .context("Querying CASM hash")?;
-        let Some(casm) = casm else {
-            return Ok(None);
+        let storage = AtBlock {
+            storage,
+            block: Some(block),
         };
 
-        let leaf = self
-            .tx
-            .class_commitment_leaf(block, &casm)
-            .context("Querying class leaf")?
-            .map(|x| x.0);
+        MerkleTree::<PoseidonHash, 251>::get_range(root, &storage, start.view_bits(), limit)?
+            .into_iter()
+            .map(|(key, value)| {
+                let key = Felt::from_bits(&key).context("Mapping path to sierra hash")?;
+                Ok((SierraHash(key), ClassCommitmentLeafHash(value)))
+            })
+            .collect()
+    }
 
-        Ok(leaf)
+    fn at_block(&self) -> AtBlock<'_, S> {
+        AtBlock {
+            storage: &self.storage,
+            block: self.block,
+        }
     }
 }
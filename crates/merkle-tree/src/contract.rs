@@ -5,18 +5,18 @@
 
 use crate::{
     merkle_node::InternalNode,
-    tree::{MerkleTree, Visit},
+    storage::{AtBlock, TrieStorage},
+    tree::{LeafDiff, MerkleTree, Visit},
 };
 use anyhow::Context;
 use bitvec::{prelude::Msb0, slice::BitSlice};
 use pathfinder_common::hash::PedersenHash;
-use pathfinder_common::trie::TrieNode;
+use pathfinder_common::trie::{Node, TrieNode};
 use pathfinder_common::{
     BlockNumber, ContractAddress, ContractRoot, ContractStateHash, StorageAddress,
     StorageCommitment, StorageValue,
 };
 use pathfinder_crypto::Felt;
-use pathfinder_storage::{Node, Transaction};
 use std::collections::HashMap;
 use std::ops::ControlFlow;
 
@@ -24,44 +24,41 @@ use std::ops::ControlFlow;
 ///
 /// It maps a contract's [storage addresses](StorageAddress) to their [values](StorageValue).
 ///
-/// Tree data is persisted by a sqlite table 'tree_contracts'.
-pub struct ContractsStorageTree<'tx> {
+/// Tree data is persisted by a sqlite table 'tree_contracts', or by any other [TrieStorage].
+pub struct ContractsStorageTree<S> {
     tree: MerkleTree<PedersenHash, 251>,
-    storage: ContractStorage<'tx>,
+    storage: S,
+    block: Option<BlockNumber>,
 }
 
-impl<'tx> ContractsStorageTree<'tx> {
-    pub fn empty(tx: &'tx Transaction<'tx>, contract: ContractAddress) -> Self {
-        let storage = ContractStorage {
-            tx,
-            block: None,
-            contract,
-        };
+impl<S: TrieStorage> ContractsStorageTree<S> {
+    /// Creates an empty tree. `storage` must be the storage of a single contract's trie.
+    pub fn empty_with(storage: S) -> Self {
         let tree = MerkleTree::empty();
 
-        Self { tree, storage }
+        Self {
+            tree,
+            storage,
+            block: None,
+        }
     }
 
-    pub fn load(
-        tx: &'tx Transaction<'tx>,
-        contract: ContractAddress,
-        block: BlockNumber,
-    ) -> anyhow::Result<Self> {
-        let root = tx
-            .contract_root_index(block, contract)
+    /// Loads the tree as of `block`. `storage` must be the storage of a single contract's trie.
+    pub fn load_with(storage: S, block: BlockNumber) -> anyhow::Result<Self> {
+        let root = storage
+            .root_index(block)
             .context("Querying contract root index")?;
         let Some(root) = root else {
-            return Ok(Self::empty(tx, contract));
+            return Ok(Self::empty_with(storage));
         };
 
-        let storage = ContractStorage {
-            tx,
-            block: Some(block),
-            contract,
-        };
         let tree = MerkleTree::new(root);
 
-        Ok(Self { tree, storage })
+        Ok(Self {
+            tree,
+            storage,
+            block: Some(block),
+        })
     }
 
     pub fn with_verify_hashes(mut self, verify_hashes: bool) -> Self {
@@ -69,39 +66,92 @@ impl<'tx> ContractsStorageTree<'tx> {
         self
     }
 
-    /// Generates a proof for `key`. See [`MerkleTree::get_proof`].
-    pub fn get_proof(
-        tx: &'tx Transaction<'tx>,
-        contract: ContractAddress,
+    /// Same as [ContractsStorageTree::get_proof], using `storage`.
+    pub fn get_proof_with(
+        storage: &S,
         block: BlockNumber,
         key: &BitSlice<u8, Msb0>,
     ) -> anyhow::Result<Vec<TrieNode>> {
-        let root = tx
-            .contract_root_index(block, contract)
+        let root = storage
+            .root_index(block)
             .context("Querying contract root index")?;
 
         let Some(root) = root else {
             return Ok(Vec::new());
         };
 
-        let storage = ContractStorage {
-            tx,
+        let storage = AtBlock {
+            storage,
             block: Some(block),
-            contract,
         };
 
         MerkleTree::<PedersenHash, 251>::get_proof(root, &storage, key)
     }
 
+    /// Same as [ContractsStorageTree::diff], using `storage`.
+    pub fn diff_with(
+        storage: &S,
+        from: BlockNumber,
+        to: BlockNumber,
+        limit: usize,
+    ) -> anyhow::Result<Vec<LeafDiff>> {
+        let root_a = storage
+            .root_index(from)
+            .context("Querying contract root index")?;
+        let root_b = storage
+            .root_index(to)
+            .context("Querying contract root index")?;
+
+        let storage_a = AtBlock {
+            storage,
+            block: Some(from),
+        };
+        let storage_b = AtBlock {
+            storage,
+            block: Some(to),
+        };
+
+        MerkleTree::<PedersenHash, 251>::diff(root_a, &storage_a, root_b, &storage_b, limit)
+    }
+
+    /// Same as [ContractsStorageTree::get_range], using `storage`.
+    pub fn get_range_with(
+        storage: &S,
+        block: BlockNumber,
+        start: StorageAddress,
+        limit: usize,
+    ) -> anyhow::Result<Vec<(StorageAddress, StorageValue)>> {
+        let root = storage
+            .root_index(block)
+            .context("Querying contract root index")?;
+
+        let Some(root) = root else {
+            return Ok(Vec::new());
+        };
+
+        let storage = AtBlock {
+            storage,
+            block: Some(block),
+        };
+
+        MerkleTree::<PedersenHash, 251>::get_range(root, &storage, start.view_bits(), limit)?
+            .into_iter()
+            .map(|(key, value)| {
+                let key = Felt::from_bits(&key).context("Mapping leaf path to storage address")?;
+                Ok((StorageAddress(key), StorageValue(value)))
+            })
+            .collect()
+    }
+
     pub fn set(&mut self, address: StorageAddress, value: StorageValue) -> anyhow::Result<()> {
         let key = address.view_bits().to_owned();
-        self.tree.set(&self.storage, key, value.0)
+        self.tree.set(&self.at_block(), key, value.0)
     }
 
     /// Commits the changes and calculates the new node hashes. Returns the new commitment and
     /// any potentially newly created nodes.
     pub fn commit(self) -> anyhow::Result<(ContractRoot, HashMap<Felt, Node>)> {
-        let update = self.tree.commit(&self.storage)?;
+        let update = self.tree.commit(&self.at_block())?;
         let commitment = ContractRoot(update.root);
         Ok((commitment, update.nodes))
     }
@@ -111,7 +161,14 @@ impl<'tx> ContractsStorageTree<'tx> {
         &mut self,
         f: &mut F,
     ) -> anyhow::Result<Option<B>> {
-        self.tree.dfs(&self.storage, f)
+        self.tree.dfs(&self.at_block(), f)
+    }
+
+    fn at_block(&self) -> AtBlock<'_, S> {
+        AtBlock {
+            storage: &self.storage,
+            block: self.block,
+        }
     }
 }
 
@@ -119,36 +176,39 @@ impl<'tx> ContractsStorageTree<'tx> {
 ///
 /// It maps each contract's [address](ContractAddress) to it's [state hash](ContractStateHash).
 ///
-/// Tree data is persisted by a sqlite table 'tree_global'.
-pub struct StorageCommitmentTree<'tx> {
+/// Tree data is persisted by a sqlite table 'tree_global', or by any other [TrieStorage].
+pub struct StorageCommitmentTree<S> {
     tree: MerkleTree<PedersenHash, 251>,
-    storage: StorageTrieStorage<'tx>,
+    storage: S,
+    block: Option<BlockNumber>,
 }
 
-impl<'tx> StorageCommitmentTree<'tx> {
-    pub fn empty(tx: &'tx Transaction<'tx>) -> Self {
-        let storage = StorageTrieStorage { tx, block: None };
+impl<S: TrieStorage> StorageCommitmentTree<S> {
+    pub fn empty_with(storage: S) -> Self {
         let tree = MerkleTree::empty();
 
-        Self { tree, storage }
+        Self {
+            tree,
+            storage,
+            block: None,
+        }
     }
 
-    pub fn load(tx: &'tx Transaction<'tx>, block: BlockNumber) -> anyhow::Result<Self> {
-        let root = tx
-            .storage_root_index(block)
+    pub fn load_with(storage: S, block: BlockNumber) -> anyhow::Result<Self> {
+        let root = storage
+            .root_index(block)
             .context("Querying storage root index")?;
         let Some(root) = root else {
-            return Ok(Self::empty(tx));
-        };
-
-        let storage = StorageTrieStorage {
-            tx,
-            block: Some(block),
+            return Ok(Self::empty_with(storage));
         };
 
         let tree = MerkleTree::new(root);
 
-        Ok(Self { tree, storage })
+        Ok(Self {
+            tree,
+            storage,
+            block: Some(block),
+        })
     }
 
     pub fn with_verify_hashes(mut self, verify_hashes: bool) -> Self {
@@ -162,109 +222,106 @@ impl<'tx> StorageCommitmentTree<'tx> {
         value: ContractStateHash,
     ) -> anyhow::Result<()> {
         let key = address.view_bits().to_owned();
-        self.tree.set(&self.storage, key, value.0)
+        self.tree.set(&self.at_block(), key, value.0)
     }
 
     /// Commits the changes and calculates the new node hashes. Returns the new commitment and
     /// any potentially newly created nodes.
     pub fn commit(self) -> anyhow::Result<(StorageCommitment, HashMap<Felt, Node>)> {
-        let update = self.tree.commit(&self.storage)?;
+        let update = self.tree.commit(&self.at_block())?;
         let commitment = StorageCommitment(update.root);
         Ok((commitment, update.nodes))
     }
 
-    /// Generates a proof for the given `key`. See [`MerkleTree::get_proof`].
-    pub fn get_proof(
-        tx: &'tx Transaction<'tx>,
+    /// Same as [StorageCommitmentTree::get_proof], using `storage`.
+    pub fn get_proof_with(
+        storage: &S,
         block: BlockNumber,
         address: &ContractAddress,
     ) -> anyhow::Result<Vec<TrieNode>> {
-        let root = tx
-            .storage_root_index(block)
+        let root = storage
+            .root_index(block)
             .context("Querying storage root index")?;
 
         let Some(root) = root else {
             return Ok(Vec::new());
         };
 
-        let storage = StorageTrieStorage {
-            tx,
+        let storage = AtBlock {
+            storage,
             block: Some(block),
         };
 
         MerkleTree::<PedersenHash, 251>::get_proof(root, &storage, address.view_bits())
     }
 
-    /// See [`MerkleTree::dfs`]
-    pub fn dfs<B, F: FnMut(&InternalNode, &BitSlice<u8, Msb0>) -> ControlFlow<B, Visit>>(
-        &mut self,
-        f: &mut F,
-    ) -> anyhow::Result<Option<B>> {
-        self.tree.dfs(&self.storage, f)
-    }
-}
-
-struct ContractStorage<'tx> {
-    tx: &'tx Transaction<'tx>,
-    block: Option<BlockNumber>,
-    contract: ContractAddress,
-}
+    /// Same as [StorageCommitmentTree::diff], using `storage`.
+    pub fn diff_with(
+        storage: &S,
+        from: BlockNumber,
+        to: BlockNumber,
+        limit: usize,
+    ) -> anyhow::Result<Vec<LeafDiff>> {
+        let root_a = storage
+            .root_index(from)
+            .context("Querying storage root index")?;
+        let root_b = storage
+            .root_index(to)
+            .context("Querying storage root index")?;
 
-impl crate::storage::Storage for ContractStorage<'_> {
-    fn get(&self, index: u64) -> anyhow::Result<Option<pathfinder_storage::StoredNode>> {
-        self.tx.contract_trie_node(index)
-    }
+        let storage_a = AtBlock {
+            storage,
+            block: Some(from),
+        };
+        let storage_b = AtBlock {
+            storage,
+            block: Some(to),
+        };
 
-    fn hash(&self, index: u64) -> anyhow::Result<Option<Felt>> {
-        self.tx.contract_trie_node_hash(index)
+        MerkleTree::<PedersenHash, 251>::diff(root_a, &storage_a, root_b, &storage_b, limit)
     }
 
-    fn leaf(&self, path: &BitSlice<u8, Msb0>) -> anyhow::Result<Option<Felt>> {
-        assert!(path.len() == 251);
+    /// Same as [StorageCommitmentTree::get_range], using `storage`.
+    pub fn get_range_with(
+        storage: &S,
+        block: BlockNumber,
+        start: ContractAddress,
+        limit: usize,
+    ) -> anyhow::Result<Vec<(ContractAddress, ContractStateHash)>> {
+        let root = storage
+            .root_index(block)
+            .context("Querying storage root index")?;
 
-        let Some(block) = self.block else {
-            return Ok(None);
+        let Some(root) = root else {
+            return Ok(Vec::new());
         };
 
-        let key =
-            StorageAddress(Felt::from_bits(path).context("Mapping leaf path to storage address")?);
-
-        let value = self
-            .tx
-            .storage_value(block.into(), self.contract, key)?
-            .map(|x| x.0);
-
-        Ok(value)
-    }
-}
-
-struct StorageTrieStorage<'tx> {
-    tx: &'tx Transaction<'tx>,
-    block: Option<BlockNumber>,
-}
+        let storage = AtBlock {
+            storage,
+            block: Some(block),
+        };
 
-impl crate::storage::Storage for StorageTrieStorage<'_> {
-    fn get(&self, index: u64) -> anyhow::Result<Option<pathfinder_storage::StoredNode>> {
-        self.tx.storage_trie_node(index)
+        MerkleTree::<PedersenHash, 251>::get_range(root, &storage, start.view_bits(), limit)?
+            .into_iter()
+            .map(|(key, value)| {
+                let key = Felt::from_bits(&key).context("Mapping leaf path to contract address")?;
+                Ok((ContractAddress(key), ContractStateHash(value)))
+            })
+            .collect()
     }
 
-    fn hash(&self, index: u64) -> anyhow::Result<Option<Felt>> {
-        self.tx.storage_trie_node_hash(index)
+    /// See [`MerkleTree::dfs`]
+    pub fn dfs<B, F: FnMut(&InternalNode, &BitSlice<u8, Msb0>) -> ControlFlow<B, Visit>>(
+        &mut self,
+        f: &mut F,
+    ) -> anyhow::Result<Option<B>> {
+        self.tree.dfs(&self.at_block(), f)
     }
 
-    fn leaf(&self, path: &BitSlice<u8, Msb0>) -> anyhow::Result<Option<Felt>> {
-        assert!(path.len() == 251);
-
-        let Some(block) = self.block else {
-            return Ok(None);
-        };
-
-        let contract = ContractAddress(
-            Felt::from_bits(path).context("Mapping leaf path to contract address")?,
-        );
-
-        let value = self.tx.contract_state_hash(block, contract)?.map(|x| x.0);
-
-        Ok(value)
+    fn at_block(&self) -> AtBlock<'_, S> {
+        AtBlock {
+            storage: &self.storage,
+            block: self.block,
+        }
     }
 }
@@ -1,14 +1,16 @@
-use std::collections::HashMap;
-
-use crate::ContractsStorageTree;
-use anyhow::Context;
-use pathfinder_common::{
-    BlockNumber, ClassHash, ContractAddress, ContractNonce, ContractRoot, ContractStateHash,
-    StorageAddress, StorageValue,
-};
+use pathfinder_common::{ClassHash, ContractNonce, ContractRoot, ContractStateHash};
 use pathfinder_crypto::{hash::pedersen_hash, Felt};
-use pathfinder_storage::{Node, Transaction};
+#[cfg(feature = "storage")]
+use {
+    crate::ContractsStorageTree,
+    anyhow::Context,
+    pathfinder_common::trie::Node,
+    pathfinder_common::{BlockNumber, ContractAddress, StorageAddress, StorageValue},
+    pathfinder_storage::Transaction,
+    std::collections::HashMap,
+};
 
+#[cfg(feature = "storage")]
 #[derive(Debug)]
 pub struct ContractStateUpdateResult {
     pub state_hash: ContractStateHash,
@@ -19,6 +21,7 @@ pub struct ContractStateUpdateResult {
     nodes: HashMap<Felt, Node>,
 }
 
+#[cfg(feature = "storage")]
 impl ContractStateUpdateResult {
     /// Inserts the results of a contract state update into the database.
     ///
@@ -48,6 +51,7 @@ impl ContractStateUpdateResult {
 }
 
 /// Updates a contract's state with and returns the resulting [ContractStateHash].
+#[cfg(feature = "storage")]
 pub fn update_contract_state(
     contract_address: ContractAddress,
     updates: &HashMap<StorageAddress, StorageValue>,
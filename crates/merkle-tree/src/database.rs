@@ -0,0 +1,354 @@
+//! Database backed [TrieStorage] implementations, and constructors for the trees which read
+//! from a database [Transaction].
+
+use std::collections::HashMap;
+
+use anyhow::Context;
+use bitvec::{prelude::Msb0, slice::BitSlice};
+use pathfinder_common::hash::{PedersenHash, PoseidonHash};
+use pathfinder_common::trie::{Node, StoredNode, TrieNode};
+use pathfinder_common::{
+    BlockNumber, ClassCommitment, ClassCommitmentLeafHash, ClassHash, ContractAddress,
+    ContractRoot, ContractStateHash, SierraHash, StorageAddress, StorageCommitment, StorageValue,
+};
+use pathfinder_crypto::Felt;
+use pathfinder_storage::Transaction;
+
+use crate::storage::TrieStorage;
+use crate::tree::{LeafDiff, MerkleTree};
+use crate::{ClassCommitmentTree, ContractsStorageTree, StorageCommitmentTree};
+
+impl<'tx> ClassCommitmentTree<ClassTrieStorage<'tx>> {
+    pub fn empty(tx: &'tx Transaction<'tx>) -> Self {
+        Self::empty_with(ClassTrieStorage::new(tx))
+    }
+
+    pub fn load(tx: &'tx Transaction<'tx>, block: BlockNumber) -> anyhow::Result<Self> {
+        Self::load_with(ClassTrieStorage::new(tx), block)
+    }
+
+    /// Returns the Sierra classes whose commitment leaf differs between blocks `from` and `to`.
+    /// See [`MerkleTree::diff`].
+    pub fn diff(
+        tx: &'tx Transaction<'tx>,
+        from: BlockNumber,
+        to: BlockNumber,
+        limit: usize,
+    ) -> anyhow::Result<Vec<LeafDiff>> {
+        Self::diff_with(&ClassTrieStorage::new(tx), from, to, limit)
+    }
+
+    /// Generates a proof for the given `class`. See [`MerkleTree::get_proof`].
+    pub fn get_proof(
+        tx: &'tx Transaction<'tx>,
+        block: BlockNumber,
+        class: SierraHash,
+    ) -> anyhow::Result<Vec<TrieNode>> {
+        Self::get_proof_with(&ClassTrieStorage::new(tx), block, class)
+    }
+
+    /// Returns up to `limit` Sierra classes starting at `start`, ordered by hash.
+    /// See [`MerkleTree::get_range`].
+    pub fn get_range(
+        tx: &'tx Transaction<'tx>,
+        block: BlockNumber,
+        start: SierraHash,
+        limit: usize,
+    ) -> anyhow::Result<Vec<(SierraHash, ClassCommitmentLeafHash)>> {
+        Self::get_range_with(&ClassTrieStorage::new(tx), block, start, limit)
+    }
+
+    /// Verifies a range of Sierra classes against the class commitment.
+    /// See [`MerkleTree::verify_range`].
+    pub fn verify_range(
+        root: ClassCommitment,
+        start: SierraHash,
+        classes: &[(SierraHash, ClassCommitmentLeafHash)],
+        start_proof: &[TrieNode],
+        end_proof: &[TrieNode],
+    ) -> anyhow::Result<bool> {
+        let leaves = classes
+            .iter()
+            .map(|(key, value)| (key.view_bits().to_owned(), value.0))
+            .collect::<Vec<_>>();
+
+        MerkleTree::<PoseidonHash, 251>::verify_range(
+            root.0,
+            start.view_bits(),
+            &leaves,
+            start_proof,
+            end_proof,
+        )
+    }
+}
+
+impl<'tx> ContractsStorageTree<ContractTrieStorage<'tx>> {
+    pub fn empty(tx: &'tx Transaction<'tx>, contract: ContractAddress) -> Self {
+        Self::empty_with(ContractTrieStorage::new(tx, contract))
+    }
+
+    pub fn load(
+        tx: &'tx Transaction<'tx>,
+        contract: ContractAddress,
+        block: BlockNumber,
+    ) -> anyhow::Result<Self> {
+        Self::load_with(ContractTrieStorage::new(tx, contract), block)
+    }
+
+    /// Generates a proof for `key`. See [`MerkleTree::get_proof`].
+    pub fn get_proof(
+        tx: &'tx Transaction<'tx>,
+        contract: ContractAddress,
+        block: BlockNumber,
+        key: &BitSlice<u8, Msb0>,
+    ) -> anyhow::Result<Vec<TrieNode>> {
+        Self::get_proof_with(&ContractTrieStorage::new(tx, contract), block, key)
+    }
+
+    /// Returns the storage slots of `contract` which differ between blocks `from` and `to`.
+    /// See [`MerkleTree::diff`].
+    pub fn diff(
+        tx: &'tx Transaction<'tx>,
+        contract: ContractAddress,
+        from: BlockNumber,
+        to: BlockNumber,
+        limit: usize,
+    ) -> anyhow::Result<Vec<LeafDiff>> {
+        Self::diff_with(&ContractTrieStorage::new(tx, contract), from, to, limit)
+    }
+
+    /// Returns up to `limit` storage slots of `contract` starting at `start`, ordered by
+    /// address. See [`MerkleTree::get_range`].
+    pub fn get_range(
+        tx: &'tx Transaction<'tx>,
+        contract: ContractAddress,
+        block: BlockNumber,
+        start: StorageAddress,
+        limit: usize,
+    ) -> anyhow::Result<Vec<(StorageAddress, StorageValue)>> {
+        Self::get_range_with(&ContractTrieStorage::new(tx, contract), block, start, limit)
+    }
+
+    /// Verifies a range of storage slots against the contract's storage root.
+    /// See [`MerkleTree::verify_range`].
+    pub fn verify_range(
+        root: ContractRoot,
+        start: StorageAddress,
+        slots: &[(StorageAddress, StorageValue)],
+        start_proof: &[TrieNode],
+        end_proof: &[TrieNode],
+    ) -> anyhow::Result<bool> {
+        let leaves = slots
+            .iter()
+            .map(|(key, value)| (key.view_bits().to_owned(), value.0))
+            .collect::<Vec<_>>();
+
+        MerkleTree::<PedersenHash, 251>::verify_range(
+            root.0,
+            start.view_bits(),
+            &leaves,
+            start_proof,
+            end_proof,
+        )
+    }
+}
+
+impl<'tx> StorageCommitmentTree<StorageTrieStorage<'tx>> {
+    pub fn empty(tx: &'tx Transaction<'tx>) -> Self {
+        Self::empty_with(StorageTrieStorage::new(tx))
+    }
+
+    pub fn load(tx: &'tx Transaction<'tx>, block: BlockNumber) -> anyhow::Result<Self> {
+        Self::load_with(StorageTrieStorage::new(tx), block)
+    }
+
+    /// Generates a proof for the given `key`. See [`MerkleTree::get_proof`].
+    pub fn get_proof(
+        tx: &'tx Transaction<'tx>,
+        block: BlockNumber,
+        address: &ContractAddress,
+    ) -> anyhow::Result<Vec<TrieNode>> {
+        Self::get_proof_with(&StorageTrieStorage::new(tx), block, address)
+    }
+
+    /// Returns the contracts whose state hash differs between blocks `from` and `to`.
+    /// See [`MerkleTree::diff`].
+    pub fn diff(
+        tx: &'tx Transaction<'tx>,
+        from: BlockNumber,
+        to: BlockNumber,
+        limit: usize,
+    ) -> anyhow::Result<Vec<LeafDiff>> {
+        Self::diff_with(&StorageTrieStorage::new(tx), from, to, limit)
+    }
+
+    /// Returns up to `limit` contracts starting at `start`, ordered by address.
+    /// See [`MerkleTree::get_range`].
+    pub fn get_range(
+        tx: &'tx Transaction<'tx>,
+        block: BlockNumber,
+        start: ContractAddress,
+        limit: usize,
+    ) -> anyhow::Result<Vec<(ContractAddress, ContractStateHash)>> {
+        Self::get_range_with(&StorageTrieStorage::new(tx), block, start, limit)
+    }
+
+    /// Verifies a range of contracts against the storage commitment.
+    /// See [`MerkleTree::verify_range`].
+    pub fn verify_range(
+        root: StorageCommitment,
+        start: ContractAddress,
+        contracts: &[(ContractAddress, ContractStateHash)],
+        start_proof: &[TrieNode],
+        end_proof: &[TrieNode],
+    ) -> anyhow::Result<bool> {
+        let leaves = contracts
+            .iter()
+            .map(|(key, value)| (key.view_bits().to_owned(), value.0))
+            .collect::<Vec<_>>();
+
+        MerkleTree::<PedersenHash, 251>::verify_range(
+            root.0,
+            start.view_bits(),
+            &leaves,
+            start_proof,
+            end_proof,
+        )
+    }
+}
+
+/// The [TrieStorage] of the class trie in the database.
+pub struct ClassTrieStorage<'tx> {
+    tx: &'tx Transaction<'tx>,
+}
+
+impl<'tx> ClassTrieStorage<'tx> {
+    pub fn new(tx: &'tx Transaction<'tx>) -> Self {
+        Self { tx }
+    }
+}
+
+impl TrieStorage for ClassTrieStorage<'_> {
+    fn root_index(&self, block: BlockNumber) -> anyhow::Result<Option<u64>> {
+        self.tx.class_root_index(block)
+    }
+
+    fn node(&self, index: u64) -> anyhow::Result<Option<StoredNode>> {
+        self.tx.class_trie_node(index)
+    }
+
+    fn hash(&self, index: u64) -> anyhow::Result<Option<Felt>> {
+        self.tx.class_trie_node_hash(index)
+    }
+
+    fn leaf(&self, block: BlockNumber, path: &BitSlice<u8, Msb0>) -> anyhow::Result<Option<Felt>> {
+        assert!(path.len() == 251);
+
+        let sierra = ClassHash(Felt::from_bits(path).context("Mapping path to sierra hash")?);
+
+        let casm = self
+            .tx
+            .casm_hash_at(block.into(), sierra)
+            .context("Querying CASM hash")?;
+        let Some(casm) = casm else {
+            return Ok(None);
+        };
+
+        let leaf = self
+            .tx
+            .class_commitment_leaf(block, &casm)
+            .context("Querying class leaf")?
+            .map(|x| x.0);
+
+        Ok(leaf)
+    }
+
+    fn insert(&self, root: Felt, nodes: &HashMap<Felt, Node>) -> anyhow::Result<u64> {
+        self.tx.insert_class_trie(ClassCommitment(root), nodes)
+    }
+}
+
+/// The [TrieStorage] of a contract's storage trie in the database.
+pub struct ContractTrieStorage<'tx> {
+    tx: &'tx Transaction<'tx>,
+    contract: ContractAddress,
+}
+
+impl<'tx> ContractTrieStorage<'tx> {
+    pub fn new(tx: &'tx Transaction<'tx>, contract: ContractAddress) -> Self {
+        Self { tx, contract }
+    }
+}
+
+impl TrieStorage for ContractTrieStorage<'_> {
+    fn root_index(&self, block: BlockNumber) -> anyhow::Result<Option<u64>> {
+        self.tx.contract_root_index(block, self.contract)
+    }
+
+    fn node(&self, index: u64) -> anyhow::Result<Option<StoredNode>> {
+        self.tx.contract_trie_node(index)
+    }
+
+    fn hash(&self, index: u64) -> anyhow::Result<Option<Felt>> {
+        self.tx.contract_trie_node_hash(index)
+    }
+
+    fn leaf(&self, block: BlockNumber, path: &BitSlice<u8, Msb0>) -> anyhow::Result<Option<Felt>> {
+        assert!(path.len() == 251);
+
+        let key =
+            StorageAddress(Felt::from_bits(path).context("Mapping leaf path to storage address")?);
+
+        let value = self
+            .tx
+            .storage_value(block.into(), self.contract, key)?
+            .map(|x| x.0);
+
+        Ok(value)
+    }
+
+    fn insert(&self, root: Felt, nodes: &HashMap<Felt, Node>) -> anyhow::Result<u64> {
+        self.tx.insert_contract_trie(ContractRoot(root), nodes)
+    }
+}
+
+/// The [TrieStorage] of the global storage trie in the database.
+pub struct StorageTrieStorage<'tx> {
+    tx: &'tx Transaction<'tx>,
+}
+
+impl<'tx> StorageTrieStorage<'tx> {
+    pub fn new(tx: &'tx Transaction<'tx>) -> Self {
+        Self { tx }
+    }
+}
+
+impl TrieStorage for StorageTrieStorage<'_> {
+    fn root_index(&self, block: BlockNumber) -> anyhow::Result<Option<u64>> {
+        self.tx.storage_root_index(block)
+    }
+
+    fn node(&self, index: u64) -> anyhow::Result<Option<StoredNode>> {
+        self.tx.storage_trie_node(index)
+    }
+
+    fn hash(&self, index: u64) -> anyhow::Result<Option<Felt>> {
+        self.tx.storage_trie_node_hash(index)
+    }
+
+    fn leaf(&self, block: BlockNumber, path: &BitSlice<u8, Msb0>) -> anyhow::Result<Option<Felt>> {
+        assert!(path.len() == 251);
+
+        let contract = ContractAddress(
+            Felt::from_bits(path).context("Mapping leaf path to contract address")?,
+        );
+
+        let value = self.tx.contract_state_hash(block, contract)?.map(|x| x.0);
+
+        Ok(value)
+    }
+
+    fn insert(&self, root: Felt, nodes: &HashMap<Felt, Node>) -> anyhow::Result<u64> {
+        self.tx.insert_storage_trie(StorageCommitment(root), nodes)
+    }
+}
@@ -1,12 +1,25 @@
+//! Starknet's binary Merkle-Patricia trees, used to calculate the state, class, transaction and
+//! event commitments, and to generate and verify proofs against them.
+//!
+//! The trees read their nodes through a [TrieStorage]. The `storage` feature, enabled by default,
+//! adds the implementations backed by pathfinder's database.
+
 pub mod contract_state;
 pub mod merkle_node;
 pub mod tree;
 
 mod class;
 mod contract;
+#[cfg(feature = "storage")]
+mod database;
 mod storage;
 mod transaction;
 
+pub use bitvec;
+
 pub use class::ClassCommitmentTree;
 pub use contract::{ContractsStorageTree, StorageCommitmentTree};
+#[cfg(feature = "storage")]
+pub use database::{ClassTrieStorage, ContractTrieStorage, StorageTrieStorage};
+pub use storage::{InMemoryTrieStorage, TrieStorage};
 pub use transaction::TransactionOrEventTree;
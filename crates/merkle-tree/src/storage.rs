@@ -1,6 +1,11 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+
+use anyhow::Context;
 use bitvec::prelude::*;
+use pathfinder_common::trie::{Node, StoredNode};
+use pathfinder_common::BlockNumber;
 use pathfinder_crypto::Felt;
-use pathfinder_storage::StoredNode;
 
 /// Read-only storage used by the [Merkle tree](crate::tree::MerkleTree).
 pub trait Storage {
@@ -11,3 +16,212 @@ pub trait Storage {
     /// Returns the value of the leaf at the given path.
     fn leaf(&self, path: &BitSlice<u8, Msb0>) -> anyhow::Result<Option<Felt>>;
 }
+
+/// The persistent storage of one of Starknet's tries, from which the trees are loaded.
+///
+/// The database is accessed through [ClassTrieStorage](crate::ClassTrieStorage),
+/// [StorageTrieStorage](crate::StorageTrieStorage) and
+/// [ContractTrieStorage](crate::ContractTrieStorage), while [InMemoryTrieStorage] keeps a trie
+/// in memory.
+pub trait TrieStorage {
+    /// Returns the index of the trie's root as of `block`, or [None] if the trie is empty.
+    fn root_index(&self, block: BlockNumber) -> anyhow::Result<Option<u64>>;
+    /// Returns the node stored at the given index.
+    fn node(&self, index: u64) -> anyhow::Result<Option<StoredNode>>;
+    /// Returns the hash of the node at the given index.
+    fn hash(&self, index: u64) -> anyhow::Result<Option<Felt>>;
+    /// Returns the value of the leaf at the given path as of `block`.
+    fn leaf(&self, block: BlockNumber, path: &BitSlice<u8, Msb0>) -> anyhow::Result<Option<Felt>>;
+    /// Stores the new nodes of a committed tree and returns the index of its root.
+    fn insert(&self, root: Felt, nodes: &HashMap<Felt, Node>) -> anyhow::Result<u64>;
+}
+
+impl<T: TrieStorage> TrieStorage for &T {
+    fn root_index(&self, block: BlockNumber) -> anyhow::Result<Option<u64>> {
+        (*self).root_index(block)
+    }
+
+    fn node(&self, index: u64) -> anyhow::Result<Option<StoredNode>> {
+        (*self).node(index)
+    }
+
+    fn hash(&self, index: u64) -> anyhow::Result<Option<Felt>> {
+        (*self).hash(index)
+    }
+
+    fn leaf(&self, block: BlockNumber, path: &BitSlice<u8, Msb0>) -> anyhow::Result<Option<Felt>> {
+        (*self).leaf(block, path)
+    }
+
+    fn insert(&self, root: Felt, nodes: &HashMap<Felt, Node>) -> anyhow::Result<u64> {
+        (*self).insert(root, nodes)
+    }
+}
+
+/// The [Storage] of a tree loaded from a [TrieStorage] as of `block`, or of an empty tree if
+/// `block` is [None].
+pub(crate) struct AtBlock<'a, S> {
+    pub storage: &'a S,
+    pub block: Option<BlockNumber>,
+}
+
+impl<S: TrieStorage> Storage for AtBlock<'_, S> {
+    fn get(&self, index: u64) -> anyhow::Result<Option<StoredNode>> {
+        self.storage.node(index)
+    }
+
+    fn hash(&self, index: u64) -> anyhow::Result<Option<Felt>> {
+        self.storage.hash(index)
+    }
+
+    fn leaf(&self, path: &BitSlice<u8, Msb0>) -> anyhow::Result<Option<Felt>> {
+        match self.block {
+            Some(block) => self.storage.leaf(block, path),
+            None => Ok(None),
+        }
+    }
+}
+
+/// A [TrieStorage] which keeps the trie in memory.
+///
+/// Unlike the database, nothing ties the nodes to blocks, so the roots and leaves of each block
+/// must be set explicitly using [InMemoryTrieStorage::set_root] and
+/// [InMemoryTrieStorage::set_leaf].
+#[derive(Debug, Default)]
+pub struct InMemoryTrieStorage(Mutex<InMemoryTrie>);
+
+#[derive(Debug, Default)]
+struct InMemoryTrie {
+    /// Nodes and their hashes, by index.
+    nodes: Vec<(Felt, StoredNode)>,
+    roots: BTreeMap<BlockNumber, Option<u64>>,
+    leaves: HashMap<Felt, BTreeMap<BlockNumber, Felt>>,
+}
+
+impl InMemoryTrieStorage {
+    /// Sets the root of the trie from `block` onwards, as returned by [TrieStorage::insert], or
+    /// [None] for an empty trie.
+    pub fn set_root(&self, block: BlockNumber, root: Option<u64>) {
+        self.0.lock().unwrap().roots.insert(block, root);
+    }
+
+    /// Sets the value of the leaf at `path` from `block` onwards.
+    pub fn set_leaf(&self, block: BlockNumber, path: Felt, value: Felt) {
+        self.0
+            .lock()
+            .unwrap()
+            .leaves
+            .entry(path)
+            .or_default()
+            .insert(block, value);
+    }
+}
+
+impl TrieStorage for InMemoryTrieStorage {
+    fn root_index(&self, block: BlockNumber) -> anyhow::Result<Option<u64>> {
+        let trie = self.0.lock().unwrap();
+        Ok(trie
+            .roots
+            .range(..=block)
+            .next_back()
+            .and_then(|(_, root)| *root))
+    }
+
+    fn node(&self, index: u64) -> anyhow::Result<Option<StoredNode>> {
+        let trie = self.0.lock().unwrap();
+        Ok(trie.nodes.get(index as usize).map(|(_, node)| node.clone()))
+    }
+
+    fn hash(&self, index: u64) -> anyhow::Result<Option<Felt>> {
+        let trie = self.0.lock().unwrap();
+        Ok(trie.nodes.get(index as usize).map(|(hash, _)| *hash))
+    }
+
+    fn leaf(&self, block: BlockNumber, path: &BitSlice<u8, Msb0>) -> anyhow::Result<Option<Felt>> {
+        let key = Felt::from_bits(path).context("Mapping leaf path to felt")?;
+
+        let trie = self.0.lock().unwrap();
+        Ok(trie
+            .leaves
+            .get(&key)
+            .and_then(|history| history.range(..=block).next_back())
+            .map(|(_, value)| *value))
+    }
+
+    fn insert(&self, root: Felt, nodes: &HashMap<Felt, Node>) -> anyhow::Result<u64> {
+        let mut trie = self.0.lock().unwrap();
+
+        // All indices are assigned up front, since the children of a node may come after it.
+        let first = trie.nodes.len() as u64;
+        let indices = nodes
+            .keys()
+            .zip(first..)
+            .map(|(hash, index)| (*hash, index))
+            .collect::<HashMap<_, _>>();
+
+        let mut new_nodes = nodes
+            .iter()
+            .map(|(hash, node)| Ok((indices[hash], *hash, node.as_stored(&indices)?)))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        new_nodes.sort_unstable_by_key(|(index, ..)| *index);
+        trie.nodes
+            .extend(new_nodes.into_iter().map(|(_, hash, node)| (hash, node)));
+
+        indices.get(&root).copied().context("Root node is missing")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::{ContractAddress, ContractStateHash};
+
+    use super::*;
+    use crate::StorageCommitmentTree;
+
+    #[test]
+    fn in_memory_trie_storage() {
+        let storage = InMemoryTrieStorage::default();
+        let contracts = [
+            (contract_address!("0x1"), contract_state_hash!("0x11")),
+            (contract_address!("0x2"), contract_state_hash!("0x22")),
+            (contract_address!("0x3"), contract_state_hash!("0x33")),
+        ];
+
+        let commit_block =
+            |block: BlockNumber, updates: &[(ContractAddress, ContractStateHash)]| {
+                let mut tree = match block.parent() {
+                    Some(parent) => StorageCommitmentTree::load_with(&storage, parent).unwrap(),
+                    None => StorageCommitmentTree::empty_with(&storage),
+                };
+                for (address, state_hash) in updates {
+                    tree.set(*address, *state_hash).unwrap();
+                    storage.set_leaf(block, address.0, state_hash.0);
+                }
+                let (root, nodes) = tree.commit().unwrap();
+                let index = storage.insert(root.0, &nodes).unwrap();
+                storage.set_root(block, Some(index));
+                root
+            };
+
+        commit_block(BlockNumber::GENESIS, &contracts[..2]);
+        let root = commit_block(BlockNumber::GENESIS + 1, &contracts[2..]);
+
+        // Building the tree in one go gives the same root as loading the previous block's tree.
+        let mut tree = StorageCommitmentTree::empty_with(InMemoryTrieStorage::default());
+        for (address, state_hash) in contracts {
+            tree.set(address, state_hash).unwrap();
+        }
+        let (expected, _) = tree.commit().unwrap();
+        assert_eq!(root, expected);
+
+        let range = StorageCommitmentTree::get_range_with(
+            &storage,
+            BlockNumber::GENESIS + 1,
+            ContractAddress::ZERO,
+            10,
+        )
+        .unwrap();
+        assert_eq!(range, contracts);
+    }
+}
@@ -1,6 +1,6 @@
 use bitvec::view::BitView;
+use pathfinder_common::trie::StoredNode;
 use pathfinder_crypto::Felt;
-use pathfinder_storage::StoredNode;
 
 use crate::tree::MerkleTree;
 use pathfinder_common::hash::PedersenHash;
@@ -49,9 +49,8 @@ use crate::storage::Storage;
 use anyhow::Context;
 use bitvec::{prelude::BitSlice, prelude::BitVec, prelude::Msb0};
 use pathfinder_common::hash::FeltHash;
-use pathfinder_common::trie::TrieNode;
+use pathfinder_common::trie::{Node, StoredNode, TrieNode};
 use pathfinder_crypto::Felt;
-use pathfinder_storage::{Node, StoredNode};
 use std::collections::HashMap;
 use std::ops::ControlFlow;
 use std::{cell::RefCell, rc::Rc};
@@ -140,7 +139,7 @@ impl<H: FeltHash, const HEIGHT: usize> MerkleTree<H, HEIGHT> {
         storage: &impl Storage,
         mut path: BitVec<u8, Msb0>,
     ) -> anyhow::Result<Felt> {
-        use pathfinder_storage::Child;
+        use pathfinder_common::trie::Child;
 
         let hash = match node {
             InternalNode::Unresolved(idx) => {
@@ -560,6 +559,311 @@ impl<H: FeltHash, const HEIGHT: usize> MerkleTree<H, HEIGHT> {
         Ok(nodes)
     }
 
+    /// Compares the trees rooted at `root_a` and `root_b` and returns every leaf whose
+    /// value differs between the two, ordered by key.
+    ///
+    /// Only subtrees which diverge are visited -- any pair of nodes with identical hashes
+    /// is skipped entirely. This makes the cost proportional to the size of the difference
+    /// rather than the size of the trees.
+    ///
+    /// Leaf values are read from `storage_a` and `storage_b` respectively, which must
+    /// therefore represent the state at each root.
+    ///
+    /// The walk stops as soon as more than `limit` differing leaves have been found, in
+    /// which case `limit + 1` leaves are returned. This lets callers reject large diffs
+    /// without paying for all of them.
+    pub fn diff(
+        root_a: Option<u64>,
+        storage_a: &impl Storage,
+        root_b: Option<u64>,
+        storage_b: &impl Storage,
+        limit: usize,
+    ) -> anyhow::Result<Vec<LeafDiff>> {
+        let into_cursor = |root: Option<u64>| root.map(DiffCursor::Node).unwrap_or_default();
+
+        let mut diffs = Vec::new();
+        let mut stack = vec![(BitVec::new(), into_cursor(root_a), into_cursor(root_b))];
+
+        while let Some((path, a, b)) = stack.pop() {
+            match (&a, &b) {
+                (DiffCursor::Empty, DiffCursor::Empty) => continue,
+                (DiffCursor::Node(x), DiffCursor::Node(y)) => {
+                    if x == y {
+                        continue;
+                    }
+
+                    let hash_a = storage_a
+                        .hash(*x)
+                        .context("Querying node hash")?
+                        .with_context(|| format!("Node {x} is missing"))?;
+                    let hash_b = storage_b
+                        .hash(*y)
+                        .context("Querying node hash")?
+                        .with_context(|| format!("Node {y} is missing"))?;
+
+                    if hash_a == hash_b {
+                        continue;
+                    }
+                }
+                (
+                    DiffCursor::Edge {
+                        path: path_a,
+                        child: Some(child_a),
+                    },
+                    DiffCursor::Edge {
+                        path: path_b,
+                        child: Some(child_b),
+                    },
+                ) if path_a == path_b && child_a == child_b => continue,
+                _ => {}
+            }
+
+            if path.len() == HEIGHT {
+                let old = match a {
+                    DiffCursor::Leaf => storage_a.leaf(&path).context("Querying old leaf")?,
+                    DiffCursor::Empty => None,
+                    other => anyhow::bail!("Expected a leaf at full height but got {other:?}"),
+                };
+                let new = match b {
+                    DiffCursor::Leaf => storage_b.leaf(&path).context("Querying new leaf")?,
+                    DiffCursor::Empty => None,
+                    other => anyhow::bail!("Expected a leaf at full height but got {other:?}"),
+                };
+
+                if old != new {
+                    diffs.push(LeafDiff {
+                        key: path,
+                        old,
+                        new,
+                    });
+
+                    if diffs.len() > limit {
+                        break;
+                    }
+                }
+
+                continue;
+            }
+
+            let (left_a, right_a) = a.expand(storage_a).context("Expanding old node")?;
+            let (left_b, right_b) = b.expand(storage_b).context("Expanding new node")?;
+
+            // Push right first so that the left subtree is visited first, keeping the
+            // output ordered by key.
+            let mut right = path.clone();
+            right.push(Direction::Right.into());
+            stack.push((right, right_a, right_b));
+
+            let mut left = path;
+            left.push(Direction::Left.into());
+            stack.push((left, left_a, left_b));
+        }
+
+        Ok(diffs)
+    }
+
+    /// Returns up to `limit` leaves of the tree rooted at `root` whose keys are at least
+    /// `start`, ordered by key.
+    ///
+    /// Subtrees containing only smaller keys are skipped without being resolved. Together
+    /// with [proofs](Self::get_proof) of `start` and the last returned key, the range can be
+    /// checked against the root using [verify_range](Self::verify_range).
+    pub fn get_range(
+        root: u64,
+        storage: &impl Storage,
+        start: &BitSlice<u8, Msb0>,
+        limit: usize,
+    ) -> anyhow::Result<Vec<(BitVec<u8, Msb0>, Felt)>> {
+        anyhow::ensure!(
+            start.len() == HEIGHT,
+            "Range start has length {} but the tree height is {HEIGHT}",
+            start.len()
+        );
+
+        let mut leaves = Vec::new();
+        // A child of `None` is a leaf.
+        let mut stack = vec![(BitVec::new(), Some(root))];
+
+        while leaves.len() < limit {
+            let Some((path, node)) = stack.pop() else {
+                break;
+            };
+
+            if path.as_bitslice() < &start[..path.len()] {
+                continue;
+            }
+
+            let Some(index) = node else {
+                let value = storage
+                    .leaf(&path)
+                    .context("Querying leaf")?
+                    .context("Leaf is missing")?;
+                leaves.push((path, value));
+                continue;
+            };
+
+            let node = storage
+                .get(index)
+                .context("Resolving node")?
+                .with_context(|| format!("Node {index} is missing"))?;
+
+            // Push right first so that the left subtree is visited first, keeping the
+            // output ordered by key.
+            let (left, right) = match node {
+                StoredNode::Binary { left, right } => (Some(left), Some(right)),
+                StoredNode::LeafBinary => (None, None),
+                StoredNode::Edge { child, path: edge } => {
+                    let mut path = path;
+                    path.extend_from_bitslice(&edge);
+                    stack.push((path, Some(child)));
+                    continue;
+                }
+                StoredNode::LeafEdge { path: edge } => {
+                    let mut path = path;
+                    path.extend_from_bitslice(&edge);
+                    stack.push((path, None));
+                    continue;
+                }
+            };
+
+            let mut right_path = path.clone();
+            right_path.push(Direction::Right.into());
+            stack.push((right_path, right));
+
+            let mut left_path = path;
+            left_path.push(Direction::Left.into());
+            stack.push((left_path, left));
+        }
+
+        Ok(leaves)
+    }
+
+    /// Verifies that `proof`, as returned by [get_proof](Self::get_proof), proves either that
+    /// the leaf at `key` has `value` in the tree with the given `root`, or that the tree has no
+    /// leaf at `key`.
+    ///
+    /// Returns [None] if the proof is inconsistent with `root`, `key` or `value`.
+    pub fn verify_proof(
+        root: Felt,
+        key: &BitSlice<u8, Msb0>,
+        value: Felt,
+        proof: &[TrieNode],
+    ) -> Option<Membership> {
+        // Protect from ill-formed keys
+        if key.len() != HEIGHT {
+            return None;
+        }
+
+        // An empty tree has no nodes to prove anything with.
+        if root == Felt::ZERO && proof.is_empty() {
+            return Some(Membership::NonMember);
+        }
+
+        let mut expected_hash = root;
+        let mut remaining_path: &BitSlice<u8, Msb0> = key;
+
+        for proof_node in proof {
+            if proof_node.hash::<H>() != expected_hash {
+                return None;
+            }
+
+            match proof_node {
+                TrieNode::Binary { left, right } => {
+                    // A proof which continues past the leaf is invalid.
+                    let direction = Direction::from(*remaining_path.first()?);
+
+                    expected_hash = match direction {
+                        Direction::Left => *left,
+                        Direction::Right => *right,
+                    };
+
+                    remaining_path = &remaining_path[1..];
+                }
+                TrieNode::Edge { child, path } => {
+                    if path.len() > remaining_path.len() {
+                        return None;
+                    }
+
+                    if path != &remaining_path[..path.len()] {
+                        // The proof leads as close to the key as the tree allows, and diverges
+                        // from it, so the tree has no leaf at the key.
+                        return Some(Membership::NonMember);
+                    }
+
+                    expected_hash = *child;
+                    remaining_path = &remaining_path[path.len()..];
+                }
+            }
+        }
+
+        // The whole path was consumed, so the last hash is the leaf's value.
+        if remaining_path.is_empty() && expected_hash == value {
+            Some(Membership::Member)
+        } else {
+            None
+        }
+    }
+
+    /// Verifies that `leaves` are exactly the leaves of the tree with the given `root` whose
+    /// keys lie between `start` and the last leaf's key, inclusive.
+    ///
+    /// `start_proof` must be a [proof](Self::get_proof) of `start`, and `end_proof` a proof of
+    /// the last leaf's key. The proofs provide the hashes of the subtrees outside of the range,
+    /// which together with the leaves must add up to `root`. An empty `end_proof` claims that
+    /// there are no leaves beyond the range.
+    ///
+    /// Returns whether the tree contains leaves beyond the range, or an error if the leaves and
+    /// proofs are inconsistent with `root`.
+    pub fn verify_range(
+        root: Felt,
+        start: &BitSlice<u8, Msb0>,
+        leaves: &[(BitVec<u8, Msb0>, Felt)],
+        start_proof: &[TrieNode],
+        end_proof: &[TrieNode],
+    ) -> anyhow::Result<bool> {
+        anyhow::ensure!(
+            start.len() == HEIGHT,
+            "Range start has length {} but the tree height is {HEIGHT}",
+            start.len()
+        );
+
+        let mut previous: &BitSlice<u8, Msb0> = start;
+        for (i, (key, value)) in leaves.iter().enumerate() {
+            anyhow::ensure!(key.len() == HEIGHT, "Leaf key has an invalid length");
+            anyhow::ensure!(
+                key.as_bitslice() > previous || (i == 0 && key.as_bitslice() == previous),
+                "Leaves are not in ascending order starting at the range start"
+            );
+            anyhow::ensure!(*value != Felt::ZERO, "Leaf value is zero");
+            previous = key.as_bitslice();
+        }
+
+        let mut nodes = leaves.to_vec();
+        collect_range_siblings::<H>(start, start_proof, Direction::Left, &mut nodes)
+            .context("Invalid start proof")?;
+
+        let outside = nodes.len();
+        if let Some((end, _)) = leaves.last() {
+            collect_range_siblings::<H>(end, end_proof, Direction::Right, &mut nodes)
+                .context("Invalid end proof")?;
+        } else {
+            anyhow::ensure!(end_proof.is_empty(), "End proof given for an empty range");
+        }
+        let has_more = nodes.len() > outside;
+
+        nodes.sort_by(|a, b| a.0.cmp(&b.0));
+        let computed = range_subtree_hash::<H>(&nodes, 0)?
+            .map(|(hash, path)| edge_hash::<H>(hash, &path))
+            .unwrap_or(Felt::ZERO);
+
+        anyhow::ensure!(
+            computed == root,
+            "Range does not match the root: expected {root}, computed {computed}"
+        );
+
+        Ok(has_more)
+    }
+
     /// Traverses from the current root towards destination node.
     /// Returns the list of nodes along the path.
     ///
@@ -784,6 +1088,203 @@ impl<H: FeltHash, const HEIGHT: usize> MerkleTree<H, HEIGHT> {
     }
 }
 
+/// Walks the proof of a range boundary `key` and collects the subtrees lying entirely outside
+/// of the range, as `(path, hash)` pairs.
+///
+/// For the start of a range these are the subtrees to the left of `key`, and for the end of a
+/// range those to the right of it.
+fn collect_range_siblings<H: FeltHash>(
+    key: &BitSlice<u8, Msb0>,
+    proof: &[TrieNode],
+    outside: Direction,
+    nodes: &mut Vec<(BitVec<u8, Msb0>, Felt)>,
+) -> anyhow::Result<()> {
+    let mut height = 0;
+    for (i, node) in proof.iter().enumerate() {
+        anyhow::ensure!(height < key.len(), "Proof is longer than the key");
+
+        match node {
+            TrieNode::Binary { left, right } => {
+                let mut sibling = key[..height].to_bitvec();
+                match (Direction::from(key[height]), outside) {
+                    (Direction::Right, Direction::Left) => {
+                        sibling.push(Direction::Left.into());
+                        nodes.push((sibling, *left));
+                    }
+                    (Direction::Left, Direction::Right) => {
+                        sibling.push(Direction::Right.into());
+                        nodes.push((sibling, *right));
+                    }
+                    _ => {}
+                }
+                height += 1;
+            }
+            TrieNode::Edge { path, .. } => {
+                let key_path = key
+                    .get(height..height + path.len())
+                    .context("Edge path is longer than the key")?;
+
+                if key_path != path.as_bitslice() {
+                    // The key does not exist, and the edge's subtree lies entirely to one
+                    // side of it.
+                    anyhow::ensure!(
+                        i == proof.len() - 1,
+                        "Proof continues past a divergent edge"
+                    );
+                    let is_outside = match outside {
+                        Direction::Left => path.as_bitslice() < key_path,
+                        Direction::Right => path.as_bitslice() > key_path,
+                    };
+                    if is_outside {
+                        nodes.push((key[..height].to_bitvec(), node.hash::<H>()));
+                    }
+                    return Ok(());
+                }
+
+                height += path.len();
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Computes the subtree at `height` containing the given nodes, which must be sorted by path.
+///
+/// The result is the hash of the subtree's topmost binary or leaf node, along with the path of
+/// the edge leading to it, if any.
+fn range_subtree_hash<H: FeltHash>(
+    nodes: &[(BitVec<u8, Msb0>, Felt)],
+    height: usize,
+) -> anyhow::Result<Option<(Felt, BitVec<u8, Msb0>)>> {
+    match nodes {
+        [] => return Ok(None),
+        [(path, hash)] if path.len() == height => return Ok(Some((*hash, BitVec::new()))),
+        _ => {}
+    }
+    anyhow::ensure!(
+        nodes.iter().all(|(path, _)| path.len() > height),
+        "Range contains overlapping nodes"
+    );
+
+    let split = nodes.partition_point(|(path, _)| Direction::from(path[height]) == Direction::Left);
+    let left = range_subtree_hash::<H>(&nodes[..split], height + 1)?;
+    let right = range_subtree_hash::<H>(&nodes[split..], height + 1)?;
+
+    let subtree = match (left, right) {
+        (Some((left, left_path)), Some((right, right_path))) => {
+            let left = edge_hash::<H>(left, &left_path);
+            let right = edge_hash::<H>(right, &right_path);
+            (BinaryNode::calculate_hash::<H>(left, right), BitVec::new())
+        }
+        (Some((hash, mut path)), None) => {
+            path.insert(0, Direction::Left.into());
+            (hash, path)
+        }
+        (None, Some((hash, mut path))) => {
+            path.insert(0, Direction::Right.into());
+            (hash, path)
+        }
+        (None, None) => unreachable!("Nodes are not empty"),
+    };
+
+    Ok(Some(subtree))
+}
+
+/// The hash of a node reached through an edge with the given path, or of the node itself if the
+/// path is empty.
+fn edge_hash<H: FeltHash>(child: Felt, path: &BitSlice<u8, Msb0>) -> Felt {
+    if path.is_empty() {
+        child
+    } else {
+        EdgeNode::calculate_hash::<H>(child, path)
+    }
+}
+
+/// The outcome of a successful [proof verification](MerkleTree::verify_proof).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Membership {
+    /// The tree contains the key with the given value.
+    Member,
+    /// The tree does not contain the key.
+    NonMember,
+}
+
+/// A leaf whose value differs between two trees, as returned by [`MerkleTree::diff`].
+///
+/// A value of `None` means the leaf does not exist in that tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LeafDiff {
+    pub key: BitVec<u8, Msb0>,
+    pub old: Option<Felt>,
+    pub new: Option<Felt>,
+}
+
+/// The remainder of a subtree at a given height while walking two trees in lock-step.
+///
+/// Edges are consumed one bit at a time so that both trees always advance together.
+#[derive(Debug, Default)]
+enum DiffCursor {
+    #[default]
+    Empty,
+    Node(u64),
+    /// The remaining part of an edge. A child of `None` means the edge ends in a leaf.
+    Edge {
+        path: BitVec<u8, Msb0>,
+        child: Option<u64>,
+    },
+    Leaf,
+}
+
+impl DiffCursor {
+    /// Splits this cursor into its left and right children.
+    fn expand(self, storage: &impl Storage) -> anyhow::Result<(Self, Self)> {
+        let (path, child) = match self {
+            DiffCursor::Empty => return Ok((DiffCursor::Empty, DiffCursor::Empty)),
+            DiffCursor::Leaf => anyhow::bail!("Leaf nodes cannot be expanded"),
+            DiffCursor::Node(index) => {
+                let node = storage
+                    .get(index)
+                    .context("Resolving node")?
+                    .with_context(|| format!("Node {index} is missing"))?;
+
+                match node {
+                    StoredNode::Binary { left, right } => {
+                        return Ok((DiffCursor::Node(left), DiffCursor::Node(right)))
+                    }
+                    StoredNode::LeafBinary => return Ok((DiffCursor::Leaf, DiffCursor::Leaf)),
+                    StoredNode::Edge { child, path } => (path, Some(child)),
+                    StoredNode::LeafEdge { path } => (path, None),
+                }
+            }
+            DiffCursor::Edge { path, child } => (path, child),
+        };
+
+        let direction = path
+            .first()
+            .map(|b| Direction::from(*b))
+            .context("Edge path is empty")?;
+
+        let remainder = path[1..].to_bitvec();
+        let next = if !remainder.is_empty() {
+            DiffCursor::Edge {
+                path: remainder,
+                child,
+            }
+        } else {
+            match child {
+                Some(child) => DiffCursor::Node(child),
+                None => DiffCursor::Leaf,
+            }
+        };
+
+        match direction {
+            Direction::Left => Ok((next, DiffCursor::Empty)),
+            Direction::Right => Ok((DiffCursor::Empty, next)),
+        }
+    }
+}
+
 /// Direction for the [`MerkleTree::dfs`] as the return value of the visitor function.
 #[derive(Default)]
 pub enum Visit {
@@ -800,7 +1301,7 @@ pub enum Visit {
 #[cfg(test)]
 mod tests {
     use pathfinder_common::hash::PedersenHash;
-    use pathfinder_storage::StoredNode;
+    use pathfinder_common::trie::StoredNode;
 
     use super::*;
     use bitvec::prelude::*;
@@ -808,7 +1309,7 @@ mod tests {
 
     type TestTree = MerkleTree<PedersenHash, 251>;
 
-    #[derive(Default, Debug)]
+    #[derive(Default, Debug, Clone)]
     struct TestStorage {
         nodes: HashMap<u64, (Felt, StoredNode)>,
         leaves: HashMap<Felt, Felt>,
@@ -835,7 +1336,7 @@ mod tests {
         tree: MerkleTree<H, HEIGHT>,
         storage: &mut TestStorage,
     ) -> (Felt, u64) {
-        use pathfinder_storage::Child;
+        use pathfinder_common::trie::Child;
 
         for (key, value) in &tree.leaves {
             let key = Felt::from_bits(key).unwrap();
@@ -1377,6 +1878,314 @@ mod tests {
         }
     }
 
+    mod diff {
+        use super::*;
+        use pathfinder_common::felt;
+
+        #[test]
+        fn identical_roots() {
+            let mut uut = TestTree::empty();
+            let mut storage = TestStorage::default();
+
+            uut.set(&storage, felt!("0x1").view_bits().to_owned(), felt!("0x10"))
+                .unwrap();
+            uut.set(&storage, felt!("0x2").view_bits().to_owned(), felt!("0x20"))
+                .unwrap();
+            let (_, root) = commit_and_persist(uut, &mut storage);
+
+            let diff =
+                TestTree::diff(Some(root), &storage, Some(root), &storage, usize::MAX).unwrap();
+            assert!(diff.is_empty());
+
+            let diff = TestTree::diff(None, &storage, None, &storage, usize::MAX).unwrap();
+            assert!(diff.is_empty());
+        }
+
+        #[test]
+        fn empty_to_tree() {
+            let mut uut = TestTree::empty();
+            let mut storage = TestStorage::default();
+
+            let leaves = [
+                (felt!("0x5"), felt!("0x50")),
+                (felt!("0x1"), felt!("0x10")),
+                (felt!("0x99cadc82"), felt!("0x1234")),
+            ];
+            for (key, value) in &leaves {
+                uut.set(&storage, key.view_bits().to_owned(), *value)
+                    .unwrap();
+            }
+            let (_, root) = commit_and_persist(uut, &mut storage);
+
+            let diff = TestTree::diff(None, &storage, Some(root), &storage, usize::MAX).unwrap();
+            let expected = [
+                (felt!("0x1"), felt!("0x10")),
+                (felt!("0x5"), felt!("0x50")),
+                (felt!("0x99cadc82"), felt!("0x1234")),
+            ]
+            .into_iter()
+            .map(|(key, value)| LeafDiff {
+                key: key.view_bits().to_owned(),
+                old: None,
+                new: Some(value),
+            })
+            .collect::<Vec<_>>();
+            assert_eq!(diff, expected);
+
+            // And the reverse removes them all again.
+            let diff = TestTree::diff(Some(root), &storage, None, &storage, usize::MAX).unwrap();
+            let expected = expected
+                .into_iter()
+                .map(|x| LeafDiff {
+                    key: x.key,
+                    old: x.new,
+                    new: None,
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(diff, expected);
+        }
+
+        #[test]
+        fn changed_removed_and_added() {
+            let unchanged = felt!("0x1");
+            let changed = felt!("0x86");
+            let removed = felt!("0x87");
+            let added = felt!("0x99cadc82");
+
+            let mut uut = TestTree::empty();
+            let mut storage = TestStorage::default();
+            uut.set(&storage, unchanged.view_bits().to_owned(), felt!("0x1"))
+                .unwrap();
+            uut.set(&storage, changed.view_bits().to_owned(), felt!("0x2"))
+                .unwrap();
+            uut.set(&storage, removed.view_bits().to_owned(), felt!("0x3"))
+                .unwrap();
+            let (_, root_a) = commit_and_persist(uut, &mut storage);
+
+            // Leaf values are versioned per block, so keep a snapshot of the old state.
+            let storage_a = storage.clone();
+
+            let mut uut = TestTree::new(root_a);
+            uut.set(&storage, changed.view_bits().to_owned(), felt!("0x22"))
+                .unwrap();
+            uut.set(&storage, removed.view_bits().to_owned(), Felt::ZERO)
+                .unwrap();
+            uut.set(&storage, added.view_bits().to_owned(), felt!("0x4"))
+                .unwrap();
+            let (_, root_b) = commit_and_persist(uut, &mut storage);
+
+            let diff = TestTree::diff(Some(root_a), &storage_a, Some(root_b), &storage, usize::MAX)
+                .unwrap();
+            let expected = vec![
+                LeafDiff {
+                    key: changed.view_bits().to_owned(),
+                    old: Some(felt!("0x2")),
+                    new: Some(felt!("0x22")),
+                },
+                LeafDiff {
+                    key: removed.view_bits().to_owned(),
+                    old: Some(felt!("0x3")),
+                    new: None,
+                },
+                LeafDiff {
+                    key: added.view_bits().to_owned(),
+                    old: None,
+                    new: Some(felt!("0x4")),
+                },
+            ];
+            assert_eq!(diff, expected);
+        }
+
+        #[test]
+        fn stops_after_limit() {
+            let mut uut = TestTree::empty();
+            let mut storage = TestStorage::default();
+
+            for key in 1..=10u64 {
+                uut.set(
+                    &storage,
+                    Felt::from_u64(key).view_bits().to_owned(),
+                    felt!("0x1"),
+                )
+                .unwrap();
+            }
+            let (_, root) = commit_and_persist(uut, &mut storage);
+
+            let diff = TestTree::diff(None, &storage, Some(root), &storage, 3).unwrap();
+            let keys = diff.into_iter().map(|x| x.key).collect::<Vec<_>>();
+            let expected = (1..=4u64)
+                .map(|key| Felt::from_u64(key).view_bits().to_owned())
+                .collect::<Vec<_>>();
+            assert_eq!(keys, expected);
+
+            let diff = TestTree::diff(None, &storage, Some(root), &storage, 10).unwrap();
+            assert_eq!(diff.len(), 10);
+        }
+    }
+
+    mod range {
+        use super::*;
+        use pathfinder_common::felt;
+
+        fn leaves() -> Vec<(Felt, Felt)> {
+            vec![
+                (felt!("0x1"), felt!("0x10")),
+                (felt!("0x5"), felt!("0x50")),
+                (felt!("0x7"), felt!("0x70")),
+                (felt!("0x86"), felt!("0x860")),
+                (felt!("0x87"), felt!("0x870")),
+                (felt!("0x99cadc82"), felt!("0x1234")),
+                (
+                    felt!("0x400000000000000000000000000000000000000000000000000000000000001"),
+                    felt!("0x4"),
+                ),
+            ]
+        }
+
+        fn tree() -> (Felt, u64, TestStorage) {
+            let mut uut = TestTree::empty();
+            let mut storage = TestStorage::default();
+            for (key, value) in leaves() {
+                uut.set(&storage, key.view_bits().to_owned(), value)
+                    .unwrap();
+            }
+            let (root, index) = commit_and_persist(uut, &mut storage);
+            (root, index, storage)
+        }
+
+        fn as_bits(leaves: &[(Felt, Felt)]) -> Vec<(BitVec<u8, Msb0>, Felt)> {
+            leaves
+                .iter()
+                .map(|(key, value)| (key.view_bits().to_owned(), *value))
+                .collect()
+        }
+
+        #[test]
+        fn get_range() {
+            let (_, index, storage) = tree();
+
+            let range = TestTree::get_range(index, &storage, Felt::ZERO.view_bits(), 100).unwrap();
+            assert_eq!(range, as_bits(&leaves()));
+
+            let range = TestTree::get_range(index, &storage, felt!("0x6").view_bits(), 3).unwrap();
+            assert_eq!(range, as_bits(&leaves()[2..5]));
+
+            let range = TestTree::get_range(index, &storage, felt!("0x87").view_bits(), 1).unwrap();
+            assert_eq!(range, as_bits(&leaves()[4..5]));
+
+            let range =
+                TestTree::get_range(index, &storage, felt!("0x99cadc83").view_bits(), 100).unwrap();
+            assert_eq!(range, as_bits(&leaves()[6..]));
+
+            let range = TestTree::get_range(index, &storage, felt!("0x1").view_bits(), 0).unwrap();
+            assert!(range.is_empty());
+        }
+
+        #[test]
+        fn verify_chunks() {
+            let (root, index, storage) = tree();
+
+            let mut start = Felt::ZERO;
+            let mut received = Vec::new();
+            loop {
+                let range = TestTree::get_range(index, &storage, start.view_bits(), 2).unwrap();
+                let start_proof = TestTree::get_proof(index, &storage, start.view_bits()).unwrap();
+                let end_proof = match range.last() {
+                    Some((key, _)) => TestTree::get_proof(index, &storage, key).unwrap(),
+                    None => Vec::new(),
+                };
+
+                let has_more = TestTree::verify_range(
+                    root,
+                    start.view_bits(),
+                    &range,
+                    &start_proof,
+                    &end_proof,
+                )
+                .unwrap();
+
+                received.extend(range.iter().cloned());
+                if !has_more {
+                    break;
+                }
+                let last = Felt::from_bits(&range.last().unwrap().0).unwrap();
+                start = last + Felt::from_u64(1);
+            }
+
+            assert_eq!(received, as_bits(&leaves()));
+        }
+
+        #[test]
+        fn empty_ranges() {
+            let (root, index, storage) = tree();
+
+            let start = felt!("0x400000000000000000000000000000000000000000000000000000000000002");
+            let range = TestTree::get_range(index, &storage, start.view_bits(), 2).unwrap();
+            assert!(range.is_empty());
+            let start_proof = TestTree::get_proof(index, &storage, start.view_bits()).unwrap();
+            let has_more =
+                TestTree::verify_range(root, start.view_bits(), &range, &start_proof, &[]).unwrap();
+            assert!(!has_more);
+
+            let has_more =
+                TestTree::verify_range(Felt::ZERO, Felt::ZERO.view_bits(), &[], &[], &[]).unwrap();
+            assert!(!has_more);
+        }
+
+        #[test]
+        fn tampered_ranges_are_rejected() {
+            let (root, index, storage) = tree();
+
+            let start = felt!("0x2");
+            let range = TestTree::get_range(index, &storage, start.view_bits(), 4).unwrap();
+            let start_proof = TestTree::get_proof(index, &storage, start.view_bits()).unwrap();
+            let end_proof = TestTree::get_proof(index, &storage, &range.last().unwrap().0).unwrap();
+
+            assert!(TestTree::verify_range(
+                root,
+                start.view_bits(),
+                &range,
+                &start_proof,
+                &end_proof
+            )
+            .unwrap());
+
+            let mut missing = range.clone();
+            missing.remove(1);
+            TestTree::verify_range(root, start.view_bits(), &missing, &start_proof, &end_proof)
+                .unwrap_err();
+
+            let mut modified = range.clone();
+            modified[2].1 = felt!("0x999");
+            TestTree::verify_range(root, start.view_bits(), &modified, &start_proof, &end_proof)
+                .unwrap_err();
+
+            // The range is not the end of the tree.
+            TestTree::verify_range(root, start.view_bits(), &range, &start_proof, &[]).unwrap_err();
+
+            let wrong_root = root + Felt::from_u64(1);
+            TestTree::verify_range(
+                wrong_root,
+                start.view_bits(),
+                &range,
+                &start_proof,
+                &end_proof,
+            )
+            .unwrap_err();
+
+            let mut unordered = range;
+            unordered.swap(0, 1);
+            TestTree::verify_range(
+                root,
+                start.view_bits(),
+                &unordered,
+                &start_proof,
+                &end_proof,
+            )
+            .unwrap_err();
+        }
+    }
+
     mod real_world {
         use super::*;
         use pathfinder_common::felt;
@@ -1655,95 +2464,21 @@ mod tests {
         use pathfinder_common::hash::PedersenHash;
         use pathfinder_common::trie::TrieNode;
 
-        use super::{Direction, TestStorage, TestTree};
+        use super::{TestStorage, TestTree};
         use bitvec::prelude::Msb0;
         use bitvec::slice::BitSlice;
         use pathfinder_common::felt;
         use pathfinder_crypto::Felt;
 
-        #[derive(Debug, PartialEq, Eq)]
-        pub enum Membership {
-            Member,
-            NonMember,
-        }
-
-        /// Verifies that the key `key` with value `value` is indeed part of the MPT that has root
-        /// `root`, given `proofs`.
-        /// Supports proofs of non-membership as well as proof of membership: this function returns
-        /// an enum corresponding to the membership of `value`, or returns `None` in case of a hash mismatch.
-        /// The algorithm follows this logic:
-        /// 1. init expected_hash <- root hash
-        /// 2. loop over nodes: current <- nodes[i]
-        ///    1. verify the current node's hash matches expected_hash (if not then we have a bad proof)
-        ///    2. move towards the target - if current is:
-        ///       1. binary node then choose the child that moves towards the target, else if
-        ///       2. edge node then check the path against the target bits
-        ///          1. If it matches then proceed with the child, else
-        ///          2. if it does not match then we now have a proof that the target does not exist
-        ///    3. nibble off target bits according to which child you got in (2). If all bits are gone then you
-        ///       have reached the target and the child hash is the value you wanted and the proof is complete.
-        ///    4. set expected_hash <- to the child hash
-        /// 3. check that the expected_hash is `value` (we should've reached the leaf)
-        fn verify_proof(
+        pub use crate::tree::Membership;
+
+        pub(super) fn verify_proof(
             root: Felt,
             key: &BitSlice<u8, Msb0>,
             value: Felt,
             proofs: &[TrieNode],
         ) -> Option<Membership> {
-            // Protect from ill-formed keys
-            if key.len() != 251 {
-                return None;
-            }
-
-            let mut expected_hash = root;
-            let mut remaining_path: &BitSlice<u8, Msb0> = key;
-
-            for proof_node in proofs.iter() {
-                // Hash mismatch? Return None.
-                if proof_node.hash::<PedersenHash>() != expected_hash {
-                    return None;
-                }
-                match proof_node {
-                    TrieNode::Binary { left, right } => {
-                        // Direction will always correspond to the 0th index
-                        // because we're removing bits on every iteration.
-                        let direction = Direction::from(remaining_path[0]);
-
-                        // Set the next hash to be the left or right hash,
-                        // depending on the direction
-                        expected_hash = match direction {
-                            Direction::Left => *left,
-                            Direction::Right => *right,
-                        };
-
-                        // Advance by a single bit
-                        remaining_path = &remaining_path[1..];
-                    }
-                    TrieNode::Edge { child, path } => {
-                        if path != &remaining_path[..path.len()] {
-                            // If paths don't match, we've found a proof of non membership because we:
-                            // 1. Correctly moved towards the target insofar as is possible, and
-                            // 2. hashing all the nodes along the path does result in the root hash, which means
-                            // 3. the target definitely does not exist in this tree
-                            return Some(Membership::NonMember);
-                        }
-
-                        // Set the next hash to the child's hash
-                        expected_hash = *child;
-
-                        // Advance by the whole edge path
-                        remaining_path = &remaining_path[path.len()..];
-                    }
-                }
-            }
-
-            // At this point, we should reach `value` !
-            if expected_hash == value {
-                Some(Membership::Member)
-            } else {
-                // Hash mismatch. Return `None`.
-                None
-            }
+            TestTree::verify_proof(root, key, value, proofs)
         }
 
         /// Structure representing a randomly generated tree.
@@ -2157,5 +2892,190 @@ mod tests {
             let verified = verify_proof(root, &key1, value_1, &proofs[0]);
             assert!(verified.is_none());
         }
+
+        #[test]
+        fn empty_tree() {
+            let key = felt!("0x1");
+            let verified = verify_proof(Felt::ZERO, key.view_bits(), Felt::ZERO, &[]);
+            assert_eq!(verified, Some(Membership::NonMember));
+        }
+
+        #[test]
+        fn proof_past_leaf() {
+            let key = felt!("0x1");
+            let key = key.view_bits();
+
+            let extra = TrieNode::Binary {
+                left: felt!("0x2"),
+                right: felt!("0x3"),
+            };
+            let leaf = TrieNode::Edge {
+                child: extra.hash::<PedersenHash>(),
+                path: key.to_bitvec(),
+            };
+            let root = leaf.hash::<PedersenHash>();
+
+            let verified = verify_proof(root, key, extra.hash::<PedersenHash>(), &[leaf, extra]);
+            assert!(verified.is_none());
+        }
+    }
+
+    mod prop {
+        //! Compares [MerkleTree] against a naive reference implementation for random
+        //! sequences of inserts, updates and deletes.
+        use std::collections::BTreeMap;
+
+        use bitvec::prelude::*;
+        use pathfinder_common::hash::{FeltHash, PedersenHash};
+        use pathfinder_crypto::Felt;
+        use proptest::prelude::*;
+
+        use super::proofs::{verify_proof, Membership};
+        use super::{commit_and_persist, TestStorage, TestTree};
+
+        /// Calculates the root of the tree containing exactly `leaves` from scratch.
+        fn reference_root(leaves: &BTreeMap<Felt, Felt>) -> Felt {
+            if leaves.is_empty() {
+                return Felt::ZERO;
+            }
+
+            let leaves = leaves
+                .iter()
+                .map(|(key, value)| (key.view_bits(), *value))
+                .collect::<Vec<_>>();
+
+            reference_hash(&leaves, 0)
+        }
+
+        /// Hashes the subtree at `height` containing `leaves`, which must be sorted and
+        /// non-empty.
+        fn reference_hash(leaves: &[(&BitSlice<u8, Msb0>, Felt)], height: usize) -> Felt {
+            if height == 251 {
+                return leaves[0].1;
+            }
+
+            // The keys are sorted, so the first and last key share the subtree's common prefix.
+            let first = &leaves[0].0[height..];
+            let last = &leaves[leaves.len() - 1].0[height..];
+            let common = first
+                .iter()
+                .zip(last.iter())
+                .take_while(|(a, b)| a == b)
+                .count();
+
+            if common > 0 {
+                let child = reference_hash(leaves, height + common);
+                let path = Felt::from_bits(&first[..common]).unwrap();
+                PedersenHash::hash(child, path) + Felt::from_u64(common as u64)
+            } else {
+                let split = leaves.partition_point(|(key, _)| !key[height]);
+                let left = reference_hash(&leaves[..split], height + 1);
+                let right = reference_hash(&leaves[split..], height + 1);
+                PedersenHash::hash(left, right)
+            }
+        }
+
+        proptest! {
+            #![proptest_config(ProptestConfig::with_cases(64))]
+            #[test]
+            fn matches_reference(keys in strategy::keys(), batches in strategy::batches()) {
+                let mut storage = TestStorage::default();
+                let mut root_idx = None;
+                let mut expected = BTreeMap::new();
+
+                // Each batch is applied on top of the previous one as loaded from storage.
+                for batch in batches {
+                    let mut uut = root_idx.map(TestTree::new).unwrap_or_else(TestTree::empty);
+
+                    for (index, value) in batch {
+                        let key = *index.get(&keys);
+                        uut.set(&storage, key.view_bits().to_bitvec(), value).unwrap();
+
+                        if value == Felt::ZERO {
+                            expected.remove(&key);
+                        } else {
+                            expected.insert(key, value);
+                        }
+                    }
+
+                    // An empty tree has no nodes to persist.
+                    let root = if expected.is_empty() {
+                        root_idx = None;
+                        uut.commit(&storage).unwrap().root
+                    } else {
+                        let (root, idx) = commit_and_persist(uut, &mut storage);
+                        root_idx = Some(idx);
+                        root
+                    };
+
+                    prop_assert_eq!(root, reference_root(&expected));
+
+                    let Some(root_idx) = root_idx else {
+                        continue;
+                    };
+
+                    for key in &keys {
+                        let path = key.view_bits().to_bitvec();
+
+                        let value = TestTree::new(root_idx).get(&storage, path.clone()).unwrap();
+                        prop_assert_eq!(value, expected.get(key).copied());
+
+                        let proof = TestTree::get_proof(root_idx, &storage, &path).unwrap();
+                        let membership =
+                            verify_proof(root, &path, value.unwrap_or(Felt::ZERO), &proof);
+                        let expected_membership = match value {
+                            Some(_) => Membership::Member,
+                            None => Membership::NonMember,
+                        };
+                        prop_assert_eq!(membership, Some(expected_membership));
+                    }
+                }
+            }
+        }
+
+        mod strategy {
+            use super::*;
+            use proptest::sample::Index;
+
+            fn key() -> impl Strategy<Value = Felt> {
+                any::<[u8; 32]>().prop_map(|mut bytes| {
+                    // Keys are 251 bits.
+                    bytes[0] &= 0x07;
+                    Felt::from_be_bytes(bytes).unwrap()
+                })
+            }
+
+            /// A pool of keys, some of which differ from a common base key by a single
+            /// bit. This causes edges to be split and merged at all heights.
+            pub fn keys() -> impl Strategy<Value = Vec<Felt>> {
+                let entries = prop::collection::vec((key(), any::<bool>(), 0..251usize), 1..16);
+
+                (key(), entries).prop_map(|(base, entries)| {
+                    entries
+                        .into_iter()
+                        .map(|(key, related, bit)| {
+                            if !related {
+                                return key;
+                            }
+
+                            let mut bits = base.view_bits().to_bitvec();
+                            let flipped = !bits[bit];
+                            bits.set(bit, flipped);
+                            Felt::from_bits(&bits).unwrap()
+                        })
+                        .collect()
+                })
+            }
+
+            /// Batches of writes to the key pool. A zero value deletes the key.
+            ///
+            /// Values are drawn from a small range so that keys are regularly overwritten
+            /// with their current value, and deleted keys are regularly deleted again.
+            pub fn batches() -> impl Strategy<Value = Vec<Vec<(Index, Felt)>>> {
+                let write = (any::<Index>(), (0..4u64).prop_map(Felt::from_u64));
+
+                prop::collection::vec(prop::collection::vec(write, 0..32), 1..6)
+            }
+        }
     }
 }
@@ -1,5 +1,5 @@
 use std::collections::hash_map::DefaultHasher;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::hash::{Hash, Hasher};
 use std::net::IpAddr;
 use std::time::{Duration, Instant};
@@ -10,24 +10,27 @@ use crate::sync::codec;
 use crate::{peers::PeerSet, Config};
 use libp2p::core::Endpoint;
 use libp2p::dcutr;
-use libp2p::gossipsub::{self, IdentTopic, MessageAuthenticity, MessageId};
+use libp2p::gossipsub::{self, IdentTopic, MessageAcceptance, MessageAuthenticity, MessageId};
 use libp2p::identify;
 use libp2p::identity;
 use libp2p::kad::{self, store::MemoryStore};
 use libp2p::multiaddr::Protocol;
 use libp2p::ping;
 use libp2p::relay;
+use libp2p::swarm::behaviour::toggle::Toggle;
 use libp2p::swarm::behaviour::ConnectionEstablished;
 use libp2p::swarm::{
     ConnectionClosed, ConnectionDenied, ConnectionId, DialFailure, FromSwarm, NetworkBehaviour,
     THandler, THandlerInEvent, THandlerOutEvent, ToSwarm,
 };
+use libp2p::upnp;
 use libp2p::StreamProtocol;
 use libp2p::{autonat, Multiaddr, PeerId};
 use p2p_proto::class::{ClassesRequest, ClassesResponse};
 use p2p_proto::event::{EventsRequest, EventsResponse};
 use p2p_proto::header::{BlockHeadersRequest, BlockHeadersResponse};
 use p2p_proto::receipt::{ReceiptsRequest, ReceiptsResponse};
+use p2p_proto::snapshot::{SnapshotRequest, SnapshotResponse};
 use p2p_proto::state::{StateDiffsRequest, StateDiffsResponse};
 use p2p_proto::transaction::{TransactionsRequest, TransactionsResponse};
 use pathfinder_common::ChainId;
@@ -42,6 +45,8 @@ pub fn kademlia_protocol_name(chain_id: ChainId) -> String {
 pub struct Behaviour {
     cfg: Config,
     peers: PeerSet,
+    /// Peers which are not allowed to connect to us, nor to be dialed by us.
+    banned: HashSet<PeerId>,
     swarm: crate::Client,
     secret: Secret,
     inner: Inner,
@@ -53,6 +58,7 @@ pub struct Inner {
     relay: relay::client::Behaviour,
     autonat: autonat::Behaviour,
     dcutr: dcutr::Behaviour,
+    upnp: Toggle<upnp::tokio::Behaviour>,
     ping: ping::Behaviour,
     identify: identify::Behaviour,
     kademlia: kad::Behaviour<MemoryStore>,
@@ -63,6 +69,7 @@ pub struct Inner {
     transactions_sync: p2p_stream::Behaviour<codec::Transactions>,
     receipts_sync: p2p_stream::Behaviour<codec::Receipts>,
     events_sync: p2p_stream::Behaviour<codec::Events>,
+    snapshot_sync: p2p_stream::Behaviour<codec::Snapshot>,
 }
 
 impl NetworkBehaviour for Behaviour {
@@ -81,6 +88,7 @@ impl NetworkBehaviour for Behaviour {
 
         self.check_duplicate_connection(peer)?;
         self.prevent_evicted_peer_reconnections(peer)?;
+        self.prevent_banned_peer_connections(peer)?;
 
         // Is the peer connecting over a relay?
         let is_relayed = remote_addr.iter().any(|p| p == Protocol::P2pCircuit);
@@ -123,6 +131,7 @@ impl NetworkBehaviour for Behaviour {
 
         self.check_duplicate_connection(peer)?;
         self.prevent_evicted_peer_reconnections(peer)?;
+        self.prevent_banned_peer_connections(peer)?;
 
         self.inner
             .handle_established_outbound_connection(connection_id, peer, addr, role_override)
@@ -344,6 +353,7 @@ impl NetworkBehaviour for Behaviour {
         // If we can extract the peer ID, prevent evicted peers from reconnecting too quickly.
         if let Some(peer_id) = peer_id {
             self.prevent_evicted_peer_reconnections(peer_id)?;
+            self.prevent_banned_peer_connections(peer_id)?;
         }
 
         drop(recent_peers);
@@ -390,6 +400,7 @@ impl NetworkBehaviour for Behaviour {
                 // hole-punching.
 
                 self.prevent_evicted_peer_reconnections(peer_id)?;
+                self.prevent_banned_peer_connections(peer_id)?;
 
                 if self.outbound_peers().count() >= self.cfg.max_outbound_peers {
                     self.evict_outbound_peer()?;
@@ -459,6 +470,8 @@ impl Behaviour {
         };
         let gossipsub_config = libp2p::gossipsub::ConfigBuilder::default()
             .message_id_fn(message_id_fn)
+            // Messages are only relayed once the application has validated them.
+            .validate_messages()
             .build()
             .expect("valid gossipsub config");
 
@@ -474,12 +487,16 @@ impl Behaviour {
         let transactions_sync = request_response_behavior::<codec::Transactions>();
         let receipts_sync = request_response_behavior::<codec::Receipts>();
         let events_sync = request_response_behavior::<codec::Events>();
+        let snapshot_sync = request_response_behavior::<codec::Snapshot>();
 
         let (relay_transport, relay) = relay::client::new(peer_id);
 
+        let upnp = Toggle::from(cfg.upnp.then(upnp::tokio::Behaviour::default));
+
         (
             Self {
                 peers: PeerSet::new(cfg.eviction_timeout),
+                banned: HashSet::new(),
                 cfg,
                 swarm,
                 secret: Secret::new(identity),
@@ -487,6 +504,7 @@ impl Behaviour {
                     relay,
                     autonat: autonat::Behaviour::new(peer_id, Default::default()),
                     dcutr: dcutr::Behaviour::new(peer_id),
+                    upnp,
                     ping: ping::Behaviour::new(ping::Config::new()),
                     identify: identify::Behaviour::new(
                         identify::Config::new(
@@ -503,6 +521,7 @@ impl Behaviour {
                     transactions_sync,
                     receipts_sync,
                     events_sync,
+                    snapshot_sync,
                 },
             },
             relay_transport,
@@ -525,6 +544,20 @@ impl Behaviour {
         Ok(())
     }
 
+    /// Relays a validated gossipsub message to other peers, or drops it.
+    pub fn report_message_validation(
+        &mut self,
+        message_id: &MessageId,
+        from: &PeerId,
+        acceptance: MessageAcceptance,
+    ) {
+        // Returns false if the message is no longer in the cache, which is not a problem.
+        let _ = self
+            .inner
+            .gossipsub
+            .report_message_validation_result(message_id, from, acceptance);
+    }
+
     /// Notify the behaviour of a ping event.
     pub fn pinged(&mut self, event: ping::Event) {
         match event.result {
@@ -758,6 +791,16 @@ impl Behaviour {
         }
     }
 
+    /// Prevent banned peers from connecting in either direction.
+    fn prevent_banned_peer_connections(&self, peer_id: PeerId) -> Result<(), ConnectionDenied> {
+        if self.banned.contains(&peer_id) {
+            tracing::debug!(%peer_id, "Banned peer attempting to connect, disconnecting");
+            Err(ConnectionDenied::new("peer is banned"))
+        } else {
+            Ok(())
+        }
+    }
+
     /// Get the IP address from a multiaddr, or disconnect the peer if it doesn't have one.
     fn get_ip(addr: &Multiaddr) -> Result<IpAddr, ConnectionDenied> {
         addr.iter()
@@ -778,6 +821,13 @@ impl Behaviour {
         });
     }
 
+    /// Bans a peer, which also removes it from the DHT routing table. Existing connections
+    /// to the peer must be closed separately.
+    pub fn ban(&mut self, peer_id: PeerId) {
+        self.banned.insert(peer_id);
+        self.inner.kademlia.remove_peer(&peer_id);
+    }
+
     pub fn kademlia_mut(&mut self) -> &mut kad::Behaviour<MemoryStore> {
         &mut self.inner.kademlia
     }
@@ -810,6 +860,10 @@ impl Behaviour {
         &mut self.inner.events_sync
     }
 
+    pub fn snapshot_sync_mut(&mut self) -> &mut p2p_stream::Behaviour<codec::Snapshot> {
+        &mut self.inner.snapshot_sync
+    }
+
     pub fn peers(&self) -> impl Iterator<Item = (PeerId, &Peer)> {
         self.peers.iter()
     }
@@ -849,6 +903,7 @@ pub enum Event {
     Relay(relay::client::Event),
     Autonat(autonat::Event),
     Dcutr(dcutr::Event),
+    Upnp(upnp::Event),
     Ping(ping::Event),
     Identify(Box<identify::Event>),
     Kademlia(kad::Event),
@@ -859,6 +914,7 @@ pub enum Event {
     TransactionsSync(p2p_stream::Event<TransactionsRequest, TransactionsResponse>),
     ReceiptsSync(p2p_stream::Event<ReceiptsRequest, ReceiptsResponse>),
     EventsSync(p2p_stream::Event<EventsRequest, EventsResponse>),
+    SnapshotSync(p2p_stream::Event<SnapshotRequest, SnapshotResponse>),
 }
 
 impl From<relay::client::Event> for Event {
@@ -879,6 +935,12 @@ impl From<dcutr::Event> for Event {
     }
 }
 
+impl From<upnp::Event> for Event {
+    fn from(event: upnp::Event) -> Self {
+        Event::Upnp(event)
+    }
+}
+
 impl From<ping::Event> for Event {
     fn from(event: ping::Event) -> Self {
         Event::Ping(event)
@@ -939,6 +1001,12 @@ impl From<p2p_stream::Event<EventsRequest, EventsResponse>> for Event {
     }
 }
 
+impl From<p2p_stream::Event<SnapshotRequest, SnapshotResponse>> for Event {
+    fn from(event: p2p_stream::Event<SnapshotRequest, SnapshotResponse>) -> Self {
+        Event::SnapshotSync(event)
+    }
+}
+
 fn string_to_key(input: &str) -> kad::RecordKey {
     use sha2::{Digest, Sha256};
     let mut hasher = Sha256::new();
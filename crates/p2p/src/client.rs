@@ -1,3 +1,4 @@
 pub mod conv;
 pub mod peer_agnostic;
 pub mod peer_aware;
+pub mod peer_scores;
@@ -384,3 +384,35 @@ impl TryFromDto<p2p_proto::common::L1DataAvailabilityMode> for L1DataAvailabilit
         })
     }
 }
+
+impl TryFromDto<p2p_proto::snapshot::PatriciaNode> for pathfinder_common::trie::TrieNode {
+    fn try_from_dto(dto: p2p_proto::snapshot::PatriciaNode) -> anyhow::Result<Self>
+    where
+        Self: Sized,
+    {
+        use p2p_proto::snapshot::PatriciaNode::{Binary, Edge};
+        Ok(match dto {
+            Binary { left, right } => Self::Binary { left, right },
+            Edge {
+                length,
+                path,
+                child,
+            } => {
+                let length = length as usize;
+                anyhow::ensure!(
+                    (1..=251).contains(&length),
+                    "edge path length {length} out of range"
+                );
+                let bits = path.view_bits();
+                anyhow::ensure!(
+                    bits[..bits.len() - length].not_any(),
+                    "edge path exceeds its length"
+                );
+                Self::Edge {
+                    child,
+                    path: bits[bits.len() - length..].to_bitvec(),
+                }
+            }
+        })
+    }
+}
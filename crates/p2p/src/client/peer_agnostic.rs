@@ -10,6 +10,9 @@ use std::{
 use anyhow::Context;
 use futures::StreamExt;
 use libp2p::PeerId;
+use p2p_proto::class::{ClassesRequest, ClassesResponse};
+use p2p_proto::event::{EventsRequest, EventsResponse};
+use p2p_proto::snapshot::{SnapshotRequest, SnapshotResponse};
 use p2p_proto::state::{StateDiffsRequest, StateDiffsResponse};
 use p2p_proto::transaction::{TransactionsRequest, TransactionsResponse};
 use p2p_proto::{
@@ -29,6 +32,7 @@ use pathfinder_common::{
 use smallvec::SmallVec;
 use tokio::{sync::RwLock, task::spawn_blocking};
 
+use crate::client::peer_scores::{PeerScores, Penalty, SLOW_RESPONSE_THRESHOLD};
 use crate::client::{conv::TryFromDto, peer_aware};
 use crate::sync::protocol;
 
@@ -50,17 +54,56 @@ pub struct Client {
     inner: peer_aware::Client,
     block_propagation_topic: String,
     peers_with_capability: Arc<RwLock<PeersWithCapability>>,
+    peer_scores: Arc<RwLock<PeerScores>>,
 }
 
 impl Client {
-    pub fn new(inner: peer_aware::Client, block_propagation_topic: String) -> Self {
+    pub fn new(
+        inner: peer_aware::Client,
+        block_propagation_topic: String,
+        peer_scores: PeerScores,
+    ) -> Self {
         Self {
             inner,
             block_propagation_topic,
             peers_with_capability: Default::default(),
+            peer_scores: Arc::new(RwLock::new(peer_scores)),
         }
     }
 
+    /// Rewards a peer for a complete and valid response.
+    pub async fn reward(&self, peer: PeerId) {
+        self.peer_scores.write().await.reward(peer);
+    }
+
+    pub async fn penalize(&self, peer: PeerId, penalty: Penalty) {
+        tracing::trace!(%peer, ?penalty, "Penalizing peer");
+        self.peer_scores.write().await.penalize(peer, penalty);
+    }
+
+    /// Returns the scores of all peers which are not at the neutral score, to be persisted.
+    pub async fn peer_scores_snapshot(&self) -> Vec<(PeerId, i64)> {
+        self.peer_scores.read().await.snapshot()
+    }
+
+    /// Awaits a sync request, penalizing the peer if it fails or is slow to be accepted.
+    async fn scored<T>(
+        &self,
+        peer: PeerId,
+        request: impl std::future::Future<Output = anyhow::Result<T>>,
+    ) -> anyhow::Result<T> {
+        let started = std::time::Instant::now();
+        let result = request.await;
+        match &result {
+            Err(_) => self.penalize(peer, Penalty::Disconnected).await,
+            Ok(_) if started.elapsed() > SLOW_RESPONSE_THRESHOLD => {
+                self.penalize(peer, Penalty::SlowResponse).await
+            }
+            Ok(_) => {}
+        }
+        result
+    }
+
     // Propagate new L2 head head
     pub async fn propagate_new_head(
         &self,
@@ -104,9 +147,21 @@ impl Client {
             peers_vec
         };
         peers.shuffle(&mut rand::thread_rng());
+        // Prefer peers with higher scores, picking randomly among those with equal scores.
+        self.peer_scores.read().await.sort(&mut peers);
         peers
     }
 
+    pub async fn get_update_peers_with_header_sync_capability(&self) -> Vec<PeerId> {
+        self.get_update_peers_with_sync_capability(protocol::Headers::NAME)
+            .await
+    }
+
+    pub async fn get_update_peers_with_state_diff_sync_capability(&self) -> Vec<PeerId> {
+        self.get_update_peers_with_sync_capability(protocol::StateDiffs::NAME)
+            .await
+    }
+
     pub async fn get_update_peers_with_transaction_sync_capability(&self) -> Vec<PeerId> {
         self.get_update_peers_with_sync_capability(protocol::Transactions::NAME)
             .await
@@ -117,6 +172,21 @@ impl Client {
             .await
     }
 
+    pub async fn get_update_peers_with_class_sync_capability(&self) -> Vec<PeerId> {
+        self.get_update_peers_with_sync_capability(protocol::Classes::NAME)
+            .await
+    }
+
+    pub async fn get_update_peers_with_event_sync_capability(&self) -> Vec<PeerId> {
+        self.get_update_peers_with_sync_capability(protocol::Events::NAME)
+            .await
+    }
+
+    pub async fn get_update_peers_with_snapshot_sync_capability(&self) -> Vec<PeerId> {
+        self.get_update_peers_with_sync_capability(protocol::Snapshot::NAME)
+            .await
+    }
+
     pub fn header_stream(
         self,
         start: BlockNumber,
@@ -148,7 +218,7 @@ impl Client {
                         },
                     };
 
-                    let mut responses = match self.inner.send_headers_sync_request(peer, request).await
+                    let mut responses = match self.scored(peer, self.inner.send_headers_sync_request(peer, request)).await
                     {
                         Ok(x) => x,
                         Err(error) => {
@@ -165,6 +235,7 @@ impl Client {
                                 Ok(hdr) => hdr,
                                 Err(error) => {
                                     tracing::debug!(%peer, %error, "Header stream failed");
+                                    self.penalize(peer, Penalty::InvalidData).await;
                                     continue 'next_peer;
                                 },
                             },
@@ -195,8 +266,19 @@ impl Client {
         peer: PeerId,
         request: TransactionsRequest,
     ) -> anyhow::Result<futures::channel::mpsc::Receiver<TransactionsResponse>> {
-        self.inner
-            .send_transactions_sync_request(peer, request)
+        self.scored(
+            peer,
+            self.inner.send_transactions_sync_request(peer, request),
+        )
+        .await
+    }
+
+    pub async fn send_classes_sync_request(
+        &self,
+        peer: PeerId,
+        request: ClassesRequest,
+    ) -> anyhow::Result<futures::channel::mpsc::Receiver<ClassesResponse>> {
+        self.scored(peer, self.inner.send_classes_sync_request(peer, request))
             .await
     }
 
@@ -205,7 +287,26 @@ impl Client {
         peer: PeerId,
         request: ReceiptsRequest,
     ) -> anyhow::Result<futures::channel::mpsc::Receiver<ReceiptsResponse>> {
-        self.inner.send_receipts_sync_request(peer, request).await
+        self.scored(peer, self.inner.send_receipts_sync_request(peer, request))
+            .await
+    }
+
+    pub async fn send_events_sync_request(
+        &self,
+        peer: PeerId,
+        request: EventsRequest,
+    ) -> anyhow::Result<futures::channel::mpsc::Receiver<EventsResponse>> {
+        self.scored(peer, self.inner.send_events_sync_request(peer, request))
+            .await
+    }
+
+    pub async fn send_snapshot_sync_request(
+        &self,
+        peer: PeerId,
+        request: SnapshotRequest,
+    ) -> anyhow::Result<futures::channel::mpsc::Receiver<SnapshotResponse>> {
+        self.scored(peer, self.inner.send_snapshot_sync_request(peer, request))
+            .await
     }
 
     pub fn contract_updates_stream(
@@ -243,8 +344,7 @@ impl Client {
                     };
 
                     let mut responses = match self
-                        .inner
-                        .send_state_diffs_sync_request(peer, request)
+                        .scored(peer, self.inner.send_state_diffs_sync_request(peer, request))
                         .await
                     {
                         Ok(x) => x,
@@ -269,7 +369,7 @@ impl Client {
                                     Some(x) => current.storage_diffs = x,
                                     None => {
                                         tracing::debug!(%peer, "Too many storage diffs: {num_values} > {}", current.storage_diffs);
-                                        // TODO punish the peer
+                                        self.penalize(peer, Penalty::InvalidData).await;
                                         continue 'next_peer;
                                     }
                                 }
@@ -290,7 +390,7 @@ impl Client {
                                             Some(x) => current.nonce_updates = x,
                                             None => {
                                                 tracing::debug!(%peer, "Too many nonce updates");
-                                                // TODO punish the peer
+                                                self.penalize(peer, Penalty::InvalidData).await;
                                                 continue 'next_peer;
                                             }
                                         }
@@ -303,7 +403,7 @@ impl Client {
                                             Some(x) => current.deployed_contracts = x,
                                             None => {
                                                 tracing::debug!(%peer, "Too many deployed contracts");
-                                                // TODO punish the peer
+                                                self.penalize(peer, Penalty::InvalidData).await;
                                                 continue 'next_peer;
                                             }
                                         }
@@ -333,7 +433,7 @@ impl Client {
                                     }
                                 } else {
                                     tracing::debug!(%peer, "Premature state diff stream Fin");
-                                    // TODO punish the peer
+                                    self.penalize(peer, Penalty::InvalidData).await;
                                     continue 'next_peer;
                                 }
                             }
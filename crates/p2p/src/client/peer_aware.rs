@@ -4,16 +4,19 @@ use std::collections::HashSet;
 
 use anyhow::Context;
 use futures::channel::mpsc::Receiver as ResponseReceiver;
-use libp2p::{gossipsub::IdentTopic, Multiaddr, PeerId};
+use libp2p::gossipsub::{IdentTopic, MessageAcceptance, MessageId};
+use libp2p::{Multiaddr, PeerId};
 use p2p_proto::class::{ClassesRequest, ClassesResponse};
 use p2p_proto::event::{EventsRequest, EventsResponse};
 use p2p_proto::header::{BlockHeadersRequest, BlockHeadersResponse, NewBlock};
 use p2p_proto::state::{StateDiffsRequest, StateDiffsResponse};
 
 use p2p_proto::receipt::{ReceiptsRequest, ReceiptsResponse};
-use p2p_proto::transaction::{TransactionsRequest, TransactionsResponse};
+use p2p_proto::snapshot::{SnapshotRequest, SnapshotResponse};
+use p2p_proto::transaction::{Transaction, TransactionsRequest, TransactionsResponse};
 use tokio::sync::{mpsc, oneshot};
 
+use crate::peers::Peer;
 #[cfg(test)]
 use crate::test_utils;
 use crate::Command;
@@ -173,6 +176,13 @@ impl Client {
         EventsResponse
     );
 
+    impl_send!(
+        send_snapshot_sync_request,
+        SendSnapshotSyncRequest,
+        SnapshotRequest,
+        SnapshotResponse
+    );
+
     pub async fn publish(&self, topic: &str, new_block: NewBlock) -> anyhow::Result<()> {
         let (sender, receiver) = oneshot::channel();
         let topic = IdentTopic::new(topic);
@@ -187,6 +197,47 @@ impl Client {
         receiver.await.expect("Sender not to be dropped")
     }
 
+    pub async fn publish_transaction(
+        &self,
+        topic: &str,
+        transaction: Transaction,
+    ) -> anyhow::Result<()> {
+        let (sender, receiver) = oneshot::channel();
+        let topic = IdentTopic::new(topic);
+        self.sender
+            .send(Command::PublishTransaction {
+                topic,
+                transaction,
+                sender,
+            })
+            .await
+            .expect("Command receiver not to be dropped");
+        receiver.await.expect("Sender not to be dropped")
+    }
+
+    /// Report whether a gossiped message is valid.
+    ///
+    /// Accepted messages are relayed to other peers, rejected ones penalize the peer they were
+    /// received from, and ignored ones are just dropped.
+    pub async fn report_message_validation(
+        &self,
+        message_id: MessageId,
+        from: PeerId,
+        acceptance: MessageAcceptance,
+    ) {
+        let (sender, receiver) = oneshot::channel();
+        self.sender
+            .send(Command::ReportMessageValidation {
+                message_id,
+                from,
+                acceptance,
+                sender,
+            })
+            .await
+            .expect("Command receiver not to be dropped");
+        receiver.await.expect("Sender not to be dropped")
+    }
+
     /// Mark a peer as not useful.
     ///
     /// These peers will be candidates for outbound peer eviction.
@@ -199,6 +250,27 @@ impl Client {
         receiver.await.expect("Sender not to be dropped")
     }
 
+    /// Returns the peers which are currently connected to us.
+    pub async fn connected_peers(&self) -> Vec<(PeerId, Peer)> {
+        let (sender, receiver) = oneshot::channel();
+        self.sender
+            .send(Command::GetConnectedPeers { sender })
+            .await
+            .expect("Command receiver not to be dropped");
+        receiver.await.expect("Sender not to be dropped")
+    }
+
+    /// Disconnects the peer, and prevents any further connections to or from it until the
+    /// node is restarted.
+    pub async fn ban(&self, peer_id: PeerId) {
+        let (sender, receiver) = oneshot::channel();
+        self.sender
+            .send(Command::Ban { peer_id, sender })
+            .await
+            .expect("Command receiver not to be dropped");
+        receiver.await.expect("Sender not to be dropped")
+    }
+
     #[cfg(test)]
     pub(crate) fn for_test(&self) -> test_utils::Client {
         test_utils::Client::new(self.sender.clone())
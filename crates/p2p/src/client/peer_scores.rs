@@ -0,0 +1,129 @@
+//! Reputation of the peers which serve sync requests.
+//!
+//! Peers start at a neutral score which improves with each complete response, and
+//! degrades with each [Penalty]. Range requests prefer peers with higher scores.
+use std::collections::HashMap;
+use std::time::Duration;
+
+use libp2p::PeerId;
+
+/// The highest score a peer can reach through rewards.
+pub const MAX_SCORE: i64 = 100;
+/// The lowest score a peer can reach through penalties.
+pub const MIN_SCORE: i64 = -1000;
+
+/// How long a peer may take to accept a request before it is penalized as slow.
+pub const SLOW_RESPONSE_THRESHOLD: Duration = Duration::from_secs(5);
+
+/// Misbehaviour of a peer serving sync requests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Penalty {
+    /// The peer sent data which failed verification, such as a hash or signature check.
+    InvalidData,
+    /// The peer could not be reached, or ended its response before it was complete.
+    Disconnected,
+    /// The peer took longer than [SLOW_RESPONSE_THRESHOLD] to accept a request.
+    SlowResponse,
+}
+
+impl Penalty {
+    fn value(&self) -> i64 {
+        match self {
+            Penalty::InvalidData => 100,
+            Penalty::Disconnected => 10,
+            Penalty::SlowResponse => 5,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct PeerScores {
+    scores: HashMap<PeerId, i64>,
+}
+
+impl PeerScores {
+    /// Creates the scores from a previously persisted [snapshot](Self::snapshot).
+    pub fn from_snapshot(snapshot: impl IntoIterator<Item = (PeerId, i64)>) -> Self {
+        Self {
+            scores: snapshot
+                .into_iter()
+                .map(|(peer, score)| (peer, score.clamp(MIN_SCORE, MAX_SCORE)))
+                .collect(),
+        }
+    }
+
+    /// Returns the score of all peers which are not at the neutral score.
+    pub fn snapshot(&self) -> Vec<(PeerId, i64)> {
+        self.scores
+            .iter()
+            .filter(|(_, score)| **score != 0)
+            .map(|(peer, score)| (*peer, *score))
+            .collect()
+    }
+
+    pub fn get(&self, peer: &PeerId) -> i64 {
+        self.scores.get(peer).copied().unwrap_or_default()
+    }
+
+    /// Rewards the peer for a complete and valid response.
+    pub fn reward(&mut self, peer: PeerId) {
+        let score = self.scores.entry(peer).or_default();
+        *score = (*score + 1).min(MAX_SCORE);
+    }
+
+    pub fn penalize(&mut self, peer: PeerId, penalty: Penalty) {
+        let score = self.scores.entry(peer).or_default();
+        *score = (*score - penalty.value()).max(MIN_SCORE);
+    }
+
+    /// Orders the peers by descending score. Peers with equal scores keep their relative order.
+    pub fn sort(&self, peers: &mut [PeerId]) {
+        peers.sort_by_key(|peer| std::cmp::Reverse(self.get(peer)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scores_are_bounded() {
+        let peer = PeerId::random();
+        let mut scores = PeerScores::default();
+
+        for _ in 0..2 * MAX_SCORE {
+            scores.reward(peer);
+        }
+        assert_eq!(scores.get(&peer), MAX_SCORE);
+
+        for _ in 0..100 {
+            scores.penalize(peer, Penalty::InvalidData);
+        }
+        assert_eq!(scores.get(&peer), MIN_SCORE);
+
+        let scores = PeerScores::from_snapshot([(peer, 2 * MAX_SCORE)]);
+        assert_eq!(scores.get(&peer), MAX_SCORE);
+    }
+
+    #[test]
+    fn sort_prefers_high_scores() {
+        let good = PeerId::random();
+        let neutral = PeerId::random();
+        let slow = PeerId::random();
+        let invalid = PeerId::random();
+
+        let mut scores = PeerScores::default();
+        scores.reward(good);
+        scores.penalize(slow, Penalty::SlowResponse);
+        scores.penalize(invalid, Penalty::InvalidData);
+
+        let mut peers = vec![invalid, slow, neutral, good];
+        scores.sort(&mut peers);
+        assert_eq!(peers, vec![good, neutral, slow, invalid]);
+
+        // Peers at the neutral score are not persisted.
+        let mut snapshot = scores.snapshot();
+        snapshot.sort_by_key(|(_, score)| *score);
+        assert_eq!(snapshot, vec![(invalid, -100), (slow, -5), (good, 1)]);
+    }
+}
@@ -5,7 +5,7 @@ use std::time::Duration;
 
 use futures::channel::mpsc::{Receiver as ResponseReceiver, Sender as ResponseSender};
 use ipnet::IpNet;
-use libp2p::gossipsub::IdentTopic;
+use libp2p::gossipsub::{IdentTopic, MessageAcceptance, MessageId};
 use libp2p::identity::Keypair;
 use libp2p::kad::RecordKey;
 use libp2p::swarm;
@@ -14,8 +14,9 @@ use p2p_proto::class::{ClassesRequest, ClassesResponse};
 use p2p_proto::event::{EventsRequest, EventsResponse};
 use p2p_proto::header::{BlockHeadersRequest, BlockHeadersResponse, NewBlock};
 use p2p_proto::receipt::{ReceiptsRequest, ReceiptsResponse};
+use p2p_proto::snapshot::{SnapshotRequest, SnapshotResponse};
 use p2p_proto::state::{StateDiffsRequest, StateDiffsResponse};
-use p2p_proto::transaction::{TransactionsRequest, TransactionsResponse};
+use p2p_proto::transaction::{Transaction, TransactionsRequest, TransactionsResponse};
 use pathfinder_common::{BlockHash, BlockNumber, ChainId};
 use peers::Peer;
 use tokio::sync::{mpsc, oneshot};
@@ -23,7 +24,7 @@ use tokio::sync::{mpsc, oneshot};
 mod behaviour;
 pub mod client;
 mod main_loop;
-mod peers;
+pub mod peers;
 mod secret;
 mod sync;
 #[cfg(test)]
@@ -50,13 +51,17 @@ pub fn new(keypair: Keypair, cfg: Config, chain_id: ChainId) -> (Client, EventRe
     let (behaviour, relay_transport) =
         behaviour::Behaviour::new(&keypair, chain_id, client.clone(), cfg.clone());
 
-    let swarm = Swarm::new(
+    let mut swarm = Swarm::new(
         transport::create(&keypair, relay_transport),
         behaviour,
         local_peer_id,
         swarm::Config::with_tokio_executor().with_idle_connection_timeout(Duration::MAX),
     );
 
+    for address in &cfg.external_addresses {
+        swarm.add_external_address(address.clone());
+    }
+
     let (event_sender, event_receiver) = mpsc::channel(1);
 
     (
@@ -87,6 +92,14 @@ pub struct Config {
     pub ip_whitelist: Vec<IpNet>,
     pub bootstrap: BootstrapConfig,
     pub inbound_connections_rate_limit: RateLimit,
+    /// How many sync requests a single peer can make in a period. Requests over the limit are
+    /// not served.
+    pub inbound_sync_requests_rate_limit: RateLimit,
+    /// Addresses on which this node can be reached by other peers, for example through a port
+    /// forwarded manually on a router. If empty, the addresses observed by peers are used.
+    pub external_addresses: Vec<Multiaddr>,
+    /// Whether to map the listening port on the router using UPnP.
+    pub upnp: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -110,6 +123,11 @@ impl Default for BootstrapConfig {
     }
 }
 
+/// The gossipsub topic on which pending transactions are propagated.
+pub fn transactions_topic(chain_id: ChainId) -> String {
+    format!("transactions/{}", chain_id.to_hex_str())
+}
+
 pub type HeadTx = tokio::sync::watch::Sender<Option<(BlockNumber, BlockHash)>>;
 pub type HeadRx = tokio::sync::watch::Receiver<Option<(BlockNumber, BlockHash)>>;
 
@@ -172,15 +190,38 @@ enum Command {
         request: EventsRequest,
         sender: oneshot::Sender<anyhow::Result<ResponseReceiver<EventsResponse>>>,
     },
+    SendSnapshotSyncRequest {
+        peer_id: PeerId,
+        request: SnapshotRequest,
+        sender: oneshot::Sender<anyhow::Result<ResponseReceiver<SnapshotResponse>>>,
+    },
     PublishPropagationMessage {
         topic: IdentTopic,
         new_block: NewBlock,
         sender: EmptyResultSender,
     },
+    PublishTransaction {
+        topic: IdentTopic,
+        transaction: Transaction,
+        sender: EmptyResultSender,
+    },
+    ReportMessageValidation {
+        message_id: MessageId,
+        from: PeerId,
+        acceptance: MessageAcceptance,
+        sender: oneshot::Sender<()>,
+    },
     NotUseful {
         peer_id: PeerId,
         sender: oneshot::Sender<()>,
     },
+    GetConnectedPeers {
+        sender: oneshot::Sender<Vec<(PeerId, Peer)>>,
+    },
+    Ban {
+        peer_id: PeerId,
+        sender: oneshot::Sender<()>,
+    },
     /// For testing purposes only
     _Test(TestCommand),
 }
@@ -226,10 +267,23 @@ pub enum Event {
         request: EventsRequest,
         channel: ResponseSender<EventsResponse>,
     },
+    InboundSnapshotSyncRequest {
+        from: PeerId,
+        request: SnapshotRequest,
+        channel: ResponseSender<SnapshotResponse>,
+    },
     BlockPropagation {
         from: PeerId,
         new_block: NewBlock,
     },
+    /// A pending transaction received via gossipsub. It is only relayed further once its
+    /// validation result has been reported using
+    /// [report_message_validation](client::peer_aware::Client::report_message_validation).
+    TransactionPropagation {
+        from: PeerId,
+        message_id: MessageId,
+        transaction: Transaction,
+    },
     /// For testing purposes only
     Test(TestEvent),
 }
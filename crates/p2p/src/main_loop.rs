@@ -1,8 +1,10 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fmt::Debug;
+use std::time::Instant;
 
 use futures::{channel::mpsc::Receiver as ResponseReceiver, StreamExt};
-use libp2p::gossipsub::{self, IdentTopic};
+use libp2p::autonat;
+use libp2p::gossipsub::{self, IdentTopic, MessageAcceptance, MessageId};
 use libp2p::identify;
 use libp2p::kad::{
     self, BootstrapError, BootstrapOk, ProgressStep, QueryId, QueryInfo, QueryResult,
@@ -10,11 +12,13 @@ use libp2p::kad::{
 use libp2p::multiaddr::Protocol;
 use libp2p::swarm::dial_opts::DialOpts;
 use libp2p::swarm::SwarmEvent;
+use libp2p::upnp;
 use libp2p::PeerId;
 use p2p_proto::class::ClassesResponse;
 use p2p_proto::event::EventsResponse;
 use p2p_proto::header::BlockHeadersResponse;
 use p2p_proto::receipt::ReceiptsResponse;
+use p2p_proto::snapshot::SnapshotResponse;
 use p2p_proto::state::StateDiffsResponse;
 use p2p_proto::transaction::TransactionsResponse;
 use p2p_proto::{ToProtobuf, TryFromProtobuf};
@@ -44,8 +48,12 @@ pub struct MainLoop {
     // request_sync_status: HashSetDelay<PeerId>,
     pending_queries: PendingQueries,
     chain_id: ChainId,
+    /// Messages on this topic are decoded as transactions, all others as blocks.
+    transactions_topic: IdentTopic,
     /// Ongoing Kademlia bootstrap query.
     ongoing_bootstrap: Option<QueryId>,
+    /// When each connected peer sent its recent inbound sync requests.
+    inbound_sync_requests: HashMap<PeerId, VecDeque<Instant>>,
     _pending_test_queries: TestQueries,
 }
 
@@ -75,6 +83,10 @@ struct PendingRequests {
         OutboundRequestId,
         oneshot::Sender<anyhow::Result<ResponseReceiver<EventsResponse>>>,
     >,
+    pub snapshot: HashMap<
+        OutboundRequestId,
+        oneshot::Sender<anyhow::Result<ResponseReceiver<SnapshotResponse>>>,
+    >,
 }
 
 #[derive(Debug, Default)]
@@ -99,7 +111,9 @@ impl MainLoop {
             pending_sync_requests: Default::default(),
             pending_queries: Default::default(),
             chain_id,
+            transactions_topic: IdentTopic::new(crate::transactions_topic(chain_id)),
             ongoing_bootstrap: None,
+            inbound_sync_requests: Default::default(),
             _pending_test_queries: Default::default(),
         }
     }
@@ -200,6 +214,26 @@ impl MainLoop {
         }
     }
 
+    /// Records an inbound sync request from `peer`. Returns `false` if the peer exceeded its
+    /// [rate limit](crate::Config::inbound_sync_requests_rate_limit), in which case the request
+    /// should not be served.
+    fn allow_inbound_sync_request(&mut self, peer: PeerId) -> bool {
+        let limit = &self.cfg.inbound_sync_requests_rate_limit;
+        let now = Instant::now();
+        let requests = self.inbound_sync_requests.entry(peer).or_default();
+        while requests
+            .front()
+            .is_some_and(|t| now.duration_since(*t) >= limit.interval)
+        {
+            requests.pop_front();
+        }
+        if requests.len() >= limit.max {
+            return false;
+        }
+        requests.push_back(now);
+        true
+    }
+
     async fn handle_event(&mut self, event: SwarmEvent<behaviour::Event>) {
         match event {
             // ===========================
@@ -242,6 +276,7 @@ impl MainLoop {
             } => {
                 tracing::debug!(%peer_id, "Connection closed");
                 if num_established == 0 {
+                    self.inbound_sync_requests.remove(&peer_id);
                     send_test_event(
                         &self.event_sender,
                         TestEvent::ConnectionClosed { remote: peer_id },
@@ -287,7 +322,11 @@ impl MainLoop {
                     // In trusted environments users can simply extract observed addresses from a
                     // libp2p-identify::Event::Received { info: libp2p_identify::Info { observed_addr }} and confirm them via Swarm::add_external_address.
 
-                    self.swarm.add_external_address(observed_addr);
+                    // Addresses configured by the operator take precedence, as the address
+                    // observed by a peer is usually not reachable from behind NAT.
+                    if self.cfg.external_addresses.is_empty() {
+                        self.swarm.add_external_address(observed_addr);
+                    }
 
                     if protocols
                         .iter()
@@ -315,43 +354,20 @@ impl MainLoop {
                 self.swarm.behaviour_mut().pinged(event);
             }
             // ===========================
-            // Block propagation
+            // Block and transaction propagation
             // ===========================
             SwarmEvent::Behaviour(behaviour::Event::Gossipsub(gossipsub::Event::Message {
                 propagation_source: peer_id,
                 message_id: id,
                 message,
             })) => {
-                use prost::Message;
-
-                match p2p_proto::proto::header::NewBlock::decode(message.data.as_ref()) {
-                    Ok(new_block) => {
-                        match p2p_proto::header::NewBlock::try_from_protobuf(new_block, "message") {
-                            Ok(new_block) => {
-                                tracing::trace!(
-                                    "Gossipsub Message: [id={}][peer={}] {:?} ({} bytes)",
-                                    id,
-                                    peer_id,
-                                    new_block,
-                                    message.data.len()
-                                );
-                                self.event_sender
-                                    .send(Event::BlockPropagation {
-                                        from: peer_id,
-                                        new_block,
-                                    })
-                                    .await
-                                    .expect("Event receiver not to be dropped");
-                            }
-                            Err(error) => {
-                                tracing::error!(from=%peer_id, %error, "Gossipsub Message")
-                            }
-                        }
-                    }
-                    Err(error) => {
-                        tracing::error!(from=%peer_id, %error, "Gossipsub Message");
-                    }
-                };
+                if message.topic == self.transactions_topic.hash() {
+                    self.handle_transaction_propagation(peer_id, id, &message.data)
+                        .await;
+                } else {
+                    self.handle_block_propagation(peer_id, id, &message.data)
+                        .await;
+                }
             }
             // ===========================
             // Discovery
@@ -464,6 +480,12 @@ impl MainLoop {
             )) => {
                 tracing::debug!(?request, %peer, %request_id, "Received sync request");
 
+                if !self.allow_inbound_sync_request(peer) {
+                    // Dropping the channel ends the response stream.
+                    tracing::debug!(%peer, %request_id, "Too many sync requests, ignoring");
+                    return;
+                }
+
                 self.event_sender
                     .send(Event::InboundHeadersSyncRequest {
                         from: peer,
@@ -499,6 +521,12 @@ impl MainLoop {
             )) => {
                 tracing::debug!(?request, %peer, %request_id, "Received sync request");
 
+                if !self.allow_inbound_sync_request(peer) {
+                    // Dropping the channel ends the response stream.
+                    tracing::debug!(%peer, %request_id, "Too many sync requests, ignoring");
+                    return;
+                }
+
                 self.event_sender
                     .send(Event::InboundClassesSyncRequest {
                         from: peer,
@@ -534,6 +562,12 @@ impl MainLoop {
             )) => {
                 tracing::debug!(?request, %peer, %request_id, "Received sync request");
 
+                if !self.allow_inbound_sync_request(peer) {
+                    // Dropping the channel ends the response stream.
+                    tracing::debug!(%peer, %request_id, "Too many sync requests, ignoring");
+                    return;
+                }
+
                 self.event_sender
                     .send(Event::InboundStateDiffsSyncRequest {
                         from: peer,
@@ -569,6 +603,12 @@ impl MainLoop {
             )) => {
                 tracing::debug!(?request, %peer, %request_id, "Received sync request");
 
+                if !self.allow_inbound_sync_request(peer) {
+                    // Dropping the channel ends the response stream.
+                    tracing::debug!(%peer, %request_id, "Too many sync requests, ignoring");
+                    return;
+                }
+
                 self.event_sender
                     .send(Event::InboundTransactionsSyncRequest {
                         from: peer,
@@ -604,6 +644,12 @@ impl MainLoop {
             )) => {
                 tracing::debug!(?request, %peer, %request_id, "Received sync request");
 
+                if !self.allow_inbound_sync_request(peer) {
+                    // Dropping the channel ends the response stream.
+                    tracing::debug!(%peer, %request_id, "Too many sync requests, ignoring");
+                    return;
+                }
+
                 self.event_sender
                     .send(Event::InboundReceiptsSyncRequest {
                         from: peer,
@@ -639,6 +685,12 @@ impl MainLoop {
             )) => {
                 tracing::debug!(?request, %peer, %request_id, "Received sync request");
 
+                if !self.allow_inbound_sync_request(peer) {
+                    // Dropping the channel ends the response stream.
+                    tracing::debug!(%peer, %request_id, "Too many sync requests, ignoring");
+                    return;
+                }
+
                 self.event_sender
                     .send(Event::InboundEventsSyncRequest {
                         from: peer,
@@ -664,6 +716,47 @@ impl MainLoop {
                     .expect("Block sync request still to be pending")
                     .send(Ok(channel));
             }
+            SwarmEvent::Behaviour(behaviour::Event::SnapshotSync(
+                p2p_stream::Event::InboundRequest {
+                    request_id,
+                    request,
+                    peer,
+                    channel,
+                },
+            )) => {
+                tracing::debug!(?request, %peer, %request_id, "Received sync request");
+
+                if !self.allow_inbound_sync_request(peer) {
+                    // Dropping the channel ends the response stream.
+                    tracing::debug!(%peer, %request_id, "Too many sync requests, ignoring");
+                    return;
+                }
+
+                self.event_sender
+                    .send(Event::InboundSnapshotSyncRequest {
+                        from: peer,
+                        request,
+                        channel,
+                    })
+                    .await
+                    .expect("Event receiver not to be dropped");
+            }
+            SwarmEvent::Behaviour(behaviour::Event::SnapshotSync(
+                p2p_stream::Event::OutboundRequestSentAwaitingResponses {
+                    request_id,
+                    peer,
+                    channel,
+                },
+            )) => {
+                tracing::debug!(%peer, %request_id, "Sync request sent");
+
+                let _ = self
+                    .pending_sync_requests
+                    .snapshot
+                    .remove(&request_id)
+                    .expect("Block sync request still to be pending")
+                    .send(Ok(channel));
+            }
             SwarmEvent::Behaviour(behaviour::Event::HeadersSync(
                 p2p_stream::Event::OutboundFailure {
                     request_id, error, ..
@@ -742,12 +835,55 @@ impl MainLoop {
                     .expect("Block sync request still to be pending")
                     .send(Err(error.into()));
             }
+            SwarmEvent::Behaviour(behaviour::Event::SnapshotSync(
+                p2p_stream::Event::OutboundFailure {
+                    request_id, error, ..
+                },
+            )) => {
+                tracing::warn!(?request_id, ?error, "Outbound request failed");
+                let _ = self
+                    .pending_sync_requests
+                    .snapshot
+                    .remove(&request_id)
+                    .expect("Block sync request still to be pending")
+                    .send(Err(error.into()));
+            }
             // ===========================
             // NAT hole punching
             // ===========================
             SwarmEvent::Behaviour(behaviour::Event::Dcutr(event)) => {
                 tracing::debug!(?event, "DCUtR event");
             }
+            SwarmEvent::Behaviour(behaviour::Event::Autonat(autonat::Event::StatusChanged {
+                old,
+                new,
+            })) => {
+                tracing::info!(?old, ?new, "Reachability changed");
+                let status = match new {
+                    autonat::NatStatus::Public(_) => 1.0,
+                    autonat::NatStatus::Unknown => 0.0,
+                    autonat::NatStatus::Private => -1.0,
+                };
+                metrics::gauge!("p2p_nat_status", status);
+            }
+            SwarmEvent::Behaviour(behaviour::Event::Upnp(event)) => match event {
+                upnp::Event::NewExternalAddr(address) => {
+                    tracing::info!(%address, "Mapped port using UPnP");
+                    metrics::gauge!("p2p_upnp_mapped", 1.0);
+                }
+                upnp::Event::ExpiredExternalAddr(address) => {
+                    tracing::info!(%address, "UPnP port mapping expired");
+                    metrics::gauge!("p2p_upnp_mapped", 0.0);
+                }
+                upnp::Event::GatewayNotFound => {
+                    tracing::warn!("No UPnP gateway found, the listening port cannot be mapped");
+                }
+                upnp::Event::NonRoutableGateway => {
+                    tracing::warn!(
+                        "UPnP gateway is not exposed directly to the public network, the listening port cannot be mapped"
+                    );
+                }
+            },
             // ===========================
             // Ignored or forwarded for
             // test purposes
@@ -931,6 +1067,22 @@ impl MainLoop {
                     .send_request(&peer_id, request);
                 self.pending_sync_requests.events.insert(request_id, sender);
             }
+            Command::SendSnapshotSyncRequest {
+                peer_id,
+                request,
+                sender,
+            } => {
+                tracing::debug!(?request, "Sending sync request");
+
+                let request_id = self
+                    .swarm
+                    .behaviour_mut()
+                    .snapshot_sync_mut()
+                    .send_request(&peer_id, request);
+                self.pending_sync_requests
+                    .snapshot
+                    .insert(request_id, sender);
+            }
             Command::PublishPropagationMessage {
                 topic,
                 new_block,
@@ -941,14 +1093,144 @@ impl MainLoop {
                 let result = self.publish_data(topic, &data);
                 let _ = sender.send(result);
             }
+            Command::PublishTransaction {
+                topic,
+                transaction,
+                sender,
+            } => {
+                use prost::Message;
+                let data: Vec<u8> = transaction.to_protobuf().encode_to_vec();
+                let result = self.publish_data(topic, &data);
+                let _ = sender.send(result);
+            }
+            Command::ReportMessageValidation {
+                message_id,
+                from,
+                acceptance,
+                sender,
+            } => {
+                self.swarm.behaviour_mut().report_message_validation(
+                    &message_id,
+                    &from,
+                    acceptance,
+                );
+                let _ = sender.send(());
+            }
             Command::NotUseful { peer_id, sender } => {
                 self.swarm.behaviour_mut().not_useful(peer_id);
                 let _ = sender.send(());
             }
+            Command::GetConnectedPeers { sender } => {
+                let peers = self
+                    .swarm
+                    .behaviour()
+                    .peers()
+                    .filter(|(_, peer)| peer.is_connected())
+                    .map(|(peer_id, peer)| (peer_id, peer.clone()))
+                    .collect();
+                let _ = sender.send(peers);
+            }
+            Command::Ban { peer_id, sender } => {
+                self.swarm.behaviour_mut().ban(peer_id);
+                if self.swarm.is_connected(&peer_id) {
+                    if let Err(error) = self.disconnect(peer_id).await {
+                        tracing::debug!(%peer_id, %error, "Failed to disconnect banned peer");
+                    }
+                }
+                tracing::debug!(%peer_id, "Banned peer");
+                let _ = sender.send(());
+            }
             Command::_Test(command) => self.handle_test_command(command).await,
         };
     }
 
+    async fn handle_block_propagation(&mut self, peer_id: PeerId, id: MessageId, data: &[u8]) {
+        use prost::Message;
+
+        let new_block = p2p_proto::proto::header::NewBlock::decode(data)
+            .map_err(std::io::Error::from)
+            .and_then(|new_block| {
+                p2p_proto::header::NewBlock::try_from_protobuf(new_block, "message")
+            });
+
+        match new_block {
+            Ok(new_block) => {
+                tracing::trace!(
+                    "Gossipsub Message: [id={}][peer={}] {:?} ({} bytes)",
+                    id,
+                    peer_id,
+                    new_block,
+                    data.len()
+                );
+                self.swarm.behaviour_mut().report_message_validation(
+                    &id,
+                    &peer_id,
+                    MessageAcceptance::Accept,
+                );
+                self.event_sender
+                    .send(Event::BlockPropagation {
+                        from: peer_id,
+                        new_block,
+                    })
+                    .await
+                    .expect("Event receiver not to be dropped");
+            }
+            Err(error) => {
+                tracing::error!(from=%peer_id, %error, "Gossipsub Message");
+                self.swarm.behaviour_mut().report_message_validation(
+                    &id,
+                    &peer_id,
+                    MessageAcceptance::Reject,
+                );
+            }
+        }
+    }
+
+    /// Decodes a gossiped transaction. Whether it is relayed further is decided by the
+    /// receiver of [Event::TransactionPropagation].
+    async fn handle_transaction_propagation(
+        &mut self,
+        peer_id: PeerId,
+        id: MessageId,
+        data: &[u8],
+    ) {
+        use prost::Message;
+
+        let transaction = p2p_proto::proto::transaction::Transaction::decode(data)
+            .map_err(std::io::Error::from)
+            .and_then(|transaction| {
+                p2p_proto::transaction::Transaction::try_from_protobuf(transaction, "message")
+            });
+
+        match transaction {
+            Ok(transaction) => {
+                tracing::trace!(
+                    "Gossipsub Message: [id={}][peer={}] {:?} ({} bytes)",
+                    id,
+                    peer_id,
+                    transaction,
+                    data.len()
+                );
+                self.event_sender
+                    .send(Event::TransactionPropagation {
+                        from: peer_id,
+                        message_id: id,
+                        transaction,
+                    })
+                    .await
+                    .expect("Event receiver not to be dropped");
+            }
+            Err(error) => {
+                tracing::debug!(from=%peer_id, %error, "Invalid gossiped transaction");
+                self.swarm.behaviour_mut().report_message_validation(
+                    &id,
+                    &peer_id,
+                    MessageAcceptance::Reject,
+                );
+            }
+        }
+    }
+
     fn publish_data(&mut self, topic: IdentTopic, data: &[u8]) -> anyhow::Result<()> {
         let message_id = self
             .swarm
@@ -24,6 +24,7 @@ pub mod protocol {
     define_protocol!(Transactions, "/starknet/transactions/1");
     define_protocol!(Receipts, "/starknet/receipts/1");
     define_protocol!(Events, "/starknet/events/1");
+    define_protocol!(Snapshot, "/starknet/snapshot/1");
 
     pub const PROTOCOLS: &[&str] = &[
         Headers::NAME,
@@ -32,6 +33,7 @@ pub mod protocol {
         Transactions::NAME,
         Receipts::NAME,
         Events::NAME,
+        Snapshot::NAME,
     ];
 }
 
@@ -39,7 +41,7 @@ pub(crate) mod codec {
     use super::protocol;
     use async_trait::async_trait;
     use futures::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
-    use p2p_proto::{class, event, header, proto, receipt, state, transaction};
+    use p2p_proto::{class, event, header, proto, receipt, snapshot, state, transaction};
     use p2p_proto::{ToProtobuf, TryFromProtobuf};
     use p2p_stream::Codec;
     use std::marker::PhantomData;
@@ -101,6 +103,15 @@ pub(crate) mod codec {
         ONE_MIB,
     >;
 
+    pub type Snapshot = SyncCodec<
+        protocol::Snapshot,
+        snapshot::SnapshotRequest,
+        snapshot::SnapshotResponse,
+        proto::snapshot::SnapshotRequest,
+        proto::snapshot::SnapshotResponse,
+        ONE_MIB,
+    >;
+
     #[derive(Clone, Debug)]
     pub struct SyncCodec<Protocol, Req, Resp, ProstReq, ProstResp, const RESPONSE_SIZE_LIMIT: usize>(
         PhantomData<(Protocol, Req, Resp, ProstReq, ProstResp)>,
@@ -14,7 +14,7 @@ use p2p_proto::event::{EventsRequest, EventsResponse};
 use p2p_proto::header::{BlockHeadersRequest, BlockHeadersResponse, NewBlock};
 use p2p_proto::receipt::{ReceiptsRequest, ReceiptsResponse};
 use p2p_proto::state::{StateDiffsRequest, StateDiffsResponse};
-use p2p_proto::transaction::{TransactionsRequest, TransactionsResponse};
+use p2p_proto::transaction::{Transaction, TransactionsRequest, TransactionsResponse};
 use pathfinder_common::ChainId;
 use rstest::rstest;
 use tokio::task::JoinHandle;
@@ -106,6 +106,12 @@ impl Default for TestPeer {
                     max: 1000,
                     interval: Duration::from_secs(1),
                 },
+                inbound_sync_requests_rate_limit: RateLimit {
+                    max: 1000,
+                    interval: Duration::from_secs(1),
+                },
+                external_addresses: vec![],
+                upnp: false,
             },
             Keypair::generate_ed25519(),
         )
@@ -244,6 +250,51 @@ async fn disconnect() {
     assert!(peer2.connected().await.is_empty());
 }
 
+/// Banned peers are disconnected and cannot reconnect.
+#[test_log::test(tokio::test)]
+async fn ban() {
+    let mut peer1 = TestPeer::default();
+    let mut peer2 = TestPeer::default();
+
+    let addr1 = peer1.start_listening().await.unwrap();
+    tracing::info!(%peer1.peer_id, %addr1);
+    let addr2 = peer2.start_listening().await.unwrap();
+    tracing::info!(%peer2.peer_id, %addr2);
+
+    peer1
+        .client
+        .dial(peer2.peer_id, addr2.clone())
+        .await
+        .unwrap();
+
+    exhaust_events(&mut peer2.event_receiver).await;
+
+    let peers_of2 = peer2.client.connected_peers().await;
+    assert_eq!(peers_of2.len(), 1);
+    assert_eq!(peers_of2[0].0, peer1.peer_id);
+
+    peer2.client.ban(peer1.peer_id).await;
+
+    wait_for_event(&mut peer1.event_receiver, move |event| match event {
+        Event::Test(TestEvent::ConnectionClosed { remote }) if remote == peer2.peer_id => Some(()),
+        _ => None,
+    })
+    .await;
+
+    consume_events(peer1.event_receiver);
+    consume_events(peer2.event_receiver);
+
+    // The banned peer cannot be dialed.
+    let result = peer2.client.dial(peer1.peer_id, addr1).await;
+    assert!(result.is_err());
+
+    // The banned peer's connection is closed before it is established. Depending on how quickly
+    // it reconnects, the connection may also be rejected earlier.
+    let _ = peer1.client.dial(peer2.peer_id, addr2).await;
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(peer2.client.connected_peers().await.is_empty());
+}
+
 #[test_log::test(tokio::test)]
 async fn periodic_bootstrap() {
     let _ = env_logger::builder().is_test(true).try_init();
@@ -266,6 +317,12 @@ async fn periodic_bootstrap() {
             max: 1000,
             interval: Duration::from_secs(1),
         },
+        inbound_sync_requests_rate_limit: RateLimit {
+            max: 1000,
+            interval: Duration::from_secs(1),
+        },
+        external_addresses: vec![],
+        upnp: false,
     };
     let mut boot = TestPeer::new(cfg.clone(), Keypair::generate_ed25519());
     let mut peer1 = TestPeer::new(cfg.clone(), Keypair::generate_ed25519());
@@ -412,6 +469,12 @@ async fn reconnect_too_quickly() {
             max: 1000,
             interval: Duration::from_secs(1),
         },
+        inbound_sync_requests_rate_limit: RateLimit {
+            max: 1000,
+            interval: Duration::from_secs(1),
+        },
+        external_addresses: vec![],
+        upnp: false,
     };
 
     let mut peer1 = TestPeer::new(cfg.clone(), Keypair::generate_ed25519());
@@ -514,6 +577,12 @@ async fn duplicate_connection() {
             max: 1000,
             interval: Duration::from_secs(1),
         },
+        inbound_sync_requests_rate_limit: RateLimit {
+            max: 1000,
+            interval: Duration::from_secs(1),
+        },
+        external_addresses: vec![],
+        upnp: false,
     };
     let keypair = Keypair::generate_ed25519();
     let mut peer1 = TestPeer::new(cfg.clone(), keypair.clone());
@@ -600,6 +669,12 @@ async fn outbound_peer_eviction() {
             max: 1000,
             interval: Duration::from_secs(1),
         },
+        inbound_sync_requests_rate_limit: RateLimit {
+            max: 1000,
+            interval: Duration::from_secs(1),
+        },
+        external_addresses: vec![],
+        upnp: false,
     };
 
     let mut peer = TestPeer::new(cfg.clone(), Keypair::generate_ed25519());
@@ -729,6 +804,12 @@ async fn inbound_peer_eviction() {
             max: 1000,
             interval: Duration::from_secs(1),
         },
+        inbound_sync_requests_rate_limit: RateLimit {
+            max: 1000,
+            interval: Duration::from_secs(1),
+        },
+        external_addresses: vec![],
+        upnp: false,
     };
 
     let mut peer = TestPeer::new(cfg.clone(), Keypair::generate_ed25519());
@@ -814,6 +895,12 @@ async fn evicted_peer_reconnection() {
             max: 1000,
             interval: Duration::from_secs(1),
         },
+        inbound_sync_requests_rate_limit: RateLimit {
+            max: 1000,
+            interval: Duration::from_secs(1),
+        },
+        external_addresses: vec![],
+        upnp: false,
     };
 
     let mut peer1 = TestPeer::new(cfg.clone(), Keypair::generate_ed25519());
@@ -905,6 +992,12 @@ async fn ip_whitelist() {
             max: 1000,
             interval: Duration::from_secs(1),
         },
+        inbound_sync_requests_rate_limit: RateLimit {
+            max: 1000,
+            interval: Duration::from_secs(1),
+        },
+        external_addresses: vec![],
+        upnp: false,
     };
     let mut peer1 = TestPeer::new(cfg.clone(), Keypair::generate_ed25519());
     let peer2 = TestPeer::new(cfg.clone(), Keypair::generate_ed25519());
@@ -938,6 +1031,12 @@ async fn ip_whitelist() {
             max: 1000,
             interval: Duration::from_secs(1),
         },
+        inbound_sync_requests_rate_limit: RateLimit {
+            max: 1000,
+            interval: Duration::from_secs(1),
+        },
+        external_addresses: vec![],
+        upnp: false,
     };
     let mut peer3 = TestPeer::new(cfg, Keypair::generate_ed25519());
 
@@ -972,6 +1071,12 @@ async fn rate_limit() {
             max: 2,
             interval: RATE_LIMIT_INTERVAL,
         },
+        inbound_sync_requests_rate_limit: RateLimit {
+            max: 1000,
+            interval: Duration::from_secs(1),
+        },
+        external_addresses: vec![],
+        upnp: false,
     };
 
     let mut peer1 = TestPeer::new(cfg.clone(), Keypair::generate_ed25519());
@@ -1004,6 +1109,70 @@ async fn rate_limit() {
     assert!(result.is_err());
 }
 
+/// Check that inbound sync requests get rate limited.
+#[test_log::test(tokio::test)]
+async fn sync_request_rate_limit() {
+    let cfg = Config {
+        direct_connection_timeout: Duration::from_secs(0),
+        relay_connection_timeout: Duration::from_secs(0),
+        max_inbound_direct_peers: 10,
+        max_inbound_relayed_peers: 10,
+        max_outbound_peers: 10,
+        low_watermark: 10,
+        ip_whitelist: vec!["::/0".parse().unwrap(), "0.0.0.0/0".parse().unwrap()],
+        bootstrap: Default::default(),
+        eviction_timeout: Duration::from_secs(15 * 60),
+        inbound_connections_rate_limit: RateLimit {
+            max: 1000,
+            interval: Duration::from_secs(1),
+        },
+        inbound_sync_requests_rate_limit: RateLimit {
+            max: 2,
+            interval: Duration::from_secs(60),
+        },
+        external_addresses: vec![],
+        upnp: false,
+    };
+
+    let mut server = TestPeer::new(cfg, Keypair::generate_ed25519());
+    let client = TestPeer::default();
+
+    let server_addr = server.start_listening().await.unwrap();
+    client
+        .client
+        .dial(server.peer_id, server_addr)
+        .await
+        .unwrap();
+
+    let mut requests = filter_events(server.event_receiver, |event| match event {
+        Event::InboundHeadersSyncRequest { channel, .. } => Some(channel),
+        _ => None,
+    });
+    consume_events(client.event_receiver);
+
+    // The first two requests are served.
+    for _ in 0..2 {
+        let mut rx = client
+            .client
+            .send_headers_sync_request(server.peer_id, Faker.fake())
+            .await
+            .unwrap();
+        let mut tx = requests.recv().await.unwrap();
+        tx.send(BlockHeadersResponse::Fin).await.unwrap();
+        assert_eq!(rx.next().await, Some(BlockHeadersResponse::Fin));
+    }
+
+    // The third one is not.
+    if let Ok(mut rx) = client
+        .client
+        .send_headers_sync_request(server.peer_id, Faker.fake())
+        .await
+    {
+        assert_eq!(rx.next().await, None);
+    }
+    assert!(requests.try_recv().is_err());
+}
+
 #[rstest]
 #[case::server_to_client(server_to_client().await)]
 #[case::client_to_server(client_to_server().await)]
@@ -1062,6 +1231,42 @@ async fn subscription_and_propagation(#[case] peers: (TestPeer, TestPeer)) {
     assert_eq!(msg, expected);
 }
 
+#[rstest]
+#[case::server_to_client(server_to_client().await)]
+#[case::client_to_server(client_to_server().await)]
+#[test_log::test(tokio::test)]
+async fn transaction_propagation(#[case] peers: (TestPeer, TestPeer)) {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let (peer1, peer2) = peers;
+
+    let mut peer2_subscribed_to_peer1 = filter_events(peer1.event_receiver, |event| match event {
+        Event::Test(TestEvent::Subscribed { .. }) => Some(()),
+        _ => None,
+    });
+
+    let mut propagated_to_peer2 = filter_events(peer2.event_receiver, |event| match event {
+        Event::TransactionPropagation { transaction, .. } => Some(transaction),
+        _ => None,
+    });
+
+    let topic = crate::transactions_topic(ChainId::GOERLI_TESTNET);
+
+    peer2.client.subscribe_topic(&topic).await.unwrap();
+    peer2_subscribed_to_peer1.recv().await;
+
+    let expected = Faker.fake::<Transaction>();
+
+    peer1
+        .client
+        .publish_transaction(&topic, expected.clone())
+        .await
+        .unwrap();
+
+    let msg = propagated_to_peer2.recv().await.unwrap();
+
+    assert_eq!(msg, expected);
+}
+
 /// Defines a sync test case named [`$test_name`], where there are 2 peers:
 /// - peer2 sends a request to peer1
 /// - peer1 responds with a random number of responses
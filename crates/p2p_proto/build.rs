@@ -8,6 +8,7 @@ fn main() -> Result<()> {
             "proto/event.proto",
             "proto/header.proto",
             "proto/receipt.proto",
+            "proto/snapshot.proto",
             "proto/state.proto",
             "proto/transaction.proto",
         ],
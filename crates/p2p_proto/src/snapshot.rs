@@ -0,0 +1,182 @@
+use std::fmt::Debug;
+
+use crate::common::{Address, Hash};
+use crate::state::ContractStoredValue;
+use crate::{proto, proto_field, ToProtobuf, TryFromProtobuf};
+use fake::Dummy;
+use pathfinder_crypto::Felt;
+
+#[derive(Debug, Clone, PartialEq, Eq, Dummy)]
+pub enum PatriciaNode {
+    Edge {
+        length: u32,
+        path: Felt,
+        child: Felt,
+    },
+    Binary {
+        left: Felt,
+        right: Felt,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, ToProtobuf, TryFromProtobuf, Dummy)]
+#[protobuf(name = "crate::proto::snapshot::ContractState")]
+pub struct ContractState {
+    pub address: Address,
+    pub class_hash: Hash,
+    pub storage_root: Hash,
+    pub nonce: Felt,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, ToProtobuf, TryFromProtobuf, Dummy)]
+#[protobuf(name = "crate::proto::snapshot::ClassLeaf")]
+pub struct ClassLeaf {
+    pub class_hash: Hash,
+    pub compiled_class_hash: Hash,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ToProtobuf, TryFromProtobuf, Dummy)]
+#[protobuf(name = "crate::proto::snapshot::ContractStorageRange")]
+pub struct ContractStorageRange {
+    pub contract: Address,
+    pub start: Felt,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Dummy)]
+pub enum SnapshotRange {
+    Contracts(Address),
+    Storage(ContractStorageRange),
+    Classes(Hash),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ToProtobuf, TryFromProtobuf, Dummy)]
+#[protobuf(name = "crate::proto::snapshot::SnapshotRequest")]
+pub struct SnapshotRequest {
+    pub block_number: u64,
+    pub range: SnapshotRange,
+    pub limit: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, ToProtobuf, TryFromProtobuf, Dummy)]
+#[protobuf(name = "crate::proto::snapshot::SnapshotProof")]
+pub struct SnapshotProof {
+    pub storage_commitment: Hash,
+    pub class_commitment: Hash,
+    pub start: Vec<PatriciaNode>,
+    pub end: Vec<PatriciaNode>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Dummy)]
+pub enum SnapshotResponse {
+    Contract(ContractState),
+    Storage(ContractStoredValue),
+    Class(ClassLeaf),
+    Proof(SnapshotProof),
+    #[default]
+    Fin,
+}
+
+impl ToProtobuf<proto::snapshot::PatriciaNode> for PatriciaNode {
+    fn to_protobuf(self) -> proto::snapshot::PatriciaNode {
+        use proto::snapshot::patricia_node::{Binary, Edge, Node};
+        proto::snapshot::PatriciaNode {
+            node: Some(match self {
+                Self::Edge {
+                    length,
+                    path,
+                    child,
+                } => Node::Edge(Edge {
+                    length,
+                    path: Some(path.to_protobuf()),
+                    child: Some(child.to_protobuf()),
+                }),
+                Self::Binary { left, right } => Node::Binary(Binary {
+                    left: Some(left.to_protobuf()),
+                    right: Some(right.to_protobuf()),
+                }),
+            }),
+        }
+    }
+}
+
+impl TryFromProtobuf<proto::snapshot::PatriciaNode> for PatriciaNode {
+    fn try_from_protobuf(
+        input: proto::snapshot::PatriciaNode,
+        field_name: &'static str,
+    ) -> Result<Self, std::io::Error> {
+        use proto::snapshot::patricia_node::Node;
+        match proto_field(input.node, field_name)? {
+            Node::Edge(edge) => Ok(Self::Edge {
+                length: edge.length,
+                path: TryFromProtobuf::try_from_protobuf(edge.path, field_name)?,
+                child: TryFromProtobuf::try_from_protobuf(edge.child, field_name)?,
+            }),
+            Node::Binary(binary) => Ok(Self::Binary {
+                left: TryFromProtobuf::try_from_protobuf(binary.left, field_name)?,
+                right: TryFromProtobuf::try_from_protobuf(binary.right, field_name)?,
+            }),
+        }
+    }
+}
+
+impl ToProtobuf<proto::snapshot::snapshot_request::Range> for SnapshotRange {
+    fn to_protobuf(self) -> proto::snapshot::snapshot_request::Range {
+        use proto::snapshot::snapshot_request::Range::{Classes, Contracts, Storage};
+        match self {
+            Self::Contracts(start) => Contracts(start.to_protobuf()),
+            Self::Storage(range) => Storage(range.to_protobuf()),
+            Self::Classes(start) => Classes(start.to_protobuf()),
+        }
+    }
+}
+
+impl TryFromProtobuf<proto::snapshot::snapshot_request::Range> for SnapshotRange {
+    fn try_from_protobuf(
+        input: proto::snapshot::snapshot_request::Range,
+        field_name: &'static str,
+    ) -> Result<Self, std::io::Error> {
+        use proto::snapshot::snapshot_request::Range::{Classes, Contracts, Storage};
+        Ok(match input {
+            Contracts(start) => Self::Contracts(Address::try_from_protobuf(start, field_name)?),
+            Storage(range) => {
+                Self::Storage(ContractStorageRange::try_from_protobuf(range, field_name)?)
+            }
+            Classes(start) => Self::Classes(Hash::try_from_protobuf(start, field_name)?),
+        })
+    }
+}
+
+impl ToProtobuf<proto::snapshot::SnapshotResponse> for SnapshotResponse {
+    fn to_protobuf(self) -> proto::snapshot::SnapshotResponse {
+        use proto::snapshot::snapshot_response::SnapshotMessage::{
+            Class, Contract, Fin, Proof, Storage,
+        };
+        proto::snapshot::SnapshotResponse {
+            snapshot_message: Some(match self {
+                Self::Contract(contract) => Contract(contract.to_protobuf()),
+                Self::Storage(value) => Storage(value.to_protobuf()),
+                Self::Class(class) => Class(class.to_protobuf()),
+                Self::Proof(proof) => Proof(proof.to_protobuf()),
+                Self::Fin => Fin(proto::common::Fin {}),
+            }),
+        }
+    }
+}
+
+impl TryFromProtobuf<proto::snapshot::SnapshotResponse> for SnapshotResponse {
+    fn try_from_protobuf(
+        input: proto::snapshot::SnapshotResponse,
+        field_name: &'static str,
+    ) -> Result<Self, std::io::Error> {
+        use proto::snapshot::snapshot_response::SnapshotMessage::{
+            Class, Contract, Fin, Proof, Storage,
+        };
+        match proto_field(input.snapshot_message, field_name)? {
+            Contract(x) => TryFromProtobuf::try_from_protobuf(x, field_name).map(Self::Contract),
+            Storage(x) => TryFromProtobuf::try_from_protobuf(x, field_name).map(Self::Storage),
+            Class(x) => TryFromProtobuf::try_from_protobuf(x, field_name).map(Self::Class),
+            Proof(x) => TryFromProtobuf::try_from_protobuf(x, field_name).map(Self::Proof),
+            Fin(_) => Ok(Self::Fin),
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use std::num::NonZeroU32;
+
+use anyhow::Context;
+use pathfinder_common::BlockNumber;
+use pathfinder_storage::{BloomFilterReport, JournalMode, Storage};
+
+/// Verify or rebuild the event Bloom filters in a pathfinder database.
+///
+/// Recomputes the Bloom filter of each block in the range from the events in its receipts
+/// and compares it with the stored filter. In `rebuild` mode missing and mismatched filters
+/// are replaced, which is required after the filter format changes or to repair databases
+/// migrated from older versions. In `verify` mode the database is left untouched.
+///
+/// The range defaults to the whole chain.
+///
+/// Usage:
+/// `cargo run --release -p pathfinder --example rebuild_bloom_filters verify ./mainnet.sqlite 0 1000`
+fn main() -> anyhow::Result<()> {
+    tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .compact()
+        .init();
+
+    const BATCH_SIZE: u64 = 1000;
+
+    let repair = match std::env::args().nth(1).unwrap().as_str() {
+        "verify" => false,
+        "rebuild" => true,
+        _ => panic!("Expected mode: verify/rebuild"),
+    };
+    let database_path = std::env::args().nth(2).unwrap();
+    let first_block = std::env::args()
+        .nth(3)
+        .map(|s| s.parse::<u64>())
+        .transpose()
+        .context("Parse first block number")?
+        .unwrap_or_default();
+
+    let storage = Storage::migrate(database_path.into(), JournalMode::WAL, 1)?
+        .create_pool(NonZeroU32::new(1).unwrap())?;
+    let mut db = storage
+        .connection()
+        .context("Opening database connection")?;
+
+    let latest_block = {
+        let tx = db.transaction()?;
+        tx.block_id(pathfinder_storage::BlockId::Latest)
+            .context("Fetching latest block number")?
+            .context("Latest block number does not exist")?
+            .0
+    };
+
+    let last_block = std::env::args()
+        .nth(4)
+        .map(|s| s.parse::<u64>())
+        .transpose()
+        .context("Parse last block number")?
+        .unwrap_or(latest_block.get())
+        .min(latest_block.get());
+
+    tracing::info!(%first_block, %last_block, %repair, "Checking Bloom filters");
+
+    let mut total = BloomFilterReport::default();
+
+    for batch_start in (first_block..=last_block).step_by(BATCH_SIZE as usize) {
+        let batch_end = (batch_start + BATCH_SIZE - 1).min(last_block);
+        let from = BlockNumber::new_or_panic(batch_start);
+        let to = BlockNumber::new_or_panic(batch_end);
+
+        let tx = db.transaction()?;
+        let report = if repair {
+            let report = tx.rebuild_bloom_filters(from, to)?;
+            tx.commit().context("Committing database transaction")?;
+            report
+        } else {
+            tx.verify_bloom_filters(from, to)?
+        };
+
+        if report.missing > 0 || report.mismatched > 0 {
+            tracing::warn!(%from, %to, missing=%report.missing, mismatched=%report.mismatched, "Invalid Bloom filters found");
+        }
+
+        total.checked += report.checked;
+        total.missing += report.missing;
+        total.mismatched += report.mismatched;
+
+        tracing::info!(block=%to, "Processed blocks");
+    }
+
+    tracing::info!(checked=%total.checked, missing=%total.missing, mismatched=%total.mismatched, "Done");
+
+    Ok(())
+}
@@ -0,0 +1,150 @@
+//! Periodic backups of the database.
+//!
+//! Backups are written by [run] using SQLite's online backup API, so sync and RPC keep running
+//! while they are taken. Each backup is first written to a temporary file and renamed once
+//! complete, so that the backup directory only ever contains complete backups.
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use anyhow::Context;
+use pathfinder_storage::Storage;
+
+const METRIC_DURATION: &str = "storage_backup_duration_seconds";
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// The directory the backups are written to.
+    pub directory: PathBuf,
+    /// The time between backups.
+    pub interval: Duration,
+    /// The number of backups which are kept. Older backups are deleted.
+    pub retention: NonZeroUsize,
+}
+
+/// Backs up `storage` forever, starting once the first interval is up.
+pub async fn run(storage: Storage, config: Config) {
+    let prefix = backup_prefix(storage.path());
+    let start = tokio::time::Instant::now() + config.interval;
+    let mut interval = tokio::time::interval_at(start, config.interval);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    loop {
+        interval.tick().await;
+
+        let storage = storage.clone();
+        let config = config.clone();
+        let prefix = prefix.clone();
+        let started = std::time::Instant::now();
+        let result = tokio::task::spawn_blocking(move || backup(&storage, &config, &prefix)).await;
+
+        match result {
+            Ok(Ok(path)) => {
+                let elapsed = started.elapsed();
+                metrics::histogram!(METRIC_DURATION, elapsed.as_secs_f64());
+                tracing::info!(path=%path.display(), ?elapsed, "Database backed up");
+            }
+            Ok(Err(error)) => tracing::warn!(?error, "Backing up database failed"),
+            Err(error) => tracing::warn!(%error, "Backing up database panicked"),
+        }
+    }
+}
+
+/// Backup files are named after the database file followed by the time of the backup, e.g.
+/// `mainnet-1700000000.sqlite`.
+fn backup_prefix(database: &Path) -> String {
+    let stem = database
+        .file_stem()
+        .map(|x| x.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "pathfinder".to_owned());
+    format!("{stem}-")
+}
+
+fn backup(storage: &Storage, config: &Config, prefix: &str) -> anyhow::Result<PathBuf> {
+    std::fs::create_dir_all(&config.directory).context("Creating backup directory")?;
+
+    let timestamp = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .context("System time is before the UNIX epoch")?
+        .as_secs();
+    let path = config.directory.join(format!("{prefix}{timestamp}.sqlite"));
+    let partial = config
+        .directory
+        .join(format!("{prefix}{timestamp}.sqlite.partial"));
+
+    storage.backup(&partial)?;
+    std::fs::rename(&partial, &path).context("Renaming backup")?;
+
+    prune(&config.directory, prefix, config.retention).context("Deleting old backups")?;
+
+    Ok(path)
+}
+
+/// Deletes all but the `retention` newest backups in `directory`.
+fn prune(directory: &Path, prefix: &str, retention: NonZeroUsize) -> anyhow::Result<()> {
+    let mut backups = Vec::new();
+    for entry in std::fs::read_dir(directory).context("Reading backup directory")? {
+        let entry = entry.context("Reading backup directory entry")?;
+        let name = entry.file_name();
+        let timestamp = name
+            .to_str()
+            .and_then(|x| x.strip_prefix(prefix))
+            .and_then(|x| x.strip_suffix(".sqlite"))
+            .and_then(|x| x.parse::<u64>().ok());
+        if let Some(timestamp) = timestamp {
+            backups.push((timestamp, entry.path()));
+        }
+    }
+
+    backups.sort_unstable_by(|a, b| b.0.cmp(&a.0));
+    for (_, path) in backups.into_iter().skip(retention.get()) {
+        std::fs::remove_file(&path)
+            .with_context(|| format!("Deleting backup {}", path.display()))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prune_keeps_newest_backups() {
+        let directory = tempfile::TempDir::new().unwrap();
+        let files = [
+            "mainnet-100.sqlite",
+            "mainnet-20.sqlite",
+            "mainnet-300.sqlite",
+            "mainnet-400.sqlite.partial",
+            "testnet-10.sqlite",
+            "notes.txt",
+        ];
+        for file in files {
+            std::fs::write(directory.path().join(file), b"").unwrap();
+        }
+
+        prune(directory.path(), "mainnet-", NonZeroUsize::new(2).unwrap()).unwrap();
+
+        let mut remaining = std::fs::read_dir(directory.path())
+            .unwrap()
+            .map(|x| x.unwrap().file_name().into_string().unwrap())
+            .collect::<Vec<_>>();
+        remaining.sort();
+        assert_eq!(
+            remaining,
+            vec![
+                "mainnet-100.sqlite",
+                "mainnet-300.sqlite",
+                "mainnet-400.sqlite.partial",
+                "notes.txt",
+                "testnet-10.sqlite",
+            ]
+        );
+    }
+
+    #[test]
+    fn backup_prefix_is_database_name() {
+        assert_eq!(backup_prefix(Path::new("/data/mainnet.sqlite")), "mainnet-");
+    }
+}
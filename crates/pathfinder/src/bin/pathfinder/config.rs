@@ -3,8 +3,12 @@ use clap::{ArgAction, CommandFactory, Parser};
 use ipnet::IpNet;
 #[cfg(feature = "p2p")]
 use p2p::libp2p::Multiaddr;
-use pathfinder_common::AllowedOrigins;
+use pathfinder_common::{AllowedOrigins, BlockHash, BlockNumber};
+use pathfinder_crypto::Felt;
+use pathfinder_lib::devnet::fork::{Config as ForkConfig, Source as ForkSource};
+use pathfinder_lib::state::l2::TrustedBlock;
 use pathfinder_storage::JournalMode;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
 use reqwest::Url;
 use std::collections::HashSet;
 use std::net::SocketAddr;
@@ -21,7 +25,12 @@ use pathfinder_common::consts::VERGEN_GIT_DESCRIBE;
 #[command(
     about = "A Starknet node implemented by Equilibrium Labs. Submit bug reports and issues at https://github.com/eqlabs/pathfinder."
 )]
+// The node's options, including the required ones, do not apply to the subcommands.
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<CliCommand>,
+
     #[arg(
         long,
         value_name = "DIR", 
@@ -50,8 +59,9 @@ Examples:
         value_name = "HTTP(s) URL",
         value_hint = clap::ValueHint::Url,
         env = "PATHFINDER_ETHEREUM_API_URL", 
+        required = true,
     )]
-    ethereum_url: Url,
+    ethereum_url: Option<Url>,
 
     #[arg(
         long = "http-rpc",
@@ -129,6 +139,70 @@ Examples:
     )]
     poll_interval: std::num::NonZeroU64,
 
+    #[arg(
+        long = "sync.block-prefetch",
+        long_help = "The number of upcoming blocks, along with their state updates and classes, \
+            to download concurrently while catching up with the feeder gateway. \
+            Higher values speed up syncing at the cost of more requests in flight. \
+            Setting this to 0 disables prefetching.",
+        value_name = "BLOCKS",
+        default_value = "4",
+        env = "PATHFINDER_SYNC_BLOCK_PREFETCH"
+    )]
+    block_prefetch: usize,
+
+    #[arg(
+        long = "sync.trusted-block",
+        long_help = r"Trust the given block, identified by its number and hash, instead of verifying the block and transaction hashes of the chain leading up to it. The transactions, receipts and events of the preceding blocks are not stored until they are backfilled, see --sync.backfill-rate. Syncing stops if the block at the given height has a different hash.
+
+This is not a checkpoint sync: sync still starts at genesis, and every preceding block is downloaded in full and its state update applied to the state tries. It only saves the verification and storage of the transactions of these blocks.
+
+Example:
+    '600000,0x1234...'",
+        value_name = "NUMBER,HASH",
+        env = "PATHFINDER_SYNC_TRUSTED_BLOCK"
+    )]
+    sync_trusted_block: Option<String>,
+
+    #[arg(
+        long = "sync.backfill-rate",
+        long_help = "The maximum number of blocks per second for which the transactions, \
+            receipts and events are backfilled in the background after syncing with \
+            `--sync.trusted-block`. Backfilling proceeds from the trusted block towards genesis. \
+            Setting this to 0 disables backfilling.",
+        value_name = "BLOCKS",
+        default_value = "10",
+        env = "PATHFINDER_SYNC_BACKFILL_RATE"
+    )]
+    backfill_rate: u32,
+
+    #[arg(
+        long = "sync.halt-on-l1-state-root-mismatch",
+        long_help = "Stop syncing if the state root recorded on the Starknet core contract for a \
+                     block differs from the locally computed one. Mismatches are always logged \
+                     and counted by the `sync_l1_state_root_mismatch_total` metric, and usually \
+                     indicate a corrupted database.",
+        default_value = "false",
+        action = ArgAction::Set,
+        env = "PATHFINDER_SYNC_HALT_ON_L1_STATE_ROOT_MISMATCH"
+    )]
+    halt_on_l1_state_root_mismatch: bool,
+
+    #[arg(
+        long = "sync.stall-timeout",
+        long_help = "Restart the L1 or L2 sync process if it makes no progress for this many \
+            seconds, e.g. because a request to the gateway or Ethereum hangs. The last activity \
+            of the process and the latest block are logged before it is restarted, and restarts \
+            are counted by the `sync_stage_restarts_total` metric. This should exceed the poll \
+            interval. The process storing the synced data cannot be interrupted, so it is not \
+            restarted. Instead, its stalls are logged in the same way and counted by the \
+            `sync_stage_stalls_total` metric. Setting this to 0 disables the checks.",
+        value_name = "SECONDS",
+        default_value = "600",
+        env = "PATHFINDER_SYNC_STALL_TIMEOUT"
+    )]
+    sync_stall_timeout: u64,
+
     #[arg(
         long = "color",
         long_help = "This flag controls when to use colors in the output logs.",
@@ -183,6 +257,135 @@ This should only be enabled for debugging purposes as it adds substantial proces
     )]
     rpc_batch_concurrency_limit: NonZeroUsize,
 
+    #[arg(
+        long = "rpc.batch-size-limit",
+        long_help = "The maximum number of requests in a single batch request. Larger batches are rejected.",
+        env = "PATHFINDER_RPC_BATCH_SIZE_LIMIT",
+        default_value = "1000"
+    )]
+    rpc_batch_size_limit: NonZeroUsize,
+
+    #[arg(
+        long = "rpc.max-request-body-size",
+        long_help = "The maximum size of a request body in bytes. Larger requests are rejected.",
+        value_name = "BYTES",
+        env = "PATHFINDER_RPC_MAX_REQUEST_BODY_SIZE",
+        default_value = "10485760"
+    )]
+    rpc_max_request_body_size: NonZeroUsize,
+
+    #[arg(
+        long = "rpc.request-timeout",
+        long_help = "The time in seconds after which a request, including reading its body, is aborted.",
+        value_name = "SECONDS",
+        env = "PATHFINDER_RPC_REQUEST_TIMEOUT",
+        default_value = "120"
+    )]
+    rpc_request_timeout: std::num::NonZeroU64,
+
+    #[arg(
+        long = "rpc.execution-max-steps",
+        long_help = "The maximum number of Cairo steps `starknet_call`, `starknet_estimateFee`, \
+                     `starknet_estimateMessageFee` and `starknet_simulateTransactions` may execute \
+                     per call or transaction. This can only lower the step limits of the network.",
+        value_name = "STEPS",
+        env = "PATHFINDER_RPC_EXECUTION_MAX_STEPS"
+    )]
+    rpc_execution_max_steps: Option<u32>,
+
+    #[arg(
+        long = "rpc.execution-timeout",
+        long_help = "The time in seconds after which `starknet_call`, `starknet_estimateFee`, \
+                     `starknet_estimateMessageFee` and `starknet_simulateTransactions` fail with an \
+                     execution timeout error. Execution itself cannot be interrupted, so this \
+                     should be combined with `--rpc.execution-max-steps`.",
+        value_name = "SECONDS",
+        env = "PATHFINDER_RPC_EXECUTION_TIMEOUT"
+    )]
+    rpc_execution_timeout: Option<std::num::NonZeroU64>,
+
+    #[arg(
+        long = "rpc.execution-queue-size",
+        long_help = "The maximum number of calls, fee estimations, simulations and traces which \
+                     are queued or executing at once. Each of these methods may occupy at most \
+                     half of the queue. Further requests wait until there is room in the queue.",
+        value_name = "SIZE",
+        env = "PATHFINDER_RPC_EXECUTION_QUEUE_SIZE",
+        default_value = "1024"
+    )]
+    rpc_execution_queue_size: NonZeroUsize,
+
+    #[arg(
+        long = "rpc.erc20-balances",
+        long_help = "Enable the pathfinder_getErc20Balances RPC method which executes the `balanceOf` \
+                     entry point of many tokens in a single request. Each request occupies a slot \
+                     of the execution queue for all of its tokens.",
+        default_value = "false",
+        action = ArgAction::Set,
+        env = "PATHFINDER_RPC_ERC20_BALANCES"
+    )]
+    rpc_erc20_balances: bool,
+
+    #[arg(
+        long = "rpc.table-sizes",
+        long_help = "Allow pathfinder_getStateStats to return the size of each database table. \
+                     Computing these reads the whole database, so only enable this on nodes \
+                     whose RPC endpoint is not exposed to untrusted users.",
+        default_value = "false",
+        action = ArgAction::Set,
+        env = "PATHFINDER_RPC_TABLE_SIZES"
+    )]
+    rpc_table_sizes: bool,
+
+    #[arg(
+        long = "rpc.l1-gas-price-floor",
+        long_help = "Estimate fees for the pending block with at least the latest Ethereum base \
+                     fee and blob base fee seen by L1 sync, instead of only the gas prices the \
+                     sequencer set for the block. The sequencer's prices can lag behind Ethereum \
+                     gas price spikes, so that transactions with estimated fees are rejected. \
+                     STRK gas prices are raised by the same ratio.",
+        default_value = "false",
+        action = ArgAction::Set,
+        env = "PATHFINDER_RPC_L1_GAS_PRICE_FLOOR"
+    )]
+    rpc_l1_gas_price_floor: bool,
+
+    #[cfg(feature = "rpc-query")]
+    #[arg(
+        long = "rpc.query",
+        long_help = "Enable the pathfinder_query RPC method which runs read-only SQL queries \
+                     against the tables holding chain data. Queries can be expensive, so only \
+                     enable this on nodes whose RPC endpoint is not exposed to untrusted users.",
+        default_value = "false",
+        action = ArgAction::Set,
+        env = "PATHFINDER_RPC_QUERY"
+    )]
+    rpc_query: bool,
+
+    #[arg(
+        long = "rpc.trace-profiles",
+        long_help = "Add a non-standard `profile` to each function invocation of the v0.6 and \
+                     v0.7 traces, containing the execution resources used by the call excluding \
+                     its inner calls, and the number of calls, deploys, events and L2 to L1 \
+                     messages made by it. Clients which reject unknown fields must not enable \
+                     this.",
+        default_value = "false",
+        action = ArgAction::Set,
+        env = "PATHFINDER_RPC_TRACE_PROFILES"
+    )]
+    rpc_trace_profiles: bool,
+
+    #[arg(
+        long = "rpc.max-signature-length",
+        long_help = "The maximum number of signature elements of deploy account transactions. \
+                     Transactions with longer signatures are rejected with `Account validation \
+                     failed` without contacting the gateway. Unlimited by default, leaving the \
+                     limit to the gateway.",
+        value_name = "LENGTH",
+        env = "PATHFINDER_RPC_MAX_SIGNATURE_LENGTH"
+    )]
+    rpc_max_signature_length: Option<NonZeroUsize>,
+
     #[arg(
         long = "sync.enable",
         long_help = "Enable syncing the chain",
@@ -192,6 +395,34 @@ This should only be enabled for debugging purposes as it adds substantial proces
     )]
     is_sync_enabled: bool,
 
+    #[arg(
+        long = "devnet.block-time",
+        long_help = "Run a devnet instead of syncing the chain. Transactions submitted through \
+            the RPC API are executed by this node and sealed into a new block at this interval, \
+            in seconds, on top of the latest block in the database. Blocks without transactions \
+            are skipped. Only applies to the primary network.",
+        value_name = "SECONDS",
+        env = "PATHFINDER_DEVNET_BLOCK_TIME"
+    )]
+    devnet_block_time: Option<std::num::NonZeroU64>,
+
+    #[arg(
+        long = "devnet.fork-from",
+        long_help = r"Fork the devnet from a block of another network instead of building on the latest block in the database. The state of the network at that block is read from the JSON-RPC API at the given URL, or from the database at the given path. Only the blocks produced by the devnet are stored, so it needs an empty database of its own. Requires '--devnet.block-time'.
+
+The environment variable takes both values separated by a space.
+
+Examples:
+    'https://node.example.com/rpc/v0_7 600000'
+    '/data/mainnet.sqlite 600000'",
+        num_args = 2,
+        value_names = ["SOURCE", "BLOCK"],
+        value_delimiter = ' ',
+        requires = "devnet_block_time",
+        env = "PATHFINDER_DEVNET_FORK_FROM"
+    )]
+    devnet_fork_from: Option<Vec<String>>,
+
     #[arg(
         long = "rpc.enable",
         long_help = "Enable serving RPC API",
@@ -209,6 +440,20 @@ This should only be enabled for debugging purposes as it adds substantial proces
     )]
     gateway_api_key: Option<String>,
 
+    #[arg(
+        long = "gateway.request-header",
+        value_name = "NAME: VALUE",
+        long_help = r"Add a header to each Starknet feeder gateway and gateway request. May be repeated.
+
+This can be used to send keys issued by infrastructure providers.
+
+Example:
+    'X-Api-Key: <KEY>'",
+        action = ArgAction::Append,
+        env = "PATHFINDER_GATEWAY_REQUEST_HEADERS"
+    )]
+    gateway_headers: Vec<String>,
+
     #[arg(
         long = "gateway.request-timeout",
         value_name = "Seconds",
@@ -218,6 +463,17 @@ This should only be enabled for debugging purposes as it adds substantial proces
     )]
     gateway_timeout: std::num::NonZeroU64,
 
+    #[arg(
+        long = "gateway.log-unknown-fields",
+        long_help = "Log the fields of feeder gateway block and state update replies which are \
+                     ignored because they are unknown to pathfinder, e.g. because they were \
+                     introduced by a newer Starknet version.",
+        default_value = "false",
+        action = ArgAction::Set,
+        env = "PATHFINDER_GATEWAY_LOG_UNKNOWN_FIELDS"
+    )]
+    gateway_log_unknown_fields: bool,
+
     #[arg(
         long = "storage.event-bloom-filter-cache-size",
         long_help = "The number of blocks whose event bloom filters are cached in memory. \
@@ -228,6 +484,162 @@ This should only be enabled for debugging purposes as it adds substantial proces
     )]
     event_bloom_filter_cache_size: std::num::NonZeroUsize,
 
+    #[arg(
+        long = "storage.persist-bloom-filter-cache",
+        long_help = "Save the event bloom filter cache to a file next to the database on \
+            shutdown, and restore it in the background on startup. This spares the first event \
+            related RPC queries after a restart from loading the filters from the database. The \
+            file takes up to 2 KiB per cached filter.",
+        default_value = "false",
+        action = ArgAction::Set,
+        env = "PATHFINDER_STORAGE_PERSIST_BLOOM_FILTER_CACHE"
+    )]
+    persist_bloom_filter_cache: bool,
+
+    #[arg(
+        long = "storage.trie-layout",
+        long_help = "How Merkle trie nodes are stored. `indexed` stores the new nodes of each \
+            block separately. `hash-keyed` looks up each node by its hash first, so identical \
+            subtrees are stored only once across blocks and contracts, at the cost of slower \
+            writes. This can only be chosen for a new database. Defaults to the layout of an \
+            existing database, or `indexed` for a new one.",
+        value_enum,
+        env = "PATHFINDER_STORAGE_TRIE_LAYOUT"
+    )]
+    trie_layout: Option<TrieLayout>,
+
+    #[arg(
+        long = "storage.wal-checkpoint-interval",
+        long_help = "Checkpoint the SQLite WAL in the background at this interval, in seconds, \
+            instead of during the commit which grows the WAL past its limit. This avoids stalls \
+            of several seconds when syncing large blocks. Only applies with `--sqlite-wal`.",
+        value_name = "SECONDS",
+        env = "PATHFINDER_STORAGE_WAL_CHECKPOINT_INTERVAL"
+    )]
+    wal_checkpoint_interval: Option<std::num::NonZeroU64>,
+
+    #[arg(
+        long = "storage.wal-checkpoint-size",
+        long_help = "With `--storage.wal-checkpoint-interval`, the WAL size in MiB above which \
+            the WAL is checkpointed and truncated before the interval is up. Truncating waits \
+            for readers to finish with the WAL, blocking writers meanwhile.",
+        value_name = "MiB",
+        env = "PATHFINDER_STORAGE_WAL_CHECKPOINT_SIZE",
+        default_value = "256"
+    )]
+    wal_checkpoint_size: std::num::NonZeroU64,
+
+    #[cfg(feature = "sqlcipher")]
+    #[arg(
+        long = "storage.encryption-key-file",
+        long_help = "Encrypt the database with SQLCipher using the passphrase in this file. A \
+            trailing newline is ignored. A new database is created encrypted, while an existing \
+            database must have been created with the same passphrase.",
+        value_name = "PATH",
+        env = "PATHFINDER_STORAGE_ENCRYPTION_KEY_FILE",
+        conflicts_with = "encryption_key"
+    )]
+    encryption_key_file: Option<PathBuf>,
+
+    #[cfg(feature = "sqlcipher")]
+    #[arg(
+        long = "storage.encryption-key",
+        long_help = "Encrypt the database with SQLCipher using this passphrase. Prefer setting \
+            it through the environment variable or `--storage.encryption-key-file`, since \
+            command line arguments are visible to other users of the system.",
+        value_name = "PASSPHRASE",
+        env = "PATHFINDER_STORAGE_ENCRYPTION_KEY",
+        hide_env_values = true
+    )]
+    encryption_key: Option<String>,
+
+    #[arg(
+        long = "storage.trie-directory",
+        long_help = "Keep the Merkle trie nodes in a separate database file in this directory, \
+            e.g. on a faster disk than the rest of the database. The file is named after the \
+            database, e.g. `mainnet-tries.sqlite`. An existing database's trie nodes are moved \
+            into the file on startup, which can take a long time. Once moved, the directory \
+            must always be given. In WAL mode SQLite does not commit to several database files \
+            atomically, so the file can end up ahead of or behind the main database if \
+            pathfinder is killed. This is repaired on startup by removing the trie nodes of \
+            blocks which were not stored, and rolling back blocks whose trie nodes are missing.",
+        value_name = "DIR",
+        env = "PATHFINDER_STORAGE_TRIE_DIRECTORY",
+        conflicts_with = "backup_directory"
+    )]
+    trie_directory: Option<PathBuf>,
+
+    #[arg(
+        long = "storage.transaction-directory",
+        long_help = "Keep the transactions, receipts and events in a separate database file in \
+            this directory, e.g. on a cheaper disk than the rest of the database. The file is \
+            named after the database, e.g. `mainnet-transactions.sqlite`. An existing \
+            database's transactions are moved into the file on startup, which can take a long \
+            time. Once moved, the directory must always be given. In WAL mode SQLite does not \
+            commit to several database files atomically, so the file can end up ahead of or \
+            behind the main database if pathfinder is killed. This is repaired on startup by \
+            removing the transactions of blocks which were not stored, and rolling back blocks \
+            whose transactions are missing.",
+        value_name = "DIR",
+        env = "PATHFINDER_STORAGE_TRANSACTION_DIRECTORY",
+        conflicts_with = "backup_directory"
+    )]
+    transaction_directory: Option<PathBuf>,
+
+    #[arg(
+        long = "storage.backup-directory",
+        long_help = "Periodically back up the database to this directory. Backups are taken \
+            using SQLite's online backup API and do not stop sync. They are most efficient with \
+            `--sqlite-wal`, as otherwise database writes wait for each backup to complete. \
+            Databases split across several files using `--storage.trie-directory` or \
+            `--storage.transaction-directory` cannot be backed up.",
+        value_name = "DIR",
+        env = "PATHFINDER_STORAGE_BACKUP_DIRECTORY"
+    )]
+    backup_directory: Option<PathBuf>,
+
+    #[arg(
+        long = "storage.backup-interval",
+        long_help = "With `--storage.backup-directory`, the time between backups in seconds.",
+        value_name = "SECONDS",
+        env = "PATHFINDER_STORAGE_BACKUP_INTERVAL",
+        default_value = "86400"
+    )]
+    backup_interval: std::num::NonZeroU64,
+
+    #[arg(
+        long = "storage.backup-retention",
+        long_help = "With `--storage.backup-directory`, the number of backups to keep. Older \
+            backups are deleted.",
+        value_name = "COUNT",
+        env = "PATHFINDER_STORAGE_BACKUP_RETENTION",
+        default_value = "3"
+    )]
+    backup_retention: std::num::NonZeroUsize,
+
+    #[arg(
+        long = "storage.slow-query-threshold",
+        long_help = "Log database statements taking at least this many milliseconds, along with \
+            the RPC method or sync task running them. Literals are redacted from the logged SQL. \
+            Statement durations are also recorded in the `storage_query_duration_seconds` \
+            metric.",
+        value_name = "MILLISECONDS",
+        env = "PATHFINDER_STORAGE_SLOW_QUERY_THRESHOLD"
+    )]
+    slow_query_threshold: Option<std::num::NonZeroU64>,
+
+    #[arg(
+        long = "db.migrate",
+        long_help = "Whether an existing database is migrated to the schema of this version on \
+            startup. With `off`, startup fails instead if the database needs to be migrated, so \
+            that production databases are only migrated on purpose, e.g. after taking a backup. \
+            New databases are always created.",
+        value_enum,
+        env = "PATHFINDER_DB_MIGRATE",
+        default_value = "auto"
+    )]
+    migration: Migration,
+
     #[arg(
         long = "rpc.get-events-max-blocks-to-scan",
         long_help = "The number of blocks to scan for events when querying for events. \
@@ -245,6 +657,126 @@ This should only be enabled for debugging purposes as it adds substantial proces
         default_value = "100000"
     )]
     get_events_max_uncached_bloom_filters_to_load: std::num::NonZeroUsize,
+
+    #[arg(
+        long = "rpc.get-events-max-page-size",
+        long_help = "The maximum number of events per page, i.e. `chunk_size`, of \
+            `starknet_getEvents`.",
+        env = "PATHFINDER_RPC_GET_EVENTS_MAX_PAGE_SIZE",
+        default_value = "1024"
+    )]
+    get_events_max_page_size: std::num::NonZeroUsize,
+
+    #[arg(
+        long = "rpc.get-events-max-keys-in-filter",
+        long_help = "The maximum number of keys in a `starknet_getEvents` filter. At most 16 keys \
+            are supported, since only the first 16 keys of events are indexed.",
+        env = "PATHFINDER_RPC_GET_EVENTS_MAX_KEYS_IN_FILTER",
+        default_value = "16",
+        value_parser = clap::value_parser!(u64)
+            .range(1..=pathfinder_storage::EVENT_KEY_FILTER_LIMIT as i64)
+    )]
+    get_events_max_keys_in_filter: u64,
+
+    #[arg(
+        long = "rpc.get-events-max-query-cost",
+        long_help = "The maximum estimated cost of a `starknet_getEvents` query, beyond which \
+            the query is rejected as too broad. The cost is the number of Bloom filters the \
+            query would check over all of its pages, which is the number of blocks in its range \
+            if it filters by address or keys. Queries without an address or keys are not \
+            limited. By default, queries are not limited by their cost.",
+        value_name = "COST",
+        env = "PATHFINDER_RPC_GET_EVENTS_MAX_QUERY_COST"
+    )]
+    get_events_max_query_cost: Option<std::num::NonZeroU64>,
+}
+
+#[derive(clap::Subcommand)]
+enum CliCommand {
+    /// Work with a pathfinder database without running the node.
+    #[command(subcommand)]
+    Db(DbCli),
+}
+
+#[derive(clap::Subcommand)]
+enum DbCli {
+    /// Export blocks, transactions, receipts or events as CSV or Parquet for analytics.
+    ///
+    /// The database is opened read-only, so it is never migrated or otherwise modified. It must
+    /// therefore already be at the schema version of this pathfinder version. All rows are
+    /// exported from a single consistent snapshot.
+    Export(ExportCli),
+}
+
+#[derive(clap::Args)]
+struct ExportCli {
+    #[arg(
+        long,
+        long_help = "The database file to export, e.g. `mainnet.sqlite`.",
+        value_name = "PATH",
+        value_hint = clap::ValueHint::FilePath
+    )]
+    database: PathBuf,
+
+    #[arg(
+        long = "storage.transaction-directory",
+        long_help = "The directory of the database's separate transaction file, if the node \
+            keeps its transactions, receipts and events in one. See the node option of the same \
+            name.",
+        value_name = "DIR",
+        value_hint = clap::ValueHint::DirPath
+    )]
+    transaction_directory: Option<PathBuf>,
+
+    #[arg(
+        long,
+        long_help = "The data to export. Blocks have one row per block header, transactions and \
+            receipts one row per transaction and events one row per event.",
+        value_enum
+    )]
+    table: ExportTable,
+
+    #[arg(
+        long,
+        long_help = r"The blocks to export, including both ends. Either end may be omitted, in which case the export starts at genesis or ends at the latest block. By default, the whole chain is exported.
+
+Examples:
+    '0..1000', '600000..', '..1000'",
+        value_name = "FIRST..LAST"
+    )]
+    range: Option<String>,
+
+    #[arg(
+        long,
+        long_help = "The output format. Felts are written as hex strings, and the keys and data \
+            of events as space separated hex strings.",
+        value_enum,
+        default_value = "csv"
+    )]
+    format: ExportFormat,
+
+    #[arg(
+        long,
+        long_help = "The file to write the export to, replacing any existing file. By default, \
+            the export is written to stdout.",
+        value_name = "PATH",
+        value_hint = clap::ValueHint::FilePath
+    )]
+    output: Option<PathBuf>,
+}
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq)]
+pub enum ExportTable {
+    Blocks,
+    Transactions,
+    Receipts,
+    Events,
+}
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    Csv,
+    Parquet,
 }
 
 #[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq)]
@@ -267,6 +799,53 @@ impl Color {
     }
 }
 
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq)]
+pub enum TrieLayout {
+    Indexed,
+    HashKeyed,
+}
+
+impl From<TrieLayout> for pathfinder_storage::TrieLayout {
+    fn from(value: TrieLayout) -> Self {
+        match value {
+            TrieLayout::Indexed => Self::Indexed,
+            TrieLayout::HashKeyed => Self::HashKeyed,
+        }
+    }
+}
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq)]
+pub enum Migration {
+    Auto,
+    Off,
+}
+
+impl From<Migration> for pathfinder_storage::Migration {
+    fn from(value: Migration) -> Self {
+        match value {
+            Migration::Auto => Self::Auto,
+            Migration::Off => Self::Off,
+        }
+    }
+}
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq)]
+pub enum DropPolicy {
+    DropOldest,
+    DropNewest,
+    Close,
+}
+
+impl From<DropPolicy> for pathfinder_rpc::context::DropPolicy {
+    fn from(value: DropPolicy) -> Self {
+        match value {
+            DropPolicy::DropOldest => Self::DropOldest,
+            DropPolicy::DropNewest => Self::DropNewest,
+            DropPolicy::Close => Self::Close,
+        }
+    }
+}
+
 #[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq)]
 pub enum RpcVersion {
     V04,
@@ -287,6 +866,31 @@ Note that 'custom' requires also setting the --gateway-url and --feeder-gateway-
     )]
     network: Option<Network>,
 
+    #[arg(
+        long = "network.additional-config",
+        long_help = r#"Run additional Starknet networks in the same process, as listed in this JSON file.
+
+Each additional network gets its own database in the data directory, its own Ethereum endpoint, its own HTTP-RPC listening address and optionally its own Ethereum password and gateway API key. The file is used instead of command line arguments so that these secrets are not visible to other users of the system, so make it readable only by the pathfinder user.
+
+The gateway API key and the Ethereum password of the primary network are not used for additional networks. Metrics of an additional network are labelled with its network instead of the primary one. Custom networks are not supported.
+
+All other storage, RPC, gateway and monitoring options are shared with the primary network. Split database directories and backups hold a separate file per network. The following apply to the primary network only: --sync.trusted-block, --gateway-url, --feeder-gateway-url, --chain-id, the --devnet.* options and the --p2p.* options.
+
+Format:
+    [
+        {
+            "network": "sepolia-testnet",
+            "ethereum.url": "https://sepolia.infura.io/v3/<PROJECT_ID>",
+            "http-rpc": "127.0.0.1:9546",
+            "ethereum.password": "<optional>",
+            "gateway-api-key": "<optional>"
+        }
+    ]"#,
+        value_name = "PATH",
+        env = "PATHFINDER_NETWORK_ADDITIONAL_CONFIG"
+    )]
+    additional_config: Option<PathBuf>,
+
     #[arg(
         long,
         long_help = "Set a custom Starknet chain ID (e.g. SN_GOERLI)",
@@ -364,6 +968,27 @@ Example:
     )]
     predefined_peers: Vec<String>,
 
+    #[arg(
+        long = "p2p.external-address",
+        long_help = r#"Comma separated list of multiaddresses on which this node can be reached by other peers, such as a port forwarded on a router. If not provided, the addresses observed by peers are advertised, which are usually not reachable if the node is behind NAT.
+
+Example:
+    '/ip4/203.0.113.1/tcp/20002'"#,
+        value_name = "MULTIADDRESS_LIST",
+        value_delimiter = ',',
+        env = "PATHFINDER_P2P_EXTERNAL_ADDRESS"
+    )]
+    external_addresses: Vec<String>,
+
+    #[arg(
+        long = "p2p.upnp",
+        long_help = "Map the listening port on the router using UPnP, so that peers can connect to a node behind NAT.",
+        default_value = "false",
+        action = clap::ArgAction::Set,
+        env = "PATHFINDER_P2P_UPNP"
+    )]
+    upnp: bool,
+
     #[arg(
         long = "p2p.max-inbound-direct-connections",
         long_help = "The maximum number of inbound direct (non-relayed) connections.",
@@ -409,6 +1034,61 @@ Example:
         env = "IP_WHITELIST"
     )]
     ip_whitelist: Vec<IpNet>,
+    #[arg(
+        long = "p2p.admin-rpc",
+        long_help = "Enable the pathfinder_peers, pathfinder_connectPeer and pathfinder_banPeer RPC methods which list, connect and ban peers. These methods should not be exposed publicly.",
+        default_value = "false",
+        action = clap::ArgAction::Set,
+        env = "PATHFINDER_P2P_ADMIN_RPC"
+    )]
+    admin_rpc: bool,
+
+    #[arg(
+        long = "p2p.sync",
+        long_help = "Sync the blocks secured by Ethereum from the p2p network, before syncing the newer blocks from the feeder gateway.",
+        default_value = "false",
+        action = clap::ArgAction::Set,
+        env = "PATHFINDER_P2P_SYNC"
+    )]
+    sync: bool,
+
+    #[arg(
+        long = "p2p.sync.gateway-stages",
+        long_help = "Comma separated list of p2p sync stages which download their data from the feeder gateway instead of peers. A stage falls back to the other source if its own is unavailable.",
+        value_name = "STAGES",
+        value_delimiter = ',',
+        env = "PATHFINDER_P2P_SYNC_GATEWAY_STAGES"
+    )]
+    sync_gateway_stages: Vec<SyncStage>,
+
+    #[arg(
+        long = "p2p.sync.snap",
+        long_help = "If no state has been synced yet, download the state at the latest block secured by Ethereum from peers instead of replaying all state updates from genesis.",
+        default_value = "false",
+        action = clap::ArgAction::Set,
+        env = "PATHFINDER_P2P_SYNC_SNAP"
+    )]
+    snap_sync: bool,
+
+    #[arg(
+        long = "p2p.sync.cross-validate",
+        long_help = "Compare the headers, transactions and receipts synced from peers with the feeder gateway's, and log and count the divergences.",
+        default_value = "false",
+        action = clap::ArgAction::Set,
+        env = "PATHFINDER_P2P_SYNC_CROSS_VALIDATE"
+    )]
+    sync_cross_validate: bool,
+}
+
+/// A stage of the p2p sync pipeline.
+#[cfg(feature = "p2p")]
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq)]
+pub enum SyncStage {
+    Headers,
+    Transactions,
+    Receipts,
+    StateUpdates,
+    Classes,
 }
 
 #[cfg(feature = "p2p")]
@@ -513,6 +1193,296 @@ pub fn parse_cors_or_exit(input: Vec<String>) -> Option<AllowedOrigins> {
     }
 }
 
+/// An additional network as listed in the `--network.additional-config` file.
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AdditionalNetworkEntry {
+    network: String,
+    #[serde(rename = "ethereum.url")]
+    ethereum_url: String,
+    #[serde(rename = "ethereum.password", default)]
+    ethereum_password: Option<String>,
+    #[serde(rename = "http-rpc")]
+    http_rpc: String,
+    #[serde(rename = "gateway-api-key", default)]
+    gateway_api_key: Option<String>,
+}
+
+/// Parses the additional networks from the contents of the `--network.additional-config` file.
+fn parse_additional_networks(
+    input: &str,
+) -> Result<Vec<AdditionalNetworkConfig>, AdditionalNetworkParseError> {
+    serde_json::from_str::<Vec<AdditionalNetworkEntry>>(input)
+        .map_err(|e| AdditionalNetworkParseError::InvalidFile(e.to_string()))?
+        .into_iter()
+        .map(parse_additional_network)
+        .collect()
+}
+
+fn parse_additional_network(
+    entry: AdditionalNetworkEntry,
+) -> Result<AdditionalNetworkConfig, AdditionalNetworkParseError> {
+    let network = <Network as clap::ValueEnum>::from_str(&entry.network, false)
+        .map_err(|_| AdditionalNetworkParseError::InvalidNetwork(entry.network.clone()))?;
+    let network = match network {
+        Network::Mainnet => NetworkConfig::Mainnet,
+        Network::GoerliTestnet => NetworkConfig::GoerliTestnet,
+        Network::GoerliIntegration => NetworkConfig::GoerliIntegration,
+        Network::SepoliaTestnet => NetworkConfig::SepoliaTestnet,
+        Network::SepoliaIntegration => NetworkConfig::SepoliaIntegration,
+        Network::Custom => return Err(AdditionalNetworkParseError::CustomNetwork),
+    };
+    // The URL may contain an API key, so it is not part of the error.
+    let url = Url::parse(&entry.ethereum_url)
+        .map_err(|e| AdditionalNetworkParseError::InvalidUrl(e.to_string()))?;
+    let rpc_address = entry
+        .http_rpc
+        .parse::<SocketAddr>()
+        .map_err(|_| AdditionalNetworkParseError::InvalidAddress(entry.http_rpc.clone()))?;
+
+    Ok(AdditionalNetworkConfig {
+        network,
+        ethereum: Ethereum {
+            url,
+            password: entry.ethereum_password,
+        },
+        rpc_address,
+        gateway_api_key: entry.gateway_api_key,
+    })
+}
+
+fn parse_additional_networks_or_exit(path: Option<PathBuf>) -> Vec<AdditionalNetworkConfig> {
+    use clap::error::ErrorKind;
+
+    let Some(path) = path else {
+        return Vec::new();
+    };
+
+    std::fs::read_to_string(&path)
+        .map_err(|e| AdditionalNetworkParseError::Read(path.display().to_string(), e.to_string()))
+        .and_then(|input| parse_additional_networks(&input))
+        .unwrap_or_else(|error| {
+            Cli::command()
+                .error(ErrorKind::ValueValidation, error)
+                .exit()
+        })
+}
+
+fn parse_gateway_headers(inputs: Vec<String>) -> Result<HeaderMap, GatewayHeaderParseError> {
+    let mut headers = HeaderMap::new();
+
+    for input in inputs {
+        let (name, value) = input
+            .split_once(':')
+            .ok_or_else(|| GatewayHeaderParseError::MissingSeparator(input.clone()))?;
+        let name = HeaderName::from_bytes(name.trim().as_bytes())
+            .map_err(|_| GatewayHeaderParseError::InvalidName(name.trim().to_owned()))?;
+        let value = HeaderValue::from_str(value.trim())
+            .map_err(|_| GatewayHeaderParseError::InvalidValue(name.to_string()))?;
+
+        headers.append(name, value);
+    }
+
+    Ok(headers)
+}
+
+fn parse_gateway_headers_or_exit(inputs: Vec<String>) -> HeaderMap {
+    use clap::error::ErrorKind;
+
+    parse_gateway_headers(inputs).unwrap_or_else(|error| {
+        Cli::command()
+            .error(ErrorKind::ValueValidation, error)
+            .exit()
+    })
+}
+
+#[cfg(feature = "sqlcipher")]
+fn parse_encryption_key_or_exit(
+    key: Option<String>,
+    key_file: Option<PathBuf>,
+) -> Option<pathfinder_storage::EncryptionKey> {
+    use clap::error::ErrorKind;
+
+    let key = match key_file {
+        Some(path) => {
+            let key = std::fs::read_to_string(&path).unwrap_or_else(|error| {
+                Cli::command()
+                    .error(
+                        ErrorKind::Io,
+                        format!(
+                            "Failed to read encryption key file {}: {error}",
+                            path.display()
+                        ),
+                    )
+                    .exit()
+            });
+            Some(key.trim_end_matches(['\n', '\r']).to_owned())
+        }
+        None => key,
+    }?;
+
+    if key.is_empty() {
+        Cli::command()
+            .error(ErrorKind::ValueValidation, "The encryption key is empty.")
+            .exit()
+    }
+
+    Some(pathfinder_storage::EncryptionKey::new(key))
+}
+
+#[derive(Debug, thiserror::Error, PartialEq)]
+enum GatewayHeaderParseError {
+    #[error("Invalid gateway request header '{0}', expected 'NAME: VALUE'.")]
+    MissingSeparator(String),
+    #[error("Invalid gateway request header name '{0}'.")]
+    InvalidName(String),
+    #[error("Invalid value for gateway request header '{0}'.")]
+    InvalidValue(String),
+}
+
+fn parse_trusted_block(input: &str) -> Result<TrustedBlock, TrustedBlockParseError> {
+    let (number, hash) = input
+        .split_once(',')
+        .ok_or_else(|| TrustedBlockParseError::MissingSeparator(input.to_owned()))?;
+    let number = number
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .and_then(BlockNumber::new)
+        .ok_or_else(|| TrustedBlockParseError::InvalidNumber(number.trim().to_owned()))?;
+    let hash = Felt::from_hex_str(hash.trim())
+        .map(BlockHash)
+        .map_err(|_| TrustedBlockParseError::InvalidHash(hash.trim().to_owned()))?;
+
+    Ok(TrustedBlock { number, hash })
+}
+
+fn parse_trusted_block_or_exit(input: Option<String>) -> Option<TrustedBlock> {
+    use clap::error::ErrorKind;
+
+    input.map(|input| {
+        parse_trusted_block(&input).unwrap_or_else(|error| {
+            Cli::command()
+                .error(ErrorKind::ValueValidation, error)
+                .exit()
+        })
+    })
+}
+
+#[derive(Debug, thiserror::Error, PartialEq)]
+enum TrustedBlockParseError {
+    #[error("Invalid trusted block '{0}', expected 'NUMBER,HASH'.")]
+    MissingSeparator(String),
+    #[error("Invalid trusted block number '{0}'.")]
+    InvalidNumber(String),
+    #[error("Invalid trusted block hash '{0}'.")]
+    InvalidHash(String),
+}
+
+type BlockRange = (Option<BlockNumber>, Option<BlockNumber>);
+
+fn parse_block_range(input: &str) -> Result<BlockRange, BlockRangeParseError> {
+    let (first, last) = input
+        .split_once("..")
+        .ok_or_else(|| BlockRangeParseError::MissingSeparator(input.to_owned()))?;
+    let parse = |number: &str| match number.trim() {
+        "" => Ok(None),
+        number => number
+            .parse::<u64>()
+            .ok()
+            .and_then(BlockNumber::new)
+            .map(Some)
+            .ok_or_else(|| BlockRangeParseError::InvalidNumber(number.to_owned())),
+    };
+    let (first, last) = (parse(first)?, parse(last)?);
+
+    if let (Some(first), Some(last)) = (first, last) {
+        if first > last {
+            return Err(BlockRangeParseError::Empty(input.to_owned()));
+        }
+    }
+
+    Ok((first, last))
+}
+
+fn parse_block_range_or_exit(input: Option<String>) -> BlockRange {
+    use clap::error::ErrorKind;
+
+    input
+        .map(|input| {
+            parse_block_range(&input).unwrap_or_else(|error| {
+                Cli::command()
+                    .error(ErrorKind::ValueValidation, error)
+                    .exit()
+            })
+        })
+        .unwrap_or_default()
+}
+
+#[derive(Debug, thiserror::Error, PartialEq)]
+enum BlockRangeParseError {
+    #[error("Invalid block range '{0}', expected 'FIRST..LAST'.")]
+    MissingSeparator(String),
+    #[error("Invalid block number '{0}'.")]
+    InvalidNumber(String),
+    #[error("Block range '{0}' is empty.")]
+    Empty(String),
+}
+
+fn parse_fork_from(source: &str, block: &str) -> Result<ForkConfig, ForkFromParseError> {
+    let source = if source.starts_with("http://") || source.starts_with("https://") {
+        Url::parse(source)
+            .map(ForkSource::Rpc)
+            .map_err(|_| ForkFromParseError::InvalidUrl(source.to_owned()))?
+    } else {
+        ForkSource::Database(PathBuf::from(source))
+    };
+    let block = block
+        .parse::<u64>()
+        .ok()
+        .and_then(BlockNumber::new)
+        .ok_or_else(|| ForkFromParseError::InvalidBlock(block.to_owned()))?;
+
+    Ok(ForkConfig { source, block })
+}
+
+fn parse_fork_from_or_exit(input: Option<Vec<String>>) -> Option<ForkConfig> {
+    use clap::error::ErrorKind;
+
+    input.map(|input| {
+        // Clap ensures there are exactly two values.
+        parse_fork_from(&input[0], &input[1]).unwrap_or_else(|error| {
+            Cli::command()
+                .error(ErrorKind::ValueValidation, error)
+                .exit()
+        })
+    })
+}
+
+#[derive(Debug, thiserror::Error, PartialEq)]
+enum ForkFromParseError {
+    #[error("Invalid fork source URL '{0}'.")]
+    InvalidUrl(String),
+    #[error("Invalid fork block number '{0}'.")]
+    InvalidBlock(String),
+}
+
+#[derive(Debug, thiserror::Error, PartialEq)]
+enum AdditionalNetworkParseError {
+    #[error("Failed to read additional network config file '{0}': {1}.")]
+    Read(String, String),
+    #[error("Invalid additional network config file: {0}.")]
+    InvalidFile(String),
+    #[error("Invalid additional network '{0}'.")]
+    InvalidNetwork(String),
+    #[error("Custom networks are not supported as additional networks.")]
+    CustomNetwork,
+    #[error("Invalid Ethereum URL for additional network: {0}.")]
+    InvalidUrl(String),
+    #[error("Invalid HTTP-RPC address for additional network: {0}.")]
+    InvalidAddress(String),
+}
+
 #[derive(Debug, thiserror::Error, PartialEq)]
 #[error("Invalid domain for CORS: {0}")]
 struct InvalidCorsDomainError(String);
@@ -527,6 +1497,24 @@ enum RpcCorsDomainsParseError {
     WildcardAmongOtherValues,
 }
 
+/// What pathfinder was asked to do.
+pub enum Command {
+    /// Run the node.
+    Node(Box<Config>),
+    /// Export chain data from a database.
+    Export(ExportConfig),
+}
+
+pub struct ExportConfig {
+    pub database: PathBuf,
+    pub transaction_directory: Option<PathBuf>,
+    pub table: ExportTable,
+    pub first_block: Option<BlockNumber>,
+    pub last_block: Option<BlockNumber>,
+    pub format: ExportFormat,
+    pub output: Option<PathBuf>,
+}
+
 pub struct Config {
     pub data_directory: PathBuf,
     pub ethereum: Ethereum,
@@ -536,22 +1524,56 @@ pub struct Config {
     pub websocket: WebsocketConfig,
     pub monitor_address: Option<SocketAddr>,
     pub network: Option<NetworkConfig>,
+    pub additional_networks: Vec<AdditionalNetworkConfig>,
     pub execution_concurrency: Option<std::num::NonZeroU32>,
     pub sqlite_wal: JournalMode,
     pub max_rpc_connections: std::num::NonZeroUsize,
     pub poll_interval: std::time::Duration,
+    pub block_prefetch: usize,
+    pub sync_trusted_block: Option<TrustedBlock>,
+    pub backfill: Option<pathfinder_lib::state::backfill::Config>,
+    pub halt_on_l1_state_root_mismatch: bool,
+    pub sync_stall_timeout: Option<Duration>,
     pub color: Color,
     pub p2p: P2PConfig,
     pub debug: DebugConfig,
     pub verify_tree_hashes: bool,
     pub rpc_batch_concurrency_limit: NonZeroUsize,
+    pub rpc_batch_size_limit: NonZeroUsize,
+    pub rpc_max_request_body_size: NonZeroUsize,
+    pub rpc_request_timeout: Duration,
+    pub rpc_execution_max_steps: Option<u32>,
+    pub rpc_execution_timeout: Option<Duration>,
+    pub rpc_execution_queue_size: NonZeroUsize,
+    pub rpc_erc20_balances: bool,
+    pub rpc_query: bool,
+    pub rpc_table_sizes: bool,
+    pub rpc_l1_gas_price_floor: bool,
+    pub rpc_trace_profiles: bool,
+    pub rpc_max_signature_length: Option<NonZeroUsize>,
     pub is_sync_enabled: bool,
+    pub devnet: Option<pathfinder_lib::devnet::Config>,
     pub is_rpc_enabled: bool,
     pub gateway_api_key: Option<String>,
+    pub gateway_headers: HeaderMap,
     pub gateway_timeout: Duration,
+    pub gateway_log_unknown_fields: bool,
     pub event_bloom_filter_cache_size: NonZeroUsize,
+    pub persist_bloom_filter_cache: bool,
+    pub trie_layout: Option<pathfinder_storage::TrieLayout>,
+    pub wal_checkpoint: Option<pathfinder_lib::wal_checkpoint::Config>,
+    pub backup: Option<pathfinder_lib::backup::Config>,
+    pub trie_directory: Option<PathBuf>,
+    pub transaction_directory: Option<PathBuf>,
+    pub slow_query_threshold: Option<Duration>,
+    pub migration: pathfinder_storage::Migration,
+    #[cfg(feature = "sqlcipher")]
+    pub encryption_key: Option<pathfinder_storage::EncryptionKey>,
     pub get_events_max_blocks_to_scan: NonZeroUsize,
     pub get_events_max_uncached_bloom_filters_to_load: NonZeroUsize,
+    pub get_events_max_page_size: NonZeroUsize,
+    pub get_events_max_keys_in_filter: NonZeroUsize,
+    pub get_events_max_query_cost: Option<std::num::NonZeroU64>,
 }
 
 pub struct Ethereum {
@@ -559,6 +1581,14 @@ pub struct Ethereum {
     pub password: Option<String>,
 }
 
+/// A network run alongside the primary one, see `--network.additional-config`.
+pub struct AdditionalNetworkConfig {
+    pub network: NetworkConfig,
+    pub ethereum: Ethereum,
+    pub rpc_address: SocketAddr,
+    pub gateway_api_key: Option<String>,
+}
+
 pub enum NetworkConfig {
     Mainnet,
     GoerliTestnet,
@@ -573,20 +1603,29 @@ pub enum NetworkConfig {
 }
 
 #[cfg(feature = "p2p")]
+#[derive(Clone)]
 pub struct P2PConfig {
     pub proxy: bool,
     pub identity_config_file: Option<std::path::PathBuf>,
     pub listen_on: Multiaddr,
     pub bootstrap_addresses: Vec<Multiaddr>,
     pub predefined_peers: Vec<Multiaddr>,
+    pub external_addresses: Vec<Multiaddr>,
+    pub upnp: bool,
     pub max_inbound_direct_connections: usize,
     pub max_inbound_relayed_connections: usize,
     pub max_outbound_connections: usize,
     pub ip_whitelist: Vec<IpNet>,
     pub low_watermark: usize,
+    pub admin_rpc: bool,
+    pub sync: bool,
+    pub sync_gateway_stages: Vec<SyncStage>,
+    pub snap_sync: bool,
+    pub sync_cross_validate: bool,
 }
 
 #[cfg(not(feature = "p2p"))]
+#[derive(Clone)]
 pub struct P2PConfig;
 
 pub struct DebugConfig {
@@ -643,10 +1682,19 @@ impl P2PConfig {
     fn parse_or_exit(_: ()) -> Self {
         Self
     }
+
+    pub fn admin_rpc(&self) -> bool {
+        false
+    }
 }
 
 #[cfg(feature = "p2p")]
 impl P2PConfig {
+    /// Whether peers can be administered through RPC.
+    pub fn admin_rpc(&self) -> bool {
+        self.admin_rpc
+    }
+
     fn parse_or_exit(args: P2PCli) -> Self {
         use clap::error::ErrorKind;
         use p2p::libp2p::multiaddr::Result;
@@ -703,8 +1751,15 @@ impl P2PConfig {
             listen_on: args.listen_on,
             bootstrap_addresses: parse_multiaddr_vec(args.bootstrap_addresses),
             predefined_peers: parse_multiaddr_vec(args.predefined_peers),
+            external_addresses: parse_multiaddr_vec(args.external_addresses),
+            upnp: args.upnp,
             ip_whitelist: args.ip_whitelist,
             low_watermark: 0,
+            admin_rpc: args.admin_rpc,
+            sync: args.sync,
+            sync_gateway_stages: args.sync_gateway_stages,
+            snap_sync: args.snap_sync,
+            sync_cross_validate: args.sync_cross_validate,
         }
     }
 }
@@ -729,17 +1784,46 @@ impl DebugConfig {
     }
 }
 
-impl Config {
+impl Command {
     pub fn parse() -> Self {
-        let cli = Cli::parse();
+        let mut cli = Cli::parse();
 
+        match cli.command.take() {
+            Some(CliCommand::Db(DbCli::Export(args))) => Command::Export(ExportConfig::parse(args)),
+            None => Command::Node(Box::new(Config::parse(cli))),
+        }
+    }
+}
+
+impl ExportConfig {
+    fn parse(args: ExportCli) -> Self {
+        let (first_block, last_block) = parse_block_range_or_exit(args.range);
+
+        Self {
+            database: args.database,
+            transaction_directory: args.transaction_directory,
+            table: args.table,
+            first_block,
+            last_block,
+            format: args.format,
+            output: args.output,
+        }
+    }
+}
+
+impl Config {
+    fn parse(mut cli: Cli) -> Self {
+        let additional_networks =
+            parse_additional_networks_or_exit(cli.network.additional_config.take());
         let network = NetworkConfig::from_components(cli.network);
 
         Config {
             data_directory: cli.data_directory,
             ethereum: Ethereum {
                 password: cli.ethereum_password,
-                url: cli.ethereum_url,
+                url: cli
+                    .ethereum_url
+                    .expect("Required unless a subcommand is given"),
             },
             rpc_address: cli.rpc_address,
             rpc_cors_domains: parse_cors_or_exit(cli.rpc_cors_domains),
@@ -747,6 +1831,7 @@ impl Config {
             websocket: cli.websocket,
             monitor_address: cli.monitor_address,
             network,
+            additional_networks,
             execution_concurrency: cli.execution_concurrency,
             sqlite_wal: match cli.sqlite_wal {
                 true => JournalMode::WAL,
@@ -754,19 +1839,84 @@ impl Config {
             },
             max_rpc_connections: cli.max_rpc_connections,
             poll_interval: Duration::from_secs(cli.poll_interval.get()),
+            block_prefetch: cli.block_prefetch,
+            sync_trusted_block: parse_trusted_block_or_exit(cli.sync_trusted_block),
+            backfill: std::num::NonZeroU32::new(cli.backfill_rate).map(|blocks_per_second| {
+                pathfinder_lib::state::backfill::Config { blocks_per_second }
+            }),
+            halt_on_l1_state_root_mismatch: cli.halt_on_l1_state_root_mismatch,
+            sync_stall_timeout: (cli.sync_stall_timeout > 0)
+                .then(|| Duration::from_secs(cli.sync_stall_timeout)),
             color: cli.color,
             p2p: P2PConfig::parse_or_exit(cli.p2p),
             debug: DebugConfig::parse(cli.debug),
             verify_tree_hashes: cli.verify_tree_node_data,
             rpc_batch_concurrency_limit: cli.rpc_batch_concurrency_limit,
+            rpc_batch_size_limit: cli.rpc_batch_size_limit,
+            rpc_max_request_body_size: cli.rpc_max_request_body_size,
+            rpc_request_timeout: Duration::from_secs(cli.rpc_request_timeout.get()),
+            rpc_execution_max_steps: cli.rpc_execution_max_steps,
+            rpc_execution_timeout: cli
+                .rpc_execution_timeout
+                .map(|timeout| Duration::from_secs(timeout.get())),
+            rpc_execution_queue_size: cli.rpc_execution_queue_size,
+            rpc_erc20_balances: cli.rpc_erc20_balances,
+            #[cfg(feature = "rpc-query")]
+            rpc_query: cli.rpc_query,
+            #[cfg(not(feature = "rpc-query"))]
+            rpc_query: false,
+            rpc_table_sizes: cli.rpc_table_sizes,
+            rpc_l1_gas_price_floor: cli.rpc_l1_gas_price_floor,
+            rpc_trace_profiles: cli.rpc_trace_profiles,
+            rpc_max_signature_length: cli.rpc_max_signature_length,
             is_sync_enabled: cli.is_sync_enabled,
+            devnet: cli
+                .devnet_block_time
+                .map(|block_time| pathfinder_lib::devnet::Config {
+                    block_time: Duration::from_secs(block_time.get()),
+                    fork: parse_fork_from_or_exit(cli.devnet_fork_from),
+                }),
             is_rpc_enabled: cli.is_rpc_enabled,
             gateway_api_key: cli.gateway_api_key,
+            gateway_headers: parse_gateway_headers_or_exit(cli.gateway_headers),
             event_bloom_filter_cache_size: cli.event_bloom_filter_cache_size,
+            persist_bloom_filter_cache: cli.persist_bloom_filter_cache,
+            trie_layout: cli.trie_layout.map(Into::into),
+            wal_checkpoint: cli.wal_checkpoint_interval.map(|interval| {
+                pathfinder_lib::wal_checkpoint::Config {
+                    interval: Duration::from_secs(interval.get()),
+                    size_threshold: cli.wal_checkpoint_size.get() * 1024 * 1024,
+                }
+            }),
+            #[cfg(feature = "sqlcipher")]
+            encryption_key: parse_encryption_key_or_exit(
+                cli.encryption_key,
+                cli.encryption_key_file,
+            ),
+            backup: cli
+                .backup_directory
+                .map(|directory| pathfinder_lib::backup::Config {
+                    directory,
+                    interval: Duration::from_secs(cli.backup_interval.get()),
+                    retention: cli.backup_retention,
+                }),
+            trie_directory: cli.trie_directory,
+            transaction_directory: cli.transaction_directory,
+            slow_query_threshold: cli
+                .slow_query_threshold
+                .map(|millis| Duration::from_millis(millis.get())),
+            migration: cli.migration.into(),
             get_events_max_blocks_to_scan: cli.get_events_max_blocks_to_scan,
             get_events_max_uncached_bloom_filters_to_load: cli
                 .get_events_max_uncached_bloom_filters_to_load,
+            get_events_max_page_size: cli.get_events_max_page_size,
+            get_events_max_keys_in_filter: NonZeroUsize::new(
+                cli.get_events_max_keys_in_filter as usize,
+            )
+            .expect("The range starts at 1"),
+            get_events_max_query_cost: cli.get_events_max_query_cost,
             gateway_timeout: Duration::from_secs(cli.gateway_timeout.get()),
+            gateway_log_unknown_fields: cli.gateway_log_unknown_fields,
         }
     }
 }
@@ -801,12 +1951,44 @@ pub struct WebsocketConfig {
         env = "PATHFINDER_WEBSOCKET_TOPIC_CAPACITY"
     )]
     pub topic_sender_capacity: NonZeroUsize,
+    #[arg(
+        long = "rpc.websocket.max-connections-per-ip",
+        long_help = "The maximum number of concurrent WebSocket connections from a single IP address. \
+            Unlimited if not set.",
+        value_name = "CONNECTIONS",
+        env = "PATHFINDER_WEBSOCKET_MAX_CONNECTIONS_PER_IP"
+    )]
+    pub max_connections_per_ip: Option<NonZeroUsize>,
+    #[arg(
+        long = "rpc.websocket.pending-transactions-queue-size",
+        long_help = "The number of transactions queued for each pending transaction subscription \
+            whose client does not keep up. See also `rpc.websocket.pending-transactions-drop-policy`",
+        value_name = "SIZE",
+        default_value = "1000",
+        env = "PATHFINDER_WEBSOCKET_PENDING_TRANSACTIONS_QUEUE_SIZE"
+    )]
+    pub pending_transactions_queue_size: NonZeroUsize,
+    #[arg(
+        long = "rpc.websocket.pending-transactions-drop-policy",
+        long_help = "What happens to a new pending transaction when the queue of a subscription is \
+            full. `drop-oldest` drops the oldest queued transaction, `drop-newest` drops the new \
+            transaction and `close` closes the subscription",
+        value_name = "POLICY",
+        value_enum,
+        default_value = "drop-oldest",
+        env = "PATHFINDER_WEBSOCKET_PENDING_TRANSACTIONS_DROP_POLICY"
+    )]
+    pub pending_transactions_drop_policy: DropPolicy,
 }
 
 #[cfg(test)]
 mod tests {
     use super::{AllowedOrigins, RpcCorsDomainsParseError};
-    use crate::config::parse_cors;
+    use crate::config::{
+        parse_additional_networks, parse_cors, parse_fork_from, parse_gateway_headers,
+        parse_trusted_block, AdditionalNetworkParseError, ForkFromParseError,
+        GatewayHeaderParseError, NetworkConfig, TrustedBlockParseError,
+    };
 
     #[test]
     fn parse_cors_domains() {
@@ -882,4 +2064,295 @@ mod tests {
             )
         });
     }
+
+    #[test]
+    fn parse_additional_network_config() {
+        let configs = parse_additional_networks(
+            r#"[
+                {
+                    "network": "sepolia-testnet",
+                    "ethereum.url": "https://sepolia.example.com/?key=abc,def",
+                    "http-rpc": "127.0.0.1:9546"
+                },
+                {
+                    "network": "mainnet",
+                    "ethereum.url": "http://localhost:8545",
+                    "http-rpc": "127.0.0.1:9547",
+                    "ethereum.password": "password",
+                    "gateway-api-key": "secret"
+                }
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(configs.len(), 2);
+
+        let config = &configs[0];
+        assert!(matches!(config.network, NetworkConfig::SepoliaTestnet));
+        assert_eq!(
+            config.ethereum.url.as_str(),
+            "https://sepolia.example.com/?key=abc,def"
+        );
+        assert_eq!(config.ethereum.password, None);
+        assert_eq!(config.rpc_address, "127.0.0.1:9546".parse().unwrap());
+        assert_eq!(config.gateway_api_key, None);
+
+        let config = &configs[1];
+        assert!(matches!(config.network, NetworkConfig::Mainnet));
+        assert_eq!(config.ethereum.password.as_deref(), Some("password"));
+        assert_eq!(config.gateway_api_key.as_deref(), Some("secret"));
+
+        assert!(parse_additional_networks("[]").unwrap().is_empty());
+
+        [
+            (
+                r#"[{"network": "custom", "ethereum.url": "http://localhost:8545", "http-rpc": "127.0.0.1:9546"}]"#,
+                Some(AdditionalNetworkParseError::CustomNetwork),
+            ),
+            (
+                r#"[{"network": "unknown", "ethereum.url": "http://localhost:8545", "http-rpc": "127.0.0.1:9546"}]"#,
+                Some(AdditionalNetworkParseError::InvalidNetwork(
+                    "unknown".to_owned(),
+                )),
+            ),
+            (
+                r#"[{"network": "mainnet", "ethereum.url": "http://localhost:8545", "http-rpc": "localhost"}]"#,
+                Some(AdditionalNetworkParseError::InvalidAddress(
+                    "localhost".to_owned(),
+                )),
+            ),
+        ]
+        .into_iter()
+        .for_each(|(input, expected_error)| {
+            assert_eq!(
+                parse_additional_networks(input).err(),
+                expected_error,
+                "input: {input:?}"
+            );
+        });
+
+        [
+            // Missing Ethereum URL.
+            r#"[{"network": "sepolia-testnet", "http-rpc": "127.0.0.1:9546"}]"#,
+            // Unknown key.
+            r#"[{"network": "mainnet", "ethereum.url": "http://localhost:8545", "rpc": "127.0.0.1:9546"}]"#,
+            // Not a list.
+            r#"{"network": "mainnet", "ethereum.url": "http://localhost:8545", "http-rpc": "127.0.0.1:9546"}"#,
+        ]
+        .into_iter()
+        .for_each(|input| {
+            assert_matches::assert_matches!(
+                parse_additional_networks(input).err(),
+                Some(AdditionalNetworkParseError::InvalidFile(_)),
+                "input: {input:?}"
+            );
+        });
+
+        // The URL is not echoed, as it may contain an API key.
+        assert_matches::assert_matches!(
+            parse_additional_networks(
+                r#"[{"network": "mainnet", "ethereum.url": "not a url?key=secret", "http-rpc": "127.0.0.1:9546"}]"#,
+            )
+            .err(),
+            Some(AdditionalNetworkParseError::InvalidUrl(e)) if !e.contains("secret")
+        );
+    }
+
+    #[test]
+    fn parse_gateway_request_headers() {
+        let headers = parse_gateway_headers(vec![
+            "X-Api-Key: secret".to_owned(),
+            "X-Other:value: with colon".to_owned(),
+        ])
+        .unwrap();
+
+        assert_eq!(headers.len(), 2);
+        assert_eq!(headers["x-api-key"], "secret");
+        assert_eq!(headers["x-other"], "value: with colon");
+
+        assert_eq!(
+            parse_gateway_headers(vec!["X-Api-Key secret".to_owned()]).unwrap_err(),
+            GatewayHeaderParseError::MissingSeparator("X-Api-Key secret".to_owned())
+        );
+        assert_eq!(
+            parse_gateway_headers(vec!["X Api Key: secret".to_owned()]).unwrap_err(),
+            GatewayHeaderParseError::InvalidName("X Api Key".to_owned())
+        );
+        assert_eq!(
+            parse_gateway_headers(vec!["X-Api-Key: sec\nret".to_owned()]).unwrap_err(),
+            GatewayHeaderParseError::InvalidValue("x-api-key".to_owned())
+        );
+    }
+
+    #[test]
+    fn parse_trusted_block_entry() {
+        use pathfinder_common::macro_prelude::block_hash;
+        use pathfinder_common::BlockNumber;
+        use pathfinder_lib::state::l2::TrustedBlock;
+
+        assert_eq!(
+            parse_trusted_block("600000, 0x1234").unwrap(),
+            TrustedBlock {
+                number: BlockNumber::new_or_panic(600000),
+                hash: block_hash!("0x1234"),
+            }
+        );
+
+        assert_eq!(
+            parse_trusted_block("600000").unwrap_err(),
+            TrustedBlockParseError::MissingSeparator("600000".to_owned())
+        );
+        assert_eq!(
+            parse_trusted_block("latest,0x1234").unwrap_err(),
+            TrustedBlockParseError::InvalidNumber("latest".to_owned())
+        );
+        assert_eq!(
+            parse_trusted_block("600000,0xzz").unwrap_err(),
+            TrustedBlockParseError::InvalidHash("0xzz".to_owned())
+        );
+    }
+
+    #[test]
+    fn parse_block_range_entry() {
+        use pathfinder_common::BlockNumber;
+
+        let block = BlockNumber::new_or_panic;
+
+        assert_eq!(
+            parse_block_range("0..1000").unwrap(),
+            (Some(BlockNumber::GENESIS), Some(block(1000)))
+        );
+        assert_eq!(
+            parse_block_range("600000..").unwrap(),
+            (Some(block(600000)), None)
+        );
+        assert_eq!(
+            parse_block_range("..1000").unwrap(),
+            (None, Some(block(1000)))
+        );
+        assert_eq!(parse_block_range("..").unwrap(), (None, None));
+
+        assert_eq!(
+            parse_block_range("1000").unwrap_err(),
+            BlockRangeParseError::MissingSeparator("1000".to_owned())
+        );
+        assert_eq!(
+            parse_block_range("0..latest").unwrap_err(),
+            BlockRangeParseError::InvalidNumber("latest".to_owned())
+        );
+        assert_eq!(
+            parse_block_range("1000..0").unwrap_err(),
+            BlockRangeParseError::Empty("1000..0".to_owned())
+        );
+    }
+
+    #[test]
+    fn parse_fork_from_entry() {
+        use pathfinder_common::BlockNumber;
+        use pathfinder_lib::devnet::fork::{Config, Source};
+
+        assert_eq!(
+            parse_fork_from("https://node.example.com/rpc/v0_7", "600000").unwrap(),
+            Config {
+                source: Source::Rpc("https://node.example.com/rpc/v0_7".parse().unwrap()),
+                block: BlockNumber::new_or_panic(600000),
+            }
+        );
+        assert_eq!(
+            parse_fork_from("/data/mainnet.sqlite", "0").unwrap(),
+            Config {
+                source: Source::Database("/data/mainnet.sqlite".into()),
+                block: BlockNumber::GENESIS,
+            }
+        );
+
+        assert_eq!(
+            parse_fork_from("http://", "600000").unwrap_err(),
+            ForkFromParseError::InvalidUrl("http://".to_owned())
+        );
+        assert_eq!(
+            parse_fork_from("/data/mainnet.sqlite", "latest").unwrap_err(),
+            ForkFromParseError::InvalidBlock("latest".to_owned())
+        );
+    }
+
+    #[test]
+    fn backup_directory_conflicts_with_split_databases() {
+        use clap::error::ErrorKind;
+        use clap::Parser;
+
+        use super::Cli;
+
+        let args = |split: &str| {
+            [
+                "pathfinder",
+                "--ethereum.url",
+                "https://localhost:8545",
+                split,
+                "/data/split",
+                "--storage.backup-directory",
+                "/data/backups",
+            ]
+        };
+
+        for split in [
+            "--storage.trie-directory",
+            "--storage.transaction-directory",
+        ] {
+            let error = Cli::try_parse_from(args(split)).err().unwrap();
+            assert_eq!(error.kind(), ErrorKind::ArgumentConflict, "{split}");
+        }
+
+        Cli::try_parse_from([
+            "pathfinder",
+            "--ethereum.url",
+            "https://localhost:8545",
+            "--storage.backup-directory",
+            "/data/backups",
+        ])
+        .unwrap();
+    }
+
+    #[test]
+    fn db_export_does_not_require_node_options() {
+        use clap::error::ErrorKind;
+        use clap::Parser;
+
+        use super::{Cli, CliCommand, DbCli, ExportFormat, ExportTable};
+
+        let cli = Cli::try_parse_from([
+            "pathfinder",
+            "db",
+            "export",
+            "--database",
+            "mainnet.sqlite",
+            "--table",
+            "events",
+            "--format",
+            "parquet",
+        ])
+        .unwrap();
+        let Some(CliCommand::Db(DbCli::Export(args))) = cli.command else {
+            panic!("Expected the export subcommand");
+        };
+        assert_eq!(args.table, ExportTable::Events);
+        assert_eq!(args.format, ExportFormat::Parquet);
+
+        // The node still requires its options.
+        let error = Cli::try_parse_from(["pathfinder"]).err().unwrap();
+        assert_eq!(error.kind(), ErrorKind::MissingRequiredArgument);
+
+        // Node options are not accepted along with a subcommand.
+        assert!(Cli::try_parse_from([
+            "pathfinder",
+            "--ethereum.url",
+            "https://localhost:8545",
+            "db",
+            "export",
+            "--database",
+            "mainnet.sqlite",
+            "--table",
+            "events",
+        ])
+        .is_err());
+    }
 }
@@ -0,0 +1,442 @@
+//! The `pathfinder db export` command, which dumps chain data from a database as CSV or Parquet
+//! so that it can be loaded into analytics tools.
+
+use std::io::Write;
+use std::num::NonZeroU32;
+use std::sync::Arc;
+
+use anyhow::Context;
+use parquet::basic::{Compression, ConvertedType, Repetition, Type as PhysicalType};
+use parquet::data_type::{ByteArray, ByteArrayType, Int64Type};
+use parquet::file::properties::WriterProperties;
+use parquet::file::writer::SerializedFileWriter;
+use parquet::schema::types::Type;
+use pathfinder_common::transaction::TransactionVariant;
+use pathfinder_common::BlockNumber;
+use pathfinder_storage::{BlockId, Storage};
+
+use crate::config::{ExportConfig, ExportFormat, ExportTable};
+
+/// The number of rows buffered for each Parquet row group.
+const ROW_GROUP_SIZE: usize = 100_000;
+
+/// Writes the rows of `config.table` for the blocks in its range to `config.output`.
+///
+/// The database is opened read-only and all rows are read within a single transaction.
+pub fn run(config: ExportConfig) -> anyhow::Result<()> {
+    // Logs go to stderr, since the export itself may be written to stdout.
+    tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .with_writer(std::io::stderr)
+        .compact()
+        .init();
+
+    let split = crate::split_databases(
+        None,
+        config.transaction_directory.as_deref(),
+        &config.database,
+    );
+    let storage = Storage::open_read_only(config.database.clone(), split)
+        .context("Opening database")?
+        .create_pool(NonZeroU32::new(1).unwrap())?;
+    let mut db = storage
+        .connection()
+        .context("Opening database connection")?;
+    let tx = db.transaction()?;
+
+    let latest_block = tx
+        .block_id(BlockId::Latest)
+        .context("Fetching latest block number")?
+        .context("Database is empty")?
+        .0;
+    let first_block = config.first_block.unwrap_or(BlockNumber::GENESIS);
+    let last_block = config.last_block.unwrap_or(latest_block).min(latest_block);
+
+    let output: Box<dyn Write + Send> = match &config.output {
+        Some(path) => Box::new(
+            std::fs::File::create(path)
+                .with_context(|| format!("Creating output file {}", path.display()))?,
+        ),
+        None => Box::new(std::io::stdout()),
+    };
+    let output = std::io::BufWriter::new(output);
+    let columns = columns(config.table);
+    let mut output = match config.format {
+        ExportFormat::Csv => Output::Csv(CsvWriter::new(output, columns)?),
+        ExportFormat::Parquet => Output::Parquet(ParquetWriter::new(output, columns)?),
+    };
+
+    tracing::info!(table=?config.table, format=?config.format, %first_block, %last_block, "Exporting");
+
+    let mut rows = 0usize;
+    let mut block = first_block;
+    while block <= last_block {
+        match config.table {
+            ExportTable::Blocks => {
+                let header = tx
+                    .block_header(block.into())?
+                    .context("Fetching block header")?;
+                output.write_row(vec![
+                    Value::Integer(header.number.get()),
+                    Value::Text(header.hash.0.to_string()),
+                    Value::Text(header.parent_hash.0.to_string()),
+                    Value::Integer(header.timestamp.get()),
+                    Value::Text(header.sequencer_address.0.to_string()),
+                    Value::Text(header.starknet_version.as_str().to_owned()),
+                    Value::Text(header.eth_l1_gas_price.0.to_string()),
+                    Value::Text(header.strk_l1_gas_price.0.to_string()),
+                    Value::Text(header.state_commitment.0.to_string()),
+                    Value::Integer(header.transaction_count as u64),
+                    Value::Integer(header.event_count as u64),
+                ])?;
+                rows += 1;
+            }
+            ExportTable::Transactions | ExportTable::Receipts | ExportTable::Events => {
+                let transactions = tx
+                    .transaction_data_for_block(block.into())?
+                    .context("Fetching block transactions")?;
+
+                for (transaction, receipt) in transactions {
+                    let index = receipt.transaction_index.get();
+                    let hash = transaction.hash.0.to_string();
+
+                    match config.table {
+                        ExportTable::Transactions => {
+                            output.write_row(vec![
+                                Value::Integer(block.get()),
+                                Value::Integer(index),
+                                Value::Text(hash),
+                                Value::Text(transaction_type(&transaction.variant).to_owned()),
+                                Value::Text(transaction.version().0.to_string()),
+                            ])?;
+                            rows += 1;
+                        }
+                        ExportTable::Receipts => {
+                            output.write_row(vec![
+                                Value::Integer(block.get()),
+                                Value::Integer(index),
+                                Value::Text(hash),
+                                Value::Text(
+                                    receipt
+                                        .actual_fee
+                                        .map(|fee| fee.0.to_string())
+                                        .unwrap_or_default(),
+                                ),
+                                Value::Text(
+                                    if receipt.is_reverted() {
+                                        "REVERTED"
+                                    } else {
+                                        "SUCCEEDED"
+                                    }
+                                    .to_owned(),
+                                ),
+                                Value::Text(receipt.revert_reason().unwrap_or_default().to_owned()),
+                                Value::Integer(receipt.execution_resources.n_steps),
+                                Value::Integer(receipt.events.len() as u64),
+                                Value::Integer(receipt.l2_to_l1_messages.len() as u64),
+                            ])?;
+                            rows += 1;
+                        }
+                        ExportTable::Events => {
+                            for (event_index, event) in receipt.events.iter().enumerate() {
+                                output.write_row(vec![
+                                    Value::Integer(block.get()),
+                                    Value::Integer(index),
+                                    Value::Text(hash.clone()),
+                                    Value::Integer(event_index as u64),
+                                    Value::Text(event.from_address.0.to_string()),
+                                    Value::Text(join_felts(event.keys.iter().map(|k| &k.0))),
+                                    Value::Text(join_felts(event.data.iter().map(|d| &d.0))),
+                                ])?;
+                                rows += 1;
+                            }
+                        }
+                        ExportTable::Blocks => unreachable!(),
+                    }
+                }
+            }
+        }
+
+        if block.get() % 1000 == 0 {
+            tracing::info!(%block, %rows, "Exported blocks");
+        }
+
+        block += 1;
+    }
+
+    output.finish()?;
+
+    tracing::info!(%rows, "Done");
+
+    Ok(())
+}
+
+/// The type of a column.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    /// Block numbers, indices and counts.
+    Integer,
+    /// Everything else, including felts and gas prices which do not fit into 64 bits.
+    Text,
+}
+
+fn columns(table: ExportTable) -> &'static [(&'static str, Kind)] {
+    use Kind::*;
+
+    match table {
+        ExportTable::Blocks => &[
+            ("block_number", Integer),
+            ("block_hash", Text),
+            ("parent_hash", Text),
+            ("timestamp", Integer),
+            ("sequencer_address", Text),
+            ("starknet_version", Text),
+            ("eth_l1_gas_price", Text),
+            ("strk_l1_gas_price", Text),
+            ("state_commitment", Text),
+            ("transaction_count", Integer),
+            ("event_count", Integer),
+        ],
+        ExportTable::Transactions => &[
+            ("block_number", Integer),
+            ("transaction_index", Integer),
+            ("transaction_hash", Text),
+            ("type", Text),
+            ("version", Text),
+        ],
+        ExportTable::Receipts => &[
+            ("block_number", Integer),
+            ("transaction_index", Integer),
+            ("transaction_hash", Text),
+            ("actual_fee", Text),
+            ("execution_status", Text),
+            ("revert_reason", Text),
+            ("n_steps", Integer),
+            ("event_count", Integer),
+            ("message_count", Integer),
+        ],
+        ExportTable::Events => &[
+            ("block_number", Integer),
+            ("transaction_index", Integer),
+            ("transaction_hash", Text),
+            ("event_index", Integer),
+            ("from_address", Text),
+            ("keys", Text),
+            ("data", Text),
+        ],
+    }
+}
+
+enum Value {
+    Integer(u64),
+    Text(String),
+}
+
+type Sink = std::io::BufWriter<Box<dyn Write + Send>>;
+
+enum Output {
+    Csv(CsvWriter),
+    Parquet(ParquetWriter),
+}
+
+impl Output {
+    fn write_row(&mut self, row: Vec<Value>) -> anyhow::Result<()> {
+        match self {
+            Output::Csv(writer) => writer.write_row(row),
+            Output::Parquet(writer) => writer.write_row(row),
+        }
+    }
+
+    fn finish(self) -> anyhow::Result<()> {
+        match self {
+            Output::Csv(writer) => writer.finish(),
+            Output::Parquet(writer) => writer.finish(),
+        }
+    }
+}
+
+/// Writes one line per row, preceded by a header line.
+struct CsvWriter {
+    out: Sink,
+}
+
+impl CsvWriter {
+    fn new(mut out: Sink, columns: &[(&str, Kind)]) -> anyhow::Result<Self> {
+        let header = columns.iter().map(|(name, _)| name.to_string()).collect();
+        write_csv_line(&mut out, header)?;
+
+        Ok(Self { out })
+    }
+
+    fn write_row(&mut self, row: Vec<Value>) -> anyhow::Result<()> {
+        let fields = row
+            .into_iter()
+            .map(|value| match value {
+                Value::Integer(value) => value.to_string(),
+                Value::Text(value) => value,
+            })
+            .collect();
+
+        write_csv_line(&mut self.out, fields)
+    }
+
+    fn finish(mut self) -> anyhow::Result<()> {
+        self.out.flush().context("Flushing output")
+    }
+}
+
+/// Writes a CSV line, quoting fields which contain separators, quotes or newlines.
+fn write_csv_line(out: &mut impl Write, fields: Vec<String>) -> anyhow::Result<()> {
+    for (i, field) in fields.iter().enumerate() {
+        if i > 0 {
+            out.write_all(b",")?;
+        }
+
+        if field.contains([',', '"', '\n', '\r']) {
+            write!(out, "\"{}\"", field.replace('"', "\"\""))?;
+        } else {
+            out.write_all(field.as_bytes())?;
+        }
+    }
+    out.write_all(b"\n").context("Writing output")?;
+
+    Ok(())
+}
+
+/// Buffers rows column by column and writes them as a Parquet row group once
+/// [ROW_GROUP_SIZE] rows have been buffered.
+///
+/// Integer columns are written as `INT64` and text columns as `UTF8` byte arrays.
+struct ParquetWriter {
+    writer: SerializedFileWriter<Sink>,
+    columns: Vec<Column>,
+    rows: usize,
+}
+
+enum Column {
+    Integer(Vec<i64>),
+    Text(Vec<ByteArray>),
+}
+
+impl ParquetWriter {
+    fn new(out: Sink, columns: &[(&str, Kind)]) -> anyhow::Result<Self> {
+        let fields = columns
+            .iter()
+            .map(|(name, kind)| {
+                let field = match kind {
+                    Kind::Integer => Type::primitive_type_builder(name, PhysicalType::INT64),
+                    Kind::Text => Type::primitive_type_builder(name, PhysicalType::BYTE_ARRAY)
+                        .with_converted_type(ConvertedType::UTF8),
+                };
+                field
+                    .with_repetition(Repetition::REQUIRED)
+                    .build()
+                    .map(Arc::new)
+            })
+            .collect::<Result<_, _>>()
+            .context("Building Parquet schema")?;
+        let schema = Type::group_type_builder("schema")
+            .with_fields(fields)
+            .build()
+            .context("Building Parquet schema")?;
+        let properties = WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .build();
+        let writer = SerializedFileWriter::new(out, Arc::new(schema), Arc::new(properties))
+            .context("Creating Parquet writer")?;
+
+        let columns = columns
+            .iter()
+            .map(|(_, kind)| match kind {
+                Kind::Integer => Column::Integer(Vec::new()),
+                Kind::Text => Column::Text(Vec::new()),
+            })
+            .collect();
+
+        Ok(Self {
+            writer,
+            columns,
+            rows: 0,
+        })
+    }
+
+    fn write_row(&mut self, row: Vec<Value>) -> anyhow::Result<()> {
+        for (column, value) in self.columns.iter_mut().zip(row) {
+            match (column, value) {
+                (Column::Integer(values), Value::Integer(value)) => {
+                    values.push(i64::try_from(value).context("Integer out of range")?)
+                }
+                (Column::Text(values), Value::Text(value)) => {
+                    values.push(ByteArray::from(value.into_bytes()))
+                }
+                _ => anyhow::bail!("Value does not match column type"),
+            }
+        }
+        self.rows += 1;
+
+        if self.rows == ROW_GROUP_SIZE {
+            self.write_row_group()?;
+        }
+
+        Ok(())
+    }
+
+    fn write_row_group(&mut self) -> anyhow::Result<()> {
+        let mut row_group = self.writer.next_row_group().context("Creating row group")?;
+        for column in &mut self.columns {
+            let mut writer = row_group
+                .next_column()
+                .context("Creating column writer")?
+                .context("Schema has fewer columns than the rows")?;
+            match column {
+                Column::Integer(values) => {
+                    writer
+                        .typed::<Int64Type>()
+                        .write_batch(values, None, None)?;
+                    values.clear();
+                }
+                Column::Text(values) => {
+                    writer
+                        .typed::<ByteArrayType>()
+                        .write_batch(values, None, None)?;
+                    values.clear();
+                }
+            }
+            writer.close().context("Closing column writer")?;
+        }
+        row_group.close().context("Closing row group")?;
+        self.rows = 0;
+
+        Ok(())
+    }
+
+    fn finish(mut self) -> anyhow::Result<()> {
+        if self.rows > 0 {
+            self.write_row_group()?;
+        }
+
+        let mut out = self.writer.into_inner().context("Writing Parquet footer")?;
+        out.flush().context("Flushing output")
+    }
+}
+
+fn transaction_type(variant: &TransactionVariant) -> &'static str {
+    match variant {
+        TransactionVariant::DeclareV0(_)
+        | TransactionVariant::DeclareV1(_)
+        | TransactionVariant::DeclareV2(_)
+        | TransactionVariant::DeclareV3(_) => "DECLARE",
+        TransactionVariant::Deploy(_) => "DEPLOY",
+        TransactionVariant::DeployAccountV0V1(_) | TransactionVariant::DeployAccountV3(_) => {
+            "DEPLOY_ACCOUNT"
+        }
+        TransactionVariant::InvokeV0(_)
+        | TransactionVariant::InvokeV1(_)
+        | TransactionVariant::InvokeV3(_) => "INVOKE",
+        TransactionVariant::L1Handler(_) => "L1_HANDLER",
+    }
+}
+
+fn join_felts<'a>(felts: impl Iterator<Item = &'a pathfinder_crypto::Felt>) -> String {
+    felts.map(|f| f.to_string()).collect::<Vec<_>>().join(" ")
+}
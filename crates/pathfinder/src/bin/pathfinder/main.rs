@@ -1,28 +1,36 @@
 #![deny(rust_2018_idioms)]
 
 use anyhow::Context;
+use futures::stream::{FuturesUnordered, StreamExt};
 use metrics_exporter_prometheus::PrometheusBuilder;
 
-use pathfinder_common::{consts::VERGEN_GIT_DESCRIBE, BlockNumber, Chain, ChainId, EthereumChain};
+use pathfinder_common::transaction::Transaction;
+use pathfinder_common::{
+    consts::VERGEN_GIT_DESCRIBE, BlockHash, BlockNumber, Chain, ChainId, EthereumChain,
+};
 use pathfinder_ethereum::{EthereumApi, EthereumClient};
+use pathfinder_lib::devnet::DevnetContext;
 use pathfinder_lib::monitoring::{self};
 use pathfinder_lib::state;
 use pathfinder_lib::state::SyncContext;
-use pathfinder_rpc::context::WebsocketContext;
+use pathfinder_rpc::context::{QueueLimit, WebsocketContext};
+use pathfinder_rpc::local_sequencer::LocalSequencer;
+use pathfinder_rpc::peer_admin::PeerAdminRequest;
 use pathfinder_rpc::SyncState;
-use pathfinder_storage::Storage;
+use pathfinder_storage::{JournalMode, Storage};
 use primitive_types::H160;
 use starknet_gateway_client::GatewayApi;
 use std::net::SocketAddr;
-use std::num::NonZeroU32;
+use std::num::{NonZeroU32, NonZeroUsize};
 use std::path::PathBuf;
 use std::sync::{atomic::AtomicBool, Arc};
 use tokio::signal::unix::{signal, SignalKind};
-use tracing::info;
+use tracing::{info, Instrument};
 
 use crate::config::NetworkConfig;
 
 mod config;
+mod export;
 mod update;
 
 // The Cairo VM allocates felts on the stack, so during execution it's making
@@ -32,21 +40,26 @@ mod update;
 static GLOBAL: jemallocator::Jemalloc = jemallocator::Jemalloc;
 
 fn main() -> anyhow::Result<()> {
+    if std::env::var_os("RUST_LOG").is_none() {
+        // Disable all dependency logs by default.
+        std::env::set_var("RUST_LOG", "pathfinder=info");
+    }
+
+    let config = match config::Command::parse() {
+        config::Command::Node(config) => *config,
+        config::Command::Export(config) => return export::run(config),
+    };
+
     tokio::runtime::Builder::new_multi_thread()
         .enable_all()
         .thread_stack_size(8 * 1024 * 1024)
         .build()
         .unwrap()
-        .block_on(async { async_main().await })
+        .block_on(async { async_main(config).await })
 }
 
-async fn async_main() -> anyhow::Result<()> {
-    if std::env::var_os("RUST_LOG").is_none() {
-        // Disable all dependency logs by default.
-        std::env::set_var("RUST_LOG", "pathfinder=info");
-    }
-
-    let config = config::Config::parse();
+async fn async_main(mut config: config::Config) -> anyhow::Result<()> {
+    let additional_networks = std::mem::take(&mut config.additional_networks);
 
     setup_tracing(config.color, config.debug.pretty_log);
 
@@ -68,12 +81,15 @@ async fn async_main() -> anyhow::Result<()> {
     // A readiness flag which is used to indicate that pathfinder is ready via monitoring.
     let readiness = Arc::new(AtomicBool::new(false));
 
-    let ethereum = EthereumContext::setup(config.ethereum.url, config.ethereum.password)
-        .await
-        .context("Creating Ethereum context")?;
+    let ethereum = EthereumContext::setup(
+        config.ethereum.url.clone(),
+        config.ethereum.password.clone(),
+    )
+    .await
+    .context("Creating Ethereum context")?;
 
     // Use the default starknet network if none was configured.
-    let network = match config.network {
+    let network = match config.network.take() {
         Some(network) => network,
         None => ethereum
             .default_network()
@@ -82,38 +98,282 @@ async fn async_main() -> anyhow::Result<()> {
 
     // Spawn monitoring if configured.
     if let Some(address) = config.monitor_address {
-        let network_label = match &network {
-            NetworkConfig::Mainnet => "mainnet",
-            NetworkConfig::GoerliTestnet => "testnet-goerli",
-            NetworkConfig::GoerliIntegration => "integration-goerli",
-            NetworkConfig::SepoliaTestnet => "testnet-sepolia",
-            NetworkConfig::SepoliaIntegration => "integration-sepolia",
-            NetworkConfig::Custom { .. } => "custom",
-        };
-        spawn_monitoring(network_label, address, readiness.clone())
+        spawn_monitoring(network_label(&network), address, readiness.clone())
             .await
             .context("Starting monitoring task")?;
     }
 
     let pathfinder_context = PathfinderContext::configure_and_proxy_check(
         network,
-        config.data_directory,
-        config.gateway_api_key,
+        config.data_directory.clone(),
+        config.gateway_api_key.clone(),
+        config.gateway_headers.clone(),
         config.gateway_timeout,
+        config.gateway_log_unknown_fields,
     )
     .await
     .context("Configuring pathfinder")?;
 
     verify_networks(pathfinder_context.network, ethereum.chain)?;
+    verify_chain_configuration(&pathfinder_context, &ethereum.client)
+        .await
+        .context("Verifying network configuration")?;
+
+    let network = pathfinder_context.network;
+    let mut databases = vec![pathfinder_context.database.clone()];
+    let mut rpc_addresses = vec![config.rpc_address];
+
+    let mut bloom_filter_caches = Vec::new();
+
+    let mut handles = start_network(
+        &config,
+        pathfinder_context,
+        ethereum.client,
+        config.rpc_address,
+        Some(config.p2p.clone()),
+        config.sync_trusted_block,
+        config.devnet.clone(),
+        tracing::Span::none(),
+    )
+    .await?;
+
+    let mut networks = FuturesUnordered::new();
+    bloom_filter_caches.extend(handles.bloom_filter_cache.take());
+    networks.push(handles.exited(network));
+
+    for additional in additional_networks {
+        let label = network_label(&additional.network);
+        let ethereum =
+            EthereumContext::setup(additional.ethereum.url, additional.ethereum.password)
+                .await
+                .context("Creating Ethereum context for additional network")?;
+
+        let pathfinder_context = PathfinderContext::configure_and_proxy_check(
+            additional.network,
+            config.data_directory.clone(),
+            additional.gateway_api_key,
+            config.gateway_headers.clone(),
+            config.gateway_timeout,
+            config.gateway_log_unknown_fields,
+        )
+        .await
+        .context("Configuring additional network")?;
+        let network = pathfinder_context.network;
+
+        anyhow::ensure!(
+            !databases.contains(&pathfinder_context.database),
+            "{network} Starknet is configured more than once"
+        );
+        anyhow::ensure!(
+            !rpc_addresses.contains(&additional.rpc_address),
+            "HTTP-RPC address {} of {network} Starknet is already used by another network",
+            additional.rpc_address
+        );
+        databases.push(pathfinder_context.database.clone());
+        rpc_addresses.push(additional.rpc_address);
+
+        verify_networks(network, ethereum.chain)?;
+        verify_chain_configuration(&pathfinder_context, &ethereum.client)
+            .await
+            .with_context(|| format!("Verifying {network} network configuration"))?;
+
+        // Metrics recorded within this span are labelled with the additional network.
+        let span = tracing::info_span!("network", network = label);
+        let mut handles = start_network(
+            &config,
+            pathfinder_context,
+            ethereum.client,
+            additional.rpc_address,
+            None,
+            None,
+            None,
+            span.clone(),
+        )
+        .instrument(span)
+        .await
+        .with_context(|| format!("Starting {network} network"))?;
+
+        bloom_filter_caches.extend(handles.bloom_filter_cache.take());
+        networks.push(handles.exited(network));
+    }
+
+    tokio::spawn(update::poll_github_for_releases());
+
+    let mut term_signal = signal(SignalKind::terminate())?;
+    let mut int_signal = signal(SignalKind::interrupt())?;
+
+    // We are now ready.
+    readiness.store(true, std::sync::atomic::Ordering::Relaxed);
+
+    // Monitor our critical spawned process tasks.
+    tokio::select! {
+        _ = networks.next() => {
+            anyhow::bail!("Unexpected shutdown");
+        }
+        _ = term_signal.recv() => {
+            tracing::info!("TERM signal received, exiting gracefully");
+        }
+        _ = int_signal.recv() => {
+            tracing::info!("INT signal received, exiting gracefully");
+        }
+    }
+
+    for cache in bloom_filter_caches {
+        tokio::task::spawn_blocking(move || cache.save())
+            .await
+            .context("Saving event Bloom filter cache")?;
+    }
+
+    Ok(())
+}
+
+/// Handles of the critical tasks of a single network.
+struct NetworkHandles {
+    sync: tokio::task::JoinHandle<anyhow::Result<()>>,
+    rpc: tokio::task::JoinHandle<anyhow::Result<()>>,
+    p2p: tokio::task::JoinHandle<()>,
+    /// Set if the event Bloom filter cache is to be saved on shutdown.
+    bloom_filter_cache: Option<BloomFilterCacheFile>,
+}
+
+/// The file which the event Bloom filter cache of a database is persisted to across restarts,
+/// see `--storage.persist-bloom-filter-cache`.
+#[derive(Clone)]
+struct BloomFilterCacheFile {
+    storage: Storage,
+    path: PathBuf,
+    span: tracing::Span,
+}
+
+impl BloomFilterCacheFile {
+    fn new(storage: Storage, database: &std::path::Path, span: tracing::Span) -> Self {
+        let stem = database
+            .file_stem()
+            .map(|x| x.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "pathfinder".to_owned());
+        let path = database.with_file_name(format!("{stem}-bloom-filters.cache"));
+
+        Self {
+            storage,
+            path,
+            span,
+        }
+    }
+
+    /// Restores the cache saved on the last shutdown, if any.
+    fn load(&self) {
+        let _g = self.span.enter();
+        if !self.path.exists() {
+            return;
+        }
+
+        match self.storage.load_bloom_filter_cache(&self.path) {
+            Ok(count) => info!(%count, "Restored event Bloom filter cache"),
+            Err(e) => tracing::warn!(error=?e, "Failed to restore event Bloom filter cache"),
+        }
+    }
+
+    fn save(&self) {
+        let _g = self.span.enter();
+        match self.storage.save_bloom_filter_cache(&self.path) {
+            Ok(count) => info!(%count, path=?self.path, "Saved event Bloom filter cache"),
+            Err(e) => tracing::warn!(error=?e, "Failed to save event Bloom filter cache"),
+        }
+    }
+}
+
+impl NetworkHandles {
+    /// Completes once any of the tasks ends, which is always unexpected.
+    async fn exited(self, network: Chain) {
+        tokio::select! {
+            result = self.sync => {
+                match result {
+                    Ok(task_result) => tracing::error!(%network, "Sync process ended unexpected with: {:?}", task_result),
+                    Err(err) => tracing::error!(%network, "Sync process ended unexpected; failed to join task handle: {:?}", err),
+                }
+            }
+            result = self.rpc => {
+                match result {
+                    Ok(_) => tracing::error!(%network, "RPC server process ended unexpectedly"),
+                    Err(err) => tracing::error!(%network, error=%err, "RPC server process ended unexpectedly"),
+                }
+            }
+            result = self.p2p => {
+                match result {
+                    Ok(_) => tracing::error!(%network, "P2P process ended unexpectedly"),
+                    Err(err) => tracing::error!(%network, error=%err, "P2P process ended unexpectedly"),
+                }
+            }
+        }
+    }
+}
+
+/// Sets up the database of a network and spawns its sync, RPC and p2p tasks.
+///
+/// P2P is only started if its configuration is given. With a `devnet` configuration, blocks are
+/// produced by this node instead of being synced. The sync task runs within `span`.
+#[allow(clippy::too_many_arguments)]
+async fn start_network(
+    config: &config::Config,
+    pathfinder_context: PathfinderContext,
+    ethereum: EthereumClient,
+    rpc_address: SocketAddr,
+    p2p: Option<config::P2PConfig>,
+    trusted_block: Option<state::l2::TrustedBlock>,
+    devnet: Option<pathfinder_lib::devnet::Config>,
+    span: tracing::Span,
+) -> anyhow::Result<NetworkHandles> {
+    let available_parallelism = std::thread::available_parallelism()?;
 
     // Setup and verify database
 
-    let storage_manager = Storage::migrate(
+    let split = split_databases(
+        config.trie_directory.as_deref(),
+        config.transaction_directory.as_deref(),
+        &pathfinder_context.database,
+    );
+    #[cfg(feature = "sqlcipher")]
+    let storage_manager = match config.encryption_key.clone() {
+        Some(key) => Storage::migrate_encrypted(
+            pathfinder_context.database.clone(),
+            config.sqlite_wal,
+            config.event_bloom_filter_cache_size.get(),
+            key,
+            split,
+            config.migration,
+        ),
+        None => Storage::migrate_split(
+            pathfinder_context.database.clone(),
+            config.sqlite_wal,
+            config.event_bloom_filter_cache_size.get(),
+            split,
+            config.migration,
+        ),
+    };
+    #[cfg(not(feature = "sqlcipher"))]
+    let storage_manager = Storage::migrate_split(
         pathfinder_context.database.clone(),
         config.sqlite_wal,
         config.event_bloom_filter_cache_size.get(),
-    )
-    .unwrap();
+        split,
+        config.migration,
+    );
+    let storage_manager = storage_manager
+        .with_context(|| format!("Opening database {}", pathfinder_context.database.display()))?;
+
+    // Background checkpointing replaces SQLite's automatic checkpoints, so it must be set up
+    // before any connections are created.
+    let wal_checkpoint = config
+        .wal_checkpoint
+        .filter(|_| matches!(config.sqlite_wal, JournalMode::WAL));
+    let storage_manager = match wal_checkpoint {
+        Some(_) => storage_manager.without_wal_autocheckpoint(),
+        None => storage_manager,
+    };
+    let storage_manager = match config.slow_query_threshold {
+        Some(threshold) => storage_manager.with_slow_query_threshold(threshold),
+        None => storage_manager,
+    };
     let sync_storage = storage_manager
         // 5 is enough for normal sync operations, and then `available_parallelism` for
         // the rayon thread pool workers to use.
@@ -125,6 +385,88 @@ Hint: This is usually caused by exceeding the file descriptor limit of your syst
       Try increasing the file limit to using `ulimit` or similar tooling.",
         )?;
 
+    if let Some(trie_layout) = config.trie_layout {
+        let mut db = sync_storage
+            .connection()
+            .context("Creating database connection")?;
+        let tx = db.transaction().context("Creating database transaction")?;
+        tx.set_trie_layout(trie_layout)
+            .context("Setting trie layout")?;
+        tx.commit().context("Committing database transaction")?;
+    }
+
+    // Blocks left incomplete by an unclean shutdown are rolled back, so that sync downloads them
+    // again, and data stored without its block is removed. Devnet blocks cannot be downloaded
+    // again, so they are left as is.
+    if config.is_sync_enabled && devnet.is_none() {
+        let mut db = sync_storage
+            .connection()
+            .context("Creating database connection")?;
+        let tx = db.transaction().context("Creating database transaction")?;
+        let orphaned = tx.remove_orphaned_data().context(
+            r"Removing data of blocks which were not stored.
+
+Hint: This is usually caused by database files which do not belong together, such as a trie or
+      transaction database from another node or an older backup.",
+        )?;
+        let latest = tx
+            .block_id(pathfinder_storage::BlockId::Latest)
+            .context("Querying latest block")?;
+        let rolled_back = tx
+            .roll_back_incomplete_blocks(trusted_block.map(|trusted_block| trusted_block.number))
+            .context(
+                r"Rolling back incomplete blocks.
+
+Hint: This is usually caused by database files which do not belong together, such as a trie or
+      transaction database from another node or an older backup.",
+            )?;
+        tx.commit().context("Committing database transaction")?;
+
+        if !orphaned.is_empty() {
+            tracing::warn!(
+                transactions=%orphaned.transactions,
+                event_filters=%orphaned.event_filters,
+                trie_nodes=%orphaned.trie_nodes,
+                "Removed data of blocks which were not stored"
+            );
+        }
+        if let (Some(block), Some((latest, _))) = (rolled_back, latest) {
+            tracing::warn!(
+                from=%block.number, to=%latest, missing=%block.missing.as_str(),
+                "Rolled back blocks which were only partially stored"
+            );
+        }
+    }
+
+    if let Some(wal_checkpoint) = wal_checkpoint {
+        let storage = storage_manager
+            .create_pool(NonZeroU32::new(1).unwrap())
+            .context("Creating database connection pool for WAL checkpoints")?;
+        tokio::spawn(
+            pathfinder_lib::wal_checkpoint::run(storage, wal_checkpoint).instrument(span.clone()),
+        );
+    }
+
+    if let Some(backup) = config.backup.clone() {
+        let storage = storage_manager
+            .create_pool(NonZeroU32::new(1).unwrap())
+            .context("Creating database connection pool for backups")?;
+        tokio::spawn(pathfinder_lib::backup::run(storage, backup).instrument(span.clone()));
+    }
+
+    let bloom_filter_cache = if config.persist_bloom_filter_cache {
+        let storage = storage_manager
+            .create_pool(NonZeroU32::new(1).unwrap())
+            .context("Creating database connection pool for the Bloom filter cache")?;
+        let cache = BloomFilterCacheFile::new(storage, &pathfinder_context.database, span.clone());
+        // Restored in the background, since a large cache takes a while to read.
+        let load = cache.clone();
+        tokio::task::spawn_blocking(move || load.load());
+        Some(cache)
+    } else {
+        None
+    };
+
     // Set the rpc file connection limit to a fraction of the RPC connections.
     // Having this be too large is counter productive as disk IO will then slow down
     // all queries.
@@ -150,8 +492,9 @@ Hint: This is usually caused by exceeding the file descriptor limit of your syst
         .create_pool(execution_storage_pool_size)
         .context(r"")?;
 
+    // Inbound sync requests are served concurrently, each holding a connection while streaming.
     let p2p_storage = storage_manager
-        .create_pool(NonZeroU32::new(1).unwrap())
+        .create_pool(NonZeroU32::new(10).unwrap())
         .context(
             r"Creating database connection pool for p2p
 
@@ -168,15 +511,39 @@ Hint: This is usually caused by exceeding the file descriptor limit of your syst
     .await
     .context("Verifying database")?;
 
+    let fork = match devnet.as_ref().and_then(|devnet| devnet.fork.as_ref()) {
+        Some(fork) => Some(
+            pathfinder_lib::devnet::fork::connect(fork, sync_storage.clone())
+                .await
+                .context("Forking devnet")?,
+        ),
+        None => None,
+    };
+
     let sync_state = Arc::new(SyncState::default());
 
     let (tx_pending, rx_pending) = tokio::sync::watch::channel(Default::default());
 
     let rpc_config = pathfinder_rpc::context::RpcConfig {
         batch_concurrency_limit: config.rpc_batch_concurrency_limit,
+        batch_size_limit: config.rpc_batch_size_limit,
         get_events_max_blocks_to_scan: config.get_events_max_blocks_to_scan,
         get_events_max_uncached_bloom_filters_to_load: config
             .get_events_max_uncached_bloom_filters_to_load,
+        get_events_max_page_size: config.get_events_max_page_size,
+        get_events_max_keys_in_filter: config.get_events_max_keys_in_filter,
+        get_events_max_query_cost: config.get_events_max_query_cost,
+        execution_max_steps: config.rpc_execution_max_steps,
+        execution_timeout: config.rpc_execution_timeout,
+        execution_concurrency: NonZeroUsize::new(execution_storage_pool_size.get() as usize)
+            .expect("The execution concurrency should be non-zero"),
+        execution_queue_size: config.rpc_execution_queue_size,
+        erc20_balances: config.rpc_erc20_balances,
+        query: config.rpc_query,
+        table_sizes: config.rpc_table_sizes,
+        l1_gas_price_floor: config.rpc_l1_gas_price_floor,
+        trace_profiles: config.rpc_trace_profiles,
+        max_signature_length: config.rpc_max_signature_length,
     };
 
     let context = pathfinder_rpc::context::RpcContext::new(
@@ -189,11 +556,47 @@ Hint: This is usually caused by exceeding the file descriptor limit of your syst
         rpc_config,
     );
 
+    // Transactions sequenced by a devnet never reach the gateway, so there is nothing to poll.
+    let (local_sequencer, submissions) = LocalSequencer::new();
+    let context = if devnet.is_some() {
+        context.with_local_sequencer(local_sequencer)
+    } else {
+        tokio::spawn(
+            pathfinder_rpc::mempool::poll(
+                context.mempool.clone(),
+                context.storage.clone(),
+                context.sequencer.clone().with_own_circuit_breakers(),
+            )
+            .instrument(span.clone()),
+        );
+        context
+    };
+    let context = match fork.clone() {
+        Some(fork) => context.with_fork(fork),
+        None => context,
+    };
+    let mempool = context.mempool.clone();
+    let submitted_transactions = context.mempool.subscribe();
+
+    let (peer_admin, peer_admin_requests) = pathfinder_rpc::peer_admin::PeerAdmin::new();
+    let context = if p2p.as_ref().is_some_and(config::P2PConfig::admin_rpc) {
+        context.with_peer_admin(peer_admin)
+    } else {
+        context
+    };
+
     let context = if config.websocket.enabled {
-        context.with_websockets(WebsocketContext::new(
-            config.websocket.socket_buffer_capacity,
-            config.websocket.topic_sender_capacity,
-        ))
+        context.with_websockets(
+            WebsocketContext::new(
+                config.websocket.socket_buffer_capacity,
+                config.websocket.topic_sender_capacity,
+            )
+            .with_max_connections_per_ip(config.websocket.max_connections_per_ip)
+            .with_pending_transaction_queue(QueueLimit {
+                capacity: config.websocket.pending_transactions_queue_size,
+                drop_policy: config.websocket.pending_transactions_drop_policy.into(),
+            }),
+        )
     } else {
         context
     };
@@ -205,36 +608,103 @@ Hint: This is usually caused by exceeding the file descriptor limit of your syst
         config::RpcVersion::V07 => pathfinder_rpc::RpcVersion::V07,
     };
 
-    let rpc_server = pathfinder_rpc::RpcServer::new(config.rpc_address, context, default_version);
-    let rpc_server = match config.rpc_cors_domains {
+    let rpc_server = pathfinder_rpc::RpcServer::new(rpc_address, context, default_version);
+    let rpc_server = match config.rpc_cors_domains.clone() {
         Some(allowed_origins) => rpc_server.with_cors(allowed_origins),
         None => rpc_server,
     };
 
-    let (p2p_handle, gossiper) =
-        start_p2p(pathfinder_context.network_id, p2p_storage, config.p2p).await?;
-
-    let sync_context = SyncContext {
-        storage: sync_storage,
-        ethereum: ethereum.client,
-        chain: pathfinder_context.network,
-        chain_id: pathfinder_context.network_id,
-        core_address: pathfinder_context.l1_core_address,
-        sequencer: pathfinder_context.gateway,
-        state: sync_state.clone(),
-        head_poll_interval: config.poll_interval,
-        pending_data: tx_pending,
-        // Currently p2p does not perform block hash and state commitment verification if p2p header lacks state commitment
-        block_validation_mode: state::l2::BlockValidationMode::Strict,
-        websocket_txs: rpc_server.get_topic_broadcasters().cloned(),
-        block_cache_size: 1_000,
-        restart_delay: config.debug.restart_delay,
-        verify_tree_hashes: config.verify_tree_hashes,
-        gossiper,
+    let (p2p_handle, gossiper, p2p_sync) = match p2p {
+        Some(p2p) => {
+            let sync = P2PSyncContext {
+                storage: sync_storage.clone(),
+                ethereum: ethereum.clone(),
+                core_address: pathfinder_context.l1_core_address,
+                gateway: pathfinder_context.gateway.clone(),
+                chain: pathfinder_context.network,
+            };
+            start_p2p(
+                pathfinder_context.network_id,
+                p2p_storage,
+                p2p,
+                submitted_transactions,
+                peer_admin_requests,
+                sync,
+            )
+            .await?
+        }
+        None => (
+            tokio::spawn(std::future::pending()),
+            Default::default(),
+            None,
+        ),
     };
 
-    let sync_handle = if config.is_sync_enabled {
-        tokio::spawn(state::sync(sync_context, state::l1::sync, state::l2::sync))
+    if let (true, Some(trusted_block), Some(backfill)) =
+        (config.is_sync_enabled, trusted_block, config.backfill)
+    {
+        let backfill = state::backfill::run(
+            sync_storage.clone(),
+            pathfinder_context.gateway.clone(),
+            pathfinder_context.network,
+            pathfinder_context.network_id,
+            trusted_block,
+            backfill,
+        );
+        tokio::spawn(
+            async move {
+                if let Err(error) = backfill.await {
+                    tracing::error!(reason=?error, "Backfilling blocks failed");
+                }
+            }
+            .instrument(span.clone()),
+        );
+    }
+
+    let sync_handle = if let Some(devnet) = devnet {
+        let devnet_context = DevnetContext {
+            storage: sync_storage,
+            chain_id: pathfinder_context.network_id,
+            config: devnet,
+            submissions,
+            pending_data: tx_pending,
+            mempool,
+            websocket_txs: rpc_server.get_topic_broadcasters().cloned(),
+            fork,
+        };
+        tokio::spawn(pathfinder_lib::devnet::run(devnet_context).instrument(span))
+    } else if config.is_sync_enabled {
+        let sync_context = SyncContext {
+            storage: sync_storage,
+            ethereum,
+            chain: pathfinder_context.network,
+            chain_id: pathfinder_context.network_id,
+            core_address: pathfinder_context.l1_core_address,
+            sequencer: pathfinder_context.gateway,
+            state: sync_state.clone(),
+            head_poll_interval: config.poll_interval,
+            pending_data: tx_pending,
+            // Currently p2p does not perform block hash and state commitment verification if p2p header lacks state commitment
+            block_validation_mode: state::l2::BlockValidationMode::Strict,
+            websocket_txs: rpc_server.get_topic_broadcasters().cloned(),
+            block_cache_size: 1_000,
+            restart_delay: config.debug.restart_delay,
+            verify_tree_hashes: config.verify_tree_hashes,
+            gossiper,
+            block_prefetch: config.block_prefetch,
+            trusted_block,
+            halt_on_l1_state_root_mismatch: config.halt_on_l1_state_root_mismatch,
+            stall_timeout: config.sync_stall_timeout,
+        };
+        let sync = state::sync(sync_context, state::l1::sync, state::l2::sync);
+        let sync = async move {
+            // The blocks secured by Ethereum are synced from peers first, if enabled.
+            if let Some(p2p_sync) = p2p_sync {
+                p2p_sync.await.context("Syncing from p2p network")?;
+            }
+            sync.await
+        };
+        tokio::spawn(sync.instrument(span))
     } else {
         tokio::spawn(std::future::pending())
     };
@@ -242,6 +712,8 @@ Hint: This is usually caused by exceeding the file descriptor limit of your syst
     let rpc_handle = if config.is_rpc_enabled {
         let (rpc_handle, local_addr) = rpc_server
             .with_max_connections(config.max_rpc_connections.get())
+            .with_max_request_body_size(config.rpc_max_request_body_size.get())
+            .with_request_timeout(config.rpc_request_timeout)
             .spawn()
             .context("Starting the RPC server")?;
         info!("📡 HTTP-RPC server started on: {}", local_addr);
@@ -250,45 +722,32 @@ Hint: This is usually caused by exceeding the file descriptor limit of your syst
         tokio::spawn(std::future::pending())
     };
 
-    tokio::spawn(update::poll_github_for_releases());
-
-    let mut term_signal = signal(SignalKind::terminate())?;
-    let mut int_signal = signal(SignalKind::interrupt())?;
+    Ok(NetworkHandles {
+        sync: sync_handle,
+        rpc: rpc_handle,
+        p2p: p2p_handle,
+        bloom_filter_cache,
+    })
+}
 
-    // We are now ready.
-    readiness.store(true, std::sync::atomic::Ordering::Relaxed);
+/// The separate database files of `database`, named after it so that networks sharing a directory
+/// get their own files.
+fn split_databases(
+    trie_directory: Option<&std::path::Path>,
+    transaction_directory: Option<&std::path::Path>,
+    database: &std::path::Path,
+) -> pathfinder_storage::SplitDatabases {
+    let stem = database
+        .file_stem()
+        .map(|x| x.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "pathfinder".to_owned());
+    let file = |directory: &std::path::Path, suffix: &str| {
+        directory.join(format!("{stem}-{suffix}.sqlite"))
+    };
 
-    // Monitor our critical spawned process tasks.
-    tokio::select! {
-        result = sync_handle => {
-            match result {
-                Ok(task_result) => tracing::error!("Sync process ended unexpected with: {:?}", task_result),
-                Err(err) => tracing::error!("Sync process ended unexpected; failed to join task handle: {:?}", err),
-            }
-            anyhow::bail!("Unexpected shutdown");
-        }
-        result = rpc_handle => {
-            match result {
-                Ok(_) => tracing::error!("RPC server process ended unexpectedly"),
-                Err(err) => tracing::error!(error=%err, "RPC server process ended unexpectedly"),
-            }
-            anyhow::bail!("Unexpected shutdown");
-        }
-        result = p2p_handle => {
-            match result {
-                Ok(_) => tracing::error!("P2P process ended unexpectedly"),
-                Err(err) => tracing::error!(error=%err, "P2P process ended unexpectedly"),
-            }
-            anyhow::bail!("Unexpected shutdown");
-        }
-        _ = term_signal.recv() => {
-            tracing::info!("TERM signal received, exiting gracefully");
-            Ok(())
-        }
-        _ = int_signal.recv() => {
-            tracing::info!("INT signal received, exiting gracefully");
-            Ok(())
-        }
+    pathfinder_storage::SplitDatabases {
+        tries: trie_directory.map(|x| file(x, "tries")),
+        transactions: transaction_directory.map(|x| file(x, "transactions")),
     }
 }
 
@@ -309,11 +768,13 @@ fn setup_tracing(color: config::Color, pretty_log: bool) {
         tracing_subscriber::registry()
             .with(fmt_layer.pretty().with_filter(filter))
             .with(console_subscriber::spawn())
+            .with(metrics_tracing_context::MetricsLayer::new())
             .init();
     } else {
         tracing_subscriber::registry()
             .with(fmt_layer.compact().with_filter(filter))
             .with(console_subscriber::spawn())
+            .with(metrics_tracing_context::MetricsLayer::new())
             .init();
     }
 }
@@ -321,6 +782,7 @@ fn setup_tracing(color: config::Color, pretty_log: bool) {
 #[cfg(not(feature = "tokio-console"))]
 fn setup_tracing(color: config::Color, pretty_log: bool) {
     use time::macros::format_description;
+    use tracing_subscriber::prelude::*;
 
     let time_fmt = format_description!("[year]-[month]-[day]T[hour]:[minute]:[second]");
     let time_fmt = tracing_subscriber::fmt::time::UtcTime::new(time_fmt);
@@ -331,10 +793,13 @@ fn setup_tracing(color: config::Color, pretty_log: bool) {
         .with_timer(time_fmt)
         .with_ansi(color.is_color_enabled());
 
+    // Makes the fields of spans available as metrics labels, see `spawn_monitoring`.
+    let metrics_layer = metrics_tracing_context::MetricsLayer::new();
+
     if pretty_log {
-        subscriber.pretty().init();
+        subscriber.pretty().finish().with(metrics_layer).init();
     } else {
-        subscriber.compact().init();
+        subscriber.compact().finish().with(metrics_layer).init();
     }
 }
 
@@ -347,14 +812,38 @@ fn permission_check(base: &std::path::Path) -> Result<(), anyhow::Error> {
     Ok(())
 }
 
+/// What syncing from the p2p network requires besides the network itself.
+#[cfg_attr(not(feature = "p2p"), allow(dead_code))]
+struct P2PSyncContext {
+    storage: Storage,
+    ethereum: EthereumClient,
+    core_address: H160,
+    gateway: starknet_gateway_client::Client,
+    chain: Chain,
+}
+
+/// Syncs the blocks secured by Ethereum from the p2p network, see [config::P2PConfig::sync].
+type P2PSync = futures::future::BoxFuture<'static, anyhow::Result<()>>;
+
+/// The p2p network task, the gossiper of new blocks and the p2p sync, if enabled.
+type P2PHandles = (
+    tokio::task::JoinHandle<()>,
+    state::Gossiper,
+    Option<P2PSync>,
+);
+
 #[cfg(feature = "p2p")]
 async fn start_p2p(
     chain_id: ChainId,
     storage: Storage,
     config: config::P2PConfig,
-) -> anyhow::Result<(tokio::task::JoinHandle<()>, state::Gossiper)> {
+    submitted_transactions: tokio::sync::broadcast::Receiver<Transaction>,
+    peer_admin_requests: tokio::sync::mpsc::Receiver<PeerAdminRequest>,
+    sync: P2PSyncContext,
+) -> anyhow::Result<P2PHandles> {
     use p2p::libp2p::identity::Keypair;
     use pathfinder_lib::p2p_network::P2PContext;
+    use pathfinder_lib::sync::p2p::{Gateway, Source, Sources};
     use serde::Deserialize;
     use std::{path::Path, time::Duration};
     use zeroize::Zeroizing;
@@ -388,6 +877,21 @@ async fn start_p2p(
         }
     };
 
+    let sync_enabled = config.sync;
+    let snap_sync = config.snap_sync;
+    let cross_validate = config.sync_cross_validate;
+    let mut sources = Sources::default();
+    for stage in &config.sync_gateway_stages {
+        let source = match stage {
+            config::SyncStage::Headers => &mut sources.headers,
+            config::SyncStage::Transactions => &mut sources.transactions,
+            config::SyncStage::Receipts => &mut sources.receipts,
+            config::SyncStage::StateUpdates => &mut sources.state_updates,
+            config::SyncStage::Classes => &mut sources.classes,
+        };
+        *source = Source::Gateway;
+    }
+
     let context = P2PContext {
         cfg: p2p::Config {
             direct_connection_timeout: Duration::from_secs(30),
@@ -403,6 +907,12 @@ async fn start_p2p(
                 max: 10,
                 interval: Duration::from_secs(1),
             },
+            inbound_sync_requests_rate_limit: p2p::RateLimit {
+                max: 300,
+                interval: Duration::from_secs(60),
+            },
+            external_addresses: config.external_addresses,
+            upnp: config.upnp,
         },
         chain_id,
         storage,
@@ -411,12 +921,32 @@ async fn start_p2p(
         listen_on: config.listen_on,
         bootstrap_addresses: config.bootstrap_addresses,
         predefined_peers: config.predefined_peers,
+        submitted_transactions,
+        peer_admin_requests,
     };
 
     let (p2p_client, _head_receiver, p2p_handle) =
         pathfinder_lib::p2p_network::start(context).await?;
 
-    Ok((p2p_handle, state::Gossiper::new(p2p_client)))
+    let p2p_sync = sync_enabled.then(|| {
+        let gateway = Gateway {
+            client: sync.gateway,
+            chain: sync.chain,
+            chain_id,
+        };
+        let p2p_sync = pathfinder_lib::sync::p2p::Sync::new(
+            sync.storage,
+            p2p_client.clone(),
+            (sync.ethereum, sync.core_address),
+        )
+        .with_gateway(gateway)
+        .with_sources(sources)
+        .with_snap_sync(snap_sync)
+        .with_cross_validation(cross_validate);
+        Box::pin(async move { p2p_sync.run().await.map_err(anyhow::Error::from) }) as P2PSync
+    });
+
+    Ok((p2p_handle, state::Gossiper::new(p2p_client), p2p_sync))
 }
 
 #[cfg(not(feature = "p2p"))]
@@ -424,22 +954,43 @@ async fn start_p2p(
     _: ChainId,
     _: Storage,
     _: config::P2PConfig,
-) -> anyhow::Result<(tokio::task::JoinHandle<()>, state::Gossiper)> {
+    _: tokio::sync::broadcast::Receiver<Transaction>,
+    _: tokio::sync::mpsc::Receiver<PeerAdminRequest>,
+    _: P2PSyncContext,
+) -> anyhow::Result<P2PHandles> {
     let join_handle = tokio::task::spawn(futures::future::pending());
 
-    Ok((join_handle, Default::default()))
+    Ok((join_handle, Default::default(), None))
 }
 
 /// Spawns the monitoring task at the given address.
+/// The value of the `network` metrics label of `network`.
+fn network_label(network: &NetworkConfig) -> &'static str {
+    match network {
+        NetworkConfig::Mainnet => "mainnet",
+        NetworkConfig::GoerliTestnet => "testnet-goerli",
+        NetworkConfig::GoerliIntegration => "integration-goerli",
+        NetworkConfig::SepoliaTestnet => "testnet-sepolia",
+        NetworkConfig::SepoliaIntegration => "integration-sepolia",
+        NetworkConfig::Custom { .. } => "custom",
+    }
+}
+
 async fn spawn_monitoring(
     network: &str,
     address: SocketAddr,
     readiness: Arc<AtomicBool>,
 ) -> anyhow::Result<tokio::task::JoinHandle<()>> {
-    let prometheus_handle = PrometheusBuilder::new()
+    use metrics_util::layers::Layer;
+
+    let recorder = PrometheusBuilder::new()
         .add_global_label("network", network)
-        .install_recorder()
-        .context("Creating Prometheus recorder")?;
+        .build_recorder();
+    let prometheus_handle = recorder.handle();
+    // The `network` field of the span of an additional network overrides the global label.
+    let recorder =
+        metrics_tracing_context::TracingContextLayer::only_allow(["network"]).layer(recorder);
+    metrics::set_boxed_recorder(Box::new(recorder)).context("Creating Prometheus recorder")?;
 
     metrics::gauge!("pathfinder_build_info", 1.0, "version" => VERGEN_GIT_DESCRIBE);
 
@@ -510,6 +1061,7 @@ mod pathfinder_context {
     use pathfinder_common::{Chain, ChainId};
     use pathfinder_ethereum::core_addr;
     use primitive_types::H160;
+    use reqwest::header::HeaderMap;
     use reqwest::Url;
     use starknet_gateway_client::Client as GatewayClient;
 
@@ -518,20 +1070,28 @@ mod pathfinder_context {
             cfg: NetworkConfig,
             data_directory: PathBuf,
             api_key: Option<String>,
+            headers: HeaderMap,
             gateway_timeout: Duration,
+            log_unknown_fields: bool,
         ) -> anyhow::Result<Self> {
             let context = match cfg {
                 NetworkConfig::Mainnet => Self {
                     network: Chain::Mainnet,
                     network_id: ChainId::MAINNET,
-                    gateway: GatewayClient::mainnet(gateway_timeout).with_api_key(api_key),
+                    gateway: GatewayClient::mainnet(gateway_timeout)
+                        .with_api_key(api_key)
+                        .with_headers(headers)
+                        .with_unknown_field_logging(log_unknown_fields),
                     database: data_directory.join("mainnet.sqlite"),
                     l1_core_address: H160::from(core_addr::MAINNET),
                 },
                 NetworkConfig::GoerliTestnet => Self {
                     network: Chain::GoerliTestnet,
                     network_id: ChainId::GOERLI_TESTNET,
-                    gateway: GatewayClient::goerli_testnet(gateway_timeout).with_api_key(api_key),
+                    gateway: GatewayClient::goerli_testnet(gateway_timeout)
+                        .with_api_key(api_key)
+                        .with_headers(headers)
+                        .with_unknown_field_logging(log_unknown_fields),
                     database: data_directory.join("goerli.sqlite"),
                     l1_core_address: H160::from(core_addr::GOERLI_TESTNET),
                 },
@@ -539,14 +1099,19 @@ mod pathfinder_context {
                     network: Chain::GoerliIntegration,
                     network_id: ChainId::GOERLI_INTEGRATION,
                     gateway: GatewayClient::goerli_integration(gateway_timeout)
-                        .with_api_key(api_key),
+                        .with_api_key(api_key)
+                        .with_headers(headers)
+                        .with_unknown_field_logging(log_unknown_fields),
                     database: data_directory.join("integration.sqlite"),
                     l1_core_address: H160::from(core_addr::GOERLI_INTEGRATION),
                 },
                 NetworkConfig::SepoliaTestnet => Self {
                     network: Chain::SepoliaTestnet,
                     network_id: ChainId::SEPOLIA_TESTNET,
-                    gateway: GatewayClient::sepolia_testnet(gateway_timeout).with_api_key(api_key),
+                    gateway: GatewayClient::sepolia_testnet(gateway_timeout)
+                        .with_api_key(api_key)
+                        .with_headers(headers)
+                        .with_unknown_field_logging(log_unknown_fields),
                     database: data_directory.join("testnet-sepolia.sqlite"),
                     l1_core_address: H160::from(core_addr::SEPOLIA_TESTNET),
                 },
@@ -554,7 +1119,9 @@ mod pathfinder_context {
                     network: Chain::SepoliaIntegration,
                     network_id: ChainId::SEPOLIA_INTEGRATION,
                     gateway: GatewayClient::sepolia_integration(gateway_timeout)
-                        .with_api_key(api_key),
+                        .with_api_key(api_key)
+                        .with_headers(headers)
+                        .with_unknown_field_logging(log_unknown_fields),
                     database: data_directory.join("integration-sepolia.sqlite"),
                     l1_core_address: H160::from(core_addr::SEPOLIA_INTEGRATION),
                 },
@@ -568,7 +1135,9 @@ mod pathfinder_context {
                     chain_id,
                     data_directory,
                     api_key,
+                    headers,
                     gateway_timeout,
+                    log_unknown_fields,
                 )
                 .await
                 .context("Configuring custom network")?,
@@ -586,14 +1155,18 @@ mod pathfinder_context {
             chain_id: String,
             data_directory: PathBuf,
             api_key: Option<String>,
+            headers: HeaderMap,
             gateway_timeout: Duration,
+            log_unknown_fields: bool,
         ) -> anyhow::Result<Self> {
             use pathfinder_crypto::Felt;
             use starknet_gateway_client::GatewayApi;
 
             let gateway = GatewayClient::with_urls(gateway, feeder, gateway_timeout)
                 .context("Creating gateway client")?
-                .with_api_key(api_key);
+                .with_api_key(api_key)
+                .with_headers(headers)
+                .with_unknown_field_logging(log_unknown_fields);
 
             let network_id =
                 ChainId(Felt::from_be_slice(chain_id.as_bytes()).context("Parsing chain ID")?);
@@ -611,7 +1184,7 @@ mod pathfinder_context {
                 x if x == core_addr::GOERLI_TESTNET => Chain::GoerliTestnet,
                 x if x == core_addr::GOERLI_INTEGRATION => Chain::GoerliIntegration,
                 x if x == core_addr::SEPOLIA_TESTNET => Chain::SepoliaTestnet,
-                x if x == core_addr::GOERLI_INTEGRATION => Chain::SepoliaIntegration,
+                x if x == core_addr::SEPOLIA_INTEGRATION => Chain::SepoliaIntegration,
                 _ => Chain::Custom,
             };
 
@@ -648,6 +1221,123 @@ fn verify_networks(starknet: Chain, ethereum: EthereumChain) -> anyhow::Result<(
     Ok(())
 }
 
+/// Errors if the configured chain ID, the gateway's genesis block and Starknet core contract and
+/// the Ethereum network do not agree on the Starknet network.
+async fn verify_chain_configuration(
+    context: &PathfinderContext,
+    ethereum: &EthereumClient,
+) -> anyhow::Result<()> {
+    // The chain ID of a built-in network is compiled in, but that of a custom gateway proxying a
+    // built-in network is configured by the user.
+    let expected_chain_id = match context.network {
+        Chain::Mainnet => Some(ChainId::MAINNET),
+        Chain::GoerliTestnet => Some(ChainId::GOERLI_TESTNET),
+        Chain::GoerliIntegration => Some(ChainId::GOERLI_INTEGRATION),
+        Chain::SepoliaTestnet => Some(ChainId::SEPOLIA_TESTNET),
+        Chain::SepoliaIntegration => Some(ChainId::SEPOLIA_INTEGRATION),
+        Chain::Custom => None,
+    };
+    if let Some(expected) = expected_chain_id {
+        anyhow::ensure!(
+            context.network_id == expected,
+            "Configured chain ID ({}) does not match the expected chain ID ({}) for {} Starknet",
+            context.network_id.to_hex_str(),
+            expected.to_hex_str(),
+            context.network
+        );
+    }
+
+    // The gateway must serve the chain of the network, which is identified by its genesis block.
+    if let (Some(expected), Some(gateway_genesis)) = (
+        known_genesis_hash(context.network),
+        gateway_genesis_hash(&context.gateway).await,
+    ) {
+        anyhow::ensure!(
+            gateway_genesis == expected,
+            "Gateway's genesis block ({}) does not match the expected genesis block ({}) for {} \
+             Starknet",
+            gateway_genesis,
+            expected,
+            context.network
+        );
+    }
+
+    // The core contract of a custom network is taken from its gateway in the first place, while
+    // that of a built-in network is compiled in. An unavailable gateway does not prevent startup,
+    // as the sync retries its requests anyway.
+    if context.network != Chain::Custom {
+        match context.gateway.eth_contract_addresses().await {
+            Ok(addresses) => {
+                let gateway_core_address = addresses.starknet.0;
+                anyhow::ensure!(
+                    gateway_core_address == context.l1_core_address,
+                    "Gateway's Starknet core contract ({:?}) does not match the expected contract ({:?}) for {} Starknet",
+                    gateway_core_address,
+                    context.l1_core_address,
+                    context.network
+                );
+            }
+            Err(error) => {
+                tracing::warn!(
+                    %error,
+                    "Downloading Starknet L1 address from gateway failed, skipping its verification"
+                );
+            }
+        }
+    }
+
+    ethereum
+        .get_starknet_state(&context.l1_core_address)
+        .await
+        .with_context(|| {
+            format!(
+                r"Querying Starknet core contract {:?} on Ethereum.
+
+Hint: Make sure the provided ethereum.url points to the Ethereum network of the Starknet network.",
+                context.l1_core_address
+            )
+        })?;
+
+    Ok(())
+}
+
+/// The genesis block hash of a built-in network.
+fn known_genesis_hash(network: Chain) -> Option<BlockHash> {
+    use pathfinder_common::consts::{
+        GOERLI_INTEGRATION_GENESIS_HASH, GOERLI_TESTNET_GENESIS_HASH, MAINNET_GENESIS_HASH,
+        SEPOLIA_INTEGRATION_GENESIS_HASH, SEPOLIA_TESTNET_GENESIS_HASH,
+    };
+
+    match network {
+        Chain::Mainnet => Some(MAINNET_GENESIS_HASH),
+        Chain::GoerliTestnet => Some(GOERLI_TESTNET_GENESIS_HASH),
+        Chain::GoerliIntegration => Some(GOERLI_INTEGRATION_GENESIS_HASH),
+        Chain::SepoliaTestnet => Some(SEPOLIA_TESTNET_GENESIS_HASH),
+        Chain::SepoliaIntegration => Some(SEPOLIA_INTEGRATION_GENESIS_HASH),
+        Chain::Custom => None,
+    }
+}
+
+/// Downloads the genesis block hash from the gateway. An unavailable gateway does not prevent
+/// startup, as the sync retries its requests anyway, so `None` is returned after a warning.
+async fn gateway_genesis_hash(
+    gateway_client: &starknet_gateway_client::Client,
+) -> Option<BlockHash> {
+    match gateway_client
+        .block_header(BlockNumber::GENESIS.into())
+        .await
+    {
+        Ok((_, hash)) => Some(hash),
+        Err(error) => {
+            tracing::warn!(
+                %error,
+                "Downloading genesis block from gateway failed, skipping its verification"
+            );
+            None
+        }
+    }
+}
+
 async fn verify_database(
     storage: &Storage,
     network: Chain,
@@ -680,27 +1370,23 @@ async fn verify_database(
             _ => Chain::Custom,
         };
 
-        match (network, db_network) {
-            (Chain::Custom, _) => {
-                // Verify against gateway.
-                let (_, gateway_hash) = gateway_client
-                    .block_header(BlockNumber::GENESIS.into())
-                    .await
-                    .context("Downloading genesis block from gateway for database verification")?;
-
-                anyhow::ensure!(
-                    database_genesis == gateway_hash,
-                    "Database genesis block does not match gateway. {} != {}",
-                    database_genesis,
-                    gateway_hash
-                );
-            }
-            (network, db_network) => anyhow::ensure!(
+        if network != Chain::Custom {
+            anyhow::ensure!(
                 network == db_network,
                 "Database ({}) does not match the expected network ({})",
                 db_network,
                 network
-            ),
+            );
+        }
+
+        // The database must also hold the chain the gateway serves.
+        if let Some(gateway_hash) = gateway_genesis_hash(gateway_client).await {
+            anyhow::ensure!(
+                database_genesis == gateway_hash,
+                "Database genesis block does not match gateway. {} != {}",
+                database_genesis,
+                gateway_hash
+            );
         }
     }
 
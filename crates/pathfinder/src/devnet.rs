@@ -0,0 +1,1012 @@
+//! Block production for devnets.
+//!
+//! In devnet mode this node sequences the transactions submitted through its own RPC API instead
+//! of syncing a network. Each [Submission] is executed on top of the open block, which is
+//! published as the pending block, and rejected with the error the gateway would report if it
+//! fails validation. The open block is sealed every [Config::block_time], unless it is empty.
+//!
+//! Blocks are produced on top of the latest block in the database, e.g. a copy of a synced
+//! network's database, or on top of a block of another network when [forked](fork).
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use anyhow::Context;
+use pathfinder_common::event::Event;
+use pathfinder_common::prelude::*;
+use pathfinder_common::receipt::{
+    BuiltinCounters, ExecutionDataAvailability, ExecutionResources, ExecutionStatus, L2ToL1Message,
+    Receipt,
+};
+use pathfinder_common::state_update::ContractClassUpdate;
+use pathfinder_common::transaction::{Transaction, TransactionVariant};
+use pathfinder_crypto::Felt;
+use pathfinder_executor::types::{
+    ExecuteInvocation, FunctionInvocation, StateDiff, TransactionTrace,
+};
+use pathfinder_executor::{
+    ExecutionState, ForkedState, L1BlobDataAvailability, TransactionExecutionError,
+};
+use pathfinder_rpc::local_sequencer::Submission;
+use pathfinder_rpc::mempool::Mempool;
+use pathfinder_rpc::{BlockEvents, PendingData, TopicBroadcasters};
+use pathfinder_storage::{Connection, Storage, TransactionBehavior};
+use primitive_types::H160;
+use starknet_gateway_types::error::{KnownStarknetErrorCode, StarknetError};
+use starknet_gateway_types::reply::{GasPrices, PendingBlock, Status};
+use tokio::sync::mpsc::Receiver;
+use tokio::sync::watch::Sender as WatchSender;
+
+use crate::state::block_hash::{
+    calculate_event_commitment, calculate_transaction_commitment, compute_final_hash,
+    TransactionCommitmentFinalHashType,
+};
+use crate::state::update_starknet_state;
+
+pub mod fork;
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// The time between the blocks produced.
+    pub block_time: Duration,
+    pub fork: Option<fork::Config>,
+}
+
+pub struct DevnetContext {
+    pub storage: Storage,
+    pub chain_id: ChainId,
+    pub config: Config,
+    pub submissions: Receiver<Submission>,
+    pub pending_data: WatchSender<PendingData>,
+    pub mempool: Mempool,
+    pub websocket_txs: Option<TopicBroadcasters>,
+    /// Set if the devnet is [forked](fork), see [fork::connect].
+    pub fork: Option<Arc<dyn ForkedState>>,
+}
+
+/// Produces blocks from the submitted transactions until all
+/// [LocalSequencer](pathfinder_rpc::local_sequencer::LocalSequencer) handles are dropped.
+pub async fn run(context: DevnetContext) -> anyhow::Result<()> {
+    let DevnetContext {
+        storage,
+        chain_id,
+        config,
+        mut submissions,
+        pending_data,
+        mempool,
+        mut websocket_txs,
+        fork,
+    } = context;
+
+    let mut connection = storage
+        .connection()
+        .context("Creating database connection")?;
+    let parent = connection
+        .transaction()
+        .context("Creating database transaction")?
+        .block_header(pathfinder_storage::BlockId::Latest)
+        .context("Querying latest block header")?
+        .context("Devnet requires a block to build on, but the database is empty")?;
+
+    tracing::info!(parent=%parent.number, "Producing devnet blocks");
+
+    let mut block = OpenBlock::on_top_of(&parent);
+    pending_data.send_replace(block.pending_data());
+
+    let mut interval = tokio::time::interval(config.block_time);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    // The first tick completes immediately.
+    interval.tick().await;
+
+    loop {
+        tokio::select! {
+            submission = submissions.recv() => {
+                let Some(submission) = submission else {
+                    return Ok(());
+                };
+
+                let hash = submission.transaction.hash;
+                let result = tokio::task::block_in_place(|| {
+                    execute(
+                        &mut connection,
+                        chain_id,
+                        &mut block,
+                        fork.as_ref(),
+                        submission.transaction,
+                        submission.class_definition,
+                    )
+                })
+                .unwrap_or_else(|error| {
+                    tracing::error!(transaction=%hash, ?error, "Executing transaction failed");
+                    Err(rejection(
+                        KnownStarknetErrorCode::TransactionFailed,
+                        "Internal error while executing the transaction".to_owned(),
+                    ))
+                });
+
+                if result.is_ok() {
+                    pending_data.send_replace(block.pending_data());
+                }
+                // The submitter may have gone away in the meantime.
+                let _ = submission.reply.send(result);
+            }
+            _ = interval.tick() => {
+                if block.transactions.is_empty() {
+                    continue;
+                }
+
+                let header = tokio::task::block_in_place(|| {
+                    let forked = fork.is_some();
+                    seal(&mut connection, &storage, forked, block, &mempool, &mut websocket_txs)
+                })?;
+                tracing::info!(
+                    number=%header.number,
+                    transactions=%header.transaction_count,
+                    "Sealed block"
+                );
+
+                block = OpenBlock::on_top_of(&header);
+                pending_data.send_replace(block.pending_data());
+            }
+        }
+    }
+}
+
+/// The block to which submitted transactions are added until it is sealed.
+struct OpenBlock {
+    /// Only the fields known before sealing are set.
+    header: BlockHeader,
+    transactions: Vec<Transaction>,
+    receipts: Vec<Receipt>,
+    state_update: StateUpdate,
+}
+
+impl OpenBlock {
+    fn on_top_of(parent: &BlockHeader) -> Self {
+        let header = BlockHeader {
+            parent_hash: parent.hash,
+            number: parent.number + 1,
+            timestamp: current_timestamp(parent.timestamp),
+            eth_l1_gas_price: parent.eth_l1_gas_price,
+            strk_l1_gas_price: parent.strk_l1_gas_price,
+            eth_l1_data_gas_price: parent.eth_l1_data_gas_price,
+            strk_l1_data_gas_price: parent.strk_l1_data_gas_price,
+            sequencer_address: parent.sequencer_address,
+            starknet_version: parent.starknet_version.clone(),
+            l1_da_mode: parent.l1_da_mode,
+            ..Default::default()
+        };
+
+        Self {
+            header,
+            transactions: Vec::new(),
+            receipts: Vec::new(),
+            state_update: StateUpdate::default(),
+        }
+    }
+
+    fn pending_data(&self) -> PendingData {
+        let block = PendingBlock {
+            eth_l1_gas_price_implementation_detail: Some(self.header.eth_l1_gas_price),
+            strk_l1_gas_price_implementation_detail: Some(self.header.strk_l1_gas_price),
+            l1_data_gas_price: Some(GasPrices {
+                price_in_wei: self.header.eth_l1_data_gas_price,
+                price_in_fri: self.header.strk_l1_data_gas_price,
+            }),
+            l1_gas_price_implementation_detail: None,
+            parent_hash: self.header.parent_hash,
+            sequencer_address: self.header.sequencer_address,
+            status: Status::Pending,
+            timestamp: self.header.timestamp,
+            transaction_receipts: self.receipts.clone(),
+            transactions: self.transactions.clone(),
+            starknet_version: self.header.starknet_version.clone(),
+            l1_da_mode: Some(self.header.l1_da_mode.into()),
+        };
+
+        PendingData {
+            block: Arc::new(block),
+            state_update: Arc::new(self.state_update.clone()),
+            number: self.header.number,
+        }
+    }
+}
+
+/// The current time, or `earliest` if the clock is behind it.
+fn current_timestamp(earliest: BlockTimestamp) -> BlockTimestamp {
+    let seconds = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    BlockTimestamp::new_or_panic(seconds.max(earliest.get()))
+}
+
+fn rejection(code: KnownStarknetErrorCode, message: String) -> StarknetError {
+    StarknetError {
+        code: code.into(),
+        message,
+    }
+}
+
+/// Executes the transaction on top of the open block, and adds it to the block unless it is
+/// rejected.
+///
+/// Reverted transactions are added as well, like on the gateway.
+fn execute(
+    connection: &mut Connection,
+    chain_id: ChainId,
+    block: &mut OpenBlock,
+    fork: Option<&Arc<dyn ForkedState>>,
+    transaction: Transaction,
+    class_definition: Option<Vec<u8>>,
+) -> anyhow::Result<Result<(), StarknetError>> {
+    let db = connection
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .context("Create database transaction")?;
+
+    let duplicate = block
+        .transactions
+        .iter()
+        .any(|tx| tx.hash == transaction.hash)
+        || db
+            .transaction(transaction.hash)
+            .context("Querying transaction")?
+            .is_some();
+    if duplicate {
+        return Ok(Err(rejection(
+            KnownStarknetErrorCode::DuplicatedTransaction,
+            format!("Transaction with hash {} already exists", transaction.hash),
+        )));
+    }
+
+    // Declared classes must be in the database for the transaction to be executed. They are
+    // rolled back along with the database transaction if it is rejected.
+    if let Some(definition) = class_definition {
+        let declared = insert_class(&db, &block.state_update, fork, &transaction, &definition)?;
+        if let Err(rejection) = declared {
+            return Ok(Err(rejection));
+        }
+    }
+
+    // The transactions of a block share its timestamp, which is set by its first transaction.
+    if block.transactions.is_empty() {
+        block.header.timestamp = current_timestamp(block.header.timestamp);
+    }
+
+    let executor_transaction = pathfinder_rpc::compose_executor_transaction(&transaction, &db)
+        .context("Composing executor transaction")?;
+    let state = ExecutionState::simulation(
+        &db,
+        chain_id,
+        block.header.clone(),
+        Some(Arc::new(block.state_update.clone())),
+        L1BlobDataAvailability::Enabled,
+    )
+    .with_fork(fork.cloned());
+
+    let simulation =
+        match pathfinder_executor::simulate(state, vec![executor_transaction], false, false) {
+            Ok(mut simulations) => simulations.pop().context("Missing simulation result")?,
+            Err(TransactionExecutionError::ExecutionError { error, .. }) => {
+                return Ok(Err(rejection(
+                    KnownStarknetErrorCode::ValidateFailure,
+                    error,
+                )));
+            }
+            Err(TransactionExecutionError::StepLimitExceeded { .. }) => {
+                return Ok(Err(rejection(
+                    KnownStarknetErrorCode::ValidateFailure,
+                    "Step limit exceeded".to_owned(),
+                )));
+            }
+            Err(TransactionExecutionError::Custom(error)) => {
+                return Ok(Err(rejection(
+                    KnownStarknetErrorCode::ValidateFailure,
+                    format!("{error:#}"),
+                )));
+            }
+            Err(TransactionExecutionError::Internal(error)) => {
+                return Err(error.context("Executing transaction"));
+            }
+        };
+
+    db.commit().context("Commit database transaction")?;
+
+    // The fee is computed as a u128.
+    let fee = Fee(Felt::from(simulation.fee_estimation.overall_fee.low_u128()));
+    let index = TransactionIndex::new_or_panic(block.transactions.len() as u64);
+    let receipt = receipt(&simulation.trace, fee, transaction.hash, index);
+
+    merge_state_diff(&mut block.state_update, state_diff(&simulation.trace));
+    block.transactions.push(transaction);
+    block.receipts.push(receipt);
+
+    Ok(Ok(()))
+}
+
+/// Inserts the definition of the class declared by `transaction`, compiling it if it is a Sierra
+/// class.
+fn insert_class(
+    db: &pathfinder_storage::Transaction<'_>,
+    pending: &StateUpdate,
+    fork: Option<&Arc<dyn ForkedState>>,
+    transaction: &Transaction,
+    definition: &[u8],
+) -> anyhow::Result<Result<(), StarknetError>> {
+    let (class_hash, compiled_class_hash) = match &transaction.variant {
+        TransactionVariant::DeclareV0(tx) => (tx.class_hash, None),
+        TransactionVariant::DeclareV1(tx) => (tx.class_hash, None),
+        TransactionVariant::DeclareV2(tx) => (tx.class_hash, Some(tx.compiled_class_hash)),
+        TransactionVariant::DeclareV3(tx) => (tx.class_hash, Some(tx.compiled_class_hash)),
+        _ => anyhow::bail!("Class definition submitted with a transaction other than a declare"),
+    };
+
+    // Definitions of classes which are not declared yet may be stored already, e.g. those
+    // downloaded for the pending block while syncing.
+    let declared = db
+        .class_definition_with_block_number(class_hash)
+        .context("Querying class definition")?
+        .is_some_and(|(block, _)| block.is_some());
+    let declared_on_fork = match fork {
+        Some(fork) => {
+            fork.class_definition(class_hash)
+                .context("Querying class definition from fork")?
+                .is_some()
+                || fork
+                    .casm_definition(class_hash)
+                    .context("Querying class definition from fork")?
+                    .is_some()
+        }
+        None => false,
+    };
+    if declared || declared_on_fork || pending.class_is_declared(class_hash) {
+        return Ok(Err(rejection(
+            KnownStarknetErrorCode::ClassAlreadyDeclared,
+            format!("Class with hash {class_hash} is already declared"),
+        )));
+    }
+
+    let Some(expected_casm_hash) = compiled_class_hash else {
+        db.insert_cairo_class(class_hash, definition)
+            .context("Inserting class definition")?;
+        return Ok(Ok(()));
+    };
+
+    let casm_definition =
+        match pathfinder_compiler::compile_to_casm_with_latest_compiler(definition) {
+            Ok(casm_definition) => casm_definition,
+            Err(error) => {
+                return Ok(Err(rejection(
+                    KnownStarknetErrorCode::CompilationFailed,
+                    format!("{error:#}"),
+                )));
+            }
+        };
+    let casm_hash = pathfinder_compiler::casm_class_hash(&casm_definition)
+        .context("Computing CASM class hash")?;
+    if casm_hash != expected_casm_hash {
+        return Ok(Err(rejection(
+            KnownStarknetErrorCode::InvalidCompiledClassHash,
+            format!(
+                "Compiled class hash mismatch: expected {expected_casm_hash}, computed {casm_hash}"
+            ),
+        )));
+    }
+
+    db.insert_sierra_class(
+        &SierraHash(class_hash.0),
+        definition,
+        &casm_hash,
+        &casm_definition,
+    )
+    .context("Inserting class definition")?;
+
+    Ok(Ok(()))
+}
+
+/// The top-level invocations of a transaction, in the order in which they are executed.
+fn invocations(trace: &TransactionTrace) -> Vec<&FunctionInvocation> {
+    let invocations = match trace {
+        TransactionTrace::Declare(trace) => vec![
+            trace.validate_invocation.as_ref(),
+            trace.fee_transfer_invocation.as_ref(),
+        ],
+        TransactionTrace::DeployAccount(trace) => vec![
+            trace.constructor_invocation.as_ref(),
+            trace.validate_invocation.as_ref(),
+            trace.fee_transfer_invocation.as_ref(),
+        ],
+        TransactionTrace::Invoke(trace) => {
+            let execute_invocation = match &trace.execute_invocation {
+                ExecuteInvocation::FunctionInvocation(invocation) => invocation.as_ref(),
+                ExecuteInvocation::RevertedReason(_) => None,
+            };
+            vec![
+                trace.validate_invocation.as_ref(),
+                execute_invocation,
+                trace.fee_transfer_invocation.as_ref(),
+            ]
+        }
+        TransactionTrace::L1Handler(trace) => vec![trace.function_invocation.as_ref()],
+    };
+
+    invocations.into_iter().flatten().collect()
+}
+
+fn state_diff(trace: &TransactionTrace) -> &StateDiff {
+    match trace {
+        TransactionTrace::Declare(trace) => &trace.state_diff,
+        TransactionTrace::DeployAccount(trace) => &trace.state_diff,
+        TransactionTrace::Invoke(trace) => &trace.state_diff,
+        TransactionTrace::L1Handler(trace) => &trace.state_diff,
+    }
+}
+
+fn receipt(
+    trace: &TransactionTrace,
+    fee: Fee,
+    transaction_hash: TransactionHash,
+    transaction_index: TransactionIndex,
+) -> Receipt {
+    let mut events = Vec::new();
+    let mut l2_to_l1_messages = Vec::new();
+    // Events and messages are ordered within the call tree of each top-level invocation.
+    for invocation in invocations(trace) {
+        let mut invocation_events = Vec::new();
+        let mut invocation_messages = Vec::new();
+        collect_events_and_messages(invocation, &mut invocation_events, &mut invocation_messages);
+
+        invocation_events.sort_by_key(|(order, _)| *order);
+        invocation_messages.sort_by_key(|(order, _)| *order);
+        events.extend(invocation_events.into_iter().map(|(_, event)| event));
+        l2_to_l1_messages.extend(invocation_messages.into_iter().map(|(_, message)| message));
+    }
+
+    let (execution_status, resources) = match trace {
+        TransactionTrace::Invoke(trace) => {
+            let execution_status = match &trace.execute_invocation {
+                ExecuteInvocation::RevertedReason(reason) => ExecutionStatus::Reverted {
+                    reason: reason.clone(),
+                },
+                ExecuteInvocation::FunctionInvocation(_) => ExecutionStatus::Succeeded,
+            };
+            (execution_status, &trace.execution_resources)
+        }
+        TransactionTrace::Declare(trace) => {
+            (ExecutionStatus::Succeeded, &trace.execution_resources)
+        }
+        TransactionTrace::DeployAccount(trace) => {
+            (ExecutionStatus::Succeeded, &trace.execution_resources)
+        }
+        TransactionTrace::L1Handler(trace) => {
+            (ExecutionStatus::Succeeded, &trace.execution_resources)
+        }
+    };
+
+    let computation = &resources.computation_resources;
+    let execution_resources = ExecutionResources {
+        builtins: BuiltinCounters {
+            output: 0,
+            pedersen: computation.pedersen_builtin_applications as u64,
+            range_check: computation.range_check_builtin_applications as u64,
+            ecdsa: computation.ecdsa_builtin_applications as u64,
+            bitwise: computation.bitwise_builtin_applications as u64,
+            ec_op: computation.ec_op_builtin_applications as u64,
+            keccak: computation.keccak_builtin_applications as u64,
+            poseidon: computation.poseidon_builtin_applications as u64,
+            segment_arena: computation.segment_arena_builtin as u64,
+        },
+        n_steps: computation.steps as u64,
+        n_memory_holes: computation.memory_holes as u64,
+        data_availability: ExecutionDataAvailability {
+            l1_gas: resources.data_availability.l1_gas,
+            l1_data_gas: resources.data_availability.l1_data_gas,
+        },
+    };
+
+    Receipt {
+        actual_fee: Some(fee),
+        events,
+        execution_resources,
+        l2_to_l1_messages,
+        execution_status,
+        transaction_hash,
+        transaction_index,
+    }
+}
+
+fn collect_events_and_messages(
+    invocation: &FunctionInvocation,
+    events: &mut Vec<(i64, Event)>,
+    messages: &mut Vec<(usize, L2ToL1Message)>,
+) {
+    events.extend(invocation.events.iter().map(|event| {
+        let converted = Event {
+            data: event.data.iter().copied().map(EventData).collect(),
+            from_address: invocation.contract_address,
+            keys: event.keys.iter().copied().map(EventKey).collect(),
+        };
+        (event.order, converted)
+    }));
+    messages.extend(invocation.messages.iter().map(|message| {
+        let converted = L2ToL1Message {
+            from_address: invocation.contract_address,
+            payload: message
+                .payload
+                .iter()
+                .copied()
+                .map(L2ToL1MessagePayloadElem)
+                .collect(),
+            // Ethereum addresses are the low 20 bytes of the felt.
+            to_address: EthereumAddress(H160::from_slice(&message.to_address.as_be_bytes()[12..])),
+        };
+        (message.order, converted)
+    }));
+
+    for call in &invocation.internal_calls {
+        collect_events_and_messages(call, events, messages);
+    }
+}
+
+/// Applies the state diff of a transaction executed on top of `state_update`.
+fn merge_state_diff(state_update: &mut StateUpdate, diff: &StateDiff) {
+    for (contract, storage_diffs) in &diff.storage_diffs {
+        let storage = storage_diffs.iter().map(|diff| (diff.key, diff.value));
+        if *contract == ContractAddress::ONE {
+            state_update
+                .system_contract_updates
+                .entry(*contract)
+                .or_default()
+                .storage
+                .extend(storage);
+        } else {
+            state_update
+                .contract_updates
+                .entry(*contract)
+                .or_default()
+                .storage
+                .extend(storage);
+        }
+    }
+
+    for deployed in &diff.deployed_contracts {
+        state_update
+            .contract_updates
+            .entry(deployed.address)
+            .or_default()
+            .class = Some(ContractClassUpdate::Deploy(deployed.class_hash));
+    }
+
+    for replaced in &diff.replaced_classes {
+        let update = state_update
+            .contract_updates
+            .entry(replaced.contract_address)
+            .or_default();
+        // A contract deployed in this block is deployed with its final class.
+        update.class = match update.class {
+            Some(ContractClassUpdate::Deploy(_)) => {
+                Some(ContractClassUpdate::Deploy(replaced.class_hash))
+            }
+            _ => Some(ContractClassUpdate::Replace(replaced.class_hash)),
+        };
+    }
+
+    for (contract, nonce) in &diff.nonces {
+        state_update
+            .contract_updates
+            .entry(*contract)
+            .or_default()
+            .nonce = Some(*nonce);
+    }
+
+    state_update
+        .declared_cairo_classes
+        .extend(diff.deprecated_declared_classes.iter().copied());
+    state_update.declared_sierra_classes.extend(
+        diff.declared_classes
+            .iter()
+            .map(|class| (class.class_hash, class.compiled_class_hash)),
+    );
+}
+
+/// Commits the block to the database and notifies subscribers of it.
+///
+/// The state tries of a `forked` devnet are not available locally, so its commitments are left
+/// as zero.
+fn seal(
+    connection: &mut Connection,
+    storage: &Storage,
+    forked: bool,
+    block: OpenBlock,
+    mempool: &Mempool,
+    websocket_txs: &mut Option<TopicBroadcasters>,
+) -> anyhow::Result<BlockHeader> {
+    let OpenBlock {
+        header,
+        transactions,
+        receipts,
+        state_update,
+    } = block;
+
+    let transaction = connection
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .context("Create database transaction")?;
+    let (storage_commitment, class_commitment, state_commitment) = if forked {
+        Default::default()
+    } else {
+        let (storage_commitment, class_commitment) = update_starknet_state(
+            &transaction,
+            &state_update,
+            false,
+            header.number,
+            storage.clone(),
+        )
+        .context("Updating Starknet state")?;
+        let state_commitment = StateCommitment::calculate(storage_commitment, class_commitment);
+        (storage_commitment, class_commitment, state_commitment)
+    };
+
+    let final_hash_type =
+        TransactionCommitmentFinalHashType::for_version(&header.starknet_version)?;
+    let transaction_commitment = calculate_transaction_commitment(&transactions, final_hash_type)
+        .context("Calculating transaction commitment")?;
+    let event_commitment =
+        calculate_event_commitment(&receipts).context("Calculating event commitment")?;
+    let transaction_count = transactions.len();
+    let event_count = receipts.iter().map(|r| r.events.len()).sum();
+
+    let hash = compute_final_hash(
+        header.number,
+        state_commitment,
+        &header.sequencer_address,
+        header.timestamp,
+        transaction_count as u64,
+        transaction_commitment.0,
+        event_count as u64,
+        event_commitment.0,
+        header.parent_hash,
+    );
+
+    let header = BlockHeader {
+        hash,
+        class_commitment,
+        event_commitment,
+        state_commitment,
+        storage_commitment,
+        transaction_commitment,
+        transaction_count,
+        event_count,
+        ..header
+    };
+
+    transaction
+        .insert_block_header(&header)
+        .context("Inserting block header into database")?;
+    transaction
+        .insert_state_update_counts(header.number, &state_update.counts())
+        .context("Inserting state update counts into database")?;
+
+    let block_events = websocket_txs
+        .as_ref()
+        .filter(|txs| txs.events.is_receiving())
+        .map(|_| BlockEvents::from_receipts(Some(header.hash), header.number, &receipts));
+    let statuses = receipts
+        .iter()
+        .map(|receipt| {
+            let status = if receipt.is_reverted() {
+                Status::Reverted
+            } else {
+                Status::AcceptedOnL2
+            };
+            (receipt.transaction_hash, status)
+        })
+        .collect::<Vec<_>>();
+
+    let transaction_data = transactions
+        .into_iter()
+        .zip(receipts.into_iter().map(Some))
+        .collect::<Vec<_>>();
+    transaction
+        .insert_transaction_data(header.hash, header.number, &transaction_data)
+        .context("Insert transaction data into database")?;
+
+    let state_update = state_update
+        .with_block_hash(header.hash)
+        .with_state_commitment(state_commitment);
+    transaction
+        .insert_state_update(header.number, &state_update)
+        .context("Insert state update into database")?;
+
+    transaction
+        .commit()
+        .context("Commit database transaction")?;
+
+    for (hash, status) in statuses {
+        mempool.update(hash, status);
+    }
+
+    if let Some(sender) = websocket_txs {
+        if let Err(e) = sender.new_head.send_if_receiving(header.clone().into()) {
+            tracing::error!(error=?e, "Failed to send header over websocket broadcaster.");
+            // Disable websocket entirely so that the closed channel doesn't spam this error.
+            *websocket_txs = None;
+        }
+    }
+
+    if let (Some(sender), Some(block_events)) = (websocket_txs, block_events) {
+        sender.events.send_if_receiving(block_events);
+    }
+
+    Ok(header)
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::transaction::InvokeTransactionV1;
+    use pathfinder_executor::types::{
+        CallType, ComputationResources, DeployedContract, EntryPointType, InvokeTransactionTrace,
+        MsgToL1, ReplacedClass, StorageDiff,
+    };
+
+    use super::*;
+
+    fn invocation(
+        contract_address: ContractAddress,
+        events: Vec<pathfinder_executor::types::Event>,
+        internal_calls: Vec<FunctionInvocation>,
+    ) -> FunctionInvocation {
+        FunctionInvocation {
+            calldata: vec![],
+            contract_address,
+            selector: Felt::ZERO,
+            call_type: CallType::Call,
+            caller_address: Felt::ZERO,
+            internal_calls,
+            class_hash: None,
+            entry_point_type: EntryPointType::External,
+            events,
+            messages: vec![],
+            result: vec![],
+            computation_resources: ComputationResources::default(),
+        }
+    }
+
+    fn event(order: i64, key: Felt) -> pathfinder_executor::types::Event {
+        pathfinder_executor::types::Event {
+            order,
+            data: vec![],
+            keys: vec![key],
+        }
+    }
+
+    fn empty_state_diff() -> StateDiff {
+        StateDiff {
+            storage_diffs: Default::default(),
+            deployed_contracts: vec![],
+            deprecated_declared_classes: Default::default(),
+            declared_classes: vec![],
+            nonces: Default::default(),
+            replaced_classes: vec![],
+        }
+    }
+
+    #[test]
+    fn state_diff_is_merged() {
+        let mut state_update = StateUpdate::default()
+            .with_storage_update(
+                contract_address!("0x10"),
+                storage_address!("0x1"),
+                storage_value!("0x1"),
+            )
+            .with_contract_nonce(contract_address!("0x10"), contract_nonce!("0x1"));
+
+        let diff = StateDiff {
+            storage_diffs: [
+                (
+                    contract_address!("0x10"),
+                    vec![StorageDiff {
+                        key: storage_address!("0x2"),
+                        value: storage_value!("0x2"),
+                    }],
+                ),
+                (
+                    ContractAddress::ONE,
+                    vec![StorageDiff {
+                        key: storage_address!("0x3"),
+                        value: storage_value!("0x3"),
+                    }],
+                ),
+            ]
+            .into(),
+            deployed_contracts: vec![DeployedContract {
+                address: contract_address!("0x20"),
+                class_hash: class_hash!("0xa"),
+            }],
+            replaced_classes: vec![
+                ReplacedClass {
+                    contract_address: contract_address!("0x20"),
+                    class_hash: class_hash!("0xb"),
+                },
+                ReplacedClass {
+                    contract_address: contract_address!("0x10"),
+                    class_hash: class_hash!("0xc"),
+                },
+            ],
+            nonces: [(contract_address!("0x10"), contract_nonce!("0x2"))].into(),
+            ..empty_state_diff()
+        };
+
+        merge_state_diff(&mut state_update, &diff);
+
+        let expected = StateUpdate::default()
+            .with_storage_update(
+                contract_address!("0x10"),
+                storage_address!("0x1"),
+                storage_value!("0x1"),
+            )
+            .with_storage_update(
+                contract_address!("0x10"),
+                storage_address!("0x2"),
+                storage_value!("0x2"),
+            )
+            .with_system_storage_update(
+                ContractAddress::ONE,
+                storage_address!("0x3"),
+                storage_value!("0x3"),
+            )
+            .with_contract_nonce(contract_address!("0x10"), contract_nonce!("0x2"))
+            .with_deployed_contract(contract_address!("0x20"), class_hash!("0xb"))
+            .with_replaced_class(contract_address!("0x10"), class_hash!("0xc"));
+        assert_eq!(state_update, expected);
+    }
+
+    #[test]
+    fn receipt_orders_events_by_invocation() {
+        let validate = invocation(
+            contract_address!("0x1"),
+            vec![event(0, felt!("0x1"))],
+            vec![],
+        );
+        // The nested call emits its event between those of its caller.
+        let mut execute = invocation(
+            contract_address!("0x2"),
+            vec![event(0, felt!("0x2")), event(2, felt!("0x4"))],
+            vec![invocation(
+                contract_address!("0x3"),
+                vec![event(1, felt!("0x3"))],
+                vec![],
+            )],
+        );
+        execute.messages = vec![MsgToL1 {
+            order: 0,
+            payload: vec![felt!("0x5")],
+            to_address: felt!("0x6"),
+            from_address: felt!("0x2"),
+        }];
+        let trace = TransactionTrace::Invoke(InvokeTransactionTrace {
+            validate_invocation: Some(validate),
+            execute_invocation: ExecuteInvocation::FunctionInvocation(Some(execute)),
+            fee_transfer_invocation: None,
+            state_diff: empty_state_diff(),
+            execution_resources: Default::default(),
+        });
+
+        let receipt = receipt(
+            &trace,
+            fee!("0x7"),
+            transaction_hash!("0x8"),
+            TransactionIndex::new_or_panic(1),
+        );
+
+        let emitters = receipt
+            .events
+            .iter()
+            .map(|event| (event.from_address, event.keys[0].0))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            emitters,
+            vec![
+                (contract_address!("0x1"), felt!("0x1")),
+                (contract_address!("0x2"), felt!("0x2")),
+                (contract_address!("0x3"), felt!("0x3")),
+                (contract_address!("0x2"), felt!("0x4")),
+            ]
+        );
+        assert_eq!(
+            receipt.l2_to_l1_messages,
+            vec![L2ToL1Message {
+                from_address: contract_address!("0x2"),
+                payload: vec![l2_to_l1_message_payload_elem!("0x5")],
+                to_address: EthereumAddress(H160::from_low_u64_be(6)),
+            }]
+        );
+        assert_eq!(receipt.actual_fee, Some(fee!("0x7")));
+        assert_eq!(receipt.execution_status, ExecutionStatus::Succeeded);
+    }
+
+    #[test]
+    fn reverted_receipt() {
+        let trace = TransactionTrace::Invoke(InvokeTransactionTrace {
+            validate_invocation: None,
+            execute_invocation: ExecuteInvocation::RevertedReason("Out of gas".to_owned()),
+            fee_transfer_invocation: None,
+            state_diff: empty_state_diff(),
+            execution_resources: Default::default(),
+        });
+
+        let receipt = receipt(
+            &trace,
+            fee!("0x1"),
+            transaction_hash!("0x2"),
+            TransactionIndex::new_or_panic(0),
+        );
+
+        assert_eq!(receipt.revert_reason(), Some("Out of gas"));
+    }
+
+    #[test]
+    fn seal_block() {
+        let storage = Storage::in_memory().unwrap();
+        let mut connection = storage.connection().unwrap();
+
+        let genesis = BlockHeader::builder()
+            .with_number(BlockNumber::GENESIS)
+            .with_timestamp(BlockTimestamp::new_or_panic(1000))
+            .with_sequencer_address(sequencer_address!("0x123"))
+            .with_starknet_version(StarknetVersion::new(0, 13, 1))
+            .finalize_with_hash(block_hash!("0xabc"));
+        let tx = connection.transaction().unwrap();
+        tx.insert_block_header(&genesis).unwrap();
+        tx.commit().unwrap();
+
+        let transaction = Transaction {
+            hash: transaction_hash!("0x1"),
+            variant: TransactionVariant::InvokeV1(InvokeTransactionV1 {
+                sender_address: contract_address!("0x10"),
+                ..Default::default()
+            }),
+        };
+        let mut block = OpenBlock::on_top_of(&genesis);
+        block.transactions.push(transaction.clone());
+        block.receipts.push(Receipt {
+            transaction_hash: transaction.hash,
+            ..Default::default()
+        });
+        block.state_update = StateUpdate::default()
+            .with_deployed_contract(contract_address!("0x10"), class_hash!("0xa"))
+            .with_storage_update(
+                contract_address!("0x10"),
+                storage_address!("0x1"),
+                storage_value!("0x1"),
+            )
+            .with_contract_nonce(contract_address!("0x10"), contract_nonce!("0x1"));
+
+        let mempool = Mempool::default();
+        mempool.insert(
+            transaction.hash,
+            pathfinder_rpc::mempool::TransactionKind::Invoke,
+        );
+
+        let header = seal(&mut connection, &storage, false, block, &mempool, &mut None).unwrap();
+
+        assert_eq!(header.number, BlockNumber::new_or_panic(1));
+        assert_eq!(header.parent_hash, genesis.hash);
+        assert_eq!(header.sequencer_address, genesis.sequencer_address);
+        assert_eq!(header.transaction_count, 1);
+        assert_ne!(header.state_commitment, StateCommitment::ZERO);
+
+        let tx = connection.transaction().unwrap();
+        let latest = tx
+            .block_header(pathfinder_storage::BlockId::Latest)
+            .unwrap()
+            .unwrap();
+        assert_eq!(latest.hash, header.hash);
+        assert_eq!(tx.transaction(transaction.hash).unwrap(), Some(transaction));
+        let nonce = tx
+            .contract_nonce(contract_address!("0x10"), header.number.into())
+            .unwrap();
+        assert_eq!(nonce, Some(contract_nonce!("0x1")));
+
+        assert_eq!(mempool.transactions()[0].status, Status::AcceptedOnL2);
+    }
+}
@@ -0,0 +1,695 @@
+//! Devnets forked from another network.
+//!
+//! A forked devnet produces its blocks on top of a block of another network, whose state is read
+//! from that network's database or JSON-RPC API as of the fork block. The local database starts
+//! out with just the header of the fork block, and only stores what changes on top of it.
+//!
+//! The state and class tries of the forked network are not available locally, so the blocks
+//! produced by a forked devnet have zero state, storage and class commitments.
+use std::num::NonZeroU32;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::Context;
+use cached::{Cached, SizedCache};
+use pathfinder_common::prelude::*;
+use pathfinder_common::L1DataAvailabilityMode;
+use pathfinder_crypto::Felt;
+use pathfinder_executor::ForkedState;
+use pathfinder_rpc::v02::types::ContractClass;
+use pathfinder_storage::{JournalMode, Storage};
+use reqwest::Url;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+/// JSON-RPC error code for contracts which do not exist.
+const CONTRACT_NOT_FOUND: i64 = 20;
+/// JSON-RPC error code for classes which are not declared.
+const CLASS_HASH_NOT_FOUND: i64 = 28;
+
+const RPC_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The number of entries kept of each kind of state read from a JSON-RPC fork. Classes are by far
+/// the largest entries, so fewer of them are kept.
+const CACHE_SIZE: usize = 16_384;
+const CLASS_CACHE_SIZE: usize = 128;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Config {
+    pub source: Source,
+    /// The block whose state the devnet starts from.
+    pub block: BlockNumber,
+}
+
+/// Where the state of the forked network is read from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    /// The JSON-RPC API of a node of the network, e.g. `http://localhost:9545/rpc/v0_7`.
+    Rpc(Url),
+    /// The database of a node of the network, which must have synced the fork block.
+    Database(PathBuf),
+}
+
+/// Connects to the source of the fork and prepares `storage`, the local database, for producing
+/// blocks on top of the fork block.
+///
+/// An empty database is initialized with the header of the fork block. Otherwise the database
+/// must have been forked from the same block before.
+pub async fn connect(config: &Config, storage: Storage) -> anyhow::Result<Arc<dyn ForkedState>> {
+    let (fork, header): (Arc<dyn ForkedState>, BlockHeader) = match &config.source {
+        Source::Rpc(url) => {
+            let fork = RpcFork::new(url.clone(), config.block)
+                .await
+                .with_context(|| format!("Connecting to {url}"))?;
+            let header = fork.header.clone();
+            (Arc::new(fork), header)
+        }
+        Source::Database(path) => {
+            let path = path.clone();
+            let local = storage.path().to_owned();
+            let block = config.block;
+            let fork = tokio::task::spawn_blocking(move || DatabaseFork::open(path, &local, block))
+                .await
+                .context("Joining database task")??;
+            let header = fork.header.clone();
+            (Arc::new(fork), header)
+        }
+    };
+
+    tokio::task::spawn_blocking(move || initialize(&storage, &header))
+        .await
+        .context("Joining database task")??;
+
+    Ok(fork)
+}
+
+fn initialize(storage: &Storage, fork_header: &BlockHeader) -> anyhow::Result<()> {
+    let mut connection = storage
+        .connection()
+        .context("Creating database connection")?;
+    let transaction = connection
+        .transaction()
+        .context("Creating database transaction")?;
+
+    if let Some(parent) = fork_header.number.parent() {
+        anyhow::ensure!(
+            !transaction
+                .block_exists(parent.into())
+                .context("Querying parent of the fork block")?,
+            "The database holds blocks from before the fork block. A forked devnet needs a \
+             database of its own, use a separate data directory"
+        );
+    }
+
+    match transaction
+        .block_hash(fork_header.number.into())
+        .context("Querying fork block")?
+    {
+        Some(hash) => {
+            anyhow::ensure!(
+                hash == fork_header.hash,
+                "The database was forked from a different block {} with hash {hash}",
+                fork_header.number
+            );
+            Ok(())
+        }
+        None => {
+            anyhow::ensure!(
+                transaction
+                    .block_id(pathfinder_storage::BlockId::Latest)
+                    .context("Querying latest block")?
+                    .is_none(),
+                "The database holds blocks which are not part of the fork. A forked devnet needs \
+                 a database of its own, use a separate data directory"
+            );
+
+            transaction
+                .insert_block_header(fork_header)
+                .context("Inserting fork block header")?;
+            transaction
+                .commit()
+                .context("Committing database transaction")
+        }
+    }
+}
+
+/// Reads the forked state from another node's database.
+struct DatabaseFork {
+    storage: Storage,
+    header: BlockHeader,
+}
+
+impl DatabaseFork {
+    fn open(path: PathBuf, local: &Path, block: BlockNumber) -> anyhow::Result<Self> {
+        let canonical = path
+            .canonicalize()
+            .with_context(|| format!("Resolving {}", path.display()))?;
+        anyhow::ensure!(
+            local.canonicalize().ok().as_ref() != Some(&canonical),
+            "The database to fork from cannot be the devnet's own database"
+        );
+
+        let storage = Storage::migrate(path, JournalMode::WAL, 16)
+            .context("Opening database to fork from")?
+            .create_pool(NonZeroU32::new(4).unwrap())
+            .context("Creating database connection pool")?;
+
+        let header = storage
+            .connection()
+            .context("Creating database connection")?
+            .transaction()
+            .context("Creating database transaction")?
+            .block_header(block.into())
+            .context("Querying fork block header")?
+            .with_context(|| format!("Block {block} is not in the database to fork from"))?;
+
+        Ok(Self { storage, header })
+    }
+
+    fn query<T>(
+        &self,
+        f: impl FnOnce(&pathfinder_storage::Transaction<'_>) -> anyhow::Result<T>,
+    ) -> anyhow::Result<T> {
+        let mut connection = self
+            .storage
+            .connection()
+            .context("Creating database connection")?;
+        let transaction = connection
+            .transaction()
+            .context("Creating database transaction")?;
+        f(&transaction)
+    }
+
+    fn block_id(&self) -> pathfinder_storage::BlockId {
+        self.header.number.into()
+    }
+}
+
+impl ForkedState for DatabaseFork {
+    fn block_number(&self) -> BlockNumber {
+        self.header.number
+    }
+
+    fn block_hash(&self, block: BlockNumber) -> anyhow::Result<Option<BlockHash>> {
+        self.query(|tx| tx.block_hash(block.into()))
+    }
+
+    fn storage_value(
+        &self,
+        contract_address: ContractAddress,
+        key: StorageAddress,
+    ) -> anyhow::Result<Option<StorageValue>> {
+        self.query(|tx| tx.storage_value(self.block_id(), contract_address, key))
+    }
+
+    fn contract_nonce(
+        &self,
+        contract_address: ContractAddress,
+    ) -> anyhow::Result<Option<ContractNonce>> {
+        self.query(|tx| tx.contract_nonce(contract_address, self.block_id()))
+    }
+
+    fn contract_class_hash(
+        &self,
+        contract_address: ContractAddress,
+    ) -> anyhow::Result<Option<ClassHash>> {
+        self.query(|tx| tx.contract_class_hash(self.block_id(), contract_address))
+    }
+
+    fn class_definition(&self, class_hash: ClassHash) -> anyhow::Result<Option<Vec<u8>>> {
+        self.query(|tx| tx.class_definition_at(self.block_id(), class_hash))
+    }
+
+    fn casm_definition(&self, class_hash: ClassHash) -> anyhow::Result<Option<Vec<u8>>> {
+        self.query(|tx| tx.casm_definition_at(self.block_id(), class_hash))
+    }
+
+    fn casm_hash(&self, class_hash: ClassHash) -> anyhow::Result<Option<CasmHash>> {
+        self.query(|tx| tx.casm_hash_at(self.block_id(), class_hash))
+    }
+}
+
+/// Reads the forked state from a node's JSON-RPC API.
+///
+/// The executor reads state from blocking threads, which wait for the requests on the runtime the
+/// fork was connected on. The most recently read state is cached, since the state at the fork
+/// block never changes.
+struct RpcFork {
+    client: reqwest::Client,
+    url: Url,
+    header: BlockHeader,
+    runtime: tokio::runtime::Handle,
+    cache: Mutex<Cache>,
+}
+
+/// Least recently used entries are evicted once a cache is full.
+struct Cache {
+    block_hashes: SizedCache<BlockNumber, Option<BlockHash>>,
+    storage: SizedCache<(ContractAddress, StorageAddress), Option<StorageValue>>,
+    nonces: SizedCache<ContractAddress, Option<ContractNonce>>,
+    class_hashes: SizedCache<ContractAddress, Option<ClassHash>>,
+    classes: SizedCache<ClassHash, Option<Arc<Class>>>,
+}
+
+impl Default for Cache {
+    fn default() -> Self {
+        Self {
+            block_hashes: SizedCache::with_size(CACHE_SIZE),
+            storage: SizedCache::with_size(CACHE_SIZE),
+            nonces: SizedCache::with_size(CACHE_SIZE),
+            class_hashes: SizedCache::with_size(CACHE_SIZE),
+            classes: SizedCache::with_size(CLASS_CACHE_SIZE),
+        }
+    }
+}
+
+enum Class {
+    Cairo {
+        definition: Vec<u8>,
+    },
+    Sierra {
+        definition: Vec<u8>,
+        casm_definition: Vec<u8>,
+        casm_hash: CasmHash,
+    },
+}
+
+/// The fields of a block in the JSON-RPC API which make up its header.
+#[derive(Deserialize)]
+struct RpcBlockHeader {
+    block_hash: BlockHash,
+    parent_hash: BlockHash,
+    block_number: BlockNumber,
+    new_root: StateCommitment,
+    timestamp: BlockTimestamp,
+    sequencer_address: SequencerAddress,
+    l1_gas_price: RpcResourcePrice,
+    /// Only reported since version 0.7 of the API.
+    l1_data_gas_price: Option<RpcResourcePrice>,
+    l1_da_mode: Option<L1DataAvailabilityMode>,
+    starknet_version: StarknetVersion,
+}
+
+#[derive(Deserialize)]
+struct RpcResourcePrice {
+    price_in_fri: Felt,
+    price_in_wei: Felt,
+}
+
+impl RpcBlockHeader {
+    fn into_header(self) -> anyhow::Result<BlockHeader> {
+        let l1_data_gas_price = self.l1_data_gas_price.unwrap_or(RpcResourcePrice {
+            price_in_fri: Felt::ZERO,
+            price_in_wei: Felt::ZERO,
+        });
+
+        Ok(BlockHeader {
+            hash: self.block_hash,
+            parent_hash: self.parent_hash,
+            number: self.block_number,
+            timestamp: self.timestamp,
+            eth_l1_gas_price: self.l1_gas_price.price_in_wei.try_into()?,
+            strk_l1_gas_price: self.l1_gas_price.price_in_fri.try_into()?,
+            eth_l1_data_gas_price: l1_data_gas_price.price_in_wei.try_into()?,
+            strk_l1_data_gas_price: l1_data_gas_price.price_in_fri.try_into()?,
+            sequencer_address: self.sequencer_address,
+            starknet_version: self.starknet_version,
+            state_commitment: self.new_root,
+            l1_da_mode: self.l1_da_mode.unwrap_or_default(),
+            ..Default::default()
+        })
+    }
+}
+
+impl RpcFork {
+    async fn new(url: Url, block: BlockNumber) -> anyhow::Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(RPC_TIMEOUT)
+            .build()
+            .context("Creating HTTP client")?;
+
+        let mut fork = Self {
+            client,
+            url,
+            header: BlockHeader::default(),
+            runtime: tokio::runtime::Handle::current(),
+            cache: Default::default(),
+        };
+
+        fork.header = fork
+            .request::<RpcBlockHeader>(
+                "starknet_getBlockWithTxHashes",
+                serde_json::json!({ "block_id": { "block_number": block } }),
+            )
+            .await?
+            .with_context(|| format!("Block {block} not found"))?
+            .into_header()
+            .context("Parsing fork block header")?;
+
+        Ok(fork)
+    }
+
+    /// Sends a JSON-RPC request, returning `None` if the contract or class it is about does not
+    /// exist.
+    async fn request<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: serde_json::Value,
+    ) -> anyhow::Result<Option<T>> {
+        let request = serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": method,
+            "params": params,
+            "id": 0
+        });
+
+        let mut response: serde_json::Value = self
+            .client
+            .post(self.url.clone())
+            .json(&request)
+            .send()
+            .await
+            .with_context(|| format!("Sending {method} request"))?
+            .error_for_status()
+            .with_context(|| format!("Requesting {method}"))?
+            .json()
+            .await
+            .with_context(|| format!("Receiving {method} response"))?;
+
+        if let Some(error) = response.get("error") {
+            return match error["code"].as_i64() {
+                Some(CONTRACT_NOT_FOUND | CLASS_HASH_NOT_FOUND) => Ok(None),
+                _ => Err(anyhow::anyhow!("{method} failed: {error}")),
+            };
+        }
+
+        let result = serde_json::from_value(response["result"].take())
+            .with_context(|| format!("Parsing {method} response"))?;
+        Ok(Some(result))
+    }
+
+    /// Sends a request from a blocking thread.
+    fn request_blocking<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: serde_json::Value,
+    ) -> anyhow::Result<Option<T>> {
+        self.runtime.block_on(self.request(method, params))
+    }
+
+    fn block_id(&self) -> serde_json::Value {
+        serde_json::json!({ "block_number": self.header.number })
+    }
+
+    fn class(&self, class_hash: ClassHash) -> anyhow::Result<Option<Arc<Class>>> {
+        if let Some(class) = self.cache.lock().unwrap().classes.cache_get(&class_hash) {
+            return Ok(class.clone());
+        }
+
+        let class = self
+            .request_blocking::<ContractClass>(
+                "starknet_getClass",
+                serde_json::json!({ "block_id": self.block_id(), "class_hash": class_hash }),
+            )?
+            .map(|class| self.convert_class(class))
+            .transpose()
+            .with_context(|| format!("Converting class {class_hash}"))?
+            .map(Arc::new);
+
+        self.cache
+            .lock()
+            .unwrap()
+            .classes
+            .cache_set(class_hash, class.clone());
+        Ok(class)
+    }
+
+    fn convert_class(&self, class: ContractClass) -> anyhow::Result<Class> {
+        match class {
+            ContractClass::Cairo(class) => Ok(Class::Cairo {
+                definition: class.serialize_to_json()?,
+            }),
+            ContractClass::Sierra(class) => {
+                let definition = class.serialize_to_json()?;
+                let casm_definition = pathfinder_compiler::compile_to_casm(
+                    &definition,
+                    &self.header.starknet_version,
+                )
+                .context("Compiling Sierra class")?;
+                let casm_hash = pathfinder_compiler::casm_class_hash(&casm_definition)
+                    .context("Computing CASM class hash")?;
+                Ok(Class::Sierra {
+                    definition,
+                    casm_definition,
+                    casm_hash,
+                })
+            }
+        }
+    }
+}
+
+impl ForkedState for RpcFork {
+    fn block_number(&self) -> BlockNumber {
+        self.header.number
+    }
+
+    fn block_hash(&self, block: BlockNumber) -> anyhow::Result<Option<BlockHash>> {
+        if let Some(hash) = self.cache.lock().unwrap().block_hashes.cache_get(&block) {
+            return Ok(*hash);
+        }
+
+        #[derive(Deserialize)]
+        struct Block {
+            block_hash: BlockHash,
+        }
+
+        let hash = self
+            .request_blocking::<Block>(
+                "starknet_getBlockWithTxHashes",
+                serde_json::json!({ "block_id": { "block_number": block } }),
+            )?
+            .map(|block| block.block_hash);
+
+        self.cache
+            .lock()
+            .unwrap()
+            .block_hashes
+            .cache_set(block, hash);
+        Ok(hash)
+    }
+
+    fn storage_value(
+        &self,
+        contract_address: ContractAddress,
+        key: StorageAddress,
+    ) -> anyhow::Result<Option<StorageValue>> {
+        let cache_key = (contract_address, key);
+        if let Some(value) = self.cache.lock().unwrap().storage.cache_get(&cache_key) {
+            return Ok(*value);
+        }
+
+        let value = self.request_blocking(
+            "starknet_getStorageAt",
+            serde_json::json!({
+                "contract_address": contract_address,
+                "key": key,
+                "block_id": self.block_id(),
+            }),
+        )?;
+
+        self.cache
+            .lock()
+            .unwrap()
+            .storage
+            .cache_set(cache_key, value);
+        Ok(value)
+    }
+
+    fn contract_nonce(
+        &self,
+        contract_address: ContractAddress,
+    ) -> anyhow::Result<Option<ContractNonce>> {
+        if let Some(nonce) = self
+            .cache
+            .lock()
+            .unwrap()
+            .nonces
+            .cache_get(&contract_address)
+        {
+            return Ok(*nonce);
+        }
+
+        let nonce = self.request_blocking(
+            "starknet_getNonce",
+            serde_json::json!({
+                "block_id": self.block_id(),
+                "contract_address": contract_address,
+            }),
+        )?;
+
+        self.cache
+            .lock()
+            .unwrap()
+            .nonces
+            .cache_set(contract_address, nonce);
+        Ok(nonce)
+    }
+
+    fn contract_class_hash(
+        &self,
+        contract_address: ContractAddress,
+    ) -> anyhow::Result<Option<ClassHash>> {
+        if let Some(class_hash) = self
+            .cache
+            .lock()
+            .unwrap()
+            .class_hashes
+            .cache_get(&contract_address)
+        {
+            return Ok(*class_hash);
+        }
+
+        let class_hash = self.request_blocking(
+            "starknet_getClassHashAt",
+            serde_json::json!({
+                "block_id": self.block_id(),
+                "contract_address": contract_address,
+            }),
+        )?;
+
+        self.cache
+            .lock()
+            .unwrap()
+            .class_hashes
+            .cache_set(contract_address, class_hash);
+        Ok(class_hash)
+    }
+
+    fn class_definition(&self, class_hash: ClassHash) -> anyhow::Result<Option<Vec<u8>>> {
+        Ok(self.class(class_hash)?.map(|class| match class.as_ref() {
+            Class::Cairo { definition } | Class::Sierra { definition, .. } => definition.clone(),
+        }))
+    }
+
+    fn casm_definition(&self, class_hash: ClassHash) -> anyhow::Result<Option<Vec<u8>>> {
+        Ok(self
+            .class(class_hash)?
+            .and_then(|class| match class.as_ref() {
+                Class::Cairo { .. } => None,
+                Class::Sierra {
+                    casm_definition, ..
+                } => Some(casm_definition.clone()),
+            }))
+    }
+
+    fn casm_hash(&self, class_hash: ClassHash) -> anyhow::Result<Option<CasmHash>> {
+        Ok(self
+            .class(class_hash)?
+            .and_then(|class| match class.as_ref() {
+                Class::Cairo { .. } => None,
+                Class::Sierra { casm_hash, .. } => Some(*casm_hash),
+            }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+
+    use super::*;
+
+    fn fork_header(number: u64) -> BlockHeader {
+        BlockHeader {
+            number: BlockNumber::new_or_panic(number),
+            hash: block_hash!("0xf0"),
+            parent_hash: block_hash!("0xef"),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn empty_database_is_initialized() {
+        let storage = Storage::in_memory().unwrap();
+        let header = fork_header(10);
+
+        initialize(&storage, &header).unwrap();
+        // Starting again from the same block is fine.
+        initialize(&storage, &header).unwrap();
+
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+        let latest = tx
+            .block_header(pathfinder_storage::BlockId::Latest)
+            .unwrap();
+        assert_eq!(latest, Some(header));
+    }
+
+    #[test]
+    fn different_fork_block_is_rejected() {
+        let storage = Storage::in_memory().unwrap();
+        initialize(&storage, &fork_header(10)).unwrap();
+
+        let other = BlockHeader {
+            hash: block_hash!("0xaa"),
+            ..fork_header(10)
+        };
+        initialize(&storage, &other).unwrap_err();
+        initialize(&storage, &fork_header(11)).unwrap_err();
+        initialize(&storage, &fork_header(9)).unwrap_err();
+    }
+
+    #[test]
+    fn database_with_history_is_rejected() {
+        let storage = Storage::in_memory().unwrap();
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+        tx.insert_block_header(&BlockHeader::builder().finalize_with_hash(block_hash!("0x1")))
+            .unwrap();
+        tx.commit().unwrap();
+
+        initialize(&storage, &fork_header(10)).unwrap_err();
+    }
+
+    #[test]
+    fn rpc_block_header() {
+        let block = serde_json::json!({
+            "status": "ACCEPTED_ON_L2",
+            "block_hash": "0x10",
+            "parent_hash": "0xf",
+            "block_number": 5,
+            "new_root": "0x123",
+            "timestamp": 1700000000,
+            "sequencer_address": "0x1234",
+            "l1_gas_price": { "price_in_fri": "0x2", "price_in_wei": "0x1" },
+            "l1_data_gas_price": { "price_in_fri": "0x4", "price_in_wei": "0x3" },
+            "l1_da_mode": "BLOB",
+            "starknet_version": "0.13.1",
+            "transactions": []
+        });
+
+        let header = serde_json::from_value::<RpcBlockHeader>(block)
+            .unwrap()
+            .into_header()
+            .unwrap();
+
+        assert_eq!(
+            header,
+            BlockHeader {
+                hash: block_hash!("0x10"),
+                parent_hash: block_hash!("0xf"),
+                number: BlockNumber::new_or_panic(5),
+                timestamp: BlockTimestamp::new_or_panic(1700000000),
+                eth_l1_gas_price: GasPrice(1),
+                strk_l1_gas_price: GasPrice(2),
+                eth_l1_data_gas_price: GasPrice(3),
+                strk_l1_data_gas_price: GasPrice(4),
+                sequencer_address: sequencer_address!("0x1234"),
+                starknet_version: StarknetVersion::new(0, 13, 1),
+                state_commitment: state_commitment!("0x123"),
+                l1_da_mode: L1DataAvailabilityMode::Blob,
+                ..Default::default()
+            }
+        );
+    }
+}
@@ -1,8 +1,11 @@
 #![deny(rust_2018_idioms)]
 
+pub mod backup;
+pub mod devnet;
 pub mod monitoring;
 pub mod state;
-mod sync;
+pub mod sync;
+pub mod wal_checkpoint;
 
 #[cfg(feature = "p2p")]
 pub mod p2p_network;
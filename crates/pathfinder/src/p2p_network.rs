@@ -1,19 +1,41 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
 use anyhow::Context;
-use p2p::client::peer_agnostic;
-use p2p::libp2p::{identity::Keypair, multiaddr::Multiaddr};
+use p2p::client::peer_scores::PeerScores;
+use p2p::client::{peer_agnostic, peer_aware};
+use p2p::libp2p::{identity::Keypair, multiaddr::Multiaddr, PeerId};
 use p2p::{HeadRx, HeadTx};
 use p2p_proto::header::BlockHeadersResponse;
+use pathfinder_common::transaction::Transaction;
 use pathfinder_common::{BlockHash, BlockNumber, ChainId};
+use pathfinder_rpc::peer_admin::{PeerAdminRequest, PeerDirection, PeerInfo};
 use pathfinder_storage::Storage;
+use tokio::sync::{mpsc, Semaphore};
 use tracing::Instrument;
 
 pub mod client;
-mod sync_handlers;
+pub(crate) mod sync_handlers;
+mod transaction_gossip;
 
 use sync_handlers::{
-    get_classes, get_events, get_headers, get_receipts, get_state_diffs, get_transactions,
+    get_classes, get_events, get_headers, get_receipts, get_snapshot, get_state_diffs,
+    get_transactions,
 };
 
+/// How often the scores and addresses of our peers are persisted.
+const PEERS_PERSIST_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How many inbound sync requests are served at the same time, across all peers. This is below
+/// the size of the p2p database connection pool, which is also used for other tasks.
+const MAX_CONCURRENT_SYNC_REQUESTS: usize = 8;
+
+/// How many gossiped transactions are validated at the same time. Transactions received while
+/// all of them are busy are dropped without being relayed.
+const MAX_CONCURRENT_TRANSACTION_VALIDATIONS: usize = 4;
+
 // Silence clippy
 pub type P2PNetworkHandle = (peer_agnostic::Client, HeadRx, tokio::task::JoinHandle<()>);
 
@@ -26,6 +48,10 @@ pub struct P2PContext {
     pub listen_on: Multiaddr,
     pub bootstrap_addresses: Vec<Multiaddr>,
     pub predefined_peers: Vec<Multiaddr>,
+    /// Transactions submitted through this node, to be published to its peers.
+    pub submitted_transactions: tokio::sync::broadcast::Receiver<Transaction>,
+    /// Peer administration requests of the RPC server.
+    pub peer_admin_requests: mpsc::Receiver<PeerAdminRequest>,
 }
 
 #[tracing::instrument(name = "p2p", skip_all)]
@@ -39,6 +65,8 @@ pub async fn start(context: P2PContext) -> anyhow::Result<P2PNetworkHandle> {
         listen_on,
         bootstrap_addresses,
         predefined_peers,
+        submitted_transactions,
+        peer_admin_requests,
     } = context;
 
     let peer_id = keypair.public().to_peer_id();
@@ -56,14 +84,21 @@ pub async fn start(context: P2PContext) -> anyhow::Result<P2PNetworkHandle> {
         .await
         .context("Starting P2P listener")?;
 
-    let ensure_peer_id_in_multiaddr = |addr: &Multiaddr, msg: &'static str| {
-        addr.iter()
-            .find_map(|p| match p {
-                p2p::libp2p::multiaddr::Protocol::P2p(peer_id) => Some(peer_id),
-                _ => None,
-            })
-            .ok_or_else(|| anyhow::anyhow!(msg))
-    };
+    // Bans must be in place before any peers are dialed.
+    let mut known_peers = Vec::new();
+    for peer in load_known_peers(storage.clone())
+        .await
+        .context("Loading known peers")?
+    {
+        let Ok(peer_id) = peer.peer_id.parse::<PeerId>() else {
+            continue;
+        };
+        if peer.banned {
+            p2p_client.ban(peer_id).await;
+        } else if let Some(address) = peer.address.and_then(|address| address.parse().ok()) {
+            known_peers.push((peer_id, address));
+        }
+    }
 
     for bootstrap_address in bootstrap_addresses {
         let peer_id = ensure_peer_id_in_multiaddr(
@@ -86,20 +121,57 @@ pub async fn start(context: P2PContext) -> anyhow::Result<P2PNetworkHandle> {
         p2p_client.dial(peer_id, peer).await?;
     }
 
+    // Peers from previous runs may no longer be reachable, so they are dialed in the background.
+    tokio::task::spawn(redial_known_peers(p2p_client.clone(), known_peers).in_current_span());
+
     let block_propagation_topic = format!("blocks/{}", chain_id.to_hex_str());
+    let transactions_topic = p2p::transactions_topic(chain_id);
 
     if !proxy {
         p2p_client.subscribe_topic(&block_propagation_topic).await?;
         tracing::info!(topic=%block_propagation_topic, "Subscribed to");
+        p2p_client.subscribe_topic(&transactions_topic).await?;
+        tracing::info!(topic=%transactions_topic, "Subscribed to");
+
+        tokio::task::spawn(
+            transaction_gossip::publish_submitted_transactions(
+                p2p_client.clone(),
+                transactions_topic,
+                submitted_transactions,
+            )
+            .in_current_span(),
+        );
     }
 
     for capability in p2p::PROTOCOLS {
         p2p_client.provide_capability(capability).await?
     }
 
+    let peer_scores = load_peer_scores(storage.clone())
+        .await
+        .context("Loading peer scores")?;
+    let sync_client =
+        peer_agnostic::Client::new(p2p_client.clone(), block_propagation_topic, peer_scores);
+    tokio::task::spawn(
+        persist_peers(p2p_client.clone(), sync_client.clone(), storage.clone()).in_current_span(),
+    );
+    tokio::task::spawn(
+        serve_peer_admin_requests(
+            p2p_client.clone(),
+            sync_client.clone(),
+            storage.clone(),
+            peer_admin_requests,
+        )
+        .in_current_span(),
+    );
+
     let (mut tx, rx) = tokio::sync::watch::channel(None);
 
     let join_handle = {
+        let p2p_client = p2p_client.clone();
+        let sync_requests = Arc::new(Semaphore::new(MAX_CONCURRENT_SYNC_REQUESTS));
+        let transaction_validations =
+            Arc::new(Semaphore::new(MAX_CONCURRENT_TRANSACTION_VALIDATIONS));
         tokio::task::spawn(
             async move {
                 loop {
@@ -109,7 +181,15 @@ pub async fn start(context: P2PContext) -> anyhow::Result<P2PNetworkHandle> {
                             break;
                         }
                         Some(event) = p2p_events.recv() => {
-                            match handle_p2p_event(event, storage.clone(), &mut tx).await {
+                            match handle_p2p_event(
+                                event,
+                                &p2p_client,
+                                storage.clone(),
+                                chain_id,
+                                &sync_requests,
+                                &transaction_validations,
+                                &mut tx,
+                            ).await {
                                 Ok(()) => {},
                                 Err(e) => { tracing::error!("Failed to handle P2P event: {}", e) },
                             }
@@ -121,48 +201,274 @@ pub async fn start(context: P2PContext) -> anyhow::Result<P2PNetworkHandle> {
         )
     };
 
-    Ok((
-        peer_agnostic::Client::new(p2p_client, block_propagation_topic),
-        rx,
-        join_handle,
-    ))
+    Ok((sync_client, rx, join_handle))
+}
+
+async fn load_peer_scores(storage: Storage) -> anyhow::Result<PeerScores> {
+    let scores = tokio::task::spawn_blocking(move || {
+        let mut db = storage
+            .connection()
+            .context("Creating database connection")?;
+        let db = db.transaction().context("Creating database transaction")?;
+        db.peer_scores()
+    })
+    .await
+    .context("Joining blocking task")??;
+
+    let scores = scores.into_iter().filter_map(|(peer, score)| {
+        let peer = peer.parse::<PeerId>().ok()?;
+        Some((peer, score))
+    });
+    Ok(PeerScores::from_snapshot(scores))
+}
+
+fn ensure_peer_id_in_multiaddr(addr: &Multiaddr, msg: &'static str) -> anyhow::Result<PeerId> {
+    addr.iter()
+        .find_map(|p| match p {
+            p2p::libp2p::multiaddr::Protocol::P2p(peer_id) => Some(peer_id),
+            _ => None,
+        })
+        .ok_or_else(|| anyhow::anyhow!(msg))
+}
+
+async fn load_known_peers(storage: Storage) -> anyhow::Result<Vec<pathfinder_storage::KnownPeer>> {
+    tokio::task::spawn_blocking(move || {
+        let mut db = storage
+            .connection()
+            .context("Creating database connection")?;
+        let db = db.transaction().context("Creating database transaction")?;
+        db.known_peers()
+    })
+    .await
+    .context("Joining blocking task")?
+}
+
+async fn redial_known_peers(client: peer_aware::Client, peers: Vec<(PeerId, Multiaddr)>) {
+    for (peer_id, address) in peers {
+        if let Err(error) = client.dial(peer_id, address).await {
+            tracing::debug!(%peer_id, %error, "Redialing known peer failed");
+        }
+    }
+}
+
+/// Periodically persists the scores of our peers, and the addresses of our outbound peers, so
+/// that they survive restarts.
+async fn persist_peers(
+    client: peer_aware::Client,
+    sync_client: peer_agnostic::Client,
+    storage: Storage,
+) {
+    let mut interval = tokio::time::interval(PEERS_PERSIST_INTERVAL);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    // The first tick completes immediately, at which point the peers were just loaded.
+    interval.tick().await;
+
+    loop {
+        interval.tick().await;
+
+        let scores = sync_client
+            .peer_scores_snapshot()
+            .await
+            .into_iter()
+            .map(|(peer, score)| (peer.to_string(), score))
+            .collect::<Vec<_>>();
+        // Only addresses we dialed are known to accept connections.
+        let addresses = client
+            .connected_peers()
+            .await
+            .into_iter()
+            .filter(|(_, peer)| peer.is_outbound() && !peer.is_relayed())
+            .filter_map(|(peer_id, peer)| Some((peer_id.to_string(), peer.addr?.to_string())))
+            .collect::<Vec<_>>();
+
+        let storage = storage.clone();
+        let result = tokio::task::spawn_blocking(move || {
+            let mut db = storage
+                .connection()
+                .context("Creating database connection")?;
+            let db = db.transaction().context("Creating database transaction")?;
+            db.replace_peer_scores(&scores)
+                .context("Replacing peer scores")?;
+            // Keep the previous addresses while we have no connectivity.
+            if !addresses.is_empty() {
+                db.replace_peer_addresses(&addresses)
+                    .context("Replacing peer addresses")?;
+            }
+            db.commit().context("Committing database transaction")
+        })
+        .await
+        .context("Joining blocking task")
+        .and_then(|result| result);
+
+        if let Err(error) = result {
+            tracing::warn!(%error, "Persisting peers failed");
+        }
+    }
+}
+
+/// Serves the peer administration requests of the RPC server, until it is dropped.
+async fn serve_peer_admin_requests(
+    client: peer_aware::Client,
+    sync_client: peer_agnostic::Client,
+    storage: Storage,
+    mut requests: mpsc::Receiver<PeerAdminRequest>,
+) {
+    while let Some(request) = requests.recv().await {
+        match request {
+            PeerAdminRequest::ListPeers { reply } => {
+                let scores = sync_client
+                    .peer_scores_snapshot()
+                    .await
+                    .into_iter()
+                    .collect::<HashMap<_, _>>();
+                let peers = client
+                    .connected_peers()
+                    .await
+                    .into_iter()
+                    .map(|(peer_id, peer)| PeerInfo {
+                        peer_id: peer_id.to_string(),
+                        address: peer.addr.as_ref().map(ToString::to_string),
+                        direction: if peer.is_inbound() {
+                            PeerDirection::Inbound
+                        } else {
+                            PeerDirection::Outbound
+                        },
+                        score: scores.get(&peer_id).copied().unwrap_or_default(),
+                    })
+                    .collect();
+                let _ = reply.send(peers);
+            }
+            PeerAdminRequest::ConnectPeer { address, reply } => {
+                let _ = reply.send(connect_peer(&client, &address).await);
+            }
+            PeerAdminRequest::BanPeer { peer_id, reply } => {
+                let _ = reply.send(ban_peer(&client, storage.clone(), &peer_id).await);
+            }
+        }
+    }
+}
+
+async fn connect_peer(client: &peer_aware::Client, address: &str) -> anyhow::Result<()> {
+    let address = address
+        .parse::<Multiaddr>()
+        .context("Parsing multiaddress")?;
+    let peer_id = ensure_peer_id_in_multiaddr(&address, "Address must include peer ID")?;
+    client.dial(peer_id, address).await
+}
+
+async fn ban_peer(
+    client: &peer_aware::Client,
+    storage: Storage,
+    peer_id: &str,
+) -> anyhow::Result<()> {
+    let peer_id = peer_id.parse::<PeerId>().context("Parsing peer ID")?;
+
+    let id = peer_id.to_string();
+    tokio::task::spawn_blocking(move || {
+        let mut db = storage
+            .connection()
+            .context("Creating database connection")?;
+        let db = db.transaction().context("Creating database transaction")?;
+        db.ban_peer(&id).context("Banning peer")?;
+        db.commit().context("Committing database transaction")
+    })
+    .await
+    .context("Joining blocking task")??;
+
+    client.ban(peer_id).await;
+    Ok(())
+}
+
+/// Serves an inbound sync request in the background, unless too many requests are already being
+/// served. Per-peer limits are enforced by the p2p main loop.
+fn serve_sync_request(
+    sync_requests: &Arc<Semaphore>,
+    from: PeerId,
+    serve: impl Future<Output = anyhow::Result<()>> + Send + 'static,
+) {
+    let Ok(permit) = sync_requests.clone().try_acquire_owned() else {
+        // Dropping the request ends the response stream.
+        tracing::debug!(%from, "Too many sync requests being served, ignoring");
+        return;
+    };
+
+    tokio::task::spawn(
+        async move {
+            if let Err(error) = serve.await {
+                tracing::error!(%from, %error, "Failed to serve sync request");
+            }
+            drop(permit);
+        }
+        .in_current_span(),
+    );
 }
 
 async fn handle_p2p_event(
     event: p2p::Event,
+    client: &p2p::client::peer_aware::Client,
     storage: Storage,
+    chain_id: ChainId,
+    sync_requests: &Arc<Semaphore>,
+    transaction_validations: &Arc<Semaphore>,
     tx: &mut HeadTx,
 ) -> anyhow::Result<()> {
     match event {
         p2p::Event::InboundHeadersSyncRequest {
-            request, channel, ..
+            from,
+            request,
+            channel,
         } => {
-            get_headers(storage, request, channel).await?;
+            serve_sync_request(sync_requests, from, get_headers(storage, request, channel));
         }
         p2p::Event::InboundClassesSyncRequest {
-            request, channel, ..
+            from,
+            request,
+            channel,
         } => {
-            get_classes(storage, request, channel).await?;
+            serve_sync_request(sync_requests, from, get_classes(storage, request, channel));
         }
         p2p::Event::InboundStateDiffsSyncRequest {
-            request, channel, ..
+            from,
+            request,
+            channel,
         } => {
-            get_state_diffs(storage, request, channel).await?;
+            serve_sync_request(
+                sync_requests,
+                from,
+                get_state_diffs(storage, request, channel),
+            );
         }
         p2p::Event::InboundTransactionsSyncRequest {
-            request, channel, ..
+            from,
+            request,
+            channel,
         } => {
-            get_transactions(storage, request, channel).await?;
+            serve_sync_request(
+                sync_requests,
+                from,
+                get_transactions(storage, request, channel),
+            );
         }
         p2p::Event::InboundReceiptsSyncRequest {
-            request, channel, ..
+            from,
+            request,
+            channel,
         } => {
-            get_receipts(storage, request, channel).await?;
+            serve_sync_request(sync_requests, from, get_receipts(storage, request, channel));
         }
         p2p::Event::InboundEventsSyncRequest {
-            request, channel, ..
+            from,
+            request,
+            channel,
+        } => {
+            serve_sync_request(sync_requests, from, get_events(storage, request, channel));
+        }
+        p2p::Event::InboundSnapshotSyncRequest {
+            from,
+            request,
+            channel,
         } => {
-            get_events(storage, request, channel).await?;
+            serve_sync_request(sync_requests, from, get_snapshot(storage, request, channel));
         }
         p2p::Event::BlockPropagation { from, new_block } => {
             tracing::info!(%from, ?new_block, "Block Propagation");
@@ -194,6 +500,37 @@ async fn handle_p2p_event(
                 }
             }
         }
+        p2p::Event::TransactionPropagation {
+            from,
+            message_id,
+            transaction,
+        } => {
+            // Without a verdict the transaction is not relayed, and gossipsub forgets about it
+            // once it drops out of the message cache.
+            let Ok(permit) = transaction_validations.clone().try_acquire_owned() else {
+                tracing::trace!(%from, "Too many gossiped transactions being validated, dropping");
+                return Ok(());
+            };
+
+            // Reporting the verdict is a command to the p2p main loop, which may in turn be
+            // waiting for this event to be handled.
+            let client = client.clone();
+            tokio::task::spawn(
+                async move {
+                    transaction_gossip::handle_propagated_transaction(
+                        client,
+                        storage,
+                        chain_id,
+                        from,
+                        message_id,
+                        transaction,
+                    )
+                    .await;
+                    drop(permit);
+                }
+                .in_current_span(),
+            );
+        }
         p2p::Event::SyncPeerConnected { .. } | p2p::Event::Test(_) => { /* Ignore me */ }
     }
 
@@ -10,10 +10,17 @@ use p2p_proto::common::{
 use p2p_proto::event::{EventsRequest, EventsResponse};
 use p2p_proto::header::{BlockHeadersRequest, BlockHeadersResponse, SignedBlockHeader};
 use p2p_proto::receipt::{ReceiptsRequest, ReceiptsResponse};
+use p2p_proto::snapshot::{
+    ClassLeaf, ContractState, SnapshotProof, SnapshotRange, SnapshotRequest, SnapshotResponse,
+};
 use p2p_proto::state::{ContractDiff, ContractStoredValue, StateDiffsRequest, StateDiffsResponse};
 use p2p_proto::transaction::{TransactionsRequest, TransactionsResponse};
-use pathfinder_common::{BlockHash, BlockNumber};
+use pathfinder_common::trie::TrieNode;
+use pathfinder_common::{
+    BlockHash, BlockNumber, ClassHash, ContractAddress, SierraHash, StorageAddress,
+};
 use pathfinder_crypto::Felt;
+use pathfinder_merkle_tree::{ClassCommitmentTree, ContractsStorageTree, StorageCommitmentTree};
 use pathfinder_storage::Storage;
 use pathfinder_storage::Transaction;
 use starknet_gateway_types::class_definition;
@@ -35,6 +42,9 @@ const MAX_COUNT_IN_TESTS: u64 = 10;
 #[cfg(test)]
 const MAX_BLOCKS_COUNT: u64 = MAX_COUNT_IN_TESTS;
 
+/// The maximum number of trie leaves served in response to a single snapshot request.
+const MAX_SNAPSHOT_LEAVES: u32 = 1024;
+
 pub async fn get_headers(
     storage: Storage,
     request: BlockHeadersRequest,
@@ -83,6 +93,14 @@ pub async fn get_events(
     spawn_blocking_get(request, storage, blocking::get_events, tx).await
 }
 
+pub async fn get_snapshot(
+    storage: Storage,
+    request: SnapshotRequest,
+    tx: futures::channel::mpsc::Sender<SnapshotResponse>,
+) -> anyhow::Result<()> {
+    spawn_blocking_get(request, storage, blocking::get_snapshot, tx).await
+}
+
 pub(crate) mod blocking {
     use super::*;
 
@@ -133,6 +151,24 @@ pub(crate) mod blocking {
     ) -> anyhow::Result<()> {
         iterate(db_tx, request.iteration, get_events_for_block, tx)
     }
+
+    pub(crate) fn get_snapshot(
+        db_tx: Transaction<'_>,
+        request: SnapshotRequest,
+        tx: mpsc::Sender<SnapshotResponse>,
+    ) -> anyhow::Result<()> {
+        if let Some(block_number) = BlockNumber::new(request.block_number) {
+            let limit = request.limit.min(MAX_SNAPSHOT_LEAVES) as usize;
+            if limit > 0 {
+                get_snapshot_range(&db_tx, block_number, request.range, limit, &tx)?;
+            }
+        }
+
+        tx.blocking_send(SnapshotResponse::Fin)
+            .map_err(|_| anyhow::anyhow!("Sending Fin"))?;
+
+        Ok(())
+    }
 }
 
 fn get_header(
@@ -374,6 +410,129 @@ fn get_events_for_block(
     Ok(true)
 }
 
+/// Sends the leaves of the requested trie range followed by their [SnapshotProof].
+///
+/// Nothing is sent if we don't have the tries of the requested block.
+fn get_snapshot_range(
+    db_tx: &Transaction<'_>,
+    block_number: BlockNumber,
+    range: SnapshotRange,
+    limit: usize,
+    tx: &mpsc::Sender<SnapshotResponse>,
+) -> anyhow::Result<()> {
+    let Some(header) = db_tx.block_header(block_number.into())? else {
+        return Ok(());
+    };
+
+    // The tries of this block may have been pruned.
+    if header.storage_commitment.0 != Felt::ZERO
+        && db_tx.storage_root_index(block_number)?.is_none()
+    {
+        return Ok(());
+    }
+    if header.class_commitment.0 != Felt::ZERO && db_tx.class_root_index(block_number)?.is_none() {
+        return Ok(());
+    }
+
+    let send = |response| {
+        tx.blocking_send(response)
+            .map_err(|_| anyhow::anyhow!("Sending snapshot"))
+    };
+
+    let (start_proof, end_proof) = match range {
+        SnapshotRange::Contracts(start) => {
+            let start = ContractAddress(start.0);
+            let contracts = StorageCommitmentTree::get_range(db_tx, block_number, start, limit)?;
+
+            for (address, _) in &contracts {
+                let class_hash = if *address == ContractAddress::ONE {
+                    // This is a special system contract at address 0x1, which doesn't have a class hash.
+                    ClassHash::ZERO
+                } else {
+                    db_tx
+                        .contract_class_hash(block_number.into(), *address)?
+                        .context("Contract's class hash is missing")?
+                };
+                let nonce = db_tx
+                    .contract_nonce(*address, block_number.into())?
+                    .unwrap_or_default();
+                let root = db_tx
+                    .contract_root(block_number, *address)?
+                    .unwrap_or_default();
+
+                send(SnapshotResponse::Contract(ContractState {
+                    address: Address(address.0),
+                    class_hash: Hash(class_hash.0),
+                    storage_root: Hash(root.0),
+                    nonce: nonce.0,
+                }))?;
+            }
+
+            let start_proof = StorageCommitmentTree::get_proof(db_tx, block_number, &start)?;
+            let end_proof = match contracts.last() {
+                Some((last, _)) => StorageCommitmentTree::get_proof(db_tx, block_number, last)?,
+                None => Vec::new(),
+            };
+            (start_proof, end_proof)
+        }
+        SnapshotRange::Storage(range) => {
+            let contract = ContractAddress(range.contract.0);
+            let start = StorageAddress(range.start);
+            let slots =
+                ContractsStorageTree::get_range(db_tx, contract, block_number, start, limit)?;
+
+            for (key, value) in &slots {
+                send(SnapshotResponse::Storage(ContractStoredValue {
+                    key: key.0,
+                    value: value.0,
+                }))?;
+            }
+
+            let start_proof =
+                ContractsStorageTree::get_proof(db_tx, contract, block_number, start.view_bits())?;
+            let end_proof = match slots.last() {
+                Some((last, _)) => ContractsStorageTree::get_proof(
+                    db_tx,
+                    contract,
+                    block_number,
+                    last.view_bits(),
+                )?,
+                None => Vec::new(),
+            };
+            (start_proof, end_proof)
+        }
+        SnapshotRange::Classes(start) => {
+            let start = SierraHash(start.0);
+            let classes = ClassCommitmentTree::get_range(db_tx, block_number, start, limit)?;
+
+            for (class_hash, _) in &classes {
+                let casm_hash = db_tx
+                    .casm_hash_at(block_number.into(), ClassHash(class_hash.0))?
+                    .context("Compiled class hash is missing")?;
+
+                send(SnapshotResponse::Class(ClassLeaf {
+                    class_hash: Hash(class_hash.0),
+                    compiled_class_hash: Hash(casm_hash.0),
+                }))?;
+            }
+
+            let start_proof = ClassCommitmentTree::get_proof(db_tx, block_number, start)?;
+            let end_proof = match classes.last() {
+                Some((last, _)) => ClassCommitmentTree::get_proof(db_tx, block_number, *last)?,
+                None => Vec::new(),
+            };
+            (start_proof, end_proof)
+        }
+    };
+
+    send(SnapshotResponse::Proof(SnapshotProof {
+        storage_commitment: Hash(header.storage_commitment.0),
+        class_commitment: Hash(header.class_commitment.0),
+        start: start_proof.into_iter().map(TrieNode::to_dto).collect(),
+        end: end_proof.into_iter().map(TrieNode::to_dto).collect(),
+    }))
+}
+
 /// Assupmtions:
 /// - `block_handler` returns `Ok(true)` if the iteration should continue,
 /// - `T::default()` always returns the `Fin` variant of the implementing type.
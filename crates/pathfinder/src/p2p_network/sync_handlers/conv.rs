@@ -10,6 +10,7 @@ use p2p_proto::receipt::{
 use p2p_proto::transaction::{AccountSignature, ResourceBounds};
 use pathfinder_common::receipt::Receipt;
 use pathfinder_common::transaction::DataAvailabilityMode;
+use pathfinder_common::trie::TrieNode;
 use pathfinder_common::{event::Event, transaction::ResourceBound, transaction::Transaction};
 use pathfinder_common::{
     AccountDeploymentDataElem, L1DataAvailabilityMode, PaymasterDataElem, TransactionHash,
@@ -305,6 +306,21 @@ impl ToDto<p2p_proto::common::L1DataAvailabilityMode> for L1DataAvailabilityMode
     }
 }
 
+impl ToDto<p2p_proto::snapshot::PatriciaNode> for TrieNode {
+    fn to_dto(self) -> p2p_proto::snapshot::PatriciaNode {
+        use p2p_proto::snapshot::PatriciaNode::{Binary, Edge};
+        match self {
+            TrieNode::Binary { left, right } => Binary { left, right },
+            TrieNode::Edge { child, path } => Edge {
+                // Safe as the path is at most 251 bits long
+                length: path.len() as u32,
+                path: Felt::from_bits(&path).expect("path fits into a felt"),
+                child,
+            },
+        }
+    }
+}
+
 pub fn sierra_def_into_dto(sierra: Sierra<'_>, compiled: Vec<u8>) -> Cairo1Class {
     let into_dto = |x: SelectorAndFunctionIndex| SierraEntryPoint {
         selector: x.selector.0,
@@ -33,7 +33,8 @@ fn get_next_block_number(
 mod boundary_conditions {
     use super::I64_MAX;
     use crate::p2p_network::sync_handlers::{
-        get_classes, get_events, get_headers, get_receipts, get_state_diffs, get_transactions,
+        get_classes, get_events, get_headers, get_receipts, get_snapshot, get_state_diffs,
+        get_transactions,
     };
     use fake::{Fake, Faker};
     use futures::channel::mpsc;
@@ -43,6 +44,7 @@ mod boundary_conditions {
     use p2p_proto::event::EventsRequest;
     use p2p_proto::header::BlockHeadersRequest;
     use p2p_proto::receipt::ReceiptsRequest;
+    use p2p_proto::snapshot::{SnapshotRequest, SnapshotResponse};
     use p2p_proto::state::StateDiffsRequest;
     use p2p_proto::transaction::TransactionsRequest;
     use pathfinder_storage::Storage;
@@ -91,6 +93,23 @@ mod boundary_conditions {
         define_test!(receipts, get_receipts, ReceiptsRequest);
         define_test!(events, get_events, EventsRequest);
     }
+
+    #[rstest]
+    #[case::zero_limit(0, 0)]
+    #[case::missing_block(1, 10)]
+    #[case::invalid_block(I64_MAX + 1, 10)]
+    #[tokio::test]
+    async fn snapshot_without_state_yields_fin(#[case] block_number: u64, #[case] limit: u32) {
+        let storage = Storage::in_memory().unwrap();
+        let (tx, mut rx) = mpsc::channel(0);
+        let request = SnapshotRequest {
+            block_number,
+            limit,
+            ..Faker.fake()
+        };
+        let _jh = tokio::spawn(get_snapshot(storage, request, tx));
+        assert_eq!(rx.next().await.unwrap(), SnapshotResponse::Fin);
+    }
 }
 
 /// Property tests, grouped to be immediately visible when executed
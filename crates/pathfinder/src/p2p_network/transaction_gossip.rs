@@ -0,0 +1,334 @@
+//! Propagation of pending transactions via gossipsub.
+//!
+//! Transactions received from peers are validated before they are relayed any further. Only
+//! checks which do not require execution are performed: the transaction hash, the presence of a
+//! signature, non-zero fee bounds and a nonce within [NONCE_WINDOW] of the sender's current
+//! nonce. Declare transactions are not propagated as they would have to include the class
+//! definition.
+use anyhow::Context;
+use p2p::client::conv::TryFromDto;
+use p2p::client::peer_aware::Client;
+use p2p::libp2p::gossipsub::{MessageAcceptance, MessageId};
+use p2p::libp2p::PeerId;
+use pathfinder_common::transaction::{ResourceBounds, Transaction, TransactionVariant};
+use pathfinder_common::{ChainId, ContractAddress, TransactionNonce};
+use pathfinder_crypto::Felt;
+use pathfinder_storage::{BlockId, Storage};
+use tokio::sync::broadcast;
+
+use super::sync_handlers::conv::ToDto;
+
+/// How far ahead of its sender's current nonce a relayed transaction's nonce may be.
+const NONCE_WINDOW: u64 = 32;
+
+#[derive(Debug, PartialEq, Eq)]
+enum Verdict {
+    Accept,
+    /// The transaction is invalid, and the peer which sent it is penalized.
+    Reject(&'static str),
+    /// The transaction may be valid, but is not relayed.
+    Ignore(&'static str),
+}
+
+/// Reports whether a transaction received from `from` should be relayed to other peers.
+pub(super) async fn handle_propagated_transaction(
+    client: Client,
+    storage: Storage,
+    chain_id: ChainId,
+    from: PeerId,
+    message_id: MessageId,
+    transaction: p2p_proto::transaction::Transaction,
+) {
+    let hash = transaction.hash.0;
+
+    let verdict = tokio::task::spawn_blocking(move || {
+        let mut db = storage
+            .connection()
+            .context("Creating database connection")?;
+        let db = db.transaction().context("Creating database transaction")?;
+        validate(transaction, chain_id, &db)
+    })
+    .await
+    .context("Joining blocking task")
+    .and_then(|verdict| verdict)
+    .unwrap_or_else(|error| {
+        tracing::warn!(%hash, %error, "Validating gossiped transaction failed");
+        Verdict::Ignore("validation failed")
+    });
+
+    let acceptance = match verdict {
+        Verdict::Accept => {
+            tracing::trace!(%from, %hash, "Relaying gossiped transaction");
+            MessageAcceptance::Accept
+        }
+        Verdict::Reject(reason) => {
+            tracing::debug!(%from, %hash, %reason, "Rejecting gossiped transaction");
+            MessageAcceptance::Reject
+        }
+        Verdict::Ignore(reason) => {
+            tracing::trace!(%from, %hash, %reason, "Ignoring gossiped transaction");
+            MessageAcceptance::Ignore
+        }
+    };
+
+    client
+        .report_message_validation(message_id, from, acceptance)
+        .await;
+}
+
+/// Publishes transactions submitted through this node's RPC to its peers.
+pub(super) async fn publish_submitted_transactions(
+    client: Client,
+    topic: String,
+    mut submitted: broadcast::Receiver<Transaction>,
+) {
+    loop {
+        match submitted.recv().await {
+            Ok(transaction) => {
+                let hash = transaction.hash;
+                if let Err(error) = client
+                    .publish_transaction(&topic, transaction.to_dto())
+                    .await
+                {
+                    tracing::debug!(%hash, %error, "Publishing transaction failed");
+                }
+            }
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                tracing::warn!(%skipped, "Publishing submitted transactions lagged behind");
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        }
+    }
+}
+
+fn validate(
+    transaction: p2p_proto::transaction::Transaction,
+    chain_id: ChainId,
+    db: &pathfinder_storage::Transaction<'_>,
+) -> anyhow::Result<Verdict> {
+    let Ok(mut transaction) = Transaction::try_from_dto(transaction) else {
+        return Ok(Verdict::Reject("malformed transaction"));
+    };
+    normalize(&mut transaction);
+
+    let (sender, nonce, signature, has_fee) = match &transaction.variant {
+        TransactionVariant::InvokeV1(tx) => (
+            tx.sender_address,
+            tx.nonce,
+            &tx.signature,
+            tx.max_fee.0 != Felt::ZERO,
+        ),
+        TransactionVariant::InvokeV3(tx) => (
+            tx.sender_address,
+            tx.nonce,
+            &tx.signature,
+            has_resource_bounds(&tx.resource_bounds),
+        ),
+        TransactionVariant::DeployAccountV0V1(tx) => (
+            tx.contract_address,
+            tx.nonce,
+            &tx.signature,
+            tx.max_fee.0 != Felt::ZERO,
+        ),
+        TransactionVariant::DeployAccountV3(tx) => (
+            tx.contract_address,
+            tx.nonce,
+            &tx.signature,
+            has_resource_bounds(&tx.resource_bounds),
+        ),
+        TransactionVariant::DeclareV0(_)
+        | TransactionVariant::DeclareV1(_)
+        | TransactionVariant::DeclareV2(_)
+        | TransactionVariant::DeclareV3(_) => {
+            return Ok(Verdict::Ignore("declare transactions are not propagated"))
+        }
+        TransactionVariant::Deploy(_)
+        | TransactionVariant::InvokeV0(_)
+        | TransactionVariant::L1Handler(_) => {
+            return Ok(Verdict::Reject(
+                "transaction type can no longer be submitted",
+            ))
+        }
+    };
+
+    // Accounts are free to accept transactions without a signature.
+    if signature.is_empty() {
+        return Ok(Verdict::Ignore("missing signature"));
+    }
+    if !has_fee {
+        return Ok(Verdict::Reject("zero fee bounds"));
+    }
+    if !transaction.verify_hash(chain_id) {
+        return Ok(Verdict::Reject("transaction hash mismatch"));
+    }
+
+    let is_deploy_account = matches!(
+        transaction.variant,
+        TransactionVariant::DeployAccountV0V1(_) | TransactionVariant::DeployAccountV3(_)
+    );
+    if is_deploy_account {
+        if nonce != TransactionNonce::ZERO {
+            return Ok(Verdict::Reject("deploy account nonce must be zero"));
+        }
+        if db
+            .contract_exists(sender, BlockId::Latest)
+            .context("Querying contract existence")?
+        {
+            return Ok(Verdict::Ignore("account already deployed"));
+        }
+        return Ok(Verdict::Accept);
+    }
+
+    let current = match db
+        .contract_nonce(sender, BlockId::Latest)
+        .context("Querying sender nonce")?
+    {
+        Some(nonce) => nonce.0,
+        // Contracts have no nonce entry until their first transaction.
+        None if db
+            .contract_exists(sender, BlockId::Latest)
+            .context("Querying contract existence")? =>
+        {
+            Felt::ZERO
+        }
+        // The account may be deployed by a transaction we have not seen yet.
+        None => return Ok(Verdict::Ignore("unknown sender")),
+    };
+
+    if nonce.0 < current {
+        return Ok(Verdict::Ignore("nonce already used"));
+    }
+    if nonce.0 >= current + Felt::from_u64(NONCE_WINDOW) {
+        return Ok(Verdict::Ignore("nonce too far ahead"));
+    }
+
+    Ok(Verdict::Accept)
+}
+
+/// Undoes the lossy parts of the conversion from the p2p representation.
+fn normalize(transaction: &mut Transaction) {
+    match &mut transaction.variant {
+        // The deployed address is not part of the p2p representation.
+        TransactionVariant::DeployAccountV0V1(tx) => {
+            tx.contract_address = ContractAddress::deployed_contract_address(
+                tx.constructor_calldata.iter().copied(),
+                &tx.contract_address_salt,
+                &tx.class_hash,
+            );
+        }
+        TransactionVariant::DeployAccountV3(tx) => {
+            tx.contract_address = ContractAddress::deployed_contract_address(
+                tx.constructor_calldata.iter().copied(),
+                &tx.contract_address_salt,
+                &tx.class_hash,
+            );
+            // The p2p representation carries exactly one element, which is zero if there was
+            // none.
+            if tx.paymaster_data.iter().all(|x| x.0 == Felt::ZERO) {
+                tx.paymaster_data.clear();
+            }
+        }
+        TransactionVariant::InvokeV3(tx) => {
+            if tx.paymaster_data.iter().all(|x| x.0 == Felt::ZERO) {
+                tx.paymaster_data.clear();
+            }
+            if tx.account_deployment_data.iter().all(|x| x.0 == Felt::ZERO) {
+                tx.account_deployment_data.clear();
+            }
+        }
+        _ => {}
+    }
+}
+
+fn has_resource_bounds(bounds: &ResourceBounds) -> bool {
+    bounds.l1_gas.max_amount.0 != 0 && bounds.l1_gas.max_price_per_unit.0 != 0
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::transaction::{compute_transaction_hash, InvokeTransactionV1};
+    use pathfinder_common::{state_update::StateUpdate, BlockHeader, ContractNonce, Fee};
+
+    use super::*;
+
+    fn invoke(nonce: u64) -> Transaction {
+        let variant = TransactionVariant::InvokeV1(InvokeTransactionV1 {
+            sender_address: contract_address_bytes!(b"sender"),
+            max_fee: Fee(Felt::from_u64(1000)),
+            signature: vec![transaction_signature_elem_bytes!(b"signature")],
+            nonce: TransactionNonce(Felt::from_u64(nonce)),
+            ..Default::default()
+        });
+        Transaction {
+            hash: compute_transaction_hash(&variant, ChainId::SEPOLIA_TESTNET),
+            variant,
+        }
+    }
+
+    fn validate_invoke(transaction: Transaction) -> Verdict {
+        let storage = pathfinder_storage::Storage::in_memory().unwrap();
+        let mut connection = storage.connection().unwrap();
+        let db = connection.transaction().unwrap();
+
+        let header = BlockHeader::builder().finalize_with_hash(block_hash_bytes!(b"genesis"));
+        db.insert_block_header(&header).unwrap();
+        let state_update = StateUpdate::default()
+            .with_block_hash(header.hash)
+            .with_deployed_contract(
+                contract_address_bytes!(b"sender"),
+                class_hash_bytes!(b"account"),
+            )
+            .with_contract_nonce(
+                contract_address_bytes!(b"sender"),
+                ContractNonce(Felt::from_u64(5)),
+            );
+        db.insert_state_update(header.number, &state_update)
+            .unwrap();
+
+        validate(transaction.to_dto(), ChainId::SEPOLIA_TESTNET, &db).unwrap()
+    }
+
+    #[test]
+    fn valid_invoke() {
+        assert_eq!(validate_invoke(invoke(5)), Verdict::Accept);
+        assert_eq!(
+            validate_invoke(invoke(5 + NONCE_WINDOW - 1)),
+            Verdict::Accept
+        );
+    }
+
+    #[test]
+    fn nonce_outside_window() {
+        assert_matches::assert_matches!(validate_invoke(invoke(4)), Verdict::Ignore(_));
+        assert_matches::assert_matches!(
+            validate_invoke(invoke(5 + NONCE_WINDOW)),
+            Verdict::Ignore(_)
+        );
+    }
+
+    #[test]
+    fn invalid_invoke() {
+        let mut wrong_hash = invoke(5);
+        wrong_hash.hash = transaction_hash_bytes!(b"wrong");
+        assert_matches::assert_matches!(validate_invoke(wrong_hash), Verdict::Reject(_));
+
+        let mut no_fee = invoke(5);
+        let TransactionVariant::InvokeV1(tx) = &mut no_fee.variant else {
+            unreachable!()
+        };
+        tx.max_fee = Fee::ZERO;
+        assert_matches::assert_matches!(validate_invoke(no_fee), Verdict::Reject(_));
+    }
+
+    #[test]
+    fn unsigned_invoke() {
+        let mut unsigned = invoke(5);
+        let TransactionVariant::InvokeV1(tx) = &mut unsigned.variant else {
+            unreachable!()
+        };
+        tx.signature.clear();
+        unsigned.hash = compute_transaction_hash(&unsigned.variant, ChainId::SEPOLIA_TESTNET);
+        assert_matches::assert_matches!(validate_invoke(unsigned), Verdict::Ignore(_));
+    }
+}
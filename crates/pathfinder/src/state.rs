@@ -1,4 +1,5 @@
 pub mod block_hash;
 mod sync;
 
-pub use sync::{l1, l2, sync, Gossiper, SyncContext};
+pub use sync::{backfill, l1, l2, sync, Gossiper, SyncContext};
+pub(crate) use sync::{class, update_starknet_state};
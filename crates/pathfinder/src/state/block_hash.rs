@@ -1,5 +1,6 @@
 use anyhow::{Context, Result};
 use pathfinder_common::event::Event;
+use pathfinder_common::protocol::ProtocolChange;
 use pathfinder_common::receipt::Receipt;
 use pathfinder_common::transaction::{Transaction, TransactionVariant};
 use pathfinder_common::{
@@ -254,7 +255,7 @@ fn compute_final_hash_pre_0_7(
 
 /// This implements the final hashing step for post-0.7 blocks.
 #[allow(clippy::too_many_arguments)]
-fn compute_final_hash(
+pub(crate) fn compute_final_hash(
     block_number: BlockNumber,
     state_root: StateCommitment,
     sequencer_address: &SequencerAddress,
@@ -300,13 +301,11 @@ pub enum TransactionCommitmentFinalHashType {
 
 impl TransactionCommitmentFinalHashType {
     pub fn for_version(version: &StarknetVersion) -> anyhow::Result<Self> {
-        const V_0_11_1: semver::Version = semver::Version::new(0, 11, 1);
-
-        Ok(match version.parse_as_semver()? {
-            None => Self::SignatureIncludedForInvokeOnly,
-            Some(v) if v < V_0_11_1 => Self::SignatureIncludedForInvokeOnly,
-            Some(_) => Self::Normal,
-        })
+        if version.supports(ProtocolChange::TransactionCommitmentSignatures)? {
+            Ok(Self::Normal)
+        } else {
+            Ok(Self::SignatureIncludedForInvokeOnly)
+        }
     }
 }
 
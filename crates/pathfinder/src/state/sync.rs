@@ -1,20 +1,22 @@
-mod class;
+pub mod backfill;
+pub(crate) mod class;
 pub mod l1;
 pub mod l2;
 mod pending;
+mod watchdog;
 
 use anyhow::Context;
 use pathfinder_common::prelude::*;
 use pathfinder_common::BlockCommitmentSignature;
 use pathfinder_common::Chain;
 use pathfinder_crypto::Felt;
-use pathfinder_ethereum::{EthereumApi, EthereumStateUpdate};
+use pathfinder_ethereum::{EthereumApi, EthereumStateUpdate, L1GasPrices};
 use pathfinder_merkle_tree::contract_state::update_contract_state;
 use pathfinder_merkle_tree::{ClassCommitmentTree, StorageCommitmentTree};
 use pathfinder_rpc::PendingData;
 use pathfinder_rpc::{
     v02::types::syncing::{self, NumberedBlock, Syncing},
-    SyncState, TopicBroadcasters,
+    BlockEvents, ReorgHead, SyncState, TopicBroadcasters,
 };
 use pathfinder_storage::{Connection, Storage, Transaction, TransactionBehavior};
 use primitive_types::H160;
@@ -29,13 +31,16 @@ use std::time::Instant;
 use tokio::sync::mpsc::{self, Receiver};
 
 use crate::state::l1::L1SyncContext;
-use crate::state::l2::{BlockChain, L2SyncContext};
+use crate::state::l2::{BlockChain, L2SyncContext, TrustedBlock};
+use watchdog::{Progress, Stall, Watchdog};
 
 use tokio::sync::watch::Sender as WatchSender;
 
 #[derive(Debug)]
 pub enum SyncEvent {
     L1Update(EthereumStateUpdate),
+    /// The gas prices of the latest Ethereum block changed.
+    L1GasPrices(L1GasPrices),
     /// New L2 [block update](StateUpdate) found.
     Block(
         (Box<Block>, (TransactionCommitment, EventCommitment)),
@@ -79,6 +84,14 @@ pub struct SyncContext<G, E> {
     pub restart_delay: Duration,
     pub verify_tree_hashes: bool,
     pub gossiper: Gossiper,
+    pub block_prefetch: usize,
+    pub trusted_block: Option<TrustedBlock>,
+    /// Stop syncing if the state root the Starknet core contract recorded for a block differs
+    /// from the local one, instead of only raising an alarm.
+    pub halt_on_l1_state_root_mismatch: bool,
+    /// Restart the L1 or L2 sync process if it makes no progress for this long, or never if
+    /// [None].
+    pub stall_timeout: Option<Duration>,
 }
 
 impl<G, E> From<&SyncContext<G, E>> for L1SyncContext<E>
@@ -91,6 +104,7 @@ where
             chain: value.chain,
             core_address: value.core_address,
             poll_interval: value.head_poll_interval,
+            progress: Default::default(),
         }
     }
 }
@@ -106,6 +120,9 @@ where
             chain_id: value.chain_id,
             block_validation_mode: value.block_validation_mode,
             storage: value.storage.clone(),
+            block_prefetch: value.block_prefetch,
+            trusted_block: value.trusted_block,
+            progress: Default::default(),
         }
     }
 }
@@ -181,6 +198,10 @@ where
         restart_delay,
         verify_tree_hashes: _,
         gossiper,
+        block_prefetch: _,
+        trusted_block,
+        halt_on_l1_state_root_mismatch,
+        stall_timeout,
     } = context;
 
     let mut db_conn = storage
@@ -234,12 +255,20 @@ where
         block_chain,
     ));
 
+    let mut watchdog = Watchdog::new(stall_timeout);
+    let l1_progress = l1_context.progress.clone();
+    let l2_progress = l2_context.progress.clone();
+    let consumer_progress = Progress::default();
+
     let consumer_context = ConsumerContext {
         storage,
-        state,
+        state: state.clone(),
         pending_data,
         verify_tree_hashes: context.verify_tree_hashes,
         websocket_txs,
+        trusted_block,
+        halt_on_l1_state_root_mismatch,
+        progress: consumer_progress.clone(),
     };
     let mut consumer_handle = tokio::spawn(consumer(event_receiver, consumer_context));
 
@@ -253,15 +282,19 @@ where
     loop {
         tokio::select! {
             l1_producer_result = &mut l1_handle => {
-                match l1_producer_result.context("Join L1 sync process handle")? {
-                    Ok(()) => {
+                match l1_producer_result {
+                    Ok(Ok(())) => {
                         tracing::error!("L1 sync process terminated without an error.");
                     }
-                    Err(e) => {
+                    Ok(Err(e)) => {
                         tracing::warn!("L1 sync process terminated with: {e:?}");
                     }
+                    // Aborted by the watchdog.
+                    Err(e) if e.is_cancelled() => {}
+                    Err(e) => return Err(e).context("Join L1 sync process handle"),
                 }
 
+                l1_progress.restart_after(RESET_DELAY_ON_FAILURE);
                 let fut = l1_sync(event_sender.clone(), l1_context.clone());
                 l1_handle = tokio::spawn(async move {
                     tokio::time::sleep(RESET_DELAY_ON_FAILURE).await;
@@ -270,13 +303,16 @@ where
             },
             l2_producer_result = &mut l2_handle => {
                 // L2 sync process failed; restart it.
-                match l2_producer_result.context("Join L2 sync process handle")? {
-                    Ok(()) => {
+                match l2_producer_result {
+                    Ok(Ok(())) => {
                         tracing::error!("L2 sync process terminated without an error.");
                     }
-                    Err(e) => {
+                    Ok(Err(e)) => {
                         tracing::warn!("L2 sync process terminated with: {e:?}");
                     }
+                    // Aborted by the watchdog.
+                    Err(e) if e.is_cancelled() => {}
+                    Err(e) => return Err(e).context("Join L2 sync process handle"),
                 }
 
                 let l2_head = tokio::task::block_in_place(|| {
@@ -290,12 +326,49 @@ where
                 let block_chain = BlockChain::with_capacity(1_000, latest_blocks);
                 let fut = l2_sync(event_sender.clone(), l2_context.clone(), l2_head, block_chain);
 
+                l2_progress.restart_after(restart_delay);
                 l2_handle = tokio::spawn(async move {
                     tokio::time::sleep(restart_delay).await;
                     fut.await
                 });
                 tracing::info!("L2 sync process restarted.");
             },
+            _ = watchdog.tick() => {
+                // The consumer is idle while there are no events for it. An event it is still
+                // storing is not counted, but the producers send further events well within
+                // the timeout.
+                if event_sender.capacity() == event_sender.max_capacity() {
+                    consumer_progress.report("Waiting for sync events");
+                }
+
+                // Storing an event cannot be interrupted, and aborting the consumer between
+                // events would lose the event it received, so a stalled consumer is only
+                // reported. It is reported again if it makes no progress for another timeout.
+                if let Some(stall) = watchdog.check(&consumer_progress) {
+                    log_stall("consumer", &stall, false, &mut db_conn, &state).await;
+                    consumer_progress.report(stall.last_activity);
+                }
+
+                // Producers waiting for the consumer to make room for their events are not
+                // stalled, so their progress is not checked until the consumer catches up. A
+                // stalled consumer is reported above instead.
+                if event_sender.capacity() == 0 {
+                    l1_progress.report("Waiting for the sync consumer");
+                    l2_progress.report("Waiting for the sync consumer");
+                } else {
+                    if let Some(stall) = watchdog.check(&l1_progress) {
+                        log_stall("L1", &stall, true, &mut db_conn, &state).await;
+                        l1_progress.restart_after(RESET_DELAY_ON_FAILURE);
+                        l1_handle.abort();
+                    }
+
+                    if let Some(stall) = watchdog.check(&l2_progress) {
+                        log_stall("L2", &stall, true, &mut db_conn, &state).await;
+                        l2_progress.restart_after(restart_delay);
+                        l2_handle.abort();
+                    }
+                }
+            },
             consumer_result = &mut consumer_handle => {
                 match consumer_result {
                     Ok(Ok(())) => {
@@ -353,12 +426,54 @@ where
     }
 }
 
+/// Logs the state of sync along with the last activity of the stalled stage, before it is
+/// restarted if `restart` is set.
+async fn log_stall(
+    stage: &'static str,
+    stall: &Stall,
+    restart: bool,
+    db_conn: &mut Connection,
+    state: &SyncState,
+) {
+    match restart {
+        true => metrics::increment_counter!("sync_stage_restarts_total", "stage" => stage),
+        false => metrics::increment_counter!("sync_stage_stalls_total", "stage" => stage),
+    }
+
+    let latest_block = tokio::task::block_in_place(|| {
+        let tx = db_conn.transaction()?;
+        tx.block_id(pathfinder_storage::BlockId::Latest)
+    })
+    .map_err(|error| tracing::debug!(%error, "Querying latest block"))
+    .ok()
+    .flatten()
+    .map(|(number, _)| number);
+
+    let highest_block = match &*state.status.read().await {
+        Syncing::Status(status) => Some(status.highest.number),
+        Syncing::False(_) => None,
+    };
+
+    tracing::warn!(
+        stage,
+        stalled_for=?stall.duration,
+        last_activity=%stall.last_activity,
+        ?latest_block,
+        ?highest_block,
+        restarting = restart,
+        "Sync stage made no progress"
+    );
+}
+
 struct ConsumerContext {
     pub storage: Storage,
     pub state: Arc<SyncState>,
     pub pending_data: WatchSender<PendingData>,
     pub verify_tree_hashes: bool,
     pub websocket_txs: Option<TopicBroadcasters>,
+    pub trusted_block: Option<TrustedBlock>,
+    pub halt_on_l1_state_root_mismatch: bool,
+    pub progress: Progress,
 }
 
 async fn consumer(mut events: Receiver<SyncEvent>, context: ConsumerContext) -> anyhow::Result<()> {
@@ -368,11 +483,17 @@ async fn consumer(mut events: Receiver<SyncEvent>, context: ConsumerContext) ->
         pending_data,
         verify_tree_hashes,
         mut websocket_txs,
+        trusted_block,
+        halt_on_l1_state_root_mismatch,
+        progress,
     } = context;
 
     let mut last_block_start = std::time::Instant::now();
     let mut block_time_avg = std::time::Duration::ZERO;
     const BLOCK_TIME_WEIGHT: f32 = 0.05;
+    // Ethereum gas prices are only stored with blocks at most this old, as they are unrelated to
+    // the blocks stored while catching up.
+    const MAX_L1_GAS_PRICES_BLOCK_AGE: u64 = 5 * 60;
 
     let mut db_conn = storage
         .connection()
@@ -392,12 +513,25 @@ async fn consumer(mut events: Receiver<SyncEvent>, context: ConsumerContext) ->
     })
     .context("Fetching latest block time")?;
 
+    let mut pending_broadcast = PendingBroadcast::default();
+    // The latest Ethereum gas prices, stored with each new block.
+    let mut l1_gas_prices = None;
+
     while let Some(event) = events.recv().await {
         use SyncEvent::*;
         match event {
             L1Update(update) => {
-                l1_update(&mut db_conn, &update).await?;
+                l1_update(&mut db_conn, &update, halt_on_l1_state_root_mismatch).await?;
                 tracing::info!("L1 sync updated to block {}", update.block_number);
+                progress.report(format!("Stored L1 update to block {}", update.block_number));
+            }
+            L1GasPrices(prices) => {
+                tracing::debug!(block=%prices.block_number, "Updated L1 gas prices");
+                l1_gas_prices = Some(prices);
+                progress.report(format!(
+                    "Updated gas prices to Ethereum block {}",
+                    prices.block_number
+                ));
             }
             Block((block, (tx_comm, ev_comm)), state_update, signature, timings) => {
                 if block.block_number < next_number {
@@ -413,6 +547,14 @@ async fn consumer(mut events: Receiver<SyncEvent>, context: ConsumerContext) ->
                     .iter()
                     .map(|x| x.1.storage.len())
                     .sum();
+                // Transactions of blocks preceding the trusted block are not stored.
+                let store_transactions =
+                    !trusted_block.is_some_and(|trusted_block| trusted_block.covers(block_number));
+                let block_age = (time::OffsetDateTime::now_utc().unix_timestamp() as u64)
+                    .saturating_sub(block_timestamp.get());
+                let block_l1_gas_prices = l1_gas_prices
+                    .as_ref()
+                    .filter(|_| block_age <= MAX_L1_GAS_PRICES_BLOCK_AGE);
                 let update_t = std::time::Instant::now();
                 l2_update(
                     &mut db_conn,
@@ -422,6 +564,9 @@ async fn consumer(mut events: Receiver<SyncEvent>, context: ConsumerContext) ->
                     *state_update,
                     *signature,
                     verify_tree_hashes,
+                    store_transactions,
+                    halt_on_l1_state_root_mismatch,
+                    block_l1_gas_prices,
                     storage.clone(),
                     &mut websocket_txs,
                 )
@@ -466,6 +611,7 @@ async fn consumer(mut events: Receiver<SyncEvent>, context: ConsumerContext) ->
                 );
                 latest_timestamp = block_timestamp;
                 next_number += 1;
+                progress.report(format!("Stored block {block_number}"));
 
                 // Give a simple log under INFO level, and a more verbose log
                 // with timing information under DEBUG+ level.
@@ -492,11 +638,12 @@ async fn consumer(mut events: Receiver<SyncEvent>, context: ConsumerContext) ->
                 }
             }
             Reorg(reorg_tail) => {
-                l2_reorg(&mut db_conn, reorg_tail)
+                l2_reorg(&mut db_conn, reorg_tail, &mut websocket_txs)
                     .await
                     .with_context(|| format!("Reorg L2 state to {reorg_tail:?}"))?;
 
                 next_number = reorg_tail;
+                progress.report(format!("Reorged to block {reorg_tail}"));
 
                 let new_head = match reorg_tail {
                     BlockNumber::GENESIS => None,
@@ -521,6 +668,7 @@ async fn consumer(mut events: Receiver<SyncEvent>, context: ConsumerContext) ->
                 .with_context(|| format!("Insert Cairo contract definition with hash: {hash}"))?;
 
                 tracing::debug!(%hash, "Inserted new Cairo class");
+                progress.report(format!("Stored Cairo class {hash}"));
             }
             SierraClass {
                 sierra_definition,
@@ -546,6 +694,7 @@ async fn consumer(mut events: Receiver<SyncEvent>, context: ConsumerContext) ->
                 })?;
 
                 tracing::debug!(sierra=%sierra_hash, casm=%casm_hash, "Inserted new Sierra class");
+                progress.report(format!("Stored Sierra class {sierra_hash}"));
             }
             Pending(pending) => {
                 let (number, hash) = tokio::task::block_in_place(|| {
@@ -562,6 +711,10 @@ async fn consumer(mut events: Receiver<SyncEvent>, context: ConsumerContext) ->
                 .context("Fetching latest block hash")?;
 
                 if pending.0.parent_hash == hash {
+                    if let Some(sender) = &websocket_txs {
+                        pending_broadcast.send(sender, &pending.0, number + 1);
+                    }
+
                     let data = PendingData {
                         block: pending.0,
                         state_update: pending.1,
@@ -570,6 +723,7 @@ async fn consumer(mut events: Receiver<SyncEvent>, context: ConsumerContext) ->
                     pending_data.send_replace(data);
                     tracing::debug!("Updated pending data");
                 }
+                progress.report("Updated pending data");
             }
         }
     }
@@ -577,6 +731,48 @@ async fn consumer(mut events: Receiver<SyncEvent>, context: ConsumerContext) ->
     Ok(())
 }
 
+/// Tracks what was broadcast of the pending block, so that only its new transactions and
+/// events are broadcast as it grows.
+#[derive(Default)]
+struct PendingBroadcast {
+    parent_hash: BlockHash,
+    transactions: usize,
+    receipts: usize,
+}
+
+impl PendingBroadcast {
+    fn send(&mut self, sender: &TopicBroadcasters, pending: &PendingBlock, number: BlockNumber) {
+        if self.parent_hash != pending.parent_hash {
+            *self = Self {
+                parent_hash: pending.parent_hash,
+                ..Default::default()
+            };
+        }
+
+        let transactions = pending
+            .transactions
+            .get(self.transactions..)
+            .unwrap_or_default();
+        if !transactions.is_empty() && sender.pending_transactions.is_receiving() {
+            sender
+                .pending_transactions
+                .send_if_receiving(transactions.to_vec());
+        }
+        self.transactions = self.transactions.max(pending.transactions.len());
+
+        let receipts = pending
+            .transaction_receipts
+            .get(self.receipts..)
+            .unwrap_or_default();
+        if !receipts.is_empty() && sender.events.is_receiving() {
+            sender
+                .events
+                .send_if_receiving(BlockEvents::from_receipts(None, number, receipts));
+        }
+        self.receipts = self.receipts.max(pending.transaction_receipts.len());
+    }
+}
+
 async fn latest_n_blocks(
     connection: &mut Connection,
     n: usize,
@@ -687,6 +883,7 @@ async fn propagate_head(gossiper: &Gossiper, last_propagated: &mut Instant, head
 async fn l1_update(
     connection: &mut Connection,
     update: &EthereumStateUpdate,
+    halt_on_state_root_mismatch: bool,
 ) -> anyhow::Result<()> {
     tokio::task::block_in_place(move || {
         let transaction = connection
@@ -697,12 +894,18 @@ async fn l1_update(
             .upsert_l1_state(update)
             .context("Insert update")?;
 
-        let l2_hash = transaction
-            .block_hash(update.block_number.into())
-            .context("Fetching block hash")?;
+        let l2_header = transaction
+            .block_header(update.block_number.into())
+            .context("Fetching block header")?;
 
-        if let Some(l2_hash) = l2_hash {
+        if let Some(l2_header) = l2_header {
+            let l2_hash = l2_header.hash;
             if l2_hash == update.block_hash {
+                check_l1_state_root(
+                    update,
+                    l2_header.state_commitment,
+                    halt_on_state_root_mismatch,
+                )?;
                 transaction
                     .update_l1_l2_pointer(Some(update.block_number))
                     .context("Updating L1-L2 pointer")?;
@@ -719,6 +922,37 @@ async fn l1_update(
     })
 }
 
+/// Compares the state root the Starknet core contract recorded for a block with the local one,
+/// as a safety net against local corruption.
+///
+/// A mismatch is logged and counted by the `sync_l1_state_root_mismatch_total` metric, and only
+/// fails if `halt` is set.
+fn check_l1_state_root(
+    l1_state: &EthereumStateUpdate,
+    local: StateCommitment,
+    halt: bool,
+) -> anyhow::Result<()> {
+    if l1_state.state_root == local {
+        return Ok(());
+    }
+
+    metrics::increment_counter!("sync_l1_state_root_mismatch_total");
+    tracing::error!(
+        block_number=%l1_state.block_number,
+        L1=%l1_state.state_root,
+        local=%local,
+        "L1 state root mismatch, the local state may be corrupted"
+    );
+
+    anyhow::ensure!(
+        !halt,
+        "State root of block {} does not match the one on L1",
+        l1_state.block_number
+    );
+
+    Ok(())
+}
+
 /// Returns the new [StateCommitment] after the update.
 #[allow(clippy::too_many_arguments)]
 async fn l2_update(
@@ -729,6 +963,9 @@ async fn l2_update(
     state_update: StateUpdate,
     signature: BlockCommitmentSignature,
     verify_tree_hashes: bool,
+    store_transactions: bool,
+    halt_on_l1_state_root_mismatch: bool,
+    l1_gas_prices: Option<&L1GasPrices>,
     // we need this so that we can create extra read-only transactions for
     // parallel contract state updates
     storage: Storage,
@@ -815,6 +1052,24 @@ async fn l2_update(
             .insert_state_update_counts(header.number, &state_update.counts())
             .context("Inserting state update counts into database")?;
 
+        if let Some(l1_gas_prices) = l1_gas_prices {
+            transaction
+                .insert_l1_gas_prices(header.number, l1_gas_prices)
+                .context("Inserting L1 gas prices into database")?;
+        }
+
+        // Collected before the receipts are moved into storage.
+        let block_events = websocket_txs
+            .as_ref()
+            .filter(|txs| txs.events.is_receiving())
+            .map(|_| {
+                BlockEvents::from_receipts(
+                    Some(header.hash),
+                    header.number,
+                    &block.transaction_receipts,
+                )
+            });
+
         // Insert the transactions.
         anyhow::ensure!(
             block.transactions.len() == block.transaction_receipts.len(),
@@ -822,15 +1077,17 @@ async fn l2_update(
             block.transactions.len(),
             block.transaction_receipts.len()
         );
-        let transaction_data = block
-            .transactions
-            .into_iter()
-            .zip(block.transaction_receipts.into_iter().map(Some))
-            .collect::<Vec<_>>();
+        if store_transactions {
+            let transaction_data = block
+                .transactions
+                .into_iter()
+                .zip(block.transaction_receipts.into_iter().map(Some))
+                .collect::<Vec<_>>();
 
-        transaction
-            .insert_transaction_data(header.hash, header.number, &transaction_data)
-            .context("Insert transaction data into database")?;
+            transaction
+                .insert_transaction_data(header.hash, header.number, &transaction_data)
+                .context("Insert transaction data into database")?;
+        }
 
         // Insert state updates
         transaction
@@ -848,16 +1105,23 @@ async fn l2_update(
             .map(|head| head + 1)
             .unwrap_or(BlockNumber::GENESIS);
 
-        if expected_next == header.number {
-            if let Some(l1_state) = transaction
-                .l1_state_at_number(header.number)
-                .context("Query L1 state")?
-            {
-                if l1_state.block_hash == header.hash {
-                    transaction
-                        .update_l1_l2_pointer(Some(header.number))
-                        .context("Update L1-L2 head")?;
-                }
+        // L1 may be ahead of L2 while catching up, in which case the block is checked against
+        // L1 here instead of when the L1 update arrived.
+        if let Some(l1_state) = transaction
+            .l1_state_at_number(header.number)
+            .context("Query L1 state")?
+            .filter(|l1_state| l1_state.block_hash == header.hash)
+        {
+            check_l1_state_root(
+                &l1_state,
+                header.state_commitment,
+                halt_on_l1_state_root_mismatch,
+            )?;
+
+            if expected_next == header.number {
+                transaction
+                    .update_l1_l2_pointer(Some(header.number))
+                    .context("Update L1-L2 head")?;
             }
         }
 
@@ -874,23 +1138,35 @@ async fn l2_update(
             }
         }
 
+        if let (Some(sender), Some(block_events)) = (websocket_txs, block_events) {
+            sender.events.send_if_receiving(block_events);
+        }
+
         Ok(())
     })?;
 
     Ok(())
 }
 
-async fn l2_reorg(connection: &mut Connection, reorg_tail: BlockNumber) -> anyhow::Result<()> {
+async fn l2_reorg(
+    connection: &mut Connection,
+    reorg_tail: BlockNumber,
+    websocket_txs: &mut Option<TopicBroadcasters>,
+) -> anyhow::Result<()> {
     tokio::task::block_in_place(move || {
         let transaction = connection
             .transaction_with_behavior(TransactionBehavior::Immediate)
             .context("Create database transaction")?;
 
-        let mut head = transaction
+        let (mut head, head_hash) = transaction
             .block_id(pathfinder_storage::BlockId::Latest)
             .context("Querying latest block number")?
-            .context("Latest block number is none during reorg")?
-            .0;
+            .context("Latest block number is none during reorg")?;
+        // None if there is nothing to purge.
+        let reorg_tail_hash = transaction
+            .block_hash(reorg_tail.into())
+            .context("Querying reorg tail block hash")?;
+        let orphaned_head = head;
 
         transaction
             .increment_reorg_counter()
@@ -933,11 +1209,49 @@ async fn l2_reorg(connection: &mut Connection, reorg_tail: BlockNumber) -> anyho
             }
         }
 
-        transaction.commit().context("Commit database transaction")
+        let new_head = transaction
+            .block_id(pathfinder_storage::BlockId::Latest)
+            .context("Querying new head")?
+            .map(|(number, hash)| ReorgHead { number, hash });
+
+        let depth = (orphaned_head.get() + 1).saturating_sub(reorg_tail.get());
+        transaction
+            .insert_reorg(&pathfinder_storage::Reorg {
+                timestamp: time::OffsetDateTime::now_utc().unix_timestamp() as u64,
+                old_head: (orphaned_head, head_hash),
+                new_head: new_head.as_ref().map(|head| (head.number, head.hash)),
+                depth,
+            })
+            .context("Inserting reorg into history")?;
+
+        transaction
+            .commit()
+            .context("Commit database transaction")?;
+
+        metrics::increment_counter!("sync_reorgs_total");
+        metrics::histogram!("sync_reorg_depth", depth as f64);
+
+        if let (Some(sender), Some(reorg_tail_hash)) = (websocket_txs.as_ref(), reorg_tail_hash) {
+            // Not imported since it would clash with `SyncEvent::Reorg`.
+            let reorg = pathfinder_rpc::Reorg {
+                first_block_number: reorg_tail,
+                first_block_hash: reorg_tail_hash,
+                last_block_number: orphaned_head,
+                last_block_hash: head_hash,
+                new_head,
+            };
+            if let Err(e) = sender.send_reorg_if_receiving(reorg) {
+                tracing::error!(error=?e, "Failed to send reorg over websocket broadcaster.");
+                // Disable websocket entirely, as is done for new heads.
+                *websocket_txs = None;
+            }
+        }
+
+        Ok(())
     })
 }
 
-fn update_starknet_state(
+pub(crate) fn update_starknet_state(
     transaction: &Transaction<'_>,
     state_update: &StateUpdate,
     verify_hashes: bool,
@@ -1051,9 +1365,14 @@ fn update_starknet_state(
     }
     .with_verify_hashes(verify_hashes);
 
-    for (sierra, casm) in &state_update.declared_sierra_classes {
-        let leaf_hash = pathfinder_common::calculate_class_commitment_leaf_hash(*casm);
-
+    let casm_hashes = state_update
+        .declared_sierra_classes
+        .values()
+        .copied()
+        .collect::<Vec<_>>();
+    let leaf_hashes = pathfinder_common::calculate_class_commitment_leaf_hashes(&casm_hashes);
+    for ((sierra, casm), leaf_hash) in state_update.declared_sierra_classes.iter().zip(leaf_hashes)
+    {
         transaction
             .insert_class_commitment_leaf(block, &leaf_hash, casm)
             .context("Adding class commitment leaf")?;
@@ -1088,14 +1407,15 @@ fn update_starknet_state(
 #[cfg(test)]
 mod tests {
     use super::l2;
-    use crate::state::sync::{consumer, ConsumerContext, SyncEvent};
+    use crate::state::sync::{consumer, ConsumerContext, PendingBroadcast, SyncEvent};
+    use pathfinder_common::transaction::{L1HandlerTransaction, Transaction, TransactionVariant};
     use pathfinder_common::{
-        felt_bytes, BlockHash, BlockHeader, BlockNumber, ClassHash, EventCommitment, SierraHash,
-        StateCommitment, StateUpdate, TransactionCommitment,
+        felt_bytes, BlockHash, BlockHeader, BlockNumber, BlockTimestamp, ClassHash,
+        EventCommitment, GasPrice, SierraHash, StateCommitment, StateUpdate, TransactionCommitment,
     };
     use pathfinder_common::{macro_prelude::*, BlockCommitmentSignature};
     use pathfinder_crypto::Felt;
-    use pathfinder_rpc::SyncState;
+    use pathfinder_rpc::{ReorgHead, SyncState, TopicBroadcasters};
     use pathfinder_storage::Storage;
     use starknet_gateway_types::reply::Block;
     use starknet_gateway_types::reply::{self, GasPrices};
@@ -1198,16 +1518,26 @@ mod tests {
         drop(event_tx);
 
         let (tx, _rx) = tokio::sync::watch::channel(Default::default());
+        let progress = super::Progress::default();
         let context = ConsumerContext {
             storage,
             state: Arc::new(SyncState::default()),
             pending_data: tx,
             verify_tree_hashes: false,
             websocket_txs: None,
+            trusted_block: None,
+            halt_on_l1_state_root_mismatch: false,
+            progress: progress.clone(),
         };
 
         consumer(event_rx, context).await.unwrap();
 
+        // The watchdog sees the consumer's progress.
+        assert_eq!(
+            progress.last().1,
+            format!("Stored block {}", num_blocks - 1)
+        );
+
         let tx = connection.transaction().unwrap();
         for i in 0..num_blocks {
             // TODO: Ideally we would test data consistency as well, but that will be easier once we use
@@ -1249,6 +1579,9 @@ mod tests {
             pending_data: tx,
             verify_tree_hashes: false,
             websocket_txs: None,
+            trusted_block: None,
+            halt_on_l1_state_root_mismatch: false,
+            progress: Default::default(),
         };
 
         consumer(event_rx, context).await.unwrap();
@@ -1266,6 +1599,222 @@ mod tests {
             .block_exists(BlockNumber::new_or_panic(2).into())
             .unwrap();
         assert!(!block_2_exists);
+
+        let history = tx.reorg_history(10).unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].old_head.0, BlockNumber::new_or_panic(2));
+        assert_eq!(
+            history[0].new_head.map(|(number, _)| number),
+            Some(BlockNumber::new_or_panic(1))
+        );
+        assert_eq!(history[0].depth, 1);
+    }
+
+    #[rstest::rstest]
+    #[case::alarm_only(false)]
+    #[case::halt(true)]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn l1_state_root_mismatch(#[case] halt: bool, #[values(true, false)] l1_first: bool) {
+        let storage = Storage::in_memory().unwrap();
+        let mut connection = storage.connection().unwrap();
+
+        let (event_tx, event_rx) = tokio::sync::mpsc::channel(100);
+
+        let blocks = generate_block_data();
+        let l1_update = SyncEvent::L1Update(pathfinder_ethereum::EthereumStateUpdate {
+            state_root: state_commitment!("0x1234"),
+            block_number: blocks[1].0 .0.block_number,
+            block_hash: blocks[1].0 .0.block_hash,
+        });
+        // L1 is checked against L2 when either of them catches up with the other.
+        if l1_first {
+            event_tx.send(l1_update).await.unwrap();
+            for (a, b, c, d) in blocks {
+                event_tx.send(SyncEvent::Block(a, b, c, d)).await.unwrap();
+            }
+        } else {
+            for (a, b, c, d) in blocks {
+                event_tx.send(SyncEvent::Block(a, b, c, d)).await.unwrap();
+            }
+            event_tx.send(l1_update).await.unwrap();
+        }
+        // Close the event channel which allows the consumer task to exit.
+        drop(event_tx);
+
+        let (tx, _rx) = tokio::sync::watch::channel(Default::default());
+        let context = ConsumerContext {
+            storage,
+            state: Arc::new(SyncState::default()),
+            pending_data: tx,
+            verify_tree_hashes: false,
+            websocket_txs: None,
+            trusted_block: None,
+            halt_on_l1_state_root_mismatch: halt,
+            progress: Default::default(),
+        };
+
+        let result = consumer(event_rx, context).await;
+        assert_eq!(result.is_err(), halt);
+
+        // Halting before storing the mismatching block.
+        let tx = connection.transaction().unwrap();
+        let block_1_exists = tx
+            .block_exists(BlockNumber::new_or_panic(1).into())
+            .unwrap();
+        assert_eq!(block_1_exists, !(halt && l1_first));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn l1_gas_prices_are_stored_with_blocks() {
+        let storage = Storage::in_memory().unwrap();
+        let mut connection = storage.connection().unwrap();
+
+        let (event_tx, event_rx) = tokio::sync::mpsc::channel(100);
+
+        // Only recent blocks are stored with gas prices, so genesis keeps its old timestamp.
+        let now = time::OffsetDateTime::now_utc().unix_timestamp() as u64;
+        let mut blocks = generate_block_data();
+        for block in &mut blocks[1..] {
+            block.0 .0.timestamp = BlockTimestamp::new_or_panic(now);
+        }
+        let prices = pathfinder_ethereum::L1GasPrices {
+            block_number: 100,
+            base_fee: GasPrice(10),
+            blob_base_fee: Some(GasPrice(1)),
+        };
+
+        event_tx.send(SyncEvent::L1GasPrices(prices)).await.unwrap();
+        for (a, b, c, d) in blocks {
+            event_tx.send(SyncEvent::Block(a, b, c, d)).await.unwrap();
+        }
+        // Close the event channel which allows the consumer task to exit.
+        drop(event_tx);
+
+        let (tx, _rx) = tokio::sync::watch::channel(Default::default());
+        let context = ConsumerContext {
+            storage,
+            state: Arc::new(SyncState::default()),
+            pending_data: tx,
+            verify_tree_hashes: false,
+            websocket_txs: None,
+            trusted_block: None,
+            halt_on_l1_state_root_mismatch: false,
+            progress: Default::default(),
+        };
+
+        consumer(event_rx, context).await.unwrap();
+
+        let tx = connection.transaction().unwrap();
+        let genesis = tx.l1_gas_prices(BlockNumber::GENESIS.into()).unwrap();
+        assert_eq!(genesis, None);
+        let block_1 = tx
+            .l1_gas_prices(BlockNumber::new_or_panic(1).into())
+            .unwrap();
+        assert_eq!(block_1, Some(prices));
+        let latest = tx
+            .l1_gas_prices(pathfinder_storage::BlockId::Latest)
+            .unwrap();
+        assert_eq!(latest, Some(prices));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn reorg_is_broadcast() {
+        let storage = Storage::in_memory().unwrap();
+
+        let (event_tx, event_rx) = tokio::sync::mpsc::channel(100);
+
+        // Blocks 0 to 2, of which only block 2 is orphaned.
+        let blocks = generate_block_data();
+        let block1_hash = blocks[1].0 .0.block_hash;
+        let block2_hash = blocks[2].0 .0.block_hash;
+        for (a, b, c, d) in blocks {
+            event_tx.send(SyncEvent::Block(a, b, c, d)).await.unwrap();
+        }
+        event_tx
+            .send(SyncEvent::Reorg(BlockNumber::new_or_panic(2)))
+            .await
+            .unwrap();
+        // Close the event channel which allows the consumer task to exit.
+        drop(event_tx);
+
+        let websocket_txs = TopicBroadcasters::default();
+        let mut receiver = websocket_txs.new_head.subscribe();
+
+        let (tx, _rx) = tokio::sync::watch::channel(Default::default());
+        let context = ConsumerContext {
+            storage,
+            state: Arc::new(SyncState::default()),
+            pending_data: tx,
+            verify_tree_hashes: false,
+            websocket_txs: Some(websocket_txs),
+            trusted_block: None,
+            halt_on_l1_state_root_mismatch: false,
+            progress: Default::default(),
+        };
+
+        consumer(event_rx, context).await.unwrap();
+
+        // Skip the new heads preceding the reorg.
+        let reorg = loop {
+            let item = receiver.recv().await.unwrap();
+            if item["type"] == "reorg" {
+                break item;
+            }
+        };
+
+        let expected = pathfinder_rpc::Reorg {
+            first_block_number: BlockNumber::new_or_panic(2),
+            first_block_hash: block2_hash,
+            last_block_number: BlockNumber::new_or_panic(2),
+            last_block_hash: block2_hash,
+            new_head: Some(ReorgHead {
+                number: BlockNumber::new_or_panic(1),
+                hash: block1_hash,
+            }),
+        };
+        assert_eq!(*reorg, serde_json::to_value(expected).unwrap());
+    }
+
+    #[test]
+    fn only_new_pending_transactions_are_broadcast() {
+        let websocket_txs = TopicBroadcasters::default();
+        let mut receiver = websocket_txs.pending_transactions.subscribe();
+
+        let transaction = |hash| Transaction {
+            hash,
+            variant: TransactionVariant::L1Handler(L1HandlerTransaction::default()),
+        };
+        let mut pending = reply::PendingBlock {
+            parent_hash: block_hash!("0x1"),
+            transactions: vec![transaction(transaction_hash!("0x10"))],
+            ..Default::default()
+        };
+
+        let mut broadcast = PendingBroadcast::default();
+        let number = BlockNumber::new_or_panic(2);
+        broadcast.send(&websocket_txs, &pending, number);
+        // Unchanged pending data is not broadcast again.
+        broadcast.send(&websocket_txs, &pending, number);
+        pending
+            .transactions
+            .push(transaction(transaction_hash!("0x11")));
+        broadcast.send(&websocket_txs, &pending, number);
+        // A pending block on top of a new parent starts over.
+        pending.parent_hash = block_hash!("0x2");
+        pending.transactions = vec![transaction(transaction_hash!("0x20"))];
+        broadcast.send(&websocket_txs, &pending, number + 1);
+
+        let hashes = std::iter::from_fn(|| receiver.try_recv().ok())
+            .map(|transactions| transactions.iter().map(|x| x.hash).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            hashes,
+            vec![
+                vec![transaction_hash!("0x10")],
+                vec![transaction_hash!("0x11")],
+                vec![transaction_hash!("0x20")],
+            ]
+        );
     }
 
     #[tokio::test(flavor = "multi_thread")]
@@ -1306,6 +1855,9 @@ mod tests {
             pending_data: tx,
             verify_tree_hashes: false,
             websocket_txs: None,
+            trusted_block: None,
+            halt_on_l1_state_root_mismatch: false,
+            progress: Default::default(),
         };
 
         consumer(event_rx, context).await.unwrap();
@@ -1350,6 +1902,9 @@ mod tests {
             pending_data: tx,
             verify_tree_hashes: false,
             websocket_txs: None,
+            trusted_block: None,
+            halt_on_l1_state_root_mismatch: false,
+            progress: Default::default(),
         };
 
         consumer(event_rx, context).await.unwrap();
@@ -1386,6 +1941,9 @@ mod tests {
             pending_data: tx,
             verify_tree_hashes: false,
             websocket_txs: None,
+            trusted_block: None,
+            halt_on_l1_state_root_mismatch: false,
+            progress: Default::default(),
         };
 
         consumer(event_rx, context).await.unwrap();
@@ -1425,6 +1983,9 @@ mod tests {
             pending_data: tx,
             verify_tree_hashes: false,
             websocket_txs: None,
+            trusted_block: None,
+            halt_on_l1_state_root_mismatch: false,
+            progress: Default::default(),
         };
 
         consumer(event_rx, context).await.unwrap();
@@ -1461,6 +2022,9 @@ mod tests {
             pending_data: tx,
             verify_tree_hashes: false,
             websocket_txs: None,
+            trusted_block: None,
+            halt_on_l1_state_root_mismatch: false,
+            progress: Default::default(),
         };
 
         consumer(event_rx, context).await.unwrap();
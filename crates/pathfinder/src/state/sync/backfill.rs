@@ -0,0 +1,243 @@
+//! Background backfill of the blocks preceding a [TrustedBlock].
+//!
+//! Blocks preceding a trusted block are synced without storing their transactions, so only
+//! their headers, state updates and signatures are stored. [run] downloads their transactions, receipts and events from the trusted block towards genesis,
+//! verifying each block against its stored hash. It is rate limited so that it does not compete
+//! with syncing the head of the chain.
+use std::num::NonZeroU32;
+use std::time::Duration;
+
+use anyhow::Context;
+use pathfinder_common::{BlockHeader, BlockNumber, Chain, ChainId};
+use pathfinder_storage::Storage;
+use starknet_gateway_client::GatewayApi;
+
+use super::l2::{self, BlockValidationMode, TrustedBlock};
+
+/// How long to wait for the head sync to store a header which is not yet available.
+const HEADER_POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+const METRIC_BLOCKS: &str = "sync_backfill_blocks_total";
+
+#[derive(Debug, Clone, Copy)]
+pub struct Config {
+    /// The maximum number of blocks backfilled per second.
+    pub blocks_per_second: NonZeroU32,
+}
+
+/// The next block to backfill.
+enum Next {
+    /// The block's header is stored, but its transactions are not.
+    Missing(Box<BlockHeader>),
+    /// The block's header has not been stored by the head sync yet.
+    NotSynced(BlockNumber),
+    /// All blocks down to genesis have been backfilled.
+    Done,
+}
+
+/// Backfills the blocks preceding `trusted_block`, starting with the latest one.
+///
+/// Returns once genesis has been reached, or if a block does not match its stored hash.
+pub async fn run<GatewayClient>(
+    storage: Storage,
+    sequencer: GatewayClient,
+    chain: Chain,
+    chain_id: ChainId,
+    trusted_block: TrustedBlock,
+    config: Config,
+) -> anyhow::Result<()>
+where
+    GatewayClient: GatewayApi + Clone + Send + 'static,
+{
+    let Some(mut cursor) = trusted_block.number.parent() else {
+        return Ok(());
+    };
+
+    let period = Duration::from_secs(1) / config.blocks_per_second.get();
+    let mut rate_limit = tokio::time::interval(period);
+    rate_limit.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    tracing::info!(trusted_block=%trusted_block.number, "Backfilling blocks preceding the trusted block");
+
+    loop {
+        let header = match next_missing(storage.clone(), cursor).await? {
+            Next::Missing(header) => header,
+            Next::NotSynced(number) => {
+                cursor = number;
+                tokio::time::sleep(HEADER_POLL_INTERVAL).await;
+                continue;
+            }
+            Next::Done => break,
+        };
+
+        rate_limit.tick().await;
+
+        // The block is fully verified since its hash is known to be part of the chain leading
+        // up to the trusted block.
+        let (block, (transaction_commitment, event_commitment), _) = l2::fetch_block(
+            header.number,
+            chain,
+            chain_id,
+            &sequencer,
+            BlockValidationMode::Strict,
+            None,
+        )
+        .await
+        .with_context(|| format!("Downloading block {}", header.number))?
+        .with_context(|| format!("Block {} not found on gateway", header.number))?;
+        anyhow::ensure!(
+            block.block_hash == header.hash,
+            "Block {} hash mismatch, actual {:x}, stored {:x}",
+            header.number,
+            block.block_hash.0,
+            header.hash.0,
+        );
+        anyhow::ensure!(
+            block.transactions.len() == block.transaction_receipts.len(),
+            "Transactions and receipts mismatch. There were {} transactions and {} receipts.",
+            block.transactions.len(),
+            block.transaction_receipts.len()
+        );
+
+        let transaction_data = block
+            .transactions
+            .into_iter()
+            .zip(block.transaction_receipts.into_iter().map(Some))
+            .collect::<Vec<_>>();
+
+        let storage = storage.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut connection = storage
+                .connection()
+                .context("Creating database connection")?;
+            let transaction = connection
+                .transaction()
+                .context("Creating database transaction")?;
+            transaction
+                .insert_transaction_data(header.hash, header.number, &transaction_data)
+                .context("Inserting transaction data")?;
+            transaction
+                .update_block_commitments(header.number, transaction_commitment, event_commitment)
+                .context("Updating block commitments")?;
+            transaction
+                .commit()
+                .context("Committing database transaction")
+        })
+        .await
+        .context("Joining blocking task")??;
+
+        metrics::increment_counter!(METRIC_BLOCKS);
+        tracing::debug!(block=%header.number, "Backfilled block");
+
+        match header.number.parent() {
+            Some(parent) => cursor = parent,
+            None => break,
+        }
+    }
+
+    tracing::info!("Backfilled all blocks preceding the trusted block");
+
+    Ok(())
+}
+
+/// Finds the latest block at or below `start` which has transactions that are not stored.
+async fn next_missing(storage: Storage, start: BlockNumber) -> anyhow::Result<Next> {
+    tokio::task::spawn_blocking(move || {
+        let mut connection = storage
+            .connection()
+            .context("Creating database connection")?;
+        let transaction = connection
+            .transaction()
+            .context("Creating database transaction")?;
+
+        let mut number = start;
+        loop {
+            let Some(header) = transaction
+                .block_header(number.into())
+                .context("Querying block header")?
+            else {
+                return Ok(Next::NotSynced(number));
+            };
+
+            // Empty blocks have nothing to backfill.
+            if header.transaction_count > 0
+                && transaction
+                    .transaction_count(number.into())
+                    .context("Counting stored transactions")?
+                    == 0
+            {
+                return Ok(Next::Missing(Box::new(header)));
+            }
+
+            match number.parent() {
+                Some(parent) => number = parent,
+                None => return Ok(Next::Done),
+            }
+        }
+    })
+    .await
+    .context("Joining blocking task")?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_matches::assert_matches;
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::receipt::Receipt;
+    use pathfinder_common::transaction::{L1HandlerTransaction, Transaction, TransactionVariant};
+
+    fn transaction_data(name: &[u8]) -> Vec<(Transaction, Option<Receipt>)> {
+        let transaction = Transaction {
+            hash: transaction_hash_bytes!(name),
+            variant: TransactionVariant::L1Handler(L1HandlerTransaction::default()),
+        };
+        vec![(transaction, Some(Receipt::default()))]
+    }
+
+    #[tokio::test]
+    async fn next_missing_skips_empty_and_backfilled_blocks() {
+        let storage = Storage::in_memory().unwrap();
+        let mut connection = storage.connection().unwrap();
+
+        // Genesis is missing its transactions, block 1 has been backfilled and block 2 is empty.
+        let genesis = BlockHeader::builder()
+            .with_transaction_count(1)
+            .finalize_with_hash(block_hash_bytes!(b"genesis"));
+        let block1 = genesis
+            .child_builder()
+            .with_transaction_count(1)
+            .finalize_with_hash(block_hash_bytes!(b"block 1"));
+        let block2 = block1
+            .child_builder()
+            .finalize_with_hash(block_hash_bytes!(b"block 2"));
+
+        let tx = connection.transaction().unwrap();
+        for header in [&genesis, &block1, &block2] {
+            tx.insert_block_header(header).unwrap();
+        }
+        tx.insert_transaction_data(block1.hash, block1.number, &transaction_data(b"tx 1"))
+            .unwrap();
+        tx.commit().unwrap();
+
+        let not_synced = block2.number + 1;
+        assert_matches!(
+            next_missing(storage.clone(), not_synced).await.unwrap(),
+            Next::NotSynced(number) => assert_eq!(number, not_synced)
+        );
+        assert_matches!(
+            next_missing(storage.clone(), block2.number).await.unwrap(),
+            Next::Missing(header) => assert_eq!(*header, genesis)
+        );
+
+        let tx = connection.transaction().unwrap();
+        tx.insert_transaction_data(genesis.hash, genesis.number, &transaction_data(b"tx 0"))
+            .unwrap();
+        tx.commit().unwrap();
+
+        assert_matches!(
+            next_missing(storage.clone(), block2.number).await.unwrap(),
+            Next::Done
+        );
+    }
+}
@@ -1,11 +1,12 @@
 use std::{num::NonZeroU64, time::Duration};
 
 use pathfinder_common::Chain;
-use pathfinder_ethereum::{EthereumApi, EthereumStateUpdate};
+use pathfinder_ethereum::{EthereumApi, EthereumStateUpdate, L1GasPrices};
 use pathfinder_retry::Retry;
 use primitive_types::H160;
 use tokio::sync::mpsc;
 
+use crate::state::sync::watchdog::Progress;
 use crate::state::sync::SyncEvent;
 
 #[derive(Clone)]
@@ -15,10 +16,14 @@ pub struct L1SyncContext<EthereumClient> {
     /// The Starknet core contract address on Ethereum
     pub core_address: H160,
     pub poll_interval: Duration,
+    pub progress: Progress,
 }
 
 /// Syncs L1 state update logs. Emits [Ethereum state update](EthereumStateUpdate)
 /// which should be handled to update storage and respond to queries.
+///
+/// Also polls the gas prices of the latest Ethereum block, emitting [L1GasPrices]
+/// whenever they change.
 pub async fn sync<T>(
     tx_event: mpsc::Sender<SyncEvent>,
     context: L1SyncContext<T>,
@@ -31,9 +36,11 @@ where
         chain: _,
         core_address,
         poll_interval,
+        progress,
     } = context;
 
     let mut previous = EthereumStateUpdate::default();
+    let mut previous_gas_prices = L1GasPrices::default();
 
     loop {
         let state_update = Retry::exponential(
@@ -44,12 +51,34 @@ where
         .max_delay(poll_interval / 2)
         .when(|_| true)
         .await?;
+        progress.report(format!(
+            "Received Starknet state of block {}",
+            state_update.block_number
+        ));
 
         if previous != state_update {
             previous = state_update.clone();
             tx_event.send(SyncEvent::L1Update(state_update)).await?;
         }
 
+        let gas_prices = Retry::exponential(
+            || async { ethereum.get_gas_prices().await },
+            NonZeroU64::new(1).unwrap(),
+        )
+        .factor(NonZeroU64::new(2).unwrap())
+        .max_delay(poll_interval / 2)
+        .when(|_| true)
+        .await?;
+        progress.report(format!(
+            "Received gas prices of Ethereum block {}",
+            gas_prices.block_number
+        ));
+
+        if previous_gas_prices != gas_prices {
+            previous_gas_prices = gas_prices;
+            tx_event.send(SyncEvent::L1GasPrices(gas_prices)).await?;
+        }
+
         tokio::time::sleep(poll_interval).await;
     }
 }
@@ -1,5 +1,6 @@
 use crate::state::block_hash::{verify_block_hash, VerifyResult};
 use crate::state::sync::class::{download_class, DownloadedClass};
+use crate::state::sync::watchdog::Progress;
 use crate::state::sync::{pending, SyncEvent};
 use anyhow::{anyhow, Context};
 use pathfinder_common::state_update::ContractClassUpdate;
@@ -15,8 +16,10 @@ use starknet_gateway_types::{
 };
 use std::collections::HashMap;
 use std::collections::HashSet;
+use std::collections::VecDeque;
 use std::time::Duration;
 use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
 
 #[derive(Default, Debug, Clone, Copy)]
 pub struct Timings {
@@ -85,6 +88,54 @@ pub struct L2SyncContext<GatewayClient> {
     pub chain_id: ChainId,
     pub block_validation_mode: BlockValidationMode,
     pub storage: Storage,
+    /// The number of blocks downloaded ahead of the block currently being processed.
+    /// Zero disables prefetching.
+    pub block_prefetch: usize,
+    pub trusted_block: Option<TrustedBlock>,
+    pub progress: Progress,
+}
+
+/// A block whose hash is trusted, e.g. because it was configured by the operator.
+///
+/// The blocks preceding the trusted block are still downloaded in full and their state is still
+/// applied, since the gateway serves no state snapshot to start from. Only their block and
+/// transaction hashes are not verified, and their transactions are not stored. The trusted block
+/// itself must match the trusted hash.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct TrustedBlock {
+    pub number: BlockNumber,
+    pub hash: BlockHash,
+}
+
+impl TrustedBlock {
+    /// Returns true if `block` precedes the trusted block and is therefore trusted as is.
+    pub fn covers(&self, block: BlockNumber) -> bool {
+        block < self.number
+    }
+
+    /// Fails if `hash` belongs to the block at the trusted block's height but is not the trusted one.
+    fn verify(&self, number: BlockNumber, hash: BlockHash) -> anyhow::Result<()> {
+        if number == self.number {
+            anyhow::ensure!(
+                hash == self.hash,
+                "Trusted block hash mismatch, actual {:x}, expected {:x}",
+                hash.0,
+                self.hash.0,
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// Aborts the task when dropped, so that it stops along with the sync process which spawned it,
+/// e.g. when that is restarted by the watchdog.
+struct AbortOnDrop<T>(tokio::task::JoinHandle<T>);
+
+impl<T> Drop for AbortOnDrop<T> {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
 }
 
 pub async fn sync<GatewayClient>(
@@ -102,9 +153,21 @@ where
         chain_id,
         block_validation_mode,
         storage,
+        block_prefetch,
+        trusted_block,
+        progress,
     } = context;
 
     let mut pending_handle = None;
+    let mut prefetcher = BlockPrefetcher::new(
+        sequencer.clone(),
+        chain,
+        chain_id,
+        block_validation_mode,
+        storage.clone(),
+        block_prefetch,
+        trusted_block,
+    );
 
     'outer: loop {
         // Get the next block from L2.
@@ -127,17 +190,37 @@ where
 
         let t_block = std::time::Instant::now();
 
+        let (mut prefetched, prefetched_classes) = match prefetcher.take(next).await? {
+            Some(PrefetchedBlock {
+                block,
+                commitments,
+                state_update,
+                classes,
+            }) => (
+                Some(DownloadBlock::Block(block, commitments, state_update)),
+                classes,
+            ),
+            None => (None, HashMap::new()),
+        };
+
         let (block, commitments, state_update) = loop {
-            match download_block(
-                next,
-                chain,
-                chain_id,
-                head_meta.map(|h| h.1),
-                &sequencer,
-                block_validation_mode,
-            )
-            .await?
-            {
+            let download = match prefetched.take() {
+                Some(download) => download,
+                None => {
+                    download_block(
+                        next,
+                        chain,
+                        chain_id,
+                        head_meta.map(|h| h.1),
+                        &sequencer,
+                        block_validation_mode,
+                        trusted_block,
+                    )
+                    .await?
+                }
+            };
+
+            match download {
                 DownloadBlock::Block(block, commitments, state_update) => {
                     break (block, commitments, state_update)
                 }
@@ -151,12 +234,12 @@ where
                         tokio::time::sleep(PENDING_POLL_INTERVAL).await;
                     } else if pending_handle.is_none() {
                         tracing::info!("At head of chain, enabling polling of pending data");
-                        pending_handle = Some(tokio::spawn(pending::poll_pending(
+                        pending_handle = Some(AbortOnDrop(tokio::spawn(pending::poll_pending(
                             tx_event.clone(),
                             sequencer.clone(),
                             PENDING_POLL_INTERVAL,
                             storage.clone(),
-                        )));
+                        ))));
                     }
 
                     // Poll the head until it changes. This query is very quick and cheap to perform.
@@ -164,7 +247,9 @@ where
                     let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(1));
                     interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
                     loop {
-                        let (_, hash) = sequencer.head().await.context("Polling head of chain")?;
+                        let (number, hash) =
+                            sequencer.head().await.context("Polling head of chain")?;
+                        progress.report(format!("Polled head of chain at block {number}"));
                         if hash != head.unwrap_or_default().1 {
                             break;
                         }
@@ -180,6 +265,7 @@ where
                             &tx_event,
                             &sequencer,
                             block_validation_mode,
+                            trusted_block,
                             &blocks,
                         )
                         .await
@@ -199,6 +285,7 @@ where
             }
         };
         let t_block = t_block.elapsed();
+        progress.report(format!("Downloaded block {next}"));
 
         if let Some(some_head) = &head {
             if some_head.1 != block.parent_block_hash {
@@ -209,6 +296,7 @@ where
                     &tx_event,
                     &sequencer,
                     block_validation_mode,
+                    trusted_block,
                     &blocks,
                 )
                 .await
@@ -223,6 +311,10 @@ where
             }
         }
 
+        if let Some(trusted_block) = trusted_block {
+            trusted_block.verify(next, block.block_hash)?;
+        }
+
         // Download and emit newly declared classes.
         let t_declare = std::time::Instant::now();
         download_new_classes(
@@ -231,6 +323,7 @@ where
             &tx_event,
             &block.starknet_version,
             storage.clone(),
+            prefetched_classes,
         )
         .await
         .with_context(|| format!("Handling newly declared classes for block {next:?}"))?;
@@ -288,6 +381,7 @@ where
             ))
             .await
             .context("Event channel closed")?;
+        progress.report(format!("Emitted block {next}"));
     }
 }
 
@@ -304,13 +398,78 @@ where
 /// can show up in `replaced_classes`. This is caused by DECLARE v0 transactions
 /// that were _failing_ but the sequencer has still added the class to its list of
 /// known classes...
+///
+/// Classes present in `prefetched` are used as is instead of being downloaded again.
 pub async fn download_new_classes(
     state_update: &StateUpdate,
     sequencer: &impl GatewayApi,
     tx_event: &mpsc::Sender<SyncEvent>,
     version: &StarknetVersion,
     storage: Storage,
+    mut prefetched: HashMap<ClassHash, DownloadedClass>,
 ) -> Result<(), anyhow::Error> {
+    let require_downloading = missing_classes(state_update, storage).await?;
+
+    for class_hash in require_downloading {
+        let class = match prefetched.remove(&class_hash) {
+            Some(class) => class,
+            None => download_class(sequencer, class_hash, version.clone())
+                .await
+                .with_context(|| format!("Downloading class {}", class_hash.0))?,
+        };
+
+        match class {
+            DownloadedClass::Cairo { definition, hash } => tx_event
+                .send(SyncEvent::CairoClass { definition, hash })
+                .await
+                .with_context(|| {
+                    format!(
+                        "Sending Event::NewCairoContract for declared class {}",
+                        class_hash.0
+                    )
+                })?,
+            DownloadedClass::Sierra {
+                sierra_definition,
+                sierra_hash,
+                casm_definition,
+            } => {
+                // NOTE: we _have_ to use the same compiled_class_class hash as returned by the feeder gateway,
+                // since that's what has been added to the class commitment tree.
+                let Some(casm_hash) = state_update
+                    .declared_sierra_classes
+                    .iter()
+                    .find_map(|(sierra, casm)| (sierra.0 == class_hash.0).then_some(*casm))
+                else {
+                    // This can occur if the sierra was in here as a deploy contract, if the class was
+                    // declared in a previous block but not yet persisted by the database.
+                    continue;
+                };
+                tx_event
+                    .send(SyncEvent::SierraClass {
+                        sierra_definition,
+                        sierra_hash,
+                        casm_definition,
+                        casm_hash,
+                    })
+                    .await
+                    .with_context(|| {
+                        format!(
+                            "Sending Event::NewSierraContract for declared class {}",
+                            class_hash.0
+                        )
+                    })?
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the classes introduced by `state_update` which are not yet in storage.
+async fn missing_classes(
+    state_update: &StateUpdate,
+    storage: Storage,
+) -> anyhow::Result<HashSet<ClassHash>> {
     let deployed_classes = state_update
         .contract_updates
         .iter()
@@ -333,10 +492,10 @@ pub async fn download_new_classes(
         .collect::<Vec<_>>();
 
     if new_classes.is_empty() {
-        return Ok(());
+        return Ok(HashSet::new());
     }
 
-    let require_downloading = tokio::task::spawn_blocking(move || {
+    tokio::task::spawn_blocking(move || {
         let mut db_conn = storage
             .connection()
             .context("Creating database connection")?;
@@ -358,58 +517,182 @@ pub async fn download_new_classes(
     })
     .await
     .context("Joining database task")?
-    .context("Querying database for missing classes")?;
+    .context("Querying database for missing classes")
+}
 
-    for class_hash in require_downloading {
-        let class = download_class(sequencer, class_hash, version.clone())
-            .await
-            .with_context(|| format!("Downloading class {}", class_hash.0))?;
+/// A block downloaded ahead of time by [BlockPrefetcher], along with the classes
+/// it introduces which were missing from storage at the time.
+struct PrefetchedBlock {
+    block: Box<Block>,
+    commitments: (TransactionCommitment, EventCommitment),
+    state_update: Box<StateUpdate>,
+    classes: HashMap<ClassHash, DownloadedClass>,
+}
 
-        match class {
-            DownloadedClass::Cairo { definition, hash } => tx_event
-                .send(SyncEvent::CairoClass { definition, hash })
-                .await
-                .with_context(|| {
-                    format!(
-                        "Sending Event::NewCairoContract for declared class {}",
-                        class_hash.0
-                    )
-                })?,
-            DownloadedClass::Sierra {
-                sierra_definition,
-                sierra_hash,
-                casm_definition,
-            } => {
-                // NOTE: we _have_ to use the same compiled_class_class hash as returned by the feeder gateway,
-                // since that's what has been added to the class commitment tree.
-                let Some(casm_hash) = state_update
-                    .declared_sierra_classes
-                    .iter()
-                    .find_map(|(sierra, casm)| (sierra.0 == class_hash.0).then_some(*casm))
-                else {
-                    // This can occur if the sierra was in here as a deploy contract, if the class was
-                    // declared in a previous block but not yet persisted by the database.
-                    continue;
-                };
-                tx_event
-                    .send(SyncEvent::SierraClass {
-                        sierra_definition,
-                        sierra_hash,
-                        casm_definition,
-                        casm_hash,
-                    })
+/// Downloads the blocks following the one currently being processed concurrently.
+///
+/// Prefetched blocks are only checked for internal consistency. Linking them to the
+/// local chain is left to the sync loop, which falls back to reorg handling if a
+/// block's parent hash does not match.
+struct BlockPrefetcher<GatewayClient> {
+    sequencer: GatewayClient,
+    chain: Chain,
+    chain_id: ChainId,
+    mode: BlockValidationMode,
+    storage: Storage,
+    window: usize,
+    trusted_block: Option<TrustedBlock>,
+    /// The latest block reported by the gateway. Blocks past it are not prefetched.
+    gateway_head: Option<BlockNumber>,
+    tasks: VecDeque<(
+        BlockNumber,
+        JoinHandle<anyhow::Result<Option<PrefetchedBlock>>>,
+    )>,
+}
+
+impl<GatewayClient> BlockPrefetcher<GatewayClient>
+where
+    GatewayClient: GatewayApi + Clone + Send + 'static,
+{
+    fn new(
+        sequencer: GatewayClient,
+        chain: Chain,
+        chain_id: ChainId,
+        mode: BlockValidationMode,
+        storage: Storage,
+        window: usize,
+        trusted_block: Option<TrustedBlock>,
+    ) -> Self {
+        Self {
+            sequencer,
+            chain,
+            chain_id,
+            mode,
+            storage,
+            window,
+            trusted_block,
+            gateway_head: None,
+            tasks: VecDeque::with_capacity(window),
+        }
+    }
+
+    /// Returns block `next` if it has been prefetched, and schedules downloads for the
+    /// blocks following it.
+    ///
+    /// Returns `None` if the block has to be downloaded by the caller instead, e.g. because
+    /// the chain has been reorganized or the block is not yet available.
+    async fn take(&mut self, next: BlockNumber) -> anyhow::Result<Option<PrefetchedBlock>> {
+        if self.window == 0 {
+            return Ok(None);
+        }
+
+        let prefetched = match self.tasks.pop_front() {
+            Some((number, task)) if number == next => {
+                match task.await.context("Joining block prefetch task")? {
+                    Ok(Some(block)) => Some(block),
+                    // We've caught up with the gateway, so none of the later blocks are available either.
+                    Ok(None) => {
+                        self.clear();
+                        None
+                    }
+                    Err(error) => {
+                        tracing::debug!(block=%next, reason=?error, "Prefetching block failed");
+                        self.clear();
+                        None
+                    }
+                }
+            }
+            Some((_, task)) => {
+                task.abort();
+                self.clear();
+                None
+            }
+            None => None,
+        };
+
+        self.schedule(next + 1).await?;
+
+        Ok(prefetched)
+    }
+
+    /// Spawns downloads for the blocks in `[first, first + window)` that exist on the
+    /// gateway and aren't being downloaded already.
+    async fn schedule(&mut self, first: BlockNumber) -> anyhow::Result<()> {
+        let last = first + (self.window as u64 - 1);
+
+        let gateway_head = match self.gateway_head {
+            Some(head) if head >= last => head,
+            _ => {
+                let (head, _) = self
+                    .sequencer
+                    .head()
                     .await
-                    .with_context(|| {
-                        format!(
-                            "Sending Event::NewSierraContract for declared class {}",
-                            class_hash.0
-                        )
-                    })?
+                    .context("Polling head of chain")?;
+                self.gateway_head = Some(head);
+                head
             }
+        };
+        let last = last.min(gateway_head);
+
+        let mut number = self
+            .tasks
+            .back()
+            .map(|(number, _)| *number + 1)
+            .unwrap_or(first);
+
+        while number <= last {
+            let sequencer = self.sequencer.clone();
+            let storage = self.storage.clone();
+            let chain = self.chain;
+            let chain_id = self.chain_id;
+            let mode = self.mode;
+            let trusted_block = self.trusted_block;
+
+            let task = tokio::spawn(async move {
+                let Some((block, commitments, state_update)) =
+                    fetch_block(number, chain, chain_id, &sequencer, mode, trusted_block).await?
+                else {
+                    return Ok(None);
+                };
+
+                let mut classes = HashMap::new();
+                for class_hash in missing_classes(&state_update, storage).await? {
+                    let class =
+                        download_class(&sequencer, class_hash, block.starknet_version.clone())
+                            .await
+                            .with_context(|| format!("Downloading class {}", class_hash.0))?;
+                    classes.insert(class_hash, class);
+                }
+
+                Ok(Some(PrefetchedBlock {
+                    block,
+                    commitments,
+                    state_update,
+                    classes,
+                }))
+            });
+
+            self.tasks.push_back((number, task));
+            number += 1;
         }
+
+        Ok(())
     }
+}
 
-    Ok(())
+impl<GatewayClient> BlockPrefetcher<GatewayClient> {
+    /// Cancels all outstanding downloads.
+    fn clear(&mut self) {
+        for (_, task) in self.tasks.drain(..) {
+            task.abort();
+        }
+    }
+}
+
+impl<GatewayClient> Drop for BlockPrefetcher<GatewayClient> {
+    fn drop(&mut self) {
+        self.clear();
+    }
 }
 
 enum DownloadBlock {
@@ -438,119 +721,145 @@ async fn download_block(
     prev_block_hash: Option<BlockHash>,
     sequencer: &impl GatewayApi,
     mode: BlockValidationMode,
+    trusted_block: Option<TrustedBlock>,
 ) -> anyhow::Result<DownloadBlock> {
+    if let Some((block, commitments, state_update)) = fetch_block(
+        block_number,
+        chain,
+        chain_id,
+        sequencer,
+        mode,
+        trusted_block,
+    )
+    .await?
+    {
+        return Ok(DownloadBlock::Block(block, commitments, state_update));
+    }
+
+    // This would occur if we queried past the head of the chain. We now need to check that
+    // a reorg hasn't put us too far in the future. This does run into race conditions with
+    // the sequencer but this is the best we can do I think.
+    let (latest_block_number, latest_block_hash) = sequencer
+        .head()
+        .await
+        .context("Query sequencer for latest block")?;
+
+    if latest_block_number + 1 == block_number {
+        match prev_block_hash {
+            // We are definitely still at the head and it's just that a new block
+            // has not been published yet
+            Some(parent_block_hash) if parent_block_hash == latest_block_hash => {
+                Ok(DownloadBlock::AtHead)
+            }
+            // Our head is not valid anymore so there must have been a reorg only at this height
+            Some(_) => Ok(DownloadBlock::Reorg),
+            // There is something wrong with the sequencer, as we are attempting to get the genesis block
+            // Let's retry in a while
+            None => Ok(DownloadBlock::AtHead),
+        }
+    } else {
+        // The new head is at lower height than our head which means there must have been a reorg
+        Ok(DownloadBlock::Reorg)
+    }
+}
+
+/// Downloads a block and its state update, and verifies the block and transaction hashes
+/// unless the block precedes the `trusted_block`.
+///
+/// Returns `None` if the block does not exist (yet).
+pub(super) async fn fetch_block(
+    block_number: BlockNumber,
+    chain: Chain,
+    chain_id: ChainId,
+    sequencer: &impl GatewayApi,
+    mode: BlockValidationMode,
+    trusted_block: Option<TrustedBlock>,
+) -> anyhow::Result<
+    Option<(
+        Box<Block>,
+        (TransactionCommitment, EventCommitment),
+        Box<StateUpdate>,
+    )>,
+> {
     use starknet_gateway_types::error::KnownStarknetErrorCode::BlockNotFound;
 
-    let result = sequencer.state_update_with_block(block_number).await;
+    let (block, state_update) = match sequencer.state_update_with_block(block_number).await {
+        Ok(result) => result,
+        Err(SequencerError::StarknetError(err)) if err.code == BlockNotFound.into() => {
+            return Ok(None)
+        }
+        Err(other) => return Err(other).context("Download block from sequencer"),
+    };
 
-    let result = match result {
-        Ok((block, state_update)) => {
-            let block = Box::new(block);
-            let state_update = Box::new(state_update);
+    let block = Box::new(block);
+    let state_update = Box::new(state_update);
 
-            // Check if block hash is correct.
-            let verify_hash = tokio::task::spawn_blocking(move || -> anyhow::Result<_> {
-                let block_number = block.block_number;
-                // In p2p the state commitment which is required to calculate the block hash can be missing, and in such case it is marked as 0s.
-                #[cfg(feature = "p2p")]
-                if block.state_commitment == StateCommitment::ZERO {
-                    return Ok((block, VerifyResult::NotVerifiable));
-                }
+    // Blocks preceding the trusted block are trusted, and since their transactions are not stored
+    // their commitments are not computed either.
+    if trusted_block.is_some_and(|trusted_block| trusted_block.covers(block_number)) {
+        return Ok(Some((block, Default::default(), state_update)));
+    }
 
-                let verify_result = verify_block_hash(&block, chain, chain_id, block.block_hash)
-                    .with_context(move || format!("Verify block {block_number}"))?;
-                Ok((block, verify_result))
-            });
-            let (block, verify_result) = verify_hash.await.context("Verify block hash")??;
-            match (block.status, verify_result, mode) {
-                (
-                    Status::AcceptedOnL1 | Status::AcceptedOnL2,
-                    VerifyResult::Match(commitments),
-                    _,
-                ) => Ok(DownloadBlock::Block(block, commitments, state_update)),
-                (Status::AcceptedOnL1 | Status::AcceptedOnL2, VerifyResult::NotVerifiable, _) => {
-                    Ok(DownloadBlock::Block(
-                        block,
-                        Default::default(),
-                        state_update,
-                    ))
-                }
-                (
-                    Status::AcceptedOnL1 | Status::AcceptedOnL2,
-                    VerifyResult::Mismatch,
-                    BlockValidationMode::AllowMismatch,
-                ) => Ok(DownloadBlock::Block(
-                    block,
-                    Default::default(),
-                    state_update,
-                )),
-                (_, VerifyResult::Mismatch, BlockValidationMode::Strict) => {
-                    Err(anyhow!("Block hash mismatch"))
-                }
-                _ => Err(anyhow!(
-                    "Rejecting block as its status is {}, and only accepted blocks are allowed",
-                    block.status
-                )),
-            }
+    // Check if block hash is correct.
+    let verify_hash = tokio::task::spawn_blocking(move || -> anyhow::Result<_> {
+        let block_number = block.block_number;
+        // In p2p the state commitment which is required to calculate the block hash can be missing, and in such case it is marked as 0s.
+        #[cfg(feature = "p2p")]
+        if block.state_commitment == StateCommitment::ZERO {
+            return Ok((block, VerifyResult::NotVerifiable));
         }
-        Err(SequencerError::StarknetError(err)) if err.code == BlockNotFound.into() => {
-            // This would occur if we queried past the head of the chain. We now need to check that
-            // a reorg hasn't put us too far in the future. This does run into race conditions with
-            // the sequencer but this is the best we can do I think.
-            let (latest_block_number, latest_block_hash) = sequencer
-                .head()
-                .await
-                .context("Query sequencer for latest block")?;
-
-            if latest_block_number + 1 == block_number {
-                match prev_block_hash {
-                    // We are definitely still at the head and it's just that a new block
-                    // has not been published yet
-                    Some(parent_block_hash) if parent_block_hash == latest_block_hash => {
-                        Ok(DownloadBlock::AtHead)
-                    }
-                    // Our head is not valid anymore so there must have been a reorg only at this height
-                    Some(_) => Ok(DownloadBlock::Reorg),
-                    // There is something wrong with the sequencer, as we are attempting to get the genesis block
-                    // Let's retry in a while
-                    None => Ok(DownloadBlock::AtHead),
-                }
-            } else {
-                // The new head is at lower height than our head which means there must have been a reorg
-                Ok(DownloadBlock::Reorg)
-            }
+
+        let verify_result = verify_block_hash(&block, chain, chain_id, block.block_hash)
+            .with_context(move || format!("Verify block {block_number}"))?;
+        Ok((block, verify_result))
+    });
+    let (block, verify_result) = verify_hash.await.context("Verify block hash")??;
+    let commitments = match (block.status, verify_result, mode) {
+        (Status::AcceptedOnL1 | Status::AcceptedOnL2, VerifyResult::Match(commitments), _) => {
+            commitments
+        }
+        (Status::AcceptedOnL1 | Status::AcceptedOnL2, VerifyResult::NotVerifiable, _) => {
+            Default::default()
+        }
+        (
+            Status::AcceptedOnL1 | Status::AcceptedOnL2,
+            VerifyResult::Mismatch,
+            BlockValidationMode::AllowMismatch,
+        ) => Default::default(),
+        (_, VerifyResult::Mismatch, BlockValidationMode::Strict) => {
+            return Err(anyhow!("Block hash mismatch"))
+        }
+        _ => {
+            return Err(anyhow!(
+                "Rejecting block as its status is {}, and only accepted blocks are allowed",
+                block.status
+            ))
         }
-        Err(other) => Err(other).context("Download block from sequencer"),
     };
 
-    match result {
-        Ok(DownloadBlock::Block(block, commitments, state_update)) => {
-            use rayon::prelude::*;
-
-            let (send, recv) = tokio::sync::oneshot::channel();
-
-            rayon::spawn(move || {
-                let result = block
-                    .transactions
-                    .par_iter()
-                    .enumerate()
-                    .try_for_each(|(i, txn)| {
-                        if !txn.verify_hash(chain_id) {
-                            anyhow::bail!("Transaction hash mismatch: block {block_number} idx {i}")
-                        };
-                        Ok(())
-                    })
-                    .map(|_| block);
+    use rayon::prelude::*;
 
-                let _ = send.send(result);
-            });
+    let (send, recv) = tokio::sync::oneshot::channel();
+
+    rayon::spawn(move || {
+        let result = block
+            .transactions
+            .par_iter()
+            .enumerate()
+            .try_for_each(|(i, txn)| {
+                if !txn.verify_hash(chain_id) {
+                    anyhow::bail!("Transaction hash mismatch: block {block_number} idx {i}")
+                };
+                Ok(())
+            })
+            .map(|_| block);
 
-            let block = recv.await.expect("Panic on rayon thread")?;
+        let _ = send.send(result);
+    });
 
-            Ok(DownloadBlock::Block(block, commitments, state_update))
-        }
-        Ok(DownloadBlock::AtHead | DownloadBlock::Reorg) | Err(_) => result,
-    }
+    let block = recv.await.expect("Panic on rayon thread")?;
+
+    Ok(Some((block, commitments, state_update)))
 }
 
 async fn reorg(
@@ -560,6 +869,7 @@ async fn reorg(
     tx_event: &mpsc::Sender<SyncEvent>,
     sequencer: &impl GatewayApi,
     mode: BlockValidationMode,
+    trusted_block: Option<TrustedBlock>,
     blocks: &BlockChain,
 ) -> anyhow::Result<Option<(BlockNumber, BlockHash, StateCommitment)>> {
     // Go back in history until we find an L2 block that does still exist.
@@ -583,12 +893,20 @@ async fn reorg(
             Some(previous.0),
             sequencer,
             mode,
+            trusted_block,
         )
         .await
         .with_context(|| format!("Download block {previous_block_number} from sequencer"))?
         {
-            DownloadBlock::Block(block, _, _) if block.block_hash == previous.0 => {
-                break Some((previous_block_number, previous.0, previous.1));
+            DownloadBlock::Block(block, _, _) => {
+                // The reorg must not replace the trusted block.
+                if let Some(trusted_block) = trusted_block {
+                    trusted_block.verify(previous_block_number, block.block_hash)?;
+                }
+
+                if block.block_hash == previous.0 {
+                    break Some((previous_block_number, previous.0, previous.1));
+                }
             }
             _ => {}
         };
@@ -613,7 +931,7 @@ async fn reorg(
 mod tests {
 
     mod sync {
-        use crate::state::l2::{BlockChain, L2SyncContext};
+        use crate::state::l2::{BlockChain, L2SyncContext, TrustedBlock};
         use pathfinder_common::macro_prelude::*;
         use pathfinder_common::BlockCommitmentSignature;
         use pathfinder_common::StateUpdate;
@@ -806,6 +1124,9 @@ mod tests {
                 chain_id: ChainId::GOERLI_TESTNET,
                 block_validation_mode: MODE,
                 storage,
+                block_prefetch: 0,
+                trusted_block: None,
+                progress: Default::default(),
             };
 
             tokio::spawn(sync(
@@ -1177,6 +1498,9 @@ mod tests {
                     chain_id: ChainId::GOERLI_TESTNET,
                     block_validation_mode: MODE,
                     storage: Storage::in_memory().unwrap(),
+                    block_prefetch: 0,
+                    trusted_block: None,
+                    progress: Default::default(),
                 };
 
                 let _jh = tokio::spawn(sync(
@@ -1198,6 +1522,109 @@ mod tests {
                     assert_eq!(*state_update, *STATE_UPDATE1);
                 });
             }
+            #[tokio::test]
+            async fn prefetched() {
+                let (tx_event, mut rx_event) = tokio::sync::mpsc::channel(1);
+                let mut mock = MockGatewayApi::new();
+                let mut seq = mockall::Sequence::new();
+                let mut prefetch_seq = mockall::Sequence::new();
+                let mut signature_seq = mockall::Sequence::new();
+
+                // The gateway head is polled to limit the prefetch window.
+                mock.expect_block_header()
+                    .with(mockall::predicate::eq(BlockId::Latest))
+                    .returning(|_| Ok((BLOCK1_NUMBER, BLOCK1_HASH)));
+
+                // Download the genesis block while block #1 is being prefetched
+                expect_state_update_with_block(
+                    &mut mock,
+                    &mut seq,
+                    BLOCK0_NUMBER,
+                    Ok((BLOCK0.clone(), STATE_UPDATE0.clone())),
+                );
+                expect_class_by_hash(
+                    &mut mock,
+                    &mut seq,
+                    CONTRACT0_HASH,
+                    Ok(CONTRACT0_DEF.clone()),
+                );
+                expect_signature(
+                    &mut mock,
+                    &mut signature_seq,
+                    BLOCK0_NUMBER.into(),
+                    Ok(BLOCK0_SIGNATURE.clone()),
+                );
+                // Block #1 and its class are only downloaded once, by the prefetcher
+                expect_state_update_with_block(
+                    &mut mock,
+                    &mut prefetch_seq,
+                    BLOCK1_NUMBER,
+                    Ok((BLOCK1.clone(), STATE_UPDATE1.clone())),
+                );
+                expect_class_by_hash(
+                    &mut mock,
+                    &mut prefetch_seq,
+                    CONTRACT1_HASH,
+                    Ok(CONTRACT1_DEF.clone()),
+                );
+                expect_signature(
+                    &mut mock,
+                    &mut signature_seq,
+                    BLOCK1_NUMBER.into(),
+                    Ok(BLOCK1_SIGNATURE.clone()),
+                );
+                // Stay at head, no more blocks available
+                expect_state_update_with_block(
+                    &mut mock,
+                    &mut seq,
+                    BLOCK2_NUMBER,
+                    Err(block_not_found()),
+                );
+                expect_signature(
+                    &mut mock,
+                    &mut signature_seq,
+                    BLOCK2_NUMBER.into(),
+                    Err(block_not_found()),
+                );
+
+                // Let's run the UUT
+                let mock = std::sync::Arc::new(mock);
+                let context = L2SyncContext {
+                    sequencer: mock,
+                    chain: Chain::GoerliTestnet,
+                    chain_id: ChainId::GOERLI_TESTNET,
+                    block_validation_mode: MODE,
+                    storage: Storage::in_memory().unwrap(),
+                    block_prefetch: 1,
+                    trusted_block: None,
+                    progress: Default::default(),
+                };
+
+                let _jh = tokio::spawn(sync(
+                    tx_event,
+                    context,
+                    None,
+                    BlockChain::with_capacity(100, vec![]),
+                ));
+
+                assert_matches!(rx_event.recv().await.unwrap(),
+                    SyncEvent::CairoClass { hash, .. } => {
+                        assert_eq!(hash, CONTRACT0_HASH);
+                });
+                assert_matches!(rx_event.recv().await.unwrap(), SyncEvent::Block((block, _), state_update, _, _) => {
+                    assert_eq!(*block, *BLOCK0);
+                    assert_eq_sorted!(*state_update, *STATE_UPDATE0);
+                });
+                assert_matches!(rx_event.recv().await.unwrap(),
+                    SyncEvent::CairoClass { hash, .. } => {
+                    assert_eq!(hash, CONTRACT1_HASH);
+                });
+                assert_matches!(rx_event.recv().await.unwrap(), SyncEvent::Block((block, _), state_update, signature, _) => {
+                    assert_eq!(*block, *BLOCK1);
+                    assert_eq_sorted!(*state_update, *STATE_UPDATE1);
+                    assert_eq!(*signature, BLOCK1_COMMITMENT_SIGNATURE);
+                });
+            }
         }
 
         mod errors {
@@ -1234,6 +1661,50 @@ mod tests {
                     "Rejecting block as its status is REVERTED, and only accepted blocks are allowed"
                 );
             }
+
+            #[tokio::test]
+            async fn trusted_block_hash_mismatch() {
+                let (tx_event, _rx_event) = tokio::sync::mpsc::channel(1);
+                let mut mock = MockGatewayApi::new();
+                let mut seq = mockall::Sequence::new();
+                let mut signature_seq = mockall::Sequence::new();
+
+                expect_state_update_with_block(
+                    &mut mock,
+                    &mut seq,
+                    BLOCK0_NUMBER,
+                    Ok((BLOCK0.clone(), STATE_UPDATE0.clone())),
+                );
+                expect_signature(
+                    &mut mock,
+                    &mut signature_seq,
+                    BLOCK0_NUMBER.into(),
+                    Ok(BLOCK0_SIGNATURE.clone()),
+                );
+
+                let context = L2SyncContext {
+                    sequencer: std::sync::Arc::new(mock),
+                    chain: Chain::GoerliTestnet,
+                    chain_id: ChainId::GOERLI_TESTNET,
+                    block_validation_mode: MODE,
+                    storage: Storage::in_memory().unwrap(),
+                    block_prefetch: 0,
+                    trusted_block: Some(TrustedBlock {
+                        number: BLOCK0_NUMBER,
+                        hash: BLOCK1_HASH,
+                    }),
+                    progress: Default::default(),
+                };
+
+                let jh = tokio::spawn(sync(
+                    tx_event,
+                    context,
+                    None,
+                    BlockChain::with_capacity(100, vec![]),
+                ));
+                let error = jh.await.unwrap().unwrap_err();
+                assert!(error.to_string().starts_with("Trusted block hash mismatch"));
+            }
         }
 
         mod reorg {
@@ -2336,6 +2807,53 @@ mod tests {
                     .unwrap()
                     .unwrap_err();
             }
+            #[tokio::test]
+            // The reorg must not replace the trusted block.
+            //
+            // [block 0 (trusted)]-[block 1]
+            //
+            // Becomes:
+            //
+            // [block 0 v2]
+            //
+            async fn replacing_trusted_block() {
+                let (tx_event, _rx_event) = tokio::sync::mpsc::channel(1);
+                let mut mock = MockGatewayApi::new();
+                let mut seq = mockall::Sequence::new();
+
+                expect_state_update_with_block(
+                    &mut mock,
+                    &mut seq,
+                    BLOCK0_NUMBER,
+                    Ok((BLOCK0_V2.clone(), STATE_UPDATE0_V2.clone())),
+                );
+
+                let trusted_block = TrustedBlock {
+                    number: BLOCK0_NUMBER,
+                    hash: BLOCK0_HASH,
+                };
+                let blocks = BlockChain::with_capacity(
+                    100,
+                    vec![
+                        (BLOCK0_NUMBER, BLOCK0_HASH, GLOBAL_ROOT0),
+                        (BLOCK1_NUMBER, BLOCK1_HASH, GLOBAL_ROOT1),
+                    ],
+                );
+
+                let error = crate::state::l2::reorg(
+                    &(BLOCK1_NUMBER, BLOCK1_HASH, GLOBAL_ROOT1),
+                    Chain::GoerliTestnet,
+                    ChainId::GOERLI_TESTNET,
+                    &tx_event,
+                    &mock,
+                    MODE,
+                    Some(trusted_block),
+                    &blocks,
+                )
+                .await
+                .unwrap_err();
+                assert!(error.to_string().starts_with("Trusted block hash mismatch"));
+            }
         }
     }
 
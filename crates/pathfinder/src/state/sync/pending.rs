@@ -57,6 +57,7 @@ pub async fn poll_pending<S: GatewayApi + Clone + Send + 'static>(
             &tx_event,
             &block.starknet_version,
             storage.clone(),
+            Default::default(),
         )
         .await
         {
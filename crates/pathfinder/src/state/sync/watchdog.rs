@@ -0,0 +1,162 @@
+//! Detects sync stages which have stopped making progress, e.g. because a request to the gateway
+//! or to Ethereum never completes, so that they can be restarted without restarting the node.
+//!
+//! Each stage reports its progress along with a description of its last activity, such as the
+//! last response it received, which is logged once the stage is found to be stalled.
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use tokio::time::{Instant, Interval, MissedTickBehavior};
+
+/// The progress of a sync stage, shared between the stage and the [Watchdog].
+#[derive(Clone, Debug)]
+pub struct Progress(Arc<Mutex<Activity>>);
+
+#[derive(Clone, Debug)]
+struct Activity {
+    /// May be in the future while a restarted stage is waiting to start.
+    at: Instant,
+    description: String,
+}
+
+impl Default for Progress {
+    fn default() -> Self {
+        Self(Arc::new(Mutex::new(Activity {
+            at: Instant::now(),
+            description: "Started".to_owned(),
+        })))
+    }
+}
+
+impl Progress {
+    fn locked(&self) -> MutexGuard<'_, Activity> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Records that the stage made progress, described by `description`.
+    pub fn report(&self, description: impl Into<String>) {
+        *self.locked() = Activity {
+            at: Instant::now(),
+            description: description.into(),
+        };
+    }
+
+    /// Records that the stage is being restarted after `delay`, which is not counted as a stall.
+    pub fn restart_after(&self, delay: Duration) {
+        *self.locked() = Activity {
+            at: Instant::now() + delay,
+            description: "Restarting".to_owned(),
+        };
+    }
+
+    /// Returns the time since the stage last made progress, along with the description of that
+    /// progress.
+    pub(super) fn last(&self) -> (Duration, String) {
+        let activity = self.locked();
+        (
+            Instant::now().saturating_duration_since(activity.at),
+            activity.description.clone(),
+        )
+    }
+}
+
+/// A stage which has made no progress for longer than the timeout of the [Watchdog].
+#[derive(Debug, PartialEq, Eq)]
+pub struct Stall {
+    pub duration: Duration,
+    /// The description of the last progress of the stage.
+    pub last_activity: String,
+}
+
+/// Periodically checks the [Progress] of the sync stages.
+pub struct Watchdog {
+    /// [None] if the watchdog is disabled.
+    timeout: Option<Duration>,
+    interval: Option<Interval>,
+}
+
+impl Watchdog {
+    /// The shortest interval between checks.
+    const MIN_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+    /// Creates a watchdog which treats stages without progress for longer than `timeout` as
+    /// stalled, or one which never checks them if `timeout` is [None].
+    pub fn new(timeout: Option<Duration>) -> Self {
+        let interval = timeout.map(|timeout| {
+            let mut interval = tokio::time::interval((timeout / 4).max(Self::MIN_CHECK_INTERVAL));
+            interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            interval
+        });
+
+        Self { timeout, interval }
+    }
+
+    /// Completes when the stages are due to be checked, and never if the watchdog is disabled.
+    pub async fn tick(&mut self) {
+        match &mut self.interval {
+            Some(interval) => {
+                interval.tick().await;
+            }
+            None => std::future::pending().await,
+        }
+    }
+
+    /// Returns the stall of the stage if it has made no progress for longer than the timeout.
+    pub fn check(&self, progress: &Progress) -> Option<Stall> {
+        let timeout = self.timeout?;
+        let (duration, last_activity) = progress.last();
+
+        (duration > timeout).then_some(Stall {
+            duration,
+            last_activity,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn detects_stall() {
+        let timeout = Duration::from_secs(60);
+        let mut watchdog = Watchdog::new(Some(timeout));
+        let progress = Progress::default();
+
+        progress.report("Downloaded block 1");
+        tokio::time::advance(timeout / 2).await;
+        watchdog.tick().await;
+        assert_eq!(watchdog.check(&progress), None);
+
+        tokio::time::advance(timeout).await;
+        let stall = watchdog.check(&progress).unwrap();
+        assert!(stall.duration > timeout);
+        assert_eq!(stall.last_activity, "Downloaded block 1");
+
+        progress.report("Downloaded block 2");
+        assert_eq!(watchdog.check(&progress), None);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn restart_delay_is_not_a_stall() {
+        let timeout = Duration::from_secs(60);
+        let watchdog = Watchdog::new(Some(timeout));
+        let progress = Progress::default();
+
+        progress.restart_after(timeout * 2);
+        tokio::time::advance(timeout * 2).await;
+        assert_eq!(watchdog.check(&progress), None);
+
+        tokio::time::advance(timeout * 2).await;
+        assert!(watchdog.check(&progress).is_some());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn disabled() {
+        let watchdog = Watchdog::new(None);
+        let progress = Progress::default();
+
+        tokio::time::advance(Duration::from_secs(24 * 60 * 60)).await;
+        assert_eq!(watchdog.check(&progress), None);
+    }
+}
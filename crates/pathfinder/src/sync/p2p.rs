@@ -1,6 +1,12 @@
 #![allow(dead_code, unused_variables)]
+mod classes;
+mod cross_validation;
+mod error;
+mod gateway;
 mod headers;
+mod pipeline;
 mod receipts;
+mod snapshot;
 mod state_updates;
 mod transactions;
 
@@ -10,18 +16,21 @@ use std::sync::Arc;
 use anyhow::Context;
 use futures::StreamExt;
 use futures::TryStreamExt;
-use p2p::client::{conv::TryFromDto, peer_agnostic::Client as P2PClient};
+use p2p::client::{conv::TryFromDto, peer_agnostic::Client as P2PClient, peer_scores::Penalty};
 use p2p_proto::{
-    common::{BlockNumberOrHash, Direction, Iteration},
-    receipt::{ReceiptsRequest, ReceiptsResponse},
+    class::{ClassesRequest, ClassesResponse},
+    common::{Address, BlockNumberOrHash, Direction, Hash, Iteration},
+    snapshot::{
+        ContractStorageRange, SnapshotProof, SnapshotRange, SnapshotRequest, SnapshotResponse,
+    },
     transaction::{TransactionsRequest, TransactionsResponse},
 };
-use pathfinder_common::receipt::Receipt;
 use pathfinder_common::state_update::StateUpdateCounts;
 use pathfinder_common::{transaction::Transaction, BlockHeader};
-use pathfinder_common::{BlockHash, BlockNumber};
+use pathfinder_common::{BlockHash, BlockNumber, ContractAddress, SierraHash, StorageAddress};
+use pathfinder_crypto::Felt;
 use pathfinder_ethereum::EthereumStateUpdate;
-use pathfinder_storage::Storage;
+use pathfinder_storage::{Storage, SyncStage};
 use primitive_types::H160;
 use smallvec::SmallVec;
 use tokio::task::spawn_blocking;
@@ -30,9 +39,23 @@ use crate::state::block_hash::{
     calculate_transaction_commitment, TransactionCommitmentFinalHashType,
 };
 
-use state_updates::ContractDiffSyncError;
+pub use error::SyncError;
+pub use gateway::Gateway;
+pub use pipeline::{Source, Sources};
+
+/// The number of blocks whose receipts and events are requested at once.
+const RECEIPTS_CHUNK_SIZE: u64 = 100;
+
+/// How long to wait before retrying after a [transient](SyncError::Transient) failure, or invalid
+/// data served by the gateway.
+const RETRY_DELAY: std::time::Duration = std::time::Duration::from_secs(10);
 
 /// Provides P2P sync capability for blocks secured by L1.
+///
+/// Sync runs as a pipeline of [stages](pipeline::STAGES), each of which downloads its data from
+/// the [Source] configured for it. Stages configured to use the gateway fall back to P2P if no
+/// gateway is available, and stages configured to use P2P fall back to the gateway if no peer
+/// supports them.
 #[derive(Clone)]
 pub struct Sync {
     storage: Storage,
@@ -40,6 +63,10 @@ pub struct Sync {
     // TODO: merge these two inside the client.
     eth_client: pathfinder_ethereum::EthereumClient,
     eth_address: H160,
+    snap_sync: bool,
+    gateway: Option<Gateway>,
+    sources: Sources,
+    cross_validate: bool,
 }
 
 impl Sync {
@@ -53,17 +80,70 @@ impl Sync {
             p2p,
             eth_client: ethereum.0,
             eth_address: ethereum.1,
+            snap_sync: false,
+            gateway: None,
+            sources: Sources::default(),
+            cross_validate: false,
         }
     }
 
+    /// Enables snap-sync: if no state has been synced yet, the state at the L1 anchor is
+    /// downloaded directly instead of replaying all state diffs from genesis.
+    pub fn with_snap_sync(mut self, snap_sync: bool) -> Self {
+        self.snap_sync = snap_sync;
+        self
+    }
+
+    /// Allows stages to download their data from the gateway.
+    pub fn with_gateway(mut self, gateway: Gateway) -> Self {
+        self.gateway = Some(gateway);
+        self
+    }
+
+    /// Sets the preferred [Source] of each stage.
+    pub fn with_sources(mut self, sources: Sources) -> Self {
+        self.sources = sources;
+        self
+    }
+
+    /// Enables cross-validation: the data each stage syncs from peers is compared with the
+    /// gateway's, and any divergence is logged. Requires a [gateway](Self::with_gateway).
+    pub fn with_cross_validation(mut self, cross_validate: bool) -> Self {
+        self.cross_validate = cross_validate;
+        self
+    }
+
     /// Syncs using p2p until the latest Ethereum checkpoint.
-    pub async fn run(&self) -> anyhow::Result<()> {
+    ///
+    /// Sync is retried until it succeeds or fails with a [fatal](SyncError::Fatal) error, see
+    /// [recover](error::recover).
+    pub async fn run(&self) -> Result<(), SyncError> {
+        if self.cross_validate && self.gateway.is_none() {
+            tracing::warn!("No gateway configured, cross-validation is disabled");
+        }
+
+        let p2p = &self.p2p;
+        error::retry(
+            move || self.sync_to_checkpoint(),
+            move |peer| p2p.penalize(peer, Penalty::InvalidData),
+        )
+        .await
+    }
+
+    /// Recovers from a sync failure, see [error::recover].
+    async fn recover(&self, error: SyncError) -> Result<(), SyncError> {
+        let p2p = &self.p2p;
+        error::recover(error, move |peer| p2p.penalize(peer, Penalty::InvalidData)).await
+    }
+
+    async fn sync_to_checkpoint(&self) -> Result<(), SyncError> {
         use pathfinder_ethereum::EthereumApi;
         let checkpoint = self
             .eth_client
             .get_starknet_state(&self.eth_address)
             .await
-            .context("Fetching latest L1 checkpoint")?;
+            .context("Fetching latest L1 checkpoint")
+            .map_err(SyncError::Transient)?;
 
         let local_state = LocalState::from_db(self.storage.clone(), checkpoint.clone())
             .await
@@ -86,22 +166,122 @@ impl Sync {
 
         let head = anchor.block_number;
 
-        // Sync missing headers in reverse chronological order, from the new anchor to genesis.
-        self.sync_headers(anchor).await.context("Syncing headers")?;
+        for stage in pipeline::STAGES {
+            if stage == SyncStage::StateUpdates
+                && self.snap_sync
+                && state_updates::next_missing(self.storage.clone(), head)
+                    .await
+                    .context("Finding next missing state update")?
+                    == Some(BlockNumber::GENESIS)
+            {
+                // The classes stage assumes that classes were synced together with any state
+                // update which predates its checkpoint, so it must complete before the state is
+                // persisted.
+                let source = self.source(SyncStage::Classes).await;
+                self.sync_classes(source, head)
+                    .await
+                    .map_err(|e| e.context(format!("Syncing classes from {source}")))?;
 
-        // Sync missing transactions in chronological order for all synced headers.
-        self.sync_transactions()
-            .await
-            .context("Syncing transactions")?;
+                self.snap_sync(&anchor)
+                    .await
+                    .context("Snap-syncing state")?;
+            }
 
-        // Sync the rest of the data in chronological order.
-        self.sync_state_updates(head)
-            .await
-            .context("Syncing state updates")?;
+            let previous = sync_checkpoint(self.storage.clone(), stage)
+                .await
+                .with_context(|| format!("Querying {} checkpoint", stage.as_str()))?;
+
+            let source = self.source(stage).await;
+            match stage {
+                // Sync missing headers in reverse chronological order, from the new anchor to
+                // genesis.
+                SyncStage::Headers => self.sync_headers(source, anchor.clone()).await,
+                // The remaining stages sync in chronological order for all blocks completed by
+                // the previous stage.
+                SyncStage::Transactions => self.sync_transactions(source).await,
+                SyncStage::Receipts => self.sync_receipts(source).await,
+                SyncStage::StateUpdates => self.sync_state_updates(source, head).await,
+                SyncStage::Classes => self.sync_classes(source, head).await,
+            }
+            .map_err(|e| e.context(format!("Syncing {} from {source}", stage.as_str())))?;
+
+            if let Some(block) = sync_checkpoint(self.storage.clone(), stage)
+                .await
+                .with_context(|| format!("Querying {} checkpoint", stage.as_str()))?
+            {
+                pipeline::report_progress(stage, source, block);
+
+                if let (true, Source::P2P, Some(gateway)) =
+                    (self.cross_validate, source, &self.gateway)
+                {
+                    let first = previous.map_or(BlockNumber::GENESIS, |x| x + 1);
+                    if first <= block {
+                        cross_validation::validate(
+                            gateway,
+                            self.storage.clone(),
+                            stage,
+                            first,
+                            block,
+                        )
+                        .await
+                        .map_err(|e| e.context(format!("Cross-validating {}", stage.as_str())))?;
+                    }
+                }
+            }
+        }
 
         Ok(())
     }
 
+    /// Selects the [Source] of `stage`, see [pipeline::select_source].
+    async fn source(&self, stage: SyncStage) -> Source {
+        let configured = self.sources.get(stage);
+        let has_gateway = self.gateway.is_some();
+        // Peers only matter if the stage could fall back to the gateway.
+        let has_peers = match (configured, has_gateway) {
+            (Source::P2P, true) => self.has_peers(stage).await,
+            _ => true,
+        };
+        pipeline::select_source(stage, configured, has_gateway, has_peers)
+    }
+
+    /// Whether any peer is known to serve the data of `stage`.
+    async fn has_peers(&self, stage: SyncStage) -> bool {
+        let p2p = &self.p2p;
+        match stage {
+            SyncStage::Headers => !p2p
+                .get_update_peers_with_header_sync_capability()
+                .await
+                .is_empty(),
+            SyncStage::Transactions => !p2p
+                .get_update_peers_with_transaction_sync_capability()
+                .await
+                .is_empty(),
+            // Receipts are synced together with their events.
+            SyncStage::Receipts => {
+                !p2p.get_update_peers_with_receipt_sync_capability()
+                    .await
+                    .is_empty()
+                    && !p2p
+                        .get_update_peers_with_event_sync_capability()
+                        .await
+                        .is_empty()
+            }
+            SyncStage::StateUpdates => !p2p
+                .get_update_peers_with_state_diff_sync_capability()
+                .await
+                .is_empty(),
+            SyncStage::Classes => !p2p
+                .get_update_peers_with_class_sync_capability()
+                .await
+                .is_empty(),
+        }
+    }
+
+    fn gateway(&self) -> anyhow::Result<&Gateway> {
+        self.gateway.as_ref().context("No gateway configured")
+    }
+
     /// Syncs all headers in reverse chronological order, from the anchor point
     /// back to genesis. Fills in any gaps left by previous header syncs.
     ///
@@ -109,7 +289,18 @@ impl Sync {
     /// guarantee that all sync'd headers are secured by L1.
     ///
     /// No guarantees are made about any headers newer than the anchor.
-    async fn sync_headers(&self, anchor: EthereumStateUpdate) -> anyhow::Result<()> {
+    async fn sync_headers(
+        &self,
+        source: Source,
+        anchor: EthereumStateUpdate,
+    ) -> Result<(), SyncError> {
+        let checkpoint = sync_checkpoint(self.storage.clone(), SyncStage::Headers)
+            .await
+            .context("Querying headers checkpoint")?;
+        if checkpoint.is_some_and(|checkpoint| checkpoint >= anchor.block_number) {
+            return Ok(());
+        }
+
         while let Some(gap) =
             headers::next_gap(self.storage.clone(), anchor.block_number, anchor.block_hash)
                 .await
@@ -119,6 +310,13 @@ impl Sync {
 
             tracing::info!("Syncing headers");
 
+            if source == Source::Gateway {
+                self.gateway()?
+                    .sync_headers(self.storage.clone(), gap)
+                    .await?;
+                continue;
+            }
+
             // TODO: consider .inspect_ok(tracing::trace!) for each stage.
             let result = self
                 .p2p
@@ -145,24 +343,24 @@ impl Sync {
                 Ok(()) => {
                     tracing::info!("Syncing headers complete");
                 }
-                Err(error) => {
-                    if let Some(peer_data) = error.peer_id_and_data() {
-                        // TODO: punish peer.
-                        tracing::debug!(
-                            peer=%peer_data.peer, block=%peer_data.data.header.number, %error,
-                            "Error while streaming headers"
-                        );
-                    } else {
-                        tracing::debug!(%error, "Error while streaming headers");
-                    }
-                }
+                // The gap is fetched again, unless the error is fatal.
+                Err(error) => self.recover(error.into()).await?,
             }
         }
 
+        // All headers from genesis up to the anchor are now present.
+        update_sync_checkpoint(
+            self.storage.clone(),
+            SyncStage::Headers,
+            anchor.block_number,
+        )
+        .await
+        .context("Updating headers checkpoint")?;
+
         Ok(())
     }
 
-    async fn sync_transactions(&self) -> anyhow::Result<()> {
+    async fn sync_transactions(&self, source: Source) -> Result<(), SyncError> {
         let (first_block, last_block) = spawn_blocking({
             let storage = self.storage.clone();
             move || -> anyhow::Result<(Option<BlockNumber>, Option<BlockNumber>)> {
@@ -170,13 +368,22 @@ impl Sync {
                     .connection()
                     .context("Creating database connection")?;
                 let db = db.transaction().context("Creating database transaction")?;
-                let first_block = db
-                    .first_block_without_transactions()
-                    .context("Querying first block without transactions")?;
                 let last_block = db
                     .block_id(pathfinder_storage::BlockId::Latest)
                     .context("Querying latest block without transactions")?
                     .map(|(block_number, _)| block_number);
+                let first_block = match db
+                    .sync_checkpoint(SyncStage::Transactions)
+                    .context("Querying transactions checkpoint")?
+                {
+                    Some(checkpoint) => last_block
+                        .filter(|last_block| checkpoint < *last_block)
+                        .map(|_| checkpoint + 1),
+                    // The database predates sync checkpoints.
+                    None => db
+                        .first_block_without_transactions()
+                        .context("Querying first block without transactions")?,
+                };
                 Ok((first_block, last_block))
             }
         })
@@ -188,6 +395,13 @@ impl Sync {
         };
         let last_block = last_block.context("Last block not found but first block found")?;
 
+        if source == Source::Gateway {
+            return self
+                .gateway()?
+                .sync_transactions(self.storage.clone(), first_block, last_block)
+                .await;
+        }
+
         let mut curr_block = headers::query(self.storage.clone(), first_block)
             .await?
             .ok_or_else(|| anyhow::anyhow!("First block not found"))?;
@@ -234,6 +448,7 @@ impl Sync {
                                             "Invalid transactions for block {}, trying next peer",
                                             curr_block.number
                                         );
+                                        self.p2p.penalize(peer, Penalty::InvalidData).await;
                                         continue 'next_peer;
                                     }
                                     transactions::persist(
@@ -243,6 +458,7 @@ impl Sync {
                                     )
                                     .await
                                     .context("Inserting transactions")?;
+                                    self.p2p.reward(peer).await;
                                     if curr_block.number == last_block {
                                         return Ok(());
                                     }
@@ -257,6 +473,7 @@ impl Sync {
                                 }
                                 Err(error) => {
                                     tracing::debug!(%peer, %error, "Transaction stream returned unexpected DTO");
+                                    self.p2p.penalize(peer, Penalty::InvalidData).await;
                                     continue 'next_peer;
                                 }
                             }
@@ -267,11 +484,13 @@ impl Sync {
                                     "Invalid transactions for block {}, trying next peer",
                                     curr_block.number
                                 );
+                                self.p2p.penalize(peer, Penalty::InvalidData).await;
                                 continue 'next_peer;
                             }
                             transactions::persist(self.storage.clone(), curr_block, transactions)
                                 .await
                                 .context("Inserting transactions")?;
+                            self.p2p.reward(peer).await;
                             return Ok(());
                         }
                         TransactionsResponse::Fin => {
@@ -284,7 +503,20 @@ impl Sync {
         }
     }
 
-    async fn sync_receipts(&self) -> anyhow::Result<()> {
+    /// Syncs receipts and events in chronological order for all synced transactions.
+    ///
+    /// Blocks are processed in chunks of [RECEIPTS_CHUNK_SIZE]. Receipts must belong to the
+    /// block's stored transactions, and events must match the block's event commitment.
+    ///
+    /// Block headers do not commit to the contents of receipts, so receipts received from peers
+    /// are only accepted if they match the gateway's. Without a gateway, receipts are not synced
+    /// from peers at all.
+    async fn sync_receipts(&self, source: Source) -> Result<(), SyncError> {
+        if source == Source::P2P && self.gateway.is_none() {
+            tracing::warn!("No gateway configured to verify receipts against, skipping receipts");
+            return Ok(());
+        }
+
         let (first_block, last_block) = spawn_blocking({
             let storage = self.storage.clone();
             move || -> anyhow::Result<(Option<BlockNumber>, Option<BlockNumber>)> {
@@ -292,113 +524,261 @@ impl Sync {
                     .connection()
                     .context("Creating database connection")?;
                 let db = db.transaction().context("Creating database transaction")?;
-                let first_block = db
-                    .first_block_without_receipts()
-                    .context("Querying first block without receipts")?;
-                let last_block = db
-                    .block_id(pathfinder_storage::BlockId::Latest)
-                    .context("Querying latest block without receipts")?
-                    .map(|(block_number, _)| block_number);
+                // Receipts can only be stored for blocks whose transactions are present.
+                let last_block = match db
+                    .sync_checkpoint(SyncStage::Transactions)
+                    .context("Querying transactions checkpoint")?
+                {
+                    Some(checkpoint) => Some(checkpoint),
+                    None => db
+                        .block_id(pathfinder_storage::BlockId::Latest)
+                        .context("Querying latest block")?
+                        .map(|(block_number, _)| block_number),
+                };
+                let first_block = match db
+                    .sync_checkpoint(SyncStage::Receipts)
+                    .context("Querying receipts checkpoint")?
+                {
+                    Some(checkpoint) => last_block
+                        .filter(|last_block| checkpoint < *last_block)
+                        .map(|_| checkpoint + 1),
+                    // The database predates sync checkpoints.
+                    None => db
+                        .first_block_without_receipts()
+                        .context("Querying first block without receipts")?,
+                };
                 Ok((first_block, last_block))
             }
         })
         .await
         .context("Joining blocking task")??;
 
-        let Some(first_block) = first_block else {
+        let Some(mut start) = first_block else {
             return Ok(());
         };
         let last_block = last_block.context("Last block not found but first block found")?;
 
-        let mut curr_block = headers::query(self.storage.clone(), first_block)
-            .await?
-            .ok_or_else(|| anyhow::anyhow!("First block not found"))?;
+        while start <= last_block {
+            let stop = std::cmp::min(start + (RECEIPTS_CHUNK_SIZE - 1), last_block);
+            let blocks = receipts::query(self.storage.clone(), start, stop)
+                .await
+                .context("Querying blocks")?;
+
+            let receipts = match source {
+                Source::P2P => {
+                    let expected = self.gateway()?.fetch_receipts(&blocks).await?;
+                    let receipts = receipts::fetch_receipts(&self.p2p, &blocks, &expected).await;
+                    receipts::fetch_events(&self.p2p, &blocks, receipts)
+                        .await
+                        .context("Verifying events")?
+                }
+                Source::Gateway => self.gateway()?.fetch_receipts(&blocks).await?,
+            };
+
+            receipts::persist(
+                self.storage.clone(),
+                blocks
+                    .into_iter()
+                    .map(|block| block.header)
+                    .zip(receipts)
+                    .collect(),
+            )
+            .await
+            .context("Inserting receipts")?;
+            pipeline::report_progress(SyncStage::Receipts, source, stop);
+
+            start = stop + 1;
+        }
+
+        Ok(())
+    }
+
+    /// Downloads the state at the anchor from ranges of the contracts, storage and classes tries,
+    /// and persists it as the anchor's state update.
+    ///
+    /// Every range is verified against the anchor's state root before it is accepted. Nothing is
+    /// persisted until the whole state has been downloaded.
+    async fn snap_sync(&self, anchor: &EthereumStateUpdate) -> anyhow::Result<()> {
+        tracing::info!(block=%anchor.block_number, "Snap-syncing state");
 
+        let state_root = anchor.state_root;
+        let mut state = snapshot::State::default();
+
+        let mut start = ContractAddress::ZERO;
+        loop {
+            let range = self
+                .fetch_snapshot_range(
+                    anchor.block_number,
+                    SnapshotRange::Contracts(Address(start.0)),
+                    move |leaves, proof| {
+                        snapshot::verify_contracts(state_root, start, leaves, proof)
+                    },
+                )
+                .await?;
+            let next = range
+                .leaves
+                .last()
+                .map(|contract| ContractAddress(snapshot::next_key(contract.address.0)));
+            state.contracts.extend(range.leaves);
+            match next {
+                Some(next) if range.has_more => start = next,
+                _ => break,
+            }
+        }
+        tracing::info!(contracts=%state.contracts.len(), "Snap-syncing contracts complete");
+
+        for contract in state
+            .contracts
+            .iter()
+            .filter(|x| x.storage_root.0 != Felt::ZERO)
+        {
+            let storage_root = contract.storage_root;
+            let mut start = StorageAddress(Felt::ZERO);
+            loop {
+                let range = self
+                    .fetch_snapshot_range(
+                        anchor.block_number,
+                        SnapshotRange::Storage(ContractStorageRange {
+                            contract: Address(contract.address.0),
+                            start: start.0,
+                        }),
+                        move |leaves, proof| {
+                            snapshot::verify_storage(state_root, storage_root, start, leaves, proof)
+                        },
+                    )
+                    .await?;
+                let next = range
+                    .leaves
+                    .last()
+                    .map(|(key, _)| StorageAddress(snapshot::next_key(key.0)));
+                state
+                    .storage
+                    .entry(contract.address)
+                    .or_default()
+                    .extend(range.leaves);
+                match next {
+                    Some(next) if range.has_more => start = next,
+                    _ => break,
+                }
+            }
+            tracing::trace!(contract=%contract.address, "Contract storage synced");
+        }
+        tracing::info!("Snap-syncing storage complete");
+
+        let mut start = SierraHash(Felt::ZERO);
+        loop {
+            let range = self
+                .fetch_snapshot_range(
+                    anchor.block_number,
+                    SnapshotRange::Classes(Hash(start.0)),
+                    move |leaves, proof| snapshot::verify_classes(state_root, start, leaves, proof),
+                )
+                .await?;
+            let next = range
+                .leaves
+                .last()
+                .map(|(class, _)| SierraHash(snapshot::next_key(class.0)));
+            state.classes.extend(range.leaves);
+            match next {
+                Some(next) if range.has_more => start = next,
+                _ => break,
+            }
+        }
+        tracing::info!(classes=%state.classes.len(), "Snap-syncing classes complete");
+
+        snapshot::persist(self.storage.clone(), anchor.clone(), state)
+            .await
+            .context("Persisting state")?;
+        tracing::info!("Snap-syncing state complete");
+
+        Ok(())
+    }
+
+    /// Fetches a range of trie leaves at `block`, retrying with other peers until one of them
+    /// provides a range which passes `verify`.
+    async fn fetch_snapshot_range<T, F>(
+        &self,
+        block: BlockNumber,
+        range: SnapshotRange,
+        verify: F,
+    ) -> anyhow::Result<snapshot::VerifiedRange<T>>
+    where
+        T: Send + 'static,
+        F: Fn(Vec<SnapshotResponse>, SnapshotProof) -> anyhow::Result<snapshot::VerifiedRange<T>>
+            + Clone
+            + Send
+            + 'static,
+    {
         // Loop which refreshes peer set once we exhaust it.
         loop {
             let peers = self
                 .p2p
-                .get_update_peers_with_transaction_sync_capability()
+                .get_update_peers_with_snapshot_sync_capability()
                 .await;
 
             // Attempt each peer.
             'next_peer: for peer in peers {
-                let request = ReceiptsRequest {
-                    iteration: Iteration {
-                        start: BlockNumberOrHash::Number(curr_block.number.get()),
-                        direction: Direction::Forward,
-                        limit: last_block.get() - curr_block.number.get() + 1,
-                        step: 1.into(),
-                    },
+                let request = SnapshotRequest {
+                    block_number: block.get(),
+                    range,
+                    limit: snapshot::RANGE_LIMIT,
                 };
 
-                let mut responses = match self.p2p.send_receipts_sync_request(peer, request).await {
+                let mut responses = match self.p2p.send_snapshot_sync_request(peer, request).await {
                     Ok(x) => x,
                     Err(error) => {
                         // Failed to establish connection, try next peer.
-                        tracing::debug!(%peer, reason=%error, "Receipts request failed");
+                        tracing::debug!(%peer, reason=%error, "Snapshot request failed");
                         continue 'next_peer;
                     }
                 };
 
-                let mut receipts = Vec::new();
-                while let Some(receipt) = responses.next().await {
-                    match receipt {
-                        ReceiptsResponse::Receipt(receipt) => {
-                            match Receipt::try_from_dto(receipt) {
-                                Ok(receipt) if receipts.len() < curr_block.transaction_count => {
-                                    receipts.push(receipt)
-                                }
-                                Ok(receipt) => {
-                                    receipts::persist(
-                                        self.storage.clone(),
-                                        curr_block.clone(),
-                                        receipts.clone(),
-                                    )
-                                    .await
-                                    .context("Inserting receipts")?;
-                                    if curr_block.number == last_block {
-                                        return Ok(());
-                                    }
-                                    curr_block =
-                                        headers::query(self.storage.clone(), curr_block.number + 1)
-                                            .await?
-                                            .ok_or_else(|| {
-                                                anyhow::anyhow!("Next block not found")
-                                            })?;
-                                    receipts.clear();
-                                    receipts.push(receipt);
-                                }
-                                Err(error) => {
-                                    tracing::debug!(%peer, %error, "Receipt stream returned unexpected DTO");
-                                    continue 'next_peer;
-                                }
-                            }
-                        }
-                        ReceiptsResponse::Fin if curr_block.number == last_block => {
-                            if receipts.len() != curr_block.transaction_count {
-                                tracing::debug!(
-                                    "Invalid receipts for block {}, trying next peer",
-                                    curr_block.number
-                                );
-                                continue 'next_peer;
-                            }
-                            receipts::persist(self.storage.clone(), curr_block, receipts)
-                                .await
-                                .context("Inserting receipts")?;
-                            return Ok(());
+                let mut leaves = Vec::new();
+                let mut proof = None;
+                while let Some(response) = responses.next().await {
+                    match response {
+                        SnapshotResponse::Proof(x) if proof.is_none() => proof = Some(x),
+                        SnapshotResponse::Fin => break,
+                        _ if proof.is_some() => {
+                            tracing::debug!(%peer, "Snapshot leaf after proof");
+                            self.p2p.penalize(peer, Penalty::InvalidData).await;
+                            continue 'next_peer;
                         }
-                        ReceiptsResponse::Fin => {
-                            tracing::debug!(%peer, "Unexpected receipts stream Fin");
+                        leaf if (leaves.len() as u32) < snapshot::RANGE_LIMIT => leaves.push(leaf),
+                        _ => {
+                            tracing::debug!(%peer, "Too many leaves in snapshot stream");
+                            self.p2p.penalize(peer, Penalty::InvalidData).await;
                             continue 'next_peer;
                         }
-                    };
+                    }
+                }
+
+                let Some(proof) = proof else {
+                    // The peer does not have the state at this block, e.g. because it was pruned.
+                    tracing::debug!(%peer, %block, "Snapshot not available");
+                    continue 'next_peer;
+                };
+
+                let verify = verify.clone();
+                match spawn_blocking(move || verify(leaves, proof))
+                    .await
+                    .context("Joining blocking task")?
+                {
+                    Ok(verified) => {
+                        self.p2p.reward(peer).await;
+                        return Ok(verified);
+                    }
+                    Err(error) => {
+                        tracing::debug!(%peer, %block, %error, "Invalid snapshot range, trying next peer");
+                        self.p2p.penalize(peer, Penalty::InvalidData).await;
+                        continue 'next_peer;
+                    }
                 }
             }
         }
     }
 
-    async fn sync_state_updates(&self, stop: BlockNumber) -> anyhow::Result<()> {
+    async fn sync_state_updates(&self, source: Source, stop: BlockNumber) -> Result<(), SyncError> {
         let storage = self.storage.clone();
         let getter = move |start: BlockNumber,
                            limit: NonZeroUsize|
@@ -418,6 +798,13 @@ impl Sync {
             .await
             .context("Finding next missing state update")?
         {
+            if source == Source::Gateway {
+                return self
+                    .gateway()?
+                    .sync_state_updates(self.storage.clone(), start, stop)
+                    .await;
+            }
+
             let getter = getter.clone();
             let result = self
                 .p2p
@@ -434,23 +821,138 @@ impl Sync {
                 .try_fold((), |_, _| std::future::ready(Ok(())))
                 .await;
 
-            match result {
-                Ok(()) => {
-                    tracing::info!("Syncing contract updates complete");
-                }
-                Err(ContractDiffSyncError::SignatureVerification(peer_data)) => {
-                    tracing::debug!(peer=%peer_data.peer, block=%peer_data.data, "Error while streaming contract updates: signature verification failed");
+            result?;
+            tracing::info!("Syncing contract updates complete");
+        }
+
+        Ok(())
+    }
+
+    /// Syncs the definitions of the classes declared in each block up to `stop`.
+    ///
+    /// Classes are requested one block at a time, as the response stream does not delimit blocks.
+    /// The number of classes declared in a block is taken from its header, so blocks which did not
+    /// declare any classes do not require a request.
+    async fn sync_classes(&self, source: Source, stop: BlockNumber) -> Result<(), SyncError> {
+        let Some(mut block) = classes::next_missing(self.storage.clone(), stop)
+            .await
+            .context("Finding next block with missing classes")?
+        else {
+            return Ok(());
+        };
+
+        loop {
+            let expected = classes::declared_class_count(self.storage.clone(), block)
+                .await
+                .context("Querying declared class count")?;
+            if expected > 0 {
+                let verified = match source {
+                    Source::P2P => {
+                        let declared = classes::declared(self.storage.clone(), block)
+                            .await
+                            .context("Querying declared classes")?;
+                        self.fetch_classes(block, expected, &declared).await
+                    }
+                    Source::Gateway => {
+                        self.gateway()?
+                            .fetch_classes(self.storage.clone(), block, expected)
+                            .await?
+                    }
+                };
+                classes::persist(self.storage.clone(), block, verified)
+                    .await
+                    .context("Inserting classes")?;
+                tracing::trace!(%block, "Classes synced");
+            }
+
+            if block >= stop {
+                break;
+            }
+            block += 1;
+        }
+
+        // Blocks without declared classes since the last persisted block are complete as well.
+        update_sync_checkpoint(self.storage.clone(), SyncStage::Classes, stop)
+            .await
+            .context("Updating classes checkpoint")?;
+        tracing::info!("Syncing classes complete");
+
+        Ok(())
+    }
+
+    /// Fetches the `expected` number of classes declared in `block`, retrying with other peers
+    /// until one of them provides the full set of valid classes.
+    ///
+    /// Classes which are not `declared` in the block are rejected, so that a peer cannot plant
+    /// arbitrary class declarations.
+    async fn fetch_classes(
+        &self,
+        block: BlockNumber,
+        expected: u64,
+        declared: &classes::Declared,
+    ) -> Vec<classes::VerifiedClass> {
+        // Loop which refreshes peer set once we exhaust it.
+        loop {
+            let peers = self.p2p.get_update_peers_with_class_sync_capability().await;
+
+            // Attempt each peer.
+            'next_peer: for peer in peers {
+                let request = ClassesRequest {
+                    iteration: Iteration {
+                        start: BlockNumberOrHash::Number(block.get()),
+                        direction: Direction::Forward,
+                        limit: 1,
+                        step: 1.into(),
+                    },
+                };
+
+                let mut responses = match self.p2p.send_classes_sync_request(peer, request).await {
+                    Ok(x) => x,
+                    Err(error) => {
+                        // Failed to establish connection, try next peer.
+                        tracing::debug!(%peer, reason=%error, "Classes request failed");
+                        continue 'next_peer;
+                    }
+                };
+
+                let mut received = Vec::new();
+                while let Some(response) = responses.next().await {
+                    match response {
+                        ClassesResponse::Class(class) if (received.len() as u64) < expected => {
+                            received.push(class)
+                        }
+                        ClassesResponse::Class(_) => {
+                            tracing::debug!(%peer, %block, "Too many classes in stream");
+                            self.p2p.penalize(peer, Penalty::InvalidData).await;
+                            continue 'next_peer;
+                        }
+                        ClassesResponse::Fin => break,
+                    }
                 }
-                Err(ContractDiffSyncError::StateDiffCommitmentMismatch(peer_data)) => {
-                    tracing::debug!(peer=%peer_data.peer, block=%peer_data.data, "Error while streaming contract updates: state diff commitment mismatch");
+
+                if received.len() as u64 != expected {
+                    tracing::debug!(%peer, %block, received=%received.len(), %expected, "Missing classes in stream");
+                    self.p2p.penalize(peer, Penalty::Disconnected).await;
+                    continue 'next_peer;
                 }
-                Err(ContractDiffSyncError::DatabaseOrComputeError(error)) => {
-                    tracing::debug!(%error, "Error while streaming contract updates");
+
+                let verified = classes::verify(received).await.and_then(|verified| {
+                    classes::check_declared(&verified, declared)?;
+                    Ok(verified)
+                });
+                match verified {
+                    Ok(verified) => {
+                        self.p2p.reward(peer).await;
+                        return verified;
+                    }
+                    Err(error) => {
+                        tracing::debug!(%peer, %block, %error, "Invalid classes, trying next peer");
+                        self.p2p.penalize(peer, Penalty::InvalidData).await;
+                        continue 'next_peer;
+                    }
                 }
             }
         }
-
-        Ok(())
     }
 }
 
@@ -667,7 +1169,40 @@ impl LocalState {
 /// then all data will be rolled back.
 async fn rollback_to_anchor(storage: Storage, anchor: Option<BlockNumber>) -> anyhow::Result<()> {
     spawn_blocking(move || {
-        todo!("Rollback storage to anchor point");
+        anyhow::bail!("Rolling back to the Ethereum anchor is not supported yet");
+    })
+    .await
+    .context("Joining blocking task")?
+}
+
+async fn sync_checkpoint(
+    storage: Storage,
+    stage: SyncStage,
+) -> anyhow::Result<Option<BlockNumber>> {
+    spawn_blocking(move || {
+        let mut db = storage
+            .connection()
+            .context("Creating database connection")?;
+        let db = db.transaction().context("Creating database transaction")?;
+        db.sync_checkpoint(stage)
+    })
+    .await
+    .context("Joining blocking task")?
+}
+
+async fn update_sync_checkpoint(
+    storage: Storage,
+    stage: SyncStage,
+    block: BlockNumber,
+) -> anyhow::Result<()> {
+    spawn_blocking(move || {
+        let mut db = storage
+            .connection()
+            .context("Creating database connection")?;
+        let db = db.transaction().context("Creating database transaction")?;
+        db.update_sync_checkpoint(stage, block)
+            .context("Updating sync checkpoint")?;
+        db.commit().context("Committing database transaction")
     })
     .await
     .context("Joining blocking task")?
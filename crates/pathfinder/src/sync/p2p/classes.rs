@@ -0,0 +1,307 @@
+use std::collections::HashSet;
+use std::num::NonZeroUsize;
+
+use anyhow::Context;
+use p2p_proto::class::Class;
+use pathfinder_common::transaction::TransactionVariant;
+use pathfinder_common::{BlockNumber, CasmHash, ClassHash, SierraHash};
+use pathfinder_storage::{Storage, SyncStage};
+use tokio::task::spawn_blocking;
+
+use crate::p2p_network::client::conv::{
+    cairo_hash_and_def_from_dto, sierra_defs_and_hashes_from_dto,
+};
+
+/// A class definition whose hash was computed locally.
+pub(super) enum VerifiedClass {
+    Cairo {
+        hash: ClassHash,
+        definition: Vec<u8>,
+    },
+    Sierra {
+        hash: SierraHash,
+        definition: Vec<u8>,
+        casm_hash: CasmHash,
+        casm_definition: Vec<u8>,
+    },
+}
+
+impl VerifiedClass {
+    fn class_hash(&self) -> ClassHash {
+        match self {
+            VerifiedClass::Cairo { hash, .. } => *hash,
+            VerifiedClass::Sierra { hash, .. } => ClassHash(hash.0),
+        }
+    }
+}
+
+/// The classes which a block is known to declare.
+pub(super) enum Declared {
+    /// The block's state update is stored, so its declared classes are known exactly.
+    Exact(HashSet<ClassHash>),
+    /// Only the block's transactions are stored, which is the case for snap-synced blocks. The
+    /// declared classes must be among those of its declare transactions or, for old blocks which
+    /// declared Cairo 0 classes implicitly, its deploy transactions.
+    Candidates(HashSet<ClassHash>),
+}
+
+/// Returns the first block whose declared classes are missing in storage, counting from genesis
+pub(super) async fn next_missing(
+    storage: Storage,
+    head: BlockNumber,
+) -> anyhow::Result<Option<BlockNumber>> {
+    spawn_blocking(move || {
+        let mut db = storage
+            .connection()
+            .context("Creating database connection")?;
+        let db = db.transaction().context("Creating database transaction")?;
+
+        let highest = match db
+            .sync_checkpoint(SyncStage::Classes)
+            .context("Querying classes checkpoint")?
+        {
+            Some(checkpoint) => Some(checkpoint),
+            // The database predates sync checkpoints, in which case classes were downloaded
+            // together with their state update.
+            None => db
+                .highest_block_with_state_update()
+                .context("Querying highest block with state update")?,
+        };
+
+        if let Some(highest) = highest {
+            Ok((highest < head).then_some(highest + 1))
+        } else {
+            Ok(Some(BlockNumber::GENESIS))
+        }
+    })
+    .await
+    .context("Joining blocking task")?
+}
+
+/// Returns the number of classes declared in `block`, according to its header.
+pub(super) async fn declared_class_count(
+    storage: Storage,
+    block: BlockNumber,
+) -> anyhow::Result<u64> {
+    spawn_blocking(move || {
+        let mut db = storage
+            .connection()
+            .context("Creating database connection")?;
+        let db = db.transaction().context("Creating database transaction")?;
+        let counts = db
+            .state_update_counts(block.into(), NonZeroUsize::MIN)
+            .context("Querying state update counts")?;
+        let counts = counts
+            .first()
+            .with_context(|| format!("Header for block {block} not found"))?;
+        Ok(counts.declared_classes)
+    })
+    .await
+    .context("Joining blocking task")?
+}
+
+/// Returns the classes declared in `block`, according to its stored state update or transactions.
+pub(super) async fn declared(storage: Storage, block: BlockNumber) -> anyhow::Result<Declared> {
+    spawn_blocking(move || {
+        let mut db = storage
+            .connection()
+            .context("Creating database connection")?;
+        let db = db.transaction().context("Creating database transaction")?;
+
+        if let Some(state_update) = db
+            .state_update(block.into())
+            .context("Querying state update")?
+        {
+            let declared = state_update
+                .declared_cairo_classes
+                .into_iter()
+                .chain(
+                    state_update
+                        .declared_sierra_classes
+                        .into_keys()
+                        .map(|hash| ClassHash(hash.0)),
+                )
+                .collect();
+            return Ok(Declared::Exact(declared));
+        }
+
+        let transactions = db
+            .transactions_for_block(block.into())
+            .context("Querying transactions")?
+            .with_context(|| format!("Transactions for block {block} not found"))?;
+        let candidates = transactions
+            .into_iter()
+            .filter_map(|transaction| match transaction.variant {
+                TransactionVariant::DeclareV0(tx) | TransactionVariant::DeclareV1(tx) => {
+                    Some(tx.class_hash)
+                }
+                TransactionVariant::DeclareV2(tx) => Some(tx.class_hash),
+                TransactionVariant::DeclareV3(tx) => Some(tx.class_hash),
+                TransactionVariant::Deploy(tx) => Some(tx.class_hash),
+                _ => None,
+            })
+            .collect();
+        Ok(Declared::Candidates(candidates))
+    })
+    .await
+    .context("Joining blocking task")?
+}
+
+/// Checks that `classes` are exactly the classes declared in their block, without duplicates.
+///
+/// Any error indicates that the peer sent classes which were not requested, or left some out.
+pub(super) fn check_declared(classes: &[VerifiedClass], declared: &Declared) -> anyhow::Result<()> {
+    let mut received = HashSet::with_capacity(classes.len());
+    for class in classes {
+        let hash = class.class_hash();
+        anyhow::ensure!(received.insert(hash), "Duplicate class {hash}");
+    }
+
+    let (Declared::Exact(expected) | Declared::Candidates(expected)) = declared;
+    if let Some(hash) = received.difference(expected).next() {
+        anyhow::bail!("Class {hash} is not declared in the block");
+    }
+    if let Declared::Exact(expected) = declared {
+        if let Some(hash) = expected.difference(&received).next() {
+            anyhow::bail!("Declared class {hash} is missing");
+        }
+    }
+
+    Ok(())
+}
+
+/// Computes the hash of each class and checks it against the hash the peer claimed.
+///
+/// Any error indicates that the peer sent invalid data.
+pub(super) async fn verify(classes: Vec<Class>) -> anyhow::Result<Vec<VerifiedClass>> {
+    spawn_blocking(move || {
+        classes
+            .into_iter()
+            .map(|class| {
+                let (verified, expected) = match class {
+                    Class::Cairo0 {
+                        class, class_hash, ..
+                    } => {
+                        let (hash, definition) = cairo_hash_and_def_from_dto(class)
+                            .context("Computing Cairo class hash")?;
+                        (VerifiedClass::Cairo { hash, definition }, class_hash.0)
+                    }
+                    Class::Cairo1 {
+                        class, class_hash, ..
+                    } => {
+                        let (hash, definition, casm_hash, casm_definition) =
+                            sierra_defs_and_hashes_from_dto(class)
+                                .context("Computing Sierra class hash")?;
+                        let verified = VerifiedClass::Sierra {
+                            hash,
+                            definition,
+                            casm_hash,
+                            casm_definition,
+                        };
+                        (verified, class_hash.0)
+                    }
+                };
+                anyhow::ensure!(
+                    verified.class_hash().0 == expected,
+                    "Class hash mismatch: expected {}, computed {}",
+                    expected,
+                    verified.class_hash()
+                );
+                Ok(verified)
+            })
+            .collect()
+    })
+    .await
+    .context("Joining blocking task")?
+}
+
+/// Persists the classes declared in `block` and marks the stage complete up to it.
+pub(super) async fn persist(
+    storage: Storage,
+    block: BlockNumber,
+    classes: Vec<VerifiedClass>,
+) -> anyhow::Result<()> {
+    spawn_blocking(move || {
+        let mut db = storage
+            .connection()
+            .context("Creating database connection")?;
+        let db = db.transaction().context("Creating database transaction")?;
+        let hashes = classes
+            .iter()
+            .map(VerifiedClass::class_hash)
+            .collect::<Vec<_>>();
+        for class in classes {
+            match class {
+                VerifiedClass::Cairo { hash, definition } => db
+                    .insert_cairo_class(hash, &definition)
+                    .context("Inserting Cairo class")?,
+                VerifiedClass::Sierra {
+                    hash,
+                    definition,
+                    casm_hash,
+                    casm_definition,
+                } => db
+                    .insert_sierra_class(&hash, &definition, &casm_hash, &casm_definition)
+                    .context("Inserting Sierra class")?,
+            }
+        }
+        db.update_class_declarations(block, &hashes)
+            .context("Updating class declarations")?;
+        db.update_sync_checkpoint(SyncStage::Classes, block)
+            .context("Updating classes checkpoint")?;
+        db.commit().context("Committing database transaction")
+    })
+    .await
+    .context("Joining blocking task")??;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+
+    use super::*;
+
+    fn cairo(hash: ClassHash) -> VerifiedClass {
+        VerifiedClass::Cairo {
+            hash,
+            definition: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn declared_classes_are_accepted() {
+        let classes = [cairo(class_hash!("0x1")), cairo(class_hash!("0x2"))];
+        let declared = HashSet::from([class_hash!("0x1"), class_hash!("0x2")]);
+
+        check_declared(&classes, &Declared::Exact(declared.clone())).unwrap();
+        check_declared(&classes, &Declared::Candidates(declared)).unwrap();
+    }
+
+    #[test]
+    fn unsolicited_class_is_rejected() {
+        let classes = [cairo(class_hash!("0x1")), cairo(class_hash!("0xbad"))];
+        let declared = HashSet::from([class_hash!("0x1"), class_hash!("0x2")]);
+
+        check_declared(&classes, &Declared::Exact(declared.clone())).unwrap_err();
+        check_declared(&classes, &Declared::Candidates(declared)).unwrap_err();
+    }
+
+    #[test]
+    fn duplicate_class_is_rejected() {
+        let classes = [cairo(class_hash!("0x1")), cairo(class_hash!("0x1"))];
+        let declared = HashSet::from([class_hash!("0x1"), class_hash!("0x2")]);
+
+        check_declared(&classes, &Declared::Candidates(declared)).unwrap_err();
+    }
+
+    #[test]
+    fn missing_declared_class_is_rejected() {
+        let classes = [cairo(class_hash!("0x1"))];
+        let declared = HashSet::from([class_hash!("0x1"), class_hash!("0x2")]);
+
+        check_declared(&classes, &Declared::Exact(declared.clone())).unwrap_err();
+        // Candidates may include classes which were only deployed.
+        check_declared(&classes, &Declared::Candidates(declared)).unwrap();
+    }
+}
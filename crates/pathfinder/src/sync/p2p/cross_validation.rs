@@ -0,0 +1,362 @@
+//! Cross-validation of the data synced from peers against the feeder gateway.
+//!
+//! Intended for the transition to P2P sync, to find peers or conversions which produce data that
+//! differs from the gateway's. Divergences are logged and counted, but sync carries on with the
+//! data received from peers, which was already verified against the L1 anchor.
+use anyhow::Context;
+use pathfinder_common::receipt::Receipt;
+use pathfinder_common::{BlockHeader, BlockNumber, TransactionHash};
+use pathfinder_storage::{Storage, SyncStage};
+use starknet_gateway_types::reply::Block;
+use tokio::task::spawn_blocking;
+
+use super::error::SyncError;
+use super::gateway::Gateway;
+
+const METRIC_BLOCKS: &str = "sync_cross_validated_blocks_total";
+const METRIC_DIVERGENCES: &str = "sync_cross_validation_divergences_total";
+
+/// The data of a block stored by a stage.
+struct Stored {
+    header: BlockHeader,
+    /// Empty unless the stage stores transactions.
+    transaction_hashes: Vec<TransactionHash>,
+    /// Empty unless the stage stores receipts.
+    receipts: Vec<Receipt>,
+}
+
+/// Compares the data stored by `stage` for the blocks in `[first, last]` with the gateway's.
+///
+/// Only headers, transactions and receipts are compared, since the state updates and classes
+/// received from peers are incomplete until the state is updated.
+pub(super) async fn validate(
+    gateway: &Gateway,
+    storage: Storage,
+    stage: SyncStage,
+    first: BlockNumber,
+    last: BlockNumber,
+) -> Result<(), SyncError> {
+    if !matches!(
+        stage,
+        SyncStage::Headers | SyncStage::Transactions | SyncStage::Receipts
+    ) {
+        return Ok(());
+    }
+
+    for number in first.get()..=last.get() {
+        let number = BlockNumber::new_or_panic(number);
+        let stored = stored(storage.clone(), stage, number).await?;
+        let (block, _) = gateway.block(number).await?;
+
+        for field in divergences(stage, &stored, &block) {
+            metrics::increment_counter!(METRIC_DIVERGENCES, "stage" => stage.as_str(), "field" => field);
+            tracing::warn!(stage=%stage.as_str(), block=%number, %field, "Data synced from peers diverges from the gateway");
+        }
+        metrics::increment_counter!(METRIC_BLOCKS, "stage" => stage.as_str());
+    }
+
+    Ok(())
+}
+
+async fn stored(storage: Storage, stage: SyncStage, block: BlockNumber) -> anyhow::Result<Stored> {
+    spawn_blocking(move || {
+        let mut db = storage
+            .connection()
+            .context("Creating database connection")?;
+        let db = db.transaction().context("Creating database transaction")?;
+
+        let header = db
+            .block_header(block.into())
+            .context("Querying block header")?
+            .with_context(|| format!("Header for block {block} not found"))?;
+        let transaction_hashes = match stage {
+            SyncStage::Transactions => db
+                .transaction_hashes_for_block(block.into())
+                .context("Querying transaction hashes")?
+                .unwrap_or_default(),
+            _ => Vec::new(),
+        };
+        let receipts = match stage {
+            SyncStage::Receipts => db
+                .receipts_for_block(block.into())
+                .context("Querying receipts")?
+                .unwrap_or_default(),
+            _ => Vec::new(),
+        };
+
+        Ok(Stored {
+            header,
+            transaction_hashes,
+            receipts,
+        })
+    })
+    .await
+    .context("Joining blocking task")?
+}
+
+/// Returns the names of the fields in which the stored data differs from the gateway's block.
+fn divergences(stage: SyncStage, stored: &Stored, block: &Block) -> Vec<&'static str> {
+    let mut fields = Vec::new();
+    let header = &stored.header;
+
+    match stage {
+        SyncStage::Headers => {
+            if header.hash != block.block_hash {
+                fields.push("block_hash");
+            }
+            if header.parent_hash != block.parent_block_hash {
+                fields.push("parent_hash");
+            }
+            if header.state_commitment != block.state_commitment {
+                fields.push("state_commitment");
+            }
+            if header.timestamp != block.timestamp {
+                fields.push("timestamp");
+            }
+            // Only included by the gateway since Starknet 0.13.1.
+            if block
+                .transaction_commitment
+                .is_some_and(|x| x != header.transaction_commitment)
+            {
+                fields.push("transaction_commitment");
+            }
+            if block
+                .event_commitment
+                .is_some_and(|x| x != header.event_commitment)
+            {
+                fields.push("event_commitment");
+            }
+        }
+        SyncStage::Transactions => {
+            if !stored
+                .transaction_hashes
+                .iter()
+                .eq(block.transactions.iter().map(|tx| &tx.hash))
+            {
+                fields.push("transactions");
+            }
+        }
+        SyncStage::Receipts => {
+            if stored.receipts != block.transaction_receipts {
+                fields.push("receipts");
+            }
+        }
+        SyncStage::StateUpdates | SyncStage::Classes => {}
+    }
+
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::receipt::ExecutionStatus;
+    use pathfinder_common::transaction::{Transaction, TransactionVariant};
+    use pathfinder_common::{BlockTimestamp, StarknetVersion};
+    use starknet_gateway_types::reply::Status;
+
+    use super::*;
+
+    /// Stored data of a block and the gateway's block with the same contents.
+    fn matching() -> (Stored, Block) {
+        let header = BlockHeader::builder()
+            .with_number(BlockNumber::new_or_panic(1))
+            .with_parent_hash(block_hash!("0x1"))
+            .with_state_commitment(state_commitment!("0x10"))
+            .with_timestamp(BlockTimestamp::new_or_panic(100))
+            .with_transaction_commitment(transaction_commitment!("0x20"))
+            .with_event_commitment(event_commitment!("0x30"))
+            .finalize_with_hash(block_hash!("0x2"));
+        let transactions = vec![
+            Transaction {
+                hash: transaction_hash!("0x100"),
+                variant: TransactionVariant::DeclareV0(Default::default()),
+            },
+            Transaction {
+                hash: transaction_hash!("0x101"),
+                variant: TransactionVariant::DeclareV0(Default::default()),
+            },
+        ];
+        let receipts = transactions
+            .iter()
+            .map(|tx| Receipt {
+                transaction_hash: tx.hash,
+                ..Default::default()
+            })
+            .collect::<Vec<_>>();
+
+        let block = Block {
+            block_hash: header.hash,
+            block_number: header.number,
+            eth_l1_gas_price_implementation_detail: None,
+            strk_l1_gas_price_implementation_detail: None,
+            l1_data_gas_price: None,
+            l1_gas_price_implementation_detail: None,
+            parent_block_hash: header.parent_hash,
+            sequencer_address: None,
+            state_commitment: header.state_commitment,
+            status: Status::AcceptedOnL1,
+            timestamp: header.timestamp,
+            transaction_receipts: receipts.clone(),
+            transactions: transactions.clone(),
+            starknet_version: StarknetVersion::default(),
+            transaction_commitment: Some(header.transaction_commitment),
+            event_commitment: Some(header.event_commitment),
+            l1_da_mode: None,
+        };
+        let stored = Stored {
+            header,
+            transaction_hashes: transactions.iter().map(|tx| tx.hash).collect(),
+            receipts,
+        };
+
+        (stored, block)
+    }
+
+    #[test]
+    fn matching_data_has_no_divergences() {
+        let (stored, block) = matching();
+
+        for stage in [
+            SyncStage::Headers,
+            SyncStage::Transactions,
+            SyncStage::Receipts,
+        ] {
+            assert!(divergences(stage, &stored, &block).is_empty());
+        }
+    }
+
+    #[test]
+    fn block_hash() {
+        let (stored, mut block) = matching();
+        block.block_hash = block_hash!("0xdead");
+
+        assert_eq!(
+            divergences(SyncStage::Headers, &stored, &block),
+            vec!["block_hash"]
+        );
+    }
+
+    #[test]
+    fn parent_hash() {
+        let (stored, mut block) = matching();
+        block.parent_block_hash = block_hash!("0xdead");
+
+        assert_eq!(
+            divergences(SyncStage::Headers, &stored, &block),
+            vec!["parent_hash"]
+        );
+    }
+
+    #[test]
+    fn state_root() {
+        let (stored, mut block) = matching();
+        block.state_commitment = state_commitment!("0xdead");
+
+        assert_eq!(
+            divergences(SyncStage::Headers, &stored, &block),
+            vec!["state_commitment"]
+        );
+    }
+
+    #[test]
+    fn timestamp() {
+        let (stored, mut block) = matching();
+        block.timestamp = BlockTimestamp::new_or_panic(101);
+
+        assert_eq!(
+            divergences(SyncStage::Headers, &stored, &block),
+            vec!["timestamp"]
+        );
+    }
+
+    #[test]
+    fn commitments() {
+        let (stored, mut block) = matching();
+        block.transaction_commitment = Some(transaction_commitment!("0xdead"));
+        block.event_commitment = Some(event_commitment!("0xdead"));
+
+        assert_eq!(
+            divergences(SyncStage::Headers, &stored, &block),
+            vec!["transaction_commitment", "event_commitment"]
+        );
+    }
+
+    #[test]
+    fn commitments_missing_before_starknet_0_13_1_are_not_compared() {
+        let (stored, mut block) = matching();
+        block.transaction_commitment = None;
+        block.event_commitment = None;
+
+        assert!(divergences(SyncStage::Headers, &stored, &block).is_empty());
+    }
+
+    #[test]
+    fn transactions() {
+        let (stored, mut block) = matching();
+        block.transactions[1].hash = transaction_hash!("0xdead");
+
+        assert_eq!(
+            divergences(SyncStage::Transactions, &stored, &block),
+            vec!["transactions"]
+        );
+    }
+
+    #[test]
+    fn missing_transactions() {
+        let (stored, mut block) = matching();
+        block.transactions.pop();
+
+        assert_eq!(
+            divergences(SyncStage::Transactions, &stored, &block),
+            vec!["transactions"]
+        );
+    }
+
+    #[test]
+    fn receipts() {
+        let (stored, mut block) = matching();
+        block.transaction_receipts[0].execution_status = ExecutionStatus::Reverted {
+            reason: "Out of gas".to_owned(),
+        };
+
+        assert_eq!(
+            divergences(SyncStage::Receipts, &stored, &block),
+            vec!["receipts"]
+        );
+    }
+
+    #[test]
+    fn reordered_receipts() {
+        let (stored, mut block) = matching();
+        block.transaction_receipts.reverse();
+
+        assert_eq!(
+            divergences(SyncStage::Receipts, &stored, &block),
+            vec!["receipts"]
+        );
+    }
+
+    #[test]
+    fn only_the_data_of_the_stage_is_compared() {
+        let (stored, mut block) = matching();
+        block.block_hash = block_hash!("0xdead");
+        block.transactions.clear();
+        block.transaction_receipts.clear();
+
+        assert_eq!(
+            divergences(SyncStage::Headers, &stored, &block),
+            vec!["block_hash"]
+        );
+        assert_eq!(
+            divergences(SyncStage::Transactions, &stored, &block),
+            vec!["transactions"]
+        );
+        assert_eq!(
+            divergences(SyncStage::Receipts, &stored, &block),
+            vec!["receipts"]
+        );
+        assert!(divergences(SyncStage::StateUpdates, &stored, &block).is_empty());
+        assert!(divergences(SyncStage::Classes, &stored, &block).is_empty());
+    }
+}
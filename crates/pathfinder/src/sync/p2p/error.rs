@@ -0,0 +1,258 @@
+use std::future::Future;
+
+use p2p::libp2p::PeerId;
+
+use super::headers::HeaderSyncError;
+use super::state_updates::ContractDiffSyncError;
+use super::RETRY_DELAY;
+
+/// A sync failure, classified by how sync recovers from it.
+#[derive(Debug, thiserror::Error)]
+pub enum SyncError {
+    /// A temporary failure, such as a request to the gateway failing. Sync is retried after a
+    /// delay.
+    #[error(transparent)]
+    Transient(anyhow::Error),
+    /// Data which failed verification. The peer which served it, if any, is penalized and the
+    /// data is fetched again.
+    #[error("Invalid data")]
+    InvalidData {
+        /// [None] if the data was served by the gateway.
+        peer: Option<PeerId>,
+        #[source]
+        error: anyhow::Error,
+    },
+    /// A failure which sync cannot recover from, such as a database error or an Ethereum
+    /// checkpoint which is inconsistent with the local chain. Sync stops.
+    #[error(transparent)]
+    Fatal(#[from] anyhow::Error),
+}
+
+impl SyncError {
+    /// Invalid data served by the gateway.
+    pub(super) fn invalid_gateway_data(error: anyhow::Error) -> Self {
+        Self::InvalidData { peer: None, error }
+    }
+
+    /// Wraps the underlying error with additional context, keeping its class.
+    pub fn context<C>(self, context: C) -> Self
+    where
+        C: std::fmt::Display + Send + Sync + 'static,
+    {
+        match self {
+            SyncError::Transient(error) => SyncError::Transient(error.context(context)),
+            SyncError::InvalidData { peer, error } => SyncError::InvalidData {
+                peer,
+                error: error.context(context),
+            },
+            SyncError::Fatal(error) => SyncError::Fatal(error.context(context)),
+        }
+    }
+}
+
+impl From<HeaderSyncError> for SyncError {
+    fn from(error: HeaderSyncError) -> Self {
+        match error {
+            HeaderSyncError::DatabaseError(error) => SyncError::Fatal(error),
+            HeaderSyncError::BadSignature(ref x)
+            | HeaderSyncError::BadBlockHash(ref x)
+            | HeaderSyncError::Discontinuity(ref x) => SyncError::InvalidData {
+                peer: Some(x.peer),
+                error: anyhow::anyhow!("{error} at block {}", x.data.header.number),
+            },
+        }
+    }
+}
+
+impl From<ContractDiffSyncError> for SyncError {
+    fn from(error: ContractDiffSyncError) -> Self {
+        match error {
+            ContractDiffSyncError::DatabaseOrComputeError(error) => SyncError::Fatal(error),
+            ContractDiffSyncError::SignatureVerification(ref x)
+            | ContractDiffSyncError::StateDiffCommitmentMismatch(ref x) => SyncError::InvalidData {
+                peer: Some(x.peer),
+                error: anyhow::anyhow!("{error} at block {}", x.data),
+            },
+        }
+    }
+}
+
+/// Runs `attempt` until it succeeds, [recovering](recover) from each failure which is not
+/// [fatal](SyncError::Fatal).
+pub(super) async fn retry<A, AF, P, PF>(mut attempt: A, mut penalize: P) -> Result<(), SyncError>
+where
+    A: FnMut() -> AF,
+    AF: Future<Output = Result<(), SyncError>>,
+    P: FnMut(PeerId) -> PF,
+    PF: Future<Output = ()>,
+{
+    loop {
+        match attempt().await {
+            Ok(()) => return Ok(()),
+            Err(error) => recover(error, &mut penalize).await?,
+        }
+    }
+}
+
+/// Recovers from a sync failure according to its class, or returns it if it is fatal.
+///
+/// Transient failures are waited out. Peers which served invalid data are penalized so that the
+/// data is fetched from another peer.
+pub(super) async fn recover<P, PF>(error: SyncError, penalize: P) -> Result<(), SyncError>
+where
+    P: FnOnce(PeerId) -> PF,
+    PF: Future<Output = ()>,
+{
+    match error {
+        SyncError::Transient(error) => {
+            tracing::debug!(?error, "Transient sync failure, retrying");
+            tokio::time::sleep(RETRY_DELAY).await;
+        }
+        SyncError::InvalidData {
+            peer: Some(peer),
+            error,
+        } => {
+            tracing::debug!(%peer, ?error, "Peer sent invalid data");
+            penalize(peer).await;
+        }
+        SyncError::InvalidData { peer: None, error } => {
+            tracing::warn!(?error, "Gateway sent invalid data, retrying");
+            tokio::time::sleep(RETRY_DELAY).await;
+        }
+        fatal @ SyncError::Fatal(_) => return Err(fatal),
+    }
+
+    Ok(())
+}
+
+/// Like [anyhow::ensure], but fails with [SyncError::InvalidData] for data served by the
+/// gateway.
+macro_rules! ensure_valid {
+    ($cond:expr, $($arg:tt)+) => {
+        if !$cond {
+            return Err($crate::sync::p2p::SyncError::invalid_gateway_data(anyhow::anyhow!($($arg)+)));
+        }
+    };
+}
+
+pub(super) use ensure_valid;
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use assert_matches::assert_matches;
+    use p2p::PeerData;
+    use pathfinder_common::BlockNumber;
+    use tokio::time::Instant;
+
+    use super::*;
+
+    /// Retries the failures in order until they are exhausted, returning the number of attempts,
+    /// the penalized peers and the result.
+    async fn retry_failures(
+        failures: Vec<SyncError>,
+    ) -> (usize, Vec<PeerId>, Result<(), SyncError>) {
+        let mut failures = failures.into_iter();
+        let mut attempts = 0;
+        let penalized = RefCell::new(Vec::new());
+
+        let result = retry(
+            || {
+                attempts += 1;
+                let result = failures.next().map_or(Ok(()), Err);
+                async move { result }
+            },
+            |peer| {
+                penalized.borrow_mut().push(peer);
+                async {}
+            },
+        )
+        .await;
+
+        (attempts, penalized.into_inner(), result)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn transient_failures_are_retried_after_a_delay() {
+        let start = Instant::now();
+
+        let (attempts, penalized, result) = retry_failures(vec![
+            SyncError::Transient(anyhow::anyhow!("Timeout")),
+            SyncError::Transient(anyhow::anyhow!("Timeout")),
+        ])
+        .await;
+
+        result.unwrap();
+        assert_eq!(attempts, 3);
+        assert!(penalized.is_empty());
+        assert_eq!(start.elapsed(), 2 * RETRY_DELAY);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn peer_serving_invalid_data_is_penalized() {
+        let peer = PeerId::random();
+        let start = Instant::now();
+
+        let (attempts, penalized, result) = retry_failures(vec![SyncError::InvalidData {
+            peer: Some(peer),
+            error: anyhow::anyhow!("Bad hash"),
+        }])
+        .await;
+
+        result.unwrap();
+        assert_eq!(attempts, 2);
+        assert_eq!(penalized, vec![peer]);
+        // The data is fetched again from another peer right away.
+        assert_eq!(start.elapsed(), std::time::Duration::ZERO);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn invalid_gateway_data_is_retried_after_a_delay() {
+        let start = Instant::now();
+
+        let (attempts, penalized, result) = retry_failures(vec![SyncError::invalid_gateway_data(
+            anyhow::anyhow!("Bad hash"),
+        )])
+        .await;
+
+        result.unwrap();
+        assert_eq!(attempts, 2);
+        assert!(penalized.is_empty());
+        assert_eq!(start.elapsed(), RETRY_DELAY);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn fatal_failure_stops_sync() {
+        let (attempts, penalized, result) = retry_failures(vec![
+            SyncError::Fatal(anyhow::anyhow!("Database error")),
+            SyncError::Transient(anyhow::anyhow!("Timeout")),
+        ])
+        .await;
+
+        assert_matches!(result, Err(SyncError::Fatal(_)));
+        assert_eq!(attempts, 1);
+        assert!(penalized.is_empty());
+    }
+
+    #[test]
+    fn state_diff_failures_are_classified() {
+        let peer = PeerId::random();
+        let block = BlockNumber::new_or_panic(1);
+
+        assert_matches!(
+            SyncError::from(ContractDiffSyncError::DatabaseOrComputeError(
+                anyhow::anyhow!("Database error")
+            )),
+            SyncError::Fatal(_)
+        );
+        assert_matches!(
+            SyncError::from(ContractDiffSyncError::SignatureVerification(PeerData::new(peer, block))),
+            SyncError::InvalidData { peer: Some(x), .. } if x == peer
+        );
+        assert_matches!(
+            SyncError::from(ContractDiffSyncError::StateDiffCommitmentMismatch(PeerData::new(peer, block))),
+            SyncError::InvalidData { peer: Some(x), .. } if x == peer
+        );
+    }
+}
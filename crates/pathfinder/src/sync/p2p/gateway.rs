@@ -0,0 +1,362 @@
+//! The stages of the pipeline sourced from the Starknet feeder gateway.
+//!
+//! The gateway serves each block together with its transactions, receipts and state update, so
+//! every stage downloads the whole block and keeps the part it needs. Unlike peers, the gateway
+//! cannot be swapped for another source when it serves invalid data, so this fails the stage
+//! with [SyncError::InvalidData].
+use anyhow::Context;
+use pathfinder_common::receipt::Receipt;
+use pathfinder_common::{
+    BlockNumber, Chain, ChainId, ClassCommitment, ClassHash, EventCommitment, GasPrice,
+    SequencerAddress, SignedBlockHeader, StateUpdate, StorageCommitment, TransactionCommitment,
+};
+use pathfinder_crypto::Felt;
+use pathfinder_storage::{Storage, SyncStage};
+use starknet_gateway_client::GatewayApi;
+use starknet_gateway_types::reply::Block;
+use tokio::task::spawn_blocking;
+
+use super::classes::VerifiedClass;
+use super::error::{ensure_valid, SyncError};
+use super::headers::{self, HeaderGap};
+use super::pipeline::{report_progress, Source};
+use super::{check_transactions, receipts, state_updates, transactions};
+use crate::state::block_hash::{
+    calculate_event_commitment, calculate_transaction_commitment, verify_block_hash,
+    TransactionCommitmentFinalHashType, VerifyResult,
+};
+use crate::state::class::{download_class, DownloadedClass};
+
+/// The number of headers persisted at once.
+const HEADERS_CHUNK_SIZE: usize = 1024;
+
+/// The number of state updates persisted at once.
+const STATE_UPDATES_CHUNK_SIZE: usize = 100;
+
+/// The feeder gateway of the chain being synced.
+#[derive(Clone)]
+pub struct Gateway {
+    pub client: starknet_gateway_client::Client,
+    pub chain: Chain,
+    pub chain_id: ChainId,
+}
+
+impl Gateway {
+    /// Downloads block `number` along with its state update.
+    pub(super) async fn block(
+        &self,
+        number: BlockNumber,
+    ) -> Result<(Box<Block>, StateUpdate), SyncError> {
+        let (block, state_update) = self
+            .client
+            .state_update_with_block(number)
+            .await
+            .with_context(|| format!("Downloading block {number}"))
+            .map_err(SyncError::Transient)?;
+        Ok((Box::new(block), state_update))
+    }
+
+    /// Syncs the headers of `gap` in reverse chronological order, starting with its head.
+    ///
+    /// The head must match the gap's head hash, and every other block the parent hash of its
+    /// child, which links the headers to the L1 anchor.
+    pub(super) async fn sync_headers(
+        &self,
+        storage: Storage,
+        gap: HeaderGap,
+    ) -> Result<(), SyncError> {
+        let mut expected_hash = gap.head_hash;
+        let mut number = gap.head;
+        let mut chunk = Vec::with_capacity(HEADERS_CHUNK_SIZE);
+
+        loop {
+            let (block, state_update) = self.block(number).await?;
+            ensure_valid!(
+                block.block_hash == expected_hash,
+                "Block {number} hash mismatch, actual {:x}, expected {:x}",
+                block.block_hash.0,
+                expected_hash.0,
+            );
+
+            let chain = self.chain;
+            let chain_id = self.chain_id;
+            let (block, (transaction_commitment, event_commitment)) =
+                spawn_blocking(move || -> anyhow::Result<_> {
+                    let commitments = commitments(&block, chain, chain_id)?;
+                    Ok((block, commitments))
+                })
+                .await
+                .context("Joining blocking task")?
+                .with_context(|| format!("Verifying block {number}"))
+                .map_err(SyncError::invalid_gateway_data)?;
+
+            let signature = self
+                .client
+                .signature(number.into())
+                .await
+                .with_context(|| format!("Downloading signature of block {number}"))
+                .map_err(SyncError::Transient)?;
+            ensure_valid!(
+                signature.signature_input.block_hash == block.block_hash,
+                "Signature block hash mismatch for block {number}"
+            );
+
+            expected_hash = block.parent_block_hash;
+            chunk.push(SignedBlockHeader {
+                header: pathfinder_common::BlockHeader {
+                    hash: block.block_hash,
+                    parent_hash: block.parent_block_hash,
+                    number: block.block_number,
+                    timestamp: block.timestamp,
+                    eth_l1_gas_price: block.eth_l1_gas_price().unwrap_or(GasPrice::ZERO),
+                    strk_l1_gas_price: block.strk_l1_gas_price().unwrap_or(GasPrice::ZERO),
+                    eth_l1_data_gas_price: block
+                        .l1_data_gas_price
+                        .map(|x| x.price_in_wei)
+                        .unwrap_or(GasPrice::ZERO),
+                    strk_l1_data_gas_price: block
+                        .l1_data_gas_price
+                        .map(|x| x.price_in_fri)
+                        .unwrap_or(GasPrice::ZERO),
+                    sequencer_address: block
+                        .sequencer_address
+                        .unwrap_or(SequencerAddress(Felt::ZERO)),
+                    starknet_version: block.starknet_version.clone(),
+                    class_commitment: ClassCommitment::ZERO,
+                    event_commitment,
+                    state_commitment: block.state_commitment,
+                    storage_commitment: StorageCommitment::ZERO,
+                    transaction_commitment,
+                    transaction_count: block.transactions.len(),
+                    event_count: block
+                        .transaction_receipts
+                        .iter()
+                        .map(|r| r.events.len())
+                        .sum(),
+                    l1_da_mode: block.l1_da_mode.map(Into::into).unwrap_or_default(),
+                },
+                signature: signature.into(),
+                state_update_counts: state_update.counts(),
+            });
+
+            let done = number == gap.tail;
+            if done || chunk.len() == HEADERS_CHUNK_SIZE {
+                headers::persist_verified(std::mem::take(&mut chunk), storage.clone())
+                    .await
+                    .context("Inserting headers")?;
+                tracing::info!(tail=%number, "Header chunk synced");
+            }
+            if done {
+                return Ok(());
+            }
+            number -= 1;
+        }
+    }
+
+    /// Syncs the transactions of the blocks in `[first, last]`.
+    pub(super) async fn sync_transactions(
+        &self,
+        storage: Storage,
+        first: BlockNumber,
+        last: BlockNumber,
+    ) -> Result<(), SyncError> {
+        for number in first.get()..=last.get() {
+            let number = BlockNumber::new_or_panic(number);
+            let header = headers::query(storage.clone(), number)
+                .await?
+                .with_context(|| format!("Header for block {number} not found"))?;
+
+            let (block, _) = self.block(number).await?;
+            ensure_valid!(
+                block.block_hash == header.hash,
+                "Block {number} hash mismatch"
+            );
+            let transactions = block.transactions;
+            ensure_valid!(
+                check_transactions(&header, &transactions).await?,
+                "Transactions of block {number} do not match its header"
+            );
+
+            transactions::persist(storage.clone(), header, transactions)
+                .await
+                .context("Inserting transactions")?;
+            report_progress(SyncStage::Transactions, Source::Gateway, number);
+        }
+
+        Ok(())
+    }
+
+    /// Fetches the receipts, including their events, of the given consecutive blocks.
+    pub(super) async fn fetch_receipts(
+        &self,
+        blocks: &[receipts::Block],
+    ) -> Result<Vec<Vec<Receipt>>, SyncError> {
+        let mut all_receipts = Vec::with_capacity(blocks.len());
+
+        for block in blocks {
+            let number = block.header.number;
+            let (downloaded, _) = self.block(number).await?;
+            ensure_valid!(
+                downloaded.block_hash == block.header.hash,
+                "Block {number} hash mismatch"
+            );
+
+            let mut receipts = downloaded.transaction_receipts;
+            ensure_valid!(
+                receipts::verify_receipts(block, &mut receipts),
+                "Receipts of block {number} do not match its transactions"
+            );
+            // The events are verified the same way as those received from peers.
+            let events = receipts
+                .iter_mut()
+                .flat_map(|receipt| {
+                    let transaction_hash = receipt.transaction_hash;
+                    std::mem::take(&mut receipt.events)
+                        .into_iter()
+                        .map(move |event| (transaction_hash, event))
+                })
+                .collect();
+            let Some(receipts) = receipts::with_events(block, receipts, events).await? else {
+                return Err(SyncError::invalid_gateway_data(anyhow::anyhow!(
+                    "Events of block {number} do not match its header"
+                )));
+            };
+
+            all_receipts.push(receipts);
+        }
+
+        Ok(all_receipts)
+    }
+
+    /// Syncs the state updates of the blocks in `[start, stop]`.
+    pub(super) async fn sync_state_updates(
+        &self,
+        storage: Storage,
+        start: BlockNumber,
+        stop: BlockNumber,
+    ) -> Result<(), SyncError> {
+        let mut chunk = Vec::with_capacity(STATE_UPDATES_CHUNK_SIZE);
+
+        for number in start.get()..=stop.get() {
+            let number = BlockNumber::new_or_panic(number);
+            let header = headers::query(storage.clone(), number)
+                .await?
+                .with_context(|| format!("Header for block {number} not found"))?;
+
+            let (_, state_update) = self.block(number).await?;
+            ensure_valid!(
+                state_update.block_hash == header.hash,
+                "State update of block {number} has a different block hash"
+            );
+            ensure_valid!(
+                state_update.state_commitment == header.state_commitment,
+                "State update of block {number} has a different state commitment"
+            );
+
+            chunk.push((number, state_update));
+            if number == stop || chunk.len() == STATE_UPDATES_CHUNK_SIZE {
+                state_updates::persist_verified(storage.clone(), std::mem::take(&mut chunk))
+                    .await
+                    .context("Inserting state updates")?;
+                report_progress(SyncStage::StateUpdates, Source::Gateway, number);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Fetches the `expected` number of classes declared in `block`.
+    ///
+    /// The declared classes are taken from the block's state update on the gateway, since those
+    /// synced from peers do not include them.
+    pub(super) async fn fetch_classes(
+        &self,
+        storage: Storage,
+        block: BlockNumber,
+        expected: u64,
+    ) -> Result<Vec<VerifiedClass>, SyncError> {
+        let header = headers::query(storage, block)
+            .await?
+            .with_context(|| format!("Header for block {block} not found"))?;
+        let (_, state_update) = self.block(block).await?;
+        ensure_valid!(
+            state_update.block_hash == header.hash,
+            "State update of block {block} has a different block hash"
+        );
+        let version = header.starknet_version;
+
+        let class_hashes = state_update
+            .declared_cairo_classes
+            .iter()
+            .copied()
+            .chain(
+                state_update
+                    .declared_sierra_classes
+                    .keys()
+                    .map(|sierra| ClassHash(sierra.0)),
+            )
+            .collect::<Vec<_>>();
+        ensure_valid!(
+            class_hashes.len() as u64 == expected,
+            "State update of block {block} declares {} classes instead of {expected}",
+            class_hashes.len()
+        );
+
+        let mut verified = Vec::with_capacity(class_hashes.len());
+        for class_hash in class_hashes {
+            let class = match download_class(&self.client, class_hash, version.clone())
+                .await
+                .with_context(|| format!("Downloading class {class_hash}"))
+                .map_err(SyncError::Transient)?
+            {
+                DownloadedClass::Cairo { definition, hash } => {
+                    VerifiedClass::Cairo { hash, definition }
+                }
+                DownloadedClass::Sierra {
+                    sierra_definition,
+                    sierra_hash,
+                    casm_definition,
+                } => {
+                    let casm_hash = *state_update
+                        .declared_sierra_classes
+                        .get(&sierra_hash)
+                        .with_context(|| format!("Class {sierra_hash} was not declared"))
+                        .map_err(SyncError::invalid_gateway_data)?;
+                    VerifiedClass::Sierra {
+                        hash: sierra_hash,
+                        definition: sierra_definition,
+                        casm_hash,
+                        casm_definition,
+                    }
+                }
+            };
+            verified.push(class);
+        }
+
+        Ok(verified)
+    }
+}
+
+/// Returns the transaction and event commitments of the block after verifying its hash.
+///
+/// The commitments are computed locally for old blocks whose hash cannot be verified.
+fn commitments(
+    block: &Block,
+    chain: Chain,
+    chain_id: ChainId,
+) -> anyhow::Result<(TransactionCommitment, EventCommitment)> {
+    match verify_block_hash(block, chain, chain_id, block.block_hash)? {
+        VerifyResult::Match(commitments) => Ok(commitments),
+        VerifyResult::Mismatch => anyhow::bail!("Block hash mismatch"),
+        VerifyResult::NotVerifiable => {
+            let final_hash_type =
+                TransactionCommitmentFinalHashType::for_version(&block.starknet_version)?;
+            let transaction_commitment =
+                calculate_transaction_commitment(&block.transactions, final_hash_type)
+                    .context("Calculating transaction commitment")?;
+            let event_commitment = calculate_event_commitment(&block.transaction_receipts)
+                .context("Calculating event commitment")?;
+            Ok((transaction_commitment, event_commitment))
+        }
+    }
+}
@@ -158,37 +158,8 @@ pub(super) async fn persist(
             .context("Creating database connection")?;
         let tx = db.transaction().context("Creating database transaction")?;
 
-        for SignedBlockHeader {
-            header,
-            signature,
-            state_update_counts,
-        } in signed_headers.iter().map(|x| &x.data)
-        {
-            tx.insert_block_header(&pathfinder_common::BlockHeader {
-                hash: header.hash,
-                parent_hash: header.parent_hash,
-                number: header.number,
-                timestamp: header.timestamp,
-                eth_l1_gas_price: header.eth_l1_gas_price,
-                strk_l1_gas_price: header.strk_l1_gas_price,
-                eth_l1_data_gas_price: header.eth_l1_data_gas_price,
-                strk_l1_data_gas_price: header.strk_l1_data_gas_price,
-                sequencer_address: header.sequencer_address,
-                starknet_version: header.starknet_version.clone(),
-                class_commitment: ClassCommitment::ZERO,
-                event_commitment: header.event_commitment,
-                state_commitment: header.state_commitment,
-                storage_commitment: StorageCommitment::ZERO,
-                transaction_commitment: header.transaction_commitment,
-                transaction_count: header.transaction_count,
-                event_count: header.event_count,
-                l1_da_mode: header.l1_da_mode,
-            })
-            .context("Persisting block header")?;
-            tx.insert_signature(header.number, signature)
-                .context("Persisting block signature")?;
-            tx.insert_state_update_counts(header.number, state_update_counts)
-                .context("Persisting state update counts")?;
+        for signed_header in signed_headers.iter().map(|x| &x.data) {
+            insert(&tx, signed_header)?;
         }
 
         tx.commit().context("Committing database transaction")?;
@@ -199,6 +170,65 @@ pub(super) async fn persist(
     .expect("Task should not crash")
 }
 
+/// Writes headers which were verified by the caller to storage.
+pub(super) async fn persist_verified(
+    signed_headers: Vec<SignedBlockHeader>,
+    storage: Storage,
+) -> anyhow::Result<()> {
+    spawn_blocking(move || {
+        let mut db = storage
+            .connection()
+            .context("Creating database connection")?;
+        let tx = db.transaction().context("Creating database transaction")?;
+
+        for signed_header in &signed_headers {
+            insert(&tx, signed_header)?;
+        }
+
+        tx.commit().context("Committing database transaction")
+    })
+    .await
+    .context("Joining blocking task")?
+}
+
+/// Inserts the header along with its signature and state update counts.
+fn insert(
+    tx: &pathfinder_storage::Transaction<'_>,
+    SignedBlockHeader {
+        header,
+        signature,
+        state_update_counts,
+    }: &SignedBlockHeader,
+) -> anyhow::Result<()> {
+    tx.insert_block_header(&pathfinder_common::BlockHeader {
+        hash: header.hash,
+        parent_hash: header.parent_hash,
+        number: header.number,
+        timestamp: header.timestamp,
+        eth_l1_gas_price: header.eth_l1_gas_price,
+        strk_l1_gas_price: header.strk_l1_gas_price,
+        eth_l1_data_gas_price: header.eth_l1_data_gas_price,
+        strk_l1_data_gas_price: header.strk_l1_data_gas_price,
+        sequencer_address: header.sequencer_address,
+        starknet_version: header.starknet_version.clone(),
+        class_commitment: ClassCommitment::ZERO,
+        event_commitment: header.event_commitment,
+        state_commitment: header.state_commitment,
+        storage_commitment: StorageCommitment::ZERO,
+        transaction_commitment: header.transaction_commitment,
+        transaction_count: header.transaction_count,
+        event_count: header.event_count,
+        l1_da_mode: header.l1_da_mode,
+    })
+    .context("Persisting block header")?;
+    tx.insert_signature(header.number, signature)
+        .context("Persisting block signature")?;
+    tx.insert_state_update_counts(header.number, state_update_counts)
+        .context("Persisting state update counts")?;
+
+    Ok(())
+}
+
 pub(super) async fn query(
     storage: Storage,
     block_number: BlockNumber,
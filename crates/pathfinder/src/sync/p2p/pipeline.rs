@@ -0,0 +1,167 @@
+//! The stages of the sync pipeline and the sources they download their data from.
+use pathfinder_common::BlockNumber;
+use pathfinder_storage::SyncStage;
+
+const METRIC_STAGE_BLOCK: &str = "sync_stage_block";
+
+/// The stages of the pipeline, in the order in which they are run.
+///
+/// Each stage only processes blocks completed by the stages before it.
+pub(super) const STAGES: [SyncStage; 5] = [
+    SyncStage::Headers,
+    SyncStage::Transactions,
+    SyncStage::Receipts,
+    SyncStage::StateUpdates,
+    SyncStage::Classes,
+];
+
+/// Where a sync stage downloads its data from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Source {
+    /// Peers of the P2P network.
+    #[default]
+    P2P,
+    /// The Starknet feeder gateway.
+    Gateway,
+}
+
+impl std::fmt::Display for Source {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Source::P2P => f.write_str("p2p"),
+            Source::Gateway => f.write_str("gateway"),
+        }
+    }
+}
+
+/// The configured [Source] of each sync stage.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Sources {
+    pub headers: Source,
+    pub transactions: Source,
+    /// Receipts, including their events.
+    pub receipts: Source,
+    pub state_updates: Source,
+    pub classes: Source,
+}
+
+impl Sources {
+    /// Uses `source` for all stages.
+    pub fn all(source: Source) -> Self {
+        Self {
+            headers: source,
+            transactions: source,
+            receipts: source,
+            state_updates: source,
+            classes: source,
+        }
+    }
+
+    pub fn get(&self, stage: SyncStage) -> Source {
+        match stage {
+            SyncStage::Headers => self.headers,
+            SyncStage::Transactions => self.transactions,
+            SyncStage::Receipts => self.receipts,
+            SyncStage::StateUpdates => self.state_updates,
+            SyncStage::Classes => self.classes,
+        }
+    }
+}
+
+/// Selects the [Source] of `stage`, falling back from the `configured` source if it is
+/// unavailable.
+///
+/// Stages configured to use the gateway fall back to P2P if no gateway is available, and stages
+/// configured to use P2P fall back to the gateway if no peer supports them. State updates are
+/// always downloaded from the gateway if one is available, as those received from peers cannot
+/// be verified yet.
+pub(super) fn select_source(
+    stage: SyncStage,
+    configured: Source,
+    has_gateway: bool,
+    has_peers: bool,
+) -> Source {
+    match configured {
+        Source::Gateway if !has_gateway => {
+            tracing::warn!(stage=%stage.as_str(), "No gateway configured, falling back to p2p");
+            Source::P2P
+        }
+        Source::P2P if has_gateway && stage == SyncStage::StateUpdates => {
+            tracing::info!(stage=%stage.as_str(), "State updates from peers cannot be verified, using gateway");
+            Source::Gateway
+        }
+        Source::P2P if has_gateway && !has_peers => {
+            tracing::info!(stage=%stage.as_str(), "No peers available, falling back to gateway");
+            Source::Gateway
+        }
+        source => source,
+    }
+}
+
+/// Reports that `stage` has completed all blocks up to and including `block`.
+pub(super) fn report_progress(stage: SyncStage, source: Source, block: BlockNumber) {
+    metrics::gauge!(METRIC_STAGE_BLOCK, block.get() as f64, "stage" => stage.as_str());
+    tracing::info!(stage=%stage.as_str(), %source, %block, "Sync stage progressed");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn configured_source_is_used_if_available() {
+        for stage in STAGES {
+            assert_eq!(
+                select_source(stage, Source::Gateway, true, false),
+                Source::Gateway
+            );
+        }
+        assert_eq!(
+            select_source(SyncStage::Headers, Source::P2P, true, true),
+            Source::P2P
+        );
+        assert_eq!(
+            select_source(SyncStage::Receipts, Source::P2P, true, true),
+            Source::P2P
+        );
+    }
+
+    #[test]
+    fn gateway_falls_back_to_p2p_without_gateway() {
+        for stage in STAGES {
+            assert_eq!(
+                select_source(stage, Source::Gateway, false, true),
+                Source::P2P
+            );
+            assert_eq!(
+                select_source(stage, Source::Gateway, false, false),
+                Source::P2P
+            );
+        }
+    }
+
+    #[test]
+    fn p2p_falls_back_to_gateway_without_peers() {
+        for stage in STAGES {
+            assert_eq!(
+                select_source(stage, Source::P2P, true, false),
+                Source::Gateway
+            );
+        }
+    }
+
+    #[test]
+    fn p2p_is_kept_without_gateway() {
+        for stage in STAGES {
+            assert_eq!(select_source(stage, Source::P2P, false, false), Source::P2P);
+        }
+    }
+
+    #[test]
+    fn state_updates_use_gateway_if_available() {
+        assert_eq!(
+            select_source(SyncStage::StateUpdates, Source::P2P, true, true),
+            Source::Gateway
+        );
+    }
+}
@@ -1,25 +1,642 @@
 use anyhow::Context;
-use pathfinder_common::{receipt::Receipt, BlockHeader};
-use pathfinder_storage::Storage;
+use futures::channel::mpsc::Receiver;
+use futures::StreamExt;
+use p2p::client::{conv::TryFromDto, peer_agnostic::Client as P2PClient, peer_scores::Penalty};
+use p2p::libp2p::PeerId;
+use p2p_proto::common::{BlockNumberOrHash, Direction, Iteration};
+use p2p_proto::event::{EventsRequest, EventsResponse};
+use p2p_proto::receipt::{ReceiptsRequest, ReceiptsResponse};
+use pathfinder_common::event::Event;
+use pathfinder_common::receipt::{BuiltinCounters, ExecutionResources, Receipt};
+use pathfinder_common::{BlockHeader, BlockNumber, TransactionHash, TransactionIndex};
+use pathfinder_storage::{Storage, SyncStage};
 use tokio::task::spawn_blocking;
 
+use crate::state::block_hash::calculate_event_commitment;
+
+/// The stored data of a block which its receipts and events are verified against.
+pub(super) struct Block {
+    pub header: BlockHeader,
+    pub transaction_hashes: Vec<TransactionHash>,
+}
+
+/// Splits the items of a response stream spanning several blocks, using the expected number
+/// of items in each block.
+pub(super) struct BlockSplitter<T> {
+    counts: Vec<usize>,
+    blocks: Vec<Vec<T>>,
+    current: Vec<T>,
+}
+
+impl<T> BlockSplitter<T> {
+    pub fn new(counts: Vec<usize>) -> Self {
+        Self {
+            counts,
+            blocks: Vec::new(),
+            current: Vec::new(),
+        }
+    }
+
+    /// Adds the next item of the stream. Returns `false` if all blocks are already complete.
+    pub fn push(&mut self, item: T) -> bool {
+        self.close_complete();
+        if self.blocks.len() == self.counts.len() {
+            return false;
+        }
+        self.current.push(item);
+        true
+    }
+
+    /// Returns the items of each block, or [None] if the stream ended before all blocks were
+    /// complete.
+    pub fn finish(mut self) -> Option<Vec<Vec<T>>> {
+        self.close_complete();
+        (self.blocks.len() == self.counts.len()).then_some(self.blocks)
+    }
+
+    fn close_complete(&mut self) {
+        // Blocks without any items are skipped by the stream.
+        while let Some(&count) = self.counts.get(self.blocks.len()) {
+            if self.current.len() != count {
+                break;
+            }
+            self.blocks.push(std::mem::take(&mut self.current));
+        }
+    }
+}
+
+/// Returns the blocks in `[start, stop]`.
+pub(super) async fn query(
+    storage: Storage,
+    start: BlockNumber,
+    stop: BlockNumber,
+) -> anyhow::Result<Vec<Block>> {
+    spawn_blocking(move || {
+        let mut db = storage
+            .connection()
+            .context("Creating database connection")?;
+        let db = db.transaction().context("Creating database transaction")?;
+        (start.get()..=stop.get())
+            .map(|number| {
+                let number = BlockNumber::new_or_panic(number);
+                let header = db
+                    .block_header(number.into())
+                    .context("Querying block header")?
+                    .with_context(|| format!("Header for block {number} not found"))?;
+                let transaction_hashes = db
+                    .transaction_hashes_for_block(number.into())
+                    .context("Querying transaction hashes")?
+                    .with_context(|| format!("Transactions for block {number} not found"))?;
+                Ok(Block {
+                    header,
+                    transaction_hashes,
+                })
+            })
+            .collect()
+    })
+    .await
+    .context("Joining blocking task")?
+}
+
+/// Checks that the receipts belong to the block's transactions, in order, and sets their
+/// transaction index.
+pub(super) fn verify_receipts(block: &Block, receipts: &mut [Receipt]) -> bool {
+    if receipts.len() != block.transaction_hashes.len() {
+        return false;
+    }
+    for (idx, (receipt, hash)) in receipts
+        .iter_mut()
+        .zip(&block.transaction_hashes)
+        .enumerate()
+    {
+        if receipt.transaction_hash != *hash {
+            return false;
+        }
+        receipt.transaction_index = TransactionIndex::new_or_panic(idx as u64);
+    }
+    true
+}
+
+/// Checks that the receipts received from a peer match the authoritative `expected` receipts of
+/// the block.
+///
+/// Block headers do not commit to the contents of receipts, so a peer could otherwise forge
+/// fees, execution status, resources or L2 to L1 messages. Only the fields which are transferred
+/// over P2P are compared. Events are verified separately against the block's event commitment.
+pub(super) fn matches_expected(receipts: &[Receipt], expected: &[Receipt]) -> bool {
+    fn resources(receipt: &Receipt) -> ExecutionResources {
+        ExecutionResources {
+            builtins: BuiltinCounters {
+                segment_arena: 0,
+                ..receipt.execution_resources.builtins.clone()
+            },
+            ..receipt.execution_resources.clone()
+        }
+    }
+
+    receipts.len() == expected.len()
+        && receipts.iter().zip(expected).all(|(receipt, expected)| {
+            receipt.transaction_hash == expected.transaction_hash
+                && receipt.actual_fee.unwrap_or_default() == expected.actual_fee.unwrap_or_default()
+                && receipt.execution_status == expected.execution_status
+                && receipt.l2_to_l1_messages == expected.l2_to_l1_messages
+                && resources(receipt) == resources(expected)
+        })
+}
+
+/// Adds the events to the receipts of the transactions which emitted them, and checks the
+/// result against the block's event commitment.
+///
+/// Returns [None] if the events are not ordered by transaction or do not match the commitment.
+pub(super) async fn with_events(
+    block: &Block,
+    mut receipts: Vec<Receipt>,
+    events: Vec<(TransactionHash, Event)>,
+) -> anyhow::Result<Option<Vec<Receipt>>> {
+    if events.len() != block.header.event_count {
+        return Ok(None);
+    }
+
+    let mut idx = 0;
+    for (transaction_hash, event) in events {
+        // Events are streamed in transaction order.
+        let Some(offset) = receipts[idx..]
+            .iter()
+            .position(|receipt| receipt.transaction_hash == transaction_hash)
+        else {
+            return Ok(None);
+        };
+        idx += offset;
+        receipts[idx].events.push(event);
+    }
+
+    let expected = block.header.event_commitment;
+    spawn_blocking(move || {
+        let event_commitment =
+            calculate_event_commitment(&receipts).context("Calculating event commitment")?;
+        Ok((event_commitment == expected).then_some(receipts))
+    })
+    .await
+    .context("Joining blocking task")?
+}
+
+/// The peers which receipts and events are requested from.
+#[async_trait::async_trait]
+pub(super) trait Peers: Sync {
+    async fn receipt_peers(&self) -> Vec<PeerId>;
+
+    async fn event_peers(&self) -> Vec<PeerId>;
+
+    async fn receipts(
+        &self,
+        peer: PeerId,
+        request: ReceiptsRequest,
+    ) -> anyhow::Result<Receiver<ReceiptsResponse>>;
+
+    async fn events(
+        &self,
+        peer: PeerId,
+        request: EventsRequest,
+    ) -> anyhow::Result<Receiver<EventsResponse>>;
+
+    async fn reward(&self, peer: PeerId);
+
+    async fn penalize(&self, peer: PeerId, penalty: Penalty);
+}
+
+#[async_trait::async_trait]
+impl Peers for P2PClient {
+    async fn receipt_peers(&self) -> Vec<PeerId> {
+        self.get_update_peers_with_receipt_sync_capability().await
+    }
+
+    async fn event_peers(&self) -> Vec<PeerId> {
+        self.get_update_peers_with_event_sync_capability().await
+    }
+
+    async fn receipts(
+        &self,
+        peer: PeerId,
+        request: ReceiptsRequest,
+    ) -> anyhow::Result<Receiver<ReceiptsResponse>> {
+        self.send_receipts_sync_request(peer, request).await
+    }
+
+    async fn events(
+        &self,
+        peer: PeerId,
+        request: EventsRequest,
+    ) -> anyhow::Result<Receiver<EventsResponse>> {
+        self.send_events_sync_request(peer, request).await
+    }
+
+    async fn reward(&self, peer: PeerId) {
+        P2PClient::reward(self, peer).await
+    }
+
+    async fn penalize(&self, peer: PeerId, penalty: Penalty) {
+        P2PClient::penalize(self, peer, penalty).await
+    }
+}
+
+fn iteration(blocks: &[Block]) -> Iteration {
+    Iteration {
+        start: BlockNumberOrHash::Number(blocks[0].header.number.get()),
+        direction: Direction::Forward,
+        limit: blocks.len() as u64,
+        step: 1.into(),
+    }
+}
+
+/// Fetches the receipts of the given consecutive blocks, retrying with other peers until one of
+/// them provides receipts which belong to the blocks' transactions and match the `expected`
+/// receipts of each block.
+pub(super) async fn fetch_receipts(
+    peers: &impl Peers,
+    blocks: &[Block],
+    expected: &[Vec<Receipt>],
+) -> Vec<Vec<Receipt>> {
+    let start = blocks[0].header.number;
+
+    // Loop which refreshes peer set once we exhaust it.
+    loop {
+        // Attempt each peer.
+        'next_peer: for peer in peers.receipt_peers().await {
+            let request = ReceiptsRequest {
+                iteration: iteration(blocks),
+            };
+
+            let mut responses = match peers.receipts(peer, request).await {
+                Ok(x) => x,
+                Err(error) => {
+                    // Failed to establish connection, try next peer.
+                    tracing::debug!(%peer, reason=%error, "Receipts request failed");
+                    continue 'next_peer;
+                }
+            };
+
+            let mut splitter =
+                BlockSplitter::new(blocks.iter().map(|x| x.header.transaction_count).collect());
+            while let Some(receipt) = responses.next().await {
+                match receipt {
+                    ReceiptsResponse::Receipt(receipt) => match Receipt::try_from_dto(receipt) {
+                        Ok(receipt) => {
+                            if !splitter.push(receipt) {
+                                tracing::debug!(%peer, "Too many receipts in stream");
+                                peers.penalize(peer, Penalty::InvalidData).await;
+                                continue 'next_peer;
+                            }
+                        }
+                        Err(error) => {
+                            tracing::debug!(%peer, %error, "Receipt stream returned unexpected DTO");
+                            peers.penalize(peer, Penalty::InvalidData).await;
+                            continue 'next_peer;
+                        }
+                    },
+                    ReceiptsResponse::Fin => break,
+                };
+            }
+
+            let Some(mut receipts) = splitter.finish() else {
+                tracing::debug!(%peer, %start, "Missing receipts in stream");
+                peers.penalize(peer, Penalty::Disconnected).await;
+                continue 'next_peer;
+            };
+            for ((block, receipts), expected) in
+                blocks.iter().zip(receipts.iter_mut()).zip(expected)
+            {
+                if !verify_receipts(block, receipts) || !matches_expected(receipts, expected) {
+                    tracing::debug!(
+                        "Invalid receipts for block {}, trying next peer",
+                        block.header.number
+                    );
+                    peers.penalize(peer, Penalty::InvalidData).await;
+                    continue 'next_peer;
+                }
+            }
+
+            peers.reward(peer).await;
+            return receipts;
+        }
+    }
+}
+
+/// Fetches the events of the given consecutive blocks and adds them to their receipts, retrying
+/// with other peers until one of them provides events matching the blocks' event commitments.
+pub(super) async fn fetch_events(
+    peers: &impl Peers,
+    blocks: &[Block],
+    receipts: Vec<Vec<Receipt>>,
+) -> anyhow::Result<Vec<Vec<Receipt>>> {
+    let start = blocks[0].header.number;
+
+    // Loop which refreshes peer set once we exhaust it.
+    loop {
+        // Attempt each peer.
+        'next_peer: for peer in peers.event_peers().await {
+            let request = EventsRequest {
+                iteration: iteration(blocks),
+            };
+
+            let mut responses = match peers.events(peer, request).await {
+                Ok(x) => x,
+                Err(error) => {
+                    // Failed to establish connection, try next peer.
+                    tracing::debug!(%peer, reason=%error, "Events request failed");
+                    continue 'next_peer;
+                }
+            };
+
+            let mut splitter =
+                BlockSplitter::new(blocks.iter().map(|x| x.header.event_count).collect());
+            while let Some(event) = responses.next().await {
+                match event {
+                    EventsResponse::Event(event) => {
+                        let transaction_hash = TransactionHash(event.transaction_hash.0);
+                        match Event::try_from_dto(event) {
+                            Ok(event) => {
+                                if !splitter.push((transaction_hash, event)) {
+                                    tracing::debug!(%peer, "Too many events in stream");
+                                    peers.penalize(peer, Penalty::InvalidData).await;
+                                    continue 'next_peer;
+                                }
+                            }
+                            Err(error) => {
+                                tracing::debug!(%peer, %error, "Event stream returned unexpected DTO");
+                                peers.penalize(peer, Penalty::InvalidData).await;
+                                continue 'next_peer;
+                            }
+                        }
+                    }
+                    EventsResponse::Fin => break,
+                };
+            }
+
+            let Some(events) = splitter.finish() else {
+                tracing::debug!(%peer, %start, "Missing events in stream");
+                peers.penalize(peer, Penalty::Disconnected).await;
+                continue 'next_peer;
+            };
+
+            let mut verified = Vec::with_capacity(blocks.len());
+            for ((block, receipts), events) in
+                blocks.iter().zip(receipts.iter().cloned()).zip(events)
+            {
+                match with_events(block, receipts, events).await? {
+                    Some(receipts) => verified.push(receipts),
+                    None => {
+                        tracing::debug!(
+                            "Invalid events for block {}, trying next peer",
+                            block.header.number
+                        );
+                        peers.penalize(peer, Penalty::InvalidData).await;
+                        continue 'next_peer;
+                    }
+                }
+            }
+
+            peers.reward(peer).await;
+            return Ok(verified);
+        }
+    }
+}
+
+/// Persists the receipts of consecutive blocks, including their events, and marks the stage
+/// complete up to the last block.
 pub(super) async fn persist(
     storage: Storage,
-    block: BlockHeader,
-    receipts: Vec<Receipt>,
+    blocks: Vec<(BlockHeader, Vec<Receipt>)>,
 ) -> anyhow::Result<()> {
     spawn_blocking(move || {
         let mut db = storage
             .connection()
             .context("Creating database connection")?;
         let db = db.transaction().context("Creating database transaction")?;
-        for (transaction_idx, receipt) in receipts.into_iter().enumerate() {
-            db.update_receipt(block.hash, transaction_idx, &receipt)
-                .context("Updating receipt")?;
+        let Some(last) = blocks.last().map(|(header, _)| header.number) else {
+            return Ok(());
+        };
+        for (header, receipts) in blocks {
+            for (transaction_idx, receipt) in receipts.into_iter().enumerate() {
+                db.update_receipt(header.hash, transaction_idx, &receipt)
+                    .context("Updating receipt")?;
+            }
+            // The Bloom filter was stored without any events along with the transactions.
+            db.rebuild_bloom_filters(header.number, header.number)
+                .context("Updating event Bloom filter")?;
         }
+        db.update_sync_checkpoint(SyncStage::Receipts, last)
+            .context("Updating receipts checkpoint")?;
         db.commit().context("Committing database transaction")
     })
     .await
     .context("Joining blocking task")??;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::transaction::{Transaction, TransactionVariant};
+    use pathfinder_common::{EventData, Fee};
+    use pathfinder_crypto::Felt;
+
+    use super::*;
+    use crate::p2p_network::sync_handlers::conv::ToDto;
+
+    /// Serves canned responses and records the requests and penalties of each peer.
+    #[derive(Default)]
+    struct FakePeers {
+        peers: Vec<PeerId>,
+        receipts: HashMap<PeerId, Vec<Receipt>>,
+        events: HashMap<PeerId, Vec<(TransactionHash, Event)>>,
+        receipt_requests: Mutex<Vec<PeerId>>,
+        event_requests: Mutex<Vec<PeerId>>,
+        penalties: Mutex<Vec<(PeerId, Penalty)>>,
+    }
+
+    fn respond<T>(items: Vec<T>) -> Receiver<T> {
+        let (mut sender, receiver) = futures::channel::mpsc::channel(items.len());
+        for item in items {
+            sender.try_send(item).unwrap();
+        }
+        receiver
+    }
+
+    #[async_trait::async_trait]
+    impl Peers for FakePeers {
+        async fn receipt_peers(&self) -> Vec<PeerId> {
+            self.peers.clone()
+        }
+
+        async fn event_peers(&self) -> Vec<PeerId> {
+            self.peers.clone()
+        }
+
+        async fn receipts(
+            &self,
+            peer: PeerId,
+            _: ReceiptsRequest,
+        ) -> anyhow::Result<Receiver<ReceiptsResponse>> {
+            self.receipt_requests.lock().unwrap().push(peer);
+            let transaction = |hash| Transaction {
+                hash,
+                variant: TransactionVariant::InvokeV1(Default::default()),
+            };
+            let responses = self.receipts[&peer]
+                .iter()
+                .map(|r| {
+                    ReceiptsResponse::Receipt((transaction(r.transaction_hash), r.clone()).to_dto())
+                })
+                .chain(std::iter::once(ReceiptsResponse::Fin))
+                .collect();
+            Ok(respond(responses))
+        }
+
+        async fn events(
+            &self,
+            peer: PeerId,
+            _: EventsRequest,
+        ) -> anyhow::Result<Receiver<EventsResponse>> {
+            self.event_requests.lock().unwrap().push(peer);
+            let responses = self.events[&peer]
+                .iter()
+                .map(|event| EventsResponse::Event(event.clone().to_dto()))
+                .chain(std::iter::once(EventsResponse::Fin))
+                .collect();
+            Ok(respond(responses))
+        }
+
+        async fn reward(&self, _: PeerId) {}
+
+        async fn penalize(&self, peer: PeerId, penalty: Penalty) {
+            self.penalties.lock().unwrap().push((peer, penalty));
+        }
+    }
+
+    /// A block with two transactions which emitted an event each, and the block's receipts
+    /// without and with their events.
+    fn block() -> (Block, Vec<Receipt>, Vec<Receipt>) {
+        let receipts = vec![
+            Receipt {
+                transaction_hash: transaction_hash!("0x1"),
+                actual_fee: Some(Fee(felt!("0x10"))),
+                transaction_index: TransactionIndex::new_or_panic(0),
+                ..Default::default()
+            },
+            Receipt {
+                transaction_hash: transaction_hash!("0x2"),
+                actual_fee: Some(Fee(felt!("0x20"))),
+                transaction_index: TransactionIndex::new_or_panic(1),
+                ..Default::default()
+            },
+        ];
+        let with_events = receipts
+            .iter()
+            .cloned()
+            .enumerate()
+            .map(|(i, mut receipt)| {
+                receipt.events.push(Event {
+                    from_address: contract_address!("0x100"),
+                    keys: vec![event_key!("0x200")],
+                    data: vec![event_data!("0x300")],
+                });
+                receipt.events[0]
+                    .data
+                    .push(EventData(Felt::from_u64(i as u64)));
+                receipt
+            })
+            .collect::<Vec<_>>();
+
+        let header = BlockHeader::builder()
+            .with_number(BlockNumber::GENESIS)
+            .with_transaction_count(2)
+            .with_event_count(2)
+            .with_event_commitment(calculate_event_commitment(&with_events).unwrap())
+            .finalize_with_hash(block_hash!("0xabc"));
+        let block = Block {
+            header,
+            transaction_hashes: vec![transaction_hash!("0x1"), transaction_hash!("0x2")],
+        };
+
+        (block, receipts, with_events)
+    }
+
+    fn events(receipts: &[Receipt]) -> Vec<(TransactionHash, Event)> {
+        receipts
+            .iter()
+            .flat_map(|r| r.events.iter().map(|e| (r.transaction_hash, e.clone())))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn reordered_receipts_are_requested_again() {
+        let (block, receipts, _) = block();
+        let (bad, good) = (PeerId::random(), PeerId::random());
+        let peers = FakePeers {
+            peers: vec![bad, good],
+            receipts: HashMap::from([
+                (bad, receipts.iter().rev().cloned().collect()),
+                (good, receipts.clone()),
+            ]),
+            ..Default::default()
+        };
+
+        let fetched = fetch_receipts(&peers, &[block], &[receipts.clone()]).await;
+
+        assert_eq!(fetched, vec![receipts]);
+        assert_eq!(*peers.receipt_requests.lock().unwrap(), vec![bad, good]);
+        assert_eq!(
+            *peers.penalties.lock().unwrap(),
+            vec![(bad, Penalty::InvalidData)]
+        );
+    }
+
+    #[tokio::test]
+    async fn forged_receipts_are_requested_again() {
+        let (block, receipts, _) = block();
+        let mut forged = receipts.clone();
+        forged[1].actual_fee = Some(Fee(felt!("0x1")));
+        let (bad, good) = (PeerId::random(), PeerId::random());
+        let peers = FakePeers {
+            peers: vec![bad, good],
+            receipts: HashMap::from([(bad, forged), (good, receipts.clone())]),
+            ..Default::default()
+        };
+
+        let fetched = fetch_receipts(&peers, &[block], &[receipts.clone()]).await;
+
+        assert_eq!(fetched, vec![receipts]);
+        assert_eq!(*peers.receipt_requests.lock().unwrap(), vec![bad, good]);
+        assert_eq!(
+            *peers.penalties.lock().unwrap(),
+            vec![(bad, Penalty::InvalidData)]
+        );
+    }
+
+    #[tokio::test]
+    async fn events_not_matching_commitment_are_requested_again() {
+        let (block, receipts, with_events) = block();
+        let mut bad_events = events(&with_events);
+        bad_events[0].1.data[0] = event_data!("0xbad");
+        let (bad, good) = (PeerId::random(), PeerId::random());
+        let peers = FakePeers {
+            peers: vec![bad, good],
+            events: HashMap::from([(bad, bad_events), (good, events(&with_events))]),
+            ..Default::default()
+        };
+
+        let fetched = fetch_events(&peers, &[block], vec![receipts])
+            .await
+            .unwrap();
+
+        assert_eq!(fetched, vec![with_events]);
+        assert_eq!(*peers.event_requests.lock().unwrap(), vec![bad, good]);
+        assert_eq!(
+            *peers.penalties.lock().unwrap(),
+            vec![(bad, Penalty::InvalidData)]
+        );
+    }
+}
@@ -0,0 +1,300 @@
+//! Snap-sync of the state at the L1 anchor.
+//!
+//! The state is downloaded as ranges of trie leaves, each of which is proven against the
+//! anchor's state root. The tries are then rebuilt locally from the verified leaves.
+use std::collections::HashMap;
+
+use anyhow::Context;
+use p2p::client::conv::TryFromDto;
+use p2p_proto::snapshot::{SnapshotProof, SnapshotResponse};
+use pathfinder_common::state_update::{ContractClassUpdate, ContractUpdate, SystemContractUpdate};
+use pathfinder_common::trie::TrieNode;
+use pathfinder_common::{
+    calculate_class_commitment_leaf_hashes, BlockHash, CasmHash, ClassCommitment, ClassHash,
+    ContractAddress, ContractNonce, ContractRoot, SierraHash, StateCommitment, StateUpdate,
+    StorageAddress, StorageCommitment, StorageValue,
+};
+use pathfinder_crypto::Felt;
+use pathfinder_ethereum::EthereumStateUpdate;
+use pathfinder_merkle_tree::contract_state::calculate_contract_state_hash;
+use pathfinder_merkle_tree::{ClassCommitmentTree, ContractsStorageTree, StorageCommitmentTree};
+use pathfinder_storage::{Storage, SyncStage};
+use tokio::task::spawn_blocking;
+
+use crate::state::update_starknet_state;
+
+/// The maximum number of trie leaves requested at once.
+pub(super) const RANGE_LIMIT: u32 = 1024;
+
+/// A range of trie leaves which was proven against the anchor's state root.
+pub(super) struct VerifiedRange<T> {
+    pub leaves: Vec<T>,
+    /// Whether the trie contains leaves beyond this range.
+    pub has_more: bool,
+}
+
+/// A leaf of the contracts trie.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct ContractState {
+    pub address: ContractAddress,
+    pub class_hash: ClassHash,
+    pub storage_root: ContractRoot,
+    pub nonce: ContractNonce,
+}
+
+/// The state at the anchor, accumulated from verified ranges until it is complete.
+#[derive(Debug, Default)]
+pub(super) struct State {
+    pub contracts: Vec<ContractState>,
+    pub storage: HashMap<ContractAddress, HashMap<StorageAddress, StorageValue>>,
+    pub classes: HashMap<SierraHash, CasmHash>,
+}
+
+impl State {
+    /// Represents the whole state as the state update of the anchor block.
+    fn into_state_update(self, block_hash: BlockHash, state_root: StateCommitment) -> StateUpdate {
+        let mut storage = self.storage;
+        let mut state_update = StateUpdate {
+            block_hash,
+            state_commitment: state_root,
+            declared_sierra_classes: self.classes,
+            ..Default::default()
+        };
+
+        for contract in self.contracts {
+            let storage = storage.remove(&contract.address).unwrap_or_default();
+            if contract.address == ContractAddress::ONE {
+                // This is a special system contract at address 0x1, which doesn't have a class hash.
+                state_update
+                    .system_contract_updates
+                    .insert(contract.address, SystemContractUpdate { storage });
+            } else {
+                state_update.contract_updates.insert(
+                    contract.address,
+                    ContractUpdate {
+                        storage,
+                        class: Some(ContractClassUpdate::Deploy(contract.class_hash)),
+                        nonce: Some(contract.nonce),
+                    },
+                );
+            }
+        }
+
+        state_update
+    }
+}
+
+/// The commitments and boundary proofs of a range.
+struct CheckedProof {
+    storage_commitment: StorageCommitment,
+    class_commitment: ClassCommitment,
+    start: Vec<TrieNode>,
+    end: Vec<TrieNode>,
+}
+
+/// Checks that the commitments of the proof add up to the anchor's state root, and converts the
+/// boundary proofs of the range.
+fn check_proof(state_root: StateCommitment, proof: SnapshotProof) -> anyhow::Result<CheckedProof> {
+    let storage_commitment = StorageCommitment(proof.storage_commitment.0);
+    let class_commitment = ClassCommitment(proof.class_commitment.0);
+    anyhow::ensure!(
+        StateCommitment::calculate(storage_commitment, class_commitment) == state_root,
+        "Proof commitments do not match the state root"
+    );
+
+    let start = proof
+        .start
+        .into_iter()
+        .map(TrieNode::try_from_dto)
+        .collect::<anyhow::Result<Vec<_>>>()
+        .context("Parsing start proof")?;
+    let end = proof
+        .end
+        .into_iter()
+        .map(TrieNode::try_from_dto)
+        .collect::<anyhow::Result<Vec<_>>>()
+        .context("Parsing end proof")?;
+
+    Ok(CheckedProof {
+        storage_commitment,
+        class_commitment,
+        start,
+        end,
+    })
+}
+
+/// Verifies a range of the contracts trie starting at `start`.
+///
+/// Any error indicates that the peer sent invalid data.
+pub(super) fn verify_contracts(
+    state_root: StateCommitment,
+    start: ContractAddress,
+    leaves: Vec<SnapshotResponse>,
+    proof: SnapshotProof,
+) -> anyhow::Result<VerifiedRange<ContractState>> {
+    let proof = check_proof(state_root, proof)?;
+
+    let contracts = leaves
+        .into_iter()
+        .map(|leaf| match leaf {
+            SnapshotResponse::Contract(contract) => Ok(ContractState {
+                address: ContractAddress(contract.address.0),
+                class_hash: ClassHash(contract.class_hash.0),
+                storage_root: ContractRoot(contract.storage_root.0),
+                nonce: ContractNonce(contract.nonce),
+            }),
+            other => anyhow::bail!("Unexpected response in contracts range: {other:?}"),
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let state_hashes = contracts
+        .iter()
+        .map(|contract| {
+            let state_hash = calculate_contract_state_hash(
+                contract.class_hash,
+                contract.storage_root,
+                contract.nonce,
+            );
+            (contract.address, state_hash)
+        })
+        .collect::<Vec<_>>();
+
+    let has_more = StorageCommitmentTree::verify_range(
+        proof.storage_commitment,
+        start,
+        &state_hashes,
+        &proof.start,
+        &proof.end,
+    )?;
+
+    Ok(VerifiedRange {
+        leaves: contracts,
+        has_more,
+    })
+}
+
+/// Verifies a range of the storage trie of a contract with the given `storage_root`, starting at
+/// `start`.
+///
+/// Any error indicates that the peer sent invalid data.
+pub(super) fn verify_storage(
+    state_root: StateCommitment,
+    storage_root: ContractRoot,
+    start: StorageAddress,
+    leaves: Vec<SnapshotResponse>,
+    proof: SnapshotProof,
+) -> anyhow::Result<VerifiedRange<(StorageAddress, StorageValue)>> {
+    let proof = check_proof(state_root, proof)?;
+
+    let slots = leaves
+        .into_iter()
+        .map(|leaf| match leaf {
+            SnapshotResponse::Storage(slot) => {
+                Ok((StorageAddress(slot.key), StorageValue(slot.value)))
+            }
+            other => anyhow::bail!("Unexpected response in storage range: {other:?}"),
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let has_more =
+        ContractsStorageTree::verify_range(storage_root, start, &slots, &proof.start, &proof.end)?;
+
+    Ok(VerifiedRange {
+        leaves: slots,
+        has_more,
+    })
+}
+
+/// Verifies a range of the classes trie starting at `start`.
+///
+/// Any error indicates that the peer sent invalid data.
+pub(super) fn verify_classes(
+    state_root: StateCommitment,
+    start: SierraHash,
+    leaves: Vec<SnapshotResponse>,
+    proof: SnapshotProof,
+) -> anyhow::Result<VerifiedRange<(SierraHash, CasmHash)>> {
+    let proof = check_proof(state_root, proof)?;
+
+    let classes = leaves
+        .into_iter()
+        .map(|leaf| match leaf {
+            SnapshotResponse::Class(class) => Ok((
+                SierraHash(class.class_hash.0),
+                CasmHash(class.compiled_class_hash.0),
+            )),
+            other => anyhow::bail!("Unexpected response in classes range: {other:?}"),
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let casm_hashes = classes.iter().map(|(_, casm)| *casm).collect::<Vec<_>>();
+    let leaf_hashes = classes
+        .iter()
+        .map(|(sierra, _)| *sierra)
+        .zip(calculate_class_commitment_leaf_hashes(&casm_hashes))
+        .collect::<Vec<_>>();
+
+    let has_more = ClassCommitmentTree::verify_range(
+        proof.class_commitment,
+        start,
+        &leaf_hashes,
+        &proof.start,
+        &proof.end,
+    )?;
+
+    Ok(VerifiedRange {
+        leaves: classes,
+        has_more,
+    })
+}
+
+/// Persists the state as the state update of the anchor block, rebuilds the tries from it and
+/// marks the state updates stage complete up to the anchor.
+pub(super) async fn persist(
+    storage: Storage,
+    anchor: EthereumStateUpdate,
+    state: State,
+) -> anyhow::Result<()> {
+    spawn_blocking(move || {
+        let mut connection = storage
+            .connection()
+            .context("Creating database connection")?;
+        let transaction = connection
+            .transaction()
+            .context("Creating database transaction")?;
+
+        let state_update = state.into_state_update(anchor.block_hash, anchor.state_root);
+        transaction
+            .insert_state_update(anchor.block_number, &state_update)
+            .context("Inserting state update")?;
+
+        // The leaves were verified as they were received, so only the final commitments need to
+        // be checked.
+        let (storage_commitment, class_commitment) = update_starknet_state(
+            &transaction,
+            &state_update,
+            false,
+            anchor.block_number,
+            storage.clone(),
+        )
+        .context("Updating Starknet state")?;
+        anyhow::ensure!(
+            StateCommitment::calculate(storage_commitment, class_commitment) == anchor.state_root,
+            "State root mismatch"
+        );
+
+        transaction
+            .update_sync_checkpoint(SyncStage::StateUpdates, anchor.block_number)
+            .context("Updating state updates checkpoint")?;
+        transaction
+            .commit()
+            .context("Committing database transaction")
+    })
+    .await
+    .context("Joining blocking task")?
+}
+
+/// Returns the key following `key`, at which the next range starts.
+pub(super) fn next_key(key: Felt) -> Felt {
+    key + Felt::from_u64(1)
+}
@@ -11,7 +11,7 @@ use pathfinder_merkle_tree::{
     contract_state::{update_contract_state, ContractStateUpdateResult},
     StorageCommitmentTree,
 };
-use pathfinder_storage::{Node, Storage};
+use pathfinder_storage::{Node, Storage, SyncStage};
 use tokio::task::spawn_blocking;
 
 #[derive(Debug, thiserror::Error)]
@@ -35,10 +35,18 @@ pub(super) async fn next_missing(
             .context("Creating database connection")?;
         let db = db.transaction().context("Creating database transaction")?;
 
-        if let Some(highest) = db
-            .highest_block_with_state_update()
-            .context("Querying highest block with state update")?
+        let highest = match db
+            .sync_checkpoint(SyncStage::StateUpdates)
+            .context("Querying state updates checkpoint")?
         {
+            Some(checkpoint) => Some(checkpoint),
+            // The database predates sync checkpoints.
+            None => db
+                .highest_block_with_state_update()
+                .context("Querying highest block with state update")?,
+        };
+
+        if let Some(highest) = highest {
             Ok((highest < head).then_some(highest + 1))
         } else {
             Ok(Some(BlockNumber::GENESIS))
@@ -92,12 +100,53 @@ pub(super) async fn persist(
                 .context("Inserting state update")?;
         }
 
+        transaction
+            .update_sync_checkpoint(SyncStage::StateUpdates, tail)
+            .context("Updating state updates checkpoint")?;
+        transaction
+            .commit()
+            .context("Committing database transaction")?;
+
         Ok(tail)
     })
     .await
     .context("Joining blocking task")?
 }
 
+/// Persists the state updates of consecutive blocks, which were verified by the caller, and
+/// marks the stage complete up to the last block.
+pub(super) async fn persist_verified(
+    storage: Storage,
+    state_updates: Vec<(BlockNumber, StateUpdate)>,
+) -> anyhow::Result<()> {
+    spawn_blocking(move || {
+        let mut connection = storage
+            .connection()
+            .context("Creating database connection")?;
+        let transaction = connection
+            .transaction()
+            .context("Creating database transaction")?;
+        let Some(tail) = state_updates.last().map(|(number, _)| *number) else {
+            return Ok(());
+        };
+
+        for (block_number, state_update) in state_updates {
+            transaction
+                .insert_state_update(block_number, &state_update)
+                .context("Inserting state update")?;
+        }
+
+        transaction
+            .update_sync_checkpoint(SyncStage::StateUpdates, tail)
+            .context("Updating state updates checkpoint")?;
+        transaction
+            .commit()
+            .context("Committing database transaction")
+    })
+    .await
+    .context("Joining blocking task")?
+}
+
 #[derive(Debug)]
 pub(super) struct VerificationOk {
     block_number: BlockNumber,
@@ -275,3 +324,62 @@ fn verify_one(
         },
     ))
 }
+
+#[cfg(test)]
+mod tests {
+    use p2p::libp2p::PeerId;
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::state_update::ContractUpdate;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn persist_commits_state_updates() {
+        let storage = Storage::in_memory().unwrap();
+        let header = BlockHeader::builder()
+            .with_number(BlockNumber::GENESIS)
+            .finalize_with_hash(block_hash!("0xabc"));
+        {
+            let mut db = storage.connection().unwrap();
+            let db = db.transaction().unwrap();
+            db.insert_block_header(&header).unwrap();
+            db.commit().unwrap();
+        }
+
+        let contract_updates = ContractUpdates {
+            regular: HashMap::from([(
+                contract_address!("0x1"),
+                ContractUpdate {
+                    storage: HashMap::from([(storage_address!("0x2"), storage_value!("0x3"))]),
+                    ..Default::default()
+                },
+            )]),
+            ..Default::default()
+        };
+
+        let tail = persist(
+            storage.clone(),
+            vec![PeerData::new(
+                PeerId::random(),
+                (BlockNumber::GENESIS, contract_updates.clone()),
+            )],
+        )
+        .await
+        .unwrap();
+        assert_eq!(tail, BlockNumber::GENESIS);
+
+        // The state update must be visible to a new connection.
+        let mut db = storage.connection().unwrap();
+        let db = db.transaction().unwrap();
+        let state_update = db
+            .state_update(BlockNumber::GENESIS.into())
+            .unwrap()
+            .unwrap();
+        assert_eq!(state_update.block_hash, header.hash);
+        assert_eq!(state_update.contract_updates, contract_updates.regular);
+        assert_eq!(
+            db.sync_checkpoint(SyncStage::StateUpdates).unwrap(),
+            Some(BlockNumber::GENESIS)
+        );
+    }
+}
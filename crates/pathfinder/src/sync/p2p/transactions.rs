@@ -1,6 +1,6 @@
 use anyhow::Context;
 use pathfinder_common::{transaction::Transaction, BlockHeader};
-use pathfinder_storage::Storage;
+use pathfinder_storage::{Storage, SyncStage};
 use tokio::task::spawn_blocking;
 
 pub(super) async fn persist(
@@ -22,6 +22,8 @@ pub(super) async fn persist(
                 .collect::<Vec<_>>(),
         )
         .context("Inserting transactions")?;
+        db.update_sync_checkpoint(SyncStage::Transactions, block.number)
+            .context("Updating transactions checkpoint")?;
         db.commit().context("Committing database transaction")
     })
     .await
@@ -0,0 +1,100 @@
+//! Background checkpointing of the SQLite WAL.
+//!
+//! By default SQLite checkpoints the WAL as part of the commit which grows it past its size
+//! limit. For large blocks this stalls the sync pipeline's commit for seconds while the WAL is
+//! copied into the database and synced to disk. Instead, automatic checkpoints can be disabled
+//! and the WAL checkpointed by [run] outside of any commit.
+//!
+//! In WAL mode commits are not synced to disk themselves, so the checkpoints are also where the
+//! syncs of all commits since the previous checkpoint are grouped.
+//!
+//! Checkpoints are passive, so that they neither wait for nor block readers and writers. Since
+//! SQLite reuses the WAL file from its start after a passive checkpoint instead of shrinking it,
+//! checkpoints triggered by the WAL size are truncating, which keeps the file size an accurate
+//! measure of the frames yet to be checkpointed. Truncating checkpoints are also used when
+//! passive checkpoints keep being held back by long-running readers.
+use std::time::Duration;
+
+use pathfinder_storage::{Storage, WalCheckpointMode};
+use tokio::time::Instant;
+
+const METRIC_DURATION: &str = "storage_wal_checkpoint_duration_seconds";
+
+/// How often the size of the WAL is checked against [Config::size_threshold].
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The number of consecutive incomplete passive checkpoints after which a truncating checkpoint
+/// is performed instead.
+const MAX_INCOMPLETE_CHECKPOINTS: usize = 3;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Config {
+    /// The time between checkpoints.
+    pub interval: Duration,
+    /// The WAL size in bytes above which a checkpoint is performed before the interval is up.
+    pub size_threshold: u64,
+}
+
+/// Checkpoints the WAL of `storage` forever.
+///
+/// `storage` should come from a [StorageManager](pathfinder_storage::StorageManager) on which
+/// automatic checkpoints have been
+/// [disabled](pathfinder_storage::StorageManager::without_wal_autocheckpoint).
+pub async fn run(storage: Storage, config: Config) {
+    let mut last_checkpoint = Instant::now();
+    // The size of the WAL file after the last checkpoint. A truncating checkpoint can fail to
+    // shrink the file if readers are still using it, in which case the size only triggers
+    // another checkpoint once frames are appended to the file.
+    let mut checkpointed_size = 0;
+    let mut incomplete_checkpoints = 0;
+    let mut poll = tokio::time::interval(POLL_INTERVAL);
+    poll.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    loop {
+        poll.tick().await;
+
+        let wal_size = storage.wal_size();
+        let oversized = wal_size >= config.size_threshold && wal_size > checkpointed_size;
+        if last_checkpoint.elapsed() < config.interval && !oversized {
+            continue;
+        }
+
+        let mode = if oversized || incomplete_checkpoints >= MAX_INCOMPLETE_CHECKPOINTS {
+            WalCheckpointMode::Truncate
+        } else {
+            WalCheckpointMode::Passive
+        };
+
+        let started = std::time::Instant::now();
+        let result = tokio::task::spawn_blocking({
+            let storage = storage.clone();
+            move || storage.checkpoint_wal(mode)
+        })
+        .await;
+        last_checkpoint = Instant::now();
+        checkpointed_size = storage.wal_size();
+
+        match result {
+            Ok(Ok(checkpoint)) => {
+                if checkpoint.is_complete() {
+                    incomplete_checkpoints = 0;
+                } else {
+                    incomplete_checkpoints += 1;
+                }
+
+                let elapsed = started.elapsed();
+                metrics::histogram!(METRIC_DURATION, elapsed.as_secs_f64());
+                tracing::debug!(
+                    %wal_size,
+                    ?mode,
+                    wal_frames=%checkpoint.wal_frames,
+                    checkpointed_frames=%checkpoint.checkpointed_frames,
+                    ?elapsed,
+                    "WAL checkpointed"
+                );
+            }
+            Ok(Err(error)) => tracing::warn!(?error, "Checkpointing WAL failed"),
+            Err(error) => tracing::warn!(%error, "Checkpointing WAL panicked"),
+        }
+    }
+}
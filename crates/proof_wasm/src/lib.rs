@@ -0,0 +1,108 @@
+//! WebAssembly bindings for verifying the storage proofs served by `pathfinder_getProof`, along
+//! with the Pedersen and Poseidon hash functions.
+//!
+//! Felts are passed to and returned from JavaScript as hex strings, and proofs as the JSON
+//! returned by `pathfinder_getProof`.
+use pathfinder_common::{ContractAddress, StateCommitment, StorageAddress, StorageValue};
+use pathfinder_crypto::{hash, Felt};
+use pathfinder_merkle_tree::tree::Membership;
+use wasm_bindgen::prelude::*;
+
+mod proof;
+
+pub use proof::{verify_get_proof, verify_proof, EdgePath, GetProofOutput, ProofNode};
+
+fn parse_felt(name: &str, value: &str) -> Result<Felt, JsError> {
+    Felt::from_hex_str(value).map_err(|e| JsError::new(&format!("Invalid {name}: {e}")))
+}
+
+fn parse_json<'a, T: serde::Deserialize<'a>>(name: &str, value: &'a str) -> Result<T, JsError> {
+    serde_json::from_str(value).map_err(|e| JsError::new(&format!("Invalid {name}: {e}")))
+}
+
+fn to_js_error(error: anyhow::Error) -> JsError {
+    JsError::new(&format!("{error:#}"))
+}
+
+/// Computes the Pedersen hash of two felts.
+#[wasm_bindgen(js_name = pedersenHash)]
+pub fn pedersen_hash(a: &str, b: &str) -> Result<String, JsError> {
+    let hash = hash::pedersen_hash(parse_felt("a", a)?, parse_felt("b", b)?);
+
+    Ok(hash.to_hex_str().into_owned())
+}
+
+/// Computes the Poseidon hash of two felts.
+#[wasm_bindgen(js_name = poseidonHash)]
+pub fn poseidon_hash(a: &str, b: &str) -> Result<String, JsError> {
+    let a = parse_felt("a", a)?;
+    let b = parse_felt("b", b)?;
+
+    let hash: Felt = hash::poseidon_hash(a.into(), b.into()).into();
+
+    Ok(hash.to_hex_str().into_owned())
+}
+
+/// Computes the Poseidon hash of any number of felts.
+#[wasm_bindgen(js_name = poseidonHashMany)]
+pub fn poseidon_hash_many(values: Vec<String>) -> Result<String, JsError> {
+    let values = values
+        .iter()
+        .map(|value| parse_felt("value", value).map(Into::into))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let hash: Felt = hash::poseidon_hash_many(&values).into();
+
+    Ok(hash.to_hex_str().into_owned())
+}
+
+/// Verifies a proof of `key` from the global storage trie or a contract's storage trie with
+/// the given `root`.
+///
+/// Returns `true` if the trie contains `key` with `value`, and `false` if the trie does not
+/// contain `key`. Throws if the proof is invalid.
+#[wasm_bindgen(js_name = verifyProof)]
+pub fn verify_proof_js(root: &str, key: &str, value: &str, proof: &str) -> Result<bool, JsError> {
+    let root = parse_felt("root", root)?;
+    let key = parse_felt("key", key)?;
+    let value = parse_felt("value", value)?;
+    let proof: Vec<ProofNode> = parse_json("proof", proof)?;
+
+    let membership = verify_proof(root, key, value, &proof).map_err(to_js_error)?;
+
+    Ok(membership == Membership::Member)
+}
+
+/// Verifies that the JSON `output` of `pathfinder_getProof` proves that the storage `keys` of
+/// `contractAddress` have the given `values` in the state with the trusted `stateCommitment`.
+///
+/// Slots which are not set have a zero value. Throws if the proof is invalid.
+#[wasm_bindgen(js_name = verifyGetProof)]
+pub fn verify_get_proof_js(
+    output: &str,
+    state_commitment: &str,
+    contract_address: &str,
+    keys: Vec<String>,
+    values: Vec<String>,
+) -> Result<(), JsError> {
+    if keys.len() != values.len() {
+        return Err(JsError::new("Keys and values differ in length"));
+    }
+
+    let output: GetProofOutput = parse_json("output", output)?;
+    let state_commitment = StateCommitment(parse_felt("state commitment", state_commitment)?);
+    let contract = ContractAddress::new(parse_felt("contract address", contract_address)?)
+        .ok_or_else(|| JsError::new("Contract address is longer than 251 bits"))?;
+    let slots = keys
+        .iter()
+        .zip(&values)
+        .map(|(key, value)| {
+            let key = StorageAddress::new(parse_felt("key", key)?)
+                .ok_or_else(|| JsError::new("Key is longer than 251 bits"))?;
+            let value = StorageValue(parse_felt("value", value)?);
+            Ok((key, value))
+        })
+        .collect::<Result<Vec<_>, JsError>>()?;
+
+    verify_get_proof(&output, state_commitment, contract, &slots).map_err(to_js_error)
+}
@@ -0,0 +1,292 @@
+//! Verification of `pathfinder_getProof` responses.
+
+use anyhow::Context;
+use pathfinder_common::hash::PedersenHash;
+use pathfinder_common::trie::TrieNode;
+use pathfinder_common::{
+    ClassCommitment, ClassHash, ContractAddress, ContractNonce, ContractRoot, StateCommitment,
+    StorageAddress, StorageCommitment, StorageValue,
+};
+use pathfinder_crypto::Felt;
+use pathfinder_merkle_tree::contract_state::calculate_contract_state_hash;
+use pathfinder_merkle_tree::tree::{Membership, MerkleTree};
+use serde::Deserialize;
+
+/// The global storage trie and the contract storage tries share the same shape.
+type PedersenTree = MerkleTree<PedersenHash, 251>;
+
+/// The output of `pathfinder_getProof`.
+#[derive(Debug, Deserialize)]
+pub struct GetProofOutput {
+    state_commitment: Option<StateCommitment>,
+    class_commitment: Option<ClassCommitment>,
+    contract_proof: Vec<ProofNode>,
+    contract_data: Option<ContractData>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ContractData {
+    class_hash: ClassHash,
+    nonce: ContractNonce,
+    root: ContractRoot,
+    contract_state_hash_version: Felt,
+    storage_proofs: Vec<Vec<ProofNode>>,
+}
+
+/// A trie node as serialized by `pathfinder_getProof`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProofNode {
+    Binary { left: Felt, right: Felt },
+    Edge { path: EdgePath, child: Felt },
+}
+
+/// The path of an edge node, as the number formed by its `len` bits.
+#[derive(Debug, Deserialize)]
+pub struct EdgePath {
+    value: Felt,
+    len: usize,
+}
+
+impl ProofNode {
+    fn to_trie_node(&self) -> anyhow::Result<TrieNode> {
+        match self {
+            ProofNode::Binary { left, right } => Ok(TrieNode::Binary {
+                left: *left,
+                right: *right,
+            }),
+            ProofNode::Edge { path, child } => {
+                anyhow::ensure!(path.len <= 251, "Edge path is longer than 251 bits");
+                anyhow::ensure!(
+                    !path.value.has_more_than_251_bits(),
+                    "Edge path value is longer than 251 bits"
+                );
+
+                let (prefix, bits) = path.value.view_bits().split_at(251 - path.len);
+                anyhow::ensure!(prefix.not_any(), "Edge path value exceeds its length");
+
+                Ok(TrieNode::Edge {
+                    child: *child,
+                    path: bits.to_bitvec(),
+                })
+            }
+        }
+    }
+}
+
+fn trie_nodes(proof: &[ProofNode]) -> anyhow::Result<Vec<TrieNode>> {
+    proof.iter().map(ProofNode::to_trie_node).collect()
+}
+
+/// Verifies a proof of `key` from the global storage trie or a contract's storage trie with the
+/// given `root`, and returns whether the trie contains `key` with `value`.
+pub fn verify_proof(
+    root: Felt,
+    key: Felt,
+    value: Felt,
+    proof: &[ProofNode],
+) -> anyhow::Result<Membership> {
+    anyhow::ensure!(!key.has_more_than_251_bits(), "Key is longer than 251 bits");
+
+    let proof = trie_nodes(proof)?;
+    PedersenTree::verify_proof(root, key.view_bits(), value, &proof)
+        .context("Proof does not match the root, key and value")
+}
+
+/// Verifies that `output` proves the storage `slots` of `contract` in the state with the trusted
+/// `state_commitment`, which is typically taken from a block header.
+///
+/// `slots` must be in the order of the keys passed to `pathfinder_getProof`. Slots which are not
+/// set have a zero value.
+pub fn verify_get_proof(
+    output: &GetProofOutput,
+    state_commitment: StateCommitment,
+    contract: ContractAddress,
+    slots: &[(StorageAddress, StorageValue)],
+) -> anyhow::Result<()> {
+    if let Some(claimed) = output.state_commitment {
+        anyhow::ensure!(
+            claimed == state_commitment,
+            "Proof is for state commitment {claimed} instead of {state_commitment}"
+        );
+    }
+
+    // The first node of the contract proof is the root of the global storage trie.
+    let contract_proof = trie_nodes(&output.contract_proof)?;
+    let storage_commitment = contract_proof
+        .first()
+        .map(TrieNode::hash::<PedersenHash>)
+        .unwrap_or_default();
+    let class_commitment = output.class_commitment.unwrap_or_default();
+    anyhow::ensure!(
+        StateCommitment::calculate(StorageCommitment(storage_commitment), class_commitment)
+            == state_commitment,
+        "Contract proof does not match the state commitment"
+    );
+
+    let Some(data) = &output.contract_data else {
+        let membership = PedersenTree::verify_proof(
+            storage_commitment,
+            contract.view_bits(),
+            Felt::ZERO,
+            &contract_proof,
+        );
+        anyhow::ensure!(
+            membership == Some(Membership::NonMember),
+            "Invalid proof of the contract's absence"
+        );
+        anyhow::ensure!(
+            slots.iter().all(|(_, value)| value.0 == Felt::ZERO),
+            "Contract does not exist, so its storage is empty"
+        );
+        return Ok(());
+    };
+
+    anyhow::ensure!(
+        data.contract_state_hash_version == Felt::ZERO,
+        "Unsupported contract state hash version {}",
+        data.contract_state_hash_version
+    );
+    let state_hash = calculate_contract_state_hash(data.class_hash, data.root, data.nonce);
+    let membership = PedersenTree::verify_proof(
+        storage_commitment,
+        contract.view_bits(),
+        state_hash.0,
+        &contract_proof,
+    );
+    anyhow::ensure!(
+        membership == Some(Membership::Member),
+        "Invalid proof of the contract's state"
+    );
+
+    anyhow::ensure!(
+        data.storage_proofs.len() == slots.len(),
+        "Expected {} storage proofs but got {}",
+        slots.len(),
+        data.storage_proofs.len()
+    );
+    for ((key, value), proof) in slots.iter().zip(&data.storage_proofs) {
+        let expected = if value.0 == Felt::ZERO {
+            Membership::NonMember
+        } else {
+            Membership::Member
+        };
+        let membership = verify_proof(data.root.0, key.0, value.0, proof)
+            .with_context(|| format!("Verifying proof of storage slot {}", key.0))?;
+        anyhow::ensure!(
+            membership == expected,
+            "Invalid proof of storage slot {}",
+            key.0
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::BlockNumber;
+    use pathfinder_merkle_tree::{
+        ContractsStorageTree, InMemoryTrieStorage, StorageCommitmentTree, TrieStorage,
+    };
+    use serde_json::json;
+
+    use super::*;
+
+    /// Serializes the proof as `pathfinder_getProof` does.
+    fn to_json(proof: &[TrieNode]) -> serde_json::Value {
+        proof
+            .iter()
+            .map(|node| match node {
+                TrieNode::Binary { left, right } => json!({
+                    "binary": { "left": left, "right": right }
+                }),
+                TrieNode::Edge { child, path } => json!({
+                    "edge": {
+                        "path": { "value": Felt::from_bits(path).unwrap(), "len": path.len() },
+                        "child": child,
+                    }
+                }),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn get_proof() {
+        let contract = contract_address!("0x123");
+        let missing_contract = contract_address!("0x456");
+        let key = storage_address!("0x1");
+        let value = storage_value!("0x99");
+        let missing_key = storage_address!("0x2");
+        let class_hash = class_hash!("0xabc");
+        let nonce = contract_nonce!("0x1");
+        let block = BlockNumber::GENESIS;
+
+        let contract_storage = InMemoryTrieStorage::default();
+        let mut tree = ContractsStorageTree::empty_with(&contract_storage);
+        tree.set(key, value).unwrap();
+        let (contract_root, nodes) = tree.commit().unwrap();
+        let index = contract_storage.insert(contract_root.0, &nodes).unwrap();
+        contract_storage.set_root(block, Some(index));
+
+        let state_hash = calculate_contract_state_hash(class_hash, contract_root, nonce);
+        let global_storage = InMemoryTrieStorage::default();
+        let mut tree = StorageCommitmentTree::empty_with(&global_storage);
+        tree.set(contract, state_hash).unwrap();
+        let (storage_commitment, nodes) = tree.commit().unwrap();
+        let index = global_storage.insert(storage_commitment.0, &nodes).unwrap();
+        global_storage.set_root(block, Some(index));
+
+        let state_commitment =
+            StateCommitment::calculate(storage_commitment, ClassCommitment::ZERO);
+
+        let storage_proof =
+            ContractsStorageTree::get_proof_with(&contract_storage, block, key.view_bits())
+                .unwrap();
+        let missing_storage_proof =
+            ContractsStorageTree::get_proof_with(&contract_storage, block, missing_key.view_bits())
+                .unwrap();
+        let output = json!({
+            "contract_proof": to_json(
+                &StorageCommitmentTree::get_proof_with(&global_storage, block, &contract).unwrap()
+            ),
+            "contract_data": {
+                "class_hash": class_hash,
+                "nonce": nonce,
+                "root": contract_root,
+                "contract_state_hash_version": Felt::ZERO,
+                "storage_proofs": [to_json(&storage_proof), to_json(&missing_storage_proof)],
+            },
+        });
+        let output: GetProofOutput = serde_json::from_value(output).unwrap();
+
+        let slots = [(key, value), (missing_key, StorageValue::ZERO)];
+        verify_get_proof(&output, state_commitment, contract, &slots).unwrap();
+
+        let wrong_value = [
+            (key, storage_value!("0x98")),
+            (missing_key, StorageValue::ZERO),
+        ];
+        verify_get_proof(&output, state_commitment, contract, &wrong_value).unwrap_err();
+
+        let wrong_state = StateCommitment(felt!("0x1"));
+        verify_get_proof(&output, wrong_state, contract, &slots).unwrap_err();
+
+        let output = json!({
+            "contract_proof": to_json(
+                &StorageCommitmentTree::get_proof_with(&global_storage, block, &missing_contract)
+                    .unwrap()
+            ),
+        });
+        let output: GetProofOutput = serde_json::from_value(output).unwrap();
+        verify_get_proof(
+            &output,
+            state_commitment,
+            missing_contract,
+            &[(key, StorageValue::ZERO)],
+        )
+        .unwrap();
+        verify_get_proof(&output, state_commitment, missing_contract, &[(key, value)]).unwrap_err();
+    }
+}
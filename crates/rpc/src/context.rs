@@ -1,12 +1,16 @@
-pub use crate::jsonrpc::websocket::WebsocketContext;
+use crate::executor::ExecutionPool;
+pub use crate::jsonrpc::websocket::{DropPolicy, QueueLimit, WebsocketContext};
+use crate::local_sequencer::LocalSequencer;
+use crate::mempool::Mempool;
+use crate::peer_admin::PeerAdmin;
 use crate::pending::PendingData;
 use crate::pending::PendingWatcher;
 use crate::SyncState;
 use pathfinder_common::ChainId;
-use pathfinder_executor::TraceCache;
+use pathfinder_executor::{CallCache, ForkedState, TraceCache};
 use pathfinder_storage::Storage;
 use starknet_gateway_client::test_utils::GATEWAY_TIMEOUT;
-use std::num::NonZeroUsize;
+use std::num::{NonZeroU64, NonZeroUsize};
 use std::sync::Arc;
 
 type SequencerClient = starknet_gateway_client::Client;
@@ -15,13 +19,49 @@ use tokio::sync::watch as tokio_watch;
 #[derive(Clone)]
 pub struct RpcConfig {
     pub batch_concurrency_limit: NonZeroUsize,
+    /// The maximum number of requests in a single batch.
+    pub batch_size_limit: NonZeroUsize,
     pub get_events_max_blocks_to_scan: NonZeroUsize,
     pub get_events_max_uncached_bloom_filters_to_load: NonZeroUsize,
+    /// The maximum page size of `starknet_getEvents`.
+    pub get_events_max_page_size: NonZeroUsize,
+    /// The maximum number of keys in a `starknet_getEvents` filter, which must not exceed
+    /// [pathfinder_storage::EVENT_KEY_FILTER_LIMIT].
+    pub get_events_max_keys_in_filter: NonZeroUsize,
+    /// The maximum estimated cost of a `starknet_getEvents` query, as the number of Bloom
+    /// filters it would check, see [pathfinder_storage::Transaction::event_query_cost].
+    pub get_events_max_query_cost: Option<NonZeroU64>,
+    /// The maximum number of Cairo steps a call, or a transaction being estimated or
+    /// simulated, may execute.
+    pub execution_max_steps: Option<u32>,
+    /// The time after which calls, fee estimations and simulations are abandoned.
+    pub execution_timeout: Option<std::time::Duration>,
+    /// The number of threads executing calls, fee estimations, simulations and traces.
+    pub execution_concurrency: NonZeroUsize,
+    /// The maximum number of executions which are queued or running at once.
+    pub execution_queue_size: NonZeroUsize,
+    /// Whether `pathfinder_getErc20Balances` is enabled.
+    pub erc20_balances: bool,
+    /// Whether `pathfinder_query` is enabled. The method only exists if built with the `query`
+    /// feature.
+    pub query: bool,
+    /// Whether `pathfinder_getStateStats` may return the size of each table, which reads the
+    /// whole database.
+    pub table_sizes: bool,
+    /// Whether fees estimated for the pending block use at least the latest Ethereum gas prices
+    /// observed by L1 sync.
+    pub l1_gas_price_floor: bool,
+    /// Whether the function invocations of traces include a non-standard `profile`.
+    pub trace_profiles: bool,
+    /// The maximum number of signature elements of deploy account transactions, which are
+    /// rejected without contacting the gateway if their signature is longer.
+    pub max_signature_length: Option<NonZeroUsize>,
 }
 
 #[derive(Clone)]
 pub struct RpcContext {
     pub cache: TraceCache,
+    pub call_cache: CallCache,
     pub storage: Storage,
     pub execution_storage: Storage,
     pub pending_data: PendingWatcher,
@@ -29,6 +69,17 @@ pub struct RpcContext {
     pub chain_id: ChainId,
     pub sequencer: SequencerClient,
     pub websocket: Option<WebsocketContext>,
+    pub execution_pool: ExecutionPool,
+    /// Transactions submitted through this node.
+    pub mempool: Mempool,
+    /// Only set if peer administration through RPC is enabled.
+    pub peer_admin: Option<PeerAdmin>,
+    /// Only set in devnet mode, in which submitted transactions are sequenced by this node
+    /// instead of being forwarded to the gateway.
+    pub local_sequencer: Option<LocalSequencer>,
+    /// Only set if the devnet is forked from another network, whose state is used for anything
+    /// the local database does not have.
+    pub fork: Option<Arc<dyn ForkedState>>,
     pub config: RpcConfig,
 }
 
@@ -43,8 +94,11 @@ impl RpcContext {
         config: RpcConfig,
     ) -> Self {
         let pending_data = PendingWatcher::new(pending_data);
+        let execution_pool =
+            ExecutionPool::new(config.execution_concurrency, config.execution_queue_size);
         Self {
             cache: Default::default(),
+            call_cache: Default::default(),
             storage,
             execution_storage,
             sync_status,
@@ -52,6 +106,11 @@ impl RpcContext {
             pending_data,
             sequencer,
             websocket: None,
+            execution_pool,
+            mempool: Default::default(),
+            peer_admin: None,
+            local_sequencer: None,
+            fork: None,
             config,
         }
     }
@@ -89,8 +148,26 @@ impl RpcContext {
 
         let config = RpcConfig {
             batch_concurrency_limit: NonZeroUsize::new(8).unwrap(),
+            batch_size_limit: NonZeroUsize::new(1000).unwrap(),
             get_events_max_blocks_to_scan: NonZeroUsize::new(1000).unwrap(),
             get_events_max_uncached_bloom_filters_to_load: NonZeroUsize::new(1000).unwrap(),
+            get_events_max_page_size: NonZeroUsize::new(pathfinder_storage::EVENT_PAGE_SIZE_LIMIT)
+                .unwrap(),
+            get_events_max_keys_in_filter: NonZeroUsize::new(
+                pathfinder_storage::EVENT_KEY_FILTER_LIMIT,
+            )
+            .unwrap(),
+            get_events_max_query_cost: None,
+            execution_max_steps: None,
+            execution_timeout: None,
+            execution_concurrency: NonZeroUsize::new(2).unwrap(),
+            execution_queue_size: NonZeroUsize::new(16).unwrap(),
+            erc20_balances: false,
+            query: false,
+            table_sizes: false,
+            l1_gas_price_floor: false,
+            trace_profiles: false,
+            max_signature_length: None,
         };
 
         Self::new(
@@ -138,4 +215,25 @@ impl RpcContext {
             ..self
         }
     }
+
+    pub fn with_peer_admin(self, peer_admin: PeerAdmin) -> Self {
+        Self {
+            peer_admin: Some(peer_admin),
+            ..self
+        }
+    }
+
+    pub fn with_local_sequencer(self, local_sequencer: LocalSequencer) -> Self {
+        Self {
+            local_sequencer: Some(local_sequencer),
+            ..self
+        }
+    }
+
+    pub fn with_fork(self, fork: Arc<dyn ForkedState>) -> Self {
+        Self {
+            fork: Some(fork),
+            ..self
+        }
+    }
 }
@@ -3,6 +3,7 @@
 //! In addition, it supplies the [generate_rpc_error_subset!] macro which should be used
 //! by each JSON-RPC method to trivially create its subset of [ApplicationError] along with the boilerplate involved.
 #![macro_use]
+use pathfinder_common::ClassHash;
 use serde_json::json;
 
 #[derive(serde::Serialize, Clone, Copy, Debug)]
@@ -27,7 +28,10 @@ pub enum ApplicationError {
     #[error("Invalid block hash")]
     InvalidBlockHash,
     #[error("Class hash not found")]
-    ClassHashNotFound,
+    ClassHashNotFound {
+        /// The missing class hash, if known.
+        class_hash: Option<ClassHash>,
+    },
     #[error("Transaction hash not found")]
     TxnHashNotFound,
     #[error("Requested page size is too big")]
@@ -76,6 +80,34 @@ pub enum ApplicationError {
     UnexpectedError { data: String },
     #[error("Too many storage keys requested")]
     ProofLimitExceeded { limit: u32, requested: u32 },
+    #[error("Too many state changes between the requested blocks")]
+    StateDiffLimitExceeded { limit: u32 },
+    #[error("Message not found")]
+    MessageNotFound,
+    #[error("Execution timed out")]
+    ExecutionTimeout,
+    #[error("Merkle trie proof is not available")]
+    ProofMissing,
+    #[error("P2P peer administration is disabled")]
+    PeerAdminDisabled,
+    #[error("Too many storage reads requested")]
+    StorageReadLimitExceeded { limit: u32, requested: u32 },
+    #[error("ERC20 balance queries are disabled")]
+    Erc20BalancesDisabled,
+    #[error("Block data below the trusted block is not available yet")]
+    BlockDataNotAvailable,
+    #[error("Execution step limit exceeded")]
+    ExecutionStepLimitExceeded,
+    #[error("Max fee exceeds 128 bits")]
+    InvalidMaxFee,
+    #[error("Invalid call data")]
+    InvalidCallData,
+    #[error("Invalid storage key")]
+    InvalidStorageKey,
+    #[error("pathfinder_query is disabled")]
+    QueryDisabled,
+    #[error("Table sizes are disabled")]
+    TableSizesDisabled,
     #[error("Internal error")]
     GatewayError(starknet_gateway_types::error::StarknetError),
     #[error("Transaction execution error")]
@@ -106,7 +138,7 @@ impl ApplicationError {
             ApplicationError::InvalidTxnHash => 25,
             ApplicationError::InvalidBlockHash => 26,
             ApplicationError::InvalidTxnIndex => 27,
-            ApplicationError::ClassHashNotFound => 28,
+            ApplicationError::ClassHashNotFound { .. } => 28,
             ApplicationError::TxnHashNotFound => 29,
             ApplicationError::PageSizeTooBig => 31,
             ApplicationError::NoBlocks => 32,
@@ -131,6 +163,20 @@ impl ApplicationError {
             ApplicationError::UnexpectedError { .. } => 63,
             // doc/rpc/pathfinder_rpc_api.json
             ApplicationError::ProofLimitExceeded { .. } => 10000,
+            ApplicationError::StateDiffLimitExceeded { .. } => 10001,
+            ApplicationError::MessageNotFound => 10002,
+            ApplicationError::ExecutionTimeout => 10003,
+            ApplicationError::ProofMissing => 10004,
+            ApplicationError::PeerAdminDisabled => 10005,
+            ApplicationError::StorageReadLimitExceeded { .. } => 10006,
+            ApplicationError::Erc20BalancesDisabled => 10007,
+            ApplicationError::BlockDataNotAvailable => 10008,
+            ApplicationError::ExecutionStepLimitExceeded => 10009,
+            ApplicationError::InvalidMaxFee => 10010,
+            ApplicationError::InvalidCallData => 10011,
+            ApplicationError::InvalidStorageKey => 10012,
+            ApplicationError::QueryDisabled => 10013,
+            ApplicationError::TableSizesDisabled => 10014,
             // https://www.jsonrpc.org/specification#error_object
             ApplicationError::GatewayError(_)
             | ApplicationError::Internal(_)
@@ -149,8 +195,19 @@ impl ApplicationError {
             ApplicationError::InvalidTxnIndex => None,
             ApplicationError::InvalidTxnHash => None,
             ApplicationError::InvalidBlockHash => None,
-            ApplicationError::ClassHashNotFound => None,
             ApplicationError::TxnHashNotFound => None,
+            ApplicationError::MessageNotFound => None,
+            ApplicationError::ExecutionTimeout => None,
+            ApplicationError::ProofMissing => None,
+            ApplicationError::PeerAdminDisabled => None,
+            ApplicationError::Erc20BalancesDisabled => None,
+            ApplicationError::BlockDataNotAvailable => None,
+            ApplicationError::ExecutionStepLimitExceeded => None,
+            ApplicationError::InvalidMaxFee => None,
+            ApplicationError::InvalidCallData => None,
+            ApplicationError::InvalidStorageKey => None,
+            ApplicationError::QueryDisabled => None,
+            ApplicationError::TableSizesDisabled => None,
             ApplicationError::PageSizeTooBig => None,
             ApplicationError::NoBlocks => None,
             ApplicationError::InvalidContinuationToken => None,
@@ -168,6 +225,11 @@ impl ApplicationError {
             ApplicationError::CompiledClassHashMismatch => None,
             ApplicationError::UnsupportedTxVersion => None,
             ApplicationError::UnsupportedContractClassVersion => None,
+            ApplicationError::ClassHashNotFound { class_hash } => class_hash.map(|class_hash| {
+                json!({
+                    "class_hash": class_hash,
+                })
+            }),
             ApplicationError::GatewayError(error) => Some(json!({
                 "error": error,
             })),
@@ -204,6 +266,13 @@ impl ApplicationError {
                 "limit": limit,
                 "requested": requested,
             })),
+            ApplicationError::StateDiffLimitExceeded { limit } => Some(json!({
+                "limit": limit,
+            })),
+            ApplicationError::StorageReadLimitExceeded { limit, requested } => Some(json!({
+                "limit": limit,
+                "requested": requested,
+            })),
             ApplicationError::ValidationFailureV06(error) => Some(json!(error)),
         }
     }
@@ -369,6 +438,23 @@ pub(super) use generate_rpc_error_subset;
 
 #[cfg(test)]
 mod tests {
+    mod data {
+        use super::super::ApplicationError;
+        use pathfinder_common::macro_prelude::*;
+        use serde_json::json;
+
+        #[test]
+        fn class_hash_not_found() {
+            let error = ApplicationError::ClassHashNotFound {
+                class_hash: Some(class_hash!("0x123")),
+            };
+            assert_eq!(error.data(), Some(json!({"class_hash": "0x123"})));
+
+            let error = ApplicationError::ClassHashNotFound { class_hash: None };
+            assert_eq!(error.data(), None);
+        }
+    }
+
     mod rpc_error_subset {
         use super::super::{generate_rpc_error_subset, ApplicationError};
         use assert_matches::assert_matches;
@@ -4,10 +4,19 @@ use crate::v02::types::request::BroadcastedTransaction;
 use crate::v02::types::SierraContractClass;
 use anyhow::Context;
 use pathfinder_common::transaction::TransactionVariant;
-use pathfinder_common::ChainId;
+use pathfinder_common::{
+    BlockHeader, ChainId, ClassHash, ContractAddress, ContractNonce, Fee, GasPrice, StateUpdate,
+    StorageAddress, StorageValue,
+};
+use pathfinder_crypto::Felt;
+use pathfinder_ethereum::L1GasPrices;
 use pathfinder_executor::{ClassInfo, IntoStarkFelt};
 use starknet_api::core::PatriciaKey;
 
+mod pool;
+
+pub use pool::{ExecutionMethod, ExecutionPool};
+
 pub enum ExecutionStateError {
     BlockNotFound,
     Internal(anyhow::Error),
@@ -19,8 +28,152 @@ impl From<anyhow::Error> for ExecutionStateError {
     }
 }
 
-pub const VERSIONS_LOWER_THAN_THIS_SHOULD_FALL_BACK_TO_FETCHING_TRACE_FROM_GATEWAY:
-    semver::Version = semver::Version::new(0, 13, 0);
+/// Execution did not complete within [RpcConfig::execution_timeout](crate::context::RpcConfig::execution_timeout).
+pub struct ExecutionTimeout;
+
+/// Waits for `execution`, giving up on it after `timeout`.
+///
+/// An execution which has already started cannot be interrupted, and keeps running in the
+/// background until it completes. Its work should therefore also be bounded by
+/// [ExecutionState::with_max_steps](pathfinder_executor::ExecutionState::with_max_steps).
+pub(crate) async fn with_timeout<T, E>(
+    timeout: Option<std::time::Duration>,
+    execution: impl std::future::Future<Output = Result<T, E>>,
+) -> Result<T, E>
+where
+    E: From<ExecutionTimeout>,
+{
+    match timeout {
+        Some(timeout) => match tokio::time::timeout(timeout, execution).await {
+            Ok(result) => result,
+            Err(_) => {
+                tracing::debug!(?timeout, "Execution timed out");
+                Err(ExecutionTimeout.into())
+            }
+        },
+        None => execution.await,
+    }
+}
+
+/// State applied on top of the block state when estimating fees or simulating transactions.
+///
+/// This is a pathfinder extension of the JSON-RPC specification, allowing wallets to estimate
+/// fees for accounts which are not yet deployed or funded.
+#[derive(serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct StateOverride {
+    pub contract_address: ContractAddress,
+    /// Replaces the contract's class. The class must already be declared.
+    #[serde(default)]
+    pub class_hash: Option<ClassHash>,
+    #[serde(default)]
+    pub nonce: Option<ContractNonce>,
+    /// Sets the contract's balance of both the ETH and STRK fee tokens.
+    #[serde(default)]
+    pub balance: Option<Felt>,
+    #[serde(default)]
+    pub storage: Vec<StorageOverride>,
+}
+
+#[derive(serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct StorageOverride {
+    pub key: StorageAddress,
+    pub value: StorageValue,
+}
+
+pub(crate) fn map_state_overrides(overrides: &[StateOverride]) -> StateUpdate {
+    let mut state_update = StateUpdate::default();
+
+    for o in overrides {
+        if let Some(class_hash) = o.class_hash {
+            // A replacement, unlike a deployment, keeps the contract's existing storage and nonce.
+            state_update = state_update.with_replaced_class(o.contract_address, class_hash);
+        }
+
+        if let Some(nonce) = o.nonce {
+            state_update = state_update.with_contract_nonce(o.contract_address, nonce);
+        }
+
+        if let Some(balance) = o.balance {
+            // Fee token balances are u256 values, stored as their low and high 128 bits.
+            let low_key =
+                StorageAddress::from_map_name_and_key(b"ERC20_balances", o.contract_address.0);
+            let high_key = StorageAddress::new_or_panic(low_key.0 + Felt::from_u64(1));
+
+            let bytes = balance.to_be_bytes();
+            let low = Felt::from_be_slice(&bytes[16..]).expect("128 bits fit into a felt");
+            let high = Felt::from_be_slice(&bytes[..16]).expect("128 bits fit into a felt");
+
+            for fee_token in [
+                pathfinder_executor::ETH_FEE_TOKEN_ADDRESS,
+                pathfinder_executor::STRK_FEE_TOKEN_ADDRESS,
+            ] {
+                state_update = state_update
+                    .with_storage_update(fee_token, low_key, StorageValue(low))
+                    .with_storage_update(fee_token, high_key, StorageValue(high));
+            }
+        }
+
+        for storage in &o.storage {
+            state_update =
+                state_update.with_storage_update(o.contract_address, storage.key, storage.value);
+        }
+    }
+
+    state_update
+}
+
+/// Raises the pending block's gas prices to at least the latest Ethereum gas prices observed by
+/// L1 sync, if enabled by [RpcConfig::l1_gas_price_floor](crate::context::RpcConfig::l1_gas_price_floor).
+///
+/// The sequencer only updates its prices from time to time, so fees estimated with them can be
+/// too low to be accepted when Ethereum gas prices spike.
+pub(crate) fn apply_l1_gas_price_floor(
+    db: &pathfinder_storage::Transaction<'_>,
+    header: &mut BlockHeader,
+) -> anyhow::Result<()> {
+    let prices = db
+        .l1_gas_prices(pathfinder_storage::BlockId::Latest)
+        .context("Querying L1 gas prices")?;
+
+    if let Some(prices) = prices {
+        raise_gas_prices(header, &prices);
+    }
+
+    Ok(())
+}
+
+fn raise_gas_prices(header: &mut BlockHeader, prices: &L1GasPrices) {
+    raise_gas_price(
+        &mut header.eth_l1_gas_price,
+        &mut header.strk_l1_gas_price,
+        prices.base_fee,
+    );
+    if let Some(blob_base_fee) = prices.blob_base_fee {
+        raise_gas_price(
+            &mut header.eth_l1_data_gas_price,
+            &mut header.strk_l1_data_gas_price,
+            blob_base_fee,
+        );
+    }
+}
+
+/// Raises the price in wei to `floor`, and the price in fri by the same ratio. The price in fri is
+/// left unchanged if there is no price in wei to take the ratio from.
+fn raise_gas_price(wei: &mut GasPrice, fri: &mut GasPrice, floor: GasPrice) {
+    if wei.0 >= floor.0 {
+        return;
+    }
+
+    if *wei != GasPrice::ZERO {
+        fri.0 = match fri.0.checked_mul(floor.0) {
+            Some(x) => x / wei.0,
+            None => (fri.0 / wei.0).saturating_mul(floor.0),
+        };
+    }
+    *wei = floor;
+}
 
 pub(crate) fn map_broadcasted_transaction(
     transaction: &BroadcastedTransaction,
@@ -152,15 +305,28 @@ pub(crate) fn map_broadcasted_transaction(
     Ok(tx)
 }
 
+/// Converts a `max_fee`, which is a felt but limited to 128 bits by execution.
+fn max_fee(fee: Fee) -> anyhow::Result<starknet_api::transaction::Fee> {
+    let bytes = fee.0.to_be_bytes();
+    let (high, low) = bytes.split_at(16);
+    anyhow::ensure!(
+        high.iter().all(|b| *b == 0),
+        "max_fee {} exceeds 128 bits",
+        fee.0
+    );
+
+    Ok(starknet_api::transaction::Fee(u128::from_be_bytes(
+        low.try_into().expect("16 bytes"),
+    )))
+}
+
 fn map_transaction_variant(
     variant: TransactionVariant,
 ) -> anyhow::Result<starknet_api::transaction::Transaction> {
     match variant {
         TransactionVariant::DeclareV0(tx) => {
             let tx = starknet_api::transaction::DeclareTransactionV0V1 {
-                max_fee: starknet_api::transaction::Fee(u128::from_be_bytes(
-                    tx.max_fee.0.to_be_bytes()[16..].try_into().unwrap(),
-                )),
+                max_fee: max_fee(tx.max_fee)?,
                 signature: starknet_api::transaction::TransactionSignature(
                     tx.signature.iter().map(|s| s.0.into_starkfelt()).collect(),
                 ),
@@ -178,9 +344,7 @@ fn map_transaction_variant(
         }
         TransactionVariant::DeclareV1(tx) => {
             let tx = starknet_api::transaction::DeclareTransactionV0V1 {
-                max_fee: starknet_api::transaction::Fee(u128::from_be_bytes(
-                    tx.max_fee.0.to_be_bytes()[16..].try_into().unwrap(),
-                )),
+                max_fee: max_fee(tx.max_fee)?,
                 signature: starknet_api::transaction::TransactionSignature(
                     tx.signature.iter().map(|s| s.0.into_starkfelt()).collect(),
                 ),
@@ -198,9 +362,7 @@ fn map_transaction_variant(
         }
         TransactionVariant::DeclareV2(tx) => {
             let tx = starknet_api::transaction::DeclareTransactionV2 {
-                max_fee: starknet_api::transaction::Fee(u128::from_be_bytes(
-                    tx.max_fee.0.to_be_bytes()[16..].try_into().unwrap(),
-                )),
+                max_fee: max_fee(tx.max_fee)?,
                 signature: starknet_api::transaction::TransactionSignature(
                     tx.signature.iter().map(|s| s.0.into_starkfelt()).collect(),
                 ),
@@ -267,9 +429,7 @@ fn map_transaction_variant(
         TransactionVariant::DeployAccountV0V1(tx) => {
             let tx = starknet_api::transaction::DeployAccountTransaction::V1(
                 starknet_api::transaction::DeployAccountTransactionV1 {
-                    max_fee: starknet_api::transaction::Fee(u128::from_be_bytes(
-                        tx.max_fee.0.to_be_bytes()[16..].try_into().unwrap(),
-                    )),
+                    max_fee: max_fee(tx.max_fee)?,
                     signature: starknet_api::transaction::TransactionSignature(
                         tx.signature.iter().map(|s| s.0.into_starkfelt()).collect(),
                     ),
@@ -332,10 +492,7 @@ fn map_transaction_variant(
         }
         TransactionVariant::InvokeV0(tx) => {
             let tx = starknet_api::transaction::InvokeTransactionV0 {
-                // TODO: maybe we should store tx.max_fee as u128 internally?
-                max_fee: starknet_api::transaction::Fee(u128::from_be_bytes(
-                    tx.max_fee.0.to_be_bytes()[16..].try_into().unwrap(),
-                )),
+                max_fee: max_fee(tx.max_fee)?,
                 signature: starknet_api::transaction::TransactionSignature(
                     tx.signature.iter().map(|s| s.0.into_starkfelt()).collect(),
                 ),
@@ -357,10 +514,7 @@ fn map_transaction_variant(
         }
         TransactionVariant::InvokeV1(tx) => {
             let tx = starknet_api::transaction::InvokeTransactionV1 {
-                // TODO: maybe we should store tx.max_fee as u128 internally?
-                max_fee: starknet_api::transaction::Fee(u128::from_be_bytes(
-                    tx.max_fee.0.to_be_bytes()[16..].try_into().unwrap(),
-                )),
+                max_fee: max_fee(tx.max_fee)?,
                 signature: starknet_api::transaction::TransactionSignature(
                     tx.signature.iter().map(|s| s.0.into_starkfelt()).collect(),
                 ),
@@ -587,3 +741,88 @@ fn map_resource_bounds(
 
     bounds.try_into()
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::v05::method::call::CallError;
+
+    use super::*;
+
+    #[test]
+    fn raise_gas_prices_to_l1() {
+        let mut header = BlockHeader {
+            eth_l1_gas_price: GasPrice(10),
+            strk_l1_gas_price: GasPrice(30),
+            eth_l1_data_gas_price: GasPrice(5),
+            strk_l1_data_gas_price: GasPrice(15),
+            ..Default::default()
+        };
+        let prices = L1GasPrices {
+            block_number: 1,
+            base_fee: GasPrice(20),
+            blob_base_fee: Some(GasPrice(2)),
+        };
+
+        raise_gas_prices(&mut header, &prices);
+
+        // Only prices below Ethereum's are raised, with the STRK price keeping the exchange rate.
+        assert_eq!(header.eth_l1_gas_price, GasPrice(20));
+        assert_eq!(header.strk_l1_gas_price, GasPrice(60));
+        assert_eq!(header.eth_l1_data_gas_price, GasPrice(5));
+        assert_eq!(header.strk_l1_data_gas_price, GasPrice(15));
+    }
+
+    #[test]
+    fn raise_gas_prices_without_price_in_wei() {
+        let mut header = BlockHeader {
+            strk_l1_gas_price: GasPrice(30),
+            ..Default::default()
+        };
+        let prices = L1GasPrices {
+            block_number: 1,
+            base_fee: GasPrice(20),
+            blob_base_fee: None,
+        };
+
+        raise_gas_prices(&mut header, &prices);
+
+        assert_eq!(header.eth_l1_gas_price, GasPrice(20));
+        assert_eq!(header.strk_l1_gas_price, GasPrice(30));
+        assert_eq!(header.eth_l1_data_gas_price, GasPrice::ZERO);
+    }
+
+    #[tokio::test]
+    async fn execution_timeout() {
+        let execution = async {
+            tokio::time::sleep(Duration::from_millis(500)).await;
+            Ok::<_, CallError>(())
+        };
+
+        let result = with_timeout(Some(Duration::from_millis(10)), execution).await;
+
+        assert_matches::assert_matches!(result, Err(CallError::ExecutionTimeout));
+    }
+
+    #[tokio::test]
+    async fn completes_within_timeout() {
+        let execution = async { Ok::<_, CallError>(1) };
+
+        let result = with_timeout(Some(Duration::from_secs(10)), execution)
+            .await
+            .unwrap();
+
+        assert_eq!(result, 1);
+    }
+
+    #[test]
+    fn max_fee_exceeding_128_bits() {
+        use pathfinder_common::macro_prelude::*;
+
+        let fee = max_fee(fee!("0xffffffffffffffffffffffffffffffff")).unwrap();
+        assert_eq!(fee, starknet_api::transaction::Fee(u128::MAX));
+
+        max_fee(fee!("0x100000000000000000000000000000000")).unwrap_err();
+    }
+}
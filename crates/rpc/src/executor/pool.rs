@@ -0,0 +1,233 @@
+//! A dedicated thread pool for local execution.
+//!
+//! Calls, fee estimations, simulations and traces can keep a thread busy for a long time.
+//! Running them on tokio's blocking threads would let a burst of such requests starve
+//! everything else which relies on those threads, such as database access by other RPC
+//! methods and the sync task.
+//!
+//! Work is admitted to the pool through a bounded queue, of which each method may only
+//! occupy a share. This way a flood of simulations can't prevent calls from being served.
+use std::num::NonZeroUsize;
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+
+use anyhow::Context;
+use tokio::sync::{oneshot, Semaphore};
+
+const METRIC_QUEUE_DEPTH: &str = "rpc_execution_queue_depth";
+
+/// The kinds of requests which execute on the [ExecutionPool].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExecutionMethod {
+    Call,
+    EstimateFee,
+    EstimateMessageFee,
+    Simulate,
+    Trace,
+}
+
+impl ExecutionMethod {
+    const COUNT: usize = 5;
+
+    fn index(self) -> usize {
+        match self {
+            ExecutionMethod::Call => 0,
+            ExecutionMethod::EstimateFee => 1,
+            ExecutionMethod::EstimateMessageFee => 2,
+            ExecutionMethod::Simulate => 3,
+            ExecutionMethod::Trace => 4,
+        }
+    }
+}
+
+type Job = Box<dyn FnOnce() + Send>;
+
+#[derive(Clone)]
+pub struct ExecutionPool(Arc<Inner>);
+
+struct Inner {
+    sender: mpsc::Sender<Job>,
+    /// Bounds the number of jobs which are queued or running.
+    queue: Arc<Semaphore>,
+    /// Bounds the number of jobs per [ExecutionMethod] which are queued or running.
+    quotas: [Arc<Semaphore>; ExecutionMethod::COUNT],
+    /// The number of jobs waiting for a worker.
+    depth: Arc<AtomicUsize>,
+}
+
+impl ExecutionPool {
+    /// Starts `workers` threads, which exit once the pool is dropped.
+    ///
+    /// At most `queue_size` jobs are queued or running at once, and each method may use
+    /// up to half of those.
+    pub fn new(workers: NonZeroUsize, queue_size: NonZeroUsize) -> Self {
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(std::sync::Mutex::new(receiver));
+
+        for i in 0..workers.get() {
+            let receiver = receiver.clone();
+            std::thread::Builder::new()
+                .name(format!("rpc-execution-{i}"))
+                .spawn(move || loop {
+                    // The lock is only held while waiting for the next job.
+                    let job = receiver.lock().unwrap().recv();
+                    match job {
+                        // A panic is reported to the caller by its dropped result channel,
+                        // the worker itself carries on.
+                        Ok(job) => {
+                            let _ = std::panic::catch_unwind(AssertUnwindSafe(job));
+                        }
+                        // The pool has been dropped.
+                        Err(_) => break,
+                    }
+                })
+                .expect("Spawning execution worker thread");
+        }
+
+        let quota = (queue_size.get() / 2).max(1);
+
+        Self(Arc::new(Inner {
+            sender,
+            queue: Arc::new(Semaphore::new(queue_size.get())),
+            quotas: std::array::from_fn(|_| Arc::new(Semaphore::new(quota))),
+            depth: Default::default(),
+        }))
+    }
+
+    /// Runs `task` on the pool, waiting for room in the queue first.
+    ///
+    /// If the returned future is dropped before a worker picks up the task, for example
+    /// because the request timed out, the task is skipped.
+    pub async fn execute<T, E>(
+        &self,
+        method: ExecutionMethod,
+        task: impl FnOnce() -> Result<T, E> + Send + 'static,
+    ) -> Result<T, E>
+    where
+        T: Send + 'static,
+        E: From<anyhow::Error> + Send + 'static,
+    {
+        let quota = self.0.quotas[method.index()]
+            .clone()
+            .acquire_owned()
+            .await
+            .context("Execution pool closed")?;
+        let slot = self
+            .0
+            .queue
+            .clone()
+            .acquire_owned()
+            .await
+            .context("Execution pool closed")?;
+
+        let (tx, rx) = oneshot::channel();
+        let span = tracing::Span::current();
+        let depth = self.0.depth.clone();
+
+        let job = Box::new(move || {
+            let _permits = (quota, slot);
+
+            let remaining = depth.fetch_sub(1, Ordering::Relaxed) - 1;
+            metrics::gauge!(METRIC_QUEUE_DEPTH, remaining as f64);
+
+            if tx.is_closed() {
+                return;
+            }
+
+            let _g = span.enter();
+            let _ = tx.send(task());
+        });
+
+        let queued = self.0.depth.fetch_add(1, Ordering::Relaxed) + 1;
+        metrics::gauge!(METRIC_QUEUE_DEPTH, queued as f64);
+
+        if self.0.sender.send(job).is_err() {
+            self.0.depth.fetch_sub(1, Ordering::Relaxed);
+            return Err(anyhow::anyhow!("Execution pool closed").into());
+        }
+
+        rx.await.context("Execution panicked or shutting down")?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn pool(workers: usize, queue_size: usize) -> ExecutionPool {
+        ExecutionPool::new(
+            NonZeroUsize::new(workers).unwrap(),
+            NonZeroUsize::new(queue_size).unwrap(),
+        )
+    }
+
+    #[tokio::test]
+    async fn executes_off_the_runtime() {
+        let pool = pool(1, 2);
+
+        let thread = pool
+            .execute(ExecutionMethod::Call, || {
+                Ok::<_, anyhow::Error>(std::thread::current().name().map(ToOwned::to_owned))
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(thread.as_deref(), Some("rpc-execution-0"));
+    }
+
+    #[tokio::test]
+    async fn survives_panics() {
+        let pool = pool(1, 2);
+
+        let result = pool
+            .execute(ExecutionMethod::Call, || -> anyhow::Result<()> {
+                panic!("Oh no")
+            })
+            .await;
+        assert!(result.is_err());
+
+        let result = pool
+            .execute(ExecutionMethod::Call, || Ok::<_, anyhow::Error>(1))
+            .await
+            .unwrap();
+        assert_eq!(result, 1);
+    }
+
+    #[tokio::test]
+    async fn method_quota_does_not_block_other_methods() {
+        // Each method may occupy one slot of the queue.
+        let pool = pool(2, 2);
+        let (release, wait) = std::sync::mpsc::channel::<()>();
+
+        let blocked = tokio::spawn({
+            let pool = pool.clone();
+            async move {
+                pool.execute(ExecutionMethod::Simulate, move || {
+                    wait.recv_timeout(Duration::from_secs(10)).ok();
+                    Ok::<_, anyhow::Error>(())
+                })
+                .await
+            }
+        });
+        // Give the simulation time to be picked up.
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        // A second simulation must wait for the first one.
+        let second = pool.execute(ExecutionMethod::Simulate, || Ok::<_, anyhow::Error>(()));
+        let second = tokio::time::timeout(Duration::from_millis(50), second).await;
+        assert!(second.is_err());
+
+        // But calls are still served.
+        let result = pool
+            .execute(ExecutionMethod::Call, || Ok::<_, anyhow::Error>(1))
+            .await
+            .unwrap();
+        assert_eq!(result, 1);
+
+        release.send(()).unwrap();
+        blocked.await.unwrap().unwrap();
+    }
+}
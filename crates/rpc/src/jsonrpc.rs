@@ -1,12 +1,13 @@
 mod error;
-mod request;
+pub(crate) mod request;
 mod response;
 mod router;
 pub mod websocket;
 
 pub use error::RpcError;
-pub use request::RpcRequest;
+pub use request::{InvalidInput, RpcRequest};
 pub use response::RpcResponse;
+pub(crate) use router::DEFAULT_BLOCK_ID_HEADER;
 pub use router::{rpc_handler, RpcRouter, RpcRouterBuilder};
 
 #[derive(Debug, PartialEq, Clone)]
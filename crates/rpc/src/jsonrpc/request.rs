@@ -4,6 +4,7 @@ use serde_json::value::RawValue;
 use crate::jsonrpc::{RequestId, RpcError};
 
 use std::borrow::Cow;
+use std::cell::Cell;
 
 #[derive(Debug)]
 pub struct RpcRequest<'a> {
@@ -45,10 +46,67 @@ impl<'a> RawParams<'a> {
         false
     }
 
+    /// Deserializes the params, failing with the error of an [InvalidInput] if one caused the
+    /// failure, or `Invalid params` otherwise.
     pub fn deserialize<T: Deserialize<'a>>(self) -> Result<T, RpcError> {
         let s = self.0.map(|x| x.get()).unwrap_or_default();
 
-        serde_json::from_str::<T>(s).map_err(|e| RpcError::InvalidParams(e.to_string()))
+        INVALID_INPUT.with(|x| x.set(None));
+        serde_json::from_str::<T>(s).map_err(|e| match INVALID_INPUT.with(Cell::take) {
+            Some(input) => RpcError::ApplicationError(input.into()),
+            None => RpcError::InvalidParams(e.to_string()),
+        })
+    }
+}
+
+/// Inputs which are rejected with a dedicated error instead of `Invalid params`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvalidInput {
+    /// A `max_fee` exceeding the 128 bits used by execution.
+    MaxFee,
+    /// Calldata which is not a list of field elements.
+    CallData,
+    /// A storage key which is not a field element below 2^251.
+    StorageKey,
+}
+
+thread_local! {
+    /// The input which failed deserialization, as serde errors only carry a message. Params are
+    /// deserialized synchronously, so no other request runs on the thread meanwhile.
+    static INVALID_INPUT: Cell<Option<InvalidInput>> = const { Cell::new(None) };
+}
+
+impl InvalidInput {
+    /// Deserializes the input, reporting a failure as caused by it.
+    pub(crate) fn deserialize<'de, T, D>(self, deserializer: D) -> Result<T, D::Error>
+    where
+        T: Deserialize<'de>,
+        D: serde::Deserializer<'de>,
+    {
+        T::deserialize(deserializer).map_err(|e| {
+            self.report();
+            e
+        })
+    }
+
+    /// Reports the input as the cause of the deserialization failure, unless another input
+    /// failed first.
+    pub(crate) fn report(self) {
+        INVALID_INPUT.with(|x| {
+            if x.get().is_none() {
+                x.set(Some(self));
+            }
+        });
+    }
+}
+
+impl From<InvalidInput> for crate::error::ApplicationError {
+    fn from(input: InvalidInput) -> Self {
+        match input {
+            InvalidInput::MaxFee => Self::InvalidMaxFee,
+            InvalidInput::CallData => Self::InvalidCallData,
+            InvalidInput::StorageKey => Self::InvalidStorageKey,
+        }
     }
 }
 
@@ -238,4 +296,51 @@ mod tests {
             assert!(!uut.is_empty());
         }
     }
+
+    mod invalid_input {
+        use super::*;
+        use crate::error::ApplicationError;
+        use assert_matches::assert_matches;
+
+        #[derive(Debug, Deserialize)]
+        struct Input {
+            #[serde(deserialize_with = "storage_key")]
+            _key: u8,
+            _other: u8,
+        }
+
+        fn storage_key<'de, D: serde::Deserializer<'de>>(d: D) -> Result<u8, D::Error> {
+            InvalidInput::StorageKey.deserialize(d)
+        }
+
+        fn deserialize(params: serde_json::Value) -> Result<Input, RpcError> {
+            let params = to_raw_value(&params).unwrap();
+            RawParams(Some(&params)).deserialize::<Input>()
+        }
+
+        #[test]
+        fn reported_input_is_the_error() {
+            let error = deserialize(json!([256, 1])).unwrap_err();
+            assert_matches!(
+                error,
+                RpcError::ApplicationError(ApplicationError::InvalidStorageKey)
+            );
+        }
+
+        #[test]
+        fn other_failures_are_invalid_params() {
+            let error = deserialize(json!([1, 256])).unwrap_err();
+            assert_matches!(error, RpcError::InvalidParams(_));
+        }
+
+        #[test]
+        fn report_does_not_leak_into_next_request() {
+            InvalidInput::StorageKey.report();
+
+            let error = deserialize(json!([1])).unwrap_err();
+            assert_matches!(error, RpcError::InvalidParams(_));
+
+            deserialize(json!([1, 1])).unwrap();
+        }
+    }
 }
@@ -9,6 +9,8 @@ use axum::http::StatusCode;
 use axum::response::IntoResponse;
 use futures::{Future, FutureExt, StreamExt};
 use http::HeaderValue;
+use pathfinder_common::{BlockHash, BlockId, BlockNumber};
+use pathfinder_crypto::Felt;
 use serde::de::DeserializeOwned;
 use serde_json::value::RawValue;
 use serde_json::Value;
@@ -19,11 +21,17 @@ use crate::jsonrpc::error::RpcError;
 use crate::jsonrpc::request::{RawParams, RpcRequest};
 use crate::jsonrpc::response::{RpcResponse, RpcResult};
 
+/// Requests carrying this header use its block, e.g. `latest`, `123` or `0x4a5b...`, for the
+/// methods whose `block_id` parameter is omitted. Only named parameters can be omitted.
+pub(crate) const DEFAULT_BLOCK_ID_HEADER: &str = "x-pathfinder-block-id";
+
 #[derive(Clone)]
 pub struct RpcRouter {
     context: RpcContext,
     methods: &'static HashMap<&'static str, Box<dyn RpcMethod>>,
     version: RpcVersion,
+    /// Set from the [DEFAULT_BLOCK_ID_HEADER] of the request being served.
+    default_block_id: Option<BlockId>,
 }
 
 pub struct RpcRouterBuilder {
@@ -61,6 +69,7 @@ impl RpcRouterBuilder {
             context,
             methods,
             version: self.version,
+            default_block_id: None,
         }
     }
 
@@ -101,28 +110,129 @@ impl RpcRouter {
 
         metrics::increment_counter!("rpc_method_calls_total", "method" => method_name, "version" => self.version.to_str());
 
-        let method = method.invoke(self.context.clone(), request.params, self.version);
+        // Use the default block if the method takes one and it was omitted.
+        let params_with_block_id = self
+            .default_block_id
+            .filter(|_| method.takes_block_id())
+            .and_then(|block_id| with_block_id(&request.params, block_id));
+        let params = match &params_with_block_id {
+            Some(params) => RawParams(Some(params)),
+            None => RawParams(request.params.0),
+        };
+
+        let output = self.invoke(method.as_ref(), method_name, params).await;
+
+        if output.is_err() {
+            metrics::increment_counter!("rpc_method_calls_failed_total", "method" => method_name, "version" => self.version.to_str());
+        }
+
+        Some(RpcResponse {
+            output,
+            id: request.id,
+        })
+    }
+
+    /// Invokes the method, turning panics into internal errors.
+    async fn invoke(
+        &self,
+        method: &dyn RpcMethod,
+        method_name: &str,
+        params: RawParams<'_>,
+    ) -> RpcResult {
+        // The span identifies the method in its logs, e.g. those of slow database queries.
+        let method = method
+            .invoke(self.context.clone(), params, self.version)
+            .instrument(tracing::info_span!("rpc", method = %method_name));
         let result = std::panic::AssertUnwindSafe(method).catch_unwind().await;
 
-        let output = match result {
+        match result {
             Ok(output) => output,
             Err(e) => {
-                tracing::warn!(method=%request.method, backtrace=?e, "RPC method panic'd");
+                tracing::warn!(method=%method_name, backtrace=?e, "RPC method panic'd");
                 Err(RpcError::InternalError(anyhow::anyhow!(
                     "RPC method panic'd"
                 )))
             }
-        };
+        }
+    }
+}
 
-        if output.is_err() {
-            metrics::increment_counter!("rpc_method_calls_failed_total", "method" => method_name, "version" => self.version.to_str());
+/// Parses the value of the [DEFAULT_BLOCK_ID_HEADER].
+fn parse_block_id(value: &HeaderValue) -> Option<BlockId> {
+    match value.to_str().ok()?.trim() {
+        "latest" => Some(BlockId::Latest),
+        "pending" => Some(BlockId::Pending),
+        hash if hash.starts_with("0x") => Felt::from_hex_str(hash)
+            .ok()
+            .map(|hash| BlockId::Hash(BlockHash(hash))),
+        number => number
+            .parse::<u64>()
+            .ok()
+            .and_then(BlockNumber::new)
+            .map(BlockId::Number),
+    }
+}
+
+/// Whether `T` is a struct with a `block_id` field.
+///
+/// The field names are read from the deserialization schema, so that the default block can be
+/// added before the parameters are deserialized.
+fn takes_block_id<T: DeserializeOwned>() -> bool {
+    /// Records the fields of the struct it is asked to deserialize, and fails.
+    struct Fields<'a>(&'a mut &'static [&'static str]);
+
+    impl<'de> serde::Deserializer<'de> for Fields<'_> {
+        type Error = serde::de::value::Error;
+
+        fn deserialize_any<V: serde::de::Visitor<'de>>(
+            self,
+            _: V,
+        ) -> Result<V::Value, Self::Error> {
+            Err(serde::de::Error::custom("not a struct"))
         }
 
-        Some(RpcResponse {
-            output,
-            id: request.id,
-        })
+        fn deserialize_struct<V: serde::de::Visitor<'de>>(
+            self,
+            _name: &'static str,
+            fields: &'static [&'static str],
+            _visitor: V,
+        ) -> Result<V::Value, Self::Error> {
+            *self.0 = fields;
+            Err(serde::de::Error::custom("fields recorded"))
+        }
+
+        serde::forward_to_deserialize_any! {
+            bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+            option unit unit_struct newtype_struct seq tuple tuple_struct map enum identifier
+            ignored_any
+        }
     }
+
+    let mut fields: &'static [&'static str] = &[];
+    let _ = T::deserialize(Fields(&mut fields));
+    fields.contains(&"block_id")
+}
+
+/// Adds `block_id` to the named parameters, unless they have one already.
+fn with_block_id(params: &RawParams<'_>, block_id: BlockId) -> Option<Box<RawValue>> {
+    let mut params = if params.is_empty() {
+        serde_json::Map::new()
+    } else {
+        serde_json::from_str::<serde_json::Map<String, Value>>(params.0?.get()).ok()?
+    };
+    if params.contains_key("block_id") {
+        return None;
+    }
+
+    let block_id = match block_id {
+        BlockId::Number(number) => serde_json::json!({ "block_number": number }),
+        BlockId::Hash(hash) => serde_json::json!({ "block_hash": hash }),
+        BlockId::Latest => serde_json::json!("latest"),
+        BlockId::Pending => serde_json::json!("pending"),
+    };
+    params.insert("block_id".to_owned(), block_id);
+
+    serde_json::value::to_raw_value(&params).ok()
 }
 
 // A slight variation on the axum json extractor.
@@ -156,10 +266,21 @@ fn is_utf8_encoded_json(headers: http::HeaderMap) -> bool {
 
 #[axum::debug_handler]
 pub async fn rpc_handler(
-    State(state): State<RpcRouter>,
+    State(mut state): State<RpcRouter>,
     headers: http::HeaderMap,
     body: axum::body::Bytes,
 ) -> impl axum::response::IntoResponse {
+    if let Some(value) = headers.get(DEFAULT_BLOCK_ID_HEADER) {
+        let Some(block_id) = parse_block_id(value) else {
+            let message = format!(
+                "Invalid {DEFAULT_BLOCK_ID_HEADER} header, expected 'latest', 'pending', a block \
+                 number or a block hash"
+            );
+            return (StatusCode::BAD_REQUEST, message).into_response();
+        };
+        state.default_block_id = Some(block_id);
+    }
+
     // Only utf8 json content allowed.
     if !is_utf8_encoded_json(headers) {
         return StatusCode::UNSUPPORTED_MEDIA_TYPE.into_response();
@@ -203,6 +324,14 @@ pub async fn rpc_handler(
                 .into_response();
             }
 
+            let limit = state.context.config.batch_size_limit;
+            if requests.len() > limit.get() {
+                return RpcResponse::invalid_request(format!(
+                    "A batch request must not contain more than {limit} requests"
+                ))
+                .into_response();
+            }
+
             let responses = run_concurrently(
                 state.context.config.batch_concurrency_limit,
                 requests.into_iter().enumerate(),
@@ -243,6 +372,12 @@ pub trait RpcMethod: Send + Sync {
         input: RawParams<'a>,
         version: RpcVersion,
     ) -> RpcResult;
+
+    /// Whether the method has a `block_id` parameter, which defaults to the
+    /// [DEFAULT_BLOCK_ID_HEADER] when omitted.
+    fn takes_block_id(&self) -> bool {
+        false
+    }
 }
 
 /// Utility trait which automates the serde of an RPC methods input and output.
@@ -310,6 +445,7 @@ mod sealed {
         fn into_method(self) -> Box<dyn RpcMethod> {
             struct Helper<F, Input, Output, Error> {
                 f: F,
+                takes_block_id: bool,
                 _marker: PhantomData<(Input, Output, Error)>,
             }
 
@@ -322,6 +458,10 @@ mod sealed {
                 Error: Into<RpcError> + Send + Sync,
                 Fut: Future<Output = Result<Output, Error>> + Send,
             {
+                fn takes_block_id(&self) -> bool {
+                    self.takes_block_id
+                }
+
                 async fn invoke<'a>(
                     &self,
                     state: RpcContext,
@@ -339,6 +479,7 @@ mod sealed {
 
             Box::new(Helper {
                 f: self,
+                takes_block_id: takes_block_id::<Input>(),
                 _marker: Default::default(),
             })
         }
@@ -359,6 +500,7 @@ mod sealed {
         fn into_method(self) -> Box<dyn RpcMethod> {
             struct Helper<F, Input, Output, Error> {
                 f: F,
+                takes_block_id: bool,
                 _marker: PhantomData<(Input, Output, Error)>,
             }
 
@@ -371,6 +513,10 @@ mod sealed {
                 Error: Into<RpcError> + Send + Sync,
                 Fut: Future<Output = Result<Output, Error>> + Send,
             {
+                fn takes_block_id(&self) -> bool {
+                    self.takes_block_id
+                }
+
                 async fn invoke<'a>(
                     &self,
                     _state: RpcContext,
@@ -388,6 +534,7 @@ mod sealed {
 
             Box::new(Helper {
                 f: self,
+                takes_block_id: takes_block_id::<Input>(),
                 _marker: Default::default(),
             })
         }
@@ -883,6 +1030,231 @@ mod tests {
         }
     }
 
+    mod default_block_id {
+        use super::*;
+
+        fn block_router() -> RpcRouter {
+            crate::error::generate_rpc_error_subset!(ExampleError:);
+
+            #[derive(Debug, Deserialize)]
+            #[serde(deny_unknown_fields)]
+            struct BlockInput {
+                block_id: BlockId,
+            }
+            async fn block(input: BlockInput) -> Result<Value, ExampleError> {
+                let block_id = match input.block_id {
+                    BlockId::Latest => "latest".to_owned(),
+                    BlockId::Pending => "pending".to_owned(),
+                    BlockId::Number(number) => number.to_string(),
+                    BlockId::Hash(hash) => hash.0.to_hex_str().into_owned(),
+                };
+                Ok(json!(block_id))
+            }
+
+            #[derive(Debug, Deserialize)]
+            #[serde(deny_unknown_fields)]
+            struct NameInput {
+                name: String,
+            }
+            async fn name(input: NameInput) -> Result<Value, ExampleError> {
+                Ok(json!(input.name))
+            }
+
+            #[derive(Debug, Deserialize)]
+            #[serde(deny_unknown_fields)]
+            struct NameAndBlockInput {
+                name: String,
+                block_id: BlockId,
+            }
+            async fn name_and_block(input: NameAndBlockInput) -> Result<Value, ExampleError> {
+                let _ = input.block_id;
+                Ok(json!(input.name))
+            }
+
+            RpcRouter::builder(Default::default())
+                .register("block", block)
+                .register("name", name)
+                .register("name_and_block", name_and_block)
+                .build(RpcContext::for_tests())
+        }
+
+        async fn query(header: &str, request: Value) -> reqwest::Response {
+            let url = spawn_server(block_router()).await;
+
+            reqwest::Client::new()
+                .post(url)
+                .header(DEFAULT_BLOCK_ID_HEADER, header)
+                .json(&request)
+                .send()
+                .await
+                .unwrap()
+        }
+
+        #[rstest::rstest]
+        #[case::latest("latest", "latest")]
+        #[case::pending("pending", "pending")]
+        #[case::number("123", "123")]
+        #[case::hash("0xabc", "0xabc")]
+        #[tokio::test]
+        async fn omitted_block_id(#[case] header: &str, #[case] expected: &str) {
+            let response = query(
+                header,
+                json!({"jsonrpc": "2.0", "method": "block", "params": {}, "id": 1}),
+            )
+            .await;
+            let response = response.json::<Value>().await.unwrap();
+            assert_eq!(
+                response,
+                json!({"jsonrpc": "2.0", "result": expected, "id": 1})
+            );
+
+            let response = query(
+                header,
+                json!({"jsonrpc": "2.0", "method": "block", "id": 1}),
+            )
+            .await
+            .json::<Value>()
+            .await
+            .unwrap();
+            assert_eq!(
+                response,
+                json!({"jsonrpc": "2.0", "result": expected, "id": 1})
+            );
+        }
+
+        #[tokio::test]
+        async fn explicit_block_id_is_kept() {
+            let response = query(
+                "latest",
+                json!({
+                    "jsonrpc": "2.0",
+                    "method": "block",
+                    "params": {"block_id": "pending"},
+                    "id": 1
+                }),
+            )
+            .await
+            .json::<Value>()
+            .await
+            .unwrap();
+
+            assert_eq!(
+                response,
+                json!({"jsonrpc": "2.0", "result": "pending", "id": 1})
+            );
+        }
+
+        #[tokio::test]
+        async fn methods_without_block_are_not_affected() {
+            let response = query(
+                "latest",
+                json!([
+                    {"jsonrpc": "2.0", "method": "name", "params": {"name": "pathfinder"}, "id": 1},
+                    {"jsonrpc": "2.0", "method": "name", "params": {}, "id": 2},
+                ]),
+            )
+            .await
+            .json::<Value>()
+            .await
+            .unwrap();
+
+            assert_eq!(
+                response,
+                json!([
+                    {"jsonrpc": "2.0", "result": "pathfinder", "id": 1},
+                    {"jsonrpc": "2.0", "id": 2, "error": {
+                        "code": -32602,
+                        "message": "Invalid params",
+                        "data": {"reason": "missing field `name` at line 1 column 2"}
+                    }},
+                ])
+            );
+        }
+
+        #[tokio::test]
+        async fn block_id_is_added_before_other_missing_fields_are_reported() {
+            let response = query(
+                "latest",
+                json!([
+                    {"jsonrpc": "2.0", "method": "name_and_block", "params": {"name": "pathfinder"}, "id": 1},
+                    {"jsonrpc": "2.0", "method": "name_and_block", "params": {}, "id": 2},
+                ]),
+            )
+            .await
+            .json::<Value>()
+            .await
+            .unwrap();
+
+            assert_eq!(
+                response[0],
+                json!({"jsonrpc": "2.0", "result": "pathfinder", "id": 1})
+            );
+            assert_eq!(response[1]["error"]["code"], json!(-32602));
+            let reason = response[1]["error"]["data"]["reason"].as_str().unwrap();
+            assert!(reason.starts_with("missing field `name`"), "{reason}");
+        }
+
+        #[test]
+        fn block_id_parameter_is_read_from_the_schema() {
+            #[derive(Deserialize)]
+            #[allow(dead_code)]
+            struct WithBlock {
+                contract_address: Felt,
+                block_id: BlockId,
+            }
+            #[derive(Deserialize)]
+            #[allow(dead_code)]
+            struct WithoutBlock {
+                block_hash: BlockHash,
+            }
+
+            assert!(takes_block_id::<WithBlock>());
+            assert!(!takes_block_id::<WithoutBlock>());
+            assert!(!takes_block_id::<Value>());
+            assert!(!takes_block_id::<Vec<BlockId>>());
+        }
+
+        #[tokio::test]
+        async fn invalid_header() {
+            let response = query(
+                "yesterday",
+                json!({"jsonrpc": "2.0", "method": "block", "id": 1}),
+            )
+            .await;
+
+            assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+        }
+    }
+
+    #[tokio::test]
+    async fn rejects_batch_over_size_limit() {
+        async fn always_success(_ctx: RpcContext) -> RpcResult {
+            Ok(json!("Success"))
+        }
+
+        let mut context = RpcContext::for_tests();
+        context.config.batch_size_limit = NonZeroUsize::new(2).unwrap();
+        let router = RpcRouter::builder(Default::default())
+            .register("success", always_success)
+            .build(context);
+
+        let response = serve_and_query(
+            router,
+            json!([
+                {"jsonrpc": "2.0", "method": "success", "id": 1},
+                {"jsonrpc": "2.0", "method": "success", "id": 2},
+                {"jsonrpc": "2.0", "method": "success", "id": 3},
+            ]),
+        )
+        .await;
+
+        let expected = json!({"jsonrpc": "2.0", "id": null,
+        "error": {"code": -32600, "message": "Invalid request", "data": {
+            "reason": "A batch request must not contain more than 2 requests"
+        }}});
+        assert_eq!(response, expected);
+    }
+
     #[tokio::test]
     async fn rejects_non_json_content_header() {
         async fn always_success(_ctx: RpcContext) -> RpcResult {
@@ -16,6 +16,31 @@
 //! < {"jsonrpc":"2.0","method":"pathfinder_subscription","result":{"subscription":0,"event":{"class_commitment":"0x4a1c4c3cd477eb052655963781fd7ae0cd647752f01595e4e33fed2ab0eff90","eth_l1_gas_price":1000000015,"event_commitment":"0x79789afccc8f0cac4a3992b2b52cc15f560b4f5a997d883b29d73236b2dfce7","event_count":387,"hash":"0x412edf5929693f8d6bb29512d1a777066dfbf493f3ee64bcb14c64165f5006b","number":908104,"parent_hash":"0x16562de7d258e27809ec6b3d3da5edaedc6526a046442f2f5d72fe7c5dc0a1d","sequencer_address":"0x1176a1bd84444c89232ec27754698e5d2e7e1a7f1539f12027f28b23ec9f3d8","starknet_version":"0.12.3","state_commitment":"0x1d00410c349e70996834a144598bc762602df09cd38a51c25528fb2fd662403","storage_commitment":"0x5129d4a27efa0429975f67440314ab921cc554681ac3ecf476850c1f6b723bf","strk_l1_gas_price":0,"timestamp":1700823087,"transaction_commitment":"0x273bfec6af3c812b59a864e67334132d5bd26c570a9b202e0adce2bb4d6b0cf","transaction_count":36}}}
 //! ```
 //!
+//! Events can be subscribed to with `starknet_subscribeEvents`, which only sends the events
+//! matching the filter. Events of the pending block are sent without a block hash and number, and
+//! again once the block is accepted:
+//! ```
+//! > {"jsonrpc":"2.0", "id": 2, "method": "starknet_subscribeEvents", "params": {"filter": {"address": "0x49d36570d4e46f48e99674bd3fcc84644ddd6b96f7c741b1562b82f9e004dc7", "keys": [["0x99cd8bde557814842a3121e8ddfd433a539b8c9f14bf31ebf108d12e6196e9"]]}}}
+//! < {"jsonrpc":"2.0","result":1,"id":2}
+//! < {"jsonrpc":"2.0","method":"pathfinder_subscription","result":{"subscription":1,"result":{"block_hash":null,"block_number":null,"data":["0x1176a1bd84444c89232ec27754698e5d2e7e1a7f1539f12027f28b23ec9f3d8","0x1","0x2386f26fc10000","0x0"],"from_address":"0x49d36570d4e46f48e99674bd3fcc84644ddd6b96f7c741b1562b82f9e004dc7","keys":["0x99cd8bde557814842a3121e8ddfd433a539b8c9f14bf31ebf108d12e6196e9"],"transaction_hash":"0x6a4e5b0b3d1f2c8e97f05c6a1d2e7b8c9f3a4d5e6b7c8d9e0f1a2b3c4d5e6f7"}}}
+//! ```
+//!
+//! New transactions of the pending block can be subscribed to with
+//! `starknet_subscribePendingTransactions`, which sends their hashes, or the full transactions if
+//! `with_details` is set. Transactions are queued for clients which do not keep up, and dropped or
+//! the subscription closed once the queue is full, as configured by the
+//! `--rpc.websocket.pending-transactions-*` options:
+//! ```
+//! > {"jsonrpc":"2.0", "id": 3, "method": "starknet_subscribePendingTransactions", "params": {"with_details": false}}
+//! < {"jsonrpc":"2.0","result":2,"id":3}
+//! < {"jsonrpc":"2.0","method":"pathfinder_subscription","result":{"subscription":2,"result":"0x273bfec6af3c812b59a864e67334132d5bd26c570a9b202e0adce2bb4d6b0cf"}}
+//! ```
+//!
+//! Block and event subscriptions receive a `reorg` notification when blocks are rolled back:
+//! ```
+//! < {"jsonrpc":"2.0","method":"pathfinder_subscription","result":{"subscription":1,"result":{"type":"reorg","first_block_number":908103,"first_block_hash":"0x16562de7d258e27809ec6b3d3da5edaedc6526a046442f2f5d72fe7c5dc0a1d","last_block_number":908104,"last_block_hash":"0x412edf5929693f8d6bb29512d1a777066dfbf493f3ee64bcb14c64165f5006b","new_head":{"number":908102,"hash":"0x7d2b4cd0a1c69f4b0c3fa61e3b8d2f4e9a0c5b7d1e3f2a4c6b8d0e2f4a6c8e1"}}}}
+//! ```
+//!
 //! Subscriptions may lag behind because of a slow network or slow client and result in an error:
 //! ```
 //! > pierre:~/pathfinder$ wscat -c ws://localhost:9545/ws
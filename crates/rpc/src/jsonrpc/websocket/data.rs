@@ -1,6 +1,12 @@
 //! See [the parent module documentation](super)
 
 use crate::jsonrpc::{RequestId, RpcError, RpcResponse};
+use crate::v06::types::TransactionWithHash;
+use pathfinder_common::event::Event;
+use pathfinder_common::receipt::Receipt;
+use pathfinder_common::{
+    BlockHash, BlockNumber, ContractAddress, EventData, EventKey, TransactionHash,
+};
 use serde::ser::Error;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
@@ -18,6 +24,30 @@ pub(super) struct SubscriptionId {
     pub(super) id: u32,
 }
 
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub(super) struct SubscribeEventsParams {
+    pub(super) filter: EventSubscriptionFilter,
+}
+
+#[derive(Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub(super) struct SubscribePendingTransactionsParams {
+    /// Send the transactions instead of only their hashes.
+    #[serde(default)]
+    pub(super) with_details: bool,
+}
+
+/// The same as the `starknet_getEvents` filter, without the block range and paging.
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub(super) struct EventSubscriptionFilter {
+    #[serde(default)]
+    pub(super) address: Option<ContractAddress>,
+    #[serde(default)]
+    pub(super) keys: Vec<Vec<EventKey>>,
+}
+
 pub(super) struct SubscriptionItem<T> {
     pub(super) subscription_id: u32,
     pub(super) item: T,
@@ -95,6 +125,9 @@ pub(super) enum ResponseEvent {
     InvalidMethod(OwnedRequestId),
     InvalidParams(OwnedRequestId, String),
     Header(SubscriptionItem<Arc<Value>>),
+    Event(SubscriptionItem<EmittedEvent>),
+    Reorg(SubscriptionItem<Arc<Value>>),
+    PendingTransaction(SubscriptionItem<PendingTransaction>),
 }
 
 impl ResponseEvent {
@@ -103,6 +136,9 @@ impl ResponseEvent {
             ResponseEvent::InvalidRequest(_) => "InvalidRequest",
             ResponseEvent::InvalidMethod(_) => "InvalidMethod",
             ResponseEvent::Header(_) => "BlockHeader",
+            ResponseEvent::Event(_) => "Event",
+            ResponseEvent::Reorg(_) => "Reorg",
+            ResponseEvent::PendingTransaction(_) => "PendingTransaction",
             ResponseEvent::Subscribed { .. } => "Subscribed",
             ResponseEvent::Unsubscribed { .. } => "Unsubscribed",
             ResponseEvent::SubscriptionClosed { .. } => "SubscriptionClosed",
@@ -127,6 +163,9 @@ impl Serialize for ResponseEvent {
                 RpcResponse::invalid_params(id.into(), e.clone()).serialize(serializer)
             }
             ResponseEvent::Header(header) => header.serialize(serializer),
+            ResponseEvent::Event(event) => event.serialize(serializer),
+            ResponseEvent::Reorg(reorg) => reorg.serialize(serializer),
+            ResponseEvent::PendingTransaction(transaction) => transaction.serialize(serializer),
             ResponseEvent::Subscribed {
                 subscription_id,
                 request_id,
@@ -206,7 +245,7 @@ impl serde::Serialize for BlockHeader {
             l1_da_mode,
         } = &self.0;
 
-        let mut map = serializer.serialize_map(Some(15))?;
+        let mut map = serializer.serialize_map(Some(18))?;
 
         map.serialize_entry("hash", &hash)?;
         map.serialize_entry("parent_hash", &parent_hash)?;
@@ -230,3 +269,78 @@ impl serde::Serialize for BlockHeader {
         map.end()
     }
 }
+
+/// Notifies `newHeads` and event subscribers that blocks were rolled back by a reorg.
+///
+/// Headers and events of the orphaned blocks which were already sent are no longer valid, and
+/// those of the replacement blocks follow.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename = "reorg")]
+pub struct Reorg {
+    pub first_block_number: BlockNumber,
+    pub first_block_hash: BlockHash,
+    pub last_block_number: BlockNumber,
+    pub last_block_hash: BlockHash,
+    /// The head of the chain after the reorg, `None` if genesis was orphaned.
+    pub new_head: Option<ReorgHead>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct ReorgHead {
+    pub number: BlockNumber,
+    pub hash: BlockHash,
+}
+
+/// The events of a block, which event subscriptions filter before sending them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BlockEvents {
+    /// [None] for the pending block.
+    pub block_hash: Option<BlockHash>,
+    pub block_number: BlockNumber,
+    pub events: Vec<(TransactionHash, Event)>,
+}
+
+impl BlockEvents {
+    pub fn from_receipts<'a>(
+        block_hash: Option<BlockHash>,
+        block_number: BlockNumber,
+        receipts: impl IntoIterator<Item = &'a Receipt>,
+    ) -> Self {
+        let events = receipts
+            .into_iter()
+            .flat_map(|receipt| {
+                receipt
+                    .events
+                    .iter()
+                    .map(|event| (receipt.transaction_hash, event.clone()))
+            })
+            .collect();
+
+        Self {
+            block_hash,
+            block_number,
+            events,
+        }
+    }
+}
+
+/// An event sent to event subscribers, in the format of `starknet_getEvents`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub(super) struct EmittedEvent {
+    pub(super) data: Vec<EventData>,
+    pub(super) keys: Vec<EventKey>,
+    pub(super) from_address: ContractAddress,
+    /// [None] for pending events.
+    pub(super) block_hash: Option<BlockHash>,
+    /// [None] for pending events.
+    pub(super) block_number: Option<BlockNumber>,
+    pub(super) transaction_hash: TransactionHash,
+}
+
+/// A transaction sent to pending transaction subscribers.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(untagged)]
+pub(super) enum PendingTransaction {
+    Hash(TransactionHash),
+    Details(TransactionWithHash),
+}
@@ -1,15 +1,16 @@
 //! See [the parent module documentation](super)
 
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::marker::PhantomData;
+use std::net::{IpAddr, SocketAddr};
 use std::num::NonZeroUsize;
 use std::ops::ControlFlow;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
 use crate::jsonrpc::request::RawParams;
-use crate::jsonrpc::{RequestId, RpcRequest};
+use crate::jsonrpc::{RequestId, RpcRequest, DEFAULT_BLOCK_ID_HEADER};
 use axum::extract::ws::{Message, WebSocket};
-use axum::extract::{State, WebSocketUpgrade};
+use axum::extract::{ConnectInfo, State, WebSocketUpgrade};
 use axum::response::IntoResponse;
 use futures::sink::Buffer;
 use futures::stream::{SplitSink, SplitStream};
@@ -19,10 +20,17 @@ use serde_json::Value;
 use tokio::sync::{broadcast, mpsc};
 use tracing::error;
 
-use crate::jsonrpc::websocket::data::{Kind, ResponseEvent, SubscriptionId, SubscriptionItem};
-use crate::BlockHeader;
+use crate::jsonrpc::websocket::data::{
+    EmittedEvent, Kind, PendingTransaction, ResponseEvent, SubscribeEventsParams,
+    SubscribePendingTransactionsParams, SubscriptionId, SubscriptionItem,
+};
+use crate::{BlockEvents, BlockHeader, Reorg};
+use pathfinder_common::transaction::Transaction;
+use pathfinder_storage::EventFilter;
 
 const SUBSCRIBE_METHOD: &str = "pathfinder_subscribe";
+const SUBSCRIBE_EVENTS_METHOD: &str = "starknet_subscribeEvents";
+const SUBSCRIBE_PENDING_TRANSACTIONS_METHOD: &str = "starknet_subscribePendingTransactions";
 const UNSUBSCRIBE_METHOD: &str = "pathfinder_unsubscribe";
 const NEW_HEADS_TOPIC: &str = "newHeads";
 
@@ -30,6 +38,8 @@ const NEW_HEADS_TOPIC: &str = "newHeads";
 pub struct WebsocketContext {
     socket_buffer_capacity: NonZeroUsize,
     pub broadcasters: TopicBroadcasters,
+    connections: ConnectionLimiter,
+    pending_transaction_queue: QueueLimit,
 }
 
 impl WebsocketContext {
@@ -39,8 +49,22 @@ impl WebsocketContext {
         Self {
             socket_buffer_capacity,
             broadcasters: senders,
+            connections: Default::default(),
+            pending_transaction_queue: Default::default(),
         }
     }
+
+    /// Limits the transactions queued for each pending transaction subscription.
+    pub fn with_pending_transaction_queue(mut self, limit: QueueLimit) -> Self {
+        self.pending_transaction_queue = limit;
+        self
+    }
+
+    /// Limits the number of concurrent connections from a single IP address. Unlimited if `None`.
+    pub fn with_max_connections_per_ip(mut self, max_connections: Option<NonZeroUsize>) -> Self {
+        self.connections.max_per_ip = max_connections;
+        self
+    }
 }
 
 impl Default for WebsocketContext {
@@ -49,15 +73,105 @@ impl Default for WebsocketContext {
             socket_buffer_capacity: NonZeroUsize::new(100)
                 .expect("Invalid socket buffer capacity default value"),
             broadcasters: TopicBroadcasters::default(),
+            connections: Default::default(),
+            pending_transaction_queue: Default::default(),
+        }
+    }
+}
+
+/// What a pending transaction subscription does with a new transaction when its queue is full,
+/// because the client does not read the notifications quickly enough.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DropPolicy {
+    /// Drops the oldest queued transaction to make room for the new one.
+    DropOldest,
+    /// Drops the new transaction.
+    DropNewest,
+    /// Closes the subscription.
+    Close,
+}
+
+/// Bounds the transactions queued for each pending transaction subscription.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct QueueLimit {
+    pub capacity: NonZeroUsize,
+    pub drop_policy: DropPolicy,
+}
+
+impl Default for QueueLimit {
+    fn default() -> Self {
+        Self {
+            capacity: NonZeroUsize::new(1000).expect("Invalid queue capacity default value"),
+            drop_policy: DropPolicy::DropOldest,
+        }
+    }
+}
+
+/// Tracks the number of open connections per IP address.
+#[derive(Clone, Default)]
+struct ConnectionLimiter {
+    max_per_ip: Option<NonZeroUsize>,
+    open: Arc<Mutex<HashMap<IpAddr, usize>>>,
+}
+
+impl ConnectionLimiter {
+    /// Returns `None` if `ip` already has the maximum number of open connections.
+    fn acquire(&self, ip: IpAddr) -> Option<ConnectionPermit> {
+        let mut open = self.open.lock().unwrap();
+        let count = open.entry(ip).or_default();
+
+        if let Some(max) = self.max_per_ip {
+            if *count >= max.get() {
+                return None;
+            }
+        }
+        *count += 1;
+
+        Some(ConnectionPermit {
+            ip,
+            open: self.open.clone(),
+        })
+    }
+}
+
+/// Releases its connection slot when dropped.
+struct ConnectionPermit {
+    ip: IpAddr,
+    open: Arc<Mutex<HashMap<IpAddr, usize>>>,
+}
+
+impl Drop for ConnectionPermit {
+    fn drop(&mut self) {
+        let mut open = self.open.lock().unwrap();
+        if let Some(count) = open.get_mut(&self.ip) {
+            *count -= 1;
+            if *count == 0 {
+                open.remove(&self.ip);
+            }
         }
     }
 }
 
 pub async fn websocket_handler(
     ws: WebSocketUpgrade,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
     State(state): State<WebsocketContext>,
+    headers: http::HeaderMap,
 ) -> impl IntoResponse {
-    let mut upgrade_response = ws.on_upgrade(|socket| handle_socket(socket, state));
+    // Subscriptions take no block, so a default block would be silently ignored.
+    if headers.contains_key(DEFAULT_BLOCK_ID_HEADER) {
+        let message = format!(
+            "The {DEFAULT_BLOCK_ID_HEADER} header is not supported on WebSocket connections"
+        );
+        return (http::StatusCode::BAD_REQUEST, message).into_response();
+    }
+
+    let Some(permit) = state.connections.acquire(addr.ip()) else {
+        tracing::debug!(%addr, "Rejecting WebSocket connection, too many open connections from this address");
+        return http::StatusCode::TOO_MANY_REQUESTS.into_response();
+    };
+
+    let mut upgrade_response = ws.on_upgrade(|socket| handle_socket(socket, state, permit));
 
     static APPLICATION_JSON: http::HeaderValue = http::HeaderValue::from_static("application/json");
     upgrade_response
@@ -67,7 +181,7 @@ pub async fn websocket_handler(
     upgrade_response
 }
 
-async fn handle_socket(socket: WebSocket, context: WebsocketContext) {
+async fn handle_socket(socket: WebSocket, context: WebsocketContext, permit: ConnectionPermit) {
     let (ws_sender, ws_receiver) = socket.split();
 
     let (response_sender, response_receiver) = mpsc::channel(10);
@@ -77,7 +191,17 @@ async fn handle_socket(socket: WebSocket, context: WebsocketContext) {
         response_receiver,
         context.socket_buffer_capacity,
     ));
-    tokio::spawn(read(ws_receiver, response_sender, context.broadcasters));
+    tokio::spawn(async move {
+        read(
+            ws_receiver,
+            response_sender,
+            context.broadcasters,
+            context.pending_transaction_queue,
+        )
+        .await;
+        // The connection is closed once the client stops sending.
+        drop(permit);
+    });
 }
 
 async fn write(
@@ -122,8 +246,12 @@ async fn read(
     mut receiver: SplitStream<WebSocket>,
     response_sender: mpsc::Sender<ResponseEvent>,
     source: TopicBroadcasters,
+    pending_transaction_queue: QueueLimit,
 ) {
-    let mut subscription_manager = SubscriptionManager::default();
+    let mut subscription_manager = SubscriptionManager {
+        pending_transaction_queue,
+        ..Default::default()
+    };
 
     loop {
         let request = match receiver.next().await {
@@ -165,6 +293,19 @@ async fn read(
                 response_sender.clone(),
                 source.clone(),
             ),
+            SUBSCRIBE_EVENTS_METHOD => subscription_manager.subscribe_events(
+                request.id,
+                request.params,
+                response_sender.clone(),
+                source.clone(),
+            ),
+            SUBSCRIBE_PENDING_TRANSACTIONS_METHOD => subscription_manager
+                .subscribe_pending_transactions(
+                    request.id,
+                    request.params,
+                    response_sender.clone(),
+                    source.clone(),
+                ),
             UNSUBSCRIBE_METHOD => {
                 subscription_manager
                     .unsubscribe(request.id, request.params)
@@ -190,6 +331,7 @@ async fn read(
 struct SubscriptionManager {
     next_id: u32,
     subscriptions: HashMap<u32, tokio::task::JoinHandle<()>>,
+    pending_transaction_queue: QueueLimit,
 }
 
 impl SubscriptionManager {
@@ -273,6 +415,106 @@ impl SubscriptionManager {
         }
     }
 
+    fn subscribe_events(
+        &mut self,
+        request_id: RequestId<'_>,
+        request_params: RawParams<'_>,
+        response_sender: mpsc::Sender<ResponseEvent>,
+        websocket_source: TopicBroadcasters,
+    ) -> ResponseEvent {
+        let params = match request_params.deserialize::<SubscribeEventsParams>() {
+            Ok(x) => x,
+            Err(crate::jsonrpc::RpcError::InvalidParams(e)) => {
+                return ResponseEvent::InvalidParams(request_id.into(), e)
+            }
+            Err(_) => {
+                return ResponseEvent::InvalidParams(
+                    request_id.into(),
+                    "Unexpected parsing error".to_owned(),
+                )
+            }
+        };
+
+        if params.filter.keys.len() > pathfinder_storage::EVENT_KEY_FILTER_LIMIT {
+            return ResponseEvent::InvalidParams(
+                request_id.into(),
+                format!(
+                    "Too many keys in filter, the limit is {}",
+                    pathfinder_storage::EVENT_KEY_FILTER_LIMIT
+                ),
+            );
+        }
+
+        // The block range and paging are not used for matching events.
+        let filter = EventFilter {
+            from_block: None,
+            to_block: None,
+            contract_address: params.filter.address,
+            keys: params.filter.keys,
+            page_size: 0,
+            offset: 0,
+            order: pathfinder_storage::EventOrder::Ascending,
+        };
+
+        let subscription_id = self.next_id;
+        self.next_id += 1;
+        let handle = tokio::spawn(event_subscription(
+            response_sender,
+            websocket_source.events.sender.subscribe(),
+            subscription_id,
+            filter,
+        ));
+
+        self.subscriptions.insert(subscription_id, handle);
+
+        ResponseEvent::Subscribed {
+            subscription_id,
+            request_id: request_id.into(),
+        }
+    }
+
+    fn subscribe_pending_transactions(
+        &mut self,
+        request_id: RequestId<'_>,
+        request_params: RawParams<'_>,
+        response_sender: mpsc::Sender<ResponseEvent>,
+        websocket_source: TopicBroadcasters,
+    ) -> ResponseEvent {
+        let params = if request_params.is_empty() {
+            SubscribePendingTransactionsParams::default()
+        } else {
+            match request_params.deserialize::<SubscribePendingTransactionsParams>() {
+                Ok(x) => x,
+                Err(crate::jsonrpc::RpcError::InvalidParams(e)) => {
+                    return ResponseEvent::InvalidParams(request_id.into(), e)
+                }
+                Err(_) => {
+                    return ResponseEvent::InvalidParams(
+                        request_id.into(),
+                        "Unexpected parsing error".to_owned(),
+                    )
+                }
+            }
+        };
+
+        let subscription_id = self.next_id;
+        self.next_id += 1;
+        let handle = tokio::spawn(pending_transaction_subscription(
+            response_sender,
+            websocket_source.pending_transactions.subscribe(),
+            subscription_id,
+            params.with_details,
+            self.pending_transaction_queue,
+        ));
+
+        self.subscriptions.insert(subscription_id, handle);
+
+        ResponseEvent::Subscribed {
+            subscription_id,
+            request_id: request_id.into(),
+        }
+    }
+
     fn abort_all(self) {
         for (_, handle) in self.subscriptions {
             handle.abort();
@@ -314,6 +556,133 @@ async fn header_subscription(
     }
 }
 
+async fn event_subscription(
+    msg_sender: mpsc::Sender<ResponseEvent>,
+    mut items: broadcast::Receiver<EventsItem>,
+    subscription_id: u32,
+    filter: EventFilter,
+) {
+    use broadcast::error::RecvError;
+    loop {
+        let responses = match items.recv().await {
+            Ok(EventsItem::Events(block)) => block
+                .events
+                .iter()
+                .filter(|(_, event)| filter.matches(event))
+                .map(|(transaction_hash, event)| {
+                    ResponseEvent::Event(SubscriptionItem {
+                        subscription_id,
+                        item: EmittedEvent {
+                            data: event.data.clone(),
+                            keys: event.keys.clone(),
+                            from_address: event.from_address,
+                            block_hash: block.block_hash,
+                            block_number: block.block_hash.map(|_| block.block_number),
+                            transaction_hash: *transaction_hash,
+                        },
+                    })
+                })
+                .collect(),
+            Ok(EventsItem::Reorg(reorg)) => vec![ResponseEvent::Reorg(SubscriptionItem {
+                subscription_id,
+                item: reorg,
+            })],
+            Err(RecvError::Closed) => break,
+            Err(RecvError::Lagged(amount)) => {
+                tracing::info!(
+                    amount,
+                    "Lagging event stream, missed some blocks, closing subscription"
+                );
+
+                let _ = msg_sender
+                    .send(ResponseEvent::SubscriptionClosed {
+                        subscription_id,
+                        reason: "Lagging stream, some events were skipped. Closing subscription."
+                            .to_owned(),
+                    })
+                    .await;
+                break;
+            }
+        };
+
+        for response in responses {
+            if msg_sender.send(response).await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+/// Unlike other subscriptions, transactions are queued in the subscription so that a slow client
+/// is handled according to the [QueueLimit] instead of blocking the subscription.
+async fn pending_transaction_subscription(
+    msg_sender: mpsc::Sender<ResponseEvent>,
+    mut transactions: broadcast::Receiver<Arc<Vec<Transaction>>>,
+    subscription_id: u32,
+    with_details: bool,
+    queue_limit: QueueLimit,
+) {
+    use broadcast::error::RecvError;
+
+    let mut queue = VecDeque::new();
+    let close_reason = loop {
+        tokio::select! {
+            received = transactions.recv() => match received {
+                Ok(received) => {
+                    let mut overflowed = false;
+                    for transaction in received.iter() {
+                        if queue.len() >= queue_limit.capacity.get() {
+                            match queue_limit.drop_policy {
+                                DropPolicy::DropOldest => {
+                                    queue.pop_front();
+                                }
+                                DropPolicy::DropNewest => continue,
+                                DropPolicy::Close => {
+                                    overflowed = true;
+                                    break;
+                                }
+                            }
+                        }
+
+                        queue.push_back(if with_details {
+                            PendingTransaction::Details(transaction.clone().into())
+                        } else {
+                            PendingTransaction::Hash(transaction.hash)
+                        });
+                    }
+
+                    if overflowed {
+                        tracing::debug!("Pending transaction queue is full, closing subscription");
+                        break "Transaction queue is full. Closing subscription.";
+                    }
+                }
+                Err(RecvError::Closed) => return,
+                Err(RecvError::Lagged(amount)) => {
+                    tracing::info!(
+                        amount,
+                        "Lagging pending transaction stream, closing subscription"
+                    );
+                    break "Lagging stream, some transactions were skipped. Closing subscription.";
+                }
+            },
+            permit = msg_sender.reserve(), if !queue.is_empty() => match permit {
+                Ok(permit) => permit.send(ResponseEvent::PendingTransaction(SubscriptionItem {
+                    subscription_id,
+                    item: queue.pop_front().expect("Queue is not empty"),
+                })),
+                Err(_) => return,
+            },
+        }
+    };
+
+    let _ = msg_sender
+        .send(ResponseEvent::SubscriptionClosed {
+            subscription_id,
+            reason: close_reason.to_owned(),
+        })
+        .await;
+}
+
 /// A Tokio broadcast sender pre-serializing the value once for all subscribers.
 /// Relies on `Arc`s to flatten the cloning costs inherent to Tokio broadcast channels.
 #[derive(Debug, Clone)]
@@ -352,9 +721,65 @@ where
     }
 }
 
+#[derive(Debug, Clone)]
+enum EventsItem {
+    Events(Arc<BlockEvents>),
+    Reorg(Arc<Value>),
+}
+
+/// A Tokio broadcast sender of block events, which each event subscription filters.
+#[derive(Debug, Clone)]
+pub struct EventBroadcaster {
+    sender: broadcast::Sender<EventsItem>,
+}
+
+impl EventBroadcaster {
+    /// Collecting the events of a block clones them, which is unnecessary if there are no
+    /// subscribers.
+    pub fn is_receiving(&self) -> bool {
+        self.sender.receiver_count() > 0
+    }
+
+    pub fn send_if_receiving(&self, events: BlockEvents) {
+        if self.is_receiving() {
+            if let Err(err) = self.sender.send(EventsItem::Events(Arc::new(events))) {
+                tracing::warn!("Broadcasting failed, the buffer might be full: {}", err);
+            }
+        }
+    }
+}
+
+/// A Tokio broadcast sender of new pending transactions.
+#[derive(Debug, Clone)]
+pub struct TransactionBroadcaster {
+    sender: broadcast::Sender<Arc<Vec<Transaction>>>,
+}
+
+impl TransactionBroadcaster {
+    /// Collecting the transactions clones them, which is unnecessary if there are no
+    /// subscribers.
+    pub fn is_receiving(&self) -> bool {
+        self.sender.receiver_count() > 0
+    }
+
+    pub fn send_if_receiving(&self, transactions: Vec<Transaction>) {
+        if self.is_receiving() {
+            if let Err(err) = self.sender.send(Arc::new(transactions)) {
+                tracing::warn!("Broadcasting failed, the buffer might be full: {}", err);
+            }
+        }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Arc<Vec<Transaction>>> {
+        self.sender.subscribe()
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct TopicBroadcasters {
     pub new_head: JsonBroadcaster<BlockHeader>,
+    pub events: EventBroadcaster,
+    pub pending_transactions: TransactionBroadcaster,
 }
 
 impl TopicBroadcasters {
@@ -364,8 +789,34 @@ impl TopicBroadcasters {
                 sender: broadcast::channel(capacity.get()).0,
                 item_type: PhantomData {},
             },
+            events: EventBroadcaster {
+                sender: broadcast::channel(capacity.get()).0,
+            },
+            pending_transactions: TransactionBroadcaster {
+                sender: broadcast::channel(capacity.get()).0,
+            },
         }
     }
+
+    /// Sends the reorg to both new head and event subscribers. Reorgs share the channels of new
+    /// heads and events so that subscribers receive them in the order in which they happened.
+    pub fn send_reorg_if_receiving(&self, reorg: Reorg) -> Result<(), serde_json::Error> {
+        let value = Arc::new(serde_json::to_value(reorg)?);
+
+        if self.new_head.sender.receiver_count() > 0 {
+            if let Err(err) = self.new_head.sender.send(value.clone()) {
+                tracing::warn!("Broadcasting failed, the buffer might be full: {}", err);
+            }
+        }
+
+        if self.events.is_receiving() {
+            if let Err(err) = self.events.sender.send(EventsItem::Reorg(value)) {
+                tracing::warn!("Broadcasting failed, the buffer might be full: {}", err);
+            }
+        }
+
+        Ok(())
+    }
 }
 
 impl Default for TopicBroadcasters {
@@ -379,10 +830,17 @@ impl Default for TopicBroadcasters {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::jsonrpc::websocket::data::successful_response;
+    use crate::jsonrpc::websocket::data::{successful_response, EventSubscriptionFilter};
     use crate::jsonrpc::{RpcError, RpcResponse};
+    use crate::ReorgHead;
     use axum::routing::get;
     use futures::{SinkExt, StreamExt};
+    use pathfinder_common::event::Event;
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::transaction::TransactionVariant;
+    use pathfinder_common::BlockNumber;
+    use pathfinder_common::TransactionHash;
+    use pathfinder_crypto::Felt;
     use serde::Serialize;
     use serde_json::value::RawValue;
     use serde_json::{json, Number, Value};
@@ -478,6 +936,349 @@ mod tests {
         client.destroy().await;
     }
 
+    #[tokio::test]
+    async fn reorgs_are_sent_in_order_with_heads() {
+        let mut client = Client::new().await;
+
+        let req_id = RequestId::Number(1);
+        client
+            .send_request(&RpcRequest {
+                method: Cow::from(SUBSCRIBE_METHOD),
+                params: RawParams(Some(&value(&Kind {
+                    kind: NEW_HEADS_TOPIC.into(),
+                }))),
+                id: req_id.clone(),
+            })
+            .await;
+        client
+            .expect_response(&successful_response(&0, req_id).unwrap())
+            .await;
+
+        let reorg = Reorg {
+            first_block_number: BlockNumber::new_or_panic(2),
+            first_block_hash: block_hash_bytes!(b"block 2"),
+            last_block_number: BlockNumber::new_or_panic(3),
+            last_block_hash: block_hash_bytes!(b"block 3"),
+            new_head: Some(ReorgHead {
+                number: BlockNumber::new_or_panic(1),
+                hash: block_hash_bytes!(b"block 1"),
+            }),
+        };
+        client
+            .head_sender
+            .send_if_receiving(header_sample())
+            .unwrap();
+        client.broadcasters.send_reorg_if_receiving(reorg).unwrap();
+        client
+            .head_sender
+            .send_if_receiving(header_sample())
+            .unwrap();
+
+        client
+            .expect_response(&SubscriptionItem {
+                subscription_id: 0,
+                item: header_sample(),
+            })
+            .await;
+        client
+            .expect_response(&json!({
+                "jsonrpc": "2.0",
+                "method": "pathfinder_subscription",
+                "result": {
+                    "subscription": 0,
+                    "result": {
+                        "type": "reorg",
+                        "first_block_number": 2,
+                        "first_block_hash": block_hash_bytes!(b"block 2"),
+                        "last_block_number": 3,
+                        "last_block_hash": block_hash_bytes!(b"block 3"),
+                        "new_head": {
+                            "number": 1,
+                            "hash": block_hash_bytes!(b"block 1"),
+                        },
+                    },
+                },
+            }))
+            .await;
+        client
+            .expect_response(&SubscriptionItem {
+                subscription_id: 0,
+                item: header_sample(),
+            })
+            .await;
+
+        client.destroy().await;
+    }
+
+    #[tokio::test]
+    async fn can_subscribe_to_events() {
+        let mut client = Client::new().await;
+
+        let req_id = RequestId::Number(1);
+        client
+            .send_request(&RpcRequest {
+                method: Cow::from(SUBSCRIBE_EVENTS_METHOD),
+                params: RawParams(Some(&value(&SubscribeEventsParams {
+                    filter: EventSubscriptionFilter {
+                        address: Some(contract_address!("0x1")),
+                        keys: vec![vec![], vec![event_key!("0xb")]],
+                    },
+                }))),
+                id: req_id.clone(),
+            })
+            .await;
+        client
+            .expect_response(&successful_response(&0, req_id).unwrap())
+            .await;
+
+        let event = |from_address, keys| Event {
+            data: vec![event_data!("0x5")],
+            from_address,
+            keys,
+        };
+        let matching = event(
+            contract_address!("0x1"),
+            vec![event_key!("0xa"), event_key!("0xb")],
+        );
+        let events = vec![
+            (transaction_hash!("0x10"), matching.clone()),
+            (
+                transaction_hash!("0x11"),
+                event(
+                    contract_address!("0x2"),
+                    vec![event_key!("0xa"), event_key!("0xb")],
+                ),
+            ),
+            (
+                transaction_hash!("0x12"),
+                event(contract_address!("0x1"), vec![event_key!("0xa")]),
+            ),
+        ];
+
+        client.broadcasters.events.send_if_receiving(BlockEvents {
+            block_hash: None,
+            block_number: BlockNumber::new_or_panic(3),
+            events: events.clone(),
+        });
+        client.broadcasters.events.send_if_receiving(BlockEvents {
+            block_hash: Some(block_hash!("0x3")),
+            block_number: BlockNumber::new_or_panic(3),
+            events,
+        });
+
+        client
+            .expect_response(&SubscriptionItem {
+                subscription_id: 0,
+                item: EmittedEvent {
+                    data: matching.data.clone(),
+                    keys: matching.keys.clone(),
+                    from_address: matching.from_address,
+                    block_hash: None,
+                    block_number: None,
+                    transaction_hash: transaction_hash!("0x10"),
+                },
+            })
+            .await;
+        client
+            .expect_response(&SubscriptionItem {
+                subscription_id: 0,
+                item: EmittedEvent {
+                    data: matching.data,
+                    keys: matching.keys,
+                    from_address: matching.from_address,
+                    block_hash: Some(block_hash!("0x3")),
+                    block_number: Some(BlockNumber::new_or_panic(3)),
+                    transaction_hash: transaction_hash!("0x10"),
+                },
+            })
+            .await;
+        client.expect_no_response().await;
+
+        // Reorgs are sent to event subscribers as well.
+        let reorg = Reorg {
+            first_block_number: BlockNumber::new_or_panic(3),
+            first_block_hash: block_hash!("0x3"),
+            last_block_number: BlockNumber::new_or_panic(3),
+            last_block_hash: block_hash!("0x3"),
+            new_head: None,
+        };
+        client
+            .broadcasters
+            .send_reorg_if_receiving(reorg.clone())
+            .unwrap();
+        client
+            .expect_response(&SubscriptionItem {
+                subscription_id: 0,
+                item: reorg,
+            })
+            .await;
+
+        client.destroy().await;
+    }
+
+    #[tokio::test]
+    async fn event_subscription_key_limit() {
+        let mut client = Client::new().await;
+
+        let req_id = RequestId::Number(1);
+        client
+            .send_request(&RpcRequest {
+                method: Cow::from(SUBSCRIBE_EVENTS_METHOD),
+                params: RawParams(Some(&value(&SubscribeEventsParams {
+                    filter: EventSubscriptionFilter {
+                        address: None,
+                        keys: vec![vec![]; pathfinder_storage::EVENT_KEY_FILTER_LIMIT + 1],
+                    },
+                }))),
+                id: req_id.clone(),
+            })
+            .await;
+        client
+            .expect_response(&RpcResponse {
+                output: Err(RpcError::InvalidParams(
+                    "Too many keys in filter, the limit is 16".to_owned(),
+                )),
+                id: req_id,
+            })
+            .await;
+
+        client.destroy().await;
+    }
+
+    #[tokio::test]
+    async fn can_subscribe_to_pending_transactions() {
+        let mut client = Client::new().await;
+
+        for (id, with_details) in [(0, false), (1, true)] {
+            let req_id = RequestId::Number(id);
+            client
+                .send_request(&RpcRequest {
+                    method: Cow::from(SUBSCRIBE_PENDING_TRANSACTIONS_METHOD),
+                    params: RawParams(Some(&value(&SubscribePendingTransactionsParams {
+                        with_details,
+                    }))),
+                    id: req_id.clone(),
+                })
+                .await;
+            client
+                .expect_response(&successful_response(&id, req_id).unwrap())
+                .await;
+        }
+
+        let transaction = pending_transaction(1);
+        client
+            .broadcasters
+            .pending_transactions
+            .send_if_receiving(vec![transaction.clone()]);
+
+        // The order of the two subscriptions' notifications is not deterministic.
+        let mut received = Vec::new();
+        for _ in 0..2 {
+            received.push(client.receive().await);
+        }
+        received.sort_by_key(|x| x["result"]["subscription"].as_u64());
+
+        let expected = [
+            SubscriptionItem {
+                subscription_id: 0,
+                item: PendingTransaction::Hash(transaction.hash),
+            },
+            SubscriptionItem {
+                subscription_id: 1,
+                item: PendingTransaction::Details(transaction.into()),
+            },
+        ]
+        .map(|x| serde_json::to_value(x).unwrap());
+        assert_eq!(received, expected);
+
+        client.destroy().await;
+    }
+
+    #[tokio::test]
+    async fn pending_transaction_queue_drop_policies() {
+        let cases = [
+            (DropPolicy::DropOldest, vec![json!("0x4"), json!("0x5")]),
+            (DropPolicy::DropNewest, vec![json!("0x1"), json!("0x2")]),
+            (DropPolicy::Close, vec![]),
+        ];
+
+        for (drop_policy, expected) in cases {
+            let broadcaster = TopicBroadcasters::default().pending_transactions;
+            // A full message channel stands in for a client which does not keep up.
+            let (msg_sender, mut msg_receiver) = mpsc::channel(1);
+            let subscription = tokio::spawn(pending_transaction_subscription(
+                msg_sender,
+                broadcaster.subscribe(),
+                0,
+                false,
+                QueueLimit {
+                    capacity: NonZeroUsize::new(2).unwrap(),
+                    drop_policy,
+                },
+            ));
+
+            broadcaster.send_if_receiving((1..=5).map(pending_transaction).collect());
+
+            let mut received = Vec::new();
+            for _ in 0..expected.len() {
+                let response = timeout(Duration::from_millis(100), msg_receiver.recv())
+                    .await
+                    .unwrap()
+                    .unwrap();
+                let response = serde_json::to_value(response).unwrap();
+                received.push(response["result"]["result"].clone());
+            }
+            assert_eq!(received, expected, "{drop_policy:?}");
+
+            if drop_policy == DropPolicy::Close {
+                let response = timeout(Duration::from_millis(100), msg_receiver.recv())
+                    .await
+                    .unwrap()
+                    .unwrap();
+                assert_matches::assert_matches!(response, ResponseEvent::SubscriptionClosed { .. });
+            }
+
+            subscription.abort();
+        }
+    }
+
+    #[tokio::test]
+    async fn default_block_id_header_is_rejected() {
+        use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+
+        let (server_handle, ws_addr) = spawn_server(WebsocketContext::default());
+
+        let mut request = ws_addr.as_str().into_client_request().unwrap();
+        request.headers_mut().insert(
+            DEFAULT_BLOCK_ID_HEADER,
+            http::HeaderValue::from_static("latest"),
+        );
+        let error = connect_async(request).await.unwrap_err();
+        assert_matches::assert_matches!(
+            error,
+            tokio_tungstenite::tungstenite::Error::Http(response) if response.status() == http::StatusCode::BAD_REQUEST
+        );
+
+        server_handle.abort();
+    }
+
+    #[tokio::test]
+    async fn connection_limit_per_ip() {
+        let context = WebsocketContext::default().with_max_connections_per_ip(NonZeroUsize::new(1));
+        let (server_handle, ws_addr) = spawn_server(context);
+
+        let (first, _) = connect_async(ws_addr.as_str()).await.unwrap();
+
+        let error = connect_async(ws_addr.as_str()).await.unwrap_err();
+        assert_matches::assert_matches!(
+            error,
+            tokio_tungstenite::tungstenite::Error::Http(response) if response.status() == http::StatusCode::TOO_MANY_REQUESTS
+        );
+
+        drop(first);
+        server_handle.abort();
+    }
+
     // TODO Prevent duplicate subscriptions?
     // This is actually tolerated by Alchemy, you can subscribe multiple times
     // to the same topic and receive duplicated messages as a result.
@@ -494,33 +1295,52 @@ mod tests {
         BlockHeader(Default::default())
     }
 
+    fn pending_transaction(hash: u8) -> Transaction {
+        Transaction {
+            hash: TransactionHash(Felt::from_u64(hash.into())),
+            variant: TransactionVariant::L1Handler(Default::default()),
+        }
+    }
+
+    fn spawn_server(context: WebsocketContext) -> (JoinHandle<()>, String) {
+        let router = axum::Router::new()
+            .route("/ws", get(websocket_handler))
+            .with_state(context)
+            .layer(tower::ServiceBuilder::new());
+
+        let listener =
+            std::net::TcpListener::bind("127.0.0.1:0").expect("Websocket address already in use");
+        let addr = listener.local_addr().unwrap();
+        let server = axum::Server::from_tcp(listener).unwrap();
+        let server_handle = tokio::spawn(async move {
+            server
+                .serve(router.into_make_service_with_connect_info::<SocketAddr>())
+                .await
+                .unwrap()
+        });
+
+        (
+            server_handle,
+            "ws://".to_string() + &addr.to_string() + "/ws",
+        )
+    }
+
     struct Client {
         sender: SplitSink<WebSocketStream<MaybeTlsStream<TcpStream>>, Message>,
         receiver: SplitStream<WebSocketStream<MaybeTlsStream<TcpStream>>>,
         server_handle: JoinHandle<()>,
         head_sender: JsonBroadcaster<BlockHeader>,
+        broadcasters: TopicBroadcasters,
     }
 
     impl Client {
         async fn new() -> Client {
             let context = WebsocketContext::default();
             let head_sender = context.broadcasters.new_head.clone();
+            let broadcasters = context.broadcasters.clone();
 
-            let router = axum::Router::new()
-                .route("/ws", get(websocket_handler))
-                .with_state(context)
-                .layer(tower::ServiceBuilder::new());
-
-            let listener = std::net::TcpListener::bind("127.0.0.1:0")
-                .expect("Websocket address already in use");
-            let addr = listener.local_addr().unwrap();
-            let server = axum::Server::from_tcp(listener).unwrap();
-            let server_handle =
-                tokio::spawn(
-                    async move { server.serve(router.into_make_service()).await.unwrap() },
-                );
+            let (server_handle, ws_addr) = spawn_server(context);
 
-            let ws_addr = "ws://".to_string() + &addr.to_string() + "/ws";
             let ws_stream = match connect_async(ws_addr).await {
                 Ok((stream, _response)) => stream,
                 Err(e) => {
@@ -532,6 +1352,7 @@ mod tests {
 
             Client {
                 head_sender,
+                broadcasters,
                 sender,
                 receiver,
                 server_handle,
@@ -559,6 +1380,12 @@ mod tests {
         where
             R: Serialize,
         {
+            let received = self.receive().await;
+            let expected = serde_json::to_value(response).unwrap();
+            assert_eq!(received, expected);
+        }
+
+        async fn receive(&mut self) -> Value {
             let message = timeout(Duration::from_millis(100), self.receiver.next())
                 .await
                 .unwrap()
@@ -569,9 +1396,7 @@ mod tests {
             };
 
             // Deserialize it to a generic value to avoid field ordering issues.
-            let received: Value = serde_json::from_str(&raw_text).unwrap();
-            let expected = serde_json::to_value(response).unwrap();
-            assert_eq!(received, expected);
+            serde_json::from_str(&raw_text).unwrap()
         }
 
         async fn expect_no_response(&mut self) {
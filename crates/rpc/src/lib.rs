@@ -5,9 +5,12 @@ mod error;
 mod executor;
 mod felt;
 mod jsonrpc;
+pub mod local_sequencer;
+pub mod mempool;
 pub(crate) mod method;
 pub mod middleware;
 mod pathfinder;
+pub mod peer_admin;
 mod pending;
 #[cfg(test)]
 mod test_setup;
@@ -23,7 +26,9 @@ pub use pending::PendingData;
 
 use crate::jsonrpc::rpc_handler;
 use crate::jsonrpc::websocket::websocket_handler;
-pub use crate::jsonrpc::websocket::{BlockHeader, TopicBroadcasters};
+pub use crate::jsonrpc::websocket::{
+    BlockEvents, BlockHeader, Reorg, ReorgHead, TopicBroadcasters,
+};
 use crate::v02::types::syncing::Syncing;
 use anyhow::Context;
 use axum::error_handling::HandleErrorLayer;
@@ -39,8 +44,11 @@ use tokio::sync::RwLock;
 use tokio::task::JoinHandle;
 use tower_http::cors::CorsLayer;
 use tower_http::ServiceBuilderExt;
+use tracing::Instrument;
 
 const DEFAULT_MAX_CONNECTIONS: usize = 1024;
+const DEFAULT_MAX_REQUEST_BODY_SIZE: usize = 10 * 1024 * 1024;
+const DEFAULT_REQUEST_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(120);
 
 #[derive(Copy, Clone, Debug, Default, PartialEq, PartialOrd)]
 pub enum RpcVersion {
@@ -68,6 +76,8 @@ pub struct RpcServer {
     addr: SocketAddr,
     context: RpcContext,
     max_connections: usize,
+    max_request_body_size: usize,
+    request_timeout: std::time::Duration,
     cors: Option<CorsLayer>,
     default_version: RpcVersion,
 }
@@ -78,6 +88,8 @@ impl RpcServer {
             addr,
             context,
             max_connections: DEFAULT_MAX_CONNECTIONS,
+            max_request_body_size: DEFAULT_MAX_REQUEST_BODY_SIZE,
+            request_timeout: DEFAULT_REQUEST_TIMEOUT,
             cors: None,
             default_version,
         }
@@ -88,6 +100,19 @@ impl RpcServer {
         self
     }
 
+    /// Requests with a larger body are rejected with `413 Payload Too Large`.
+    pub fn with_max_request_body_size(mut self, max_size: usize) -> Self {
+        self.max_request_body_size = max_size;
+        self
+    }
+
+    /// Requests taking longer, including the time spent reading the request body, are
+    /// aborted with `408 Request Timeout`.
+    pub fn with_request_timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.request_timeout = timeout;
+        self
+    }
+
     pub fn with_cors(self, allowed_origins: AllowedOrigins) -> Self {
         Self {
             cors: Some(middleware::cors::with_allowed_origins(allowed_origins)),
@@ -96,14 +121,11 @@ impl RpcServer {
     }
 
     /// Starts the HTTP-RPC server.
+    ///
+    /// Connections are served within the current span.
     pub fn spawn(self) -> Result<(JoinHandle<anyhow::Result<()>>, SocketAddr), anyhow::Error> {
         use axum::routing::{get, post};
 
-        // TODO: make this configurable
-        const REQUEST_MAX_SIZE: usize = 10 * 1024 * 1024;
-        // TODO: make this configurable
-        const REQUEST_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(120);
-
         let listener = match std::net::TcpListener::bind(self.addr) {
             Ok(listener) => listener,
             Err(e) => return Err(e).context(format!("RPC address {} is already in use.
@@ -115,7 +137,10 @@ impl RpcServer {
         let addr = listener
             .local_addr()
             .context("Getting local address from listener")?;
-        let server = axum::Server::from_tcp(listener).context("Binding server to tcp listener")?;
+        let span = tracing::Span::current();
+        let server = axum::Server::from_tcp(listener)
+            .context("Binding server to tcp listener")?
+            .executor(InstrumentedExecutor(span.clone()));
 
         async fn handle_middleware_errors(err: axum::BoxError) -> (http::StatusCode, String) {
             use http::StatusCode;
@@ -143,8 +168,8 @@ impl RpcServer {
             // make sure to set request ids before the request reaches `TraceLayer`
             .set_x_request_id(middleware::request_id::RequestIdSource::default())
             .concurrency_limit(self.max_connections)
-            .layer(DefaultBodyLimit::max(REQUEST_MAX_SIZE))
-            .timeout(REQUEST_TIMEOUT)
+            .layer(DefaultBodyLimit::max(self.max_request_body_size))
+            .timeout(self.request_timeout)
             .layer(middleware::tracing::trace_layer())
             .option_layer(self.cors)
             .propagate_x_request_id();
@@ -204,12 +229,16 @@ impl RpcServer {
             .with_state(self.context.websocket.clone().unwrap_or_default())
             .layer(middleware);
 
-        let server_handle = tokio::spawn(async move {
-            server
-                .serve(router.into_make_service())
-                .await
-                .map_err(Into::into)
-        });
+        let server_handle = tokio::spawn(
+            async move {
+                server
+                    // The peer address is required to limit WebSocket connections per IP.
+                    .serve(router.into_make_service_with_connect_info::<SocketAddr>())
+                    .await
+                    .map_err(Into::into)
+            }
+            .instrument(span),
+        );
 
         Ok((server_handle, addr))
     }
@@ -222,6 +251,21 @@ impl RpcServer {
     }
 }
 
+/// Spawns the connection tasks of the HTTP-RPC server within a span, so that the logs and
+/// metrics of requests are attributed to the network being served.
+#[derive(Clone)]
+struct InstrumentedExecutor(tracing::Span);
+
+impl<F> hyper::rt::Executor<F> for InstrumentedExecutor
+where
+    F: std::future::Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    fn execute(&self, future: F) {
+        tokio::spawn(future.instrument(self.0.clone()));
+    }
+}
+
 pub struct SyncState {
     pub status: RwLock<Syncing>,
 }
@@ -0,0 +1,269 @@
+//! Submission of transactions to the local sequencer of a devnet.
+//!
+//! In devnet mode the write API hands transactions to this node's own block producer instead of
+//! forwarding them to the gateway. Each [LocalSequencer] submission is sent to the receiving end
+//! of its channel, which is served by the block producer. The producer replies once it has
+//! executed the transaction, with the same errors the gateway would report for it.
+//!
+//! Transactions are tracked by the [Mempool] before they are submitted, so that the block
+//! producer can update their status as soon as it includes them in a block.
+use pathfinder_common::transaction::{Transaction, TransactionVariant};
+use pathfinder_common::ChainId;
+use starknet_gateway_types::class_hash::compute_class_hash;
+use starknet_gateway_types::error::{KnownStarknetErrorCode, SequencerError, StarknetError};
+use starknet_gateway_types::reply::add_transaction::{
+    DeclareResponse, DeployAccountResponse, InvokeResponse,
+};
+use starknet_gateway_types::reply::Status;
+use tokio::sync::{mpsc, oneshot};
+
+use crate::mempool::{Mempool, TransactionKind};
+use crate::v02::types::request::{BroadcastedDeclareTransaction, BroadcastedTransaction};
+
+/// The number of submissions which can be queued before callers have to wait.
+const SUBMISSION_CAPACITY: usize = 64;
+
+/// The code the gateway replies with for accepted transactions.
+const TRANSACTION_RECEIVED: &str = "TRANSACTION_RECEIVED";
+
+#[derive(Debug)]
+pub struct Submission {
+    pub transaction: Transaction,
+    /// The definition of the class declared by the transaction, serialized like the gateway's.
+    /// Sierra classes still have to be compiled.
+    pub class_definition: Option<Vec<u8>>,
+    pub reply: oneshot::Sender<Result<(), StarknetError>>,
+}
+
+#[derive(Clone, Debug)]
+pub struct LocalSequencer {
+    submissions: mpsc::Sender<Submission>,
+}
+
+impl LocalSequencer {
+    /// Returns the handle along with the receiving end of its submissions.
+    pub fn new() -> (Self, mpsc::Receiver<Submission>) {
+        let (submissions, receiver) = mpsc::channel(SUBMISSION_CAPACITY);
+        (Self { submissions }, receiver)
+    }
+
+    pub(crate) async fn add_invoke_transaction(
+        &self,
+        transaction: Transaction,
+        mempool: &Mempool,
+    ) -> Result<InvokeResponse, SequencerError> {
+        let transaction_hash = transaction.hash;
+        self.submit(transaction, None, mempool, TransactionKind::Invoke)
+            .await?;
+
+        Ok(InvokeResponse {
+            code: TRANSACTION_RECEIVED.to_owned(),
+            transaction_hash,
+        })
+    }
+
+    pub(crate) async fn add_deploy_account(
+        &self,
+        transaction: Transaction,
+        mempool: &Mempool,
+    ) -> Result<DeployAccountResponse, SequencerError> {
+        let transaction_hash = transaction.hash;
+        self.submit(transaction, None, mempool, TransactionKind::DeployAccount)
+            .await?;
+
+        Ok(DeployAccountResponse {
+            code: TRANSACTION_RECEIVED.to_owned(),
+            transaction_hash,
+        })
+    }
+
+    pub(crate) async fn add_declare_transaction(
+        &self,
+        transaction: BroadcastedDeclareTransaction,
+        chain_id: ChainId,
+        mempool: &Mempool,
+    ) -> Result<DeclareResponse, SequencerError> {
+        let class_definition = match &transaction {
+            BroadcastedDeclareTransaction::V0(_) => {
+                return Err(starknet_error(
+                    KnownStarknetErrorCode::InvalidTransactionVersion,
+                    "Declare transactions of version 0 are not supported".to_owned(),
+                ));
+            }
+            BroadcastedDeclareTransaction::V1(tx) => tx.contract_class.serialize_to_json(),
+            BroadcastedDeclareTransaction::V2(tx) => tx.contract_class.serialize_to_json(),
+            BroadcastedDeclareTransaction::V3(tx) => tx.contract_class.serialize_to_json(),
+        }
+        // Converting the transaction below computes the class hash as well, and panics if that
+        // fails.
+        .and_then(|definition| compute_class_hash(&definition).map(|_| definition))
+        .map_err(|error| {
+            starknet_error(
+                KnownStarknetErrorCode::InvalidContractClass,
+                format!("{error:#}"),
+            )
+        })?;
+
+        let transaction = BroadcastedTransaction::Declare(transaction).into_common(chain_id);
+        let transaction_hash = transaction.hash;
+        let class_hash = match &transaction.variant {
+            TransactionVariant::DeclareV1(tx) => tx.class_hash,
+            TransactionVariant::DeclareV2(tx) => tx.class_hash,
+            TransactionVariant::DeclareV3(tx) => tx.class_hash,
+            _ => unreachable!("Converted from a declare transaction"),
+        };
+
+        self.submit(
+            transaction,
+            Some(class_definition),
+            mempool,
+            TransactionKind::Declare,
+        )
+        .await?;
+
+        Ok(DeclareResponse {
+            code: TRANSACTION_RECEIVED.to_owned(),
+            transaction_hash,
+            class_hash,
+        })
+    }
+
+    async fn submit(
+        &self,
+        transaction: Transaction,
+        class_definition: Option<Vec<u8>>,
+        mempool: &Mempool,
+        kind: TransactionKind,
+    ) -> Result<(), SequencerError> {
+        let transaction_hash = transaction.hash;
+        // Inserted before submitting, as the block producer may include the transaction in a
+        // block before the reply reaches us.
+        mempool.insert(transaction_hash, kind);
+
+        let (reply, receiver) = oneshot::channel();
+        let submission = Submission {
+            transaction,
+            class_definition,
+            reply,
+        };
+
+        let result = match self.submissions.send(submission).await {
+            Ok(()) => receiver
+                .await
+                .map_err(|_| not_running())
+                .and_then(|reply| reply.map_err(SequencerError::StarknetError)),
+            Err(_) => Err(not_running()),
+        };
+        if result.is_err() {
+            mempool.update(transaction_hash, Status::Rejected);
+        }
+
+        result
+    }
+}
+
+fn starknet_error(code: KnownStarknetErrorCode, message: String) -> SequencerError {
+    SequencerError::StarknetError(StarknetError {
+        code: code.into(),
+        message,
+    })
+}
+
+fn not_running() -> SequencerError {
+    starknet_error(
+        KnownStarknetErrorCode::TransactionFailed,
+        "Local sequencer is not running".to_owned(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::transaction::InvokeTransactionV1;
+    use starknet_gateway_types::error::StarknetErrorCode;
+
+    use super::*;
+
+    fn invoke() -> Transaction {
+        Transaction {
+            hash: transaction_hash!("0x123"),
+            variant: TransactionVariant::InvokeV1(InvokeTransactionV1 {
+                sender_address: contract_address!("0x456"),
+                ..Default::default()
+            }),
+        }
+    }
+
+    #[tokio::test]
+    async fn accepted() {
+        let (sequencer, mut submissions) = LocalSequencer::new();
+        let mempool = Mempool::default();
+
+        let tracked = mempool.clone();
+        tokio::spawn(async move {
+            let submission = submissions.recv().await.unwrap();
+            assert_eq!(submission.transaction, invoke());
+            assert_eq!(submission.class_definition, None);
+            // Already tracked while the block producer executes the transaction.
+            let transactions = tracked.transactions();
+            assert_eq!(transactions.len(), 1);
+            assert_eq!(transactions[0].hash, transaction_hash!("0x123"));
+            assert_eq!(transactions[0].status, Status::Received);
+            submission.reply.send(Ok(())).unwrap();
+        });
+
+        let response = sequencer
+            .add_invoke_transaction(invoke(), &mempool)
+            .await
+            .unwrap();
+        assert_eq!(
+            response,
+            InvokeResponse {
+                code: TRANSACTION_RECEIVED.to_owned(),
+                transaction_hash: transaction_hash!("0x123"),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn rejected() {
+        let (sequencer, mut submissions) = LocalSequencer::new();
+        let mempool = Mempool::default();
+
+        tokio::spawn(async move {
+            let submission = submissions.recv().await.unwrap();
+            let error = StarknetError {
+                code: KnownStarknetErrorCode::InvalidTransactionNonce.into(),
+                message: "Invalid nonce".to_owned(),
+            };
+            submission.reply.send(Err(error)).unwrap();
+        });
+
+        let error = sequencer
+            .add_invoke_transaction(invoke(), &mempool)
+            .await
+            .unwrap_err();
+        let expected = StarknetErrorCode::from(KnownStarknetErrorCode::InvalidTransactionNonce);
+        assert_matches::assert_matches!(
+            error,
+            SequencerError::StarknetError(e) if e.code == expected
+        );
+        assert_eq!(mempool.transactions()[0].status, Status::Rejected);
+    }
+
+    #[tokio::test]
+    async fn sequencer_not_running() {
+        let (sequencer, submissions) = LocalSequencer::new();
+        drop(submissions);
+
+        let error = sequencer
+            .add_invoke_transaction(invoke(), &Mempool::default())
+            .await
+            .unwrap_err();
+        let expected = StarknetErrorCode::from(KnownStarknetErrorCode::TransactionFailed);
+        assert_matches::assert_matches!(
+            error,
+            SequencerError::StarknetError(e) if e.code == expected
+        );
+    }
+}
@@ -0,0 +1,348 @@
+//! Tracks transactions submitted through this node.
+//!
+//! Transactions are added once the gateway has accepted them, after which [poll] follows their
+//! status until they are included in a block or rejected. Entries are kept for [EXPIRY] after
+//! submission so that their final status can still be listed.
+//!
+//! Transactions which have already been synced are resolved from local storage. Only the
+//! remaining ones are polled from the gateway, at most [MAX_POLLS_PER_ROUND] per round.
+//!
+//! Accepted invoke and deploy account transactions are also announced to
+//! [subscribers](Mempool::subscribe), e.g. for propagation to P2P peers.
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use anyhow::Context;
+use futures::StreamExt;
+use pathfinder_common::transaction::Transaction;
+use pathfinder_common::TransactionHash;
+use pathfinder_storage::Storage;
+use starknet_gateway_client::GatewayApi;
+use starknet_gateway_types::reply::Status;
+use tokio::sync::broadcast;
+
+/// How long a transaction is tracked after its submission.
+pub const EXPIRY: Duration = Duration::from_secs(60 * 60);
+/// The maximum number of tracked transactions. The oldest transaction is dropped to make room
+/// for new ones.
+const CAPACITY: usize = 10_000;
+const POLL_INTERVAL: Duration = Duration::from_secs(10);
+/// The maximum number of transactions polled from the gateway per [POLL_INTERVAL]. The others
+/// are polled in the following rounds.
+const MAX_POLLS_PER_ROUND: usize = 100;
+/// The maximum number of concurrent gateway requests.
+const MAX_CONCURRENT_POLLS: usize = 8;
+/// The number of announced transactions a slow subscriber can lag behind before missing some.
+const ANNOUNCEMENT_CAPACITY: usize = 1024;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TransactionKind {
+    Declare,
+    DeployAccount,
+    Invoke,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MempoolTransaction {
+    pub hash: TransactionHash,
+    pub kind: TransactionKind,
+    pub submitted_at: SystemTime,
+    pub status: Status,
+}
+
+#[derive(Clone)]
+pub struct Mempool {
+    transactions: Arc<Mutex<HashMap<TransactionHash, MempoolTransaction>>>,
+    announcements: broadcast::Sender<Transaction>,
+}
+
+impl Default for Mempool {
+    fn default() -> Self {
+        Self {
+            transactions: Default::default(),
+            announcements: broadcast::channel(ANNOUNCEMENT_CAPACITY).0,
+        }
+    }
+}
+
+impl Mempool {
+    pub fn insert(&self, hash: TransactionHash, kind: TransactionKind) {
+        let mut transactions = self.transactions.lock().unwrap();
+
+        if transactions.len() >= CAPACITY && !transactions.contains_key(&hash) {
+            let oldest = transactions
+                .values()
+                .min_by_key(|tx| tx.submitted_at)
+                .map(|tx| tx.hash);
+            if let Some(oldest) = oldest {
+                transactions.remove(&oldest);
+            }
+        }
+
+        transactions.insert(
+            hash,
+            MempoolTransaction {
+                hash,
+                kind,
+                submitted_at: SystemTime::now(),
+                status: Status::Received,
+            },
+        );
+    }
+
+    /// Returns the tracked transactions, oldest first.
+    pub fn transactions(&self) -> Vec<MempoolTransaction> {
+        let mut transactions = self
+            .transactions
+            .lock()
+            .unwrap()
+            .values()
+            .cloned()
+            .collect::<Vec<_>>();
+        transactions.sort_by_key(|tx| tx.submitted_at);
+        transactions
+    }
+
+    /// Announces a transaction accepted by the gateway to all current subscribers.
+    pub fn announce(&self, transaction: Transaction) {
+        // Fails only if there are no subscribers, e.g. when P2P is disabled.
+        let _ = self.announcements.send(transaction);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Transaction> {
+        self.announcements.subscribe()
+    }
+
+    /// Sets the status of a tracked transaction. Used by the local sequencer of a devnet, whose
+    /// transactions are not polled from the gateway.
+    pub fn update(&self, hash: TransactionHash, status: Status) {
+        if let Some(tx) = self.transactions.lock().unwrap().get_mut(&hash) {
+            tx.status = status;
+        }
+    }
+
+    fn remove_expired(&self, now: SystemTime) {
+        self.transactions.lock().unwrap().retain(|_, tx| {
+            now.duration_since(tx.submitted_at)
+                .map(|age| age < EXPIRY)
+                .unwrap_or(true)
+        });
+    }
+}
+
+/// Returns true once a transaction's status can no longer change, other than from L2 to L1
+/// acceptance.
+fn is_final(status: Status) -> bool {
+    matches!(
+        status,
+        Status::Rejected
+            | Status::Reverted
+            | Status::Aborted
+            | Status::AcceptedOnL2
+            | Status::AcceptedOnL1
+    )
+}
+
+/// Polls the status of the tracked transactions from storage and the gateway, and removes
+/// expired transactions, forever.
+///
+/// `gateway` should not share its circuit breaker with the clients used for syncing, so that a
+/// backlog of polls cannot hold back the sync.
+pub async fn poll(mempool: Mempool, storage: Storage, gateway: impl GatewayApi) {
+    let mut interval = tokio::time::interval(POLL_INTERVAL);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    // Rotates through the transactions polled from the gateway when there are more than
+    // MAX_POLLS_PER_ROUND of them.
+    let mut offset = 0;
+
+    loop {
+        interval.tick().await;
+
+        mempool.remove_expired(SystemTime::now());
+
+        let hashes = mempool
+            .transactions()
+            .into_iter()
+            .filter(|tx| !is_final(tx.status))
+            .map(|tx| tx.hash)
+            .collect::<Vec<_>>();
+        if hashes.is_empty() {
+            continue;
+        }
+
+        let stored = tokio::task::spawn_blocking({
+            let storage = storage.clone();
+            let hashes = hashes.clone();
+            move || stored_statuses(&storage, &hashes)
+        })
+        .await
+        .context("Joining database task")
+        .and_then(|result| result);
+        let stored = match stored {
+            Ok(stored) => stored,
+            Err(error) => {
+                tracing::warn!(?error, "Querying stored transaction statuses failed");
+                Default::default()
+            }
+        };
+        for (hash, status) in &stored {
+            mempool.update(*hash, *status);
+        }
+
+        let unresolved = hashes
+            .into_iter()
+            .filter(|hash| !stored.contains_key(hash))
+            .collect::<Vec<_>>();
+        if unresolved.is_empty() {
+            continue;
+        }
+
+        offset %= unresolved.len();
+        let count = unresolved.len().min(MAX_POLLS_PER_ROUND);
+        let polled = unresolved.iter().cycle().skip(offset).take(count);
+        offset += count;
+
+        let gateway = &gateway;
+        let mut replies = futures::stream::iter(polled)
+            .map(|hash| async move { (*hash, gateway.transaction(*hash).await) })
+            .buffer_unordered(MAX_CONCURRENT_POLLS);
+        while let Some((hash, reply)) = replies.next().await {
+            match reply {
+                Ok(reply) => mempool.update(hash, reply.status),
+                Err(error) => {
+                    tracing::debug!(%hash, %error, "Polling transaction status failed")
+                }
+            }
+        }
+    }
+}
+
+/// Returns the status of each of the transactions which are stored locally.
+fn stored_statuses(
+    storage: &Storage,
+    hashes: &[TransactionHash],
+) -> anyhow::Result<HashMap<TransactionHash, Status>> {
+    let mut db = storage
+        .connection()
+        .context("Opening database connection")?;
+    let db_tx = db.transaction().context("Creating database transaction")?;
+
+    let mut statuses = HashMap::new();
+    for hash in hashes {
+        let Some((_, receipt, block_hash)) = db_tx
+            .transaction_with_receipt(*hash)
+            .context("Fetching receipt from database")?
+        else {
+            continue;
+        };
+
+        let status = if receipt.is_reverted() {
+            Status::Reverted
+        } else if db_tx
+            .block_is_l1_accepted(block_hash.into())
+            .context("Querying block's status")?
+        {
+            Status::AcceptedOnL1
+        } else {
+            Status::AcceptedOnL2
+        };
+        statuses.insert(*hash, status);
+    }
+
+    Ok(statuses)
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::transaction::TransactionVariant;
+
+    use super::*;
+
+    #[test]
+    fn insert_and_update() {
+        let mempool = Mempool::default();
+        mempool.insert(transaction_hash_bytes!(b"tx 0"), TransactionKind::Invoke);
+        mempool.insert(transaction_hash_bytes!(b"tx 1"), TransactionKind::Declare);
+        mempool.update(transaction_hash_bytes!(b"tx 0"), Status::AcceptedOnL2);
+
+        let mut transactions = mempool
+            .transactions()
+            .into_iter()
+            .map(|tx| (tx.hash, tx.kind, tx.status))
+            .collect::<Vec<_>>();
+        // Both transactions may have been submitted at the same time.
+        transactions.sort_by_key(|(hash, _, _)| *hash);
+        assert_eq!(
+            transactions,
+            vec![
+                (
+                    transaction_hash_bytes!(b"tx 0"),
+                    TransactionKind::Invoke,
+                    Status::AcceptedOnL2
+                ),
+                (
+                    transaction_hash_bytes!(b"tx 1"),
+                    TransactionKind::Declare,
+                    Status::Received
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn announce() {
+        let mempool = Mempool::default();
+        // Announcing without subscribers is fine.
+        mempool.announce(Transaction {
+            hash: transaction_hash_bytes!(b"tx 0"),
+            variant: TransactionVariant::InvokeV1(Default::default()),
+        });
+
+        let mut subscriber = mempool.subscribe();
+        mempool.announce(Transaction {
+            hash: transaction_hash_bytes!(b"tx 1"),
+            variant: TransactionVariant::InvokeV1(Default::default()),
+        });
+
+        let announced = subscriber.try_recv().unwrap();
+        assert_eq!(announced.hash, transaction_hash_bytes!(b"tx 1"));
+        assert!(subscriber.try_recv().is_err());
+    }
+
+    #[test]
+    fn stored_statuses() {
+        let storage = crate::test_utils::setup_storage();
+
+        let statuses = super::stored_statuses(
+            &storage,
+            &[
+                transaction_hash_bytes!(b"txn 0"),
+                transaction_hash_bytes!(b"txn reverted"),
+                transaction_hash_bytes!(b"unknown"),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(
+            statuses,
+            HashMap::from([
+                (transaction_hash_bytes!(b"txn 0"), Status::AcceptedOnL1),
+                (transaction_hash_bytes!(b"txn reverted"), Status::Reverted),
+            ])
+        );
+    }
+
+    #[test]
+    fn expiry() {
+        let mempool = Mempool::default();
+        mempool.insert(transaction_hash_bytes!(b"tx 0"), TransactionKind::Invoke);
+
+        mempool.remove_expired(SystemTime::now());
+        assert_eq!(mempool.transactions().len(), 1);
+
+        mempool.remove_expired(SystemTime::now() + EXPIRY);
+        assert!(mempool.transactions().is_empty());
+    }
+}
@@ -1,7 +1,9 @@
-use http::HeaderValue;
+use http::{HeaderName, HeaderValue};
 use pathfinder_common::AllowedOrigins;
 use tower_http::cors::{AllowOrigin, CorsLayer};
 
+use crate::jsonrpc::DEFAULT_BLOCK_ID_HEADER;
+
 pub fn with_allowed_origins(allowed_origins: AllowedOrigins) -> CorsLayer {
     let allowed_origins = match allowed_origins {
         AllowedOrigins::Any => AllowOrigin::any(),
@@ -14,7 +16,10 @@ pub fn with_allowed_origins(allowed_origins: AllowedOrigins) -> CorsLayer {
     CorsLayer::new()
         .allow_methods([hyper::Method::POST])
         .allow_origin(allowed_origins)
-        .allow_headers([hyper::header::CONTENT_TYPE])
+        .allow_headers([
+            hyper::header::CONTENT_TYPE,
+            HeaderName::from_static(DEFAULT_BLOCK_ID_HEADER),
+        ])
 }
 
 #[cfg(test)]
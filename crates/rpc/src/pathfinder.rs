@@ -2,10 +2,39 @@ use crate::jsonrpc::{RpcRouter, RpcRouterBuilder};
 
 pub(crate) mod methods;
 
+// The binding is only returned unchanged if the `query` feature is disabled.
+#[allow(clippy::let_and_return)]
 #[rustfmt::skip]
 pub fn register_routes() -> RpcRouterBuilder {
-    RpcRouter::builder(crate::RpcVersion::PathfinderV01)
-        .register("pathfinder_version",              || { pathfinder_common::consts::VERGEN_GIT_DESCRIBE })
-        .register("pathfinder_getProof",             methods::get_proof)
-        .register("pathfinder_getTransactionStatus", methods::get_transaction_status)
+    let router = RpcRouter::builder(crate::RpcVersion::PathfinderV01)
+        .register("pathfinder_version",                || { pathfinder_common::consts::VERGEN_GIT_DESCRIBE })
+        .register("pathfinder_getProof",               methods::get_proof)
+        .register("pathfinder_getBlockRange",          methods::get_block_range)
+        .register("pathfinder_getContractStateRoot",   methods::get_contract_state_root)
+        .register("pathfinder_getContractHistory",     methods::get_contract_history)
+        .register("pathfinder_getStateDiff",           methods::get_state_diff)
+        .register("pathfinder_getStateStats",          methods::get_state_stats)
+        .register("pathfinder_getGasPrices",           methods::get_gas_prices)
+        .register("pathfinder_getStorageAtBatch",      methods::get_storage_at_batch)
+        .register("pathfinder_getErc20Balances",       methods::get_erc20_balances)
+        .register("pathfinder_multicall",              methods::multicall)
+        .register("pathfinder_getClassEntryPoints",    methods::get_class_entry_points)
+        .register("pathfinder_getDeclaredClasses",     methods::get_declared_classes)
+        .register("pathfinder_getDeployedContracts",   methods::get_deployed_contracts)
+        .register("pathfinder_getEventsByTransaction", methods::get_events_by_transaction)
+        .register("pathfinder_getL2ToL1MessageProof",  methods::get_l2_to_l1_message_proof)
+        .register("pathfinder_getReorgHistory",        methods::get_reorg_history)
+        .register("pathfinder_getTransactionReceipt",  methods::get_transaction_receipt)
+        .register("pathfinder_getTransactionStatus",   methods::get_transaction_status)
+        .register("pathfinder_hashTypedData",          methods::hash_typed_data)
+        .register("pathfinder_pendingTransactions",    methods::pending_transactions)
+        .register("pathfinder_peers",                  methods::peers)
+        .register("pathfinder_connectPeer",            methods::connect_peer)
+        .register("pathfinder_banPeer",                methods::ban_peer);
+
+    #[cfg(feature = "query")]
+    let router = router
+        .register("pathfinder_query",                  methods::query);
+
+    router
 }
@@ -1,5 +1,51 @@
+mod ban_peer;
+mod connect_peer;
+mod get_block_range;
+mod get_class_entry_points;
+mod get_contract_history;
+mod get_contract_state_root;
+mod get_declared_classes;
+mod get_deployed_contracts;
+mod get_erc20_balances;
+mod get_events_by_transaction;
+mod get_gas_prices;
+mod get_l2_to_l1_message_proof;
 mod get_proof;
+mod get_reorg_history;
+mod get_state_diff;
+mod get_state_stats;
+mod get_storage_at_batch;
+mod get_transaction_receipt;
 mod get_transaction_status;
+mod hash_typed_data;
+mod multicall;
+mod peers;
+mod pending_transactions;
+#[cfg(feature = "query")]
+mod query;
 
+pub(crate) use ban_peer::ban_peer;
+pub(crate) use connect_peer::connect_peer;
+pub(crate) use get_block_range::get_block_range;
+pub(crate) use get_class_entry_points::get_class_entry_points;
+pub(crate) use get_contract_history::get_contract_history;
+pub(crate) use get_contract_state_root::get_contract_state_root;
+pub(crate) use get_declared_classes::get_declared_classes;
+pub(crate) use get_deployed_contracts::get_deployed_contracts;
+pub(crate) use get_erc20_balances::get_erc20_balances;
+pub(crate) use get_events_by_transaction::get_events_by_transaction;
+pub(crate) use get_gas_prices::get_gas_prices;
+pub(crate) use get_l2_to_l1_message_proof::get_l2_to_l1_message_proof;
 pub(crate) use get_proof::get_proof;
+pub(crate) use get_reorg_history::get_reorg_history;
+pub(crate) use get_state_diff::get_state_diff;
+pub(crate) use get_state_stats::get_state_stats;
+pub(crate) use get_storage_at_batch::get_storage_at_batch;
+pub(crate) use get_transaction_receipt::get_transaction_receipt;
 pub(crate) use get_transaction_status::get_transaction_status;
+pub(crate) use hash_typed_data::hash_typed_data;
+pub(crate) use multicall::multicall;
+pub(crate) use peers::peers;
+pub(crate) use pending_transactions::pending_transactions;
+#[cfg(feature = "query")]
+pub(crate) use query::query;
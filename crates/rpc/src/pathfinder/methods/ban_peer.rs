@@ -0,0 +1,55 @@
+use crate::context::RpcContext;
+
+#[derive(serde::Deserialize, Debug, PartialEq, Eq)]
+pub struct BanPeerInput {
+    peer_id: String,
+}
+
+crate::error::generate_rpc_error_subset!(BanPeerError: PeerAdminDisabled);
+
+/// Disconnects a P2P peer and prevents it from reconnecting, also across restarts.
+pub async fn ban_peer(context: RpcContext, input: BanPeerInput) -> Result<(), BanPeerError> {
+    let peer_admin = context.peer_admin.ok_or(BanPeerError::PeerAdminDisabled)?;
+    // Failing to ban is most likely caused by an invalid peer ID, so the details are reported.
+    peer_admin
+        .ban_peer(input.peer_id)
+        .await
+        .map_err(BanPeerError::Custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::peer_admin::{PeerAdmin, PeerAdminRequest};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn disabled() {
+        let context = RpcContext::for_tests();
+        let input = BanPeerInput {
+            peer_id: "peer".to_owned(),
+        };
+        let error = ban_peer(context, input).await.unwrap_err();
+        assert_matches::assert_matches!(error, BanPeerError::PeerAdminDisabled);
+    }
+
+    #[tokio::test]
+    async fn banned() {
+        let (peer_admin, mut requests) = PeerAdmin::new();
+        let context = RpcContext::for_tests().with_peer_admin(peer_admin);
+
+        let handler = tokio::spawn(async move {
+            let Some(PeerAdminRequest::BanPeer { peer_id, reply }) = requests.recv().await else {
+                panic!("Expected a ban peer request");
+            };
+            let _ = reply.send(Ok(()));
+            peer_id
+        });
+
+        let input = BanPeerInput {
+            peer_id: "peer".to_owned(),
+        };
+        ban_peer(context, input).await.unwrap();
+        assert_eq!(handler.await.unwrap(), "peer");
+    }
+}
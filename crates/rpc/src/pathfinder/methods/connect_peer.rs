@@ -0,0 +1,64 @@
+use crate::context::RpcContext;
+
+#[derive(serde::Deserialize, Debug, PartialEq, Eq)]
+pub struct ConnectPeerInput {
+    /// A multiaddress including the peer ID.
+    address: String,
+}
+
+crate::error::generate_rpc_error_subset!(ConnectPeerError: PeerAdminDisabled);
+
+/// Connects to a P2P peer, in addition to the peers discovered through the DHT.
+pub async fn connect_peer(
+    context: RpcContext,
+    input: ConnectPeerInput,
+) -> Result<(), ConnectPeerError> {
+    let peer_admin = context
+        .peer_admin
+        .ok_or(ConnectPeerError::PeerAdminDisabled)?;
+    // Failing to connect is most likely caused by the input, so the details are reported.
+    peer_admin
+        .connect_peer(input.address)
+        .await
+        .map_err(ConnectPeerError::Custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::peer_admin::{PeerAdmin, PeerAdminRequest};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn disabled() {
+        let context = RpcContext::for_tests();
+        let input = ConnectPeerInput {
+            address: "/ip4/127.0.0.1/tcp/1".to_owned(),
+        };
+        let error = connect_peer(context, input).await.unwrap_err();
+        assert_matches::assert_matches!(error, ConnectPeerError::PeerAdminDisabled);
+    }
+
+    #[tokio::test]
+    async fn failure_is_reported() {
+        let (peer_admin, mut requests) = PeerAdmin::new();
+        let context = RpcContext::for_tests().with_peer_admin(peer_admin);
+
+        tokio::spawn(async move {
+            let Some(PeerAdminRequest::ConnectPeer { address, reply }) = requests.recv().await
+            else {
+                panic!("Expected a connect peer request");
+            };
+            let _ = reply.send(Err(anyhow::anyhow!("Invalid address {address}")));
+        });
+
+        let input = ConnectPeerInput {
+            address: "invalid".to_owned(),
+        };
+        let error = connect_peer(context, input).await.unwrap_err();
+        assert_matches::assert_matches!(
+            error,
+            ConnectPeerError::Custom(e) if e.to_string() == "Invalid address invalid"
+        );
+    }
+}
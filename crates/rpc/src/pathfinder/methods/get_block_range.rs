@@ -0,0 +1,235 @@
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+use crate::context::RpcContext;
+use crate::v07::dto;
+use pathfinder_common::BlockNumber;
+
+/// The maximum number of blocks returned by a single request.
+const MAX_BLOCKS: u64 = 100;
+
+#[derive(Deserialize, Debug, PartialEq, Eq)]
+pub struct GetBlockRangeInput {
+    /// The first block to return. To continue a previous request, set this to its
+    /// `continuation_token`.
+    pub start: BlockNumber,
+    /// The last block to return (inclusive).
+    pub end: BlockNumber,
+    pub scope: Scope,
+}
+
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Scope {
+    /// Only the block headers.
+    #[serde(rename = "HEADERS")]
+    Headers,
+    /// The block headers, transactions and receipts.
+    #[serde(rename = "FULL")]
+    Full,
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum Block {
+    Header(dto::header::Header),
+    Full(dto::receipt::BlockWithReceipts),
+}
+
+#[derive(Serialize)]
+pub struct GetBlockRangeOutput {
+    blocks: Vec<Block>,
+    /// Present if the range was not exhausted. Pass this as `start` to fetch the next
+    /// batch of blocks.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    continuation_token: Option<BlockNumber>,
+}
+
+crate::error::generate_rpc_error_subset!(GetBlockRangeError: BlockNotFound);
+
+/// Returns consecutive blocks in `[start, end]`, in batches of at most [MAX_BLOCKS].
+///
+/// Blocks beyond the current chain head are not returned and do not produce a
+/// continuation token.
+pub async fn get_block_range(
+    context: RpcContext,
+    input: GetBlockRangeInput,
+) -> Result<GetBlockRangeOutput, GetBlockRangeError> {
+    if input.start > input.end {
+        return Err(GetBlockRangeError::Custom(anyhow::anyhow!(
+            "start must not be greater than end"
+        )));
+    }
+
+    let span = tracing::Span::current();
+
+    let jh = tokio::task::spawn_blocking(move || {
+        let _g = span.enter();
+
+        let mut db = context
+            .storage
+            .connection()
+            .context("Opening database connection")?;
+        let db = db.transaction().context("Creating database transaction")?;
+
+        let last = input.end.get().min(input.start.get() + MAX_BLOCKS - 1);
+
+        let mut blocks = Vec::new();
+        for number in input.start.get()..=last {
+            let block_id = pathfinder_storage::BlockId::Number(BlockNumber::new_or_panic(number));
+
+            let Some(header) = db.block_header(block_id).context("Fetching block header")? else {
+                break;
+            };
+
+            let block = match input.scope {
+                Scope::Headers => Block::Header(header.into()),
+                Scope::Full => {
+                    let body = db
+                        .transaction_data_for_block(block_id)
+                        .context("Fetching transaction data")?
+                        .context("Transaction data missing")?;
+
+                    let is_l1_accepted = db
+                        .block_is_l1_accepted(block_id)
+                        .context("Fetching block finality")?;
+
+                    Block::Full(dto::receipt::BlockWithReceipts::from_common(
+                        header,
+                        body,
+                        is_l1_accepted,
+                        true,
+                    ))
+                }
+            };
+
+            blocks.push(block);
+        }
+
+        if blocks.is_empty() {
+            return Err(GetBlockRangeError::BlockNotFound);
+        }
+
+        let continuation_token = if blocks.len() as u64 == MAX_BLOCKS && last < input.end.get() {
+            Some(BlockNumber::new_or_panic(last + 1))
+        } else {
+            None
+        };
+
+        Ok(GetBlockRangeOutput {
+            blocks,
+            continuation_token,
+        })
+    });
+
+    jh.await.context("Database read panic or shutting down")?
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn block_numbers(output: &GetBlockRangeOutput) -> Vec<serde_json::Value> {
+        let output = serde_json::to_value(output).unwrap();
+        output["blocks"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|block| block["block_number"].clone())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn headers() {
+        let context = RpcContext::for_tests();
+        let input = GetBlockRangeInput {
+            start: BlockNumber::GENESIS,
+            end: BlockNumber::new_or_panic(1),
+            scope: Scope::Headers,
+        };
+
+        let output = get_block_range(context, input).await.unwrap();
+
+        assert_eq!(block_numbers(&output), vec![json!(0), json!(1)]);
+        assert_eq!(output.continuation_token, None);
+        let output = serde_json::to_value(&output).unwrap();
+        assert!(output["blocks"][0].get("transactions").is_none());
+    }
+
+    #[tokio::test]
+    async fn full() {
+        let context = RpcContext::for_tests();
+        let input = GetBlockRangeInput {
+            start: BlockNumber::new_or_panic(1),
+            end: BlockNumber::new_or_panic(2),
+            scope: Scope::Full,
+        };
+
+        let output = get_block_range(context, input).await.unwrap();
+
+        assert_eq!(block_numbers(&output), vec![json!(1), json!(2)]);
+        let output = serde_json::to_value(&output).unwrap();
+        assert_eq!(
+            output["blocks"][0]["transactions"]
+                .as_array()
+                .unwrap()
+                .len(),
+            2
+        );
+        assert_eq!(
+            output["blocks"][1]["transactions"]
+                .as_array()
+                .unwrap()
+                .len(),
+            5
+        );
+    }
+
+    #[tokio::test]
+    async fn stops_at_chain_head() {
+        let context = RpcContext::for_tests();
+        let input = GetBlockRangeInput {
+            start: BlockNumber::new_or_panic(1),
+            end: BlockNumber::new_or_panic(1000),
+            scope: Scope::Headers,
+        };
+
+        let output = get_block_range(context, input).await.unwrap();
+
+        assert_eq!(block_numbers(&output), vec![json!(1), json!(2)]);
+        assert_eq!(output.continuation_token, None);
+    }
+
+    #[tokio::test]
+    async fn block_not_found() {
+        let context = RpcContext::for_tests();
+        let input = GetBlockRangeInput {
+            start: BlockNumber::new_or_panic(1000),
+            end: BlockNumber::new_or_panic(2000),
+            scope: Scope::Headers,
+        };
+
+        let err = get_block_range(context, input).await.unwrap_err();
+        assert_matches::assert_matches!(err, GetBlockRangeError::BlockNotFound);
+    }
+
+    #[test]
+    fn parse_scope() {
+        let input = json!({
+            "start": 10,
+            "end": 20,
+            "scope": "FULL"
+        });
+
+        let input = serde_json::from_value::<GetBlockRangeInput>(input).unwrap();
+        assert_eq!(
+            input,
+            GetBlockRangeInput {
+                start: BlockNumber::new_or_panic(10),
+                end: BlockNumber::new_or_panic(20),
+                scope: Scope::Full,
+            }
+        );
+    }
+}
@@ -0,0 +1,304 @@
+use std::collections::HashMap;
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+use crate::context::RpcContext;
+use crate::error::ApplicationError;
+use pathfinder_common::{ClassHash, EntryPoint};
+
+#[derive(Deserialize, Debug, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct GetClassEntryPointsInput {
+    pub class_hash: ClassHash,
+    /// Only return the entry points with this selector.
+    #[serde(default)]
+    pub selector: Option<EntryPoint>,
+}
+
+#[derive(Serialize, Debug, Default, PartialEq, Eq)]
+pub struct ClassEntryPoints {
+    pub external: Vec<NamedEntryPoint>,
+    pub l1_handler: Vec<NamedEntryPoint>,
+    pub constructor: Vec<NamedEntryPoint>,
+}
+
+#[derive(Serialize, Debug, PartialEq, Eq)]
+pub struct NamedEntryPoint {
+    pub selector: EntryPoint,
+    /// The name of the ABI function whose selector matches, if any.
+    pub name: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum GetClassEntryPointsError {
+    Internal(anyhow::Error),
+    Custom(anyhow::Error),
+    ClassHashNotFound(ClassHash),
+}
+
+impl From<anyhow::Error> for GetClassEntryPointsError {
+    fn from(e: anyhow::Error) -> Self {
+        Self::Internal(e)
+    }
+}
+
+impl From<GetClassEntryPointsError> for ApplicationError {
+    fn from(value: GetClassEntryPointsError) -> Self {
+        match value {
+            GetClassEntryPointsError::ClassHashNotFound(class_hash) => {
+                ApplicationError::ClassHashNotFound {
+                    class_hash: Some(class_hash),
+                }
+            }
+            GetClassEntryPointsError::Internal(e) => ApplicationError::Internal(e),
+            GetClassEntryPointsError::Custom(e) => ApplicationError::Custom(e),
+        }
+    }
+}
+
+/// Lists the entry points of a class, naming them by the functions of the class' ABI.
+///
+/// ABIs are provided by whoever declared the class and are not verified, so functions which are
+/// missing from the ABI, or ABIs which cannot be parsed, result in unnamed entry points.
+pub async fn get_class_entry_points(
+    context: RpcContext,
+    input: GetClassEntryPointsInput,
+) -> Result<ClassEntryPoints, GetClassEntryPointsError> {
+    let storage = context.storage.clone();
+    let span = tracing::Span::current();
+
+    let jh = tokio::task::spawn_blocking(move || {
+        let _g = span.enter();
+        let mut db = storage
+            .connection()
+            .context("Opening database connection")?;
+
+        let tx = db.transaction().context("Creating database transaction")?;
+
+        let definition = tx
+            .class_definition(input.class_hash)
+            .context("Querying class definition")?
+            .ok_or(GetClassEntryPointsError::ClassHashNotFound(
+                input.class_hash,
+            ))?;
+
+        Ok(entry_points(&definition, input.selector)?)
+    });
+
+    jh.await.context("Database read panic or shutting down")?
+}
+
+/// Parses the entry points of a Cairo or Sierra class definition.
+fn entry_points(
+    definition: &[u8],
+    selector: Option<EntryPoint>,
+) -> anyhow::Result<ClassEntryPoints> {
+    let definition = serde_json::from_slice::<serde_json::Value>(definition)
+        .context("Parsing class definition")?;
+
+    let names = definition.get("abi").map(abi_names).unwrap_or_default();
+
+    let entry_points_by_type = definition
+        .get("entry_points_by_type")
+        .context("entry_points_by_type property is missing")?;
+    let parse = |entry_point_type: &str| -> anyhow::Result<Vec<NamedEntryPoint>> {
+        #[derive(Deserialize)]
+        struct SelectorOnly {
+            selector: EntryPoint,
+        }
+
+        let entry_points = match entry_points_by_type.get(entry_point_type) {
+            Some(entry_points) => Vec::<SelectorOnly>::deserialize(entry_points)
+                .with_context(|| format!("Parsing {entry_point_type} entry points"))?,
+            None => Vec::new(),
+        };
+
+        Ok(entry_points
+            .into_iter()
+            .filter(|x| selector.map_or(true, |selector| selector == x.selector))
+            .map(|x| NamedEntryPoint {
+                selector: x.selector,
+                name: names.get(&x.selector).cloned(),
+            })
+            .collect())
+    };
+
+    Ok(ClassEntryPoints {
+        external: parse("EXTERNAL")?,
+        l1_handler: parse("L1_HANDLER")?,
+        constructor: parse("CONSTRUCTOR")?,
+    })
+}
+
+/// Maps the selectors of the functions in an ABI to their names.
+///
+/// Cairo ABIs are JSON arrays while Sierra ABIs are JSON arrays encoded as a string, whose
+/// functions may also be nested in interfaces.
+fn abi_names(abi: &serde_json::Value) -> HashMap<EntryPoint, String> {
+    fn collect(entries: &[serde_json::Value], names: &mut HashMap<EntryPoint, String>) {
+        for entry in entries {
+            let Some(name) = entry.get("name").and_then(|x| x.as_str()) else {
+                continue;
+            };
+
+            match entry.get("type").and_then(|x| x.as_str()) {
+                Some("function" | "l1_handler" | "constructor") => {
+                    names.insert(EntryPoint::from_name(name), name.to_owned());
+                }
+                Some("interface") => {
+                    if let Some(items) = entry.get("items").and_then(|x| x.as_array()) {
+                        collect(items, names);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let abi = match abi {
+        serde_json::Value::String(abi) => match serde_json::from_str(abi) {
+            Ok(abi) => abi,
+            Err(_) => return HashMap::new(),
+        },
+        other => other.clone(),
+    };
+
+    let mut names = HashMap::new();
+    if let Some(entries) = abi.as_array() {
+        collect(entries, &mut names);
+    }
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_matches::assert_matches;
+    use pathfinder_common::macro_prelude::*;
+    use serde_json::json;
+
+    #[test]
+    fn parsing() {
+        let input = json!({
+            "class_hash": "0x1",
+            "selector": "0x2",
+        });
+
+        let input = serde_json::from_value::<GetClassEntryPointsInput>(input).unwrap();
+
+        assert_eq!(
+            input,
+            GetClassEntryPointsInput {
+                class_hash: class_hash!("0x1"),
+                selector: Some(entry_point!("0x2")),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn cairo() {
+        let context = RpcContext::for_tests();
+        let input = GetClassEntryPointsInput {
+            class_hash: class_hash_bytes!(b"class 0 hash"),
+            selector: None,
+        };
+
+        let entry_points = get_class_entry_points(context, input).await.unwrap();
+
+        let mut names = entry_points
+            .external
+            .iter()
+            .map(|x| x.name.as_deref().unwrap())
+            .collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(
+            names,
+            vec!["call_increase_value", "get_value", "increase_value"]
+        );
+        assert!(entry_points.l1_handler.is_empty());
+        assert!(entry_points.constructor.is_empty());
+    }
+
+    #[tokio::test]
+    async fn sierra() {
+        let context = RpcContext::for_tests();
+        let input = GetClassEntryPointsInput {
+            class_hash: class_hash_bytes!(b"class 2 hash (sierra)"),
+            selector: None,
+        };
+
+        let entry_points = get_class_entry_points(context, input).await.unwrap();
+
+        let mut names = entry_points
+            .external
+            .iter()
+            .map(|x| x.name.as_deref().unwrap())
+            .collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, vec!["call_foo", "empty", "test"]);
+    }
+
+    #[tokio::test]
+    async fn selector_lookup() {
+        let context = RpcContext::for_tests();
+        let selector = EntryPoint::hashed(b"get_value");
+        let input = GetClassEntryPointsInput {
+            class_hash: class_hash_bytes!(b"class 0 hash"),
+            selector: Some(selector),
+        };
+
+        let entry_points = get_class_entry_points(context, input).await.unwrap();
+
+        assert_eq!(
+            entry_points,
+            ClassEntryPoints {
+                external: vec![NamedEntryPoint {
+                    selector,
+                    name: Some("get_value".to_owned()),
+                }],
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn unnamed_without_abi() {
+        let definition = json!({
+            "entry_points_by_type": {
+                "EXTERNAL": [{"selector": "0x1", "offset": "0x0"}],
+                "L1_HANDLER": [],
+                "CONSTRUCTOR": [],
+            },
+            "abi": "not json",
+        });
+        let definition = serde_json::to_vec(&definition).unwrap();
+
+        let entry_points = entry_points(&definition, None).unwrap();
+
+        assert_eq!(
+            entry_points.external,
+            vec![NamedEntryPoint {
+                selector: entry_point!("0x1"),
+                name: None,
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn class_not_found() {
+        let context = RpcContext::for_tests();
+        let input = GetClassEntryPointsInput {
+            class_hash: class_hash_bytes!(b"non-existent"),
+            selector: None,
+        };
+
+        let err = get_class_entry_points(context, input).await.unwrap_err();
+        assert_matches!(
+            err,
+            GetClassEntryPointsError::ClassHashNotFound(class_hash) => {
+                assert_eq!(class_hash, class_hash_bytes!(b"non-existent"));
+            }
+        );
+    }
+}
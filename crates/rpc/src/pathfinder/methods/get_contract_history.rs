@@ -0,0 +1,106 @@
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+use crate::context::RpcContext;
+use pathfinder_common::prelude::*;
+
+#[derive(Deserialize, Debug, PartialEq, Eq)]
+pub struct GetContractHistoryInput {
+    pub contract_address: ContractAddress,
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct ClassHashChange {
+    block_number: BlockNumber,
+    class_hash: ClassHash,
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct GetContractHistoryOutput {
+    /// The block at which the contract was deployed.
+    deployed_at: BlockNumber,
+    /// The contract's class hash as of deployment, followed by every replacement.
+    class_hashes: Vec<ClassHashChange>,
+}
+
+crate::error::generate_rpc_error_subset!(GetContractHistoryError: ContractNotFound);
+
+/// Returns the block at which a contract was deployed and every subsequent change
+/// to its class hash.
+pub async fn get_contract_history(
+    context: RpcContext,
+    input: GetContractHistoryInput,
+) -> Result<GetContractHistoryOutput, GetContractHistoryError> {
+    let storage = context.storage.clone();
+    let span = tracing::Span::current();
+
+    let jh = tokio::task::spawn_blocking(move || {
+        let _g = span.enter();
+        let mut db = storage
+            .connection()
+            .context("Opening database connection")?;
+
+        let tx = db.transaction().context("Creating database transaction")?;
+
+        let history = tx
+            .contract_class_history(input.contract_address)
+            .context("Querying contract's class history")?;
+
+        let deployed_at = history
+            .first()
+            .map(|(block_number, _)| *block_number)
+            .ok_or(GetContractHistoryError::ContractNotFound)?;
+
+        let class_hashes = history
+            .into_iter()
+            .map(|(block_number, class_hash)| ClassHashChange {
+                block_number,
+                class_hash,
+            })
+            .collect();
+
+        Ok(GetContractHistoryOutput {
+            deployed_at,
+            class_hashes,
+        })
+    });
+
+    jh.await.context("Database read panic or shutting down")?
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn deployed_contract() {
+        let context = RpcContext::for_tests();
+        let input = GetContractHistoryInput {
+            contract_address: contract_address_bytes!(b"contract 1"),
+        };
+
+        let output = get_contract_history(context, input).await.unwrap();
+
+        let expected = GetContractHistoryOutput {
+            deployed_at: BlockNumber::new_or_panic(1),
+            class_hashes: vec![ClassHashChange {
+                block_number: BlockNumber::new_or_panic(1),
+                class_hash: class_hash_bytes!(b"class 1 hash"),
+            }],
+        };
+        assert_eq!(output, expected);
+    }
+
+    #[tokio::test]
+    async fn contract_not_found() {
+        let context = RpcContext::for_tests();
+        let input = GetContractHistoryInput {
+            contract_address: contract_address_bytes!(b"invalid"),
+        };
+
+        let err = get_contract_history(context, input).await.unwrap_err();
+        assert_matches::assert_matches!(err, GetContractHistoryError::ContractNotFound);
+    }
+}
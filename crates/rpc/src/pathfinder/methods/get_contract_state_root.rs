@@ -0,0 +1,145 @@
+use anyhow::{anyhow, Context};
+use serde::{Deserialize, Serialize};
+
+use crate::context::RpcContext;
+use pathfinder_common::{prelude::*, BlockId};
+
+#[derive(Deserialize, Debug, PartialEq, Eq)]
+pub struct GetContractStateRootInput {
+    pub block_id: BlockId,
+    pub contract_address: ContractAddress,
+}
+
+/// The data required to tie a contract's storage to its contract state hash.
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct GetContractStateRootOutput {
+    /// Root of the contract's storage trie.
+    root: ContractRoot,
+    nonce: ContractNonce,
+    class_hash: ClassHash,
+    /// The leaf value of this contract in the global storage trie.
+    contract_state_hash: ContractStateHash,
+}
+
+crate::error::generate_rpc_error_subset!(
+    GetContractStateRootError: BlockNotFound,
+    ContractNotFound
+);
+
+/// Returns a contract's storage root, nonce and class hash at the given block.
+pub async fn get_contract_state_root(
+    context: RpcContext,
+    input: GetContractStateRootInput,
+) -> Result<GetContractStateRootOutput, GetContractStateRootError> {
+    let block_id = match input.block_id {
+        BlockId::Pending => {
+            return Err(GetContractStateRootError::Internal(anyhow!(
+                "'pending' is not currently supported by this method!"
+            )))
+        }
+        other => other.try_into().expect("Only pending cast should fail"),
+    };
+
+    let storage = context.storage.clone();
+    let span = tracing::Span::current();
+
+    let jh = tokio::task::spawn_blocking(move || {
+        let _g = span.enter();
+        let mut db = storage
+            .connection()
+            .context("Opening database connection")?;
+
+        let tx = db.transaction().context("Creating database transaction")?;
+
+        let (block, _) = tx
+            .block_id(block_id)
+            .context("Querying block")?
+            .ok_or(GetContractStateRootError::BlockNotFound)?;
+
+        let contract_state_hash = tx
+            .contract_state_hash(block, input.contract_address)
+            .context("Querying contract's state hash")?
+            .ok_or(GetContractStateRootError::ContractNotFound)?;
+
+        let root = tx
+            .contract_root(block, input.contract_address)
+            .context("Querying contract's root")?
+            .unwrap_or_default();
+
+        let nonce = tx
+            .contract_nonce(input.contract_address, block.into())
+            .context("Querying contract's nonce")?
+            .unwrap_or_default();
+
+        // Note that special contracts such as 0x1 have no class hash.
+        let class_hash = tx
+            .contract_class_hash(block.into(), input.contract_address)
+            .context("Querying contract's class hash")?
+            .unwrap_or_default();
+
+        Ok(GetContractStateRootOutput {
+            root,
+            nonce,
+            class_hash,
+            contract_state_hash,
+        })
+    });
+
+    jh.await.context("Database read panic or shutting down")?
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn existing_contract() {
+        let context = RpcContext::for_tests();
+        let contract_address = contract_address_bytes!(b"contract 1");
+
+        let expected_root = {
+            let mut db = context.storage.connection().unwrap();
+            let tx = db.transaction().unwrap();
+            tx.contract_root(BlockNumber::new_or_panic(2), contract_address)
+                .unwrap()
+                .unwrap()
+        };
+
+        let input = GetContractStateRootInput {
+            block_id: BlockId::Latest,
+            contract_address,
+        };
+        let output = get_contract_state_root(context, input).await.unwrap();
+
+        assert_eq!(output.root, expected_root);
+        assert_eq!(output.nonce, contract_nonce!("0x10"));
+        assert_eq!(output.class_hash, class_hash_bytes!(b"class 1 hash"));
+        assert_ne!(output.contract_state_hash, ContractStateHash::ZERO);
+    }
+
+    #[tokio::test]
+    async fn contract_not_found() {
+        let context = RpcContext::for_tests();
+        let input = GetContractStateRootInput {
+            block_id: BlockId::Latest,
+            contract_address: contract_address_bytes!(b"invalid"),
+        };
+
+        let err = get_contract_state_root(context, input).await.unwrap_err();
+        assert_matches::assert_matches!(err, GetContractStateRootError::ContractNotFound);
+    }
+
+    #[tokio::test]
+    async fn block_not_found() {
+        let context = RpcContext::for_tests();
+        let input = GetContractStateRootInput {
+            block_id: BlockId::Hash(block_hash_bytes!(b"invalid")),
+            contract_address: contract_address_bytes!(b"contract 0"),
+        };
+
+        let err = get_contract_state_root(context, input).await.unwrap_err();
+        assert_matches::assert_matches!(err, GetContractStateRootError::BlockNotFound);
+    }
+}
@@ -0,0 +1,201 @@
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+use crate::context::RpcContext;
+use pathfinder_common::{BlockId, CasmHash, ClassHash};
+
+#[derive(Deserialize, Debug, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct GetDeclaredClassesInput {
+    pub block_id: BlockId,
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct DeclaredClass {
+    class_hash: ClassHash,
+    compiled_class_hash: CasmHash,
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct GetDeclaredClassesOutput {
+    /// Sierra classes along with the hashes of their compiled classes.
+    declared_classes: Vec<DeclaredClass>,
+    /// Legacy Cairo classes.
+    deprecated_declared_classes: Vec<ClassHash>,
+}
+
+crate::error::generate_rpc_error_subset!(GetDeclaredClassesError: BlockNotFound);
+
+/// Lists the classes declared in a block.
+///
+/// Both lists are sorted by class hash.
+pub async fn get_declared_classes(
+    context: RpcContext,
+    input: GetDeclaredClassesInput,
+) -> Result<GetDeclaredClassesOutput, GetDeclaredClassesError> {
+    let storage = context.storage.clone();
+    let span = tracing::Span::current();
+
+    let jh = tokio::task::spawn_blocking(move || {
+        let _g = span.enter();
+        let mut db = storage
+            .connection()
+            .context("Opening database connection")?;
+
+        let tx = db.transaction().context("Creating database transaction")?;
+
+        let mut output = GetDeclaredClassesOutput {
+            declared_classes: Vec::new(),
+            deprecated_declared_classes: Vec::new(),
+        };
+
+        let block_id = match input.block_id {
+            BlockId::Pending => {
+                let pending = context
+                    .pending_data
+                    .get(&tx)
+                    .context("Querying pending data")?;
+
+                output.declared_classes = pending
+                    .state_update
+                    .declared_sierra_classes
+                    .iter()
+                    .map(|(sierra, casm)| DeclaredClass {
+                        class_hash: ClassHash(sierra.0),
+                        compiled_class_hash: *casm,
+                    })
+                    .collect();
+                output.deprecated_declared_classes = pending
+                    .state_update
+                    .declared_cairo_classes
+                    .iter()
+                    .copied()
+                    .collect();
+                output.sort();
+
+                return Ok(output);
+            }
+            other => other.try_into().expect("Only pending cast should fail"),
+        };
+
+        let classes = tx
+            .declared_classes_at(block_id)
+            .context("Querying declared classes")?
+            .ok_or(GetDeclaredClassesError::BlockNotFound)?;
+
+        for class_hash in classes {
+            // Only Sierra classes have a compiled class hash.
+            match tx.casm_hash(class_hash).context("Querying casm hash")? {
+                Some(compiled_class_hash) => output.declared_classes.push(DeclaredClass {
+                    class_hash,
+                    compiled_class_hash,
+                }),
+                None => output.deprecated_declared_classes.push(class_hash),
+            }
+        }
+        output.sort();
+
+        Ok(output)
+    });
+
+    jh.await.context("Database read panic or shutting down")?
+}
+
+impl GetDeclaredClassesOutput {
+    fn sort(&mut self) {
+        self.declared_classes.sort_by_key(|x| x.class_hash);
+        self.deprecated_declared_classes.sort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::BlockNumber;
+    use pathfinder_storage::fake::Block;
+
+    use super::*;
+
+    #[test]
+    fn parsing() {
+        let input = serde_json::json!({ "block_id": { "block_number": 1 } });
+
+        let input = serde_json::from_value::<GetDeclaredClassesInput>(input).unwrap();
+
+        assert_eq!(
+            input,
+            GetDeclaredClassesInput {
+                block_id: BlockId::Number(BlockNumber::new_or_panic(1)),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn declared_in_block() {
+        let storage = pathfinder_storage::Storage::in_memory().unwrap();
+        let blocks = pathfinder_storage::fake::with_n_blocks(&storage, 3);
+        let context = RpcContext::for_tests().with_storage(storage);
+
+        for Block {
+            header,
+            state_update,
+            ..
+        } in blocks
+        {
+            let input = GetDeclaredClassesInput {
+                block_id: BlockId::Hash(header.header.hash),
+            };
+
+            let output = get_declared_classes(context.clone(), input).await.unwrap();
+
+            let mut expected = GetDeclaredClassesOutput {
+                declared_classes: state_update
+                    .declared_sierra_classes
+                    .into_iter()
+                    .map(|(sierra, casm)| DeclaredClass {
+                        class_hash: ClassHash(sierra.0),
+                        compiled_class_hash: casm,
+                    })
+                    .collect(),
+                deprecated_declared_classes: state_update
+                    .declared_cairo_classes
+                    .into_iter()
+                    .collect(),
+            };
+            expected.sort();
+            assert_eq!(output, expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn pending() {
+        let context = RpcContext::for_tests_with_pending().await;
+        let input = GetDeclaredClassesInput {
+            block_id: BlockId::Pending,
+        };
+
+        let output = get_declared_classes(context, input).await.unwrap();
+
+        let mut expected = GetDeclaredClassesOutput {
+            declared_classes: vec![],
+            deprecated_declared_classes: vec![
+                class_hash_bytes!(b"pending class 0 hash"),
+                class_hash_bytes!(b"pending class 1 hash"),
+            ],
+        };
+        expected.sort();
+        assert_eq!(output, expected);
+    }
+
+    #[tokio::test]
+    async fn block_not_found() {
+        let context = RpcContext::for_tests();
+        let input = GetDeclaredClassesInput {
+            block_id: BlockId::Hash(block_hash_bytes!(b"non-existent")),
+        };
+
+        let err = get_declared_classes(context, input).await.unwrap_err();
+        assert_matches!(err, GetDeclaredClassesError::BlockNotFound);
+    }
+}
@@ -0,0 +1,191 @@
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+use crate::context::RpcContext;
+use pathfinder_common::state_update::ContractClassUpdate;
+use pathfinder_common::{BlockId, ClassHash, ContractAddress, StateUpdate};
+
+#[derive(Deserialize, Debug, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct GetDeployedContractsInput {
+    pub block_id: BlockId,
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct ContractClass {
+    contract_address: ContractAddress,
+    class_hash: ClassHash,
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct GetDeployedContractsOutput {
+    /// Contracts deployed in the block along with their class hashes.
+    deployed_contracts: Vec<ContractClass>,
+    /// Contracts whose class was replaced in the block along with their new class hashes.
+    replaced_classes: Vec<ContractClass>,
+}
+
+crate::error::generate_rpc_error_subset!(GetDeployedContractsError: BlockNotFound);
+
+/// Lists the contracts deployed or upgraded in a block, as recorded by the block's state update.
+///
+/// Both lists are sorted by contract address.
+pub async fn get_deployed_contracts(
+    context: RpcContext,
+    input: GetDeployedContractsInput,
+) -> Result<GetDeployedContractsOutput, GetDeployedContractsError> {
+    let storage = context.storage.clone();
+    let span = tracing::Span::current();
+
+    let jh = tokio::task::spawn_blocking(move || {
+        let _g = span.enter();
+        let mut db = storage
+            .connection()
+            .context("Opening database connection")?;
+
+        let tx = db.transaction().context("Creating database transaction")?;
+
+        let block_id = match input.block_id {
+            BlockId::Pending => {
+                let pending = context
+                    .pending_data
+                    .get(&tx)
+                    .context("Querying pending data")?;
+
+                return Ok(GetDeployedContractsOutput::from(
+                    pending.state_update.as_ref(),
+                ));
+            }
+            other => other.try_into().expect("Only pending cast should fail"),
+        };
+
+        let state_update = tx
+            .state_update(block_id)
+            .context("Querying state update")?
+            .ok_or(GetDeployedContractsError::BlockNotFound)?;
+
+        Ok(GetDeployedContractsOutput::from(&state_update))
+    });
+
+    jh.await.context("Database read panic or shutting down")?
+}
+
+impl From<&StateUpdate> for GetDeployedContractsOutput {
+    fn from(state_update: &StateUpdate) -> Self {
+        let mut deployed_contracts = Vec::new();
+        let mut replaced_classes = Vec::new();
+
+        for (contract_address, update) in &state_update.contract_updates {
+            match update.class {
+                Some(ContractClassUpdate::Deploy(class_hash)) => {
+                    deployed_contracts.push(ContractClass {
+                        contract_address: *contract_address,
+                        class_hash,
+                    })
+                }
+                Some(ContractClassUpdate::Replace(class_hash)) => {
+                    replaced_classes.push(ContractClass {
+                        contract_address: *contract_address,
+                        class_hash,
+                    })
+                }
+                None => {}
+            }
+        }
+
+        deployed_contracts.sort_by_key(|x| x.contract_address);
+        replaced_classes.sort_by_key(|x| x.contract_address);
+
+        Self {
+            deployed_contracts,
+            replaced_classes,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::BlockNumber;
+
+    use super::*;
+
+    #[test]
+    fn parsing() {
+        let input = serde_json::json!({ "block_id": "latest" });
+
+        let input = serde_json::from_value::<GetDeployedContractsInput>(input).unwrap();
+
+        assert_eq!(
+            input,
+            GetDeployedContractsInput {
+                block_id: BlockId::Latest,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn deployed() {
+        let context = RpcContext::for_tests();
+        let input = GetDeployedContractsInput {
+            block_id: BlockId::Number(BlockNumber::new_or_panic(1)),
+        };
+
+        let output = get_deployed_contracts(context, input).await.unwrap();
+
+        assert_eq!(
+            output,
+            GetDeployedContractsOutput {
+                deployed_contracts: vec![ContractClass {
+                    contract_address: contract_address_bytes!(b"contract 1"),
+                    class_hash: class_hash_bytes!(b"class 1 hash"),
+                }],
+                replaced_classes: vec![],
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn pending() {
+        let context = RpcContext::for_tests_with_pending().await;
+        let input = GetDeployedContractsInput {
+            block_id: BlockId::Pending,
+        };
+
+        let output = get_deployed_contracts(context, input).await.unwrap();
+
+        let mut deployed_contracts = vec![
+            ContractClass {
+                contract_address: contract_address_bytes!(b"pending contract 0 address"),
+                class_hash: class_hash_bytes!(b"pending class 0 hash"),
+            },
+            ContractClass {
+                contract_address: contract_address_bytes!(b"pending contract 1 address"),
+                class_hash: class_hash_bytes!(b"pending class 1 hash"),
+            },
+        ];
+        deployed_contracts.sort_by_key(|x| x.contract_address);
+        assert_eq!(
+            output,
+            GetDeployedContractsOutput {
+                deployed_contracts,
+                replaced_classes: vec![ContractClass {
+                    contract_address: contract_address_bytes!(b"pending contract 2 (replaced)"),
+                    class_hash: class_hash_bytes!(b"pending class 2 hash (replaced)"),
+                }],
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn block_not_found() {
+        let context = RpcContext::for_tests();
+        let input = GetDeployedContractsInput {
+            block_id: BlockId::Hash(block_hash_bytes!(b"non-existent")),
+        };
+
+        let err = get_deployed_contracts(context, input).await.unwrap_err();
+        assert_matches!(err, GetDeployedContractsError::BlockNotFound);
+    }
+}
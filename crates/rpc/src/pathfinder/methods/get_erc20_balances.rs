@@ -0,0 +1,227 @@
+use anyhow::Context;
+use pathfinder_common::{BlockId, CallParam, CallResultValue, ContractAddress, EntryPoint};
+use pathfinder_executor::{Call, CallError, ExecutionState, L1BlobDataAvailability};
+use serde::{Deserialize, Serialize};
+
+use crate::context::RpcContext;
+use crate::executor::{with_timeout, ExecutionMethod};
+
+/// The maximum number of tokens in a single request.
+const MAX_TOKENS: usize = 100;
+
+#[derive(Deserialize, Debug, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct GetErc20BalancesInput {
+    pub block_id: BlockId,
+    pub account: ContractAddress,
+    pub token_addresses: Vec<ContractAddress>,
+}
+
+/// An ERC20 balance, which is a `Uint256` split into its low and high 128 bits.
+#[derive(Serialize, Debug, PartialEq, Eq)]
+pub struct Balance {
+    pub low: CallResultValue,
+    pub high: CallResultValue,
+}
+
+crate::error::generate_rpc_error_subset!(
+    GetErc20BalancesError: BlockNotFound,
+    ExecutionTimeout,
+    ExecutionStepLimitExceeded,
+    Erc20BalancesDisabled
+);
+
+impl From<crate::executor::ExecutionTimeout> for GetErc20BalancesError {
+    fn from(_: crate::executor::ExecutionTimeout) -> Self {
+        Self::ExecutionTimeout
+    }
+}
+
+/// Calls `balanceOf(account)` of each of the tokens at the same block.
+///
+/// The balances are returned in the order of the tokens. Tokens which do not exist, or whose
+/// `balanceOf` is missing, fails or does not return a `Uint256`, have a `null` balance instead of
+/// failing the request.
+pub async fn get_erc20_balances(
+    context: RpcContext,
+    input: GetErc20BalancesInput,
+) -> Result<Vec<Option<Balance>>, GetErc20BalancesError> {
+    if !context.config.erc20_balances {
+        return Err(GetErc20BalancesError::Erc20BalancesDisabled);
+    }
+
+    if input.token_addresses.len() > MAX_TOKENS {
+        return Err(GetErc20BalancesError::Custom(anyhow::anyhow!(
+            "At most {MAX_TOKENS} tokens may be requested"
+        )));
+    }
+
+    let timeout = context.config.execution_timeout;
+    let pool = context.execution_pool.clone();
+    let execution = pool.execute(ExecutionMethod::Call, move || {
+        let mut db = context
+            .storage
+            .connection()
+            .context("Creating database connection")?;
+        let db = db.transaction().context("Creating database transaction")?;
+
+        let (header, pending) = match input.block_id {
+            BlockId::Pending => {
+                let pending = context
+                    .pending_data
+                    .get(&db)
+                    .context("Querying pending data")?;
+
+                (pending.header(), Some(pending.state_update.clone()))
+            }
+            other => {
+                let block_id = other.try_into().expect("Only pending cast should fail");
+                let header = db
+                    .block_header(block_id)
+                    .context("Querying block header")?
+                    .ok_or(GetErc20BalancesError::BlockNotFound)?;
+
+                (header, None)
+            }
+        };
+
+        let state = ExecutionState::simulation(
+            &db,
+            context.chain_id,
+            header,
+            pending,
+            L1BlobDataAvailability::Disabled,
+        )
+        .with_max_steps(context.config.execution_max_steps)
+        .with_fork(context.fork.clone());
+
+        // The tokens share the state cache, e.g. for the account's class or a common proxy.
+        let calls = input
+            .token_addresses
+            .into_iter()
+            .map(|token| Call {
+                contract_address: token,
+                entry_point_selector: EntryPoint::hashed(b"balanceOf"),
+                calldata: vec![CallParam(input.account.0)],
+            })
+            .collect();
+        let results = pathfinder_executor::multicall(state, calls)?;
+
+        let mut balances = Vec::with_capacity(results.len());
+        for result in results {
+            let balance = match result {
+                Ok(result) => match result.as_slice() {
+                    [low, high] => Some(Balance {
+                        low: *low,
+                        high: *high,
+                    }),
+                    _ => None,
+                },
+                // A token without `balanceOf` is not an ERC20 token.
+                Err(
+                    CallError::ContractNotFound
+                    | CallError::ContractError(_)
+                    | CallError::InvalidMessageSelector,
+                ) => None,
+                Err(CallError::StepLimitExceeded) => {
+                    return Err(GetErc20BalancesError::ExecutionStepLimitExceeded)
+                }
+                Err(CallError::Internal(e)) => return Err(GetErc20BalancesError::Internal(e)),
+                Err(CallError::Custom(e)) => return Err(GetErc20BalancesError::Custom(e)),
+            };
+            balances.push(balance);
+        }
+
+        Ok(balances)
+    });
+
+    with_timeout(timeout, execution).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_matches::assert_matches;
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::StarknetVersion;
+    use serde_json::json;
+
+    #[test]
+    fn parsing() {
+        let input = json!({
+            "block_id": "latest",
+            "account": "0x1",
+            "token_addresses": ["0x2", "0x3"],
+        });
+
+        let input = serde_json::from_value::<GetErc20BalancesInput>(input).unwrap();
+
+        assert_eq!(
+            input,
+            GetErc20BalancesInput {
+                block_id: BlockId::Latest,
+                account: contract_address!("0x1"),
+                token_addresses: vec![contract_address!("0x2"), contract_address!("0x3")],
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn disabled() {
+        let context = RpcContext::for_tests();
+        let input = GetErc20BalancesInput {
+            block_id: BlockId::Latest,
+            account: contract_address!("0x1"),
+            token_addresses: vec![contract_address!("0x2")],
+        };
+
+        let err = get_erc20_balances(context, input).await.unwrap_err();
+        assert_matches!(err, GetErc20BalancesError::Erc20BalancesDisabled);
+    }
+
+    #[tokio::test]
+    async fn balances() {
+        let (mut context, last_block_header, account, _) =
+            crate::test_setup::test_context_with_starknet_version(StarknetVersion::new(0, 13, 1))
+                .await;
+        context.config.erc20_balances = true;
+
+        let input = GetErc20BalancesInput {
+            block_id: BlockId::Number(last_block_header.number),
+            account,
+            token_addresses: vec![
+                pathfinder_executor::ETH_FEE_TOKEN_ADDRESS,
+                contract_address!("0xdeadbeef"),
+                pathfinder_executor::STRK_FEE_TOKEN_ADDRESS,
+                // The account has no `balanceOf`.
+                account,
+            ],
+        };
+
+        let balances = get_erc20_balances(context, input).await.unwrap();
+
+        let expected = Balance {
+            low: CallResultValue(felt!("0x10000000000000000000000000000")),
+            high: CallResultValue::ZERO,
+        };
+        assert_eq!(balances.len(), 4);
+        assert_eq!(balances[0].as_ref(), Some(&expected));
+        assert_eq!(balances[1], None);
+        assert_eq!(balances[2].as_ref(), Some(&expected));
+        assert_eq!(balances[3], None);
+    }
+
+    #[tokio::test]
+    async fn block_not_found() {
+        let mut context = RpcContext::for_tests();
+        context.config.erc20_balances = true;
+        let input = GetErc20BalancesInput {
+            block_id: BlockId::Hash(block_hash_bytes!(b"invalid")),
+            account: contract_address!("0x1"),
+            token_addresses: vec![contract_address!("0x2")],
+        };
+
+        let err = get_erc20_balances(context, input).await.unwrap_err();
+        assert_matches!(err, GetErc20BalancesError::BlockNotFound);
+    }
+}
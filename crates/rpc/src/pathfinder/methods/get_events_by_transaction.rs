@@ -0,0 +1,167 @@
+use anyhow::Context;
+use pathfinder_common::event::Event;
+use serde::{Deserialize, Serialize};
+use serde_with::skip_serializing_none;
+
+use crate::context::RpcContext;
+use pathfinder_common::prelude::*;
+
+#[derive(Deserialize, Debug, PartialEq, Eq)]
+pub struct GetEventsByTransactionInput {
+    pub transaction_hash: TransactionHash,
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct TransactionEvent {
+    /// The index of the event within the transaction's receipt.
+    event_index: usize,
+    from_address: ContractAddress,
+    keys: Vec<EventKey>,
+    data: Vec<EventData>,
+}
+
+/// The events emitted by a single transaction.
+///
+/// The block hash and number are absent if the transaction is in the pending block.
+#[skip_serializing_none]
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct GetEventsByTransactionOutput {
+    block_hash: Option<BlockHash>,
+    block_number: Option<BlockNumber>,
+    transaction_hash: TransactionHash,
+    events: Vec<TransactionEvent>,
+}
+
+crate::error::generate_rpc_error_subset!(GetEventsByTransactionError: TxnHashNotFound);
+
+/// Returns the events emitted by the given transaction.
+///
+/// These are read directly from the transaction's receipt, which avoids the event
+/// filter scan `starknet_getEvents` requires.
+pub async fn get_events_by_transaction(
+    context: RpcContext,
+    input: GetEventsByTransactionInput,
+) -> Result<GetEventsByTransactionOutput, GetEventsByTransactionError> {
+    let span = tracing::Span::current();
+
+    let jh = tokio::task::spawn_blocking(move || {
+        let _g = span.enter();
+
+        let mut db = context
+            .storage
+            .connection()
+            .context("Opening database connection")?;
+        let db_tx = db.transaction().context("Creating database transaction")?;
+
+        // Check pending transactions first.
+        let pending = context
+            .pending_data
+            .get(&db_tx)
+            .context("Querying pending data")?;
+        if let Some(receipt) = pending
+            .block
+            .transaction_receipts
+            .iter()
+            .find(|rx| rx.transaction_hash == input.transaction_hash)
+        {
+            return Ok(GetEventsByTransactionOutput {
+                block_hash: None,
+                block_number: None,
+                transaction_hash: input.transaction_hash,
+                events: map_events(&receipt.events),
+            });
+        }
+
+        let (_, receipt, block_hash) = db_tx
+            .transaction_with_receipt(input.transaction_hash)
+            .context("Fetching receipt from database")?
+            .ok_or(GetEventsByTransactionError::TxnHashNotFound)?;
+
+        let (block_number, _) = db_tx
+            .block_id(block_hash.into())
+            .context("Querying block number")?
+            .context("Block of transaction is missing")?;
+
+        Ok(GetEventsByTransactionOutput {
+            block_hash: Some(block_hash),
+            block_number: Some(block_number),
+            transaction_hash: input.transaction_hash,
+            events: map_events(&receipt.events),
+        })
+    });
+
+    jh.await.context("Database read panic or shutting down")?
+}
+
+fn map_events(events: &[Event]) -> Vec<TransactionEvent> {
+    events
+        .iter()
+        .enumerate()
+        .map(|(event_index, event)| TransactionEvent {
+            event_index,
+            from_address: event.from_address,
+            keys: event.keys.clone(),
+            data: event.data.clone(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn accepted() {
+        let context = RpcContext::for_tests();
+        let input = GetEventsByTransactionInput {
+            transaction_hash: transaction_hash_bytes!(b"txn 0"),
+        };
+
+        let output = get_events_by_transaction(context, input).await.unwrap();
+
+        let expected = GetEventsByTransactionOutput {
+            block_hash: Some(block_hash_bytes!(b"genesis")),
+            block_number: Some(BlockNumber::GENESIS),
+            transaction_hash: transaction_hash_bytes!(b"txn 0"),
+            events: vec![TransactionEvent {
+                event_index: 0,
+                from_address: contract_address_bytes!(b"event 0 from addr"),
+                keys: vec![event_key_bytes!(b"event 0 key")],
+                data: vec![event_data_bytes!(b"event 0 data")],
+            }],
+        };
+        assert_eq!(output, expected);
+    }
+
+    #[tokio::test]
+    async fn pending() {
+        let context = RpcContext::for_tests_with_pending().await;
+        let input = GetEventsByTransactionInput {
+            transaction_hash: transaction_hash_bytes!(b"pending tx hash 0"),
+        };
+
+        let output = get_events_by_transaction(context, input).await.unwrap();
+
+        assert_eq!(output.block_hash, None);
+        assert_eq!(output.block_number, None);
+        assert_eq!(output.events.len(), 3);
+        assert_eq!(output.events[2].event_index, 2);
+        assert_eq!(
+            output.events[2].from_address,
+            contract_address!("0xabcaaaaaaa")
+        );
+    }
+
+    #[tokio::test]
+    async fn not_found() {
+        let context = RpcContext::for_tests();
+        let input = GetEventsByTransactionInput {
+            transaction_hash: transaction_hash_bytes!(b"non-existent"),
+        };
+
+        let err = get_events_by_transaction(context, input).await.unwrap_err();
+        assert_matches::assert_matches!(err, GetEventsByTransactionError::TxnHashNotFound);
+    }
+}
@@ -0,0 +1,234 @@
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use serde_with::{serde_as, skip_serializing_none};
+
+use crate::context::RpcContext;
+use pathfinder_common::{BlockHash, BlockId, BlockNumber, GasPrice};
+
+#[derive(Deserialize, Debug, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct GetGasPricesInput {
+    pub block_id: BlockId,
+}
+
+#[serde_as]
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct ResourcePrice {
+    #[serde_as(as = "pathfinder_serde::GasPriceAsHexStr")]
+    price_in_wei: GasPrice,
+    #[serde_as(as = "pathfinder_serde::GasPriceAsHexStr")]
+    price_in_fri: GasPrice,
+}
+
+#[serde_as]
+#[skip_serializing_none]
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct EthereumGasPrices {
+    block_number: u64,
+    #[serde_as(as = "pathfinder_serde::GasPriceAsHexStr")]
+    base_fee: GasPrice,
+    #[serde_as(as = "Option<pathfinder_serde::GasPriceAsHexStr>")]
+    blob_base_fee: Option<GasPrice>,
+}
+
+#[skip_serializing_none]
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct GetGasPricesOutput {
+    block_number: BlockNumber,
+    /// [None] for the pending block.
+    block_hash: Option<BlockHash>,
+    l1_gas_price: ResourcePrice,
+    l1_data_gas_price: ResourcePrice,
+    /// Always serialized, as `null` means that no Ethereum gas prices were recorded.
+    #[serialize_always]
+    ethereum_gas_prices: Option<EthereumGasPrices>,
+}
+
+crate::error::generate_rpc_error_subset!(GetGasPricesError: BlockNotFound);
+
+/// Returns the gas prices reported by the sequencer for a block, together with the Ethereum gas
+/// prices observed by this node's L1 sync when the block was stored.
+///
+/// The pending block's Ethereum gas prices are those of the latest block. Blocks stored while
+/// catching up with the chain, before L1 sync polled the gas prices, or before they were recorded
+/// at all, have none.
+pub async fn get_gas_prices(
+    context: RpcContext,
+    input: GetGasPricesInput,
+) -> Result<GetGasPricesOutput, GetGasPricesError> {
+    let storage = context.storage.clone();
+    let span = tracing::Span::current();
+
+    let jh = tokio::task::spawn_blocking(move || {
+        let _g = span.enter();
+        let mut db = storage
+            .connection()
+            .context("Opening database connection")?;
+
+        let tx = db.transaction().context("Creating database transaction")?;
+
+        let (header, block_hash, ethereum_block) = match input.block_id {
+            BlockId::Pending => {
+                let header = context
+                    .pending_data
+                    .get(&tx)
+                    .context("Querying pending data")?
+                    .header();
+                (header, None, pathfinder_storage::BlockId::Latest)
+            }
+            other => {
+                let block_id = other.try_into().expect("Only pending cast should fail");
+                let header = tx
+                    .block_header(block_id)
+                    .context("Querying block header")?
+                    .ok_or(GetGasPricesError::BlockNotFound)?;
+                let (block_hash, block_number) = (header.hash, header.number);
+                (header, Some(block_hash), block_number.into())
+            }
+        };
+
+        let ethereum_gas_prices = tx
+            .l1_gas_prices(ethereum_block)
+            .context("Querying L1 gas prices")?
+            .map(|x| EthereumGasPrices {
+                block_number: x.block_number,
+                base_fee: x.base_fee,
+                blob_base_fee: x.blob_base_fee,
+            });
+
+        Ok(GetGasPricesOutput {
+            block_number: header.number,
+            block_hash,
+            l1_gas_price: ResourcePrice {
+                price_in_wei: header.eth_l1_gas_price,
+                price_in_fri: header.strk_l1_gas_price,
+            },
+            l1_data_gas_price: ResourcePrice {
+                price_in_wei: header.eth_l1_data_gas_price,
+                price_in_fri: header.strk_l1_data_gas_price,
+            },
+            ethereum_gas_prices,
+        })
+    });
+
+    jh.await.context("Database read panic or shutting down")?
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_ethereum::L1GasPrices;
+
+    use super::*;
+
+    #[test]
+    fn parsing() {
+        let input = serde_json::json!({ "block_id": "latest" });
+
+        let input = serde_json::from_value::<GetGasPricesInput>(input).unwrap();
+
+        assert_eq!(
+            input,
+            GetGasPricesInput {
+                block_id: BlockId::Latest,
+            }
+        );
+    }
+
+    fn insert_l1_gas_prices(context: &RpcContext) -> L1GasPrices {
+        let prices = L1GasPrices {
+            block_number: 100,
+            base_fee: GasPrice(10),
+            blob_base_fee: Some(GasPrice(1)),
+        };
+        let mut db = context.storage.connection().unwrap();
+        let tx = db.transaction().unwrap();
+        tx.insert_l1_gas_prices(BlockNumber::new_or_panic(2), &prices)
+            .unwrap();
+        tx.commit().unwrap();
+        prices
+    }
+
+    #[tokio::test]
+    async fn latest() {
+        let context = RpcContext::for_tests();
+        insert_l1_gas_prices(&context);
+        let input = GetGasPricesInput {
+            block_id: BlockId::Latest,
+        };
+
+        let output = get_gas_prices(context, input).await.unwrap();
+
+        assert_eq!(
+            output,
+            GetGasPricesOutput {
+                block_number: BlockNumber::new_or_panic(2),
+                block_hash: Some(block_hash_bytes!(b"latest")),
+                l1_gas_price: ResourcePrice {
+                    price_in_wei: GasPrice(2),
+                    price_in_fri: GasPrice::ZERO,
+                },
+                l1_data_gas_price: ResourcePrice {
+                    price_in_wei: GasPrice::ZERO,
+                    price_in_fri: GasPrice::ZERO,
+                },
+                ethereum_gas_prices: Some(EthereumGasPrices {
+                    block_number: 100,
+                    base_fee: GasPrice(10),
+                    blob_base_fee: Some(GasPrice(1)),
+                }),
+            }
+        );
+
+        let json = serde_json::to_value(&output).unwrap();
+        assert_eq!(json["ethereum_gas_prices"]["base_fee"], "0xa");
+    }
+
+    #[tokio::test]
+    async fn not_recorded() {
+        let context = RpcContext::for_tests();
+        let input = GetGasPricesInput {
+            block_id: BlockId::Number(BlockNumber::new_or_panic(1)),
+        };
+
+        let output = get_gas_prices(context, input).await.unwrap();
+
+        assert_eq!(output.ethereum_gas_prices, None);
+        let json = serde_json::to_value(&output).unwrap();
+        assert_eq!(json["ethereum_gas_prices"], serde_json::Value::Null);
+    }
+
+    #[tokio::test]
+    async fn pending() {
+        let context = RpcContext::for_tests_with_pending().await;
+        insert_l1_gas_prices(&context);
+        let input = GetGasPricesInput {
+            block_id: BlockId::Pending,
+        };
+
+        let output = get_gas_prices(context, input).await.unwrap();
+
+        assert_eq!(output.block_number, BlockNumber::new_or_panic(3));
+        assert_eq!(output.block_hash, None);
+        assert_eq!(
+            output.ethereum_gas_prices,
+            Some(EthereumGasPrices {
+                block_number: 100,
+                base_fee: GasPrice(10),
+                blob_base_fee: Some(GasPrice(1)),
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn block_not_found() {
+        let context = RpcContext::for_tests();
+        let input = GetGasPricesInput {
+            block_id: BlockId::Hash(block_hash_bytes!(b"non-existent")),
+        };
+
+        let err = get_gas_prices(context, input).await.unwrap_err();
+        assert_matches!(err, GetGasPricesError::BlockNotFound);
+    }
+}
@@ -0,0 +1,121 @@
+use anyhow::Context;
+use pathfinder_serde::H256AsNoLeadingZerosHexStr;
+use primitive_types::H256;
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+
+use crate::context::RpcContext;
+use pathfinder_common::prelude::*;
+
+#[serde_as]
+#[derive(Deserialize, Debug, PartialEq, Eq)]
+pub struct GetL2ToL1MessageProofInput {
+    #[serde_as(as = "H256AsNoLeadingZerosHexStr")]
+    pub message_hash: H256,
+}
+
+/// Locates an L2 to L1 message within the chain.
+///
+/// This does not yet include the Merkle path of the message.
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct GetL2ToL1MessageProofOutput {
+    block_hash: BlockHash,
+    block_number: BlockNumber,
+    transaction_hash: TransactionHash,
+    /// The index of the message within the transaction's receipt.
+    message_index: usize,
+}
+
+crate::error::generate_rpc_error_subset!(GetL2ToL1MessageProofError: MessageNotFound);
+
+/// Returns the block and transaction which sent the L2 to L1 message with the given hash.
+///
+/// If the message was sent more than once, its first occurrence is returned.
+pub async fn get_l2_to_l1_message_proof(
+    context: RpcContext,
+    input: GetL2ToL1MessageProofInput,
+) -> Result<GetL2ToL1MessageProofOutput, GetL2ToL1MessageProofError> {
+    let storage = context.storage.clone();
+    let span = tracing::Span::current();
+
+    let jh = tokio::task::spawn_blocking(move || {
+        let _g = span.enter();
+        let mut db = storage
+            .connection()
+            .context("Opening database connection")?;
+
+        let tx = db.transaction().context("Creating database transaction")?;
+
+        let (block_number, transaction_hash, message_index) = tx
+            .l2_to_l1_messages(input.message_hash)
+            .context("Querying L2 to L1 messages")?
+            .into_iter()
+            .next()
+            .ok_or(GetL2ToL1MessageProofError::MessageNotFound)?;
+
+        let (_, block_hash) = tx
+            .block_id(block_number.into())
+            .context("Querying block hash")?
+            .context("Block of message is missing")?;
+
+        Ok(GetL2ToL1MessageProofOutput {
+            block_hash,
+            block_number,
+            transaction_hash,
+            message_index,
+        })
+    });
+
+    jh.await.context("Database read panic or shutting down")?
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::receipt::L2ToL1Message;
+    use pathfinder_common::EthereumAddress;
+    use primitive_types::H160;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn sent_message() {
+        let context = RpcContext::for_tests();
+        // This message is sent by "txn 6" in the test storage.
+        let message = L2ToL1Message {
+            from_address: contract_address!("0xcafebabe"),
+            payload: vec![
+                l2_to_l1_message_payload_elem!("0x1"),
+                l2_to_l1_message_payload_elem!("0x2"),
+                l2_to_l1_message_payload_elem!("0x3"),
+            ],
+            to_address: EthereumAddress(H160::zero()),
+        };
+        let input = GetL2ToL1MessageProofInput {
+            message_hash: message.calculate_message_hash(),
+        };
+
+        let output = get_l2_to_l1_message_proof(context, input).await.unwrap();
+
+        let expected = GetL2ToL1MessageProofOutput {
+            block_hash: block_hash_bytes!(b"latest"),
+            block_number: BlockNumber::new_or_panic(2),
+            transaction_hash: transaction_hash_bytes!(b"txn 6"),
+            message_index: 0,
+        };
+        assert_eq!(output, expected);
+    }
+
+    #[tokio::test]
+    async fn message_not_found() {
+        let context = RpcContext::for_tests();
+        let input = GetL2ToL1MessageProofInput {
+            message_hash: H256::from_low_u64_be(0xdead),
+        };
+
+        let err = get_l2_to_l1_message_proof(context, input)
+            .await
+            .unwrap_err();
+        assert_matches::assert_matches!(err, GetL2ToL1MessageProofError::MessageNotFound);
+    }
+}
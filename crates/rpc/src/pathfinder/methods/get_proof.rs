@@ -21,6 +21,7 @@ pub enum GetProofError {
     Internal(anyhow::Error),
     BlockNotFound,
     ProofLimitExceeded { limit: u32, requested: u32 },
+    ProofMissing,
 }
 
 impl From<anyhow::Error> for GetProofError {
@@ -36,6 +37,7 @@ impl From<GetProofError> for crate::error::ApplicationError {
                 Self::ProofLimitExceeded { limit, requested }
             }
             GetProofError::BlockNotFound => Self::BlockNotFound,
+            GetProofError::ProofMissing => Self::ProofMissing,
             GetProofError::Internal(internal) => Self::Internal(internal),
         }
     }
@@ -180,6 +182,23 @@ pub async fn get_proof(
             .context("Fetching block header")?
             .ok_or(GetProofError::BlockNotFound)?;
 
+        // The trie state of blocks before the oldest available one has been pruned.
+        let oldest_trie_block = tx
+            .oldest_trie_block()
+            .context("Querying oldest trie block")?;
+        if oldest_trie_block.is_some_and(|oldest| header.number < oldest) {
+            return Err(GetProofError::ProofMissing);
+        }
+
+        // A missing root for a non-empty state means that the trie was never stored or has
+        // been removed, in which case a proof would wrongly claim that the contract is absent.
+        let storage_root_index = tx
+            .storage_root_index(header.number)
+            .context("Querying storage root index")?;
+        if storage_root_index.is_none() && header.storage_commitment != StorageCommitment::ZERO {
+            return Err(GetProofError::ProofMissing);
+        }
+
         let state_commitment = match header.state_commitment {
             StateCommitment::ZERO => None,
             other => Some(other),
@@ -278,4 +297,44 @@ mod tests {
         let err = get_proof(context, input).await.unwrap_err();
         assert_matches::assert_matches!(err, GetProofError::ProofLimitExceeded { .. });
     }
+
+    #[tokio::test]
+    async fn historical_block() {
+        let context = RpcContext::for_tests();
+        let input = GetProofInput {
+            block_id: BlockId::Number(BlockNumber::GENESIS),
+            contract_address: contract_address_bytes!(b"contract 0"),
+            keys: vec![storage_address_bytes!(b"storage addr 0")],
+        };
+
+        let output = get_proof(context, input).await.unwrap();
+        assert!(output.contract_data.is_some());
+    }
+
+    #[tokio::test]
+    async fn pruned_block() {
+        let context = RpcContext::for_tests();
+        {
+            let mut db = context.storage.connection().unwrap();
+            let tx = db.transaction().unwrap();
+            tx.prune_tries(BlockNumber::GENESIS + 1).unwrap();
+            tx.commit().unwrap();
+        }
+
+        let input = GetProofInput {
+            block_id: BlockId::Number(BlockNumber::GENESIS),
+            contract_address: contract_address_bytes!(b"contract 0"),
+            keys: vec![],
+        };
+        let err = get_proof(context.clone(), input).await.unwrap_err();
+        assert_matches::assert_matches!(err, GetProofError::ProofMissing);
+
+        let input = GetProofInput {
+            block_id: BlockId::Number(BlockNumber::GENESIS + 1),
+            contract_address: contract_address_bytes!(b"contract 0"),
+            keys: vec![],
+        };
+        let output = get_proof(context, input).await.unwrap();
+        assert!(output.contract_data.is_some());
+    }
 }
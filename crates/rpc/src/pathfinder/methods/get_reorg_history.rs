@@ -0,0 +1,121 @@
+use anyhow::Context;
+use pathfinder_common::{BlockHash, BlockNumber};
+use serde::Serialize;
+
+use crate::context::RpcContext;
+
+/// The maximum number of reorgs returned.
+const MAX_REORGS: usize = 100;
+
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct GetReorgHistoryOutput {
+    /// The total number of reorgs performed since the database was created.
+    reorg_count: u64,
+    /// The most recent reorgs, newest first.
+    reorgs: Vec<Reorg>,
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct Reorg {
+    /// Unix timestamp in seconds.
+    timestamp: u64,
+    old_head: Head,
+    new_head: Option<Head>,
+    depth: u64,
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct Head {
+    block_number: BlockNumber,
+    block_hash: BlockHash,
+}
+
+impl From<pathfinder_storage::Reorg> for Reorg {
+    fn from(reorg: pathfinder_storage::Reorg) -> Self {
+        let head = |(block_number, block_hash)| Head {
+            block_number,
+            block_hash,
+        };
+
+        Self {
+            timestamp: reorg.timestamp,
+            old_head: head(reorg.old_head),
+            new_head: reorg.new_head.map(head),
+            depth: reorg.depth,
+        }
+    }
+}
+
+crate::error::generate_rpc_error_subset!(GetReorgHistoryError:);
+
+/// Returns the number of reorgs performed by sync along with the most recent ones.
+pub async fn get_reorg_history(
+    context: RpcContext,
+) -> Result<GetReorgHistoryOutput, GetReorgHistoryError> {
+    let storage = context.storage.clone();
+    let span = tracing::Span::current();
+
+    let jh = tokio::task::spawn_blocking(move || {
+        let _g = span.enter();
+        let mut db = storage
+            .connection()
+            .context("Opening database connection")?;
+
+        let tx = db.transaction().context("Creating database transaction")?;
+
+        let reorg_count = tx.reorg_counter().context("Querying reorg counter")?.get();
+        let reorgs = tx
+            .reorg_history(MAX_REORGS)
+            .context("Querying reorg history")?
+            .into_iter()
+            .map(Into::into)
+            .collect();
+
+        Ok(GetReorgHistoryOutput {
+            reorg_count,
+            reorgs,
+        })
+    });
+
+    jh.await.context("Database read panic or shutting down")?
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn history() {
+        let context = RpcContext::for_tests();
+        {
+            let mut db = context.storage.connection().unwrap();
+            let tx = db.transaction().unwrap();
+            tx.increment_reorg_counter().unwrap();
+            tx.insert_reorg(&pathfinder_storage::Reorg {
+                timestamp: 1000,
+                old_head: (BlockNumber::new_or_panic(3), block_hash!("0x3")),
+                new_head: Some((BlockNumber::new_or_panic(1), block_hash!("0x1"))),
+                depth: 2,
+            })
+            .unwrap();
+            tx.commit().unwrap();
+        }
+
+        let output = get_reorg_history(context).await.unwrap();
+
+        assert_eq!(
+            serde_json::to_value(output).unwrap(),
+            serde_json::json!({
+                "reorg_count": 1,
+                "reorgs": [{
+                    "timestamp": 1000,
+                    "old_head": {"block_number": 3, "block_hash": "0x3"},
+                    "new_head": {"block_number": 1, "block_hash": "0x1"},
+                    "depth": 2,
+                }],
+            })
+        );
+    }
+}
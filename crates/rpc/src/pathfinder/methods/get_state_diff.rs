@@ -0,0 +1,350 @@
+use anyhow::{anyhow, Context};
+use serde::{Deserialize, Serialize};
+
+use crate::context::RpcContext;
+use pathfinder_common::{prelude::*, BlockId};
+use pathfinder_crypto::Felt;
+use pathfinder_merkle_tree::{ClassCommitmentTree, ContractsStorageTree, StorageCommitmentTree};
+
+/// The maximum number of contracts whose state may differ between the two blocks.
+const MAX_CONTRACTS: usize = 1000;
+/// The maximum number of storage slots which may differ, summed over all contracts.
+const MAX_STORAGE_ENTRIES: usize = 10_000;
+/// The maximum number of classes, Cairo and Sierra, which may be declared between the two blocks.
+const MAX_CLASSES: usize = 1000;
+
+#[derive(Deserialize, Debug, PartialEq, Eq)]
+pub struct GetStateDiffInput {
+    pub from_block_id: BlockId,
+    pub to_block_id: BlockId,
+}
+
+#[derive(Debug)]
+pub enum GetStateDiffError {
+    Internal(anyhow::Error),
+    BlockNotFound,
+    StateDiffLimitExceeded { limit: u32 },
+}
+
+impl From<anyhow::Error> for GetStateDiffError {
+    fn from(e: anyhow::Error) -> Self {
+        Self::Internal(e)
+    }
+}
+
+impl From<GetStateDiffError> for crate::error::ApplicationError {
+    fn from(x: GetStateDiffError) -> Self {
+        match x {
+            GetStateDiffError::StateDiffLimitExceeded { limit } => {
+                Self::StateDiffLimitExceeded { limit }
+            }
+            GetStateDiffError::BlockNotFound => Self::BlockNotFound,
+            GetStateDiffError::Internal(internal) => Self::Internal(internal),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct StorageEntry {
+    key: StorageAddress,
+    value: StorageValue,
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct StorageDiff {
+    address: ContractAddress,
+    storage_entries: Vec<StorageEntry>,
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct NonceUpdate {
+    contract_address: ContractAddress,
+    nonce: ContractNonce,
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct ContractClassUpdate {
+    contract_address: ContractAddress,
+    class_hash: ClassHash,
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct DeclaredClass {
+    class_hash: SierraHash,
+    compiled_class_hash: CasmHash,
+}
+
+/// The state changes required to go from one block's state to another's.
+///
+/// Storage slots which were removed are reported with a value of zero.
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct GetStateDiffOutput {
+    storage_diffs: Vec<StorageDiff>,
+    nonces: Vec<NonceUpdate>,
+    deployed_or_replaced_contracts: Vec<ContractClassUpdate>,
+    declared_classes: Vec<DeclaredClass>,
+    deprecated_declared_classes: Vec<ClassHash>,
+}
+
+/// Computes the state difference between two blocks by comparing their state tries.
+///
+/// Only the parts of the tries which differ are visited, so the cost depends on the
+/// size of the difference and not on the number of blocks in between.
+pub async fn get_state_diff(
+    context: RpcContext,
+    input: GetStateDiffInput,
+) -> Result<GetStateDiffOutput, GetStateDiffError> {
+    let from = non_pending(input.from_block_id)?;
+    let to = non_pending(input.to_block_id)?;
+
+    let storage = context.storage.clone();
+    let span = tracing::Span::current();
+
+    let jh = tokio::task::spawn_blocking(move || {
+        let _g = span.enter();
+        let mut db = storage
+            .connection()
+            .context("Opening database connection")?;
+
+        let tx = db.transaction().context("Creating database transaction")?;
+
+        let (from, _) = tx
+            .block_id(from)
+            .context("Querying from block")?
+            .ok_or(GetStateDiffError::BlockNotFound)?;
+        let (to, _) = tx
+            .block_id(to)
+            .context("Querying to block")?
+            .ok_or(GetStateDiffError::BlockNotFound)?;
+
+        // The diffs stop once they exceed the limit, so that large ranges are rejected
+        // without walking the full tries.
+        let contracts = StorageCommitmentTree::diff(&tx, from, to, MAX_CONTRACTS)
+            .context("Diffing storage tries")?;
+        if contracts.len() > MAX_CONTRACTS {
+            return Err(GetStateDiffError::StateDiffLimitExceeded {
+                limit: MAX_CONTRACTS as u32,
+            });
+        }
+
+        let mut storage_diffs = Vec::new();
+        let mut nonces = Vec::new();
+        let mut deployed_or_replaced_contracts = Vec::new();
+        let mut storage_entries_left = MAX_STORAGE_ENTRIES;
+
+        for contract in contracts {
+            let contract_address = ContractAddress(
+                Felt::from_bits(&contract.key).context("Mapping leaf path to contract address")?,
+            );
+
+            let storage_entries =
+                ContractsStorageTree::diff(&tx, contract_address, from, to, storage_entries_left)
+                    .context("Diffing contract storage tries")?;
+            storage_entries_left = storage_entries_left
+                .checked_sub(storage_entries.len())
+                .ok_or(GetStateDiffError::StateDiffLimitExceeded {
+                    limit: MAX_STORAGE_ENTRIES as u32,
+                })?;
+
+            let storage_entries = storage_entries
+                .into_iter()
+                .map(|leaf| {
+                    let key = StorageAddress(
+                        Felt::from_bits(&leaf.key)
+                            .context("Mapping leaf path to storage address")?,
+                    );
+                    let value = StorageValue(leaf.new.unwrap_or_default());
+
+                    anyhow::Ok(StorageEntry { key, value })
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?;
+
+            if !storage_entries.is_empty() {
+                storage_diffs.push(StorageDiff {
+                    address: contract_address,
+                    storage_entries,
+                });
+            }
+
+            let old_nonce = tx
+                .contract_nonce(contract_address, from.into())
+                .context("Querying contract's nonce")?;
+            let new_nonce = tx
+                .contract_nonce(contract_address, to.into())
+                .context("Querying contract's nonce")?;
+            if old_nonce != new_nonce {
+                nonces.push(NonceUpdate {
+                    contract_address,
+                    nonce: new_nonce.unwrap_or_default(),
+                });
+            }
+
+            let old_class = tx
+                .contract_class_hash(from.into(), contract_address)
+                .context("Querying contract's class hash")?;
+            let new_class = tx
+                .contract_class_hash(to.into(), contract_address)
+                .context("Querying contract's class hash")?;
+            if let Some(class_hash) = new_class.filter(|new| Some(*new) != old_class) {
+                deployed_or_replaced_contracts.push(ContractClassUpdate {
+                    contract_address,
+                    class_hash,
+                });
+            }
+        }
+
+        let declared_classes =
+            ClassCommitmentTree::diff(&tx, from, to, MAX_CLASSES).context("Diffing class tries")?;
+        if declared_classes.len() > MAX_CLASSES {
+            return Err(GetStateDiffError::StateDiffLimitExceeded {
+                limit: MAX_CLASSES as u32,
+            });
+        }
+
+        let declared_classes = declared_classes
+            .into_iter()
+            .filter(|leaf| leaf.old.is_none())
+            .map(|leaf| {
+                let class_hash = SierraHash(
+                    Felt::from_bits(&leaf.key).context("Mapping leaf path to sierra hash")?,
+                );
+                let compiled_class_hash = tx
+                    .casm_hash_at(to.into(), ClassHash(class_hash.0))
+                    .context("Querying CASM hash")?
+                    .context("CASM hash missing for declared class")?;
+
+                anyhow::Ok(DeclaredClass {
+                    class_hash,
+                    compiled_class_hash,
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        // Cairo classes are not part of the class trie, so they are taken from the blocks
+        // in between instead.
+        let classes_left = MAX_CLASSES - declared_classes.len();
+        let mut deprecated_declared_classes = tx
+            .cairo_classes_declared_between(from, to, classes_left + 1)
+            .context("Querying Cairo class declarations")?;
+        if deprecated_declared_classes.len() > classes_left {
+            return Err(GetStateDiffError::StateDiffLimitExceeded {
+                limit: MAX_CLASSES as u32,
+            });
+        }
+        deprecated_declared_classes.sort();
+
+        Ok(GetStateDiffOutput {
+            storage_diffs,
+            nonces,
+            deployed_or_replaced_contracts,
+            declared_classes,
+            deprecated_declared_classes,
+        })
+    });
+
+    jh.await.context("Database read panic or shutting down")?
+}
+
+fn non_pending(block_id: BlockId) -> Result<pathfinder_storage::BlockId, GetStateDiffError> {
+    match block_id {
+        BlockId::Pending => Err(GetStateDiffError::Internal(anyhow!(
+            "'pending' is not currently supported by this method!"
+        ))),
+        other => Ok(other.try_into().expect("Only pending cast should fail")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn genesis_to_latest() {
+        let context = RpcContext::for_tests();
+        let input = GetStateDiffInput {
+            from_block_id: BlockId::Number(BlockNumber::GENESIS),
+            to_block_id: BlockId::Latest,
+        };
+
+        let output = get_state_diff(context, input).await.unwrap();
+
+        let contract1 = contract_address_bytes!(b"contract 1");
+        let contract2 = contract_address_bytes!(b"contract 2 (sierra)");
+
+        let mut nonces = vec![
+            NonceUpdate {
+                contract_address: contract1,
+                nonce: contract_nonce!("0x10"),
+            },
+            NonceUpdate {
+                contract_address: contract2,
+                nonce: contract_nonce!("0xfeed"),
+            },
+        ];
+        nonces.sort_by_key(|x| x.contract_address);
+
+        let mut deployed_or_replaced_contracts = vec![
+            ContractClassUpdate {
+                contract_address: contract1,
+                class_hash: class_hash_bytes!(b"class 1 hash"),
+            },
+            ContractClassUpdate {
+                contract_address: contract2,
+                class_hash: class_hash_bytes!(b"class 2 hash (sierra)"),
+            },
+        ];
+        deployed_or_replaced_contracts.sort_by_key(|x| x.contract_address);
+
+        let expected = GetStateDiffOutput {
+            storage_diffs: vec![StorageDiff {
+                address: contract1,
+                storage_entries: vec![StorageEntry {
+                    key: storage_address_bytes!(b"storage addr 0"),
+                    value: storage_value_bytes!(b"storage value 2"),
+                }],
+            }],
+            nonces,
+            deployed_or_replaced_contracts,
+            declared_classes: vec![],
+            deprecated_declared_classes: vec![],
+        };
+
+        assert_eq!(output, expected);
+    }
+
+    #[tokio::test]
+    async fn same_block_is_empty() {
+        let context = RpcContext::for_tests();
+        let input = GetStateDiffInput {
+            from_block_id: BlockId::Latest,
+            to_block_id: BlockId::Latest,
+        };
+
+        let output = get_state_diff(context, input).await.unwrap();
+
+        assert_eq!(
+            output,
+            GetStateDiffOutput {
+                storage_diffs: vec![],
+                nonces: vec![],
+                deployed_or_replaced_contracts: vec![],
+                declared_classes: vec![],
+                deprecated_declared_classes: vec![],
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn block_not_found() {
+        let context = RpcContext::for_tests();
+        let input = GetStateDiffInput {
+            from_block_id: BlockId::Number(BlockNumber::GENESIS),
+            to_block_id: BlockId::Number(BlockNumber::new_or_panic(9999)),
+        };
+
+        let err = get_state_diff(context, input).await.unwrap_err();
+        assert_matches::assert_matches!(err, GetStateDiffError::BlockNotFound);
+    }
+}
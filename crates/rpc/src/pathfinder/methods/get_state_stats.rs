@@ -0,0 +1,204 @@
+use anyhow::{anyhow, Context};
+use serde::{Deserialize, Serialize};
+
+use crate::context::RpcContext;
+use pathfinder_common::{BlockHash, BlockId, BlockNumber};
+
+#[derive(Deserialize, Debug, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct GetStateStatsInput {
+    pub block_id: BlockId,
+    /// Also return the size of each database table, which requires reading the whole database.
+    /// Only allowed if enabled by the node operator.
+    #[serde(default)]
+    pub with_table_sizes: bool,
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct TableSize {
+    schema: String,
+    name: String,
+    size: u64,
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct GetStateStatsOutput {
+    block_number: BlockNumber,
+    block_hash: BlockHash,
+    contracts: u64,
+    declared_classes: u64,
+    storage_leaves: u64,
+    storage_diffs: u64,
+    nonce_updates: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    table_sizes: Option<Vec<TableSize>>,
+}
+
+crate::error::generate_rpc_error_subset!(GetStateStatsError: BlockNotFound, TableSizesDisabled);
+
+/// Returns totals of the chain's state as of a block.
+///
+/// The totals are maintained as blocks are stored, so this is cheap unless table sizes are
+/// requested. These scan the whole database, and are therefore only returned if enabled with
+/// `--rpc.table-sizes`.
+pub async fn get_state_stats(
+    context: RpcContext,
+    input: GetStateStatsInput,
+) -> Result<GetStateStatsOutput, GetStateStatsError> {
+    if input.with_table_sizes && !context.config.table_sizes {
+        return Err(GetStateStatsError::TableSizesDisabled);
+    }
+
+    let block_id = match input.block_id {
+        BlockId::Pending => {
+            return Err(GetStateStatsError::Custom(anyhow!(
+                "'pending' is not supported by this method"
+            )))
+        }
+        other => other.try_into().expect("Only pending cast should fail"),
+    };
+
+    let storage = context.storage.clone();
+    let span = tracing::Span::current();
+
+    let jh = tokio::task::spawn_blocking(move || {
+        let _g = span.enter();
+        let mut db = storage
+            .connection()
+            .context("Opening database connection")?;
+
+        let tx = db.transaction().context("Creating database transaction")?;
+
+        let (block_number, block_hash) = tx
+            .block_id(block_id)
+            .context("Querying block")?
+            .ok_or(GetStateStatsError::BlockNotFound)?;
+
+        let stats = tx
+            .state_stats(block_number.into())
+            .context("Querying state stats")?
+            .ok_or_else(|| {
+                GetStateStatsError::Custom(anyhow!(
+                    "State statistics are not available for this block"
+                ))
+            })?;
+
+        let table_sizes = if input.with_table_sizes {
+            let sizes = tx.table_sizes().context("Querying table sizes")?;
+            Some(
+                sizes
+                    .into_iter()
+                    .map(|x| TableSize {
+                        schema: x.schema,
+                        name: x.name,
+                        size: x.size,
+                    })
+                    .collect(),
+            )
+        } else {
+            None
+        };
+
+        Ok(GetStateStatsOutput {
+            block_number,
+            block_hash,
+            contracts: stats.contracts,
+            declared_classes: stats.declared_classes,
+            storage_leaves: stats.storage_leaves,
+            storage_diffs: stats.storage_diffs,
+            nonce_updates: stats.nonce_updates,
+            table_sizes,
+        })
+    });
+
+    jh.await.context("Database read panic or shutting down")?
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+    use pathfinder_common::macro_prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn parsing() {
+        let input = serde_json::json!({ "block_id": "latest" });
+
+        let input = serde_json::from_value::<GetStateStatsInput>(input).unwrap();
+
+        assert_eq!(
+            input,
+            GetStateStatsInput {
+                block_id: BlockId::Latest,
+                with_table_sizes: false,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn latest() {
+        let context = RpcContext::for_tests();
+        let input = GetStateStatsInput {
+            block_id: BlockId::Latest,
+            with_table_sizes: false,
+        };
+
+        let output = get_state_stats(context, input).await.unwrap();
+
+        assert_eq!(
+            output,
+            GetStateStatsOutput {
+                block_number: BlockNumber::new_or_panic(2),
+                block_hash: block_hash_bytes!(b"latest"),
+                contracts: 3,
+                declared_classes: 0,
+                storage_leaves: 1,
+                storage_diffs: 2,
+                nonce_updates: 3,
+                table_sizes: None,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn table_sizes() {
+        let mut context = RpcContext::for_tests();
+        context.config.table_sizes = true;
+        let input = GetStateStatsInput {
+            block_id: BlockId::Latest,
+            with_table_sizes: true,
+        };
+
+        let output = get_state_stats(context, input).await.unwrap();
+
+        let table_sizes = output.table_sizes.unwrap();
+        assert!(table_sizes
+            .iter()
+            .any(|x| x.schema == "main" && x.name == "state_stats"));
+    }
+
+    #[tokio::test]
+    async fn table_sizes_disabled() {
+        let context = RpcContext::for_tests();
+        let input = GetStateStatsInput {
+            block_id: BlockId::Latest,
+            with_table_sizes: true,
+        };
+
+        let err = get_state_stats(context, input).await.unwrap_err();
+        assert_matches!(err, GetStateStatsError::TableSizesDisabled);
+    }
+
+    #[tokio::test]
+    async fn block_not_found() {
+        let context = RpcContext::for_tests();
+        let input = GetStateStatsInput {
+            block_id: BlockId::Hash(block_hash_bytes!(b"non-existent")),
+            with_table_sizes: false,
+        };
+
+        let err = get_state_stats(context, input).await.unwrap_err();
+        assert_matches!(err, GetStateStatsError::BlockNotFound);
+    }
+}
@@ -0,0 +1,268 @@
+use std::collections::HashMap;
+
+use anyhow::Context;
+use serde::Deserialize;
+
+use crate::context::RpcContext;
+use pathfinder_common::{BlockId, ContractAddress, StorageAddress, StorageValue};
+
+/// The maximum number of storage reads in a single request.
+const MAX_READS: usize = 1000;
+
+#[derive(Deserialize, Debug, PartialEq, Eq)]
+pub struct GetStorageAtBatchInput {
+    pub block_id: BlockId,
+    pub requests: Vec<StorageRead>,
+}
+
+#[derive(Deserialize, Debug, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct StorageRead {
+    pub contract_address: ContractAddress,
+    pub key: StorageAddress,
+}
+
+// FIXME: allow `generate_rpc_error_subset!` to work with enum struct variants. This may not actually be possible though.
+#[derive(Debug)]
+pub enum GetStorageAtBatchError {
+    Internal(anyhow::Error),
+    BlockNotFound,
+    StorageReadLimitExceeded { limit: u32, requested: u32 },
+}
+
+impl From<anyhow::Error> for GetStorageAtBatchError {
+    fn from(e: anyhow::Error) -> Self {
+        Self::Internal(e)
+    }
+}
+
+impl From<GetStorageAtBatchError> for crate::error::ApplicationError {
+    fn from(x: GetStorageAtBatchError) -> Self {
+        match x {
+            GetStorageAtBatchError::StorageReadLimitExceeded { limit, requested } => {
+                Self::StorageReadLimitExceeded { limit, requested }
+            }
+            GetStorageAtBatchError::BlockNotFound => Self::BlockNotFound,
+            GetStorageAtBatchError::Internal(internal) => Self::Internal(internal),
+        }
+    }
+}
+
+/// Reads many storage values at the same block from a single database snapshot.
+///
+/// The values are returned in the order of the requests. Unlike `starknet_getStorageAt`, reads of
+/// contracts which do not exist do not fail the request but return `null`.
+pub async fn get_storage_at_batch(
+    context: RpcContext,
+    input: GetStorageAtBatchInput,
+) -> Result<Vec<Option<StorageValue>>, GetStorageAtBatchError> {
+    if input.requests.len() > MAX_READS {
+        return Err(GetStorageAtBatchError::StorageReadLimitExceeded {
+            limit: MAX_READS as u32,
+            requested: input.requests.len() as u32,
+        });
+    }
+
+    let storage = context.storage.clone();
+    let span = tracing::Span::current();
+
+    let jh = tokio::task::spawn_blocking(move || {
+        let _g = span.enter();
+        let mut db = storage
+            .connection()
+            .context("Opening database connection")?;
+
+        let tx = db.transaction().context("Creating database transaction")?;
+
+        let pending = if input.block_id.is_pending() {
+            Some(
+                context
+                    .pending_data
+                    .get(&tx)
+                    .context("Querying pending data")?,
+            )
+        } else {
+            None
+        };
+
+        let block_id = match input.block_id {
+            BlockId::Pending => pathfinder_storage::BlockId::Latest,
+            other => other.try_into().expect("Only pending cast should fail"),
+        };
+
+        // Check for block existence.
+        if !tx.block_exists(block_id)? {
+            return Err(GetStorageAtBatchError::BlockNotFound);
+        }
+
+        // Requests typically read many keys of the same few contracts.
+        let mut contract_exists = HashMap::new();
+        let mut values = Vec::with_capacity(input.requests.len());
+        for StorageRead {
+            contract_address,
+            key,
+        } in input.requests
+        {
+            if let Some(value) = pending
+                .as_ref()
+                .and_then(|pending| pending.state_update.storage_value(contract_address, key))
+            {
+                values.push(Some(value));
+                continue;
+            }
+
+            let value = tx
+                .storage_value(block_id, contract_address, key)
+                .context("Querying storage value")?;
+            let value = match value {
+                Some(value) => Some(value),
+                None => {
+                    let exists = match contract_exists.get(&contract_address) {
+                        Some(exists) => *exists,
+                        None => {
+                            let exists = tx.contract_exists(contract_address, block_id)?;
+                            contract_exists.insert(contract_address, exists);
+                            exists
+                        }
+                    };
+                    exists.then_some(StorageValue::ZERO)
+                }
+            };
+            values.push(value);
+        }
+
+        Ok(values)
+    });
+
+    jh.await.context("Database read panic or shutting down")?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_matches::assert_matches;
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_crypto::Felt;
+    use serde_json::json;
+
+    #[test]
+    fn parsing() {
+        let input = json!({
+            "block_id": "latest",
+            "requests": [
+                {"contract_address": "0x1", "key": "0x2"},
+                {"contract_address": "0x3", "key": "0x4"},
+            ]
+        });
+
+        let input = serde_json::from_value::<GetStorageAtBatchInput>(input).unwrap();
+
+        assert_eq!(
+            input,
+            GetStorageAtBatchInput {
+                block_id: BlockId::Latest,
+                requests: vec![
+                    StorageRead {
+                        contract_address: contract_address!("0x1"),
+                        key: storage_address!("0x2"),
+                    },
+                    StorageRead {
+                        contract_address: contract_address!("0x3"),
+                        key: storage_address!("0x4"),
+                    },
+                ],
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn latest() {
+        let context = RpcContext::for_tests_with_pending().await;
+        let input = GetStorageAtBatchInput {
+            block_id: BlockId::Latest,
+            requests: vec![
+                StorageRead {
+                    contract_address: contract_address_bytes!(b"contract 1"),
+                    key: storage_address_bytes!(b"storage addr 0"),
+                },
+                StorageRead {
+                    contract_address: contract_address_bytes!(b"contract 1"),
+                    key: storage_address_bytes!(b"non-existent"),
+                },
+                StorageRead {
+                    contract_address: contract_address_bytes!(b"non-existent"),
+                    key: storage_address_bytes!(b"storage addr 0"),
+                },
+            ],
+        };
+
+        let values = get_storage_at_batch(context, input).await.unwrap();
+
+        assert_eq!(
+            values,
+            vec![
+                Some(storage_value_bytes!(b"storage value 2")),
+                Some(StorageValue::ZERO),
+                None,
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn pending() {
+        let context = RpcContext::for_tests_with_pending().await;
+        let input = GetStorageAtBatchInput {
+            block_id: BlockId::Pending,
+            requests: vec![
+                StorageRead {
+                    contract_address: contract_address_bytes!(b"pending contract 1 address"),
+                    key: storage_address_bytes!(b"pending storage key 0"),
+                },
+                // Falls back to the latest block.
+                StorageRead {
+                    contract_address: contract_address_bytes!(b"contract 1"),
+                    key: storage_address_bytes!(b"storage addr 0"),
+                },
+            ],
+        };
+
+        let values = get_storage_at_batch(context, input).await.unwrap();
+
+        assert_eq!(
+            values,
+            vec![
+                Some(storage_value_bytes!(b"pending storage value 0")),
+                Some(storage_value_bytes!(b"storage value 2")),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn block_not_found() {
+        let context = RpcContext::for_tests();
+        let input = GetStorageAtBatchInput {
+            block_id: BlockId::Hash(block_hash_bytes!(b"invalid")),
+            requests: vec![],
+        };
+
+        let err = get_storage_at_batch(context, input).await.unwrap_err();
+        assert_matches!(err, GetStorageAtBatchError::BlockNotFound);
+    }
+
+    #[tokio::test]
+    async fn limit_exceeded() {
+        let context = RpcContext::for_tests();
+        let input = GetStorageAtBatchInput {
+            block_id: BlockId::Latest,
+            requests: (0..10_000)
+                .map(|idx| StorageRead {
+                    contract_address: contract_address!("0xdeadbeef"),
+                    key: StorageAddress::new_or_panic(Felt::from_u64(idx)),
+                })
+                .collect(),
+        };
+
+        let err = get_storage_at_batch(context, input).await.unwrap_err();
+        assert_matches!(err, GetStorageAtBatchError::StorageReadLimitExceeded { .. });
+    }
+}
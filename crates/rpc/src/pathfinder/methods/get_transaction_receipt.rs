@@ -0,0 +1,32 @@
+use crate::context::RpcContext;
+use crate::v07::method::get_transaction_receipt::{transaction_receipt, Error, Input, Output};
+
+/// Returns the v0.7 receipt of a transaction, extended with the `transaction_index` of
+/// the transaction and the L1 `message_hash` of each sent message.
+pub async fn get_transaction_receipt(context: RpcContext, input: Input) -> Result<Output, Error> {
+    transaction_receipt(context, input, true).await
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use serde::Serialize;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn includes_transaction_index() {
+        let context = RpcContext::for_tests();
+        let input = Input {
+            transaction_hash: transaction_hash_bytes!(b"txn 0"),
+        };
+
+        let output = get_transaction_receipt(context, input)
+            .await
+            .unwrap()
+            .serialize(serde_json::value::Serializer)
+            .unwrap();
+
+        assert_eq!(output["transaction_index"], serde_json::json!(0));
+    }
+}
@@ -106,6 +106,8 @@ pub enum TransactionStatus {
     Reverted,
     #[serde(rename = "ABORTED")]
     Aborted,
+    #[serde(rename = "UNKNOWN")]
+    Unknown,
 }
 
 impl From<starknet_gateway_types::reply::Status> for TransactionStatus {
@@ -120,6 +122,7 @@ impl From<starknet_gateway_types::reply::Status> for TransactionStatus {
             Status::AcceptedOnL2 => Self::AcceptedOnL2,
             Status::Reverted => Self::Reverted,
             Status::Aborted => Self::Aborted,
+            Status::Unknown => Self::Unknown,
         }
     }
 }
@@ -0,0 +1,95 @@
+use pathfinder_common::typed_data::TypedData;
+use pathfinder_common::ContractAddress;
+use pathfinder_crypto::Felt;
+
+use crate::context::RpcContext;
+
+#[derive(serde::Deserialize, Debug, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct HashTypedDataInput {
+    pub typed_data: TypedData,
+    pub account_address: ContractAddress,
+}
+
+crate::error::generate_rpc_error_subset!(HashTypedDataError);
+
+/// Computes the SNIP-12 hash of typed data which the account signs.
+///
+/// The signature can then be verified by calling the account's `is_valid_signature` with the
+/// hash.
+pub async fn hash_typed_data(
+    _context: RpcContext,
+    input: HashTypedDataInput,
+) -> Result<Felt, HashTypedDataError> {
+    // Invalid typed data is the caller's mistake, so the details are reported.
+    input
+        .typed_data
+        .message_hash(input.account_address)
+        .map_err(HashTypedDataError::Custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_matches::assert_matches;
+    use pathfinder_common::macro_prelude::*;
+    use serde_json::json;
+
+    fn input() -> serde_json::Value {
+        json!({
+            "typed_data": {
+                "types": {
+                    "StarkNetDomain": [
+                        { "name": "name", "type": "felt" },
+                        { "name": "version", "type": "felt" },
+                        { "name": "chainId", "type": "felt" }
+                    ],
+                    "Person": [
+                        { "name": "name", "type": "felt" },
+                        { "name": "wallet", "type": "felt" }
+                    ],
+                    "Mail": [
+                        { "name": "from", "type": "Person" },
+                        { "name": "to", "type": "Person" },
+                        { "name": "contents", "type": "felt" }
+                    ]
+                },
+                "primaryType": "Mail",
+                "domain": { "name": "StarkNet Mail", "version": "1", "chainId": 1 },
+                "message": {
+                    "from": { "name": "Cow", "wallet": "0xCD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826" },
+                    "to": { "name": "Bob", "wallet": "0xbBbBBBBbbBBBbbbBbbBbbbbBBbBbbbbBbBbbBBbB" },
+                    "contents": "Hello, Bob!"
+                }
+            },
+            "account_address": "0xcd2a3d9f938e13cd947ec05abc7fe734df8dd826"
+        })
+    }
+
+    #[tokio::test]
+    async fn hash() {
+        let input = serde_json::from_value::<HashTypedDataInput>(input()).unwrap();
+
+        let hash = hash_typed_data(RpcContext::for_tests(), input)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            hash,
+            felt!("0x6fcff244f63e38b9d88b9e3378d44757710d1b244282b435cb472053c8d78d0")
+        );
+    }
+
+    #[tokio::test]
+    async fn invalid_typed_data() {
+        let mut input = input();
+        input["typed_data"]["primaryType"] = json!("Letter");
+        let input = serde_json::from_value::<HashTypedDataInput>(input).unwrap();
+
+        let err = hash_typed_data(RpcContext::for_tests(), input)
+            .await
+            .unwrap_err();
+
+        assert_matches!(err, HashTypedDataError::Custom(_));
+    }
+}
@@ -0,0 +1,216 @@
+use anyhow::Context;
+use pathfinder_common::BlockId;
+use pathfinder_executor::{ExecutionState, L1BlobDataAvailability};
+use serde::{Deserialize, Serialize};
+
+use crate::context::RpcContext;
+use crate::executor::{with_timeout, ExecutionMethod};
+use crate::jsonrpc::RpcError;
+use crate::v05::method::call::{CallError, CallOutput, FunctionCall};
+
+/// The maximum number of calls in a single request.
+const MAX_CALLS: usize = 100;
+
+#[derive(Deserialize, Debug, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct MulticallInput {
+    pub block_id: BlockId,
+    pub calls: Vec<FunctionCall>,
+}
+
+/// The outcome of a single call, which is either its result or the error `starknet_call` would
+/// have failed with.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum CallResult {
+    Result(CallOutput),
+    Error(RpcError),
+}
+
+crate::error::generate_rpc_error_subset!(MulticallError: BlockNotFound, ExecutionTimeout);
+
+impl From<crate::executor::ExecutionTimeout> for MulticallError {
+    fn from(_: crate::executor::ExecutionTimeout) -> Self {
+        Self::ExecutionTimeout
+    }
+}
+
+/// Executes the calls one after the other against the same state of a block.
+///
+/// The state read by a call is cached for the calls following it, which makes this a lot faster
+/// than separate `starknet_call` requests. State changes made by a call are not visible to the
+/// others. The results are returned in the order of the calls, and a failing call does not fail
+/// the request.
+pub async fn multicall(
+    context: RpcContext,
+    input: MulticallInput,
+) -> Result<Vec<CallResult>, MulticallError> {
+    if input.calls.len() > MAX_CALLS {
+        return Err(MulticallError::Custom(anyhow::anyhow!(
+            "At most {MAX_CALLS} calls may be requested"
+        )));
+    }
+
+    let timeout = context.config.execution_timeout;
+    let pool = context.execution_pool.clone();
+    let execution = pool.execute(ExecutionMethod::Call, move || {
+        let mut db = context
+            .storage
+            .connection()
+            .context("Creating database connection")?;
+        let db = db.transaction().context("Creating database transaction")?;
+
+        let (header, pending) = match input.block_id {
+            BlockId::Pending => {
+                let pending = context
+                    .pending_data
+                    .get(&db)
+                    .context("Querying pending data")?;
+
+                (pending.header(), Some(pending.state_update.clone()))
+            }
+            other => {
+                let block_id = other.try_into().expect("Only pending cast should fail");
+                let header = db
+                    .block_header(block_id)
+                    .context("Querying block header")?
+                    .ok_or(MulticallError::BlockNotFound)?;
+
+                (header, None)
+            }
+        };
+
+        let state = ExecutionState::simulation(
+            &db,
+            context.chain_id,
+            header,
+            pending,
+            L1BlobDataAvailability::Disabled,
+        )
+        .with_max_steps(context.config.execution_max_steps)
+        .with_fork(context.fork.clone());
+
+        let calls = input
+            .calls
+            .into_iter()
+            .map(|call| pathfinder_executor::Call {
+                contract_address: call.contract_address,
+                entry_point_selector: call.entry_point_selector,
+                calldata: call.calldata,
+            })
+            .collect();
+
+        let results = pathfinder_executor::multicall(state, calls)?;
+
+        results
+            .into_iter()
+            .map(|result| match result.map_err(CallError::from) {
+                Ok(result) => Ok(CallResult::Result(CallOutput(result))),
+                // Internal errors are not the call's fault, so they fail the request.
+                Err(CallError::Internal(e)) => Err(MulticallError::Internal(e)),
+                Err(error) => Ok(CallResult::Error(RpcError::from(error))),
+            })
+            .collect()
+    });
+
+    with_timeout(timeout, execution).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_matches::assert_matches;
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::{CallParam, EntryPoint, StarknetVersion};
+    use serde_json::json;
+
+    #[test]
+    fn parsing() {
+        let input = json!({
+            "block_id": "latest",
+            "calls": [
+                {"contract_address": "0x1", "entry_point_selector": "0x2", "calldata": ["0x3"]},
+            ],
+        });
+
+        let input = serde_json::from_value::<MulticallInput>(input).unwrap();
+
+        assert_eq!(
+            input,
+            MulticallInput {
+                block_id: BlockId::Latest,
+                calls: vec![FunctionCall {
+                    contract_address: contract_address!("0x1"),
+                    entry_point_selector: entry_point!("0x2"),
+                    calldata: vec![call_param!("0x3")],
+                }],
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn calls() {
+        let (context, last_block_header, account, _) =
+            crate::test_setup::test_context_with_starknet_version(StarknetVersion::new(0, 13, 1))
+                .await;
+
+        let balance_of = |token| FunctionCall {
+            contract_address: token,
+            entry_point_selector: EntryPoint::hashed(b"balanceOf"),
+            calldata: vec![CallParam(account.0)],
+        };
+        let input = MulticallInput {
+            block_id: BlockId::Number(last_block_header.number),
+            calls: vec![
+                balance_of(pathfinder_executor::ETH_FEE_TOKEN_ADDRESS),
+                balance_of(contract_address!("0xdeadbeef")),
+                FunctionCall {
+                    entry_point_selector: EntryPoint::hashed(b"notAnEntryPoint"),
+                    ..balance_of(pathfinder_executor::ETH_FEE_TOKEN_ADDRESS)
+                },
+                balance_of(pathfinder_executor::STRK_FEE_TOKEN_ADDRESS),
+            ],
+        };
+
+        let results = multicall(context, input).await.unwrap();
+        let results = serde_json::to_value(results).unwrap();
+
+        let balance = json!({"result": ["0x10000000000000000000000000000", "0x0"]});
+        assert_eq!(results[0], balance);
+        assert_eq!(
+            results[1],
+            json!({"error": {"code": 20, "message": "Contract not found"}})
+        );
+        assert_eq!(results[2]["error"]["code"], json!(-32603));
+        assert_eq!(results[3], balance);
+    }
+
+    #[tokio::test]
+    async fn too_many_calls() {
+        let context = RpcContext::for_tests();
+        let call = FunctionCall {
+            contract_address: contract_address!("0x1"),
+            entry_point_selector: entry_point!("0x2"),
+            calldata: vec![],
+        };
+        let input = MulticallInput {
+            block_id: BlockId::Latest,
+            calls: vec![call; MAX_CALLS + 1],
+        };
+
+        let err = multicall(context, input).await.unwrap_err();
+        assert_matches!(err, MulticallError::Custom(_));
+    }
+
+    #[tokio::test]
+    async fn block_not_found() {
+        let context = RpcContext::for_tests();
+        let input = MulticallInput {
+            block_id: BlockId::Hash(block_hash_bytes!(b"invalid")),
+            calls: vec![],
+        };
+
+        let err = multicall(context, input).await.unwrap_err();
+        assert_matches!(err, MulticallError::BlockNotFound);
+    }
+}
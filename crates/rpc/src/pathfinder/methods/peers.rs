@@ -0,0 +1,86 @@
+use serde::Serialize;
+
+use crate::context::RpcContext;
+use crate::peer_admin::{PeerDirection, PeerInfo};
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct Peer {
+    peer_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    address: Option<String>,
+    direction: Direction,
+    score: i64,
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+enum Direction {
+    Inbound,
+    Outbound,
+}
+
+impl From<PeerInfo> for Peer {
+    fn from(peer: PeerInfo) -> Self {
+        Self {
+            peer_id: peer.peer_id,
+            address: peer.address,
+            direction: match peer.direction {
+                PeerDirection::Inbound => Direction::Inbound,
+                PeerDirection::Outbound => Direction::Outbound,
+            },
+            score: peer.score,
+        }
+    }
+}
+
+crate::error::generate_rpc_error_subset!(PeersError: PeerAdminDisabled);
+
+/// Returns the P2P peers currently connected to this node.
+pub async fn peers(context: RpcContext) -> Result<Vec<Peer>, PeersError> {
+    let peer_admin = context.peer_admin.ok_or(PeersError::PeerAdminDisabled)?;
+    let peers = peer_admin.list_peers().await?;
+    Ok(peers.into_iter().map(Into::into).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::peer_admin::{PeerAdmin, PeerAdminRequest};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn disabled() {
+        let context = RpcContext::for_tests();
+        let error = peers(context).await.unwrap_err();
+        assert_matches::assert_matches!(error, PeersError::PeerAdminDisabled);
+    }
+
+    #[tokio::test]
+    async fn connected_peers() {
+        let (peer_admin, mut requests) = PeerAdmin::new();
+        let context = RpcContext::for_tests().with_peer_admin(peer_admin);
+
+        tokio::spawn(async move {
+            let Some(PeerAdminRequest::ListPeers { reply }) = requests.recv().await else {
+                panic!("Expected a list peers request");
+            };
+            let _ = reply.send(vec![PeerInfo {
+                peer_id: "peer".to_owned(),
+                address: None,
+                direction: PeerDirection::Inbound,
+                score: -5,
+            }]);
+        });
+
+        let output = peers(context).await.unwrap();
+        assert_eq!(
+            output,
+            vec![Peer {
+                peer_id: "peer".to_owned(),
+                address: None,
+                direction: Direction::Inbound,
+                score: -5,
+            }]
+        );
+    }
+}
@@ -0,0 +1,81 @@
+use std::time::UNIX_EPOCH;
+
+use pathfinder_common::TransactionHash;
+use serde::Serialize;
+
+use super::get_transaction_status::TransactionStatus;
+use crate::context::RpcContext;
+use crate::mempool::{MempoolTransaction, TransactionKind};
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct PendingTransaction {
+    transaction_hash: TransactionHash,
+    r#type: TransactionType,
+    /// Unix timestamp in seconds.
+    submitted_at: u64,
+    status: TransactionStatus,
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+enum TransactionType {
+    Declare,
+    DeployAccount,
+    Invoke,
+}
+
+impl From<MempoolTransaction> for PendingTransaction {
+    fn from(tx: MempoolTransaction) -> Self {
+        Self {
+            transaction_hash: tx.hash,
+            r#type: match tx.kind {
+                TransactionKind::Declare => TransactionType::Declare,
+                TransactionKind::DeployAccount => TransactionType::DeployAccount,
+                TransactionKind::Invoke => TransactionType::Invoke,
+            },
+            submitted_at: tx
+                .submitted_at
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            status: tx.status.into(),
+        }
+    }
+}
+
+crate::error::generate_rpc_error_subset!(PendingTransactionsError:);
+
+/// Returns the transactions submitted through this node in the last hour, oldest first,
+/// along with their last known status.
+pub async fn pending_transactions(
+    context: RpcContext,
+) -> Result<Vec<PendingTransaction>, PendingTransactionsError> {
+    Ok(context
+        .mempool
+        .transactions()
+        .into_iter()
+        .map(Into::into)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn submitted_transactions() {
+        let context = RpcContext::for_tests();
+        context
+            .mempool
+            .insert(transaction_hash_bytes!(b"tx"), TransactionKind::Invoke);
+
+        let output = pending_transactions(context).await.unwrap();
+
+        assert_eq!(output.len(), 1);
+        assert_eq!(output[0].transaction_hash, transaction_hash_bytes!(b"tx"));
+        assert_eq!(output[0].r#type, TransactionType::Invoke);
+        assert_eq!(output[0].status, TransactionStatus::Received);
+    }
+}
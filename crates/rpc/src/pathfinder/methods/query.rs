@@ -0,0 +1,128 @@
+use std::time::Duration;
+
+use anyhow::Context;
+use pathfinder_storage::QueryResult;
+use serde::Deserialize;
+
+use crate::context::RpcContext;
+
+/// The maximum number of rows returned by a single query.
+const MAX_ROWS: usize = 1000;
+/// The time after which a query is interrupted.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Deserialize, Debug, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct QueryInput {
+    sql: String,
+    /// Defaults to, and is capped at, [MAX_ROWS].
+    max_rows: Option<usize>,
+}
+
+crate::error::generate_rpc_error_subset!(QueryError: QueryDisabled);
+
+impl From<pathfinder_storage::QueryError> for QueryError {
+    fn from(error: pathfinder_storage::QueryError) -> Self {
+        use pathfinder_storage::QueryError::*;
+        match error {
+            Internal(internal) => Self::Internal(internal),
+            Invalid(_) | NotReadOnly | TableNotAllowed(_) | Timeout => Self::Custom(error.into()),
+        }
+    }
+}
+
+/// Runs a read-only SQL query against the database.
+///
+/// Intended for ad hoc investigations by node operators, and therefore only available
+/// if pathfinder is built with the `rpc-query` feature and started with `--rpc.query`. Only
+/// tables holding chain data can be queried.
+pub async fn query(context: RpcContext, input: QueryInput) -> Result<QueryResult, QueryError> {
+    if !context.config.query {
+        return Err(QueryError::QueryDisabled);
+    }
+
+    let span = tracing::Span::current();
+
+    let max_rows = input.max_rows.unwrap_or(MAX_ROWS).min(MAX_ROWS);
+
+    tokio::task::spawn_blocking(move || {
+        let _g = span.enter();
+
+        let mut db = context
+            .storage
+            .connection()
+            .context("Opening database connection")?;
+        let db_tx = db.transaction().context("Creating database transaction")?;
+
+        db_tx
+            .query(&input.sql, max_rows, TIMEOUT)
+            .map_err(QueryError::from)
+    })
+    .await
+    .context("Joining database task")?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context() -> RpcContext {
+        let mut context = RpcContext::for_tests();
+        context.config.query = true;
+        context
+    }
+
+    #[tokio::test]
+    async fn select() {
+        let context = context();
+        let input = QueryInput {
+            sql: "SELECT number FROM block_headers ORDER BY number".to_owned(),
+            max_rows: Some(2),
+        };
+
+        let output = query(context, input).await.unwrap();
+
+        let expected = QueryResult {
+            columns: vec!["number".to_owned()],
+            rows: vec![vec![0.into()], vec![1.into()]],
+            truncated: true,
+        };
+        assert_eq!(output, expected);
+    }
+
+    #[tokio::test]
+    async fn write() {
+        let context = context();
+        let input = QueryInput {
+            sql: "DELETE FROM block_headers".to_owned(),
+            max_rows: None,
+        };
+
+        let error = query(context, input).await.unwrap_err();
+        assert_matches::assert_matches!(error, QueryError::Custom(_));
+    }
+
+    #[tokio::test]
+    async fn table_not_allowed() {
+        let context = context();
+        let input = QueryInput {
+            sql: "SELECT * FROM p2p_peers".to_owned(),
+            max_rows: None,
+        };
+
+        let error = query(context, input).await.unwrap_err();
+        assert_matches::assert_matches!(error, QueryError::Custom(_));
+    }
+
+    #[tokio::test]
+    async fn disabled() {
+        let context = RpcContext::for_tests();
+        let input = QueryInput {
+            sql: "SELECT number FROM block_headers".to_owned(),
+            max_rows: None,
+        };
+
+        let error = query(context, input).await.unwrap_err();
+        assert_matches::assert_matches!(error, QueryError::QueryDisabled);
+    }
+}
@@ -0,0 +1,87 @@
+//! Administration of this node's P2P peers.
+//!
+//! The RPC server has no access to the P2P network itself. Instead, each [PeerAdmin] request is
+//! forwarded to the receiving end of its channel, which is served by the P2P network task.
+use tokio::sync::{mpsc, oneshot};
+
+/// The number of requests which can be queued before callers have to wait.
+const REQUEST_CAPACITY: usize = 16;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PeerDirection {
+    Inbound,
+    Outbound,
+}
+
+/// A peer currently connected to this node.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PeerInfo {
+    pub peer_id: String,
+    pub address: Option<String>,
+    pub direction: PeerDirection,
+    /// The reputation of the peer when serving sync requests.
+    pub score: i64,
+}
+
+#[derive(Debug)]
+pub enum PeerAdminRequest {
+    ListPeers {
+        reply: oneshot::Sender<Vec<PeerInfo>>,
+    },
+    /// Dials a peer. The address must include the peer ID.
+    ConnectPeer {
+        address: String,
+        reply: oneshot::Sender<anyhow::Result<()>>,
+    },
+    /// Disconnects a peer and prevents it from reconnecting, also across restarts.
+    BanPeer {
+        peer_id: String,
+        reply: oneshot::Sender<anyhow::Result<()>>,
+    },
+}
+
+#[derive(Clone, Debug)]
+pub struct PeerAdmin {
+    requests: mpsc::Sender<PeerAdminRequest>,
+}
+
+impl PeerAdmin {
+    /// Returns the handle along with the receiving end of its requests.
+    pub fn new() -> (Self, mpsc::Receiver<PeerAdminRequest>) {
+        let (requests, receiver) = mpsc::channel(REQUEST_CAPACITY);
+        (Self { requests }, receiver)
+    }
+
+    pub async fn list_peers(&self) -> anyhow::Result<Vec<PeerInfo>> {
+        let (reply, receiver) = oneshot::channel();
+        self.send(PeerAdminRequest::ListPeers { reply }).await?;
+        receiver
+            .await
+            .map_err(|_| anyhow::anyhow!("P2P network is not running"))
+    }
+
+    pub async fn connect_peer(&self, address: String) -> anyhow::Result<()> {
+        let (reply, receiver) = oneshot::channel();
+        self.send(PeerAdminRequest::ConnectPeer { address, reply })
+            .await?;
+        receiver
+            .await
+            .map_err(|_| anyhow::anyhow!("P2P network is not running"))?
+    }
+
+    pub async fn ban_peer(&self, peer_id: String) -> anyhow::Result<()> {
+        let (reply, receiver) = oneshot::channel();
+        self.send(PeerAdminRequest::BanPeer { peer_id, reply })
+            .await?;
+        receiver
+            .await
+            .map_err(|_| anyhow::anyhow!("P2P network is not running"))?
+    }
+
+    async fn send(&self, request: PeerAdminRequest) -> anyhow::Result<()> {
+        self.requests
+            .send(request)
+            .await
+            .map_err(|_| anyhow::anyhow!("P2P network is not running"))
+    }
+}
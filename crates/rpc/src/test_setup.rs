@@ -1,9 +1,13 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
 use pathfinder_common::{macro_prelude::*, StarknetVersion, StorageAddress};
 
 use pathfinder_common::{
-    felt, BlockHash, BlockHeader, BlockNumber, BlockTimestamp, ContractAddress, GasPrice,
-    StateUpdate,
+    felt, BlockHash, BlockHeader, BlockNumber, BlockTimestamp, CasmHash, ClassHash,
+    ContractAddress, ContractNonce, GasPrice, StateUpdate, StorageValue,
 };
+use pathfinder_executor::ForkedState;
 use pathfinder_storage::Storage;
 use starknet_gateway_test_fixtures::class_definitions::{DUMMY_ACCOUNT, DUMMY_ACCOUNT_CLASS_HASH};
 
@@ -124,3 +128,60 @@ pub async fn test_context_with_starknet_version(
         universal_deployer_address,
     )
 }
+
+/// The state of a network a devnet is forked from, consisting of contracts and Cairo 0 classes.
+#[derive(Default)]
+pub struct TestFork {
+    pub contracts: HashMap<ContractAddress, ClassHash>,
+    pub classes: HashMap<ClassHash, Vec<u8>>,
+}
+
+impl TestFork {
+    pub fn into_context(self) -> RpcContext {
+        RpcContext::for_tests().with_fork(Arc::new(self))
+    }
+}
+
+impl ForkedState for TestFork {
+    fn block_number(&self) -> BlockNumber {
+        BlockNumber::GENESIS
+    }
+
+    fn block_hash(&self, _block: BlockNumber) -> anyhow::Result<Option<BlockHash>> {
+        Ok(None)
+    }
+
+    fn storage_value(
+        &self,
+        _contract_address: ContractAddress,
+        _key: StorageAddress,
+    ) -> anyhow::Result<Option<StorageValue>> {
+        Ok(None)
+    }
+
+    fn contract_nonce(
+        &self,
+        _contract_address: ContractAddress,
+    ) -> anyhow::Result<Option<ContractNonce>> {
+        Ok(None)
+    }
+
+    fn contract_class_hash(
+        &self,
+        contract_address: ContractAddress,
+    ) -> anyhow::Result<Option<ClassHash>> {
+        Ok(self.contracts.get(&contract_address).copied())
+    }
+
+    fn class_definition(&self, class_hash: ClassHash) -> anyhow::Result<Option<Vec<u8>>> {
+        Ok(self.classes.get(&class_hash).cloned())
+    }
+
+    fn casm_definition(&self, _class_hash: ClassHash) -> anyhow::Result<Option<Vec<u8>>> {
+        Ok(None)
+    }
+
+    fn casm_hash(&self, _class_hash: ClassHash) -> anyhow::Result<Option<CasmHash>> {
+        Ok(None)
+    }
+}
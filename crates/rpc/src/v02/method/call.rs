@@ -1,7 +1,13 @@
 use crate::context::RpcContext;
 use crate::v05::method::call::{CallInput, CallOutput};
 
-crate::error::generate_rpc_error_subset!(CallError: BlockNotFound, ContractNotFound, ContractError);
+crate::error::generate_rpc_error_subset!(
+    CallError: BlockNotFound,
+    ContractNotFound,
+    ContractError,
+    ExecutionTimeout,
+    ExecutionStepLimitExceeded
+);
 
 impl From<crate::v05::method::call::CallError> for CallError {
     fn from(value: crate::v05::method::call::CallError) -> Self {
@@ -12,6 +18,10 @@ impl From<crate::v05::method::call::CallError> for CallError {
             crate::v05::method::call::CallError::ContractErrorV05 { revert_error } => {
                 Self::Custom(anyhow::anyhow!("Transaction reverted: {}", revert_error))
             }
+            crate::v05::method::call::CallError::ExecutionTimeout => Self::ExecutionTimeout,
+            crate::v05::method::call::CallError::ExecutionStepLimitExceeded => {
+                Self::ExecutionStepLimitExceeded
+            }
             crate::v05::method::call::CallError::Custom(e) => Self::Custom(e),
         }
     }
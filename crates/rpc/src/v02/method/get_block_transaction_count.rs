@@ -45,16 +45,15 @@ pub async fn get_block_transaction_count(
             .context("Reading transaction count from database")?;
 
         // Check if the value was 0 because there were no transactions, or because the block hash is invalid.
+        // The header's count is used since the transactions of blocks preceding the sync
+        // checkpoint may not have been backfilled yet.
         if block_transaction_count == 0 {
             let header = tx
                 .block_header(block_id)
-                .context("Querying block existence")?;
+                .context("Querying block existence")?
+                .ok_or(GetBlockTransactionCountError::BlockNotFound)?;
 
-            return if header.is_some() {
-                Ok(0)
-            } else {
-                Err(GetBlockTransactionCountError::BlockNotFound)
-            };
+            return Ok(header.transaction_count as BlockTransactionCount);
         }
         Ok(block_transaction_count as BlockTransactionCount)
     });
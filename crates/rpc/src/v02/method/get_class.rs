@@ -1,9 +1,35 @@
 use crate::context::RpcContext;
+use crate::error::ApplicationError;
 use crate::v02::types::ContractClass;
 use anyhow::Context;
 use pathfinder_common::{BlockId, ClassHash};
 
-crate::error::generate_rpc_error_subset!(GetClassError: BlockNotFound, ClassHashNotFound);
+#[derive(Debug)]
+pub enum GetClassError {
+    Internal(anyhow::Error),
+    Custom(anyhow::Error),
+    BlockNotFound,
+    ClassHashNotFound(ClassHash),
+}
+
+impl From<anyhow::Error> for GetClassError {
+    fn from(e: anyhow::Error) -> Self {
+        Self::Internal(e)
+    }
+}
+
+impl From<GetClassError> for ApplicationError {
+    fn from(value: GetClassError) -> Self {
+        match value {
+            GetClassError::BlockNotFound => ApplicationError::BlockNotFound,
+            GetClassError::ClassHashNotFound(class_hash) => ApplicationError::ClassHashNotFound {
+                class_hash: Some(class_hash),
+            },
+            GetClassError::Internal(e) => ApplicationError::Internal(e),
+            GetClassError::Custom(e) => ApplicationError::Custom(e),
+        }
+    }
+}
 
 #[derive(serde::Deserialize, Debug, PartialEq, Eq)]
 #[serde(deny_unknown_fields)]
@@ -57,8 +83,17 @@ pub async fn get_class(
         }
         .context("Fetching class definition")?;
 
+        // A forked devnet only stores the classes declared since the fork, the rest are found in
+        // the network it was forked from.
+        let definition = match (definition, &context.fork) {
+            (None, Some(fork)) => fork
+                .class_definition(input.class_hash)
+                .context("Fetching class definition from fork")?,
+            (definition, _) => definition,
+        };
+
         let Some(definition) = definition else {
-            return Err(GetClassError::ClassHashNotFound);
+            return Err(GetClassError::ClassHashNotFound(input.class_hash));
         };
 
         let class = ContractClass::from_definition_bytes(&definition)
@@ -150,7 +185,7 @@ mod tests {
         .await
         .unwrap_err();
 
-        assert_matches!(error, GetClassError::ClassHashNotFound);
+        assert_matches!(error, GetClassError::ClassHashNotFound(_));
     }
 
     #[tokio::test]
@@ -191,7 +226,7 @@ mod tests {
         )
         .await
         .unwrap_err();
-        assert_matches!(error, GetClassError::ClassHashNotFound);
+        assert_matches!(error, GetClassError::ClassHashNotFound(_));
 
         // This class is defined, but is not declared in any canonical block, such
         // as what may occur for a pending class declaration.
@@ -205,7 +240,7 @@ mod tests {
         )
         .await
         .unwrap_err();
-        assert_matches!(error, GetClassError::ClassHashNotFound);
+        assert_matches!(error, GetClassError::ClassHashNotFound(_));
     }
 
     #[tokio::test]
@@ -249,7 +284,7 @@ mod tests {
         )
         .await
         .unwrap_err();
-        assert_matches!(error, GetClassError::ClassHashNotFound);
+        assert_matches!(error, GetClassError::ClassHashNotFound(_));
 
         let invalid = class_hash_bytes!(b"invalid");
         let error = super::get_class(
@@ -261,7 +296,7 @@ mod tests {
         )
         .await
         .unwrap_err();
-        assert_matches!(error, GetClassError::ClassHashNotFound);
+        assert_matches!(error, GetClassError::ClassHashNotFound(_));
 
         // This class is defined, but is not declared in any canonical block, such
         // as what may occur for a pending class declaration.
@@ -275,7 +310,7 @@ mod tests {
         )
         .await
         .unwrap_err();
-        assert_matches!(error, GetClassError::ClassHashNotFound);
+        assert_matches!(error, GetClassError::ClassHashNotFound(_));
 
         // Class exists, but block number does not.
         let valid = class_hash_bytes!(b"class 0 hash");
@@ -333,7 +368,7 @@ mod tests {
         )
         .await
         .unwrap_err();
-        assert_matches!(error, GetClassError::ClassHashNotFound);
+        assert_matches!(error, GetClassError::ClassHashNotFound(_));
 
         let invalid = class_hash_bytes!(b"invalid");
         let latest_hash = block_hash_bytes!(b"latest");
@@ -346,7 +381,7 @@ mod tests {
         )
         .await
         .unwrap_err();
-        assert_matches!(error, GetClassError::ClassHashNotFound);
+        assert_matches!(error, GetClassError::ClassHashNotFound(_));
 
         // This class is defined, but is not declared in any canonical block.
         let undeclared = class_hash_bytes!(b"class pending hash");
@@ -360,7 +395,7 @@ mod tests {
         )
         .await
         .unwrap_err();
-        assert_matches!(error, GetClassError::ClassHashNotFound);
+        assert_matches!(error, GetClassError::ClassHashNotFound(_));
 
         // Class exists, but block hash does not.
         let valid = class_hash_bytes!(b"class 0 hash");
@@ -376,4 +411,53 @@ mod tests {
         .unwrap_err();
         assert_matches!(error, GetClassError::BlockNotFound);
     }
+
+    #[tokio::test]
+    async fn forked() {
+        use starknet_gateway_test_fixtures::class_definitions::{
+            DUMMY_ACCOUNT, DUMMY_ACCOUNT_CLASS_HASH,
+        };
+
+        use crate::test_setup::TestFork;
+
+        let context = TestFork {
+            classes: [(DUMMY_ACCOUNT_CLASS_HASH, DUMMY_ACCOUNT.to_vec())].into(),
+            ..Default::default()
+        }
+        .into_context();
+
+        // Classes of the fork are found when they are not in the local database.
+        let class = super::get_class(
+            context.clone(),
+            GetClassInput {
+                block_id: BlockId::Latest,
+                class_hash: DUMMY_ACCOUNT_CLASS_HASH,
+            },
+        )
+        .await
+        .unwrap();
+        assert_matches!(class, ContractClass::Cairo(_));
+
+        // Local classes are still found.
+        super::get_class(
+            context.clone(),
+            GetClassInput {
+                block_id: BlockId::Latest,
+                class_hash: class_hash_bytes!(b"class 0 hash"),
+            },
+        )
+        .await
+        .unwrap();
+
+        let error = super::get_class(
+            context,
+            GetClassInput {
+                block_id: BlockId::Latest,
+                class_hash: class_hash_bytes!(b"invalid"),
+            },
+        )
+        .await
+        .unwrap_err();
+        assert_matches!(error, GetClassError::ClassHashNotFound(_));
+    }
 }
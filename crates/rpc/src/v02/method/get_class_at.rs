@@ -49,17 +49,32 @@ pub async fn get_class_at(
         }
 
         let class_hash = match pending_class_hash {
-            Some(class_hash) => class_hash,
+            Some(class_hash) => Some(class_hash),
             None => tx
                 .contract_class_hash(block_id, input.contract_address)
-                .context("Querying contract's class hash")?
-                .ok_or(GetClassAtError::ContractNotFound)?,
+                .context("Querying contract's class hash")?,
         };
 
+        // A forked devnet only stores the changes made since the fork, the rest of the state is
+        // found in the network it was forked from.
+        let class_hash = match (class_hash, &context.fork) {
+            (None, Some(fork)) => fork
+                .contract_class_hash(input.contract_address)
+                .context("Querying contract's class hash from fork")?,
+            (class_hash, _) => class_hash,
+        }
+        .ok_or(GetClassAtError::ContractNotFound)?;
+
         let definition = tx
             .class_definition(class_hash)
-            .context("Fetching class definition")?
-            .context("Class definition missing from database")?;
+            .context("Fetching class definition")?;
+        let definition = match (definition, &context.fork) {
+            (None, Some(fork)) => fork
+                .class_definition(class_hash)
+                .context("Fetching class definition from fork")?,
+            (definition, _) => definition,
+        }
+        .context("Class definition missing from database")?;
 
         let class = ContractClass::from_definition_bytes(&definition)
             .context("Parsing class definition")?;
@@ -332,4 +347,50 @@ mod tests {
         .unwrap_err();
         assert_matches!(error, GetClassAtError::BlockNotFound);
     }
+
+    #[tokio::test]
+    async fn forked() {
+        use starknet_gateway_test_fixtures::class_definitions::{
+            DUMMY_ACCOUNT, DUMMY_ACCOUNT_CLASS_HASH,
+        };
+
+        use crate::test_setup::TestFork;
+
+        let forked = contract_address!("0xf0");
+        // A contract of the fork whose class is stored locally.
+        let forked_local_class = contract_address!("0xf1");
+        let context = TestFork {
+            contracts: [
+                (forked, DUMMY_ACCOUNT_CLASS_HASH),
+                (forked_local_class, class_hash_bytes!(b"class 0 hash")),
+            ]
+            .into(),
+            classes: [(DUMMY_ACCOUNT_CLASS_HASH, DUMMY_ACCOUNT.to_vec())].into(),
+        }
+        .into_context();
+
+        for contract_address in [forked, forked_local_class] {
+            let class = super::get_class_at(
+                context.clone(),
+                GetClassAtInput {
+                    block_id: BlockId::Latest,
+                    contract_address,
+                },
+            )
+            .await
+            .unwrap();
+            assert_matches!(class, ContractClass::Cairo(_));
+        }
+
+        let error = super::get_class_at(
+            context,
+            GetClassAtInput {
+                block_id: BlockId::Latest,
+                contract_address: contract_address_bytes!(b"invalid"),
+            },
+        )
+        .await
+        .unwrap_err();
+        assert_matches!(error, GetClassAtError::ContractNotFound);
+    }
 }
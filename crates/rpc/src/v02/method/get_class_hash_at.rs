@@ -54,8 +54,20 @@ pub async fn get_class_hash_at(
             return Err(GetClassHashAtError::BlockNotFound);
         }
 
-        tx.contract_class_hash(block_id, input.contract_address)
-            .context("Fetching class hash from database")?
+        let class_hash = tx
+            .contract_class_hash(block_id, input.contract_address)
+            .context("Fetching class hash from database")?;
+
+        // A forked devnet only stores the changes made since the fork, the rest of the state is
+        // found in the network it was forked from.
+        let class_hash = match (class_hash, &context.fork) {
+            (None, Some(fork)) => fork
+                .contract_class_hash(input.contract_address)
+                .context("Fetching class hash from fork")?,
+            (class_hash, _) => class_hash,
+        };
+
+        class_hash
             .ok_or(GetClassHashAtError::ContractNotFound)
             .map(GetClassHashOutput)
     });
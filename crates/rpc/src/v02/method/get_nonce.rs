@@ -59,6 +59,15 @@ pub async fn get_nonce(
             .contract_nonce(contract_address, block_id)
             .context("Querying contract nonce from database")?;
 
+        // A forked devnet only stores the changes made since the fork, the rest of the state is
+        // found in the network it was forked from.
+        let nonce = match (nonce, &context.fork) {
+            (None, Some(fork)) => fork
+                .contract_nonce(contract_address)
+                .context("Querying contract nonce from fork")?,
+            (nonce, _) => nonce,
+        };
+
         if let Some(nonce) = nonce {
             return Ok(GetNonceOutput(nonce));
         };
@@ -66,7 +75,14 @@ pub async fn get_nonce(
         // Check whether contract exists or not.
         let contract_exists = tx
             .contract_exists(contract_address, block_id)
-            .context("Checking contract exists")?;
+            .context("Checking contract exists")?
+            || match &context.fork {
+                Some(fork) => fork
+                    .contract_class_hash(contract_address)
+                    .context("Querying class hash from fork")?
+                    .is_some(),
+                None => false,
+            };
 
         if contract_exists {
             Ok(GetNonceOutput(ContractNonce::ZERO))
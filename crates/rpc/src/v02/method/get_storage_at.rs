@@ -8,10 +8,19 @@ use serde::Deserialize;
 #[serde(deny_unknown_fields)]
 pub struct GetStorageAtInput {
     pub contract_address: ContractAddress,
+    #[serde(deserialize_with = "storage_key")]
     pub key: StorageAddress,
     pub block_id: BlockId,
 }
 
+/// Rejects keys which are not valid storage addresses with a dedicated error.
+fn storage_key<'de, D>(deserializer: D) -> Result<StorageAddress, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    crate::jsonrpc::InvalidInput::StorageKey.deserialize(deserializer)
+}
+
 #[serde_with::serde_as]
 #[derive(serde::Serialize, Debug)]
 pub struct GetStorageOutput(#[serde_as(as = "RpcFelt")] StorageValue);
@@ -60,10 +69,28 @@ pub async fn get_storage_at(
             .storage_value(block_id, input.contract_address, input.key)
             .context("Querying storage value")?;
 
+        // A forked devnet only stores the changes made since the fork, the rest of the state is
+        // found in the network it was forked from.
+        let value = match (value, &context.fork) {
+            (None, Some(fork)) => fork
+                .storage_value(input.contract_address, input.key)
+                .context("Querying storage value from fork")?,
+            (value, _) => value,
+        };
+
         match value {
             Some(value) => Ok(GetStorageOutput(value)),
             None => {
-                if tx.contract_exists(input.contract_address, block_id)? {
+                let contract_exists = tx.contract_exists(input.contract_address, block_id)?
+                    || match &context.fork {
+                        Some(fork) => fork
+                            .contract_class_hash(input.contract_address)
+                            .context("Querying class hash from fork")?
+                            .is_some(),
+                        None => false,
+                    };
+
+                if contract_exists {
                     Ok(GetStorageOutput(StorageValue::ZERO))
                 } else {
                     Err(GetStorageAtError::ContractNotFound)
@@ -102,6 +129,26 @@ mod tests {
         assert_eq!(input, expected);
     }
 
+    #[test]
+    fn key_exceeding_251_bits() {
+        let input = json!({
+            "contract_address": "0x1",
+            "key": "0x800000000000000000000000000000000000000000000000000000000000000",
+            "block_id": "latest"
+        });
+        let input = serde_json::value::to_raw_value(&input).unwrap();
+
+        let error = crate::jsonrpc::request::RawParams(Some(&input))
+            .deserialize::<GetStorageAtInput>()
+            .unwrap_err();
+        assert_matches!(
+            error,
+            crate::jsonrpc::RpcError::ApplicationError(
+                crate::error::ApplicationError::InvalidStorageKey
+            )
+        );
+    }
+
     #[tokio::test]
     async fn pending() {
         let ctx = RpcContext::for_tests_with_pending().await;
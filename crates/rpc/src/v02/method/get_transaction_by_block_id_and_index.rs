@@ -12,7 +12,8 @@ pub struct GetTransactionByBlockIdAndIndexInput {
 
 crate::error::generate_rpc_error_subset!(
     GetTransactionByBlockIdAndIndexError: BlockNotFound,
-    InvalidTxnIndex
+    InvalidTxnIndex,
+    BlockDataNotAvailable
 );
 
 pub async fn get_transaction_by_block_id_and_index_impl(
@@ -61,13 +62,22 @@ pub async fn get_transaction_by_block_id_and_index_impl(
             None => {
                 // We now need to check whether it was the block hash or transaction index which were invalid. We do this by checking if the block exists
                 // at all. If no, then the block hash is invalid. If yes, then the index is invalid.
-                let block_exists = db_tx
-                    .block_exists(block_id)
-                    .context("Querying block existence")?;
-                if block_exists {
-                    Err(GetTransactionByBlockIdAndIndexError::InvalidTxnIndex)
+                //
+                // Unless the block's transactions have not been backfilled yet.
+                let Some((block_number, _)) = db_tx
+                    .block_id(block_id)
+                    .context("Querying block existence")?
+                else {
+                    return Err(GetTransactionByBlockIdAndIndexError::BlockNotFound);
+                };
+
+                if db_tx
+                    .block_awaiting_backfill(block_number)
+                    .context("Checking block data availability")?
+                {
+                    Err(GetTransactionByBlockIdAndIndexError::BlockDataNotAvailable)
                 } else {
-                    Err(GetTransactionByBlockIdAndIndexError::BlockNotFound)
+                    Err(GetTransactionByBlockIdAndIndexError::InvalidTxnIndex)
                 }
             }
         }
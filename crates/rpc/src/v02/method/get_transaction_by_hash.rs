@@ -9,10 +9,15 @@ pub struct GetTransactionByHashInput {
     transaction_hash: TransactionHash,
 }
 
+crate::error::generate_rpc_error_subset!(
+    GetTransactionByHashError: TxnHashNotFound,
+    BlockDataNotAvailable
+);
+
 pub async fn get_transaction_by_hash_impl(
     context: RpcContext,
     input: GetTransactionByHashInput,
-) -> anyhow::Result<Option<Transaction>> {
+) -> Result<Transaction, GetTransactionByHashError> {
     let storage = context.storage.clone();
     let span = tracing::Span::current();
 
@@ -35,14 +40,26 @@ pub async fn get_transaction_by_hash_impl(
             .find(|tx| tx.hash == input.transaction_hash)
             .cloned()
         {
-            return Ok(Some(tx));
+            return Ok(tx);
         }
 
         // Get the transaction from storage.
-        db_tx
+        if let Some(tx) = db_tx
             .transaction(input.transaction_hash)
-            .context("Reading transaction from database")
-            .map(|x| x.map(Into::into))
+            .context("Reading transaction from database")?
+        {
+            return Ok(tx);
+        }
+
+        // The transaction may be part of a block whose transactions have not been backfilled yet.
+        if db_tx
+            .any_block_awaiting_backfill()
+            .context("Checking block data availability")?
+        {
+            Err(GetTransactionByHashError::BlockDataNotAvailable)
+        } else {
+            Err(GetTransactionByHashError::TxnHashNotFound)
+        }
     });
 
     jh.await.context("Database read panic or shutting down")?
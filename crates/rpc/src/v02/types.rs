@@ -74,6 +74,29 @@ pub mod request {
     use serde::Deserialize;
     use serde_with::serde_as;
 
+    serde_with::serde_conv!(
+        /// Rejects a `max_fee` which does not fit into the 128 bits used by transaction execution,
+        /// instead of silently truncating it later on.
+        MaxFee,
+        Fee,
+        |fee: &Fee| fee.0,
+        |felt: pathfinder_crypto::Felt| -> Result<Fee, &'static str> {
+            if felt.as_be_bytes()[..16].iter().any(|b| *b != 0) {
+                crate::jsonrpc::InvalidInput::MaxFee.report();
+                return Err("max_fee exceeds 128 bits");
+            }
+            Ok(Fee(felt))
+        }
+    );
+
+    /// Rejects calldata which is not a list of field elements with a dedicated error.
+    pub(crate) fn calldata<'de, D>(deserializer: D) -> Result<Vec<CallParam>, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        crate::jsonrpc::InvalidInput::CallData.deserialize(deserializer)
+    }
+
     /// "Broadcasted" L2 transaction in requests the RPC API.
     ///
     /// "Broadcasted" transactions represent the data required to submit a new transaction.
@@ -188,6 +211,7 @@ pub mod request {
         // BROADCASTED_TXN_COMMON_PROPERTIES: ideally this should just be included
         // here in a flattened struct, but `flatten` doesn't work with
         // `deny_unknown_fields`: https://serde.rs/attr-flatten.html#struct-flattening
+        #[serde_as(as = "MaxFee")]
         pub max_fee: Fee,
         pub version: TransactionVersion,
         pub signature: Vec<TransactionSignatureElem>,
@@ -204,6 +228,7 @@ pub mod request {
         // BROADCASTED_TXN_COMMON_PROPERTIES: ideally this should just be included
         // here in a flattened struct, but `flatten` doesn't work with
         // `deny_unknown_fields`: https://serde.rs/attr-flatten.html#struct-flattening
+        #[serde_as(as = "MaxFee")]
         pub max_fee: Fee,
         pub version: TransactionVersion,
         pub signature: Vec<TransactionSignatureElem>,
@@ -221,6 +246,7 @@ pub mod request {
         // BROADCASTED_TXN_COMMON_PROPERTIES: ideally this should just be included
         // here in a flattened struct, but `flatten` doesn't work with
         // `deny_unknown_fields`: https://serde.rs/attr-flatten.html#struct-flattening
+        #[serde_as(as = "MaxFee")]
         pub max_fee: Fee,
         pub version: TransactionVersion,
         pub signature: Vec<TransactionSignatureElem>,
@@ -270,6 +296,20 @@ pub mod request {
                 Self::V3(tx) => tx.deployed_contract_address(),
             }
         }
+
+        pub fn class_hash(&self) -> ClassHash {
+            match self {
+                Self::V0V1(tx) => tx.class_hash,
+                Self::V3(tx) => tx.class_hash,
+            }
+        }
+
+        pub fn signature(&self) -> &[TransactionSignatureElem] {
+            match self {
+                Self::V0V1(tx) => &tx.signature,
+                Self::V3(tx) => &tx.signature,
+            }
+        }
     }
 
     impl<'de> serde::Deserialize<'de> for BroadcastedDeployAccountTransaction {
@@ -308,6 +348,7 @@ pub mod request {
     pub struct BroadcastedDeployAccountTransactionV0V1 {
         // Fields from BROADCASTED_TXN_COMMON_PROPERTIES
         pub version: TransactionVersion,
+        #[serde_as(as = "MaxFee")]
         pub max_fee: Fee,
         pub signature: Vec<TransactionSignatureElem>,
         pub nonce: TransactionNonce,
@@ -426,11 +467,13 @@ pub mod request {
         // BROADCASTED_TXN_COMMON_PROPERTIES: ideally this should just be included
         // here in a flattened struct, but `flatten` doesn't work with
         // `deny_unknown_fields`: https://serde.rs/attr-flatten.html#struct-flattening
+        #[serde_as(as = "MaxFee")]
         pub max_fee: Fee,
         pub signature: Vec<TransactionSignatureElem>,
 
         pub contract_address: ContractAddress,
         pub entry_point_selector: EntryPoint,
+        #[serde(deserialize_with = "calldata")]
         pub calldata: Vec<CallParam>,
     }
 
@@ -444,11 +487,13 @@ pub mod request {
         // BROADCASTED_TXN_COMMON_PROPERTIES: ideally this should just be included
         // here in a flattened struct, but `flatten` doesn't work with
         // `deny_unknown_fields`: https://serde.rs/attr-flatten.html#struct-flattening
+        #[serde_as(as = "MaxFee")]
         pub max_fee: Fee,
         pub signature: Vec<TransactionSignatureElem>,
         pub nonce: TransactionNonce,
 
         pub sender_address: ContractAddress,
+        #[serde(deserialize_with = "calldata")]
         pub calldata: Vec<CallParam>,
     }
 
@@ -469,6 +514,7 @@ pub mod request {
         pub fee_data_availability_mode: super::DataAvailabilityMode,
 
         pub sender_address: ContractAddress,
+        #[serde(deserialize_with = "calldata")]
         pub calldata: Vec<CallParam>,
     }
 
@@ -612,6 +658,8 @@ pub mod request {
         /// - `*AsDecimalStr*` creeping in from `sequencer::reply` as opposed to spec.
         mod serde {
             use super::super::*;
+            use crate::error::ApplicationError;
+            use crate::jsonrpc::{request::RawParams, RpcError};
             use crate::v02::types::{
                 CairoContractClass, ContractEntryPoints, DataAvailabilityMode, SierraContractClass,
                 SierraEntryPoint, SierraEntryPoints,
@@ -808,6 +856,72 @@ pub mod request {
                     txs
                 );
             }
+
+            #[test]
+            fn max_fee_exceeding_128_bits() {
+                let json = |max_fee: &str| {
+                    serde_json::json!({
+                        "type": "INVOKE",
+                        "version": "0x1",
+                        "max_fee": max_fee,
+                        "signature": [],
+                        "nonce": "0x1",
+                        "sender_address": "0xaaa",
+                        "calldata": [],
+                    })
+                };
+
+                let tx = serde_json::from_value::<BroadcastedTransaction>(json(
+                    "0xffffffffffffffffffffffffffffffff",
+                ))
+                .unwrap();
+                assert_eq!(
+                    tx.into_invoke().unwrap(),
+                    BroadcastedInvokeTransaction::V1(BroadcastedInvokeTransactionV1 {
+                        version: TransactionVersion::ONE,
+                        max_fee: fee!("0xffffffffffffffffffffffffffffffff"),
+                        signature: vec![],
+                        nonce: transaction_nonce!("0x1"),
+                        sender_address: contract_address!("0xaaa"),
+                        calldata: vec![],
+                    })
+                );
+
+                let json = json("0x100000000000000000000000000000000");
+                let json = serde_json::value::to_raw_value(&json).unwrap();
+                let error = RawParams(Some(&json))
+                    .deserialize::<BroadcastedTransaction>()
+                    .unwrap_err();
+                assert_matches::assert_matches!(
+                    error,
+                    RpcError::ApplicationError(ApplicationError::InvalidMaxFee)
+                );
+            }
+
+            #[test]
+            fn calldata_exceeding_the_field() {
+                let json = serde_json::json!({
+                    "type": "INVOKE",
+                    "version": "0x1",
+                    "max_fee": "0x1",
+                    "signature": [],
+                    "nonce": "0x1",
+                    "sender_address": "0xaaa",
+                    "calldata": [
+                        "0x1",
+                        "0x800000000000011000000000000000000000000000000000000000000000001"
+                    ],
+                });
+                let json = serde_json::value::to_raw_value(&json).unwrap();
+
+                let error = RawParams(Some(&json))
+                    .deserialize::<BroadcastedTransaction>()
+                    .unwrap_err();
+                assert_matches::assert_matches!(
+                    error,
+                    RpcError::ApplicationError(ApplicationError::InvalidCallData)
+                );
+            }
         }
     }
 }
@@ -851,6 +965,7 @@ pub mod reply {
                 Rejected => BlockStatus::Rejected,
                 Reverted => BlockStatus::Rejected,
                 Aborted => BlockStatus::Rejected,
+                Unknown => BlockStatus::Rejected,
             }
         }
     }
@@ -6,7 +6,9 @@ use crate::{
 crate::error::generate_rpc_error_subset!(
     EstimateFeeError: BlockNotFound,
     ContractNotFound,
-    ContractError
+    ContractError,
+    ExecutionTimeout,
+    ExecutionStepLimitExceeded
 );
 
 impl From<crate::v05::method::estimate_fee::EstimateFeeError> for EstimateFeeError {
@@ -19,6 +21,8 @@ impl From<crate::v05::method::estimate_fee::EstimateFeeError> for EstimateFeeErr
             ContractErrorV05 { revert_error } => {
                 Self::Custom(anyhow::anyhow!("Transaction reverted: {}", revert_error))
             }
+            ExecutionTimeout => Self::ExecutionTimeout,
+            ExecutionStepLimitExceeded => Self::ExecutionStepLimitExceeded,
             Custom(e) => Self::Custom(e),
         }
     }
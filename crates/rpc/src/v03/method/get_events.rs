@@ -17,6 +17,7 @@ pub enum GetEventsError {
     PageSizeTooBig,
     InvalidContinuationToken,
     TooManyKeysInFilter { limit: usize, requested: usize },
+    BlockDataNotAvailable,
 }
 
 impl From<anyhow::Error> for GetEventsError {
@@ -36,6 +37,7 @@ impl From<GetEventsError> for crate::error::ApplicationError {
             GetEventsError::TooManyKeysInFilter { limit, requested } => {
                 Self::TooManyKeysInFilter { limit, requested }
             }
+            GetEventsError::BlockDataNotAvailable => Self::BlockDataNotAvailable,
         }
     }
 }
@@ -67,6 +69,30 @@ pub struct EventFilter {
     /// Offset, measured in events, which points to the requested chunk
     #[serde(default)]
     pub continuation_token: Option<String>,
+    /// An extension of the specification, which defaults to ascending order.
+    #[serde(default)]
+    pub order: EventOrder,
+}
+
+/// The order of the events returned by `starknet_getEvents`. Events are ordered by block number,
+/// transaction index and event index.
+#[derive(Default, Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum EventOrder {
+    #[default]
+    Ascending,
+    /// Newest first, which lets explorers page through the most recent events without scanning
+    /// the whole range.
+    Descending,
+}
+
+impl From<EventOrder> for pathfinder_storage::EventOrder {
+    fn from(order: EventOrder) -> Self {
+        match order {
+            EventOrder::Ascending => Self::Ascending,
+            EventOrder::Descending => Self::Descending,
+        }
+    }
 }
 
 /// Returns events matching the specified filter
@@ -97,21 +123,25 @@ pub async fn get_events(
 
     let request = input.filter;
 
-    let continuation_token = match &request.continuation_token {
-        Some(s) => Some(
-            s.parse::<ContinuationToken>()
-                .map_err(|_| GetEventsError::InvalidContinuationToken)?,
-        ),
-        None => None,
-    };
-
-    if request.keys.len() > pathfinder_storage::EVENT_KEY_FILTER_LIMIT {
+    let max_keys = context.config.get_events_max_keys_in_filter.get();
+    if request.keys.len() > max_keys {
         return Err(GetEventsError::TooManyKeysInFilter {
-            limit: pathfinder_storage::EVENT_KEY_FILTER_LIMIT,
+            limit: max_keys,
             requested: request.keys.len(),
         });
     }
 
+    if request.chunk_size > context.config.get_events_max_page_size.get() {
+        return Err(GetEventsError::PageSizeTooBig);
+    }
+
+    // Events are appended to the pending block, which would shift offsets counted from its end.
+    if request.order == EventOrder::Descending && request.to_block == Some(Pending) {
+        return Err(GetEventsError::Custom(anyhow::anyhow!(
+            "Descending order is not supported up to the pending block"
+        )));
+    }
+
     let storage = context.storage.clone();
 
     // truncate empty key lists from the end of the key filter
@@ -120,6 +150,28 @@ pub async fn get_events(
         keys.truncate(last_non_empty + 1);
     }
 
+    let filter_hash = filter_hash(
+        request.from_block,
+        request.to_block,
+        request.order,
+        request.address,
+        &keys,
+    );
+
+    let continuation_token = match &request.continuation_token {
+        Some(s) => {
+            let token = s
+                .parse::<ContinuationToken>()
+                .map_err(|_| GetEventsError::InvalidContinuationToken)?;
+            // Tokens without a filter hash were issued before it was introduced.
+            if token.filter_hash.is_some_and(|hash| hash != filter_hash) {
+                return Err(GetEventsError::InvalidContinuationToken);
+            }
+            Some(token)
+        }
+        None => None,
+    };
+
     // blocking task to perform database event query
     let span = tracing::Span::current();
     let db_events: JoinHandle<Result<_, GetEventsError>> = tokio::task::spawn_blocking(move || {
@@ -145,7 +197,7 @@ pub async fn get_events(
                     .pending_data
                     .get(&transaction)
                     .context("Querying pending data")?;
-                return get_pending_events(&request, &pending, continuation_token);
+                return get_pending_events(&request, &pending, continuation_token, filter_hash);
             }
             _ => {}
         }
@@ -153,11 +205,41 @@ pub async fn get_events(
         let from_block = map_from_block_to_number(&transaction, request.from_block)?;
         let to_block = map_to_block_to_number(&transaction, request.to_block)?;
 
-        let (from_block, requested_offset) = match continuation_token {
-            Some(token) => token.start_block_and_offset(from_block)?,
-            None => (from_block, 0),
+        // The token replaces the block the scan starts from, which is `to_block` in descending
+        // order.
+        let (from_block, to_block, requested_offset) = match (continuation_token, request.order) {
+            (Some(token), EventOrder::Ascending) => {
+                // The latest block may have advanced since the token was issued, so only block
+                // numbers and hashes are checked against the token.
+                let from_block = match request.from_block {
+                    Some(Number(_) | Hash(_)) => from_block,
+                    _ => None,
+                };
+                let (from_block, offset) = token.start_block_and_offset(from_block)?;
+                (from_block, to_block, offset)
+            }
+            (Some(token), EventOrder::Descending) => {
+                let (to_block, offset) = token.end_block_and_offset(to_block)?;
+                (from_block, to_block, offset)
+            }
+            (None, _) => (from_block, to_block, 0),
         };
 
+        // Blocks whose events have not been backfilled yet would otherwise look empty.
+        let latest = transaction
+            .block_id(pathfinder_storage::BlockId::Latest)
+            .context("Querying latest block")?;
+        if let Some(last) = to_block.or(latest.map(|(number, _)| number)) {
+            let first = from_block.unwrap_or(BlockNumber::GENESIS);
+            if transaction
+                .first_block_awaiting_backfill(first, last)
+                .context("Checking block data availability")?
+                .is_some()
+            {
+                return Err(GetEventsError::BlockDataNotAvailable);
+            }
+        }
+
         let filter = pathfinder_storage::EventFilter {
             from_block,
             to_block,
@@ -165,8 +247,21 @@ pub async fn get_events(
             keys: keys.clone(),
             page_size: request.chunk_size,
             offset: requested_offset,
+            order: request.order.into(),
         };
 
+        if let Some(max_cost) = context.config.get_events_max_query_cost {
+            let cost = transaction
+                .event_query_cost(&filter)
+                .context("Estimating event query cost")?;
+            if cost > max_cost.get() {
+                tracing::debug!(%cost, "Rejecting event query above the cost budget");
+                return Err(GetEventsError::Custom(
+                    EventFilterError::TooManyMatches.into(),
+                ));
+            }
+        }
+
         let page = transaction
             .events(
                 &filter,
@@ -174,7 +269,6 @@ pub async fn get_events(
                 context.config.get_events_max_uncached_bloom_filters_to_load,
             )
             .map_err(|e| match e {
-                EventFilterError::PageSizeTooBig(_) => GetEventsError::PageSizeTooBig,
                 EventFilterError::TooManyMatches => GetEventsError::Custom(e.into()),
                 EventFilterError::Internal(e) => GetEventsError::Internal(e),
                 EventFilterError::PageSizeTooSmall => GetEventsError::Custom(e.into()),
@@ -186,6 +280,7 @@ pub async fn get_events(
                 ContinuationToken {
                     block_number: token.block_number,
                     offset: token.offset,
+                    filter_hash: Some(filter_hash),
                 }
                 .to_string()
             }),
@@ -201,7 +296,14 @@ pub async fn get_events(
             if events.events.len() < request.chunk_size {
                 let amount = request.chunk_size - events.events.len();
 
+                // A token pointing to an earlier block was continued from the database, whose
+                // events have all been returned.
                 let current_offset = match continuation_token {
+                    Some(continuation_token)
+                        if continuation_token.block_number < pending.number =>
+                    {
+                        0
+                    }
                     Some(continuation_token) => {
                         continuation_token.offset_in_block(pending.number)?
                     }
@@ -229,6 +331,7 @@ pub async fn get_events(
                     let continuation_token = ContinuationToken {
                         block_number: pending.number,
                         offset: current_offset + amount,
+                        filter_hash: Some(filter_hash),
                     };
                     Some(continuation_token.to_string())
                 };
@@ -239,6 +342,7 @@ pub async fn get_events(
                     ContinuationToken {
                         block_number: pending.number,
                         offset: 0,
+                        filter_hash: Some(filter_hash),
                     }
                     .to_string(),
                 );
@@ -258,6 +362,7 @@ fn get_pending_events(
     request: &EventFilter,
     pending: &PendingData,
     continuation_token: Option<ContinuationToken>,
+    filter_hash: u64,
 ) -> Result<types::GetEventsResult, GetEventsError> {
     let current_offset = match continuation_token {
         Some(continuation_token) => continuation_token.offset_in_block(pending.number)?,
@@ -288,6 +393,7 @@ fn get_pending_events(
             ContinuationToken {
                 block_number: pending.number,
                 offset: current_offset + request.chunk_size,
+                filter_hash: Some(filter_hash),
             }
             .to_string(),
         )
@@ -420,37 +526,123 @@ fn append_pending_events(
     is_last_page
 }
 
+/// Hashes the parts of the filter which determine the events matched by it, so that a
+/// continuation token can only be used with the filter it was issued for.
+///
+/// The hash only depends on the filter, so that tokens remain valid across restarts and between
+/// nodes.
+///
+/// In descending order, the token replaces `to_block` rather than `from_block`, so it is hashed
+/// as well. Nothing is added in ascending order, which keeps earlier tokens valid.
+fn filter_hash(
+    from_block: Option<BlockId>,
+    to_block: Option<BlockId>,
+    order: EventOrder,
+    address: Option<ContractAddress>,
+    keys: &[Vec<EventKey>],
+) -> u64 {
+    use pathfinder_crypto::hash::PoseidonHasher;
+    use pathfinder_crypto::MontFelt;
+
+    fn write_block_id(hasher: &mut PoseidonHasher, block: Option<BlockId>) {
+        match block {
+            None => hasher.write(MontFelt::ZERO),
+            Some(BlockId::Number(number)) => {
+                hasher.write(1u64.into());
+                hasher.write(number.get().into());
+            }
+            Some(BlockId::Hash(hash)) => {
+                hasher.write(2u64.into());
+                hasher.write(hash.0.into());
+            }
+            Some(BlockId::Latest) => hasher.write(3u64.into()),
+            Some(BlockId::Pending) => hasher.write(4u64.into()),
+        }
+    }
+
+    let mut hasher = PoseidonHasher::new();
+
+    write_block_id(&mut hasher, from_block);
+
+    match address {
+        Some(address) => {
+            hasher.write(MontFelt::ONE);
+            hasher.write(address.0.into());
+        }
+        None => hasher.write(MontFelt::ZERO),
+    }
+
+    hasher.write((keys.len() as u64).into());
+    for keys in keys {
+        hasher.write((keys.len() as u64).into());
+        for key in keys {
+            hasher.write(key.0.into());
+        }
+    }
+
+    if order == EventOrder::Descending {
+        hasher.write(MontFelt::ONE);
+        write_block_id(&mut hasher, to_block);
+    }
+
+    let hash = pathfinder_crypto::Felt::from(hasher.finish()).to_be_bytes();
+    u64::from_be_bytes(hash[24..].try_into().expect("8 bytes"))
+}
+
+/// Points to the next event of a `starknet_getEvents` query, as its offset among the matching
+/// events of a block.
+///
+/// Blocks do not change once accepted, and pending blocks only ever have events appended, so the
+/// token stays valid across restarts and between nodes as long as it is used with the same filter.
 #[derive(Clone, Copy, Debug, PartialEq)]
 struct ContinuationToken {
     block_number: BlockNumber,
     offset: usize,
+    /// [None] for tokens issued before the filter hash was introduced.
+    filter_hash: Option<u64>,
 }
 
 impl FromStr for ContinuationToken {
     type Err = ParseContinuationTokenError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if let Some((block_number, offset)) = s.split_once('-') {
-            let block_number = block_number
-                .parse::<u64>()
-                .map_err(|_| ParseContinuationTokenError)?;
-            let offset = offset.parse().map_err(|_| ParseContinuationTokenError)?;
-
-            let block_number = BlockNumber::new(block_number).ok_or(ParseContinuationTokenError)?;
-
-            Ok(ContinuationToken {
-                block_number,
-                offset,
-            })
-        } else {
-            Err(ParseContinuationTokenError)
+        let mut parts = s.split('-');
+        let (Some(block_number), Some(offset)) = (parts.next(), parts.next()) else {
+            return Err(ParseContinuationTokenError);
+        };
+        let filter_hash = parts.next();
+        if parts.next().is_some() {
+            return Err(ParseContinuationTokenError);
         }
+
+        let block_number = block_number
+            .parse::<u64>()
+            .map_err(|_| ParseContinuationTokenError)?;
+        let block_number = BlockNumber::new(block_number).ok_or(ParseContinuationTokenError)?;
+        let offset = offset.parse().map_err(|_| ParseContinuationTokenError)?;
+        let filter_hash = match filter_hash {
+            Some(hash) if hash.len() == 16 => {
+                Some(u64::from_str_radix(hash, 16).map_err(|_| ParseContinuationTokenError)?)
+            }
+            Some(_) => return Err(ParseContinuationTokenError),
+            None => None,
+        };
+
+        Ok(ContinuationToken {
+            block_number,
+            offset,
+            filter_hash,
+        })
     }
 }
 
 impl std::fmt::Display for ContinuationToken {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}-{}", self.block_number.get(), self.offset)
+        write!(f, "{}-{}", self.block_number.get(), self.offset)?;
+        if let Some(filter_hash) = self.filter_hash {
+            write!(f, "-{filter_hash:016x}")?;
+        }
+        Ok(())
     }
 }
 
@@ -481,6 +673,20 @@ impl ContinuationToken {
             }
         }
     }
+
+    /// Like [ContinuationToken::start_block_and_offset], for descending order where the token
+    /// replaces the end of the range.
+    fn end_block_and_offset(
+        &self,
+        to_block: Option<BlockNumber>,
+    ) -> Result<(Option<BlockNumber>, usize), GetEventsError> {
+        match to_block {
+            Some(to_block) if to_block < self.block_number => {
+                Err(GetEventsError::InvalidContinuationToken)
+            }
+            _ => Ok((Some(self.block_number), self.offset)),
+        }
+    }
 }
 
 #[derive(Debug, Eq, PartialEq)]
@@ -537,6 +743,7 @@ mod tests {
         *,
     };
     use serde_json::json;
+    use std::num::{NonZeroU64, NonZeroUsize};
 
     use pathfinder_common::macro_prelude::*;
     use pathfinder_storage::test_utils;
@@ -569,6 +776,7 @@ mod tests {
                 keys: vec![vec![event_key!("0x2")], vec![]],
                 chunk_size: 3,
                 continuation_token: Some("4".to_string()),
+                order: EventOrder::Ascending,
             }
         } else {
             EventFilter {
@@ -582,6 +790,14 @@ mod tests {
         assert_eq!(input, expected);
     }
 
+    #[test]
+    fn parsing_order() {
+        let input = json!({"filter":{"chunk_size":5,"order":"descending"}});
+
+        let input = serde_json::from_value::<GetEventsInput>(input).unwrap();
+        assert_eq!(input.filter.order, EventOrder::Descending);
+    }
+
     #[test]
     fn continuation_token() {
         use assert_matches::assert_matches;
@@ -603,13 +819,84 @@ mod tests {
             Err(ParseContinuationTokenError)
         );
 
+        assert_matches!(
+            "1234-5678-00000000000000zz".parse::<ContinuationToken>(),
+            Err(ParseContinuationTokenError)
+        );
+        assert_matches!(
+            "1234-5678-0123456789abcdef-1".parse::<ContinuationToken>(),
+            Err(ParseContinuationTokenError)
+        );
+
         assert_eq!(
             "1234-4567".parse::<ContinuationToken>().unwrap(),
             ContinuationToken {
                 block_number: BlockNumber::new_or_panic(1234),
-                offset: 4567
+                offset: 4567,
+                filter_hash: None,
             }
         );
+
+        let token = ContinuationToken {
+            block_number: BlockNumber::new_or_panic(1234),
+            offset: 4567,
+            filter_hash: Some(0xabcdef),
+        };
+        assert_eq!(token.to_string(), "1234-4567-0000000000abcdef");
+        assert_eq!(token.to_string().parse::<ContinuationToken>(), Ok(token));
+    }
+
+    #[test]
+    fn filter_hash_depends_on_filter() {
+        use EventOrder::*;
+
+        let keys = vec![vec![event_key!("0x1")], vec![event_key!("0x2")]];
+        let hash = filter_hash(None, None, Ascending, None, &keys);
+
+        assert_eq!(hash, filter_hash(None, None, Ascending, None, &keys));
+        assert_ne!(
+            hash,
+            filter_hash(Some(BlockId::Latest), None, Ascending, None, &keys)
+        );
+        assert_ne!(
+            hash,
+            filter_hash(None, None, Ascending, Some(contract_address!("0x1")), &keys)
+        );
+        assert_ne!(hash, filter_hash(None, None, Ascending, None, &keys[..1]));
+        // Keys are hashed position by position.
+        assert_ne!(
+            hash,
+            filter_hash(
+                None,
+                None,
+                Ascending,
+                None,
+                &[vec![event_key!("0x1"), event_key!("0x2")]]
+            )
+        );
+        // The end of the range is only hashed in descending order.
+        assert_eq!(
+            hash,
+            filter_hash(None, Some(BlockId::Latest), Ascending, None, &keys)
+        );
+        let descending = filter_hash(None, None, Descending, None, &keys);
+        assert_ne!(hash, descending);
+        assert_ne!(
+            descending,
+            filter_hash(None, Some(BlockId::Latest), Descending, None, &keys)
+        );
+    }
+
+    /// Appends the hash of the filter to a token in the `<block>-<offset>` form.
+    fn token(filter: &EventFilter, token: &str) -> String {
+        let filter_hash = filter_hash(
+            filter.from_block,
+            filter.to_block,
+            filter.order,
+            filter.address,
+            &filter.keys,
+        );
+        format!("{token}-{filter_hash:016x}")
     }
 
     fn setup() -> (RpcContext, Vec<EmittedEvent>) {
@@ -672,6 +959,7 @@ mod tests {
                 keys: vec![vec![], vec![event_key!("0xdeadbeef")]],
                 chunk_size: test_utils::NUM_EVENTS,
                 continuation_token: None,
+                order: EventOrder::Ascending,
             },
         };
         let result = get_events(context.clone(), input.clone()).await.unwrap();
@@ -747,6 +1035,89 @@ mod tests {
         assert_eq!(GetEventsError::PageSizeTooBig, error);
     }
 
+    #[tokio::test]
+    async fn get_events_with_configured_limits() {
+        let (mut context, events) = setup();
+        context.config.get_events_max_page_size = NonZeroUsize::new(2).unwrap();
+        context.config.get_events_max_keys_in_filter = NonZeroUsize::new(1).unwrap();
+
+        let input = GetEventsInput {
+            filter: EventFilter {
+                chunk_size: 3,
+                ..Default::default()
+            },
+        };
+        let error = get_events(context.clone(), input).await.unwrap_err();
+        assert_eq!(GetEventsError::PageSizeTooBig, error);
+
+        let input = GetEventsInput {
+            filter: EventFilter {
+                keys: vec![vec![], vec![]],
+                chunk_size: 2,
+                ..Default::default()
+            },
+        };
+        let error = get_events(context.clone(), input).await.unwrap_err();
+        assert_eq!(
+            GetEventsError::TooManyKeysInFilter {
+                limit: 1,
+                requested: 2
+            },
+            error
+        );
+
+        let input = GetEventsInput {
+            filter: EventFilter {
+                chunk_size: 2,
+                ..Default::default()
+            },
+        };
+        let result = get_events(context, input).await.unwrap();
+        assert_eq!(result.events, events[..2].to_vec());
+    }
+
+    #[tokio::test]
+    async fn get_events_above_query_cost_budget() {
+        let (mut context, events) = setup();
+        context.config.get_events_max_query_cost = Some(NonZeroU64::new(2).unwrap());
+
+        let address = events[0].from_address;
+        let input = GetEventsInput {
+            filter: EventFilter {
+                address: Some(address),
+                chunk_size: test_utils::NUM_EVENTS,
+                ..Default::default()
+            },
+        };
+        let error = get_events(context.clone(), input).await.unwrap_err();
+        assert_eq!(
+            GetEventsError::Custom(anyhow::anyhow!("Event query too broad")),
+            error
+        );
+
+        // Narrowing the block range brings the query within the budget.
+        let input = GetEventsInput {
+            filter: EventFilter {
+                from_block: Some(BlockId::Number(BlockNumber::new_or_panic(1))),
+                to_block: Some(BlockId::Number(BlockNumber::new_or_panic(2))),
+                address: Some(address),
+                chunk_size: test_utils::NUM_EVENTS,
+                ..Default::default()
+            },
+        };
+        get_events(context.clone(), input).await.unwrap();
+
+        // Filters without an address or keys are not limited.
+        let input = GetEventsInput {
+            filter: EventFilter {
+                chunk_size: test_utils::NUM_EVENTS,
+                ..Default::default()
+            },
+        };
+        let result = get_events(context, input).await.unwrap();
+        assert_eq!(result.events, events);
+    }
+
     #[tokio::test]
     async fn get_events_with_too_many_keys_in_filter() {
         let (context, _) = setup();
@@ -793,12 +1164,12 @@ mod tests {
                 ..Default::default()
             },
         };
-        let result = get_events(context.clone(), input).await.unwrap();
+        let result = get_events(context.clone(), input.clone()).await.unwrap();
         assert_eq!(
             result,
             GetEventsResult {
                 events: expected_events[..1].to_vec(),
-                continuation_token: Some("0-1".to_string()),
+                continuation_token: Some(token(&input.filter, "0-1")),
             }
         );
 
@@ -810,12 +1181,12 @@ mod tests {
                 ..Default::default()
             },
         };
-        let result = get_events(context.clone(), input).await.unwrap();
+        let result = get_events(context.clone(), input.clone()).await.unwrap();
         assert_eq!(
             result,
             GetEventsResult {
                 events: expected_events[1..3].to_vec(),
-                continuation_token: Some("3-0".to_string()),
+                continuation_token: Some(token(&input.filter, "3-0")),
             }
         );
 
@@ -823,7 +1194,7 @@ mod tests {
             filter: EventFilter {
                 keys: keys_for_expected_events.clone(),
                 chunk_size: 3,
-                continuation_token: Some("3-0".to_string()),
+                continuation_token: result.continuation_token,
                 ..Default::default()
             },
         };
@@ -851,6 +1222,107 @@ mod tests {
         assert_eq!(result.continuation_token, None);
     }
 
+    #[tokio::test]
+    async fn get_events_in_descending_order() {
+        let (context, events) = setup();
+
+        let mut input = GetEventsInput {
+            filter: EventFilter {
+                to_block: Some(BlockId::Latest),
+                chunk_size: 7,
+                order: EventOrder::Descending,
+                ..Default::default()
+            },
+        };
+
+        let mut received = Vec::new();
+        loop {
+            let result = get_events(context.clone(), input.clone()).await.unwrap();
+            received.extend(result.events);
+
+            match result.continuation_token {
+                Some(token) => input.filter.continuation_token = Some(token),
+                None => break,
+            }
+        }
+
+        let expected = events.into_iter().rev().collect::<Vec<_>>();
+        assert_eq!(received, expected);
+
+        // Tokens issued in ascending order cannot be used.
+        input.filter.continuation_token = Some(token(
+            &EventFilter {
+                order: EventOrder::Ascending,
+                ..input.filter.clone()
+            },
+            "1-0",
+        ));
+        let error = get_events(context, input).await.unwrap_err();
+        assert_eq!(error, GetEventsError::InvalidContinuationToken);
+    }
+
+    #[tokio::test]
+    async fn descending_order_up_to_pending_block() {
+        let (context, _) = setup();
+
+        let input = GetEventsInput {
+            filter: EventFilter {
+                to_block: Some(BlockId::Pending),
+                chunk_size: 1,
+                order: EventOrder::Descending,
+                ..Default::default()
+            },
+        };
+        let error = get_events(context, input).await.unwrap_err();
+
+        assert_matches::assert_matches!(error, GetEventsError::Custom(_));
+    }
+
+    #[tokio::test]
+    async fn continuation_token_for_other_filter() {
+        let (context, _) = setup();
+
+        let mut input = GetEventsInput {
+            filter: EventFilter {
+                // we're using a key which is present in _all_ events
+                keys: vec![vec![], vec![event_key!("0xdeadbeef")]],
+                chunk_size: 1,
+                ..Default::default()
+            },
+        };
+        let result = get_events(context.clone(), input.clone()).await.unwrap();
+        assert!(result.continuation_token.is_some());
+
+        input.filter.keys = vec![];
+        input.filter.continuation_token = result.continuation_token;
+        let error = get_events(context, input).await.unwrap_err();
+
+        assert_eq!(error, GetEventsError::InvalidContinuationToken);
+    }
+
+    #[tokio::test]
+    async fn continuation_token_from_before_latest_block() {
+        let (context, events) = setup();
+
+        // Another node, or this node before new blocks were added, issued a token for block 1
+        // while it was the latest block.
+        let mut input = GetEventsInput {
+            filter: EventFilter {
+                from_block: Some(BlockId::Latest),
+                chunk_size: test_utils::EVENTS_PER_BLOCK,
+                ..Default::default()
+            },
+        };
+        input.filter.continuation_token = Some(token(&input.filter, "1-0"));
+
+        let result = get_events(context, input).await.unwrap();
+
+        assert_eq!(
+            result.events,
+            &events[test_utils::EVENTS_PER_BLOCK..test_utils::EVENTS_PER_BLOCK * 2]
+        );
+    }
+
     mod pending {
         use super::*;
         use pretty_assertions_sorted::assert_eq;
@@ -927,7 +1399,7 @@ mod tests {
             input.filter.continuation_token = None;
             let result = get_events(context.clone(), input.clone()).await.unwrap();
             assert_eq!(result.events, &all[0..1]);
-            assert_eq!(result.continuation_token, Some("3-0".to_string()));
+            assert_eq!(result.continuation_token, Some(token(&input.filter, "3-0")));
 
             // Page includes a DB event and an event from the pending block, but there are more pending
             // events for the next page
@@ -935,13 +1407,13 @@ mod tests {
             input.filter.continuation_token = None;
             let result = get_events(context.clone(), input.clone()).await.unwrap();
             assert_eq!(result.events, &all[0..2]);
-            assert_eq!(result.continuation_token, Some("3-1".to_string()));
+            assert_eq!(result.continuation_token, Some(token(&input.filter, "3-1")));
 
             input.filter.chunk_size = 1;
             input.filter.continuation_token = result.continuation_token;
             let result = get_events(context.clone(), input.clone()).await.unwrap();
             assert_eq!(result.events, &all[2..3]);
-            assert_eq!(result.continuation_token, Some("3-2".to_string()));
+            assert_eq!(result.continuation_token, Some(token(&input.filter, "3-2")));
 
             input.filter.chunk_size = 100; // Only a single event remains though
             input.filter.continuation_token = result.continuation_token;
@@ -978,6 +1450,7 @@ mod tests {
                     ]],
                     chunk_size: 1024,
                     continuation_token: None,
+                    order: EventOrder::Ascending,
                 },
             };
 
@@ -991,7 +1464,31 @@ mod tests {
             input.filter.chunk_size = 1;
             let result = get_events(context.clone(), input.clone()).await.unwrap();
             assert_eq!(result.events, &all[0..1]);
-            assert_eq!(result.continuation_token, Some("3-0".to_string()));
+            assert_eq!(result.continuation_token, Some(token(&input.filter, "3-0")));
+        }
+
+        #[tokio::test]
+        async fn continuing_from_database_into_pending() {
+            let context = RpcContext::for_tests_with_pending().await;
+
+            let mut input = GetEventsInput {
+                filter: EventFilter {
+                    to_block: Some(BlockId::Pending),
+                    chunk_size: 1024,
+                    ..Default::default()
+                },
+            };
+
+            let all = get_events(context.clone(), input.clone())
+                .await
+                .unwrap()
+                .events;
+
+            input.filter.continuation_token = Some(token(&input.filter, "0-0"));
+            let result = get_events(context, input).await.unwrap();
+
+            assert_eq!(result.events, all);
+            assert_eq!(result.continuation_token, None);
         }
 
         #[tokio::test]
@@ -1006,6 +1503,7 @@ mod tests {
                     keys: vec![],
                     chunk_size: 1024,
                     continuation_token: None,
+                    order: EventOrder::Ascending,
                 },
             };
 
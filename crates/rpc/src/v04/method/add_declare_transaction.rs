@@ -1,5 +1,6 @@
 use crate::context::RpcContext;
 use crate::felt::RpcFelt;
+use crate::mempool::TransactionKind;
 use crate::v02::types::request::BroadcastedDeclareTransaction;
 use pathfinder_common::{ClassHash, TransactionHash};
 use starknet_gateway_client::GatewayApi;
@@ -145,6 +146,18 @@ pub async fn add_declare_transaction(
 ) -> Result<AddDeclareTransactionOutput, AddDeclareTransactionError> {
     use starknet_gateway_types::request::add_transaction;
 
+    if let Some(sequencer) = &context.local_sequencer {
+        let Transaction::Declare(tx) = input.declare_transaction;
+        let response = sequencer
+            .add_declare_transaction(tx, context.chain_id, &context.mempool)
+            .await?;
+
+        return Ok(AddDeclareTransactionOutput {
+            transaction_hash: response.transaction_hash,
+            class_hash: response.class_hash,
+        });
+    }
+
     match input.declare_transaction {
         Transaction::Declare(BroadcastedDeclareTransaction::V0(_)) => {
             Err(AddDeclareTransactionError::UnsupportedTransactionVersion)
@@ -171,6 +184,10 @@ pub async fn add_declare_transaction(
                 )
                 .await?;
 
+            context
+                .mempool
+                .insert(response.transaction_hash, TransactionKind::Declare);
+
             Ok(AddDeclareTransactionOutput {
                 transaction_hash: response.transaction_hash,
                 class_hash: response.class_hash,
@@ -198,6 +215,10 @@ pub async fn add_declare_transaction(
                 )
                 .await?;
 
+            context
+                .mempool
+                .insert(response.transaction_hash, TransactionKind::Declare);
+
             Ok(AddDeclareTransactionOutput {
                 transaction_hash: response.transaction_hash,
                 class_hash: response.class_hash,
@@ -240,6 +261,10 @@ pub async fn add_declare_transaction(
                 )
                 .await?;
 
+            context
+                .mempool
+                .insert(response.transaction_hash, TransactionKind::Declare);
+
             Ok(AddDeclareTransactionOutput {
                 transaction_hash: response.transaction_hash,
                 class_hash: response.class_hash,
@@ -44,7 +44,8 @@ impl From<AddDeployAccountTransactionError> for crate::error::ApplicationError {
     fn from(value: AddDeployAccountTransactionError) -> Self {
         use AddDeployAccountTransactionError::*;
         match value {
-            ClassHashNotFound => Self::ClassHashNotFound,
+            // The gateway only reports the class hash in its error message.
+            ClassHashNotFound => Self::ClassHashNotFound { class_hash: None },
             InvalidTransactionNonce => Self::InvalidTransactionNonce,
             InsufficientMaxFee => Self::InsufficientMaxFee,
             InsufficientAccountBalance => Self::InsufficientAccountBalance,
@@ -118,6 +119,7 @@ mod tests {
     use crate::v02::types::{DataAvailabilityMode, ResourceBound, ResourceBounds};
 
     use super::*;
+    use crate::v06::method::add_deploy_account_transaction::declare_class;
     use pathfinder_common::{macro_prelude::*, ResourceAmount, ResourcePricePerUnit, Tip};
     use pathfinder_common::{TransactionNonce, TransactionVersion};
 
@@ -217,8 +219,9 @@ mod tests {
     #[ignore = "gateway 429"]
     async fn duplicate_transaction() {
         let context = RpcContext::for_tests();
-
         let input = get_input();
+        let Transaction::DeployAccount(tx) = &input.deploy_account_transaction;
+        declare_class(&context, tx.class_hash());
 
         let error = add_deploy_account_transaction(context, input)
             .await
@@ -269,6 +272,7 @@ mod tests {
             ),
         };
 
+        declare_class(&context, input.class_hash);
         let input = AddDeployAccountTransactionInput {
             deploy_account_transaction: Transaction::DeployAccount(
                 BroadcastedDeployAccountTransaction::V3(input),
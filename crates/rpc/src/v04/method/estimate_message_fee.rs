@@ -4,7 +4,9 @@ use crate::v06::method::estimate_message_fee::EstimateMessageFeeInput;
 crate::error::generate_rpc_error_subset!(
     EstimateMessageFeeError: BlockNotFound,
     ContractNotFound,
-    ContractError
+    ContractError,
+    ExecutionTimeout,
+    ExecutionStepLimitExceeded
 );
 
 impl From<crate::v06::method::estimate_message_fee::EstimateMessageFeeError>
@@ -20,6 +22,8 @@ impl From<crate::v06::method::estimate_message_fee::EstimateMessageFeeError>
             ContractErrorV05 { revert_error } => {
                 Self::Internal(anyhow::anyhow!("Transaction reverted: {}", revert_error))
             }
+            ExecutionTimeout => Self::ExecutionTimeout,
+            ExecutionStepLimitExceeded => Self::ExecutionStepLimitExceeded,
             Custom(error) => Self::Custom(error),
         }
     }
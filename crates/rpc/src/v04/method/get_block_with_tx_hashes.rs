@@ -12,7 +12,7 @@ pub struct GetBlockInput {
     block_id: BlockId,
 }
 
-crate::error::generate_rpc_error_subset!(GetBlockError: BlockNotFound);
+crate::error::generate_rpc_error_subset!(GetBlockError: BlockNotFound, BlockDataNotAvailable);
 
 /// Get block information with transaction hashes given the block id
 pub async fn get_block_with_tx_hashes(
@@ -57,6 +57,13 @@ pub async fn get_block_with_tx_hashes(
             .context("Reading block from database")?
             .ok_or(GetBlockError::BlockNotFound)?;
 
+        if transaction
+            .block_awaiting_backfill(header.number)
+            .context("Checking block data availability")?
+        {
+            return Err(GetBlockError::BlockDataNotAvailable);
+        }
+
         let l1_accepted = transaction.block_is_l1_accepted(header.number.into())?;
         let block_status = if l1_accepted {
             BlockStatus::AcceptedOnL1
@@ -1,5 +1,7 @@
 use crate::{
-    context::RpcContext, executor::ExecutionStateError, v02::types::request::BroadcastedTransaction,
+    context::RpcContext,
+    executor::{with_timeout, ExecutionMethod, ExecutionStateError, ExecutionTimeout},
+    v02::types::request::BroadcastedTransaction,
 };
 
 use anyhow::Context;
@@ -23,7 +25,9 @@ pub struct SimulateTransactionOutput(pub Vec<dto::SimulatedTransaction>);
 crate::error::generate_rpc_error_subset!(
     SimulateTransactionError: BlockNotFound,
     ContractNotFound,
-    ContractError
+    ContractError,
+    ExecutionTimeout,
+    ExecutionStepLimitExceeded
 );
 
 impl From<TransactionExecutionError> for SimulateTransactionError {
@@ -38,6 +42,7 @@ impl From<TransactionExecutionError> for SimulateTransactionError {
                 transaction_index,
                 error
             )),
+            StepLimitExceeded { .. } => Self::ExecutionStepLimitExceeded,
             Internal(e) => Self::Internal(e),
             Custom(e) => Self::Custom(e),
         }
@@ -53,14 +58,19 @@ impl From<ExecutionStateError> for SimulateTransactionError {
     }
 }
 
+impl From<ExecutionTimeout> for SimulateTransactionError {
+    fn from(_: ExecutionTimeout) -> Self {
+        Self::ExecutionTimeout
+    }
+}
+
 pub async fn simulate_transactions(
     context: RpcContext,
     input: SimulateTransactionInput,
 ) -> Result<SimulateTransactionOutput, SimulateTransactionError> {
-    let span = tracing::Span::current();
-    tokio::task::spawn_blocking(move || {
-        let _g = span.enter();
-
+    let timeout = context.config.execution_timeout;
+    let pool = context.execution_pool.clone();
+    let execution = pool.execute(ExecutionMethod::Simulate, move || {
         let skip_validate = input
             .simulation_flags
             .0
@@ -106,7 +116,9 @@ pub async fn simulate_transactions(
             header,
             pending,
             pathfinder_executor::L1BlobDataAvailability::Disabled,
-        );
+        )
+        .with_max_steps(context.config.execution_max_steps)
+        .with_fork(context.fork.clone());
 
         let transactions = input
             .transactions
@@ -130,9 +142,9 @@ pub async fn simulate_transactions(
                 Ok(SimulateTransactionOutput(txs))
             }
         }
-    })
-    .await
-    .context("Simulating transaction")?
+    });
+
+    with_timeout(timeout, execution).await
 }
 
 pub mod dto {
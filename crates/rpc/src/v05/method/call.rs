@@ -1,9 +1,10 @@
 use crate::context::RpcContext;
 use crate::error::ApplicationError;
+use crate::executor::{with_timeout, ExecutionMethod};
 use crate::felt::RpcFelt;
 use anyhow::Context;
 use pathfinder_common::{BlockId, CallParam, CallResultValue, ContractAddress, EntryPoint};
-use pathfinder_executor::{ExecutionState, L1BlobDataAvailability};
+use pathfinder_executor::{CallCacheKey, ExecutionState, L1BlobDataAvailability};
 
 #[derive(Debug)]
 pub enum CallError {
@@ -12,6 +13,8 @@ pub enum CallError {
     BlockNotFound,
     ContractNotFound,
     ContractErrorV05 { revert_error: String },
+    ExecutionTimeout,
+    ExecutionStepLimitExceeded,
 }
 
 impl From<anyhow::Error> for CallError {
@@ -29,6 +32,7 @@ impl From<pathfinder_executor::CallError> for CallError {
             ContractError(error) => Self::ContractErrorV05 {
                 revert_error: format!("Execution error: {}", error),
             },
+            StepLimitExceeded => Self::ExecutionStepLimitExceeded,
             Internal(e) => Self::Internal(e),
             Custom(e) => Self::Custom(e),
         }
@@ -45,6 +49,12 @@ impl From<crate::executor::ExecutionStateError> for CallError {
     }
 }
 
+impl From<crate::executor::ExecutionTimeout> for CallError {
+    fn from(_: crate::executor::ExecutionTimeout) -> Self {
+        Self::ExecutionTimeout
+    }
+}
+
 impl From<CallError> for ApplicationError {
     fn from(value: CallError) -> Self {
         match value {
@@ -53,6 +63,8 @@ impl From<CallError> for ApplicationError {
             CallError::ContractErrorV05 { revert_error } => {
                 ApplicationError::ContractErrorV05 { revert_error }
             }
+            CallError::ExecutionTimeout => ApplicationError::ExecutionTimeout,
+            CallError::ExecutionStepLimitExceeded => ApplicationError::ExecutionStepLimitExceeded,
             CallError::Internal(e) => ApplicationError::Internal(e),
             CallError::Custom(e) => ApplicationError::Custom(e),
         }
@@ -71,6 +83,7 @@ pub struct CallInput {
 pub struct FunctionCall {
     pub contract_address: ContractAddress,
     pub entry_point_selector: EntryPoint,
+    #[serde(deserialize_with = "crate::v02::types::request::calldata")]
     pub calldata: Vec<CallParam>,
 }
 
@@ -79,10 +92,9 @@ pub struct FunctionCall {
 pub struct CallOutput(#[serde_as(as = "Vec<RpcFelt>")] pub Vec<CallResultValue>);
 
 pub async fn call(context: RpcContext, input: CallInput) -> Result<CallOutput, CallError> {
-    let span = tracing::Span::current();
-    let result = tokio::task::spawn_blocking(move || {
-        let _g = span.enter();
-
+    let timeout = context.config.execution_timeout;
+    let pool = context.execution_pool.clone();
+    let execution = pool.execute(ExecutionMethod::Call, move || {
         let mut db = context
             .storage
             .connection()
@@ -109,13 +121,33 @@ pub async fn call(context: RpcContext, input: CallInput) -> Result<CallOutput, C
             }
         };
 
+        // The pending block changes over time, so only calls at canonical blocks are cached.
+        let cache_key = pending.is_none().then(|| {
+            CallCacheKey::new(
+                header.hash,
+                input.request.contract_address,
+                input.request.entry_point_selector,
+                &input.request.calldata,
+            )
+        });
+
+        if let Some(result) = cache_key
+            .as_ref()
+            .and_then(|key| context.call_cache.get(key))
+        {
+            tracing::trace!("Call cache hit");
+            return Ok(result);
+        }
+
         let state = ExecutionState::simulation(
             &db,
             context.chain_id,
             header,
             pending,
             L1BlobDataAvailability::Disabled,
-        );
+        )
+        .with_max_steps(context.config.execution_max_steps)
+        .with_fork(context.fork.clone());
 
         let result = pathfinder_executor::call(
             state,
@@ -124,12 +156,14 @@ pub async fn call(context: RpcContext, input: CallInput) -> Result<CallOutput, C
             input.request.calldata,
         )?;
 
+        if let Some(key) = cache_key {
+            context.call_cache.insert(key, result.clone());
+        }
+
         Ok(result)
-    })
-    .await
-    .context("Executing call")?;
+    });
 
-    result.map(CallOutput)
+    with_timeout(timeout, execution).await.map(CallOutput)
 }
 
 #[cfg(test)]
@@ -275,6 +309,63 @@ mod tests {
             assert_eq!(result, CallOutput(vec![CallResultValue(test_value.0)]));
         }
 
+        #[tokio::test]
+        async fn step_limit_exceeded() {
+            let (mut context, _last_block_header, contract_address, test_key, _test_value) =
+                test_context().await;
+            context.config.execution_max_steps = Some(1);
+
+            let input = CallInput {
+                request: FunctionCall {
+                    contract_address,
+                    entry_point_selector: EntryPoint::hashed(b"get_value"),
+                    calldata: vec![CallParam(*test_key.get())],
+                },
+                block_id: BlockId::Latest,
+            };
+            let error = call(context, input).await;
+            assert_matches::assert_matches!(error, Err(CallError::ExecutionStepLimitExceeded));
+        }
+
+        #[tokio::test]
+        async fn cached_result() {
+            let (context, last_block_header, contract_address, test_key, test_value) =
+                test_context().await;
+
+            let request = FunctionCall {
+                contract_address,
+                entry_point_selector: EntryPoint::hashed(b"get_value"),
+                calldata: vec![CallParam(*test_key.get())],
+            };
+            let key = CallCacheKey::new(
+                last_block_header.hash,
+                request.contract_address,
+                request.entry_point_selector,
+                &request.calldata,
+            );
+            assert_eq!(context.call_cache.get(&key), None);
+
+            let input = CallInput {
+                request: request.clone(),
+                block_id: BlockId::Latest,
+            };
+            call(context.clone(), input).await.unwrap();
+            assert_eq!(
+                context.call_cache.get(&key),
+                Some(vec![CallResultValue(test_value.0)])
+            );
+
+            // Subsequent calls are served from the cache.
+            let cached = vec![CallResultValue(felt!("0x1234"))];
+            context.call_cache.insert(key, cached.clone());
+            let input = CallInput {
+                request,
+                block_id: BlockId::Latest,
+            };
+            let result = call(context, input).await.unwrap();
+            assert_eq!(result, CallOutput(cached));
+        }
+
         #[tokio::test]
         async fn storage_updated_in_pending() {
             let (context, last_block_header, contract_address, test_key, test_value) =
@@ -3,7 +3,10 @@ use pathfinder_executor::{ExecutionState, L1BlobDataAvailability};
 use serde_with::serde_as;
 
 use crate::{
-    context::RpcContext, error::ApplicationError, v02::types::request::BroadcastedTransaction,
+    context::RpcContext,
+    error::ApplicationError,
+    executor::{with_timeout, ExecutionMethod},
+    v02::types::request::BroadcastedTransaction,
 };
 use pathfinder_common::BlockId;
 
@@ -21,6 +24,8 @@ pub enum EstimateFeeError {
     BlockNotFound,
     ContractNotFound,
     ContractErrorV05 { revert_error: String },
+    ExecutionTimeout,
+    ExecutionStepLimitExceeded,
 }
 
 impl From<anyhow::Error> for EstimateFeeError {
@@ -42,6 +47,7 @@ impl From<pathfinder_executor::TransactionExecutionError> for EstimateFeeError {
                     transaction_index, error
                 ),
             },
+            StepLimitExceeded { .. } => Self::ExecutionStepLimitExceeded,
             Internal(e) => Self::Internal(e),
             Custom(e) => Self::Custom(e),
         }
@@ -58,6 +64,12 @@ impl From<crate::executor::ExecutionStateError> for EstimateFeeError {
     }
 }
 
+impl From<crate::executor::ExecutionTimeout> for EstimateFeeError {
+    fn from(_: crate::executor::ExecutionTimeout) -> Self {
+        Self::ExecutionTimeout
+    }
+}
+
 impl From<EstimateFeeError> for ApplicationError {
     fn from(value: EstimateFeeError) -> Self {
         match value {
@@ -66,6 +78,10 @@ impl From<EstimateFeeError> for ApplicationError {
             EstimateFeeError::ContractErrorV05 { revert_error } => {
                 ApplicationError::ContractErrorV05 { revert_error }
             }
+            EstimateFeeError::ExecutionTimeout => ApplicationError::ExecutionTimeout,
+            EstimateFeeError::ExecutionStepLimitExceeded => {
+                ApplicationError::ExecutionStepLimitExceeded
+            }
             EstimateFeeError::Internal(e) => ApplicationError::Internal(e),
             EstimateFeeError::Custom(e) => ApplicationError::Custom(e),
         }
@@ -97,10 +113,10 @@ pub async fn estimate_fee(
     context: RpcContext,
     input: EstimateFeeInput,
 ) -> Result<Vec<FeeEstimate>, EstimateFeeError> {
-    let span = tracing::Span::current();
+    let timeout = context.config.execution_timeout;
 
-    let result = tokio::task::spawn_blocking(move || {
-        let _g = span.enter();
+    let pool = context.execution_pool.clone();
+    let execution = pool.execute(ExecutionMethod::EstimateFee, move || {
         let mut db = context
             .storage
             .connection()
@@ -114,7 +130,12 @@ pub async fn estimate_fee(
                     .get(&db)
                     .context("Querying pending data")?;
 
-                (pending.header(), Some(pending.state_update.clone()))
+                let mut header = pending.header();
+                if context.config.l1_gas_price_floor {
+                    crate::executor::apply_l1_gas_price_floor(&db, &mut header)?;
+                }
+
+                (header, Some(pending.state_update.clone()))
             }
             other => {
                 let block_id = other.try_into().expect("Only pending cast should fail");
@@ -133,7 +154,9 @@ pub async fn estimate_fee(
             header,
             pending,
             L1BlobDataAvailability::Disabled,
-        );
+        )
+        .with_max_steps(context.config.execution_max_steps)
+        .with_fork(context.fork.clone());
 
         let transactions = input
             .request
@@ -144,9 +167,9 @@ pub async fn estimate_fee(
         let result = pathfinder_executor::estimate(state, transactions, false)?;
 
         Ok::<_, EstimateFeeError>(result)
-    })
-    .await
-    .context("Executing transaction")??;
+    });
+
+    let result = with_timeout(timeout, execution).await?;
 
     Ok(result.into_iter().map(Into::into).collect())
 }
@@ -1,5 +1,7 @@
 use crate::{
-    context::RpcContext, executor::ExecutionStateError, v02::types::request::BroadcastedTransaction,
+    context::RpcContext,
+    executor::{with_timeout, ExecutionMethod, ExecutionStateError, ExecutionTimeout},
+    v02::types::request::BroadcastedTransaction,
 };
 
 use anyhow::Context;
@@ -28,6 +30,8 @@ pub enum SimulateTransactionError {
     BlockNotFound,
     ContractNotFound,
     ContractErrorV05 { revert_error: String },
+    ExecutionTimeout,
+    ExecutionStepLimitExceeded,
 }
 
 impl From<anyhow::Error> for SimulateTransactionError {
@@ -46,6 +50,10 @@ impl From<SimulateTransactionError> for crate::error::ApplicationError {
             SimulateTransactionError::ContractErrorV05 { revert_error } => {
                 Self::ContractErrorV05 { revert_error }
             }
+            SimulateTransactionError::ExecutionTimeout => Self::ExecutionTimeout,
+            SimulateTransactionError::ExecutionStepLimitExceeded => {
+                Self::ExecutionStepLimitExceeded
+            }
         }
     }
 }
@@ -63,6 +71,7 @@ impl From<TransactionExecutionError> for SimulateTransactionError {
                     transaction_index, error
                 ),
             },
+            StepLimitExceeded { .. } => Self::ExecutionStepLimitExceeded,
             Internal(e) => Self::Internal(e),
             Custom(e) => Self::Custom(e),
         }
@@ -78,14 +87,19 @@ impl From<ExecutionStateError> for SimulateTransactionError {
     }
 }
 
+impl From<ExecutionTimeout> for SimulateTransactionError {
+    fn from(_: ExecutionTimeout) -> Self {
+        Self::ExecutionTimeout
+    }
+}
+
 pub async fn simulate_transactions(
     context: RpcContext,
     input: SimulateTransactionInput,
 ) -> Result<SimulateTransactionOutput, SimulateTransactionError> {
-    let span = tracing::Span::current();
-    tokio::task::spawn_blocking(move || {
-        let _g = span.enter();
-
+    let timeout = context.config.execution_timeout;
+    let pool = context.execution_pool.clone();
+    let execution = pool.execute(ExecutionMethod::Simulate, move || {
         let skip_validate = input
             .simulation_flags
             .0
@@ -131,7 +145,9 @@ pub async fn simulate_transactions(
             header,
             pending,
             L1BlobDataAvailability::Disabled,
-        );
+        )
+        .with_max_steps(context.config.execution_max_steps)
+        .with_fork(context.fork.clone());
 
         let transactions = input
             .transactions
@@ -154,9 +170,9 @@ pub async fn simulate_transactions(
                 Ok(SimulateTransactionOutput(txs))
             }
         }
-    })
-    .await
-    .context("Simulating transaction")?
+    });
+
+    with_timeout(timeout, execution).await
 }
 
 pub mod dto {
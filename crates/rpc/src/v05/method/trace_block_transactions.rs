@@ -1,4 +1,5 @@
 use anyhow::Context;
+use pathfinder_common::protocol::ProtocolChange;
 use pathfinder_common::transaction::Transaction;
 use pathfinder_common::{BlockId, TransactionHash};
 use pathfinder_executor::{ExecutionState, TraceCache, TransactionExecutionError};
@@ -7,7 +8,7 @@ use starknet_gateway_client::GatewayApi;
 use starknet_gateway_types::trace::TransactionTrace as GatewayTxTrace;
 
 use super::simulate_transactions::dto::TransactionTrace;
-use crate::executor::VERSIONS_LOWER_THAN_THIS_SHOULD_FALL_BACK_TO_FETCHING_TRACE_FROM_GATEWAY;
+use crate::executor::ExecutionMethod;
 use crate::v05::method::simulate_transactions::dto::{
     DeclareTxnTrace, DeployAccountTxnTrace, ExecuteInvocation, InvokeTxnTrace, L1HandlerTxnTrace,
 };
@@ -76,6 +77,10 @@ impl From<TransactionExecutionError> for TraceBlockTransactionsError {
                 transaction_index,
                 error
             )),
+            StepLimitExceeded { transaction_index } => Self::Custom(anyhow::anyhow!(
+                "Execution error at transaction index {}: step limit exceeded",
+                transaction_index
+            )),
             Internal(e) => Self::Internal(e),
             Custom(e) => Self::Custom(e),
         }
@@ -141,12 +146,9 @@ pub async fn trace_block_transactions(
         Unsupported(Vec<Transaction>),
     }
 
-    let span = tracing::Span::current();
-
     let storage = context.storage.clone();
-    let traces = tokio::task::spawn_blocking(move || {
-        let _g = span.enter();
-
+    let pool = context.execution_pool.clone();
+    let execution = pool.execute(ExecutionMethod::Trace, move || {
         let mut db = storage.connection()?;
         let db = db.transaction()?;
 
@@ -184,14 +186,11 @@ pub async fn trace_block_transactions(
             }
         };
 
-        let starknet_version = header
+        let local_execution = header
             .starknet_version
-            .parse_as_semver()
-            .context("Parsing starknet version")?
-            .unwrap_or(semver::Version::new(0, 0, 0));
-        if starknet_version
-            < VERSIONS_LOWER_THAN_THIS_SHOULD_FALL_BACK_TO_FETCHING_TRACE_FROM_GATEWAY
-        {
+            .supports(ProtocolChange::LocalExecution)
+            .context("Parsing starknet version")?;
+        if !local_execution {
             match input.block_id {
                 BlockId::Pending => {
                     return Err(TraceBlockTransactionsError::Internal(anyhow::anyhow!(
@@ -212,7 +211,8 @@ pub async fn trace_block_transactions(
             .collect::<Result<Vec<_>, _>>()?;
 
         let hash = header.hash;
-        let state = ExecutionState::trace(&db, context.chain_id, header, None);
+        let state = ExecutionState::trace(&db, context.chain_id, header, None)
+            .with_fork(context.fork.clone());
         let traces = pathfinder_executor::trace(state, cache, hash, transactions, true, true)?;
 
         let result = traces
@@ -224,9 +224,9 @@ pub async fn trace_block_transactions(
             .collect();
 
         Ok(LocalExecution::Success(result))
-    })
-    .await
-    .context("trace_block_transactions: fetch block & transactions")??;
+    });
+
+    let traces = execution.await?;
 
     let transactions = match traces {
         LocalExecution::Success(traces) => return Ok(TraceBlockTransactionsOutput(traces)),
@@ -1,11 +1,12 @@
 use anyhow::Context;
+use pathfinder_common::protocol::ProtocolChange;
 use pathfinder_common::TransactionHash;
 use pathfinder_executor::{ExecutionState, TraceCache, TransactionExecutionError};
 use serde::{Deserialize, Serialize};
 use starknet_gateway_client::GatewayApi;
 
 use crate::compose_executor_transaction;
-use crate::executor::VERSIONS_LOWER_THAN_THIS_SHOULD_FALL_BACK_TO_FETCHING_TRACE_FROM_GATEWAY;
+use crate::executor::ExecutionMethod;
 use crate::v05::method::trace_block_transactions::map_gateway_trace;
 use crate::{
     context::RpcContext,
@@ -54,6 +55,10 @@ impl From<TransactionExecutionError> for TraceTransactionError {
                 transaction_index,
                 error
             )),
+            StepLimitExceeded { transaction_index } => Self::Custom(anyhow::anyhow!(
+                "Execution error at transaction index {}: step limit exceeded",
+                transaction_index
+            )),
             Internal(e) => Self::Internal(e),
             Custom(e) => Self::Custom(e),
         }
@@ -104,10 +109,8 @@ pub async fn trace_transaction(
         Unsupported(pathfinder_common::transaction::Transaction),
     }
 
-    let span = tracing::Span::current();
-    let local = tokio::task::spawn_blocking(move || {
-        let _g = span.enter();
-
+    let pool = context.execution_pool.clone();
+    let execution = pool.execute(ExecutionMethod::Trace, move || {
         let mut db = context
             .storage
             .connection()
@@ -128,14 +131,11 @@ pub async fn trace_transaction(
         {
             let header = pending.header();
 
-            let starknet_version = header
+            let local_execution = header
                 .starknet_version
-                .parse_as_semver()
-                .context("Parsing starknet version")?
-                .unwrap_or(semver::Version::new(0, 0, 0));
-            if starknet_version
-                < VERSIONS_LOWER_THAN_THIS_SHOULD_FALL_BACK_TO_FETCHING_TRACE_FROM_GATEWAY
-            {
+                .supports(ProtocolChange::LocalExecution)
+                .context("Parsing starknet version")?;
+            if !local_execution {
                 return Ok(LocalExecution::Unsupported(pending_tx.clone()));
             }
 
@@ -156,14 +156,11 @@ pub async fn trace_transaction(
                 .context("Fetching block header")?
                 .context("Block header is missing")?;
 
-            let starknet_version = header
+            let local_execution = header
                 .starknet_version
-                .parse_as_semver()
-                .context("Parsing starknet version")?
-                .unwrap_or(semver::Version::new(0, 0, 0));
-            if starknet_version
-                < VERSIONS_LOWER_THAN_THIS_SHOULD_FALL_BACK_TO_FETCHING_TRACE_FROM_GATEWAY
-            {
+                .supports(ProtocolChange::LocalExecution)
+                .context("Parsing starknet version")?;
+            if !local_execution {
                 let transaction = db
                     .transaction(input.transaction_hash)
                     .context("Fetching transaction data")?
@@ -184,7 +181,8 @@ pub async fn trace_transaction(
         };
 
         let hash = header.hash;
-        let state = ExecutionState::trace(&db, context.chain_id, header, None);
+        let state = ExecutionState::trace(&db, context.chain_id, header, None)
+            .with_fork(context.fork.clone());
 
         let transactions = transactions
             .iter()
@@ -210,9 +208,9 @@ pub async fn trace_transaction(
                     })
             })
             .map(|x| LocalExecution::Success(x.into()))
-    })
-    .await
-    .context("trace_transaction: execution")??;
+    });
+
+    let local = execution.await?;
 
     let transaction = match local {
         LocalExecution::Success(trace) => return Ok(TraceTransactionOutput(trace)),
@@ -1,5 +1,6 @@
 use crate::context::RpcContext;
 use crate::felt::RpcFelt;
+use crate::mempool::TransactionKind;
 use crate::v02::types::request::BroadcastedDeclareTransaction;
 use pathfinder_common::{ClassHash, TransactionHash};
 use starknet_gateway_client::GatewayApi;
@@ -147,6 +148,18 @@ pub async fn add_declare_transaction(
 ) -> Result<AddDeclareTransactionOutput, AddDeclareTransactionError> {
     use starknet_gateway_types::request::add_transaction;
 
+    if let Some(sequencer) = &context.local_sequencer {
+        let Transaction::Declare(tx) = input.declare_transaction;
+        let response = sequencer
+            .add_declare_transaction(tx, context.chain_id, &context.mempool)
+            .await?;
+
+        return Ok(AddDeclareTransactionOutput {
+            transaction_hash: response.transaction_hash,
+            class_hash: response.class_hash,
+        });
+    }
+
     match input.declare_transaction {
         Transaction::Declare(BroadcastedDeclareTransaction::V0(_)) => {
             Err(AddDeclareTransactionError::UnsupportedTransactionVersion)
@@ -173,6 +186,10 @@ pub async fn add_declare_transaction(
                 )
                 .await?;
 
+            context
+                .mempool
+                .insert(response.transaction_hash, TransactionKind::Declare);
+
             Ok(AddDeclareTransactionOutput {
                 transaction_hash: response.transaction_hash,
                 class_hash: response.class_hash,
@@ -200,6 +217,10 @@ pub async fn add_declare_transaction(
                 )
                 .await?;
 
+            context
+                .mempool
+                .insert(response.transaction_hash, TransactionKind::Declare);
+
             Ok(AddDeclareTransactionOutput {
                 transaction_hash: response.transaction_hash,
                 class_hash: response.class_hash,
@@ -242,6 +263,10 @@ pub async fn add_declare_transaction(
                 )
                 .await?;
 
+            context
+                .mempool
+                .insert(response.transaction_hash, TransactionKind::Declare);
+
             Ok(AddDeclareTransactionOutput {
                 transaction_hash: response.transaction_hash,
                 class_hash: response.class_hash,
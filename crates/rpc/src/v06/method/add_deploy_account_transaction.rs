@@ -1,11 +1,14 @@
 use crate::context::RpcContext;
 use crate::felt::{RpcFelt, RpcFelt251};
+use crate::mempool::TransactionKind;
 use crate::v02::types::request::{
     BroadcastedDeployAccountTransaction, BroadcastedDeployAccountTransactionV0V1,
+    BroadcastedTransaction,
 };
-use pathfinder_common::{ContractAddress, TransactionHash};
+use anyhow::Context;
+use pathfinder_common::{ClassHash, ContractAddress, TransactionHash};
 use starknet_gateway_client::GatewayApi;
-use starknet_gateway_types::error::{KnownStarknetErrorCode, SequencerError};
+use starknet_gateway_types::error::{KnownStarknetErrorCode, SequencerError, StarknetError};
 
 #[derive(serde::Deserialize, Debug, PartialEq, Eq)]
 #[serde(tag = "type")]
@@ -46,7 +49,8 @@ impl From<AddDeployAccountTransactionError> for crate::error::ApplicationError {
     fn from(value: AddDeployAccountTransactionError) -> Self {
         use AddDeployAccountTransactionError::*;
         match value {
-            ClassHashNotFound => Self::ClassHashNotFound,
+            // The gateway only reports the class hash in its error message.
+            ClassHashNotFound => Self::ClassHashNotFound { class_hash: None },
             InvalidTransactionNonce => Self::InvalidTransactionNonce,
             InsufficientMaxFee => Self::InsufficientMaxFee,
             InsufficientAccountBalance => Self::InsufficientAccountBalance,
@@ -118,7 +122,42 @@ pub(crate) async fn add_deploy_account_transaction_impl(
 ) -> Result<starknet_gateway_types::reply::add_transaction::DeployAccountResponse, SequencerError> {
     use starknet_gateway_types::request::add_transaction;
 
-    match tx {
+    let announcement =
+        BroadcastedTransaction::DeployAccount(tx.clone()).into_common(context.chain_id);
+
+    if let Some(max) = context
+        .config
+        .max_signature_length
+        .filter(|max| tx.signature().len() > max.get())
+    {
+        return Err(SequencerError::StarknetError(StarknetError {
+            code: KnownStarknetErrorCode::ValidateFailure.into(),
+            message: format!(
+                "Signature of length {} exceeds the maximum length of {max}",
+                tx.signature().len()
+            ),
+        }));
+    }
+
+    if let Some(sequencer) = &context.local_sequencer {
+        // The local sequencer can only deploy classes from the database.
+        ensure_class_declared(context, tx.class_hash()).await?;
+
+        return sequencer
+            .add_deploy_account(announcement, &context.mempool)
+            .await;
+    }
+
+    // The gateway rejects undeclared classes itself, this only spares it the round trip. While
+    // syncing, the class may have been declared in a block which is not stored yet.
+    if matches!(
+        *context.sync_status.status.read().await,
+        crate::v02::types::syncing::Syncing::False(_)
+    ) {
+        ensure_class_declared(context, tx.class_hash()).await?;
+    }
+
+    let response = match tx {
         BroadcastedDeployAccountTransaction::V0V1(
             tx @ BroadcastedDeployAccountTransactionV0V1 { version, .. },
         ) if version.without_query_version() == 0 => {
@@ -189,9 +228,77 @@ pub(crate) async fn add_deploy_account_transaction_impl(
                 ))
                 .await
         }
+    }?;
+
+    context
+        .mempool
+        .insert(response.transaction_hash, TransactionKind::DeployAccount);
+    context.mempool.announce(announcement);
+
+    Ok(response)
+}
+
+/// Rejects the transaction if its account class has not been declared. This is a best-effort
+/// check, so the class is assumed to be declared if the check fails.
+async fn ensure_class_declared(
+    context: &RpcContext,
+    class_hash: ClassHash,
+) -> Result<(), SequencerError> {
+    let declared = class_is_known(context, class_hash)
+        .await
+        .unwrap_or_else(|error| {
+            tracing::warn!(?error, "Checking whether account class is declared failed");
+            true
+        });
+    if declared {
+        Ok(())
+    } else {
+        Err(SequencerError::StarknetError(StarknetError {
+            code: KnownStarknetErrorCode::UndeclaredClass.into(),
+            message: format!("Class with hash {} is not declared", class_hash.0),
+        }))
     }
 }
 
+/// Checks whether the account class has been declared in the database or the pending block.
+async fn class_is_known(context: &RpcContext, class_hash: ClassHash) -> anyhow::Result<bool> {
+    let context = context.clone();
+    let span = tracing::Span::current();
+    tokio::task::spawn_blocking(move || {
+        let _g = span.enter();
+
+        let mut db = context
+            .storage
+            .connection()
+            .context("Opening database connection")?;
+        let tx = db.transaction().context("Creating database transaction")?;
+
+        let pending = context
+            .pending_data
+            .get(&tx)
+            .context("Querying pending data")?;
+        if pending.state_update.class_is_declared(class_hash) {
+            return Ok(true);
+        }
+
+        tx.class_definitions_exist(&[class_hash])
+            .context("Querying class definition")
+            .map(|exists| exists[0])
+    })
+    .await
+    .context("Joining database task")?
+}
+
+/// Stores a definition for the account class, so that transactions deploying it pass the
+/// class check and reach the gateway.
+#[cfg(test)]
+pub(crate) fn declare_class(context: &RpcContext, class_hash: ClassHash) {
+    let mut db = context.storage.connection().unwrap();
+    let tx = db.transaction().unwrap();
+    tx.insert_cairo_class(class_hash, b"{}").unwrap();
+    tx.commit().unwrap();
+}
+
 #[cfg(test)]
 mod tests {
     use crate::v02::types::request::BroadcastedDeployAccountTransactionV3;
@@ -293,12 +400,77 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn undeclared_class_on_local_sequencer() {
+        let (sequencer, _submissions) = crate::local_sequencer::LocalSequencer::new();
+        let context = RpcContext::for_tests().with_local_sequencer(sequencer);
+
+        let input = get_input();
+
+        let error = add_deploy_account_transaction(context, input)
+            .await
+            .expect_err("add_deploy_account_transaction");
+        assert_matches::assert_matches!(error, AddDeployAccountTransactionError::ClassHashNotFound);
+    }
+
+    #[tokio::test]
+    async fn undeclared_class() {
+        let context = RpcContext::for_tests();
+
+        let input = get_input();
+
+        let error = add_deploy_account_transaction(context, input)
+            .await
+            .expect_err("add_deploy_account_transaction");
+        assert_matches::assert_matches!(error, AddDeployAccountTransactionError::ClassHashNotFound);
+    }
+
+    #[tokio::test]
+    async fn signature_too_long() {
+        let mut context = RpcContext::for_tests();
+        context.config.max_signature_length = std::num::NonZeroUsize::new(1);
+
+        // The input has a signature of length 2.
+        let error = add_deploy_account_transaction(context, get_input())
+            .await
+            .expect_err("add_deploy_account_transaction");
+        assert_matches::assert_matches!(
+            error,
+            AddDeployAccountTransactionError::ValidationFailure(message)
+                if message.contains("Signature")
+        );
+    }
+
+    #[tokio::test]
+    async fn signature_length_is_unlimited_by_default() {
+        let context = RpcContext::for_tests();
+
+        let Transaction::DeployAccount(BroadcastedDeployAccountTransaction::V0V1(mut tx)) =
+            get_input().deploy_account_transaction
+        else {
+            unreachable!("Input is a V1 transaction");
+        };
+        tx.signature = vec![transaction_signature_elem!("0x1"); 10_000];
+        let input = AddDeployAccountTransactionInput {
+            deploy_account_transaction: Transaction::DeployAccount(
+                BroadcastedDeployAccountTransaction::V0V1(tx),
+            ),
+        };
+
+        // Fails the following class check instead.
+        let error = add_deploy_account_transaction(context, input)
+            .await
+            .expect_err("add_deploy_account_transaction");
+        assert_matches::assert_matches!(error, AddDeployAccountTransactionError::ClassHashNotFound);
+    }
+
     #[tokio::test]
     #[ignore = "gateway 429"]
     async fn duplicate_transaction() {
         let context = RpcContext::for_tests();
-
         let input = get_input();
+        let Transaction::DeployAccount(tx) = &input.deploy_account_transaction;
+        declare_class(&context, tx.class_hash());
 
         let error = add_deploy_account_transaction(context, input)
             .await
@@ -349,6 +521,7 @@ mod tests {
             ),
         };
 
+        declare_class(&context, input.class_hash);
         let input = AddDeployAccountTransactionInput {
             deploy_account_transaction: Transaction::DeployAccount(
                 BroadcastedDeployAccountTransaction::V3(input),
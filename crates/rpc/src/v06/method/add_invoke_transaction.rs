@@ -1,6 +1,7 @@
 use crate::context::RpcContext;
 use crate::felt::RpcFelt;
-use crate::v02::types::request::BroadcastedInvokeTransaction;
+use crate::mempool::TransactionKind;
+use crate::v02::types::request::{BroadcastedInvokeTransaction, BroadcastedTransaction};
 use pathfinder_common::TransactionHash;
 use starknet_gateway_client::GatewayApi;
 use starknet_gateway_types::error::SequencerError;
@@ -108,7 +109,15 @@ pub(crate) async fn add_invoke_transaction_impl(
 ) -> Result<starknet_gateway_types::reply::add_transaction::InvokeResponse, SequencerError> {
     use starknet_gateway_types::request::add_transaction;
 
-    match tx {
+    let announcement = BroadcastedTransaction::Invoke(tx.clone()).into_common(context.chain_id);
+
+    if let Some(sequencer) = &context.local_sequencer {
+        return sequencer
+            .add_invoke_transaction(announcement, &context.mempool)
+            .await;
+    }
+
+    let response = match tx {
         BroadcastedInvokeTransaction::V0(tx) => {
             context
                 .sequencer
@@ -169,7 +178,14 @@ pub(crate) async fn add_invoke_transaction_impl(
                 ))
                 .await
         }
-    }
+    }?;
+
+    context
+        .mempool
+        .insert(response.transaction_hash, TransactionKind::Invoke);
+    context.mempool.announce(announcement);
+
+    Ok(response)
 }
 
 #[cfg(test)]
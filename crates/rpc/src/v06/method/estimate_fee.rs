@@ -3,7 +3,10 @@ use pathfinder_executor::{ExecutionState, L1BlobDataAvailability};
 use serde_with::serde_as;
 
 use crate::{
-    context::RpcContext, error::ApplicationError, v02::types::request::BroadcastedTransaction,
+    context::RpcContext,
+    error::ApplicationError,
+    executor::{with_timeout, ExecutionMethod},
+    v02::types::request::BroadcastedTransaction,
     v06::types::PriceUnit,
 };
 use pathfinder_common::BlockId;
@@ -14,6 +17,9 @@ pub struct EstimateFeeInput {
     pub request: Vec<BroadcastedTransaction>,
     pub simulation_flags: SimulationFlags,
     pub block_id: BlockId,
+    /// A pathfinder extension, see [StateOverride](crate::executor::StateOverride).
+    #[serde(default)]
+    pub state_overrides: Vec<crate::executor::StateOverride>,
 }
 
 #[derive(Debug, serde::Deserialize, Eq, PartialEq)]
@@ -34,6 +40,8 @@ pub enum EstimateFeeError {
         transaction_index: usize,
         error: String,
     },
+    ExecutionTimeout,
+    ExecutionStepLimitExceeded,
 }
 
 impl From<anyhow::Error> for EstimateFeeError {
@@ -53,6 +61,7 @@ impl From<pathfinder_executor::TransactionExecutionError> for EstimateFeeError {
                 transaction_index,
                 error,
             },
+            StepLimitExceeded { .. } => Self::ExecutionStepLimitExceeded,
             Internal(e) => Self::Internal(e),
             Custom(e) => Self::Custom(e),
         }
@@ -69,6 +78,12 @@ impl From<crate::executor::ExecutionStateError> for EstimateFeeError {
     }
 }
 
+impl From<crate::executor::ExecutionTimeout> for EstimateFeeError {
+    fn from(_: crate::executor::ExecutionTimeout) -> Self {
+        Self::ExecutionTimeout
+    }
+}
+
 impl From<EstimateFeeError> for ApplicationError {
     fn from(value: EstimateFeeError) -> Self {
         match value {
@@ -80,6 +95,10 @@ impl From<EstimateFeeError> for ApplicationError {
                 transaction_index,
                 error,
             },
+            EstimateFeeError::ExecutionTimeout => ApplicationError::ExecutionTimeout,
+            EstimateFeeError::ExecutionStepLimitExceeded => {
+                ApplicationError::ExecutionStepLimitExceeded
+            }
             EstimateFeeError::Internal(e) => ApplicationError::Internal(e),
             EstimateFeeError::Custom(e) => ApplicationError::Custom(e),
         }
@@ -141,10 +160,10 @@ pub async fn estimate_fee_impl(
     input: EstimateFeeInput,
     l1_blob_data_availability: L1BlobDataAvailability,
 ) -> Result<Vec<FeeEstimate>, EstimateFeeError> {
-    let span = tracing::Span::current();
+    let timeout = context.config.execution_timeout;
 
-    let result = tokio::task::spawn_blocking(move || {
-        let _g = span.enter();
+    let pool = context.execution_pool.clone();
+    let execution = pool.execute(ExecutionMethod::EstimateFee, move || {
         let mut db = context
             .storage
             .connection()
@@ -158,7 +177,12 @@ pub async fn estimate_fee_impl(
                     .get(&db)
                     .context("Querying pending data")?;
 
-                (pending.header(), Some(pending.state_update.clone()))
+                let mut header = pending.header();
+                if context.config.l1_gas_price_floor {
+                    crate::executor::apply_l1_gas_price_floor(&db, &mut header)?;
+                }
+
+                (header, Some(pending.state_update.clone()))
             }
             other => {
                 let block_id = other.try_into().expect("Only pending cast should fail");
@@ -177,7 +201,10 @@ pub async fn estimate_fee_impl(
             header,
             pending,
             l1_blob_data_availability,
-        );
+        )
+        .with_state_overrides(crate::executor::map_state_overrides(&input.state_overrides))
+        .with_max_steps(context.config.execution_max_steps)
+        .with_fork(context.fork.clone());
 
         let skip_validate = input
             .simulation_flags
@@ -194,9 +221,9 @@ pub async fn estimate_fee_impl(
         let result = pathfinder_executor::estimate(state, transactions, skip_validate)?;
 
         Ok::<_, EstimateFeeError>(result)
-    })
-    .await
-    .context("Executing transaction")??;
+    });
+
+    let result = with_timeout(timeout, execution).await?;
 
     Ok(result.into_iter().map(Into::into).collect())
 }
@@ -254,6 +281,7 @@ pub(crate) mod tests {
                 request: vec![test_invoke_txn()],
                 simulation_flags: SimulationFlags(vec![SimulationFlag::SkipValidate]),
                 block_id: BlockId::Hash(BlockHash(felt!("0xabcde"))),
+                state_overrides: vec![],
             };
             assert_eq!(input, expected);
         }
@@ -284,6 +312,7 @@ pub(crate) mod tests {
                 request: vec![test_invoke_txn()],
                 simulation_flags: SimulationFlags(vec![SimulationFlag::SkipValidate]),
                 block_id: BlockId::Hash(BlockHash(felt!("0xabcde"))),
+                state_overrides: vec![],
             };
             assert_eq!(input, expected);
         }
@@ -440,6 +469,7 @@ pub(crate) mod tests {
                 ],
                 simulation_flags: SimulationFlags(vec![]),
                 block_id: BlockId::Number(last_block_header.number),
+                state_overrides: vec![],
             };
             let result = estimate_fee(context, input).await.unwrap();
             let declare_expected = FeeEstimate {
@@ -565,6 +595,7 @@ pub(crate) mod tests {
                 request: vec![declare_transaction],
                 simulation_flags: SimulationFlags(vec![]),
                 block_id: BlockId::Pending,
+                state_overrides: vec![],
             };
             let err = estimate_fee(context.clone(), input).await.unwrap_err();
             assert_matches!(
@@ -590,6 +621,7 @@ pub(crate) mod tests {
                 request: vec![declare_transaction],
                 simulation_flags: SimulationFlags(vec![]),
                 block_id: BlockId::Pending,
+                state_overrides: vec![],
             };
             estimate_fee(context, input).await.unwrap();
         }
@@ -8,7 +8,12 @@ use pathfinder_crypto::Felt;
 use pathfinder_executor::{ExecutionState, IntoStarkFelt, L1BlobDataAvailability};
 use starknet_api::core::PatriciaKey;
 
-use crate::{context::RpcContext, error::ApplicationError, v06::method::estimate_fee::FeeEstimate};
+use crate::{
+    context::RpcContext,
+    error::ApplicationError,
+    executor::{with_timeout, ExecutionMethod},
+    v06::method::estimate_fee::FeeEstimate,
+};
 
 #[derive(Debug)]
 pub enum EstimateMessageFeeError {
@@ -16,6 +21,8 @@ pub enum EstimateMessageFeeError {
     BlockNotFound,
     ContractNotFound,
     ContractErrorV05 { revert_error: String },
+    ExecutionTimeout,
+    ExecutionStepLimitExceeded,
     Custom(anyhow::Error),
 }
 
@@ -32,6 +39,7 @@ impl From<pathfinder_executor::TransactionExecutionError> for EstimateMessageFee
             ExecutionError { error, .. } => Self::ContractErrorV05 {
                 revert_error: format!("Execution error: {}", error),
             },
+            StepLimitExceeded { .. } => Self::ExecutionStepLimitExceeded,
             Internal(e) => Self::Internal(e),
             Custom(e) => Self::Custom(e),
         }
@@ -48,6 +56,12 @@ impl From<crate::executor::ExecutionStateError> for EstimateMessageFeeError {
     }
 }
 
+impl From<crate::executor::ExecutionTimeout> for EstimateMessageFeeError {
+    fn from(_: crate::executor::ExecutionTimeout) -> Self {
+        Self::ExecutionTimeout
+    }
+}
+
 impl From<EstimateMessageFeeError> for ApplicationError {
     fn from(value: EstimateMessageFeeError) -> Self {
         match value {
@@ -56,6 +70,10 @@ impl From<EstimateMessageFeeError> for ApplicationError {
             EstimateMessageFeeError::ContractErrorV05 { revert_error } => {
                 ApplicationError::ContractErrorV05 { revert_error }
             }
+            EstimateMessageFeeError::ExecutionTimeout => ApplicationError::ExecutionTimeout,
+            EstimateMessageFeeError::ExecutionStepLimitExceeded => {
+                ApplicationError::ExecutionStepLimitExceeded
+            }
             EstimateMessageFeeError::Internal(e) => ApplicationError::Internal(e),
             EstimateMessageFeeError::Custom(e) => ApplicationError::Custom(e),
         }
@@ -99,10 +117,10 @@ pub(crate) async fn estimate_message_fee_impl(
     input: EstimateMessageFeeInput,
     l1_blob_data_availability: L1BlobDataAvailability,
 ) -> Result<pathfinder_executor::types::FeeEstimate, EstimateMessageFeeError> {
-    let span = tracing::Span::current();
+    let timeout = context.config.execution_timeout;
 
-    let mut result = tokio::task::spawn_blocking(move || {
-        let _g = span.enter();
+    let pool = context.execution_pool.clone();
+    let execution = pool.execute(ExecutionMethod::EstimateMessageFee, move || {
         let mut db = context
             .storage
             .connection()
@@ -116,7 +134,12 @@ pub(crate) async fn estimate_message_fee_impl(
                     .get(&db)
                     .context("Querying pending data")?;
 
-                (pending.header(), Some(pending.state_update.clone()))
+                let mut header = pending.header();
+                if context.config.l1_gas_price_floor {
+                    crate::executor::apply_l1_gas_price_floor(&db, &mut header)?;
+                }
+
+                (header, Some(pending.state_update.clone()))
             }
             other => {
                 let block_id = other.try_into().expect("Only pending cast should fail");
@@ -139,16 +162,18 @@ pub(crate) async fn estimate_message_fee_impl(
             header,
             pending,
             l1_blob_data_availability,
-        );
+        )
+        .with_max_steps(context.config.execution_max_steps)
+        .with_fork(context.fork.clone());
 
         let transaction = create_executor_transaction(input, context.chain_id)?;
 
         let result = pathfinder_executor::estimate(state, vec![transaction], false)?;
 
         Ok::<_, EstimateMessageFeeError>(result)
-    })
-    .await
-    .context("Estimating message fee")??;
+    });
+
+    let mut result = with_timeout(timeout, execution).await?;
 
     if result.len() != 1 {
         return Err(
@@ -8,7 +8,10 @@ pub struct GetTransactionReceiptInput {
     pub transaction_hash: TransactionHash,
 }
 
-crate::error::generate_rpc_error_subset!(GetTransactionReceiptError: TxnHashNotFound);
+crate::error::generate_rpc_error_subset!(
+    GetTransactionReceiptError: TxnHashNotFound,
+    BlockDataNotAvailable
+);
 
 pub async fn get_transaction_receipt(
     context: RpcContext,
@@ -51,10 +54,21 @@ pub async fn get_transaction_receipt_impl(
             return Ok(types::MaybePendingTransactionReceipt::Pending(pending));
         }
 
-        let (transaction, receipt, block_hash) = db_tx
+        let Some((transaction, receipt, block_hash)) = db_tx
             .transaction_with_receipt(input.transaction_hash)
             .context("Reading transaction receipt from database")?
-            .ok_or(GetTransactionReceiptError::TxnHashNotFound)?;
+        else {
+            // The transaction may be part of a block whose transactions have not been backfilled
+            // yet.
+            return if db_tx
+                .any_block_awaiting_backfill()
+                .context("Checking block data availability")?
+            {
+                Err(GetTransactionReceiptError::BlockDataNotAvailable)
+            } else {
+                Err(GetTransactionReceiptError::TxnHashNotFound)
+            };
+        };
 
         let block_number = db_tx
             .block_id(block_hash.into())
@@ -1,5 +1,7 @@
 use crate::{
-    context::RpcContext, executor::ExecutionStateError, v02::types::request::BroadcastedTransaction,
+    context::RpcContext,
+    executor::{with_timeout, ExecutionMethod, ExecutionStateError, ExecutionTimeout},
+    v02::types::request::BroadcastedTransaction,
 };
 
 use anyhow::Context;
@@ -18,6 +20,9 @@ pub struct SimulateTransactionInput {
     pub block_id: BlockId,
     pub transactions: Vec<BroadcastedTransaction>,
     pub simulation_flags: dto::SimulationFlags,
+    /// A pathfinder extension, see [StateOverride](crate::executor::StateOverride).
+    #[serde(default)]
+    pub state_overrides: Vec<crate::executor::StateOverride>,
 }
 
 #[derive(Debug, Serialize, Eq, PartialEq)]
@@ -32,6 +37,8 @@ pub enum SimulateTransactionError {
         transaction_index: usize,
         error: String,
     },
+    ExecutionTimeout,
+    ExecutionStepLimitExceeded,
 }
 
 impl From<anyhow::Error> for SimulateTransactionError {
@@ -53,6 +60,10 @@ impl From<SimulateTransactionError> for crate::error::ApplicationError {
                 transaction_index,
                 error,
             },
+            SimulateTransactionError::ExecutionTimeout => Self::ExecutionTimeout,
+            SimulateTransactionError::ExecutionStepLimitExceeded => {
+                Self::ExecutionStepLimitExceeded
+            }
         }
     }
 }
@@ -68,6 +79,7 @@ impl From<TransactionExecutionError> for SimulateTransactionError {
                 transaction_index,
                 error,
             },
+            StepLimitExceeded { .. } => Self::ExecutionStepLimitExceeded,
             Internal(e) => Self::Internal(e),
             Custom(e) => Self::Custom(e),
         }
@@ -83,6 +95,12 @@ impl From<ExecutionStateError> for SimulateTransactionError {
     }
 }
 
+impl From<ExecutionTimeout> for SimulateTransactionError {
+    fn from(_: ExecutionTimeout) -> Self {
+        Self::ExecutionTimeout
+    }
+}
+
 pub async fn simulate_transactions(
     context: RpcContext,
     input: SimulateTransactionInput,
@@ -100,10 +118,9 @@ pub async fn simulate_transactions_impl(
     input: SimulateTransactionInput,
     l1_blob_data_availability: L1BlobDataAvailability,
 ) -> Result<SimulateTransactionOutput, SimulateTransactionError> {
-    let span = tracing::Span::current();
-    tokio::task::spawn_blocking(move || {
-        let _g = span.enter();
-
+    let timeout = context.config.execution_timeout;
+    let pool = context.execution_pool.clone();
+    let execution = pool.execute(ExecutionMethod::Simulate, move || {
         let skip_validate = input
             .simulation_flags
             .0
@@ -149,7 +166,10 @@ pub async fn simulate_transactions_impl(
             header,
             pending,
             l1_blob_data_availability,
-        );
+        )
+        .with_state_overrides(crate::executor::map_state_overrides(&input.state_overrides))
+        .with_max_steps(context.config.execution_max_steps)
+        .with_fork(context.fork.clone());
 
         let transactions = input
             .transactions
@@ -159,15 +179,18 @@ pub async fn simulate_transactions_impl(
 
         let txs =
             pathfinder_executor::simulate(state, transactions, skip_validate, skip_fee_charge)?;
-        let txs = txs
+        let mut txs = txs
             .into_iter()
             .map(TryInto::try_into)
             .collect::<Result<Vec<SimulatedTransaction>, _>>()?;
-        let txs = txs.into_iter().collect();
+        if context.config.trace_profiles {
+            txs.iter_mut()
+                .for_each(|tx| tx.transaction_trace.with_profiles());
+        }
         Ok(SimulateTransactionOutput(txs))
-    })
-    .await
-    .context("Simulating transaction")?
+    });
+
+    with_timeout(timeout, execution).await
 }
 
 pub mod dto {
@@ -284,6 +307,10 @@ pub mod dto {
         #[serde_as(as = "Vec<RpcFelt>")]
         pub result: Vec<Felt>,
         pub execution_resources: ComputationResources,
+        /// A non-standard extension, only set if [RpcConfig::trace_profiles] is enabled.
+        ///
+        /// [RpcConfig::trace_profiles]: crate::context::RpcConfig::trace_profiles
+        pub profile: Option<CallProfile>,
     }
 
     impl From<pathfinder_executor::types::FunctionInvocation> for FunctionInvocation {
@@ -303,6 +330,65 @@ pub mod dto {
                 messages: fi.messages.into_iter().map(Into::into).collect(),
                 result: fi.result.into_iter().map(Into::into).collect(),
                 execution_resources: fi.computation_resources.into(),
+                profile: None,
+            }
+        }
+    }
+
+    impl FunctionInvocation {
+        /// Sets the [CallProfile] of this and all inner calls.
+        pub fn with_profiles(&mut self) {
+            self.calls.iter_mut().for_each(Self::with_profiles);
+            self.profile = Some(CallProfile::new(self));
+        }
+    }
+
+    /// A breakdown of the work done by a single call, for profiling contracts. This is an
+    /// extension to the specification.
+    #[derive(Clone, Debug, Serialize, Eq, PartialEq)]
+    pub struct CallProfile {
+        /// The resources used by the call itself, excluding those of its inner calls.
+        pub own_execution_resources: ComputationResources,
+        pub syscalls: SyscallCounts,
+    }
+
+    /// The number of syscalls made directly by a call.
+    ///
+    /// Only syscalls which are visible in the trace are counted: storage access for example
+    /// is not tracked per call by the executor.
+    #[derive(Clone, Debug, Default, Serialize, Eq, PartialEq)]
+    pub struct SyscallCounts {
+        pub call_contract: usize,
+        pub library_call: usize,
+        pub deploy: usize,
+        pub emit_event: usize,
+        pub send_message_to_l1: usize,
+    }
+
+    impl CallProfile {
+        fn new(invocation: &FunctionInvocation) -> Self {
+            let mut own_execution_resources = invocation.execution_resources.clone();
+            let mut syscalls = SyscallCounts {
+                emit_event: invocation.events.len(),
+                send_message_to_l1: invocation.messages.len(),
+                ..Default::default()
+            };
+
+            for call in &invocation.calls {
+                // A call's resources include those of its inner calls.
+                own_execution_resources =
+                    own_execution_resources.saturating_sub(&call.execution_resources);
+
+                match (&call.call_type, &call.entry_point_type) {
+                    (_, EntryPointType::Constructor) => syscalls.deploy += 1,
+                    (CallType::Delegate | CallType::_LibraryCall, _) => syscalls.library_call += 1,
+                    (CallType::Call, _) => syscalls.call_contract += 1,
+                }
+            }
+
+            Self {
+                own_execution_resources,
+                syscalls,
             }
         }
     }
@@ -394,6 +480,39 @@ pub mod dto {
         pub segment_arena_builtin: usize,
     }
 
+    impl ComputationResources {
+        fn saturating_sub(&self, rhs: &Self) -> Self {
+            Self {
+                steps: self.steps.saturating_sub(rhs.steps),
+                memory_holes: self.memory_holes.saturating_sub(rhs.memory_holes),
+                range_check_builtin_applications: self
+                    .range_check_builtin_applications
+                    .saturating_sub(rhs.range_check_builtin_applications),
+                pedersen_builtin_applications: self
+                    .pedersen_builtin_applications
+                    .saturating_sub(rhs.pedersen_builtin_applications),
+                poseidon_builtin_applications: self
+                    .poseidon_builtin_applications
+                    .saturating_sub(rhs.poseidon_builtin_applications),
+                ec_op_builtin_applications: self
+                    .ec_op_builtin_applications
+                    .saturating_sub(rhs.ec_op_builtin_applications),
+                ecdsa_builtin_applications: self
+                    .ecdsa_builtin_applications
+                    .saturating_sub(rhs.ecdsa_builtin_applications),
+                bitwise_builtin_applications: self
+                    .bitwise_builtin_applications
+                    .saturating_sub(rhs.bitwise_builtin_applications),
+                keccak_builtin_applications: self
+                    .keccak_builtin_applications
+                    .saturating_sub(rhs.keccak_builtin_applications),
+                segment_arena_builtin: self
+                    .segment_arena_builtin
+                    .saturating_sub(rhs.segment_arena_builtin),
+            }
+        }
+    }
+
     fn builtin_applications_is_zero(n: &usize) -> bool {
         *n == 0
     }
@@ -494,6 +613,36 @@ pub mod dto {
                 TransactionTrace::L1Handler(tx) => tx.with_v06_format(),
             }
         }
+
+        /// Sets the [CallProfile] of each function invocation of the trace.
+        pub fn with_profiles(&mut self) {
+            let invocations = match self {
+                TransactionTrace::Declare(tx) => [
+                    tx.fee_transfer_invocation.as_mut(),
+                    tx.validate_invocation.as_mut(),
+                    None,
+                ],
+                TransactionTrace::DeployAccount(tx) => [
+                    Some(&mut tx.constructor_invocation),
+                    tx.fee_transfer_invocation.as_mut(),
+                    tx.validate_invocation.as_mut(),
+                ],
+                TransactionTrace::Invoke(tx) => [
+                    match &mut tx.execute_invocation {
+                        ExecuteInvocation::FunctionInvocation(invocation) => Some(invocation),
+                        _ => None,
+                    },
+                    tx.fee_transfer_invocation.as_mut(),
+                    tx.validate_invocation.as_mut(),
+                ],
+                TransactionTrace::L1Handler(tx) => [Some(&mut tx.function_invocation), None, None],
+            };
+
+            invocations
+                .into_iter()
+                .flatten()
+                .for_each(FunctionInvocation::with_profiles);
+        }
     }
 
     impl TryFrom<pathfinder_executor::types::TransactionTrace> for TransactionTrace {
@@ -741,6 +890,7 @@ pub mod dto {
                         segment_arena_builtin: builtins.segment_arena_builtin as usize,
                     }
                 },
+                profile: None,
             }
         }
     }
@@ -849,6 +999,7 @@ pub(crate) mod tests {
                                     messages: vec![],
                                     result: vec![],
                                     execution_resources: ComputationResources::default(),
+                                    profile: None,
                                 },
                             validate_invocation: Some(
                                 FunctionInvocation {
@@ -872,6 +1023,7 @@ pub(crate) mod tests {
                                         steps: 13,
                                         ..Default::default()
                                     },
+                                    profile: None,
                                 },
                             ),
                             fee_transfer_invocation: None,
@@ -936,6 +1088,7 @@ pub(crate) mod tests {
             block_id: last_block_header.number.into(),
             transactions: vec![declare],
             simulation_flags: dto::SimulationFlags(vec![]),
+            state_overrides: vec![],
         };
 
         let result = simulate_transactions(context, input).await.unwrap();
@@ -993,6 +1146,7 @@ pub(crate) mod tests {
                                 pedersen_builtin_applications: 4,
                                 ..Default::default()
                             },
+                            profile: None,
                         }
                     ),
                     validate_invocation: Some(
@@ -1014,6 +1168,7 @@ pub(crate) mod tests {
                                 steps: 12,
                                 ..Default::default()
                             },
+                            profile: None,
                         }
                     ),
                     state_diff: Some(StateDiff {
@@ -1224,6 +1379,7 @@ pub(crate) mod tests {
                         pedersen_builtin_applications: 4,
                         ..Default::default()
                     },
+                    profile: None,
                 }
             }
 
@@ -1246,6 +1402,7 @@ pub(crate) mod tests {
                         steps: 12,
                         ..Default::default()
                     },
+                    profile: None,
                 }
             }
 
@@ -1430,6 +1587,7 @@ pub(crate) mod tests {
                         range_check_builtin_applications: 1,
                         ..Default::default()
                     },
+                    profile: None,
                 }
             }
 
@@ -1460,6 +1618,7 @@ pub(crate) mod tests {
                                     messages: vec![],
                                     result: vec![],
                                     execution_resources: ComputationResources::default(),
+                                    profile: None,
                                 },
                             ],
                             class_hash: Some(UNIVERSAL_DEPLOYER_CLASS_HASH.0),
@@ -1505,6 +1664,7 @@ pub(crate) mod tests {
                                 pedersen_builtin_applications: 7,
                                 ..Default::default()
                             },
+                            profile: None,
                         }
                     ],
                     class_hash: Some(DUMMY_ACCOUNT_CLASS_HASH.0),
@@ -1538,6 +1698,7 @@ pub(crate) mod tests {
                         pedersen_builtin_applications: 7,
                         ..Default::default()
                     },
+                    profile: None,
                 }
             }
 
@@ -1582,6 +1743,7 @@ pub(crate) mod tests {
                         pedersen_builtin_applications: 4,
                         ..Default::default()
                     },
+                    profile: None,
                 }
             }
 
@@ -1737,6 +1899,7 @@ pub(crate) mod tests {
                         range_check_builtin_applications: 1,
                         ..Default::default()
                     },
+                    profile: None,
                 }
             }
 
@@ -1766,6 +1929,7 @@ pub(crate) mod tests {
                             range_check_builtin_applications: 2,
                             ..Default::default()
                         },
+                        profile: None,
                     }],
                     class_hash: Some(DUMMY_ACCOUNT_CLASS_HASH.0),
                     entry_point_type: EntryPointType::External,
@@ -1787,6 +1951,7 @@ pub(crate) mod tests {
                         range_check_builtin_applications: 22,
                         ..Default::default()
                     },
+                    profile: None,
                 }
             }
 
@@ -1830,6 +1995,7 @@ pub(crate) mod tests {
                         pedersen_builtin_applications: 4,
                         ..Default::default()
                     },
+                    profile: None,
                 }
             }
         }
@@ -1949,6 +2115,7 @@ pub(crate) mod tests {
                         pedersen_builtin_applications: 4,
                         ..Default::default()
                     },
+                    profile: None,
                 }
             }
 
@@ -1971,6 +2138,7 @@ pub(crate) mod tests {
                         steps: 12,
                         ..Default::default()
                     },
+                    profile: None,
                 }
             }
 
@@ -2086,6 +2254,7 @@ pub(crate) mod tests {
                         range_check_builtin_applications: 1,
                         ..Default::default()
                     },
+                    profile: None,
                 }
             }
 
@@ -2116,6 +2285,7 @@ pub(crate) mod tests {
                                     messages: vec![],
                                     result: vec![],
                                     execution_resources: ComputationResources::default(),
+                                    profile: None,
                                 },
                             ],
                             class_hash: Some(UNIVERSAL_DEPLOYER_CLASS_HASH.0),
@@ -2161,6 +2331,7 @@ pub(crate) mod tests {
                                 pedersen_builtin_applications: 7,
                                 ..Default::default()
                             },
+                            profile: None,
                         }
                     ],
                     class_hash: Some(DUMMY_ACCOUNT_CLASS_HASH.0),
@@ -2194,6 +2365,7 @@ pub(crate) mod tests {
                         pedersen_builtin_applications: 7,
                         ..Default::default()
                     },
+                    profile: None,
                 }
             }
 
@@ -2238,6 +2410,7 @@ pub(crate) mod tests {
                         pedersen_builtin_applications: 4,
                         ..Default::default()
                     },
+                    profile: None,
                 }
             }
 
@@ -2334,6 +2507,7 @@ pub(crate) mod tests {
                         range_check_builtin_applications: 1,
                         ..Default::default()
                     },
+                    profile: None,
                 }
             }
 
@@ -2363,6 +2537,7 @@ pub(crate) mod tests {
                             range_check_builtin_applications: 3,
                             ..Default::default()
                         },
+                        profile: None,
                     }],
                     class_hash: Some(DUMMY_ACCOUNT_CLASS_HASH.0),
                     entry_point_type: EntryPointType::External,
@@ -2384,6 +2559,7 @@ pub(crate) mod tests {
                         range_check_builtin_applications: 24,
                         ..Default::default()
                     },
+                    profile: None,
                 }
             }
 
@@ -2427,6 +2603,7 @@ pub(crate) mod tests {
                         pedersen_builtin_applications: 4,
                         ..Default::default()
                     },
+                    profile: None,
                 }
             }
         }
@@ -2454,6 +2631,7 @@ pub(crate) mod tests {
             ],
             block_id: BlockId::Number(last_block_header.number),
             simulation_flags: dto::SimulationFlags(vec![]),
+            state_overrides: vec![],
         };
         let result = simulate_transactions(context, input).await.unwrap();
 
@@ -2500,6 +2678,7 @@ pub(crate) mod tests {
             ],
             block_id: BlockId::Number(last_block_header.number),
             simulation_flags: dto::SimulationFlags(vec![dto::SimulationFlag::SkipFeeCharge]),
+            state_overrides: vec![],
         };
         let result = simulate_transactions(context, input).await.unwrap();
 
@@ -2543,6 +2722,7 @@ pub(crate) mod tests {
             ],
             block_id: BlockId::Number(last_block_header.number),
             simulation_flags: dto::SimulationFlags(vec![dto::SimulationFlag::SkipValidate]),
+            state_overrides: vec![],
         };
         let result = simulate_transactions(context, input).await.unwrap();
 
@@ -2589,6 +2769,7 @@ pub(crate) mod tests {
             ],
             block_id: BlockId::Number(last_block_header.number),
             simulation_flags: dto::SimulationFlags(vec![]),
+            state_overrides: vec![],
         };
         let result = simulate_transactions(context, input).await.unwrap();
 
@@ -2612,4 +2793,88 @@ pub(crate) mod tests {
             ])
         );
     }
+
+    #[test]
+    fn function_invocation_profile() {
+        use dto::*;
+
+        let invocation = |calls, execution_resources| FunctionInvocation {
+            call_type: CallType::Call,
+            caller_address: felt!("0x0"),
+            calls,
+            class_hash: None,
+            entry_point_type: EntryPointType::External,
+            events: vec![],
+            function_call: FunctionCall {
+                calldata: vec![],
+                contract_address: contract_address!("0x1"),
+                entry_point_selector: entry_point!("0x2"),
+            },
+            messages: vec![],
+            result: vec![],
+            execution_resources,
+            profile: None,
+        };
+
+        let mut library_call = invocation(
+            vec![],
+            ComputationResources {
+                steps: 100,
+                pedersen_builtin_applications: 2,
+                ..Default::default()
+            },
+        );
+        library_call.call_type = CallType::Delegate;
+        let contract_call = invocation(
+            vec![],
+            ComputationResources {
+                steps: 50,
+                ..Default::default()
+            },
+        );
+        let mut root = invocation(
+            vec![library_call, contract_call],
+            ComputationResources {
+                steps: 400,
+                range_check_builtin_applications: 3,
+                pedersen_builtin_applications: 2,
+                ..Default::default()
+            },
+        );
+        root.events.push(OrderedEvent {
+            order: 0,
+            data: vec![],
+            keys: vec![],
+        });
+
+        // Profiles are only included if enabled.
+        let json = serde_json::to_value(&root).unwrap();
+        assert!(json.get("profile").is_none());
+
+        root.with_profiles();
+        let json = serde_json::to_value(&root).unwrap();
+
+        assert_eq!(
+            json["profile"],
+            serde_json::json!({
+                "own_execution_resources": {
+                    "steps": 250,
+                    "range_check_builtin_applications": 3,
+                },
+                "syscalls": {
+                    "call_contract": 1,
+                    "library_call": 1,
+                    "deploy": 0,
+                    "emit_event": 1,
+                    "send_message_to_l1": 0,
+                },
+            })
+        );
+        assert_eq!(
+            json["calls"][0]["profile"]["own_execution_resources"]["steps"],
+            100
+        );
+        // The specified fields are unaffected.
+        assert_eq!(json["execution_resources"]["steps"], 400);
+    }
 }
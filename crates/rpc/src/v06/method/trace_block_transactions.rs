@@ -1,11 +1,12 @@
 use anyhow::Context;
+use pathfinder_common::protocol::ProtocolChange;
 use pathfinder_common::{BlockId, TransactionHash};
 use pathfinder_executor::{ExecutionState, TraceCache, TransactionExecutionError};
 use serde::{Deserialize, Serialize};
 use starknet_gateway_client::GatewayApi;
 use starknet_gateway_types::trace::TransactionTrace as GatewayTxTrace;
 
-use crate::executor::VERSIONS_LOWER_THAN_THIS_SHOULD_FALL_BACK_TO_FETCHING_TRACE_FROM_GATEWAY;
+use crate::executor::ExecutionMethod;
 use crate::v06::method::simulate_transactions::dto::{
     DeclareTxnTrace, DeployAccountTxnTrace, ExecuteInvocation, ExecutionResources,
     FunctionInvocation, InvokeTxnTrace, L1HandlerTxnTrace,
@@ -75,6 +76,10 @@ impl From<TransactionExecutionError> for TraceBlockTransactionsError {
                 transaction_index,
                 error
             )),
+            StepLimitExceeded { transaction_index } => Self::Custom(anyhow::anyhow!(
+                "Transaction execution failed at index {}: step limit exceeded",
+                transaction_index
+            )),
             Internal(e) => Self::Internal(e),
             Custom(e) => Self::Custom(e),
         }
@@ -185,12 +190,9 @@ pub async fn trace_block_transactions_impl(
         Unsupported(Vec<Transaction>),
     }
 
-    let span = tracing::Span::current();
-
     let storage = context.storage.clone();
-    let traces = tokio::task::spawn_blocking(move || {
-        let _g = span.enter();
-
+    let pool = context.execution_pool.clone();
+    let execution = pool.execute(ExecutionMethod::Trace, move || {
         let mut db = storage.connection()?;
         let db = db.transaction()?;
 
@@ -228,14 +230,11 @@ pub async fn trace_block_transactions_impl(
             }
         };
 
-        let starknet_version = header
+        let local_execution = header
             .starknet_version
-            .parse_as_semver()
-            .context("Parsing starknet version")?
-            .unwrap_or(semver::Version::new(0, 0, 0));
-        if starknet_version
-            < VERSIONS_LOWER_THAN_THIS_SHOULD_FALL_BACK_TO_FETCHING_TRACE_FROM_GATEWAY
-        {
+            .supports(ProtocolChange::LocalExecution)
+            .context("Parsing starknet version")?;
+        if !local_execution {
             match input.block_id {
                 BlockId::Pending => {
                     return Err(TraceBlockTransactionsError::Internal(anyhow::anyhow!(
@@ -256,7 +255,8 @@ pub async fn trace_block_transactions_impl(
             .collect::<Result<Vec<_>, _>>()?;
 
         let hash = header.hash;
-        let state = ExecutionState::trace(&db, context.chain_id, header, None);
+        let state = ExecutionState::trace(&db, context.chain_id, header, None)
+            .with_fork(context.fork.clone());
         let traces = pathfinder_executor::trace(state, cache, hash, transactions, true, true)?;
 
         let result = traces
@@ -270,39 +270,40 @@ pub async fn trace_block_transactions_impl(
             .collect::<Result<Vec<_>, TraceBlockTransactionsError>>()?;
 
         Ok(LocalExecution::Success(result))
-    })
-    .await
-    .context("trace_block_transactions: fetch block & transactions")??;
+    });
+
+    let traces = execution.await?;
+
+    let mut traces = match traces {
+        LocalExecution::Success(traces) => traces,
+        LocalExecution::Unsupported(transactions) => context
+            .sequencer
+            .block_traces(input.block_id)
+            .await
+            .context("Forwarding to feeder gateway")
+            .map_err(TraceBlockTransactionsError::from)?
+            .traces
+            .into_iter()
+            .zip(transactions.into_iter())
+            .map(|(trace, tx)| {
+                let transaction_hash = tx.hash;
+                let trace_root = map_gateway_trace(tx, trace)?;
 
-    let transactions = match traces {
-        LocalExecution::Success(traces) => return Ok(TraceBlockTransactionsOutput(traces)),
-        LocalExecution::Unsupported(transactions) => transactions,
+                Ok(Trace {
+                    transaction_hash,
+                    trace_root,
+                })
+            })
+            .collect::<Result<Vec<_>, TraceBlockTransactionsError>>()?,
     };
 
-    context
-        .sequencer
-        .block_traces(input.block_id)
-        .await
-        .context("Forwarding to feeder gateway")
-        .map_err(TraceBlockTransactionsError::from)
-        .map(|trace| {
-            Ok(TraceBlockTransactionsOutput(
-                trace
-                    .traces
-                    .into_iter()
-                    .zip(transactions.into_iter())
-                    .map(|(trace, tx)| {
-                        let transaction_hash = tx.hash;
-                        let trace_root = map_gateway_trace(tx, trace)?;
-
-                        Ok(Trace {
-                            transaction_hash,
-                            trace_root,
-                        })
-                    })
-                    .collect::<Result<Vec<_>, TraceBlockTransactionsError>>()?,
-            ))
-        })?
+    if context.config.trace_profiles {
+        traces
+            .iter_mut()
+            .for_each(|trace| trace.trace_root.with_profiles());
+    }
+
+    Ok(TraceBlockTransactionsOutput(traces))
 }
 
 #[cfg(test)]
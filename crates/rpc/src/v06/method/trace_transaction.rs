@@ -1,4 +1,5 @@
 use anyhow::Context;
+use pathfinder_common::protocol::ProtocolChange;
 use pathfinder_common::transaction::Transaction;
 use pathfinder_common::TransactionHash;
 use pathfinder_executor::{ExecutionState, TraceCache, TransactionExecutionError};
@@ -6,7 +7,7 @@ use serde::{Deserialize, Serialize};
 use starknet_gateway_client::GatewayApi;
 
 use crate::compose_executor_transaction;
-use crate::executor::VERSIONS_LOWER_THAN_THIS_SHOULD_FALL_BACK_TO_FETCHING_TRACE_FROM_GATEWAY;
+use crate::executor::ExecutionMethod;
 use crate::v06::method::trace_block_transactions::map_gateway_trace;
 use crate::{
     context::RpcContext,
@@ -55,6 +56,10 @@ impl From<TransactionExecutionError> for TraceTransactionError {
                 transaction_index,
                 error
             )),
+            StepLimitExceeded { transaction_index } => Self::Custom(anyhow::anyhow!(
+                "Transaction execution failed at index {}: step limit exceeded",
+                transaction_index
+            )),
             Internal(e) => Self::Internal(e),
             Custom(e) => Self::Custom(e),
         }
@@ -120,10 +125,8 @@ pub async fn trace_transaction_impl(
         Unsupported(Transaction),
     }
 
-    let span = tracing::Span::current();
-    let local = tokio::task::spawn_blocking(move || {
-        let _g = span.enter();
-
+    let pool = context.execution_pool.clone();
+    let execution = pool.execute(ExecutionMethod::Trace, move || {
         let mut db = context
             .storage
             .connection()
@@ -144,14 +147,11 @@ pub async fn trace_transaction_impl(
         {
             let header = pending.header();
 
-            let starknet_version = header
+            let local_execution = header
                 .starknet_version
-                .parse_as_semver()
-                .context("Parsing starknet version")?
-                .unwrap_or(semver::Version::new(0, 0, 0));
-            if starknet_version
-                < VERSIONS_LOWER_THAN_THIS_SHOULD_FALL_BACK_TO_FETCHING_TRACE_FROM_GATEWAY
-            {
+                .supports(ProtocolChange::LocalExecution)
+                .context("Parsing starknet version")?;
+            if !local_execution {
                 return Ok(LocalExecution::Unsupported(pending_tx.clone()));
             }
 
@@ -171,14 +171,11 @@ pub async fn trace_transaction_impl(
                 .context("Fetching block header")?
                 .context("Block header is missing")?;
 
-            let starknet_version = header
+            let local_execution = header
                 .starknet_version
-                .parse_as_semver()
-                .context("Parsing starknet version")?
-                .unwrap_or(semver::Version::new(0, 0, 0));
-            if starknet_version
-                < VERSIONS_LOWER_THAN_THIS_SHOULD_FALL_BACK_TO_FETCHING_TRACE_FROM_GATEWAY
-            {
+                .supports(ProtocolChange::LocalExecution)
+                .context("Parsing starknet version")?;
+            if !local_execution {
                 let transaction = db
                     .transaction(input.transaction_hash)
                     .context("Fetching transaction data")?
@@ -199,7 +196,8 @@ pub async fn trace_transaction_impl(
         };
 
         let hash = header.hash;
-        let state = ExecutionState::trace(&db, context.chain_id, header, None);
+        let state = ExecutionState::trace(&db, context.chain_id, header, None)
+            .with_fork(context.fork.clone());
 
         let transactions = transactions
             .iter()
@@ -225,22 +223,26 @@ pub async fn trace_transaction_impl(
                     })
             })
             .and_then(|x| Ok(LocalExecution::Success(x.try_into()?)))
-    })
-    .await
-    .context("trace_transaction: execution")??;
+    });
 
-    let transaction = match local {
-        LocalExecution::Success(trace) => return Ok(TraceTransactionOutput(trace)),
-        LocalExecution::Unsupported(x) => x,
-    };
+    let local = execution.await?;
 
-    let trace = context
-        .sequencer
-        .transaction_trace(input.transaction_hash)
-        .await
-        .context("Proxying call to feeder gateway")?;
+    let mut trace = match local {
+        LocalExecution::Success(trace) => trace,
+        LocalExecution::Unsupported(transaction) => {
+            let trace = context
+                .sequencer
+                .transaction_trace(input.transaction_hash)
+                .await
+                .context("Proxying call to feeder gateway")?;
 
-    let trace = map_gateway_trace(transaction, trace)?;
+            map_gateway_trace(transaction, trace)?
+        }
+    };
+
+    if context.config.trace_profiles {
+        trace.with_profiles();
+    }
 
     Ok(TraceTransactionOutput(trace))
 }
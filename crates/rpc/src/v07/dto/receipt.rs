@@ -27,11 +27,16 @@ pub struct PendingBlockWithReceipts {
 type CommonTransaction = pathfinder_common::transaction::Transaction;
 type CommonReceipt = pathfinder_common::receipt::Receipt;
 
+// Pathfinder's extensions to the receipts of the specification are only included when
+// `extensions` is set, which only `pathfinder_*` methods do. This keeps the `starknet_*`
+// methods conforming to the specification.
+
 impl BlockWithReceipts {
     pub fn from_common(
         header: pathfinder_common::BlockHeader,
         body: Vec<(CommonTransaction, CommonReceipt)>,
         is_l1_accepted: bool,
+        extensions: bool,
     ) -> Self {
         let status = if is_l1_accepted {
             BlockStatus::AcceptedOnL1
@@ -47,7 +52,7 @@ impl BlockWithReceipts {
             v06::FinalityStatus::AcceptedOnL2
         };
 
-        let body = BlockBodyWithReceipts::from_common(body, finality_status);
+        let body = BlockBodyWithReceipts::from_common(body, finality_status, extensions);
 
         Self {
             status,
@@ -72,7 +77,8 @@ impl From<PendingData> for PendingBlockWithReceipts {
             })
             .collect::<Vec<_>>();
 
-        let body = BlockBodyWithReceipts::from_common(body, v06::FinalityStatus::AcceptedOnL2);
+        let body =
+            BlockBodyWithReceipts::from_common(body, v06::FinalityStatus::AcceptedOnL2, false);
 
         Self {
             header: value.header().into(),
@@ -90,10 +96,11 @@ impl BlockBodyWithReceipts {
     fn from_common(
         value: Vec<(CommonTransaction, CommonReceipt)>,
         finality_status: v06::FinalityStatus,
+        extensions: bool,
     ) -> Self {
         let transactions = value
             .into_iter()
-            .map(|(t, r)| TransactionWithReceipt::from_common(t, r, finality_status))
+            .map(|(t, r)| TransactionWithReceipt::from_common(t, r, finality_status, extensions))
             .collect();
 
         Self { transactions }
@@ -112,8 +119,10 @@ impl TransactionWithReceipt {
         transaction: CommonTransaction,
         receipt: CommonReceipt,
         finality_status: v06::FinalityStatus,
+        extensions: bool,
     ) -> Self {
-        let receipt = PendingTxnReceipt::from_common(&transaction, receipt, finality_status);
+        let receipt =
+            PendingTxnReceipt::from_common(&transaction, receipt, finality_status, extensions);
 
         Self {
             transaction: transaction.into(),
@@ -190,6 +199,7 @@ impl TxnReceipt {
         block_hash: BlockHash,
         block_number: BlockNumber,
         finality_status: v06::FinalityStatus,
+        extensions: bool,
     ) -> Self {
         let common = CommonReceiptProperties::from_common(
             transaction,
@@ -197,6 +207,7 @@ impl TxnReceipt {
             block_hash,
             block_number,
             finality_status,
+            extensions,
         );
 
         use pathfinder_common::transaction::TransactionVariant;
@@ -233,9 +244,14 @@ impl PendingTxnReceipt {
         transaction: &CommonTransaction,
         receipt: CommonReceipt,
         finality_status: v06::FinalityStatus,
+        extensions: bool,
     ) -> Self {
-        let common =
-            PendingCommonReceiptProperties::from_common(transaction, receipt, finality_status);
+        let common = PendingCommonReceiptProperties::from_common(
+            transaction,
+            receipt,
+            finality_status,
+            extensions,
+        );
 
         use pathfinder_common::transaction::TransactionVariant;
         match &transaction.variant {
@@ -318,13 +334,39 @@ impl From<pathfinder_common::receipt::ExecutionDataAvailability> for DataResourc
     }
 }
 
+/// An L2 to L1 message sent by a transaction.
+#[serde_with::serde_as]
+#[derive(Serialize)]
+pub struct MessageToL1 {
+    #[serde(flatten)]
+    message: v06::MessageToL1,
+    /// The hash under which the message is registered by the Starknet core contract on L1.
+    /// This is an extension to the specification.
+    #[serde_as(as = "Option<H256AsNoLeadingZerosHexStr>")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message_hash: Option<primitive_types::H256>,
+}
+
+impl MessageToL1 {
+    fn from_common(value: pathfinder_common::receipt::L2ToL1Message, extensions: bool) -> Self {
+        Self {
+            message_hash: extensions.then(|| value.calculate_message_hash()),
+            message: value.into(),
+        }
+    }
+}
+
 #[derive(Serialize)]
 pub struct CommonReceiptProperties {
     transaction_hash: TransactionHash,
     actual_fee: FeePayment,
     block_hash: BlockHash,
     block_number: BlockNumber,
-    messages_sent: Vec<v06::MessageToL1>,
+    /// The index of the transaction within its block. This is an extension to the
+    /// specification.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    transaction_index: Option<TransactionIndex>,
+    messages_sent: Vec<MessageToL1>,
     events: Vec<v06::Event>,
     #[serde(skip_serializing_if = "Option::is_none")]
     revert_reason: Option<String>,
@@ -337,7 +379,7 @@ pub struct CommonReceiptProperties {
 pub struct PendingCommonReceiptProperties {
     transaction_hash: TransactionHash,
     actual_fee: FeePayment,
-    messages_sent: Vec<v06::MessageToL1>,
+    messages_sent: Vec<MessageToL1>,
     events: Vec<v06::Event>,
     #[serde(skip_serializing_if = "Option::is_none")]
     revert_reason: Option<String>,
@@ -353,6 +395,7 @@ impl CommonReceiptProperties {
         block_hash: BlockHash,
         block_number: BlockNumber,
         finality_status: v06::FinalityStatus,
+        extensions: bool,
     ) -> Self {
         let actual_fee = FeePayment {
             amount: receipt.actual_fee.unwrap_or_default(),
@@ -360,10 +403,11 @@ impl CommonReceiptProperties {
         };
 
         let revert_reason = receipt.revert_reason().map(ToOwned::to_owned);
+        let transaction_index = extensions.then_some(receipt.transaction_index);
         let messages_sent = receipt
             .l2_to_l1_messages
             .into_iter()
-            .map(Into::into)
+            .map(|message| MessageToL1::from_common(message, extensions))
             .collect();
         let events = receipt.events.into_iter().map(Into::into).collect();
         let execution_status = receipt.execution_status.into();
@@ -380,6 +424,7 @@ impl CommonReceiptProperties {
             finality_status,
             block_hash,
             block_number,
+            transaction_index,
         }
     }
 }
@@ -389,6 +434,7 @@ impl PendingCommonReceiptProperties {
         transaction: &CommonTransaction,
         receipt: CommonReceipt,
         finality_status: v06::FinalityStatus,
+        extensions: bool,
     ) -> Self {
         let actual_fee = FeePayment {
             amount: receipt.actual_fee.unwrap_or_default(),
@@ -399,7 +445,7 @@ impl PendingCommonReceiptProperties {
         let messages_sent = receipt
             .l2_to_l1_messages
             .into_iter()
-            .map(Into::into)
+            .map(|message| MessageToL1::from_common(message, extensions))
             .collect();
         let events = receipt.events.into_iter().map(Into::into).collect();
         let execution_status = receipt.execution_status.into();
@@ -482,6 +528,7 @@ mod tests {
                 },
                 block_hash: block_hash!("0x3"),
                 block_number: BlockNumber::new_or_panic(4),
+                transaction_index: None,
                 messages_sent: vec![],
                 events: vec![],
                 revert_reason: None,
@@ -574,4 +621,23 @@ mod tests {
         let encoded = serde_json::to_value(uut).unwrap();
         assert_eq!(encoded, expected);
     }
+
+    #[test]
+    fn message_hash_is_an_extension() {
+        let message = pathfinder_common::receipt::L2ToL1Message {
+            from_address: contract_address!("0xcafebabe"),
+            payload: vec![
+                l2_to_l1_message_payload_elem!("0x1"),
+                l2_to_l1_message_payload_elem!("0x2"),
+            ],
+            to_address: EthereumAddress(primitive_types::H160::from_low_u64_be(0x1234)),
+        };
+
+        let encoded =
+            serde_json::to_value(MessageToL1::from_common(message.clone(), false)).unwrap();
+        assert!(encoded.get("message_hash").is_none());
+
+        let encoded = serde_json::to_value(MessageToL1::from_common(message, true)).unwrap();
+        assert!(encoded["message_hash"].is_string());
+    }
 }
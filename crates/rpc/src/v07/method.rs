@@ -3,7 +3,7 @@ mod estimate_message_fee;
 mod get_block_with_receipts;
 mod get_block_with_tx_hashes;
 mod get_block_with_txs;
-mod get_transaction_receipt;
+pub(crate) mod get_transaction_receipt;
 mod simulate_transactions;
 mod trace_block_transactions;
 mod trace_transaction;
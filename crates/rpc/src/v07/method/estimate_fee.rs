@@ -26,6 +26,7 @@ mod tests {
     use pathfinder_common::Tip;
 
     use pathfinder_common::felt;
+    use starknet_gateway_test_fixtures::class_definitions::DUMMY_ACCOUNT_CLASS_HASH;
 
     use crate::v02::types::request::{
         BroadcastedDeclareTransaction, BroadcastedDeclareTransactionV2,
@@ -166,6 +167,7 @@ mod tests {
             ],
             simulation_flags: SimulationFlags(vec![]),
             block_id: BlockId::Number(last_block_header.number),
+            state_overrides: vec![],
         };
         let result = super::estimate_fee(context, input).await.unwrap();
         let declare_expected = FeeEstimate {
@@ -220,4 +222,57 @@ mod tests {
             ]
         );
     }
+
+    #[tokio::test]
+    async fn state_overrides() {
+        let (context, last_block_header, _, _) =
+            crate::test_setup::test_context_with_starknet_version(StarknetVersion::new(0, 13, 1))
+                .await;
+
+        // An account which is neither deployed nor funded.
+        let account = contract_address!("0xc03");
+
+        let invoke_transaction = BroadcastedTransaction::Invoke(BroadcastedInvokeTransaction::V1(
+            BroadcastedInvokeTransactionV1 {
+                nonce: transaction_nonce!("0x5"),
+                version: TransactionVersion::ONE,
+                max_fee: fee!("0x10000000"),
+                signature: vec![],
+                sender_address: account,
+                calldata: vec![
+                    CallParam(pathfinder_executor::ETH_FEE_TOKEN_ADDRESS.0),
+                    // Entry point selector for the called contract, i.e. AccountCallArray::selector
+                    CallParam(EntryPoint::hashed(b"balanceOf").0),
+                    // Length of the call data for the called contract, i.e. AccountCallArray::data_len
+                    call_param!("1"),
+                    CallParam(account.0),
+                ],
+            },
+        ));
+
+        let input = EstimateFeeInput {
+            request: vec![invoke_transaction.clone()],
+            simulation_flags: SimulationFlags(vec![]),
+            block_id: BlockId::Number(last_block_header.number),
+            state_overrides: vec![],
+        };
+        let result = super::estimate_fee(context.clone(), input).await;
+        assert!(result.is_err());
+
+        let input = EstimateFeeInput {
+            request: vec![invoke_transaction],
+            simulation_flags: SimulationFlags(vec![]),
+            block_id: BlockId::Number(last_block_header.number),
+            state_overrides: vec![crate::executor::StateOverride {
+                contract_address: account,
+                class_hash: Some(DUMMY_ACCOUNT_CLASS_HASH),
+                nonce: Some(contract_nonce!("0x5")),
+                balance: Some(felt!("0x10000000000000000000000000000")),
+                storage: vec![],
+            }],
+        };
+        let result = super::estimate_fee(context, input).await.unwrap();
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].unit, PriceUnit::Wei);
+    }
 }
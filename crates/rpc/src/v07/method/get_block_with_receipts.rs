@@ -16,7 +16,7 @@ pub struct Input {
     pub block_id: BlockId,
 }
 
-crate::error::generate_rpc_error_subset!(Error: BlockNotFound);
+crate::error::generate_rpc_error_subset!(Error: BlockNotFound, BlockDataNotAvailable);
 
 pub async fn get_block_with_receipts(context: RpcContext, input: Input) -> Result<Output, Error> {
     let span = tracing::Span::current();
@@ -46,6 +46,13 @@ pub async fn get_block_with_receipts(context: RpcContext, input: Input) -> Resul
             .context("Fetching block header")?
             .ok_or(Error::BlockNotFound)?;
 
+        if db
+            .block_awaiting_backfill(header.number)
+            .context("Checking block data availability")?
+        {
+            return Err(Error::BlockDataNotAvailable);
+        }
+
         let body = db
             .transaction_data_for_block(block_id)
             .context("Fetching transaction data")?
@@ -59,6 +66,7 @@ pub async fn get_block_with_receipts(context: RpcContext, input: Input) -> Resul
             header,
             body,
             is_l1_accepted,
+            false,
         )))
     })
     .await
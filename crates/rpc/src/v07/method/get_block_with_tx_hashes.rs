@@ -35,7 +35,7 @@ pub struct PendingBlockWithTxHashes {
     transactions: Vec<TransactionHash>,
 }
 
-crate::error::generate_rpc_error_subset!(Error: BlockNotFound);
+crate::error::generate_rpc_error_subset!(Error: BlockNotFound, BlockDataNotAvailable);
 
 /// Get block information with transaction hashes given the block id
 pub async fn get_block_with_tx_hashes(context: RpcContext, input: Input) -> Result<Output, Error> {
@@ -75,6 +75,13 @@ pub async fn get_block_with_tx_hashes(context: RpcContext, input: Input) -> Resu
             .context("Reading block from database")?
             .ok_or(Error::BlockNotFound)?;
 
+        if transaction
+            .block_awaiting_backfill(header.number)
+            .context("Checking block data availability")?
+        {
+            return Err(Error::BlockDataNotAvailable);
+        }
+
         let l1_accepted = transaction.block_is_l1_accepted(header.number.into())?;
         let status = if l1_accepted {
             BlockStatus::AcceptedOnL1
@@ -36,7 +36,7 @@ pub struct PendingBlockWithTxs {
     transactions: Vec<TransactionWithHash>,
 }
 
-crate::error::generate_rpc_error_subset!(Error: BlockNotFound);
+crate::error::generate_rpc_error_subset!(Error: BlockNotFound, BlockDataNotAvailable);
 
 /// Get block information with full transactions given the block id
 pub async fn get_block_with_txs(context: RpcContext, input: Input) -> Result<Output, Error> {
@@ -82,6 +82,13 @@ pub async fn get_block_with_txs(context: RpcContext, input: Input) -> Result<Out
             .context("Reading block from database")?
             .ok_or(Error::BlockNotFound)?;
 
+        if transaction
+            .block_awaiting_backfill(header.number)
+            .context("Checking block data availability")?
+        {
+            return Err(Error::BlockDataNotAvailable);
+        }
+
         let l1_accepted = transaction.block_is_l1_accepted(header.number.into())?;
         let status = if l1_accepted {
             BlockStatus::AcceptedOnL1
@@ -17,9 +17,19 @@ pub enum Output {
     Pending(dto::receipt::PendingTxnReceipt),
 }
 
-crate::error::generate_rpc_error_subset!(Error: TxnHashNotFound);
+crate::error::generate_rpc_error_subset!(Error: TxnHashNotFound, BlockDataNotAvailable);
 
 pub async fn get_transaction_receipt(context: RpcContext, input: Input) -> Result<Output, Error> {
+    transaction_receipt(context, input, false).await
+}
+
+/// Returns the receipt, including pathfinder's extensions to the specification if `extensions`
+/// is set.
+pub(crate) async fn transaction_receipt(
+    context: RpcContext,
+    input: Input,
+    extensions: bool,
+) -> Result<Output, Error> {
     let span = tracing::Span::current();
 
     tokio::task::spawn_blocking(move || {
@@ -48,15 +58,27 @@ pub async fn get_transaction_receipt(context: RpcContext, input: Input) -> Resul
                 &transaction,
                 receipt,
                 FinalityStatus::AcceptedOnL2,
+                extensions,
             );
 
             return Ok(Output::Pending(receipt));
         }
 
-        let (transaction, receipt, block_hash) = db_tx
+        let Some((transaction, receipt, block_hash)) = db_tx
             .transaction_with_receipt(input.transaction_hash)
             .context("Reading transaction receipt from database")?
-            .ok_or(Error::TxnHashNotFound)?;
+        else {
+            // The transaction may be part of a block whose transactions have not been backfilled
+            // yet.
+            return if db_tx
+                .any_block_awaiting_backfill()
+                .context("Checking block data availability")?
+            {
+                Err(Error::BlockDataNotAvailable)
+            } else {
+                Err(Error::TxnHashNotFound)
+            };
+        };
 
         let block_number = db_tx
             .block_id(block_hash.into())
@@ -80,8 +102,61 @@ pub async fn get_transaction_receipt(context: RpcContext, input: Input) -> Resul
             block_hash,
             block_number,
             finality_status,
+            extensions,
         )))
     })
     .await
     .context("Joining blocking task")?
 }
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use serde::Serialize;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn includes_block_number() {
+        let context = RpcContext::for_tests();
+        let input = Input {
+            transaction_hash: transaction_hash_bytes!(b"txn 0"),
+        };
+
+        let output = get_transaction_receipt(context, input)
+            .await
+            .unwrap()
+            .serialize(serde_json::value::Serializer)
+            .unwrap();
+
+        assert_eq!(output["block_number"], serde_json::json!(0));
+        assert!(output.get("transaction_index").is_none());
+    }
+
+    #[tokio::test]
+    async fn extensions_include_transaction_index() {
+        let context = RpcContext::for_tests();
+        let input = Input {
+            transaction_hash: transaction_hash_bytes!(b"txn 0"),
+        };
+
+        let output = transaction_receipt(context, input, true)
+            .await
+            .unwrap()
+            .serialize(serde_json::value::Serializer)
+            .unwrap();
+
+        assert_eq!(output["transaction_index"], serde_json::json!(0));
+    }
+
+    #[tokio::test]
+    async fn not_found() {
+        let context = RpcContext::for_tests();
+        let input = Input {
+            transaction_hash: transaction_hash_bytes!(b"non-existent"),
+        };
+
+        let result = get_transaction_receipt(context, input).await;
+        assert!(matches!(result, Err(Error::TxnHashNotFound)));
+    }
+}
@@ -95,6 +95,7 @@ pub(crate) mod tests {
                                     messages: vec![],
                                     result: vec![],
                                     execution_resources: ComputationResources::default(),
+                                    profile: None,
                                 },
                             validate_invocation: Some(
                                 FunctionInvocation {
@@ -118,6 +119,7 @@ pub(crate) mod tests {
                                         steps: 13,
                                         ..Default::default()
                                     },
+                                    profile: None,
                                 },
                             ),
                             fee_transfer_invocation: None,
@@ -190,6 +192,7 @@ pub(crate) mod tests {
             block_id: last_block_header.number.into(),
             transactions: vec![declare],
             simulation_flags: dto::SimulationFlags(vec![]),
+            state_overrides: vec![],
         };
 
         let result = simulate_transactions(context, input).await.unwrap();
@@ -247,6 +250,7 @@ pub(crate) mod tests {
                                 pedersen_builtin_applications: 4,
                                 ..Default::default()
                             },
+                            profile: None,
                         }
                     ),
                     validate_invocation: Some(
@@ -268,6 +272,7 @@ pub(crate) mod tests {
                                 steps: 12,
                                 ..Default::default()
                             },
+                            profile: None,
                         }
                     ),
                     state_diff: Some(StateDiff {
@@ -523,6 +528,7 @@ pub(crate) mod tests {
                     messages: vec![],
                     result: vec![felt!("0x1")],
                     execution_resources: declare_fee_transfer_computation_resources(),
+                    profile: None,
                 }
             }
 
@@ -542,6 +548,7 @@ pub(crate) mod tests {
                     messages: vec![],
                     result: vec![],
                     execution_resources: declare_validate_computation_resources(),
+                    profile: None,
                 }
             }
 
@@ -777,6 +784,7 @@ pub(crate) mod tests {
                     messages: vec![],
                     result: vec![],
                     execution_resources: universal_deployer_validate_computation_resources(),
+                    profile: None,
                 }
             }
 
@@ -807,6 +815,7 @@ pub(crate) mod tests {
                                     messages: vec![],
                                     result: vec![],
                                     execution_resources: ComputationResources::default(),
+                                    profile: None,
                                 },
                             ],
                             class_hash: Some(UNIVERSAL_DEPLOYER_CLASS_HASH.0),
@@ -852,6 +861,7 @@ pub(crate) mod tests {
                                 pedersen_builtin_applications: 7,
                                 ..Default::default()
                             },
+                            profile: None,
                         }
                     ],
                     class_hash: Some(DUMMY_ACCOUNT_CLASS_HASH.0),
@@ -879,6 +889,7 @@ pub(crate) mod tests {
                         *DEPLOYED_CONTRACT_ADDRESS.get(),
                     ],
                     execution_resources: universal_deployer_execute_computation_resources(),
+                    profile: None,
                 }
             }
 
@@ -917,6 +928,7 @@ pub(crate) mod tests {
                     messages: vec![],
                     result: vec![felt!("0x1")],
                     execution_resources: universal_deployer_fee_transfer_computation_resources(),
+                    profile: None,
                 }
             }
 
@@ -1118,6 +1130,7 @@ pub(crate) mod tests {
                     messages: vec![],
                     result: vec![],
                     execution_resources: invoke_validate_computation_resources(),
+                    profile: None,
                 }
             }
 
@@ -1147,6 +1160,7 @@ pub(crate) mod tests {
                             range_check_builtin_applications: 3,
                             ..Default::default()
                         },
+                        profile: None,
                     }],
                     class_hash: Some(DUMMY_ACCOUNT_CLASS_HASH.0),
                     entry_point_type: EntryPointType::External,
@@ -1164,6 +1178,7 @@ pub(crate) mod tests {
                     messages: vec![],
                     result: vec![test_storage_value.0],
                     execution_resources: invoke_execute_computation_resources(),
+                    profile: None,
                 }
             }
 
@@ -1201,6 +1216,7 @@ pub(crate) mod tests {
                     messages: vec![],
                     result: vec![felt!("0x1")],
                     execution_resources: invoke_fee_transfer_computation_resources(),
+                    profile: None,
                 }
             }
 
@@ -1356,6 +1372,7 @@ pub(crate) mod tests {
                     messages: vec![],
                     result: vec![felt!("0x1")],
                     execution_resources: invoke_fee_transfer_computation_resources(),
+                    profile: None,
                 }
             }
 
@@ -1417,6 +1434,7 @@ pub(crate) mod tests {
             ],
             block_id: BlockId::Number(last_block_header.number),
             simulation_flags: dto::SimulationFlags(vec![]),
+            state_overrides: vec![],
         };
         let result = simulate_transactions(context, input).await.unwrap();
 
@@ -1469,6 +1487,7 @@ pub(crate) mod tests {
             ],
             block_id: BlockId::Number(last_block_header.number),
             simulation_flags: dto::SimulationFlags(vec![dto::SimulationFlag::SkipFeeCharge]),
+            state_overrides: vec![],
         };
         let result = simulate_transactions(context, input).await.unwrap();
 
@@ -1517,6 +1536,7 @@ pub(crate) mod tests {
             ],
             block_id: BlockId::Number(last_block_header.number),
             simulation_flags: dto::SimulationFlags(vec![dto::SimulationFlag::SkipValidate]),
+            state_overrides: vec![],
         };
         let result = simulate_transactions(context, input).await.unwrap();
 
@@ -2,8 +2,9 @@
 
 use num_bigint::BigUint;
 use pathfinder_common::{
-    BlockNumber, CallParam, ConstructorParam, EthereumAddress, GasPrice, L1ToL2MessagePayloadElem,
-    L2ToL1MessagePayloadElem, ResourceAmount, ResourcePricePerUnit, Tip, TransactionSignatureElem,
+    BlockNumber, CallParam, ConstructorParam, ContractAddress, EthereumAddress, GasPrice,
+    L1ToL2MessagePayloadElem, L2ToL1MessagePayloadElem, ResourceAmount, ResourcePricePerUnit, Tip,
+    TransactionSignatureElem,
 };
 use pathfinder_crypto::{Felt, HexParseError, OverflowError};
 use primitive_types::{H160, H256, U256};
@@ -47,6 +48,43 @@ serde_conv!(
     |s: &str| starkhash_from_dec_str(s).map(TransactionSignatureElem)
 );
 
+serde_conv!(
+    /// Serializes a [Felt] as a "0x" prefixed hex string of exactly 64 digits.
+    ///
+    /// Any hex string of up to 64 digits, with or without the "0x" prefix, is accepted when
+    /// deserializing.
+    pub FeltAsPaddedHexStr,
+    Felt,
+    |serialize_me: &Felt| serialize_me.to_padded_hex_str(),
+    |s: &str| Felt::from_hex_str(s)
+);
+
+serde_conv!(
+    /// Serializes a [ContractAddress] as a "0x" prefixed hex string of exactly 64 digits.
+    ///
+    /// Any hex string of up to 64 digits, with or without the "0x" prefix, is accepted when
+    /// deserializing.
+    pub ContractAddressAsPaddedHexStr,
+    ContractAddress,
+    |serialize_me: &ContractAddress| serialize_me.0.to_padded_hex_str(),
+    |s: &str| -> anyhow::Result<ContractAddress> {
+        let felt = Felt::from_hex_str(s)?;
+        ContractAddress::new(felt)
+            .ok_or_else(|| anyhow::anyhow!("Address has more than 251 bits"))
+    }
+);
+
+serde_conv!(
+    /// Serializes a [ContractAddress] as a checksummed hex string, see
+    /// [ContractAddress::to_checksum_hex_str].
+    ///
+    /// The checksum is verified when deserializing strings which have one.
+    pub ContractAddressAsChecksumHexStr,
+    ContractAddress,
+    |serialize_me: &ContractAddress| serialize_me.to_checksum_hex_str(),
+    |s: &str| ContractAddress::from_checksum_hex_str(s)
+);
+
 pub struct EthereumAddressAsHexStr;
 
 impl SerializeAs<EthereumAddress> for EthereumAddressAsHexStr {
@@ -638,6 +676,80 @@ mod tests {
         );
     }
 
+    mod felt_and_address_as_hex_str {
+        use pathfinder_common::macro_prelude::*;
+        use pathfinder_common::ContractAddress;
+        use pathfinder_crypto::Felt;
+        use pretty_assertions_sorted::assert_eq;
+
+        #[serde_with::serde_as]
+        #[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+        struct Value {
+            #[serde_as(as = "super::super::FeltAsPaddedHexStr")]
+            felt: Felt,
+            #[serde_as(as = "super::super::ContractAddressAsPaddedHexStr")]
+            padded: ContractAddress,
+            #[serde_as(as = "super::super::ContractAddressAsChecksumHexStr")]
+            checksum: ContractAddress,
+        }
+
+        const ADDRESS: &str = "0x2fd23d9182193775423497fc0c472e156c57c69e4089a1967fb288a2d84e914";
+        const PADDED: &str = "0x02fd23d9182193775423497fc0c472e156c57c69e4089a1967fb288a2d84e914";
+        const CHECKSUM: &str = "0x02Fd23d9182193775423497fc0c472E156C57C69E4089A1967fb288A2d84e914";
+
+        #[test]
+        fn serialize() {
+            let value = Value {
+                felt: felt!("0x123"),
+                padded: contract_address!(ADDRESS),
+                checksum: contract_address!(ADDRESS),
+            };
+
+            let json = serde_json::to_value(&value).unwrap();
+
+            assert_eq!(
+                json,
+                serde_json::json!({
+                    "felt": format!("0x{:0>64}", "123"),
+                    "padded": PADDED,
+                    "checksum": CHECKSUM,
+                })
+            );
+            assert_eq!(serde_json::from_value::<Value>(json).unwrap(), value);
+        }
+
+        #[test]
+        fn deserialize_relaxed() {
+            let json = serde_json::json!({
+                "felt": "123",
+                "padded": ADDRESS,
+                "checksum": ADDRESS,
+            });
+
+            let value = serde_json::from_value::<Value>(json).unwrap();
+
+            assert_eq!(
+                value,
+                Value {
+                    felt: felt!("0x123"),
+                    padded: contract_address!(ADDRESS),
+                    checksum: contract_address!(ADDRESS),
+                }
+            );
+        }
+
+        #[test]
+        fn invalid_checksum() {
+            let json = serde_json::json!({
+                "felt": "0x1",
+                "padded": ADDRESS,
+                "checksum": CHECKSUM.replace("Fd23", "fD23"),
+            });
+
+            serde_json::from_value::<Value>(json).unwrap_err();
+        }
+    }
+
     mod block_number_as_hex_str {
         #[serde_with::serde_as]
         #[derive(Debug, Copy, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
@@ -0,0 +1,110 @@
+use std::num::NonZeroUsize;
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use pathfinder_storage::test_utils::ChainBuilder;
+use pathfinder_storage::{EventFilter, EventOrder, Storage};
+
+/// The number of blocks searched by the event benchmarks.
+const NUM_BLOCKS: usize = 1000;
+
+pub fn criterion_benchmark(c: &mut Criterion) {
+    // Bench block insertion
+    bench_insert_transaction_data(c);
+
+    // Bench event queries
+    bench_events(c);
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
+
+pub fn bench_insert_transaction_data(c: &mut Criterion) {
+    let mut grp_insert = c.benchmark_group("insert_transaction_data");
+    for invokes in [10, 100, 1000] {
+        let block = ChainBuilder::new(1)
+            .with_contracts_per_block(10)
+            .with_invokes_per_block(invokes)
+            .build()
+            .pop()
+            .unwrap();
+        let header = block.header.header;
+        let transaction_data = block
+            .transaction_data
+            .into_iter()
+            .map(|(transaction, receipt)| (transaction, Some(receipt)))
+            .collect::<Vec<_>>();
+
+        grp_insert.throughput(Throughput::Elements(transaction_data.len() as u64));
+        grp_insert.bench_with_input(
+            BenchmarkId::from_parameter(transaction_data.len()),
+            &transaction_data,
+            |b, transaction_data| {
+                b.iter_batched_ref(
+                    || {
+                        let storage = Storage::in_memory().unwrap();
+                        let mut connection = storage.connection().unwrap();
+                        let tx = connection.transaction().unwrap();
+                        tx.insert_block_header(&header).unwrap();
+                        tx.commit().unwrap();
+                        (storage, connection)
+                    },
+                    |(_, connection)| {
+                        let tx = connection.transaction().unwrap();
+                        tx.insert_transaction_data(header.hash, header.number, transaction_data)
+                            .unwrap();
+                        tx.commit().unwrap();
+                    },
+                    criterion::BatchSize::PerIteration,
+                )
+            },
+        );
+    }
+    grp_insert.finish();
+}
+
+/// Queries events over a chain of [NUM_BLOCKS] blocks with 20 events each.
+///
+/// The storage's Bloom filter cache is much smaller than the chain, so most filters are
+/// loaded from the database.
+pub fn bench_events(c: &mut Criterion) {
+    let storage = Storage::in_memory().unwrap();
+    let blocks = ChainBuilder::new(NUM_BLOCKS)
+        .with_invokes_per_block(20)
+        .insert(&storage)
+        .unwrap();
+    let mut connection = storage.connection().unwrap();
+    let tx = connection.transaction().unwrap();
+
+    // Every block's events are sent by the contract deployed in that block, and have unique
+    // keys. Both filters below therefore only match a single block.
+    let (_, first_receipt) = blocks.first().unwrap().transaction_data.last().unwrap();
+    let first_contract = first_receipt.events[0].from_address;
+    let (_, last_receipt) = blocks.last().unwrap().transaction_data.last().unwrap();
+    let last_key = last_receipt.events[0].keys[0];
+
+    let filters = [
+        ("no_filter", None, vec![]),
+        ("contract_address", Some(first_contract), vec![]),
+        ("key", None, vec![vec![last_key]]),
+    ];
+
+    let limit = NonZeroUsize::new(NUM_BLOCKS).unwrap();
+
+    let mut grp_events = c.benchmark_group("events");
+    for (name, contract_address, keys) in filters {
+        let filter = EventFilter {
+            from_block: None,
+            to_block: None,
+            contract_address,
+            keys,
+            page_size: 1024,
+            offset: 0,
+            order: EventOrder::Ascending,
+        };
+
+        grp_events.bench_function(name, |b| {
+            b.iter(|| black_box(tx.events(&filter, limit, limit).unwrap()))
+        });
+    }
+    grp_events.finish();
+}
@@ -1,3 +1,4 @@
+use std::io::{Read, Write};
 use std::sync::{Mutex, MutexGuard};
 
 use bloomfilter::Bloom;
@@ -13,6 +14,12 @@ use crate::ReorgCounter;
 // filter.
 pub const EVENT_KEY_FILTER_LIMIT: usize = 16;
 
+const METRIC_CACHE_HITS: &str = "storage_bloom_filter_cache_hits_total";
+const METRIC_CACHE_MISSES: &str = "storage_bloom_filter_cache_misses_total";
+
+/// Identifies the format of the files written by [Cache::save].
+const CACHE_FILE_MAGIC: [u8; 8] = *b"pfbloom1";
+
 #[derive(Clone)]
 pub(crate) struct BloomFilter(Bloom<Felt>);
 
@@ -122,6 +129,12 @@ impl BloomFilter {
     }
 }
 
+impl PartialEq for BloomFilter {
+    fn eq(&self, other: &Self) -> bool {
+        self.to_bytes() == other.to_bytes()
+    }
+}
+
 type CacheKey = (crate::ReorgCounter, BlockNumber);
 pub(crate) struct Cache(Mutex<SizedCache<CacheKey, BloomFilter>>);
 
@@ -139,15 +152,91 @@ impl Cache {
         reorg_counter: ReorgCounter,
         block_number: BlockNumber,
     ) -> Option<BloomFilter> {
-        self.locked_cache()
+        let bloom = self
+            .locked_cache()
             .cache_get(&(reorg_counter, block_number))
-            .cloned()
+            .cloned();
+
+        match bloom {
+            Some(_) => metrics::increment_counter!(METRIC_CACHE_HITS),
+            None => metrics::increment_counter!(METRIC_CACHE_MISSES),
+        }
+
+        bloom
     }
 
     pub fn set(&self, reorg_counter: ReorgCounter, block_number: BlockNumber, bloom: BloomFilter) {
         self.locked_cache()
             .cache_set((reorg_counter, block_number), bloom);
     }
+
+    /// Writes the cached filters of the blocks at `reorg_counter` and returns their number.
+    ///
+    /// The filters are written uncompressed and least recently used first, so that [Cache::load]
+    /// is cheap and restores their order.
+    pub fn save(
+        &self,
+        reorg_counter: ReorgCounter,
+        writer: &mut impl Write,
+    ) -> std::io::Result<usize> {
+        let cache = self.locked_cache();
+        let filters = cache
+            .key_order()
+            .zip(cache.value_order())
+            .filter(|((counter, _), _)| *counter == reorg_counter)
+            .map(|((_, block_number), bloom)| (block_number, bloom))
+            .collect::<Vec<_>>();
+
+        writer.write_all(&CACHE_FILE_MAGIC)?;
+        writer.write_all(&reorg_counter.get().to_le_bytes())?;
+        writer.write_all(&(filters.len() as u64).to_le_bytes())?;
+        for (block_number, bloom) in filters.iter().rev() {
+            writer.write_all(&block_number.get().to_le_bytes())?;
+            writer.write_all(&bloom.to_bytes())?;
+        }
+
+        Ok(filters.len())
+    }
+
+    /// Adds the filters written by [Cache::save] and returns their number. Nothing is added if
+    /// they were saved at a different `reorg_counter`, as they may belong to orphaned blocks.
+    pub fn load(
+        &self,
+        reorg_counter: ReorgCounter,
+        reader: &mut impl Read,
+    ) -> std::io::Result<usize> {
+        fn read_u64(reader: &mut impl Read) -> std::io::Result<u64> {
+            let mut bytes = [0u8; 8];
+            reader.read_exact(&mut bytes)?;
+            Ok(u64::from_le_bytes(bytes))
+        }
+
+        let mut magic = [0u8; 8];
+        reader.read_exact(&mut magic)?;
+        if magic != CACHE_FILE_MAGIC {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "Not a Bloom filter cache file",
+            ));
+        }
+
+        if read_u64(reader)? != reorg_counter.get() {
+            return Ok(0);
+        }
+
+        let count = read_u64(reader)?;
+        let mut bytes = vec![0u8; BloomFilter::BITMAP_BYTES as usize];
+        for _ in 0..count {
+            let block_number = BlockNumber::new(read_u64(reader)?).ok_or_else(|| {
+                std::io::Error::new(std::io::ErrorKind::InvalidData, "Invalid block number")
+            })?;
+            reader.read_exact(&mut bytes)?;
+
+            self.set(reorg_counter, block_number, BloomFilter::from_bytes(&bytes));
+        }
+
+        Ok(count as usize)
+    }
 }
 
 #[cfg(test)]
@@ -168,6 +257,47 @@ mod tests {
         assert!(!bloom.check(&KEY_NOT_IN_FILTER));
     }
 
+    #[test]
+    fn save_and_load_cache() {
+        let mut bloom = BloomFilter::new();
+        bloom.set(&KEY);
+
+        let reorg_counter = ReorgCounter::new(1);
+        let cache = Cache::with_size(4);
+        cache.set(
+            reorg_counter,
+            BlockNumber::new_or_panic(1),
+            BloomFilter::new(),
+        );
+        cache.set(reorg_counter, BlockNumber::new_or_panic(2), bloom.clone());
+        // Filters of other reorg counters are not saved.
+        cache.set(ReorgCounter::new(0), BlockNumber::new_or_panic(3), bloom);
+
+        let mut file = Vec::new();
+        assert_eq!(cache.save(reorg_counter, &mut file).unwrap(), 2);
+
+        let loaded = Cache::with_size(4);
+        assert_eq!(loaded.load(reorg_counter, &mut file.as_slice()).unwrap(), 2);
+        let bloom = loaded
+            .get(reorg_counter, BlockNumber::new_or_panic(2))
+            .unwrap();
+        assert!(bloom.check(&KEY));
+        assert!(loaded
+            .get(reorg_counter, BlockNumber::new_or_panic(1))
+            .is_some());
+        assert!(loaded
+            .get(reorg_counter, BlockNumber::new_or_panic(3))
+            .is_none());
+
+        // Filters saved before a reorg are ignored.
+        let loaded = Cache::with_size(4);
+        let reorg_counter = ReorgCounter::new(2);
+        assert_eq!(loaded.load(reorg_counter, &mut file.as_slice()).unwrap(), 0);
+        assert!(loaded
+            .get(reorg_counter, BlockNumber::new_or_panic(2))
+            .is_none());
+    }
+
     #[test]
     fn serialize_roundtrip() {
         let mut bloom = BloomFilter::new();
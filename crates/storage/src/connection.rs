@@ -4,12 +4,18 @@ use std::sync::Arc;
 
 mod block;
 mod class;
+mod consistency;
 mod ethereum;
 mod event;
+mod peer_scores;
+mod peers;
+mod query;
 mod reference;
 mod reorg_counter;
 mod signature;
+mod state_stats;
 mod state_update;
+mod sync_checkpoint;
 pub(crate) mod transaction;
 mod trie;
 
@@ -18,20 +24,35 @@ use pathfinder_common::state_update::StateUpdateCounts;
 // Re-export this so users don't require rusqlite as a direct dep.
 pub use rusqlite::TransactionBehavior;
 
+pub use consistency::{BlockData, IncompleteBlock, OrphanedData, MAX_INCOMPLETE_BLOCKS};
+
 pub use event::KEY_FILTER_LIMIT as EVENT_KEY_FILTER_LIMIT;
 pub use event::PAGE_SIZE_LIMIT as EVENT_PAGE_SIZE_LIMIT;
-pub use event::{EmittedEvent, EventFilter, EventFilterError, PageOfEvents};
+pub use event::{
+    BloomFilterReport, EmittedEvent, EventFilter, EventFilterError, EventOrder, PageOfEvents,
+};
+
+pub use peers::KnownPeer;
+
+pub use query::{QueryError, QueryResult};
 
-pub(crate) use reorg_counter::ReorgCounter;
+pub use reorg_counter::{Reorg, ReorgCounter};
+
+pub use state_stats::{StateStats, TableSize};
+
+pub use sync_checkpoint::SyncStage;
 
 use smallvec::SmallVec;
 pub use transaction::TransactionStatus;
 
-pub use trie::{Child, Node, StoredNode};
+#[cfg(fuzzing)]
+pub use trie::{fuzz_decode_node, fuzz_encode_node};
+pub use trie::{Child, Node, SqliteTrieStore, StoredNode, TrieLayout, TrieNodeStore, TrieTable};
 
 use pathfinder_common::*;
 use pathfinder_crypto::Felt;
-use pathfinder_ethereum::EthereumStateUpdate;
+use pathfinder_ethereum::{EthereumStateUpdate, L1GasPrices};
+use primitive_types::H256;
 
 use pathfinder_common::transaction::Transaction as StarknetTransaction;
 
@@ -42,16 +63,19 @@ type PooledConnection = r2d2::PooledConnection<r2d2_sqlite::SqliteConnectionMana
 pub struct Connection {
     connection: PooledConnection,
     bloom_filter_cache: Arc<crate::bloom::Cache>,
+    trie_store: Arc<dyn TrieNodeStore>,
 }
 
 impl Connection {
     pub(crate) fn new(
         connection: PooledConnection,
         bloom_filter_cache: Arc<crate::bloom::Cache>,
+        trie_store: Arc<dyn TrieNodeStore>,
     ) -> Self {
         Self {
             connection,
             bloom_filter_cache,
+            trie_store,
         }
     }
 
@@ -60,6 +84,7 @@ impl Connection {
         Ok(Transaction {
             transaction: tx,
             bloom_filter_cache: self.bloom_filter_cache.clone(),
+            trie_store: self.trie_store.clone(),
         })
     }
 
@@ -71,6 +96,7 @@ impl Connection {
         Ok(Transaction {
             transaction: tx,
             bloom_filter_cache: self.bloom_filter_cache.clone(),
+            trie_store: self.trie_store.clone(),
         })
     }
 }
@@ -78,6 +104,7 @@ impl Connection {
 pub struct Transaction<'inner> {
     transaction: rusqlite::Transaction<'inner>,
     bloom_filter_cache: Arc<crate::bloom::Cache>,
+    trie_store: Arc<dyn TrieNodeStore>,
 }
 
 impl<'inner> Transaction<'inner> {
@@ -89,6 +116,7 @@ impl<'inner> Transaction<'inner> {
         Self {
             transaction: tx,
             bloom_filter_cache: Arc::new(crate::bloom::Cache::with_size(1)),
+            trie_store: Arc::new(SqliteTrieStore),
         }
     }
 
@@ -144,6 +172,27 @@ impl<'inner> Transaction<'inner> {
         block::purge_block(self, block)
     }
 
+    /// Purges the blocks from the lowest block at the end of the chain which is missing some of
+    /// its data, e.g. because pathfinder was killed while storing it, so that sync downloads
+    /// them again. Returns that block, or [None] if there is nothing to repair.
+    ///
+    /// Transactions are not expected for the blocks before `transactions_from`, which is the
+    /// trusted block if sync was started with one. Fails without purging anything if more than
+    /// [MAX_INCOMPLETE_BLOCKS] blocks would be purged.
+    pub fn roll_back_incomplete_blocks(
+        &self,
+        transactions_from: Option<BlockNumber>,
+    ) -> anyhow::Result<Option<IncompleteBlock>> {
+        consistency::roll_back_incomplete_blocks(self, transactions_from)
+    }
+
+    /// Removes the transactions, event filters and trie nodes of blocks whose headers are not
+    /// stored. These are left behind if pathfinder is killed after committing the
+    /// [split](crate::SplitDatabases) database files but before committing the main database.
+    pub fn remove_orphaned_data(&self) -> anyhow::Result<OrphanedData> {
+        consistency::remove_orphaned_data(self)
+    }
+
     pub fn block_id(&self, block: BlockId) -> anyhow::Result<Option<(BlockNumber, BlockHash)>> {
         block::block_id(self, block)
     }
@@ -168,6 +217,36 @@ impl<'inner> Transaction<'inner> {
         block::first_block_without_receipts(self)
     }
 
+    /// Returns the first block in `from..=to` whose transactions have not been backfilled yet.
+    pub fn first_block_awaiting_backfill(
+        &self,
+        from: BlockNumber,
+        to: BlockNumber,
+    ) -> anyhow::Result<Option<BlockNumber>> {
+        block::first_block_awaiting_backfill(self, from, to)
+    }
+
+    /// Returns true if the transactions of `block` have not been backfilled yet.
+    pub fn block_awaiting_backfill(&self, block: BlockNumber) -> anyhow::Result<bool> {
+        block::first_block_awaiting_backfill(self, block, block).map(|x| x.is_some())
+    }
+
+    /// Returns true if the transactions of any block have not been backfilled yet.
+    pub fn any_block_awaiting_backfill(&self) -> anyhow::Result<bool> {
+        block::any_block_awaiting_backfill(self)
+    }
+
+    /// Sets the transaction and event commitments of the block, e.g. once its transactions have
+    /// been backfilled.
+    pub fn update_block_commitments(
+        &self,
+        block: BlockNumber,
+        transaction_commitment: TransactionCommitment,
+        event_commitment: EventCommitment,
+    ) -> anyhow::Result<()> {
+        block::update_block_commitments(self, block, transaction_commitment, event_commitment)
+    }
+
     pub fn update_l1_l2_pointer(&self, block: Option<BlockNumber>) -> anyhow::Result<()> {
         reference::update_l1_l2_pointer(self, block)
     }
@@ -191,6 +270,21 @@ impl<'inner> Transaction<'inner> {
         ethereum::latest_l1_state(self)
     }
 
+    /// Records the Ethereum gas prices observed when the block was stored.
+    pub fn insert_l1_gas_prices(
+        &self,
+        block: BlockNumber,
+        prices: &L1GasPrices,
+    ) -> anyhow::Result<()> {
+        ethereum::insert_l1_gas_prices(self, block, prices)
+    }
+
+    /// Returns the Ethereum gas prices observed when the block was stored, or [None] if the
+    /// block does not exist or none were recorded.
+    pub fn l1_gas_prices(&self, block: BlockId) -> anyhow::Result<Option<L1GasPrices>> {
+        ethereum::l1_gas_prices(self, block)
+    }
+
     /// Inserts the transaction, receipt and event data.
     pub fn insert_transaction_data(
         &self,
@@ -224,6 +318,15 @@ impl<'inner> Transaction<'inner> {
         transaction::transaction(self, hash)
     }
 
+    /// Returns the block number, transaction hash and message index of each L2 to L1 message
+    /// with the given hash.
+    pub fn l2_to_l1_messages(
+        &self,
+        message_hash: H256,
+    ) -> anyhow::Result<Vec<(BlockNumber, TransactionHash, usize)>> {
+        transaction::l2_to_l1_messages(self, message_hash)
+    }
+
     pub fn transaction_with_receipt(
         &self,
         hash: TransactionHash,
@@ -282,6 +385,32 @@ impl<'inner> Transaction<'inner> {
         )
     }
 
+    /// Estimates the cost of querying all pages of the filter's [events](Self::events), as the
+    /// number of Bloom filters which would be checked.
+    pub fn event_query_cost(&self, filter: &EventFilter) -> anyhow::Result<u64> {
+        event::event_query_cost(self, filter)
+    }
+
+    /// Checks the stored event Bloom filters of the blocks in `[from, to]` against the
+    /// events in their receipts, without modifying them.
+    pub fn verify_bloom_filters(
+        &self,
+        from: BlockNumber,
+        to: BlockNumber,
+    ) -> anyhow::Result<BloomFilterReport> {
+        event::check_bloom_filters(self, from, to, false)
+    }
+
+    /// Like [verify_bloom_filters](Self::verify_bloom_filters), but also replaces any
+    /// missing or mismatched filters with ones recomputed from the receipts.
+    pub fn rebuild_bloom_filters(
+        &self,
+        from: BlockNumber,
+        to: BlockNumber,
+    ) -> anyhow::Result<BloomFilterReport> {
+        event::check_bloom_filters(self, from, to, true)
+    }
+
     pub fn insert_sierra_class(
         &self,
         sierra_hash: &SierraHash,
@@ -307,6 +436,16 @@ impl<'inner> Transaction<'inner> {
         class::insert_cairo_class(self, cairo_hash, definition)
     }
 
+    /// Records the block in which the given classes were declared. Classes whose declaration
+    /// is already known are not changed.
+    pub fn update_class_declarations(
+        &self,
+        block_number: BlockNumber,
+        classes: &[ClassHash],
+    ) -> anyhow::Result<()> {
+        class::update_class_declarations(self, block_number, classes)
+    }
+
     pub fn insert_class_commitment_leaf(
         &self,
         block: BlockNumber,
@@ -410,6 +549,17 @@ impl<'inner> Transaction<'inner> {
         state_update::declared_classes_at(self, block)
     }
 
+    /// Returns up to `limit` hashes of Cairo classes declared after block `from`, up to and
+    /// including block `to`.
+    pub fn cairo_classes_declared_between(
+        &self,
+        from: BlockNumber,
+        to: BlockNumber,
+        limit: usize,
+    ) -> anyhow::Result<Vec<ClassHash>> {
+        state_update::cairo_classes_declared_between(self, from, to, limit)
+    }
+
     pub fn contract_class_hash(
         &self,
         block_id: BlockId,
@@ -418,6 +568,15 @@ impl<'inner> Transaction<'inner> {
         state_update::contract_class_hash(self, block_id, contract_address)
     }
 
+    /// Returns the block at which the contract was deployed, followed by every
+    /// block at which its class hash was replaced, in ascending order.
+    pub fn contract_class_history(
+        &self,
+        contract_address: ContractAddress,
+    ) -> anyhow::Result<Vec<(BlockNumber, ClassHash)>> {
+        state_update::contract_class_history(self, contract_address)
+    }
+
     /// Returns the compiled class hash for a class.
     pub fn casm_hash(&self, class_hash: ClassHash) -> anyhow::Result<Option<CasmHash>> {
         class::casm_hash(self, class_hash)
@@ -438,7 +597,8 @@ impl<'inner> Transaction<'inner> {
         root: ClassCommitment,
         nodes: &HashMap<Felt, Node>,
     ) -> anyhow::Result<u64> {
-        trie::trie_class::insert(self, root.0, nodes)
+        self.trie_store
+            .insert(self, TrieTable::Class, root.0, nodes)
     }
 
     /// Stores a single contract's storage trie information.
@@ -447,7 +607,8 @@ impl<'inner> Transaction<'inner> {
         root: ContractRoot,
         nodes: &HashMap<Felt, Node>,
     ) -> anyhow::Result<u64> {
-        trie::trie_contracts::insert(self, root.0, nodes)
+        self.trie_store
+            .insert(self, TrieTable::Contracts, root.0, nodes)
     }
 
     /// Stores the global starknet storage trie information.
@@ -456,31 +617,32 @@ impl<'inner> Transaction<'inner> {
         root: StorageCommitment,
         nodes: &HashMap<Felt, Node>,
     ) -> anyhow::Result<u64> {
-        trie::trie_storage::insert(self, root.0, nodes)
+        self.trie_store
+            .insert(self, TrieTable::Storage, root.0, nodes)
     }
 
     pub fn class_trie_node(&self, index: u64) -> anyhow::Result<Option<StoredNode>> {
-        trie::trie_class::node(self, index)
+        self.trie_store.node(self, TrieTable::Class, index)
     }
 
     pub fn storage_trie_node(&self, index: u64) -> anyhow::Result<Option<StoredNode>> {
-        trie::trie_storage::node(self, index)
+        self.trie_store.node(self, TrieTable::Storage, index)
     }
 
     pub fn contract_trie_node(&self, index: u64) -> anyhow::Result<Option<StoredNode>> {
-        trie::trie_contracts::node(self, index)
+        self.trie_store.node(self, TrieTable::Contracts, index)
     }
 
     pub fn class_trie_node_hash(&self, index: u64) -> anyhow::Result<Option<Felt>> {
-        trie::trie_class::hash(self, index)
+        self.trie_store.hash(self, TrieTable::Class, index)
     }
 
     pub fn storage_trie_node_hash(&self, index: u64) -> anyhow::Result<Option<Felt>> {
-        trie::trie_storage::hash(self, index)
+        self.trie_store.hash(self, TrieTable::Storage, index)
     }
 
     pub fn contract_trie_node_hash(&self, index: u64) -> anyhow::Result<Option<Felt>> {
-        trie::trie_contracts::hash(self, index)
+        self.trie_store.hash(self, TrieTable::Contracts, index)
     }
 
     pub fn class_root_index(&self, block: BlockNumber) -> anyhow::Result<Option<u64>> {
@@ -507,6 +669,30 @@ impl<'inner> Transaction<'inner> {
         trie::contract_root(self, block, contract)
     }
 
+    /// Drops the trie history before `block`, deleting all trie nodes which are not required to
+    /// represent the state at `block` or later. Returns the number of trie nodes deleted.
+    ///
+    /// Storage proofs and trie based queries for blocks before `block` are no longer possible
+    /// afterwards.
+    pub fn prune_tries(&self, block: BlockNumber) -> anyhow::Result<usize> {
+        trie::prune_tries(self, block)
+    }
+
+    pub fn trie_layout(&self) -> anyhow::Result<TrieLayout> {
+        trie::trie_layout(self)
+    }
+
+    /// Sets the [TrieLayout] of a new database. Fails once trie nodes have been stored.
+    pub fn set_trie_layout(&self, layout: TrieLayout) -> anyhow::Result<()> {
+        trie::set_trie_layout(self, layout)
+    }
+
+    /// Returns the oldest block whose trie state is still available, or [None] if the tries
+    /// have never been [pruned](Self::prune_tries).
+    pub fn oldest_trie_block(&self) -> anyhow::Result<Option<BlockNumber>> {
+        trie::oldest_trie_block(self)
+    }
+
     pub fn insert_class_root(
         &self,
         block_number: BlockNumber,
@@ -548,6 +734,17 @@ impl<'inner> Transaction<'inner> {
         state_update::update_state_update_counts(self, block_number, counts)
     }
 
+    /// Returns the totals of the chain's state as of `block`, or [None] if they are unknown.
+    pub fn state_stats(&self, block: BlockId) -> anyhow::Result<Option<StateStats>> {
+        state_stats::state_stats(self, block)
+    }
+
+    /// Returns the size of each table and index, including those of split databases. This
+    /// reads the whole database.
+    pub fn table_sizes(&self) -> anyhow::Result<Vec<TableSize>> {
+        state_stats::table_sizes(self)
+    }
+
     pub fn state_update(&self, block: BlockId) -> anyhow::Result<Option<StateUpdate>> {
         state_update::state_update(self, block)
     }
@@ -606,10 +803,69 @@ impl<'inner> Transaction<'inner> {
         reorg_counter::increment_reorg_counter(self)
     }
 
-    fn reorg_counter(&self) -> anyhow::Result<ReorgCounter> {
+    pub fn reorg_counter(&self) -> anyhow::Result<ReorgCounter> {
         reorg_counter::reorg_counter(self)
     }
 
+    pub fn insert_reorg(&self, reorg: &Reorg) -> anyhow::Result<()> {
+        reorg_counter::insert_reorg(self, reorg)
+    }
+
+    /// Returns up to `limit` of the most recent reorgs, newest first.
+    pub fn reorg_history(&self, limit: usize) -> anyhow::Result<Vec<Reorg>> {
+        reorg_counter::reorg_history(self, limit)
+    }
+
+    /// Returns the block up to which the given sync stage has completed, if any.
+    pub fn sync_checkpoint(&self, stage: SyncStage) -> anyhow::Result<Option<BlockNumber>> {
+        sync_checkpoint::sync_checkpoint(self, stage)
+    }
+
+    pub fn update_sync_checkpoint(
+        &self,
+        stage: SyncStage,
+        block: BlockNumber,
+    ) -> anyhow::Result<()> {
+        sync_checkpoint::update_sync_checkpoint(self, stage, block)
+    }
+
+    /// Returns the persisted score of each P2P peer, keyed by its peer ID.
+    pub fn peer_scores(&self) -> anyhow::Result<Vec<(String, i64)>> {
+        peer_scores::peer_scores(self)
+    }
+
+    /// Replaces all persisted P2P peer scores.
+    pub fn replace_peer_scores(&self, scores: &[(String, i64)]) -> anyhow::Result<()> {
+        peer_scores::replace_peer_scores(self, scores)
+    }
+
+    /// Returns the persisted P2P peers, including banned ones.
+    pub fn known_peers(&self) -> anyhow::Result<Vec<KnownPeer>> {
+        peers::known_peers(self)
+    }
+
+    /// Replaces the addresses of all persisted P2P peers which are not banned.
+    pub fn replace_peer_addresses(&self, addresses: &[(String, String)]) -> anyhow::Result<()> {
+        peers::replace_peer_addresses(self, addresses)
+    }
+
+    /// Bans a P2P peer across restarts.
+    pub fn ban_peer(&self, peer_id: &str) -> anyhow::Result<()> {
+        peers::ban_peer(self, peer_id)
+    }
+
+    /// Runs a read-only `SELECT` statement, returning at most `max_rows` rows.
+    ///
+    /// The statement is interrupted if it runs for longer than `timeout`.
+    pub fn query(
+        &self,
+        sql: &str,
+        max_rows: usize,
+        timeout: std::time::Duration,
+    ) -> Result<QueryResult, QueryError> {
+        query::query(self, sql, max_rows, timeout)
+    }
+
     pub(self) fn inner(&self) -> &rusqlite::Transaction<'_> {
         &self.transaction
     }
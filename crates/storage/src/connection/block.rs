@@ -1,5 +1,8 @@
 use anyhow::Context;
-use pathfinder_common::{BlockHash, BlockHeader, BlockNumber, GasPrice, StarknetVersion};
+use pathfinder_common::{
+    BlockHash, BlockHeader, BlockNumber, EventCommitment, GasPrice, StarknetVersion,
+    TransactionCommitment,
+};
 
 use crate::{prelude::*, BlockId};
 
@@ -141,24 +144,24 @@ pub(super) fn purge_block(tx: &Transaction<'_>, block: BlockNumber) -> anyhow::R
 
     tx.inner()
         .execute(
-            "DELETE FROM canonical_blocks WHERE number = ?",
+            "DELETE FROM l2_to_l1_messages WHERE block_number = ?",
             params![&block],
         )
-        .context("Deleting block from canonical_blocks table")?;
+        .context("Deleting L2 to L1 messages")?;
 
     tx.inner()
         .execute(
-            "DELETE FROM block_headers WHERE number = ?",
+            "DELETE FROM canonical_blocks WHERE number = ?",
             params![&block],
         )
-        .context("Deleting block from block_headers table")?;
+        .context("Deleting block from canonical_blocks table")?;
 
     tx.inner()
         .execute(
-            "DELETE FROM contract_roots WHERE block_number = ?",
+            "DELETE FROM block_headers WHERE number = ?",
             params![&block],
         )
-        .context("Deleting block from contract_roots table")?;
+        .context("Deleting block from block_headers table")?;
 
     tx.inner()
         .execute(
@@ -174,19 +177,10 @@ pub(super) fn purge_block(tx: &Transaction<'_>, block: BlockNumber) -> anyhow::R
         )
         .context("Deleting block from contract_state_hashes table")?;
 
-    tx.inner()
-        .execute(
-            "DELETE FROM class_roots WHERE block_number = ?",
-            params![&block],
-        )
-        .context("Deleting block from class_roots table")?;
+    super::trie::purge_roots(tx, block).context("Purging trie roots")?;
 
-    tx.inner()
-        .execute(
-            "DELETE FROM storage_roots WHERE block_number = ?",
-            params![&block],
-        )
-        .context("Deleting block from storage_roots table")?;
+    super::sync_checkpoint::rewind_sync_checkpoints(tx, block)
+        .context("Rewinding sync checkpoints")?;
 
     Ok(())
 }
@@ -416,6 +410,76 @@ pub(super) fn first_block_without_transactions(
     }
 }
 
+/// Returns the first block in `from..=to` whose transactions have not been stored yet, because
+/// it precedes the trusted block sync was started with and has not been backfilled.
+///
+/// Backfilling proceeds from the trusted block towards genesis, so the blocks still missing their
+/// transactions all precede the ones which have them. Only the first non-empty block in the range
+/// therefore needs to be checked.
+pub(super) fn first_block_awaiting_backfill(
+    tx: &Transaction<'_>,
+    from: BlockNumber,
+    to: BlockNumber,
+) -> anyhow::Result<Option<BlockNumber>> {
+    let first = tx
+        .inner()
+        .prepare_cached(
+            r"SELECT number, hash FROM block_headers
+            WHERE number >= ? AND number <= ? AND transaction_count > 0
+            ORDER BY number ASC
+            LIMIT 1",
+        )
+        .context("Preparing first non-empty block query")?
+        .query_row(params![&from, &to], |row| {
+            let number = row.get_block_number(0)?;
+            let hash = row.get_block_hash(1)?;
+            Ok((number, hash))
+        })
+        .optional()
+        .context("Querying first non-empty block")?;
+
+    let Some((number, hash)) = first else {
+        return Ok(None);
+    };
+
+    let stored: bool = tx
+        .inner()
+        .prepare_cached("SELECT EXISTS(SELECT 1 FROM starknet_transactions WHERE block_hash = ?)")
+        .context("Preparing transaction existence query")?
+        .query_row(params![&hash], |row| row.get(0))
+        .context("Querying transaction existence")?;
+
+    Ok((!stored).then_some(number))
+}
+
+/// Returns true if any block's transactions have not been backfilled yet.
+pub(super) fn any_block_awaiting_backfill(tx: &Transaction<'_>) -> anyhow::Result<bool> {
+    let Some((latest, _)) = block_id(tx, BlockId::Latest).context("Querying latest block")? else {
+        return Ok(false);
+    };
+
+    first_block_awaiting_backfill(tx, BlockNumber::GENESIS, latest).map(|x| x.is_some())
+}
+
+/// Sets the transaction and event commitments of an existing block header.
+pub(super) fn update_block_commitments(
+    tx: &Transaction<'_>,
+    block: BlockNumber,
+    transaction_commitment: TransactionCommitment,
+    event_commitment: EventCommitment,
+) -> anyhow::Result<()> {
+    let updated = tx
+        .inner()
+        .execute(
+            "UPDATE block_headers SET transaction_commitment = ?, event_commitment = ? WHERE number = ?",
+            params![&transaction_commitment, &event_commitment, &block],
+        )
+        .context("Updating block commitments")?;
+    anyhow::ensure!(updated == 1, "Block header {block} does not exist");
+
+    Ok(())
+}
+
 pub(super) fn first_block_without_receipts(
     tx: &Transaction<'_>,
 ) -> anyhow::Result<Option<BlockNumber>> {
@@ -564,6 +628,90 @@ mod tests {
         assert_eq!(result, None);
     }
 
+    #[test]
+    fn update_block_commitments() {
+        let (mut connection, headers) = setup();
+        let tx = connection.transaction().unwrap();
+
+        let target = &headers[1];
+        tx.update_block_commitments(
+            target.number,
+            transaction_commitment_bytes!(b"updated tx commitment"),
+            event_commitment_bytes!(b"updated event commitment"),
+        )
+        .unwrap();
+
+        let expected = BlockHeader {
+            transaction_commitment: transaction_commitment_bytes!(b"updated tx commitment"),
+            event_commitment: event_commitment_bytes!(b"updated event commitment"),
+            ..target.clone()
+        };
+        for header in &headers {
+            let result = tx.block_header(header.number.into()).unwrap().unwrap();
+            if header.number == target.number {
+                assert_eq!(result, expected);
+            } else {
+                assert_eq!(&result, header);
+            }
+        }
+
+        let past_head = headers.last().unwrap().number + 1;
+        tx.update_block_commitments(
+            past_head,
+            TransactionCommitment::ZERO,
+            EventCommitment::ZERO,
+        )
+        .unwrap_err();
+    }
+
+    #[test]
+    fn first_block_awaiting_backfill() {
+        use pathfinder_common::receipt::Receipt;
+        use pathfinder_common::transaction::{
+            L1HandlerTransaction, Transaction, TransactionVariant,
+        };
+
+        let storage = crate::Storage::in_memory().unwrap();
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+        assert!(!tx.any_block_awaiting_backfill().unwrap());
+
+        // Genesis is empty, block 1 has not been backfilled yet and block 2 has.
+        let genesis = BlockHeader::builder().finalize_with_hash(block_hash_bytes!(b"genesis"));
+        let block1 = genesis
+            .child_builder()
+            .with_transaction_count(1)
+            .finalize_with_hash(block_hash_bytes!(b"block 1"));
+        let block2 = block1
+            .child_builder()
+            .with_transaction_count(1)
+            .finalize_with_hash(block_hash_bytes!(b"block 2"));
+        for header in [&genesis, &block1, &block2] {
+            tx.insert_block_header(header).unwrap();
+        }
+
+        let transaction = Transaction {
+            hash: transaction_hash_bytes!(b"tx 2"),
+            variant: TransactionVariant::L1Handler(L1HandlerTransaction::default()),
+        };
+        tx.insert_transaction_data(
+            block2.hash,
+            block2.number,
+            &[(transaction, Some(Receipt::default()))],
+        )
+        .unwrap();
+
+        let result = tx
+            .first_block_awaiting_backfill(genesis.number, block2.number)
+            .unwrap();
+        assert_eq!(result, Some(block1.number));
+
+        assert!(!tx.block_awaiting_backfill(genesis.number).unwrap());
+        assert!(tx.block_awaiting_backfill(block1.number).unwrap());
+        assert!(!tx.block_awaiting_backfill(block2.number).unwrap());
+        assert!(tx.any_block_awaiting_backfill().unwrap());
+    }
+
     #[test]
     fn purge_block() {
         let (mut connection, headers) = setup();
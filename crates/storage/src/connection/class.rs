@@ -65,6 +65,24 @@ pub(super) fn insert_cairo_class(
     Ok(())
 }
 
+/// Sets the block in which the given classes were declared, unless it is already known.
+pub(super) fn update_class_declarations(
+    transaction: &Transaction<'_>,
+    block_number: BlockNumber,
+    classes: &[ClassHash],
+) -> anyhow::Result<()> {
+    let mut stmt = transaction.inner().prepare_cached(
+        "UPDATE class_definitions SET block_number = ? WHERE hash = ? AND block_number IS NULL",
+    )?;
+
+    for class in classes {
+        stmt.execute(params![&block_number, class])
+            .context("Updating class declaration block")?;
+    }
+
+    Ok(())
+}
+
 /// Returns whether or not the given class definitions exist.
 pub(super) fn classes_exist(
     transaction: &Transaction<'_>,
@@ -481,6 +499,24 @@ mod tests {
         assert_eq!(result, expected);
     }
 
+    #[test]
+    fn class_declarations() {
+        let mut connection = Storage::in_memory().unwrap().connection().unwrap();
+        let tx = connection.transaction().unwrap();
+
+        let (hash, _, _) = setup_class(&tx);
+        let block = BlockNumber::new_or_panic(3);
+
+        update_class_declarations(&tx, block, &[hash]).unwrap();
+        // The first declaration is kept.
+        update_class_declarations(&tx, block + 1, &[hash]).unwrap();
+
+        let (declared_at, _) = class_definition_with_block_number(&tx, hash)
+            .unwrap()
+            .unwrap();
+        assert_eq!(declared_at, Some(block));
+    }
+
     #[test]
     fn insert_cairo() {
         let mut connection = Storage::in_memory().unwrap().connection().unwrap();
@@ -0,0 +1,657 @@
+//! Detection and repair of blocks which were only partially stored, e.g. because pathfinder was
+//! killed part way through storing them.
+//!
+//! A block is stored in a single transaction by the gateway sync, but SQLite only commits such
+//! a transaction atomically per database file. Tables kept in [split](crate::SplitDatabases)
+//! database files, or trie nodes kept in a separate [TrieNodeStore](super::TrieNodeStore), can
+//! therefore lose the last few blocks while the main database keeps their headers. The p2p sync
+//! stores the data of each block in separate stages, and tracks the completed blocks of each
+//! stage with its [sync checkpoint](super::SyncStage).
+//!
+//! Such partial writes only ever affect the most recent blocks of each stage, so the search
+//! proceeds backwards from there and stops at the first complete block.
+//!
+//! The split database files can equally be ahead of the main database, in which case they hold
+//! transactions, event filters and trie nodes of blocks whose headers were never committed. These
+//! are [removed](remove_orphaned_data) so that the blocks can be stored again.
+use std::collections::HashSet;
+
+use anyhow::Context;
+use pathfinder_common::{BlockHash, BlockNumber};
+
+use super::{SqliteTrieStore, SyncStage, TrieNodeStore, TrieTable};
+use crate::prelude::*;
+
+/// The maximum number of blocks which are rolled back by
+/// [Transaction::roll_back_incomplete_blocks]. More incomplete blocks than this are unlikely to
+/// be the result of a crash, and more likely of database files which do not belong together.
+pub const MAX_INCOMPLETE_BLOCKS: u64 = 1000;
+
+/// The data of a block which is stored separately from its header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockData {
+    Transactions,
+    Receipts,
+    /// The class and storage trie roots of the block, and their root nodes.
+    StateTries,
+}
+
+impl BlockData {
+    const ALL: [BlockData; 3] = [
+        BlockData::Transactions,
+        BlockData::Receipts,
+        BlockData::StateTries,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BlockData::Transactions => "transactions",
+            BlockData::Receipts => "receipts",
+            BlockData::StateTries => "state tries",
+        }
+    }
+
+    /// The p2p sync stage which stores the data.
+    fn stage(&self) -> SyncStage {
+        match self {
+            BlockData::Transactions => SyncStage::Transactions,
+            BlockData::Receipts => SyncStage::Receipts,
+            BlockData::StateTries => SyncStage::StateUpdates,
+        }
+    }
+}
+
+/// A block whose header is stored without some of the data that is expected along with it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IncompleteBlock {
+    pub number: BlockNumber,
+    pub missing: BlockData,
+}
+
+/// Returns the lowest of the incomplete blocks at the end of the chain, if any.
+///
+/// Transactions and receipts are not expected for the blocks before `transactions_from`, which
+/// is the checkpoint if sync started from one.
+pub(super) fn first_incomplete_block(
+    tx: &Transaction<'_>,
+    transactions_from: Option<BlockNumber>,
+) -> anyhow::Result<Option<IncompleteBlock>> {
+    let Some((latest, _)) = super::block::block_id(tx, crate::BlockId::Latest)? else {
+        return Ok(None);
+    };
+    // The p2p sync stores headers first, and the other data once its stage catches up.
+    let is_staged = super::sync_checkpoint::sync_checkpoint(tx, SyncStage::Headers)?.is_some();
+
+    let mut first: Option<IncompleteBlock> = None;
+    for data in BlockData::ALL {
+        let last_expected = if is_staged {
+            super::sync_checkpoint::sync_checkpoint(tx, data.stage())?
+        } else {
+            Some(latest)
+        };
+        let mut block = last_expected;
+
+        while let Some(number) = block {
+            let is_expected = data == BlockData::StateTries
+                || transactions_from.map_or(true, |from| number >= from);
+            if !is_expected || !is_missing(tx, data, number)? {
+                break;
+            }
+
+            anyhow::ensure!(
+                latest.get().saturating_sub(number.get()) < MAX_INCOMPLETE_BLOCKS,
+                "More than {MAX_INCOMPLETE_BLOCKS} blocks are missing their {}",
+                data.as_str()
+            );
+
+            if first.map_or(true, |first| number < first.number) {
+                first = Some(IncompleteBlock {
+                    number,
+                    missing: data,
+                });
+            }
+            block = number.parent();
+        }
+    }
+
+    Ok(first)
+}
+
+fn is_missing(tx: &Transaction<'_>, data: BlockData, block: BlockNumber) -> anyhow::Result<bool> {
+    match data {
+        BlockData::Transactions => tx
+            .inner()
+            .prepare_cached(
+                r"SELECT transaction_count != (
+                    SELECT COUNT(*) FROM starknet_transactions WHERE block_hash = block_headers.hash
+                ) FROM block_headers WHERE number = ?",
+            )
+            .context("Preparing statement")?
+            .query_row(params![&block], |row| row.get(0))
+            .context("Counting transactions"),
+        BlockData::Receipts => tx
+            .inner()
+            .prepare_cached(
+                r"SELECT EXISTS(
+                    SELECT 1 FROM starknet_transactions
+                    JOIN canonical_blocks ON block_hash = canonical_blocks.hash
+                    WHERE number = ? AND receipt IS NULL
+                )",
+            )
+            .context("Preparing statement")?
+            .query_row(params![&block], |row| row.get(0))
+            .context("Querying transactions without receipts"),
+        BlockData::StateTries => {
+            for (roots, table) in [
+                ("class_roots", TrieTable::Class),
+                ("storage_roots", TrieTable::Storage),
+            ] {
+                let root: Option<Option<u64>> = tx
+                    .inner()
+                    .prepare_cached(&format!(
+                        "SELECT root_index FROM {roots} WHERE block_number = ?"
+                    ))
+                    .context("Preparing statement")?
+                    .query_row(params![&block], |row| row.get(0))
+                    .optional()
+                    .with_context(|| format!("Querying {roots}"))?;
+
+                let is_stored = match root {
+                    None => false,
+                    // The trie is empty.
+                    Some(None) => true,
+                    Some(Some(index)) => tx
+                        .trie_store
+                        .hash(tx, table, index)
+                        .context("Querying trie root node")?
+                        .is_some(),
+                };
+                if !is_stored {
+                    return Ok(true);
+                }
+            }
+
+            Ok(false)
+        }
+    }
+}
+
+/// Purges the blocks from the lowest incomplete block onwards, so that sync downloads them
+/// again, and returns that block.
+pub(super) fn roll_back_incomplete_blocks(
+    tx: &Transaction<'_>,
+    transactions_from: Option<BlockNumber>,
+) -> anyhow::Result<Option<IncompleteBlock>> {
+    let Some(first) = first_incomplete_block(tx, transactions_from)? else {
+        return Ok(None);
+    };
+    let Some((mut head, _)) = super::block::block_id(tx, crate::BlockId::Latest)? else {
+        return Ok(None);
+    };
+
+    loop {
+        super::block::purge_block(tx, head)
+            .with_context(|| format!("Purging block {head} from database"))?;
+
+        match head.parent() {
+            Some(parent) if head > first.number => head = parent,
+            _ => break,
+        }
+    }
+
+    let l1_l2_head = super::reference::l1_l2_pointer(tx).context("Query L1-L2 head")?;
+    if l1_l2_head.is_some_and(|l1_l2_head| l1_l2_head >= first.number) {
+        super::reference::update_l1_l2_pointer(tx, first.number.parent())
+            .context("Updating L1-L2 head")?;
+    }
+
+    // Data cached for the purged blocks, such as their Bloom filters, is keyed by the reorg
+    // counter, and must not be mistaken for the data of the blocks replacing them.
+    super::reorg_counter::increment_reorg_counter(tx).context("Incrementing reorg counter")?;
+
+    Ok(Some(first))
+}
+
+/// The rows removed by [Transaction::remove_orphaned_data].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct OrphanedData {
+    pub transactions: usize,
+    pub event_filters: usize,
+    pub trie_nodes: usize,
+}
+
+impl OrphanedData {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Removes the transactions, event filters and trie nodes stored for blocks whose headers are not
+/// stored.
+pub(super) fn remove_orphaned_data(tx: &Transaction<'_>) -> anyhow::Result<OrphanedData> {
+    let latest = super::block::block_id(tx, crate::BlockId::Latest)?.map(|(number, _)| number);
+
+    Ok(OrphanedData {
+        transactions: remove_orphaned_transactions(tx)?,
+        event_filters: remove_orphaned_event_filters(tx, latest)?,
+        trie_nodes: remove_orphaned_trie_nodes(tx)?,
+    })
+}
+
+/// Transactions are stored in block order, so the orphaned ones are the most recent rows.
+fn remove_orphaned_transactions(tx: &Transaction<'_>) -> anyhow::Result<usize> {
+    let mut stmt = tx
+        .inner()
+        .prepare(
+            r"SELECT rowid, block_hash, EXISTS(SELECT 1 FROM block_headers WHERE hash = block_hash)
+            FROM starknet_transactions ORDER BY rowid DESC",
+        )
+        .context("Preparing statement")?;
+    let mut rows = stmt.query([]).context("Querying transactions")?;
+
+    let mut orphans = Vec::new();
+    let mut blocks = HashSet::new();
+    while let Some(row) = rows.next().context("Iterating over transactions")? {
+        let has_block: bool = row.get(2)?;
+        if has_block {
+            break;
+        }
+
+        let block: BlockHash = row.get_block_hash(1)?;
+        blocks.insert(block);
+        anyhow::ensure!(
+            blocks.len() as u64 <= MAX_INCOMPLETE_BLOCKS,
+            "More than {MAX_INCOMPLETE_BLOCKS} blocks' transactions are stored without the blocks"
+        );
+        orphans.push(row.get::<_, i64>(0)?);
+    }
+
+    let mut delete = tx
+        .inner()
+        .prepare_cached("DELETE FROM starknet_transactions WHERE rowid = ?")
+        .context("Preparing statement")?;
+    for rowid in &orphans {
+        delete
+            .execute(params![rowid])
+            .context("Deleting transaction")?;
+    }
+
+    Ok(orphans.len())
+}
+
+fn remove_orphaned_event_filters(
+    tx: &Transaction<'_>,
+    latest: Option<BlockNumber>,
+) -> anyhow::Result<usize> {
+    let deleted = match latest {
+        Some(latest) => tx.inner().execute(
+            "DELETE FROM starknet_events_filters WHERE block_number > ?",
+            params![&latest],
+        ),
+        None => tx
+            .inner()
+            .execute("DELETE FROM starknet_events_filters", []),
+    };
+
+    deleted.context("Deleting event filters")
+}
+
+/// Trie nodes are assigned increasing indices, and the root of each insertion has the highest
+/// index of the nodes it inserted. The nodes above the latest root of a trie therefore belong to
+/// blocks which were not stored, and are deleted while releasing the older nodes they reference.
+///
+/// The references which such blocks' roots took on older nodes cannot be told apart, and are
+/// left in place. This only keeps those nodes from being pruned.
+fn remove_orphaned_trie_nodes(tx: &Transaction<'_>) -> anyhow::Result<usize> {
+    let mut deleted = 0;
+    for (roots, nodes, table) in [
+        ("class_roots", "trie_class", TrieTable::Class),
+        ("storage_roots", "trie_storage", TrieTable::Storage),
+        ("contract_roots", "trie_contracts", TrieTable::Contracts),
+    ] {
+        let latest_root: Option<u64> = tx
+            .inner()
+            .query_row(
+                &format!(
+                    r"SELECT MAX(root_index) FROM {roots}
+                    WHERE block_number = (
+                        SELECT MAX(block_number) FROM {roots} WHERE root_index IS NOT NULL
+                    )"
+                ),
+                [],
+                |row| row.get(0),
+            )
+            .with_context(|| format!("Querying latest root of {roots}"))?;
+        let latest_root = latest_root.unwrap_or_default();
+
+        // Each orphaned node is released once, and the ones still referenced by other orphaned
+        // nodes are deleted when those are released.
+        tx.inner()
+            .execute(
+                &format!("UPDATE {nodes} SET ref_count = 1 WHERE idx > ?"),
+                params![&latest_root],
+            )
+            .with_context(|| format!("Updating reference counts of {nodes}"))?;
+        let orphans = tx
+            .inner()
+            .prepare(&format!("SELECT idx FROM {nodes} WHERE idx > ?"))
+            .context("Preparing statement")?
+            .query_map(params![&latest_root], |row| row.get(0))
+            .with_context(|| format!("Querying {nodes}"))?
+            .collect::<Result<Vec<u64>, _>>()
+            .with_context(|| format!("Iterating over {nodes}"))?;
+
+        deleted += SqliteTrieStore
+            .release(tx, table, &orphans)
+            .with_context(|| format!("Releasing orphaned nodes of {nodes}"))?;
+    }
+
+    Ok(deleted)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::receipt::Receipt;
+    use pathfinder_common::transaction::Transaction as StarknetTransaction;
+    use pathfinder_common::{BlockHeader, StorageCommitment, TransactionHash};
+    use pathfinder_crypto::Felt;
+
+    use super::*;
+    use crate::{BlockId, Child, Connection, Node};
+
+    /// Stores `count` complete blocks without transactions and with empty tries.
+    fn setup(count: u64) -> (Connection, Vec<BlockHeader>) {
+        let storage = crate::Storage::in_memory().unwrap();
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+
+        let mut headers = Vec::new();
+        for number in 0..count {
+            let header = BlockHeader::builder()
+                .with_number(BlockNumber::new_or_panic(number))
+                .finalize_with_hash(BlockHash(Felt::from_u64(number + 1)));
+            tx.insert_block_header(&header).unwrap();
+            tx.insert_class_root(header.number, None).unwrap();
+            tx.insert_storage_root(header.number, None).unwrap();
+            headers.push(header);
+        }
+
+        tx.commit().unwrap();
+        (connection, headers)
+    }
+
+    #[test]
+    fn complete() {
+        let (mut connection, _) = setup(3);
+        let tx = connection.transaction().unwrap();
+
+        assert_eq!(first_incomplete_block(&tx, None).unwrap(), None);
+        assert_eq!(roll_back_incomplete_blocks(&tx, None).unwrap(), None);
+        assert!(tx
+            .block_exists(BlockNumber::new_or_panic(2).into())
+            .unwrap());
+    }
+
+    #[test]
+    fn empty() {
+        let storage = crate::Storage::in_memory().unwrap();
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+
+        assert_eq!(first_incomplete_block(&tx, None).unwrap(), None);
+    }
+
+    #[test]
+    fn missing_transactions() {
+        let (mut connection, headers) = setup(3);
+        let tx = connection.transaction().unwrap();
+
+        let header = headers[2]
+            .child_builder()
+            .with_transaction_count(2)
+            .finalize_with_hash(block_hash_bytes!(b"block 3"));
+        tx.insert_block_header(&header).unwrap();
+        tx.insert_class_root(header.number, None).unwrap();
+        tx.insert_storage_root(header.number, None).unwrap();
+
+        let expected = IncompleteBlock {
+            number: header.number,
+            missing: BlockData::Transactions,
+        };
+        assert_eq!(first_incomplete_block(&tx, None).unwrap(), Some(expected));
+        // Transactions before the checkpoint are not stored.
+        assert_eq!(
+            first_incomplete_block(&tx, Some(header.number + 1)).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn missing_trie_nodes() {
+        let (mut connection, headers) = setup(3);
+        let tx = connection.transaction().unwrap();
+
+        let mut head = headers[2].clone();
+        for i in 0..2 {
+            head = head
+                .child_builder()
+                .finalize_with_hash(BlockHash(Felt::from_u64(100 + i)));
+            tx.insert_block_header(&head).unwrap();
+            tx.insert_class_root(head.number, None).unwrap();
+            // The root node was never stored.
+            tx.insert_storage_root(head.number, Some(1234 + i)).unwrap();
+        }
+        tx.update_l1_l2_pointer(Some(head.number)).unwrap();
+
+        let rolled_back = roll_back_incomplete_blocks(&tx, None).unwrap();
+
+        assert_eq!(
+            rolled_back,
+            Some(IncompleteBlock {
+                number: BlockNumber::new_or_panic(3),
+                missing: BlockData::StateTries,
+            })
+        );
+        let latest = tx.block_id(BlockId::Latest).unwrap().unwrap().0;
+        assert_eq!(latest, headers[2].number);
+        assert_eq!(tx.l1_l2_pointer().unwrap(), Some(headers[2].number));
+        assert_eq!(tx.reorg_counter().unwrap().get(), 1);
+        assert_eq!(first_incomplete_block(&tx, None).unwrap(), None);
+    }
+
+    #[test]
+    fn staged_sync_checks_completed_stages_only() {
+        let (mut connection, headers) = setup(3);
+        let tx = connection.transaction().unwrap();
+
+        // Headers are ahead of the other stages.
+        let header = headers[2]
+            .child_builder()
+            .with_transaction_count(1)
+            .finalize_with_hash(block_hash_bytes!(b"block 3"));
+        tx.insert_block_header(&header).unwrap();
+        tx.update_sync_checkpoint(SyncStage::Headers, header.number)
+            .unwrap();
+        for stage in [SyncStage::Transactions, SyncStage::StateUpdates] {
+            tx.update_sync_checkpoint(stage, headers[2].number).unwrap();
+        }
+        assert_eq!(first_incomplete_block(&tx, None).unwrap(), None);
+
+        // The transactions stage claims to have completed the block without its transactions.
+        tx.update_sync_checkpoint(SyncStage::Transactions, header.number)
+            .unwrap();
+        assert_eq!(
+            first_incomplete_block(&tx, None).unwrap(),
+            Some(IncompleteBlock {
+                number: header.number,
+                missing: BlockData::Transactions,
+            })
+        );
+    }
+
+    #[test]
+    fn too_many_incomplete_blocks() {
+        let (mut connection, _) = setup(MAX_INCOMPLETE_BLOCKS + 1);
+        let tx = connection.transaction().unwrap();
+
+        tx.inner().execute("DELETE FROM storage_roots", []).unwrap();
+
+        roll_back_incomplete_blocks(&tx, None).unwrap_err();
+        assert!(tx.block_exists(BlockId::Latest).unwrap());
+    }
+
+    #[test]
+    fn orphaned_transactions() {
+        let (mut connection, headers) = setup(3);
+        let tx = connection.transaction().unwrap();
+
+        let transaction = |hash| {
+            let transaction = StarknetTransaction {
+                hash,
+                ..Default::default()
+            };
+            let receipt = Receipt {
+                transaction_hash: hash,
+                ..Default::default()
+            };
+            (transaction, Some(receipt))
+        };
+        tx.insert_transaction_data(
+            headers[2].hash,
+            headers[2].number,
+            &[transaction(transaction_hash_bytes!(b"stored"))],
+        )
+        .unwrap();
+        // The header of block 3 was never committed.
+        tx.insert_transaction_data(
+            block_hash_bytes!(b"block 3"),
+            headers[2].number + 1,
+            &[
+                transaction(transaction_hash_bytes!(b"orphan 0")),
+                transaction(transaction_hash_bytes!(b"orphan 1")),
+            ],
+        )
+        .unwrap();
+
+        let removed = remove_orphaned_data(&tx).unwrap();
+
+        assert_eq!(
+            removed,
+            OrphanedData {
+                transactions: 2,
+                event_filters: 1,
+                trie_nodes: 0,
+            }
+        );
+        assert!(tx
+            .transaction(transaction_hash_bytes!(b"stored"))
+            .unwrap()
+            .is_some());
+        assert!(tx
+            .transaction(transaction_hash_bytes!(b"orphan 0"))
+            .unwrap()
+            .is_none());
+        assert!(remove_orphaned_data(&tx).unwrap().is_empty());
+    }
+
+    #[test]
+    fn orphaned_trie_nodes() {
+        let (mut connection, headers) = setup(3);
+        let tx = connection.transaction().unwrap();
+
+        let leaf = felt_bytes!(b"leaf");
+        let other_leaf = felt_bytes!(b"other leaf");
+        let root = felt_bytes!(b"root");
+        let mut nodes = HashMap::new();
+        nodes.insert(leaf, Node::LeafBinary);
+        nodes.insert(other_leaf, Node::LeafBinary);
+        nodes.insert(
+            root,
+            Node::Binary {
+                left: Child::Hash(leaf),
+                right: Child::Hash(other_leaf),
+            },
+        );
+        let root_idx = tx
+            .insert_storage_trie(StorageCommitment(root), &nodes)
+            .unwrap();
+        tx.inner()
+            .execute(
+                "DELETE FROM storage_roots WHERE block_number = ?",
+                params![&headers[2].number],
+            )
+            .unwrap();
+        tx.insert_storage_root(headers[2].number, Some(root_idx))
+            .unwrap();
+
+        let leaf_idx: u64 = tx
+            .inner()
+            .query_row(
+                "SELECT idx FROM trie_storage WHERE hash = ?",
+                params![&leaf],
+                |row| row.get(0),
+            )
+            .unwrap();
+        let ref_count = |idx: u64| -> i64 {
+            tx.inner()
+                .query_row(
+                    "SELECT ref_count FROM trie_storage WHERE idx = ?",
+                    params![&idx],
+                    |row| row.get(0),
+                )
+                .unwrap()
+        };
+        let leaf_references = ref_count(leaf_idx);
+
+        // The nodes of block 3 reference the stored leaf, but its root was never committed.
+        let orphan_leaf = felt_bytes!(b"orphan leaf");
+        let orphan_root = felt_bytes!(b"orphan root");
+        let mut nodes = HashMap::new();
+        nodes.insert(orphan_leaf, Node::LeafBinary);
+        nodes.insert(
+            orphan_root,
+            Node::Binary {
+                left: Child::Id(leaf_idx),
+                right: Child::Hash(orphan_leaf),
+            },
+        );
+        let orphan_idx = tx
+            .insert_storage_trie(StorageCommitment(orphan_root), &nodes)
+            .unwrap();
+        assert_eq!(ref_count(leaf_idx), leaf_references + 1);
+
+        let removed = remove_orphaned_data(&tx).unwrap();
+
+        assert_eq!(removed.trie_nodes, 2);
+        assert!(tx.storage_trie_node(orphan_idx).unwrap().is_none());
+        assert!(tx.storage_trie_node(root_idx).unwrap().is_some());
+        assert_eq!(ref_count(leaf_idx), leaf_references);
+    }
+
+    #[test]
+    fn too_many_orphaned_blocks() {
+        let (mut connection, _) = setup(1);
+        let tx = connection.transaction().unwrap();
+
+        for i in 0..=MAX_INCOMPLETE_BLOCKS {
+            let hash = TransactionHash(Felt::from_u64(i));
+            tx.insert_transaction_data(
+                BlockHash(Felt::from_u64(1000 + i)),
+                BlockNumber::new_or_panic(i + 1),
+                &[(
+                    StarknetTransaction {
+                        hash,
+                        ..Default::default()
+                    },
+                    None,
+                )],
+            )
+            .unwrap();
+        }
+
+        remove_orphaned_data(&tx).unwrap_err();
+    }
+}
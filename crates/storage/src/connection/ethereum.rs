@@ -1,7 +1,10 @@
+use anyhow::Context;
 use pathfinder_common::BlockNumber;
-use pathfinder_ethereum::EthereumStateUpdate;
+use pathfinder_ethereum::{EthereumStateUpdate, L1GasPrices};
 
-use crate::prelude::*;
+use crate::{prelude::*, BlockId};
+
+use super::block::block_id;
 
 pub(super) fn upsert_l1_state(
     tx: &Transaction<'_>,
@@ -75,13 +78,63 @@ pub(super) fn latest_l1_state(tx: &Transaction<'_>) -> anyhow::Result<Option<Eth
         .map_err(|e| e.into())
 }
 
+pub(super) fn insert_l1_gas_prices(
+    tx: &Transaction<'_>,
+    block: BlockNumber,
+    prices: &L1GasPrices,
+) -> anyhow::Result<()> {
+    tx.inner()
+        .execute(
+            r"INSERT OR REPLACE INTO block_l1_gas_prices (
+                block_number,
+                ethereum_block_number,
+                base_fee,
+                blob_base_fee
+            ) VALUES (?, ?, ?, ?)",
+            params![
+                &block,
+                &prices.block_number,
+                &prices.base_fee.to_be_bytes().as_slice(),
+                &prices.blob_base_fee.map(|fee| fee.to_be_bytes().to_vec()),
+            ],
+        )
+        .context("Inserting L1 gas prices")?;
+
+    Ok(())
+}
+
+pub(super) fn l1_gas_prices(
+    tx: &Transaction<'_>,
+    block: BlockId,
+) -> anyhow::Result<Option<L1GasPrices>> {
+    let Some((block_number, _)) = block_id(tx, block).context("Querying block header")? else {
+        return Ok(None);
+    };
+
+    tx.inner()
+        .query_row(
+            r"SELECT ethereum_block_number, base_fee, blob_base_fee FROM block_l1_gas_prices
+            WHERE block_number = ?",
+            params![&block_number],
+            |row| {
+                Ok(L1GasPrices {
+                    block_number: row.get(0)?,
+                    base_fee: row.get_gas_price(1)?,
+                    blob_base_fee: row.get_optional_gas_price(2)?,
+                })
+            },
+        )
+        .optional()
+        .context("Querying L1 gas prices")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     use crate::Storage;
     use pathfinder_common::macro_prelude::*;
-    use pathfinder_common::{BlockHash, StateCommitment};
+    use pathfinder_common::{BlockHash, BlockHeader, GasPrice, StateCommitment};
     use pathfinder_crypto::Felt;
     use pathfinder_ethereum::EthereumStateUpdate;
 
@@ -171,4 +224,43 @@ mod tests {
             .unwrap();
         assert_eq!(result, new_value);
     }
+
+    #[test]
+    fn l1_gas_prices() {
+        let storage = Storage::in_memory().unwrap();
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+
+        let header0 = BlockHeader::builder().finalize_with_hash(block_hash_bytes!(b"block 0"));
+        let header1 = header0
+            .child_builder()
+            .finalize_with_hash(block_hash_bytes!(b"block 1"));
+        tx.insert_block_header(&header0).unwrap();
+        tx.insert_block_header(&header1).unwrap();
+
+        let before_cancun = L1GasPrices {
+            block_number: 100,
+            base_fee: GasPrice(10),
+            blob_base_fee: None,
+        };
+        let after_cancun = L1GasPrices {
+            block_number: 101,
+            base_fee: GasPrice(11),
+            blob_base_fee: Some(GasPrice(1)),
+        };
+        insert_l1_gas_prices(&tx, header0.number, &before_cancun).unwrap();
+        insert_l1_gas_prices(&tx, header1.number, &after_cancun).unwrap();
+
+        let result = l1_gas_prices(&tx, header0.hash.into()).unwrap();
+        assert_eq!(result, Some(before_cancun));
+        let result = l1_gas_prices(&tx, BlockId::Latest).unwrap();
+        assert_eq!(result, Some(after_cancun));
+
+        // Purged blocks take their gas prices with them.
+        tx.purge_block(header1.number).unwrap();
+        let result = l1_gas_prices(&tx, header1.number.into()).unwrap();
+        assert_eq!(result, None);
+        let result = l1_gas_prices(&tx, BlockId::Latest).unwrap();
+        assert_eq!(result, Some(before_cancun));
+    }
 }
@@ -1,5 +1,7 @@
 use std::num::NonZeroUsize;
 
+use anyhow::Context;
+
 use crate::bloom::BloomFilter;
 use crate::{prelude::*, ReorgCounter};
 
@@ -8,8 +10,12 @@ use pathfinder_common::{
     BlockHash, BlockNumber, ContractAddress, EventData, EventKey, TransactionHash,
 };
 
+/// The default maximum page size of event queries. The page size is not limited by
+/// [Transaction::events](crate::Transaction::events) itself.
 pub const PAGE_SIZE_LIMIT: usize = 1_024;
-pub const KEY_FILTER_LIMIT: usize = 16;
+/// The maximum number of keys in an event filter, as only this many are indexed by the event
+/// Bloom filters.
+pub const KEY_FILTER_LIMIT: usize = crate::bloom::EVENT_KEY_FILTER_LIMIT;
 
 #[derive(Debug)]
 pub struct EventFilter {
@@ -18,7 +24,61 @@ pub struct EventFilter {
     pub contract_address: Option<ContractAddress>,
     pub keys: Vec<Vec<EventKey>>,
     pub page_size: usize,
+    /// The number of matching events to skip, counted in the order of the events from the first
+    /// block scanned, which is `to_block` in [EventOrder::Descending] order.
     pub offset: usize,
+    pub order: EventOrder,
+}
+
+/// The order of the events returned by [Transaction::events](crate::Transaction::events).
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum EventOrder {
+    /// Oldest first, ordered by block number, transaction index and event index.
+    #[default]
+    Ascending,
+    /// Newest first, in the reverse of [EventOrder::Ascending] order. Scanning starts at the
+    /// latest block if `to_block` is past it.
+    Descending,
+}
+
+impl EventOrder {
+    /// Returns the block scanned after `block_number`, if there is one.
+    fn next_block(self, block_number: BlockNumber) -> Option<BlockNumber> {
+        match self {
+            EventOrder::Ascending => Some(block_number + 1),
+            EventOrder::Descending => block_number
+                .get()
+                .checked_sub(1)
+                .map(BlockNumber::new_or_panic),
+        }
+    }
+}
+
+impl EventFilter {
+    /// Returns true if the event matches the contract address and keys of the filter, in the same
+    /// way as events are matched by [Transaction::events](crate::Transaction::events). The block range and
+    /// paging are not considered.
+    pub fn matches(&self, event: &Event) -> bool {
+        if let Some(address) = self.contract_address {
+            if event.from_address != address {
+                return false;
+            }
+        }
+
+        if self.keys.iter().all(Vec::is_empty) {
+            return true;
+        }
+
+        if event.keys.len() < self.keys.len() {
+            return false;
+        }
+
+        event
+            .keys
+            .iter()
+            .zip(&self.keys)
+            .all(|(key, filter)| filter.is_empty() || filter.contains(key))
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -35,8 +95,6 @@ pub struct EmittedEvent {
 pub enum EventFilterError {
     #[error(transparent)]
     Internal(#[from] anyhow::Error),
-    #[error("requested page size is too big, supported maximum is {0}")]
-    PageSizeTooBig(usize),
     #[error("requested page size is too small, supported minimum is 1")]
     PageSizeTooSmall,
     #[error("Event query too broad. Reduce the block range or add more keys.")]
@@ -55,32 +113,151 @@ pub struct ContinuationToken {
     pub offset: usize,
 }
 
+/// A page of events, in the [EventOrder] of the filter. The order is deterministic, so the
+/// continuation token picks up exactly where the page ends.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct PageOfEvents {
     pub events: Vec<EmittedEvent>,
     pub continuation_token: Option<ContinuationToken>,
 }
 
-pub(super) fn insert_block_events<'a>(
-    tx: &Transaction<'_>,
-    block_number: BlockNumber,
-    events: impl Iterator<Item = &'a Event>,
-) -> anyhow::Result<()> {
-    let mut stmt = tx
-        .inner()
-        .prepare("INSERT INTO starknet_events_filters (block_number, bloom) VALUES (?, ?)")?;
+/// Summarizes a check of the stored event Bloom filters against the events in
+/// the blocks' receipts.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct BloomFilterReport {
+    /// The number of blocks whose filter was checked.
+    pub checked: usize,
+    /// The number of blocks which have transactions but no stored filter.
+    pub missing: usize,
+    /// The number of blocks whose stored filter does not match their events.
+    pub mismatched: usize,
+}
 
+/// Returns the Bloom filter of the events of a block.
+pub(super) fn block_bloom<'a>(events: impl Iterator<Item = &'a Event>) -> BloomFilter {
     let mut bloom = BloomFilter::new();
     for event in events {
         bloom.set_keys(&event.keys);
         bloom.set_address(&event.from_address);
     }
 
-    stmt.execute(params![&block_number, &bloom.to_compressed_bytes()])?;
+    bloom
+}
+
+/// Inserts the Bloom filter of a block, as returned by [BloomFilter::to_compressed_bytes].
+pub(super) fn insert_block_bloom(
+    tx: &Transaction<'_>,
+    block_number: BlockNumber,
+    compressed_bloom: &[u8],
+) -> anyhow::Result<()> {
+    let mut stmt = tx.inner().prepare_cached(
+        "INSERT INTO starknet_events_filters (block_number, bloom) VALUES (?, ?)",
+    )?;
+
+    stmt.execute(params![&block_number, &compressed_bloom])?;
 
     Ok(())
 }
 
+/// Recomputes the Bloom filters of the blocks in `[from, to]` from their receipts and
+/// compares them with the stored filters. If `repair` is set, missing and mismatched
+/// filters are replaced by the recomputed ones.
+///
+/// Stops at the first block which does not exist. Blocks without transactions are
+/// skipped since they are never given a filter.
+pub(super) fn check_bloom_filters(
+    tx: &Transaction<'_>,
+    from: BlockNumber,
+    to: BlockNumber,
+    repair: bool,
+) -> anyhow::Result<BloomFilterReport> {
+    let reorg_counter = tx.reorg_counter()?;
+
+    let mut select = tx
+        .inner()
+        .prepare("SELECT bloom FROM starknet_events_filters WHERE block_number = ?")
+        .context("Preparing Bloom filter query")?;
+    let mut upsert = tx
+        .inner()
+        .prepare(
+            "INSERT OR REPLACE INTO starknet_events_filters (block_number, bloom) VALUES (?, ?)",
+        )
+        .context("Preparing Bloom filter upsert")?;
+
+    let mut report = BloomFilterReport::default();
+
+    for block_number in from.get()..=to.get() {
+        let block_number = BlockNumber::new_or_panic(block_number);
+
+        let Some(transaction_data) = tx
+            .transaction_data_for_block(block_number.into())
+            .with_context(|| format!("Fetching transaction data for block {block_number}"))?
+        else {
+            break;
+        };
+
+        if transaction_data.is_empty() {
+            continue;
+        }
+
+        let expected = block_bloom(
+            transaction_data
+                .iter()
+                .flat_map(|(_, receipt)| &receipt.events),
+        );
+
+        let stored = select
+            .query_row(params![&block_number], |row| {
+                let bytes: Vec<u8> = row.get(0)?;
+                Ok(BloomFilter::from_compressed_bytes(&bytes))
+            })
+            .optional()
+            .context("Querying Bloom filter")?;
+
+        report.checked += 1;
+        match stored {
+            Some(stored) if stored == expected => continue,
+            Some(_) => report.mismatched += 1,
+            None => report.missing += 1,
+        }
+
+        if repair {
+            upsert
+                .execute(params![&block_number, &expected.to_compressed_bytes()])
+                .context("Replacing Bloom filter")?;
+            tx.bloom_filter_cache
+                .set(reorg_counter, block_number, expected);
+        }
+    }
+
+    Ok(report)
+}
+
+/// Estimates the cost of querying all pages of the filter's events, as the number of Bloom
+/// filters which would be checked. These are the filters of the blocks in the range of the
+/// filter, up to the latest block.
+///
+/// Filters without an address or keys do not use the Bloom filters, so their cost is zero. Every
+/// event matches them, which fills their pages from the first blocks scanned.
+pub(super) fn event_query_cost(tx: &Transaction<'_>, filter: &EventFilter) -> anyhow::Result<u64> {
+    let key_filter_is_empty = filter.keys.iter().flatten().count() == 0;
+    if key_filter_is_empty && filter.contract_address.is_none() {
+        return Ok(0);
+    }
+
+    let Some((latest, _)) = tx
+        .block_id(crate::BlockId::Latest)
+        .context("Querying latest block number")?
+    else {
+        return Ok(0);
+    };
+
+    let from_block = filter.from_block.unwrap_or(BlockNumber::GENESIS);
+    let to_block = filter.to_block.unwrap_or(BlockNumber::MAX).min(latest);
+
+    Ok((to_block.get() + 1).saturating_sub(from_block.get()))
+}
+
 #[tracing::instrument(skip(tx))]
 pub(super) fn get_events(
     tx: &Transaction<'_>,
@@ -88,10 +265,6 @@ pub(super) fn get_events(
     max_blocks_to_scan: NonZeroUsize,
     max_uncached_bloom_filters_to_load: NonZeroUsize,
 ) -> Result<PageOfEvents, EventFilterError> {
-    if filter.page_size > PAGE_SIZE_LIMIT {
-        return Err(EventFilterError::PageSizeTooBig(PAGE_SIZE_LIMIT));
-    }
-
     if filter.page_size < 1 {
         return Err(EventFilterError::PageSizeTooSmall);
     }
@@ -102,10 +275,28 @@ pub(super) fn get_events(
     let to_block = filter.to_block.unwrap_or(BlockNumber::MAX);
     let key_filter_is_empty = filter.keys.iter().flatten().count() == 0;
 
+    let first_block = match filter.order {
+        EventOrder::Ascending => from_block,
+        EventOrder::Descending => {
+            let latest = tx
+                .block_id(crate::BlockId::Latest)
+                .context("Querying latest block number")?;
+            match latest {
+                Some((latest, _)) => to_block.min(latest),
+                None => {
+                    return Ok(PageOfEvents {
+                        events: Vec::new(),
+                        continuation_token: None,
+                    })
+                }
+            }
+        }
+    };
+
     let mut emitted_events = Vec::new();
     let mut bloom_filters_loaded: usize = 0;
     let mut blocks_scanned: usize = 0;
-    let mut block_number = from_block;
+    let mut next_block = Some(first_block);
     let mut offset = filter.offset;
 
     enum ScanResult {
@@ -116,9 +307,10 @@ pub(super) fn get_events(
 
     let result = loop {
         // Stop if we're past the last block.
-        if block_number > to_block {
+        let Some(block_number) = next_block.filter(|n| (from_block..=to_block).contains(n)) else {
             break ScanResult::Done;
-        }
+        };
+        next_block = filter.order.next_block(block_number);
 
         // Check bloom filter
         if !key_filter_is_empty || filter.contract_address.is_some() {
@@ -128,7 +320,6 @@ pub(super) fn get_events(
                 Filter::Cached(bloom) => {
                     if !bloom.check_filter(filter) {
                         tracing::trace!("Bloom filter did not match");
-                        block_number += 1;
                         continue;
                     }
                 }
@@ -136,7 +327,6 @@ pub(super) fn get_events(
                     bloom_filters_loaded += 1;
                     if !bloom.check_filter(filter) {
                         tracing::trace!("Bloom filter did not match");
-                        block_number += 1;
                         continue;
                     }
                 }
@@ -169,12 +359,10 @@ pub(super) fn get_events(
             break ScanResult::PageFull;
         }
 
-        block_number += 1;
-
         // Check if we've reached our Bloom filter load limit
         if bloom_filters_loaded >= max_uncached_bloom_filters_to_load.get() {
             tracing::trace!("Bloom filter limit reached");
-            break ScanResult::ContinueFrom(block_number);
+            break next_block.map_or(ScanResult::Done, ScanResult::ContinueFrom);
         }
     };
 
@@ -190,7 +378,7 @@ pub(super) fn get_events(
             let continuation_token = continuation_token(
                 &emitted_events,
                 ContinuationToken {
-                    block_number: from_block,
+                    block_number: first_block,
                     offset: filter.offset,
                 },
             )
@@ -243,10 +431,17 @@ fn scan_block_into(
     };
 
     let receipts = tx.receipts_for_block(block_header.hash.into())?;
-    let Some(receipts) = receipts else {
+    let Some(mut receipts) = receipts else {
         return Ok(BlockScanResult::NoSuchBlock);
     };
 
+    if filter.order == EventOrder::Descending {
+        receipts.reverse();
+        for receipt in &mut receipts {
+            receipt.events.reverse();
+        }
+    }
+
     let keys: Vec<std::collections::HashSet<_>> = filter
         .keys
         .iter()
@@ -405,6 +600,7 @@ mod tests {
             keys: vec![vec![], vec![event_key!("0xdeadbeef")]],
             page_size: test_utils::NUM_EVENTS,
             offset: 0,
+            order: EventOrder::Ascending,
         };
 
         let events = get_events(
@@ -423,6 +619,144 @@ mod tests {
         );
     }
 
+    #[test]
+    fn filter_matches() {
+        let event = Event {
+            data: vec![],
+            from_address: contract_address!("0x1"),
+            keys: vec![event_key!("0xa"), event_key!("0xb")],
+        };
+        let filter = |contract_address, keys| EventFilter {
+            from_block: None,
+            to_block: None,
+            contract_address,
+            keys,
+            page_size: 1,
+            offset: 0,
+            order: EventOrder::Ascending,
+        };
+
+        assert!(filter(None, vec![]).matches(&event));
+        assert!(filter(None, vec![vec![], vec![]]).matches(&event));
+        assert!(filter(Some(contract_address!("0x1")), vec![]).matches(&event));
+        assert!(filter(None, vec![vec![], vec![event_key!("0xb")]]).matches(&event));
+        assert!(filter(
+            Some(contract_address!("0x1")),
+            vec![vec![event_key!("0xa"), event_key!("0xc")]]
+        )
+        .matches(&event));
+
+        assert!(!filter(Some(contract_address!("0x2")), vec![]).matches(&event));
+        assert!(!filter(None, vec![vec![event_key!("0xb")]]).matches(&event));
+        assert!(!filter(None, vec![vec![], vec![], vec![event_key!("0xc")]]).matches(&event));
+    }
+
+    #[test]
+    fn query_cost() {
+        let (storage, _) = test_utils::setup_test_storage();
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+
+        let filter = |from_block, to_block, contract_address| EventFilter {
+            from_block,
+            to_block,
+            contract_address,
+            keys: vec![],
+            page_size: 1,
+            offset: 0,
+            order: EventOrder::Ascending,
+        };
+        let address = Some(contract_address!("0x1"));
+
+        // Blocks past the latest one are not counted.
+        let cost = event_query_cost(&tx, &filter(None, None, address)).unwrap();
+        assert_eq!(cost, test_utils::NUM_BLOCKS as u64);
+
+        let cost = event_query_cost(
+            &tx,
+            &filter(Some(BlockNumber::new_or_panic(1)), None, address),
+        )
+        .unwrap();
+        assert_eq!(cost, test_utils::NUM_BLOCKS as u64 - 1);
+
+        let cost = event_query_cost(
+            &tx,
+            &filter(
+                Some(BlockNumber::new_or_panic(1)),
+                Some(BlockNumber::new_or_panic(2)),
+                address,
+            ),
+        )
+        .unwrap();
+        assert_eq!(cost, 2);
+
+        let cost = event_query_cost(
+            &tx,
+            &filter(Some(BlockNumber::MAX), Some(BlockNumber::MAX), address),
+        )
+        .unwrap();
+        assert_eq!(cost, 0);
+
+        // Bloom filters are not used without an address or keys.
+        let cost = event_query_cost(&tx, &filter(None, None, None)).unwrap();
+        assert_eq!(cost, 0);
+    }
+
+    #[test]
+    fn check_bloom_filters() {
+        let (storage, test_data) = test_utils::setup_test_storage();
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+
+        let first = test_data.headers.first().unwrap().number;
+        let last = test_data.headers.last().unwrap().number;
+
+        let report = super::check_bloom_filters(&tx, first, last, false).unwrap();
+        assert_eq!(
+            report,
+            BloomFilterReport {
+                checked: test_utils::NUM_BLOCKS,
+                missing: 0,
+                mismatched: 0,
+            }
+        );
+
+        // Corrupt one filter and remove another.
+        tx.inner()
+            .execute(
+                "UPDATE starknet_events_filters SET bloom = ? WHERE block_number = ?",
+                params![&BloomFilter::new().to_compressed_bytes(), &first],
+            )
+            .unwrap();
+        tx.inner()
+            .execute(
+                "DELETE FROM starknet_events_filters WHERE block_number = ?",
+                params![&last],
+            )
+            .unwrap();
+
+        let expected = BloomFilterReport {
+            checked: test_utils::NUM_BLOCKS,
+            missing: 1,
+            mismatched: 1,
+        };
+        // Verifying does not modify the filters.
+        let report = super::check_bloom_filters(&tx, first, last, false).unwrap();
+        assert_eq!(report, expected);
+        let report = super::check_bloom_filters(&tx, first, last, true).unwrap();
+        assert_eq!(report, expected);
+
+        let report = super::check_bloom_filters(&tx, first, BlockNumber::MAX, false).unwrap();
+        assert_eq!(
+            report,
+            BloomFilterReport {
+                checked: test_utils::NUM_BLOCKS,
+                missing: 0,
+                mismatched: 0,
+            }
+        );
+    }
+
     #[test]
     fn events_are_ordered() {
         // This is a regression test where events were incorrectly ordered by transaction hash
@@ -513,6 +847,7 @@ mod tests {
                 keys: vec![],
                 page_size: 1024,
                 offset: 0,
+                order: EventOrder::Ascending,
             },
             *MAX_BLOCKS_TO_SCAN,
             *MAX_BLOOM_FILTERS_TO_LOAD,
@@ -529,6 +864,29 @@ mod tests {
             .collect::<Vec<_>>();
 
         assert_eq!(addresses, expected);
+
+        // Descending order is the exact reverse.
+        let addresses = get_events(
+            &tx,
+            &EventFilter {
+                from_block: None,
+                to_block: None,
+                contract_address: None,
+                keys: vec![],
+                page_size: 1024,
+                offset: 0,
+                order: EventOrder::Descending,
+            },
+            *MAX_BLOCKS_TO_SCAN,
+            *MAX_BLOOM_FILTERS_TO_LOAD,
+        )
+        .unwrap()
+        .events
+        .iter()
+        .map(|e| e.from_address)
+        .collect::<Vec<_>>();
+
+        assert_eq!(addresses, expected.into_iter().rev().collect::<Vec<_>>());
     }
 
     #[test]
@@ -546,6 +904,7 @@ mod tests {
             keys: vec![],
             page_size: test_utils::NUM_EVENTS,
             offset: 0,
+            order: EventOrder::Ascending,
         };
 
         let expected_events = &emitted_events[test_utils::EVENTS_PER_BLOCK * BLOCK_NUMBER
@@ -581,6 +940,7 @@ mod tests {
             keys: vec![],
             page_size: test_utils::NUM_EVENTS,
             offset: 0,
+            order: EventOrder::Ascending,
         };
 
         let expected_events =
@@ -615,6 +975,7 @@ mod tests {
             keys: vec![],
             page_size: test_utils::EVENTS_PER_BLOCK + 1,
             offset: 0,
+            order: EventOrder::Ascending,
         };
 
         let expected_events = &emitted_events[..test_utils::EVENTS_PER_BLOCK + 1];
@@ -644,6 +1005,7 @@ mod tests {
             keys: vec![],
             page_size: test_utils::EVENTS_PER_BLOCK + 1,
             offset: events.continuation_token.unwrap().offset,
+            order: EventOrder::Ascending,
         };
 
         let expected_events =
@@ -664,6 +1026,48 @@ mod tests {
         );
     }
 
+    #[test]
+    fn get_events_descending_with_paging() {
+        let (storage, test_data) = test_utils::setup_test_storage();
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+
+        // Pages end both within blocks and at the block scan limit.
+        let max_blocks_to_scan = NonZeroUsize::new(2).unwrap();
+        let mut filter = EventFilter {
+            from_block: Some(BlockNumber::new_or_panic(1)),
+            to_block: None,
+            contract_address: None,
+            keys: vec![],
+            page_size: test_utils::EVENTS_PER_BLOCK + 1,
+            offset: 0,
+            order: EventOrder::Descending,
+        };
+
+        let mut events = Vec::new();
+        let mut pages = 0;
+        loop {
+            let page =
+                get_events(&tx, &filter, max_blocks_to_scan, *MAX_BLOOM_FILTERS_TO_LOAD).unwrap();
+            events.extend(page.events);
+            pages += 1;
+
+            let Some(token) = page.continuation_token else {
+                break;
+            };
+            filter.to_block = Some(token.block_number);
+            filter.offset = token.offset;
+        }
+
+        let expected = test_data.events[test_utils::EVENTS_PER_BLOCK..]
+            .iter()
+            .rev()
+            .cloned()
+            .collect::<Vec<_>>();
+        assert_eq!(events, expected);
+        assert!(pages > 1);
+    }
+
     #[test]
     fn get_events_from_block_onwards() {
         let (storage, test_data) = test_utils::setup_test_storage();
@@ -679,6 +1083,7 @@ mod tests {
             keys: vec![],
             page_size: test_utils::NUM_EVENTS,
             offset: 0,
+            order: EventOrder::Ascending,
         };
 
         let expected_events = &emitted_events[test_utils::EVENTS_PER_BLOCK * FROM_BLOCK_NUMBER..];
@@ -714,6 +1119,7 @@ mod tests {
             keys: vec![],
             page_size: test_utils::NUM_EVENTS,
             offset: 0,
+            order: EventOrder::Ascending,
         };
 
         let events = get_events(
@@ -747,6 +1153,7 @@ mod tests {
             keys: vec![vec![expected_event.keys[0]], vec![expected_event.keys[1]]],
             page_size: test_utils::NUM_EVENTS,
             offset: 0,
+            order: EventOrder::Ascending,
         };
 
         let events = get_events(
@@ -799,6 +1206,7 @@ mod tests {
             keys: vec![],
             page_size: test_utils::NUM_EVENTS,
             offset: 0,
+            order: EventOrder::Ascending,
         };
 
         let events = get_events(
@@ -831,6 +1239,7 @@ mod tests {
             keys: vec![],
             page_size: 10,
             offset: 0,
+            order: EventOrder::Ascending,
         };
         let events = get_events(
             &tx,
@@ -857,6 +1266,7 @@ mod tests {
             keys: vec![],
             page_size: 10,
             offset: 10,
+            order: EventOrder::Ascending,
         };
         let events = get_events(
             &tx,
@@ -883,6 +1293,7 @@ mod tests {
             keys: vec![],
             page_size: 10,
             offset: 30,
+            order: EventOrder::Ascending,
         };
         let events = get_events(
             &tx,
@@ -915,6 +1326,7 @@ mod tests {
             page_size: PAGE_SIZE,
             // _after_ the last one
             offset: test_utils::NUM_BLOCKS * test_utils::EVENTS_PER_BLOCK,
+            order: EventOrder::Ascending,
         };
         let events = get_events(
             &tx,
@@ -945,6 +1357,7 @@ mod tests {
             keys: vec![],
             page_size: 0,
             offset: 0,
+            order: EventOrder::Ascending,
         };
         let result = get_events(
             &tx,
@@ -955,6 +1368,7 @@ mod tests {
         assert!(result.is_err());
         assert_matches!(result.unwrap_err(), EventFilterError::PageSizeTooSmall);
 
+        // The maximum page size is up to the caller.
         let filter = EventFilter {
             from_block: None,
             to_block: None,
@@ -962,6 +1376,7 @@ mod tests {
             keys: vec![],
             page_size: PAGE_SIZE_LIMIT + 1,
             offset: 0,
+            order: EventOrder::Ascending,
         };
         let result = get_events(
             &tx,
@@ -969,11 +1384,7 @@ mod tests {
             *MAX_BLOCKS_TO_SCAN,
             *MAX_BLOOM_FILTERS_TO_LOAD,
         );
-        assert!(result.is_err());
-        assert_matches!(
-            result.unwrap_err(),
-            EventFilterError::PageSizeTooBig(PAGE_SIZE_LIMIT)
-        );
+        assert!(result.is_ok());
     }
 
     #[test]
@@ -996,6 +1407,7 @@ mod tests {
             keys: keys_for_expected_events.clone(),
             page_size: 2,
             offset: 0,
+            order: EventOrder::Ascending,
         };
         let events = get_events(
             &tx,
@@ -1023,6 +1435,7 @@ mod tests {
             keys: keys_for_expected_events.clone(),
             page_size: 2,
             offset: 2,
+            order: EventOrder::Ascending,
         };
         let events = get_events(
             &tx,
@@ -1050,6 +1463,7 @@ mod tests {
             keys: keys_for_expected_events.clone(),
             page_size: 2,
             offset: 2,
+            order: EventOrder::Ascending,
         };
         let events = get_events(
             &tx,
@@ -1077,6 +1491,7 @@ mod tests {
             keys: keys_for_expected_events.clone(),
             page_size: 2,
             offset: 4,
+            order: EventOrder::Ascending,
         };
         let events = get_events(
             &tx,
@@ -1101,6 +1516,7 @@ mod tests {
             keys: keys_for_expected_events,
             page_size: 2,
             offset: 1,
+            order: EventOrder::Ascending,
         };
         let events = get_events(
             &tx,
@@ -1132,6 +1548,7 @@ mod tests {
             keys: vec![],
             page_size: 20,
             offset: 0,
+            order: EventOrder::Ascending,
         };
         let events = get_events(
             &tx,
@@ -1158,6 +1575,7 @@ mod tests {
             keys: vec![],
             page_size: 20,
             offset: 0,
+            order: EventOrder::Ascending,
         };
         let events = get_events(
             &tx,
@@ -1192,6 +1610,7 @@ mod tests {
             keys: vec![vec![], vec![emitted_events[0].keys[1]]],
             page_size: emitted_events.len(),
             offset: 0,
+            order: EventOrder::Ascending,
         };
         let events = get_events(&tx, &filter, *MAX_BLOCKS_TO_SCAN, 1.try_into().unwrap()).unwrap();
         assert_eq!(
@@ -1212,6 +1631,7 @@ mod tests {
             keys: vec![vec![], vec![emitted_events[0].keys[1]]],
             page_size: emitted_events.len(),
             offset: 0,
+            order: EventOrder::Ascending,
         };
         let events = get_events(&tx, &filter, *MAX_BLOCKS_TO_SCAN, 1.try_into().unwrap()).unwrap();
         assert_eq!(
@@ -0,0 +1,62 @@
+use anyhow::Context;
+
+use crate::prelude::*;
+
+pub(super) fn peer_scores(tx: &Transaction<'_>) -> anyhow::Result<Vec<(String, i64)>> {
+    let mut stmt = tx
+        .inner()
+        .prepare("SELECT peer_id, score FROM p2p_peer_scores")
+        .context("Preparing statement")?;
+
+    let scores = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+        .context("Querying peer scores")?
+        .collect::<Result<Vec<_>, _>>()
+        .context("Iterating over peer scores")?;
+
+    Ok(scores)
+}
+
+pub(super) fn replace_peer_scores(
+    tx: &Transaction<'_>,
+    scores: &[(String, i64)],
+) -> anyhow::Result<()> {
+    tx.inner()
+        .execute("DELETE FROM p2p_peer_scores", [])
+        .context("Deleting peer scores")?;
+
+    let mut stmt = tx
+        .inner()
+        .prepare("INSERT INTO p2p_peer_scores (peer_id, score) VALUES (?, ?)")
+        .context("Preparing statement")?;
+
+    for (peer_id, score) in scores {
+        stmt.execute(params![peer_id, score])
+            .context("Inserting peer score")?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Storage;
+
+    use super::*;
+
+    #[test]
+    fn replace() {
+        let storage = Storage::in_memory().unwrap();
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+
+        assert!(peer_scores(&tx).unwrap().is_empty());
+
+        replace_peer_scores(&tx, &[("a".to_owned(), 5), ("b".to_owned(), -10)]).unwrap();
+        replace_peer_scores(&tx, &[("b".to_owned(), -20), ("c".to_owned(), 1)]).unwrap();
+
+        let mut result = peer_scores(&tx).unwrap();
+        result.sort();
+        assert_eq!(result, vec![("b".to_owned(), -20), ("c".to_owned(), 1)]);
+    }
+}
@@ -0,0 +1,121 @@
+use anyhow::Context;
+
+use crate::prelude::*;
+
+/// A P2P peer which was connected to this node in the past, or was banned by its operator.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct KnownPeer {
+    pub peer_id: String,
+    /// The address the peer was dialed on, if any.
+    pub address: Option<String>,
+    pub banned: bool,
+}
+
+pub(super) fn known_peers(tx: &Transaction<'_>) -> anyhow::Result<Vec<KnownPeer>> {
+    let mut stmt = tx
+        .inner()
+        .prepare("SELECT peer_id, address, banned FROM p2p_peers")
+        .context("Preparing statement")?;
+
+    let peers = stmt
+        .query_map([], |row| {
+            Ok(KnownPeer {
+                peer_id: row.get(0)?,
+                address: row.get(1)?,
+                banned: row.get(2)?,
+            })
+        })
+        .context("Querying known peers")?
+        .collect::<Result<Vec<_>, _>>()
+        .context("Iterating over known peers")?;
+
+    Ok(peers)
+}
+
+pub(super) fn replace_peer_addresses(
+    tx: &Transaction<'_>,
+    addresses: &[(String, String)],
+) -> anyhow::Result<()> {
+    tx.inner()
+        .execute("DELETE FROM p2p_peers WHERE NOT banned", [])
+        .context("Deleting peer addresses")?;
+
+    let mut stmt = tx
+        .inner()
+        .prepare(
+            "INSERT INTO p2p_peers (peer_id, address) VALUES (?, ?)
+            ON CONFLICT(peer_id) DO UPDATE SET address = excluded.address",
+        )
+        .context("Preparing statement")?;
+
+    for (peer_id, address) in addresses {
+        stmt.execute(params![peer_id, address])
+            .context("Inserting peer address")?;
+    }
+
+    Ok(())
+}
+
+pub(super) fn ban_peer(tx: &Transaction<'_>, peer_id: &str) -> anyhow::Result<()> {
+    tx.inner()
+        .execute(
+            "INSERT INTO p2p_peers (peer_id, banned) VALUES (?, 1)
+            ON CONFLICT(peer_id) DO UPDATE SET banned = 1",
+            [peer_id],
+        )
+        .context("Banning peer")?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Storage;
+
+    use super::*;
+
+    #[test]
+    fn bans_survive_address_replacement() {
+        let storage = Storage::in_memory().unwrap();
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+
+        assert!(known_peers(&tx).unwrap().is_empty());
+
+        replace_peer_addresses(
+            &tx,
+            &[
+                ("a".to_owned(), "/ip4/127.0.0.1/tcp/1".to_owned()),
+                ("b".to_owned(), "/ip4/127.0.0.1/tcp/2".to_owned()),
+            ],
+        )
+        .unwrap();
+        ban_peer(&tx, "b").unwrap();
+        ban_peer(&tx, "c").unwrap();
+        replace_peer_addresses(&tx, &[("d".to_owned(), "/ip4/127.0.0.1/tcp/4".to_owned())])
+            .unwrap();
+
+        let mut result = known_peers(&tx).unwrap();
+        result.sort();
+        assert_eq!(
+            result,
+            vec![
+                KnownPeer {
+                    peer_id: "b".to_owned(),
+                    address: Some("/ip4/127.0.0.1/tcp/2".to_owned()),
+                    banned: true,
+                },
+                KnownPeer {
+                    peer_id: "c".to_owned(),
+                    address: None,
+                    banned: true,
+                },
+                KnownPeer {
+                    peer_id: "d".to_owned(),
+                    address: Some("/ip4/127.0.0.1/tcp/4".to_owned()),
+                    banned: false,
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,330 @@
+use std::sync::mpsc::{channel, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use rusqlite::hooks::{AuthAction, AuthContext, Authorization};
+use rusqlite::types::ValueRef;
+
+use crate::prelude::*;
+
+/// The result of a [query](Transaction::query).
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize)]
+pub struct QueryResult {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<serde_json::Value>>,
+    /// Set if the query returned more rows than were requested.
+    pub truncated: bool,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum QueryError {
+    #[error(transparent)]
+    Internal(#[from] anyhow::Error),
+    #[error("Invalid query: {0}")]
+    Invalid(String),
+    #[error("Only read-only SELECT statements are allowed")]
+    NotReadOnly,
+    #[error("Table {0} may not be queried")]
+    TableNotAllowed(String),
+    #[error("Query timed out")]
+    Timeout,
+}
+
+/// The tables which may be queried. These hold chain data only, excluding the tries, which are
+/// large and internal, as well as the peers and sync progress of the node.
+const ALLOWED_TABLES: &[&str] = &[
+    "block_headers",
+    "block_l1_gas_prices",
+    "block_signatures",
+    "canonical_blocks",
+    "casm_definitions",
+    "class_commitment_leaves",
+    "class_definitions",
+    "class_roots",
+    "contract_roots",
+    "contract_state_hashes",
+    "contract_updates",
+    "l1_state",
+    "l2_to_l1_messages",
+    "nonce_updates",
+    "reorg_history",
+    "starknet_transactions",
+    "starknet_versions",
+    "state_stats",
+    "storage_roots",
+    "storage_updates",
+];
+
+/// The reason the authorizer rejected a statement.
+enum Denied {
+    Table(String),
+    Action,
+}
+
+pub(super) fn query(
+    tx: &Transaction<'_>,
+    sql: &str,
+    max_rows: usize,
+    timeout: Duration,
+) -> Result<QueryResult, QueryError> {
+    let keyword = sql.split_whitespace().next().unwrap_or_default();
+    if !["SELECT", "WITH"]
+        .iter()
+        .any(|allowed| keyword.eq_ignore_ascii_case(allowed))
+    {
+        return Err(QueryError::NotReadOnly);
+    }
+
+    // SQLite consults the authorizer while compiling the statement, for every table it reads,
+    // including those of attached databases. The connection is pooled, so the authorizer is
+    // removed again afterwards.
+    let denied = Arc::new(Mutex::new(None));
+    tx.inner().authorizer(Some(authorizer(denied.clone())));
+    let result = run(tx, sql, max_rows, timeout);
+    tx.inner()
+        .authorizer(None::<fn(AuthContext<'_>) -> Authorization>);
+
+    let denied = denied.lock().unwrap().take();
+    match (result, denied) {
+        (Err(_), Some(Denied::Table(table))) => Err(QueryError::TableNotAllowed(table)),
+        (Err(_), Some(Denied::Action)) => Err(QueryError::NotReadOnly),
+        (result, _) => result,
+    }
+}
+
+/// Allows reading the [ALLOWED_TABLES], and nothing but reading. The first rejection is
+/// recorded in `denied`.
+fn authorizer(
+    denied: Arc<Mutex<Option<Denied>>>,
+) -> impl for<'r> FnMut(AuthContext<'r>) -> Authorization + Send + std::panic::RefUnwindSafe + 'static
+{
+    move |context| {
+        let rejection = match context.action {
+            AuthAction::Select | AuthAction::Function { .. } | AuthAction::Recursive => None,
+            AuthAction::Read { table_name, .. } if ALLOWED_TABLES.contains(&table_name) => None,
+            AuthAction::Read { table_name, .. } => Some(Denied::Table(table_name.to_owned())),
+            _ => Some(Denied::Action),
+        };
+
+        match rejection {
+            None => Authorization::Allow,
+            Some(rejection) => {
+                denied.lock().unwrap().get_or_insert(rejection);
+                Authorization::Deny
+            }
+        }
+    }
+}
+
+fn run(
+    tx: &Transaction<'_>,
+    sql: &str,
+    max_rows: usize,
+    timeout: Duration,
+) -> Result<QueryResult, QueryError> {
+    let mut stmt = tx
+        .inner()
+        .prepare(sql)
+        .map_err(|e| QueryError::Invalid(e.to_string()))?;
+    // The keyword check alone does not exclude e.g. a CTE followed by a DELETE.
+    if !stmt.readonly() {
+        return Err(QueryError::NotReadOnly);
+    }
+
+    let columns = stmt
+        .column_names()
+        .into_iter()
+        .map(ToOwned::to_owned)
+        .collect();
+
+    // SQLite has no statement timeout, so the query is interrupted from a watchdog thread
+    // instead. Dropping `done` stops the watchdog once the query has completed.
+    let interrupt = tx.inner().get_interrupt_handle();
+    let (done, watchdog) = channel::<()>();
+    let watchdog = std::thread::spawn(move || match watchdog.recv_timeout(timeout) {
+        Err(RecvTimeoutError::Timeout) => {
+            interrupt.interrupt();
+            true
+        }
+        _ => false,
+    });
+
+    let result = read_rows(&mut stmt, max_rows);
+    drop(done);
+    let timed_out = watchdog.join().unwrap_or_default();
+
+    match result {
+        Ok((rows, truncated)) => Ok(QueryResult {
+            columns,
+            rows,
+            truncated,
+        }),
+        Err(_) if timed_out => Err(QueryError::Timeout),
+        Err(e) => Err(QueryError::Invalid(e.to_string())),
+    }
+}
+
+fn read_rows(
+    stmt: &mut rusqlite::Statement<'_>,
+    max_rows: usize,
+) -> rusqlite::Result<(Vec<Vec<serde_json::Value>>, bool)> {
+    let column_count = stmt.column_count();
+    let mut rows = stmt.query([])?;
+
+    let mut result = Vec::new();
+    while let Some(row) = rows.next()? {
+        if result.len() == max_rows {
+            return Ok((result, true));
+        }
+
+        let row = (0..column_count)
+            .map(|i| row.get_ref(i).map(to_json))
+            .collect::<rusqlite::Result<_>>()?;
+        result.push(row);
+    }
+
+    Ok((result, false))
+}
+
+/// Blobs, which are mostly felts and hashes, are returned as hex strings.
+fn to_json(value: ValueRef<'_>) -> serde_json::Value {
+    match value {
+        ValueRef::Null => serde_json::Value::Null,
+        ValueRef::Integer(i) => i.into(),
+        ValueRef::Real(f) => f.into(),
+        ValueRef::Text(text) => String::from_utf8_lossy(text).into(),
+        ValueRef::Blob(blob) => format!("0x{}", hex::encode(blob)).into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::{BlockHeader, BlockNumber};
+
+    use crate::Storage;
+
+    use super::*;
+
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    #[test]
+    fn select() {
+        let storage = Storage::in_memory().unwrap();
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+
+        let genesis = BlockHeader::builder()
+            .with_number(BlockNumber::GENESIS)
+            .finalize_with_hash(block_hash_bytes!(b"genesis"));
+        let block1 = genesis
+            .child_builder()
+            .finalize_with_hash(block_hash_bytes!(b"block 1"));
+        tx.insert_block_header(&genesis).unwrap();
+        tx.insert_block_header(&block1).unwrap();
+
+        let result = query(
+            &tx,
+            "SELECT number, hash FROM block_headers ORDER BY number",
+            10,
+            TIMEOUT,
+        )
+        .unwrap();
+        assert_eq!(
+            result,
+            QueryResult {
+                columns: vec!["number".to_owned(), "hash".to_owned()],
+                rows: vec![
+                    vec![0.into(), format!("0x{:x}", genesis.hash.0).into()],
+                    vec![1.into(), format!("0x{:x}", block1.hash.0).into()],
+                ],
+                truncated: false,
+            }
+        );
+
+        let result = query(&tx, "SELECT number FROM block_headers", 1, TIMEOUT).unwrap();
+        assert_eq!(result.rows, vec![vec![serde_json::Value::from(0)]]);
+        assert!(result.truncated);
+    }
+
+    #[test]
+    fn writes_are_rejected() {
+        let storage = Storage::in_memory().unwrap();
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+
+        for sql in [
+            "DELETE FROM block_headers",
+            "PRAGMA user_version = 0",
+            "WITH x AS (SELECT 1) DELETE FROM block_headers",
+        ] {
+            let result = query(&tx, sql, 10, TIMEOUT);
+            assert!(
+                matches!(result, Err(QueryError::NotReadOnly)),
+                "{sql} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn only_allowed_tables_can_be_queried() {
+        let storage = Storage::in_memory().unwrap();
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+
+        for (sql, table) in [
+            ("SELECT * FROM p2p_peers", "p2p_peers"),
+            (
+                "SELECT number FROM block_headers JOIN sync_checkpoints",
+                "sync_checkpoints",
+            ),
+            (
+                "WITH x AS (SELECT * FROM trie_storage) SELECT * FROM x",
+                "trie_storage",
+            ),
+            ("SELECT * FROM sqlite_master", "sqlite_master"),
+        ] {
+            let result = query(&tx, sql, 10, TIMEOUT);
+            assert_matches::assert_matches!(
+                result,
+                Err(QueryError::TableNotAllowed(t)) if t == table,
+                "{sql} should be rejected"
+            );
+        }
+
+        let result = query(&tx, "SELECT * FROM pragma_table_list", 10, TIMEOUT);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn authorizer_is_removed_afterwards() {
+        let storage = Storage::in_memory().unwrap();
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+
+        query(&tx, "SELECT * FROM p2p_peers", 10, TIMEOUT).unwrap_err();
+
+        let genesis = BlockHeader::builder()
+            .with_number(BlockNumber::GENESIS)
+            .finalize_with_hash(block_hash_bytes!(b"genesis"));
+        tx.insert_block_header(&genesis).unwrap();
+        tx.block_header(BlockNumber::GENESIS.into())
+            .unwrap()
+            .unwrap();
+    }
+
+    #[test]
+    fn timeout() {
+        let storage = Storage::in_memory().unwrap();
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+
+        let result = query(
+            &tx,
+            "WITH RECURSIVE x(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM x) SELECT count(*) FROM x",
+            10,
+            Duration::from_millis(10),
+        );
+        assert!(matches!(result, Err(QueryError::Timeout)));
+    }
+}
@@ -1,5 +1,9 @@
+use anyhow::Context;
+use pathfinder_common::{BlockHash, BlockNumber};
+
 use crate::prelude::*;
 
+/// The number of reorgs performed by sync since the database was created.
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
 pub struct ReorgCounter(i64);
 
@@ -7,6 +11,22 @@ impl ReorgCounter {
     pub fn new(value: i64) -> Self {
         Self(value)
     }
+
+    pub fn get(&self) -> u64 {
+        self.0 as u64
+    }
+}
+
+/// A reorg performed by sync.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reorg {
+    /// When the reorg was performed, in seconds since the Unix epoch.
+    pub timestamp: u64,
+    pub old_head: (BlockNumber, BlockHash),
+    /// `None` if all blocks were purged.
+    pub new_head: Option<(BlockNumber, BlockHash)>,
+    /// The number of purged blocks.
+    pub depth: u64,
 }
 
 pub(super) fn increment_reorg_counter(tx: &Transaction<'_>) -> anyhow::Result<()> {
@@ -29,6 +49,56 @@ pub(super) fn reorg_counter(tx: &Transaction<'_>) -> anyhow::Result<ReorgCounter
         .map_err(|e| e.into())
 }
 
+pub(super) fn insert_reorg(tx: &Transaction<'_>, reorg: &Reorg) -> anyhow::Result<()> {
+    tx.inner()
+        .execute(
+            r"INSERT INTO reorg_history (
+                timestamp, old_head_number, old_head_hash, new_head_number, new_head_hash, depth
+            ) VALUES (?, ?, ?, ?, ?, ?)",
+            params![
+                &reorg.timestamp,
+                &reorg.old_head.0,
+                &reorg.old_head.1,
+                &reorg.new_head.map(|(number, _)| number),
+                &reorg.new_head.map(|(_, hash)| hash),
+                &reorg.depth,
+            ],
+        )
+        .context("Inserting reorg")?;
+
+    Ok(())
+}
+
+/// Returns up to `limit` of the most recent reorgs, newest first.
+pub(super) fn reorg_history(tx: &Transaction<'_>, limit: usize) -> anyhow::Result<Vec<Reorg>> {
+    let mut stmt = tx
+        .inner()
+        .prepare_cached(
+            r"SELECT
+                timestamp, old_head_number, old_head_hash, new_head_number, new_head_hash, depth
+            FROM reorg_history ORDER BY id DESC LIMIT ?",
+        )
+        .context("Preparing statement")?;
+
+    let reorgs = stmt
+        .query_map(params![&(limit as u64)], |row| {
+            let new_head_number = row.get_optional_block_number(3)?;
+            let new_head_hash = row.get_optional_felt(4)?.map(BlockHash);
+
+            Ok(Reorg {
+                timestamp: row.get_i64(0)? as u64,
+                old_head: (row.get_block_number(1)?, row.get_block_hash(2)?),
+                new_head: new_head_number.zip(new_head_hash),
+                depth: row.get_i64(5)? as u64,
+            })
+        })
+        .context("Querying reorg history")?
+        .collect::<Result<Vec<_>, _>>()
+        .context("Iterating over rows")?;
+
+    Ok(reorgs)
+}
+
 #[cfg(test)]
 mod tests {
     use crate::Storage;
@@ -59,4 +129,33 @@ mod tests {
         let result = reorg_counter(&tx).unwrap();
         assert_eq!(result, ReorgCounter::new(2));
     }
+
+    #[test]
+    fn history() {
+        use pathfinder_common::macro_prelude::*;
+
+        let storage = Storage::in_memory().unwrap();
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+
+        assert_eq!(reorg_history(&tx, 10).unwrap(), vec![]);
+
+        let first = Reorg {
+            timestamp: 1000,
+            old_head: (BlockNumber::new_or_panic(5), block_hash!("0x5")),
+            new_head: None,
+            depth: 6,
+        };
+        let second = Reorg {
+            timestamp: 2000,
+            old_head: (BlockNumber::new_or_panic(10), block_hash!("0xa")),
+            new_head: Some((BlockNumber::new_or_panic(8), block_hash!("0x8"))),
+            depth: 2,
+        };
+        insert_reorg(&tx, &first).unwrap();
+        insert_reorg(&tx, &second).unwrap();
+
+        assert_eq!(reorg_history(&tx, 10).unwrap(), vec![second.clone(), first]);
+        assert_eq!(reorg_history(&tx, 1).unwrap(), vec![second]);
+    }
 }
@@ -0,0 +1,344 @@
+use anyhow::Context;
+use pathfinder_common::state_update::ContractClassUpdate;
+use pathfinder_common::{BlockNumber, StateUpdate, StorageValue};
+
+use crate::{prelude::*, BlockId};
+
+use super::block::block_id;
+
+/// Totals of the chain's state as of a block, maintained as blocks are inserted.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct StateStats {
+    /// Contracts deployed, excluding system contracts.
+    pub contracts: u64,
+    pub declared_classes: u64,
+    /// Non-zero storage slots, including those of system contracts.
+    pub storage_leaves: u64,
+    pub storage_diffs: u64,
+    pub nonce_updates: u64,
+}
+
+/// Records the totals as of `block_number` by applying its state update to the parent's totals.
+///
+/// Nothing is recorded if the parent's totals are unknown, which is the case for blocks inserted
+/// out of order, such as a P2P snapshot's anchor block. The totals then remain unknown for all of
+/// the following blocks, as the state preceding the anchor is not stored to count them from.
+pub(super) fn insert_state_stats(
+    tx: &Transaction<'_>,
+    block_number: BlockNumber,
+    state_update: &StateUpdate,
+) -> anyhow::Result<()> {
+    let parent = match block_number.parent() {
+        Some(parent) => match state_stats_at(tx, parent)? {
+            Some(stats) => stats,
+            None => return Ok(()),
+        },
+        None => StateStats::default(),
+    };
+
+    let mut previous_value = tx
+        .inner()
+        .prepare_cached(
+            r"SELECT storage_value FROM storage_updates
+            WHERE contract_address = ? AND storage_address = ? AND block_number < ?
+            ORDER BY block_number DESC LIMIT 1",
+        )
+        .context("Preparing previous storage value query")?;
+
+    let mut storage_leaves = parent.storage_leaves as i64;
+    let storage = state_update
+        .contract_updates
+        .iter()
+        .flat_map(|(address, update)| update.storage.iter().map(move |x| (address, x)))
+        .chain(
+            state_update
+                .system_contract_updates
+                .iter()
+                .flat_map(|(address, update)| update.storage.iter().map(move |x| (address, x))),
+        );
+    for (address, (key, value)) in storage {
+        let previous = previous_value
+            .query_row(params![address, key, &block_number], |row| {
+                row.get_storage_value(0)
+            })
+            .optional()
+            .context("Querying previous storage value")?
+            .unwrap_or(StorageValue::ZERO);
+
+        match (previous == StorageValue::ZERO, *value == StorageValue::ZERO) {
+            (true, false) => storage_leaves += 1,
+            (false, true) => storage_leaves -= 1,
+            _ => {}
+        }
+    }
+
+    // The counts' deployed contracts include replaced classes.
+    let counts = state_update.counts();
+    let deployed = state_update
+        .contract_updates
+        .values()
+        .filter(|x| matches!(x.class, Some(ContractClassUpdate::Deploy(_))))
+        .count() as u64;
+
+    let stats = StateStats {
+        contracts: parent.contracts + deployed,
+        declared_classes: parent.declared_classes + counts.declared_classes,
+        storage_leaves: storage_leaves
+            .try_into()
+            .context("Storage leaf count is negative")?,
+        storage_diffs: parent.storage_diffs + counts.storage_diffs,
+        nonce_updates: parent.nonce_updates + counts.nonce_updates,
+    };
+
+    tx.inner()
+        .execute(
+            r"INSERT OR REPLACE INTO state_stats (
+                block_number,
+                contracts,
+                declared_classes,
+                storage_leaves,
+                storage_diffs,
+                nonce_updates
+            ) VALUES (?, ?, ?, ?, ?, ?)",
+            params![
+                &block_number,
+                &stats.contracts,
+                &stats.declared_classes,
+                &stats.storage_leaves,
+                &stats.storage_diffs,
+                &stats.nonce_updates,
+            ],
+        )
+        .context("Inserting state stats")?;
+
+    Ok(())
+}
+
+/// Returns the totals as of the block, or [None] if the block does not exist or its totals are
+/// unknown.
+pub(super) fn state_stats(
+    tx: &Transaction<'_>,
+    block: BlockId,
+) -> anyhow::Result<Option<StateStats>> {
+    let Some((block_number, _)) = block_id(tx, block).context("Querying block header")? else {
+        return Ok(None);
+    };
+
+    state_stats_at(tx, block_number)
+}
+
+fn state_stats_at(
+    tx: &Transaction<'_>,
+    block_number: BlockNumber,
+) -> anyhow::Result<Option<StateStats>> {
+    tx.inner()
+        .query_row(
+            r"SELECT contracts, declared_classes, storage_leaves, storage_diffs, nonce_updates
+            FROM state_stats WHERE block_number = ?",
+            params![&block_number],
+            |row| {
+                Ok(StateStats {
+                    contracts: row.get(0)?,
+                    declared_classes: row.get(1)?,
+                    storage_leaves: row.get(2)?,
+                    storage_diffs: row.get(3)?,
+                    nonce_updates: row.get(4)?,
+                })
+            },
+        )
+        .optional()
+        .context("Querying state stats")
+}
+
+/// The size of a table or index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableSize {
+    /// The schema of the database file holding the table, which is `main` unless the table was
+    /// moved to a [split database](crate::SplitDatabases).
+    pub schema: String,
+    pub name: String,
+    /// The size in bytes.
+    pub size: u64,
+}
+
+/// Returns the size of each table and index of the main and attached databases, largest first.
+///
+/// This reads every page of the database and is therefore slow for large databases.
+pub(super) fn table_sizes(tx: &Transaction<'_>) -> anyhow::Result<Vec<TableSize>> {
+    let schemas = tx
+        .inner()
+        .prepare("SELECT name FROM pragma_database_list WHERE name != 'temp'")
+        .context("Preparing database list statement")?
+        .query_map([], |row| row.get::<_, String>(0))
+        .context("Querying database list")?
+        .collect::<Result<Vec<_>, _>>()
+        .context("Iterating over database list")?;
+
+    // dbstat only covers the main database unless a schema is given.
+    let mut stmt = tx
+        .inner()
+        .prepare("SELECT name, SUM(pgsize) FROM dbstat WHERE schema = ? GROUP BY name")
+        .context("Preparing statement")?;
+
+    let mut sizes = Vec::new();
+    for schema in schemas {
+        let rows = stmt
+            .query_map([&schema], |row| {
+                Ok(TableSize {
+                    schema: schema.clone(),
+                    name: row.get(0)?,
+                    size: row.get(1)?,
+                })
+            })
+            .context("Querying table sizes")?;
+
+        for size in rows {
+            sizes.push(size.context("Iterating over table sizes")?);
+        }
+    }
+
+    sizes.sort_by(|a, b| b.size.cmp(&a.size));
+
+    Ok(sizes)
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::{BlockHeader, ClassHash};
+
+    use super::*;
+    use crate::Storage;
+
+    #[test]
+    fn totals_are_accumulated() {
+        let storage = Storage::in_memory().unwrap();
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+
+        let contract = contract_address_bytes!(b"contract");
+        let key0 = storage_address_bytes!(b"key 0");
+        let key1 = storage_address_bytes!(b"key 1");
+        let class = class_hash_bytes!(b"class");
+
+        let header0 = BlockHeader::builder().finalize_with_hash(block_hash_bytes!(b"block 0"));
+        let header1 = header0
+            .child_builder()
+            .finalize_with_hash(block_hash_bytes!(b"block 1"));
+        let header2 = header1
+            .child_builder()
+            .finalize_with_hash(block_hash_bytes!(b"block 2"));
+
+        let state_update0 = StateUpdate::default()
+            .with_declared_cairo_class(class)
+            .with_deployed_contract(contract, class)
+            .with_storage_update(contract, key0, storage_value!("0x1"))
+            .with_storage_update(contract, key1, storage_value!("0x1"));
+        let state_update1 = StateUpdate::default()
+            .with_contract_nonce(contract, contract_nonce!("0x1"))
+            .with_storage_update(contract, key0, storage_value!("0x2"))
+            .with_storage_update(contract, key1, StorageValue::ZERO);
+        let state_update2 = StateUpdate::default()
+            .with_replaced_class(contract, ClassHash::ZERO)
+            .with_storage_update(contract, key1, storage_value!("0x3"));
+
+        for (header, state_update) in [
+            (&header0, &state_update0),
+            (&header1, &state_update1),
+            (&header2, &state_update2),
+        ] {
+            tx.insert_block_header(header).unwrap();
+            tx.insert_state_update(header.number, state_update).unwrap();
+        }
+
+        let expected = [
+            StateStats {
+                contracts: 1,
+                declared_classes: 1,
+                storage_leaves: 2,
+                storage_diffs: 2,
+                nonce_updates: 0,
+            },
+            StateStats {
+                contracts: 1,
+                declared_classes: 1,
+                storage_leaves: 1,
+                storage_diffs: 4,
+                nonce_updates: 1,
+            },
+            StateStats {
+                contracts: 1,
+                declared_classes: 1,
+                storage_leaves: 2,
+                storage_diffs: 5,
+                nonce_updates: 1,
+            },
+        ];
+        for (header, expected) in [header0, header1, header2].iter().zip(expected) {
+            let stats = tx.state_stats(header.hash.into()).unwrap();
+            assert_eq!(stats, Some(expected), "block {}", header.number);
+        }
+
+        // Purged blocks take their totals with them.
+        tx.purge_block(header2.number).unwrap();
+        assert_eq!(tx.state_stats(BlockId::Latest).unwrap(), Some(expected[1]));
+    }
+
+    #[test]
+    fn unknown_without_parent() {
+        let storage = Storage::in_memory().unwrap();
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+
+        let header = BlockHeader::builder()
+            .with_number(BlockNumber::new_or_panic(5))
+            .finalize_with_hash(block_hash_bytes!(b"block 5"));
+        tx.insert_block_header(&header).unwrap();
+        tx.insert_state_update(header.number, &StateUpdate::default())
+            .unwrap();
+
+        assert_eq!(tx.state_stats(BlockId::Latest).unwrap(), None);
+    }
+
+    #[test]
+    fn table_sizes() {
+        let storage = Storage::in_memory().unwrap();
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+
+        let sizes = tx.table_sizes().unwrap();
+        assert!(sizes
+            .iter()
+            .any(|x| x.schema == "main" && x.name == "state_stats"));
+        assert!(sizes.windows(2).all(|x| x[0].size >= x[1].size));
+    }
+
+    #[test]
+    fn table_sizes_include_split_databases() {
+        let db_dir = tempfile::TempDir::new().unwrap();
+        let split = crate::SplitDatabases {
+            tries: Some(db_dir.path().join("tries.sqlite")),
+            transactions: None,
+        };
+        let storage = Storage::migrate_split(
+            db_dir.path().join("test.sqlite"),
+            crate::JournalMode::WAL,
+            1,
+            split,
+            crate::Migration::Auto,
+        )
+        .unwrap()
+        .create_pool(std::num::NonZeroU32::new(1).unwrap())
+        .unwrap();
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+
+        let sizes = tx.table_sizes().unwrap();
+        assert!(sizes
+            .iter()
+            .any(|x| x.schema == "tries" && x.name == "trie_storage"));
+        assert!(!sizes
+            .iter()
+            .any(|x| x.schema == "main" && x.name == "trie_storage"));
+    }
+}
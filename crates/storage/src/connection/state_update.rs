@@ -93,6 +93,9 @@ pub(super) fn insert_state_update(
         update_class_defs.execute(params![&block_number, &class])?;
     }
 
+    super::state_stats::insert_state_stats(tx, block_number, state_update)
+        .context("Updating state stats")?;
+
     Ok(())
 }
 
@@ -396,6 +399,33 @@ pub(super) fn declared_classes_at(
     Ok(Some(result))
 }
 
+pub(super) fn cairo_classes_declared_between(
+    tx: &Transaction<'_>,
+    from: BlockNumber,
+    to: BlockNumber,
+    limit: usize,
+) -> anyhow::Result<Vec<ClassHash>> {
+    let mut stmt = tx
+        .inner()
+        .prepare_cached(
+            r"SELECT hash FROM class_definitions d
+            WHERE block_number > ? AND block_number <= ?
+                AND NOT EXISTS (SELECT 1 FROM casm_definitions c WHERE c.hash = d.hash)
+            ORDER BY block_number
+            LIMIT ?",
+        )
+        .context("Preparing Cairo class declaration query statement")?;
+
+    let limit = i64::try_from(limit).unwrap_or(i64::MAX);
+    let classes = stmt
+        .query_map(params![&from, &to, &limit], |row| row.get_class_hash(0))
+        .context("Querying Cairo class declarations")?
+        .collect::<Result<Vec<_>, _>>()
+        .context("Iterating over Cairo class declaration query rows")?;
+
+    Ok(classes)
+}
+
 pub(super) fn storage_value(
     tx: &Transaction<'_>,
     block: BlockId,
@@ -563,6 +593,34 @@ pub(super) fn contract_class_hash(
     .map_err(|e| e.into())
 }
 
+/// Returns every class hash `contract_address` has had, along with the block at which it
+/// was set, in ascending block order. The first entry is the contract's deployment.
+pub(super) fn contract_class_history(
+    tx: &Transaction<'_>,
+    contract_address: ContractAddress,
+) -> anyhow::Result<Vec<(BlockNumber, ClassHash)>> {
+    let mut stmt = tx
+        .inner()
+        .prepare_cached(
+            r"SELECT block_number, class_hash FROM contract_updates
+            WHERE contract_address = ?
+            ORDER BY block_number ASC",
+        )
+        .context("Preparing statement")?;
+
+    let history = stmt
+        .query_map(params![&contract_address], |row| {
+            let block = row.get_block_number(0)?;
+            let class_hash = row.get_class_hash(1)?;
+            Ok((block, class_hash))
+        })
+        .context("Querying contract class history")?
+        .collect::<Result<Vec<_>, _>>()
+        .context("Iterating over rows")?;
+
+    Ok(history)
+}
+
 #[cfg(test)]
 mod tests {
     use pathfinder_common::macro_prelude::*;
@@ -654,6 +712,103 @@ mod tests {
         assert_eq!(is_replaced, Some(replaced_class));
     }
 
+    #[test]
+    fn contract_class_history() {
+        let mut db = crate::Storage::in_memory().unwrap().connection().unwrap();
+        let tx = db.transaction().unwrap();
+
+        let original_class = class_hash!("0xdeadbeef");
+        let replaced_class = class_hash!("0xdeadbeefabcdef");
+        let contract = contract_address!("0x12345");
+
+        let header_0 = BlockHeader::builder().finalize_with_hash(block_hash!("0xabc"));
+        let header_1 = header_0
+            .child_builder()
+            .finalize_with_hash(block_hash!("0xabcdef"));
+        let header_2 = header_1
+            .child_builder()
+            .finalize_with_hash(block_hash!("0xa111123"));
+
+        let diff_0 = StateUpdate::default().with_deployed_contract(contract, original_class);
+        let diff_1 = StateUpdate::default();
+        let diff_2 = StateUpdate::default().with_replaced_class(contract, replaced_class);
+
+        tx.insert_block_header(&header_0).unwrap();
+        tx.insert_block_header(&header_1).unwrap();
+        tx.insert_block_header(&header_2).unwrap();
+
+        tx.insert_state_update(header_0.number, &diff_0).unwrap();
+        tx.insert_state_update(header_1.number, &diff_1).unwrap();
+        tx.insert_state_update(header_2.number, &diff_2).unwrap();
+
+        let history = super::contract_class_history(&tx, contract).unwrap();
+        assert_eq!(
+            history,
+            vec![
+                (header_0.number, original_class),
+                (header_2.number, replaced_class)
+            ]
+        );
+
+        let non_existent =
+            super::contract_class_history(&tx, contract_address!("0xaaaaa")).unwrap();
+        assert!(non_existent.is_empty());
+    }
+
+    #[test]
+    fn cairo_classes_declared_between() {
+        let mut db = crate::Storage::in_memory().unwrap().connection().unwrap();
+        let tx = db.transaction().unwrap();
+
+        let cairo_0 = class_hash!("0x10");
+        let cairo_1 = class_hash!("0x11");
+        let cairo_2 = class_hash!("0x12");
+        let sierra = sierra_hash!("0x20");
+
+        let header_0 = BlockHeader::builder().finalize_with_hash(block_hash!("0xabc"));
+        let header_1 = header_0
+            .child_builder()
+            .finalize_with_hash(block_hash!("0xabcdef"));
+        let header_2 = header_1
+            .child_builder()
+            .finalize_with_hash(block_hash!("0xa111123"));
+
+        let diff_0 = StateUpdate::default().with_declared_cairo_class(cairo_0);
+        let diff_1 = StateUpdate::default()
+            .with_declared_cairo_class(cairo_1)
+            .with_declared_sierra_class(sierra, casm_hash!("0x21"));
+        let diff_2 = StateUpdate::default().with_declared_cairo_class(cairo_2);
+
+        tx.insert_cairo_class(cairo_0, b"definition").unwrap();
+        tx.insert_cairo_class(cairo_1, b"definition").unwrap();
+        tx.insert_cairo_class(cairo_2, b"definition").unwrap();
+        tx.insert_sierra_class(&sierra, b"definition", &casm_hash!("0x21"), b"casm")
+            .unwrap();
+
+        tx.insert_block_header(&header_0).unwrap();
+        tx.insert_block_header(&header_1).unwrap();
+        tx.insert_block_header(&header_2).unwrap();
+
+        tx.insert_state_update(header_0.number, &diff_0).unwrap();
+        tx.insert_state_update(header_1.number, &diff_1).unwrap();
+        tx.insert_state_update(header_2.number, &diff_2).unwrap();
+
+        let result =
+            super::cairo_classes_declared_between(&tx, header_0.number, header_2.number, 10)
+                .unwrap();
+        assert_eq!(result, vec![cairo_1, cairo_2]);
+
+        let result =
+            super::cairo_classes_declared_between(&tx, header_0.number, header_2.number, 1)
+                .unwrap();
+        assert_eq!(result, vec![cairo_1]);
+
+        let result =
+            super::cairo_classes_declared_between(&tx, header_2.number, header_0.number, 10)
+                .unwrap();
+        assert!(result.is_empty());
+    }
+
     #[test]
     fn state_update() {
         let mut db = crate::Storage::in_memory().unwrap().connection().unwrap();
@@ -0,0 +1,148 @@
+use anyhow::Context;
+use pathfinder_common::BlockNumber;
+
+use crate::prelude::*;
+
+/// A stage of the sync pipeline whose progress is tracked in storage.
+///
+/// A stage's checkpoint is the block up to which, starting at genesis, the stage
+/// has completed all its work.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SyncStage {
+    /// Block headers and signatures.
+    Headers,
+    /// Block bodies i.e. the transactions of each block.
+    Transactions,
+    /// Transaction receipts, which includes their events.
+    Receipts,
+    /// State updates and the state tries computed from them.
+    StateUpdates,
+    /// Definitions of the classes declared in each block.
+    Classes,
+}
+
+impl SyncStage {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SyncStage::Headers => "headers",
+            SyncStage::Transactions => "transactions",
+            SyncStage::Receipts => "receipts",
+            SyncStage::StateUpdates => "state_updates",
+            SyncStage::Classes => "classes",
+        }
+    }
+}
+
+pub(super) fn sync_checkpoint(
+    tx: &Transaction<'_>,
+    stage: SyncStage,
+) -> anyhow::Result<Option<BlockNumber>> {
+    tx.inner()
+        .query_row(
+            "SELECT block_number FROM sync_checkpoints WHERE stage = ?",
+            params![&stage],
+            |row| row.get_block_number(0),
+        )
+        .optional()
+        .context("Querying sync checkpoint")
+}
+
+pub(super) fn update_sync_checkpoint(
+    tx: &Transaction<'_>,
+    stage: SyncStage,
+    block: BlockNumber,
+) -> anyhow::Result<()> {
+    tx.inner()
+        .execute(
+            r"INSERT INTO sync_checkpoints (stage, block_number) VALUES (?, ?)
+            ON CONFLICT(stage) DO UPDATE SET block_number = excluded.block_number",
+            params![&stage, &block],
+        )
+        .context("Updating sync checkpoint")?;
+
+    Ok(())
+}
+
+/// Moves all checkpoints at or past `block` back to its parent.
+///
+/// Used when a block is purged, as none of the stages have completed it anymore.
+pub(super) fn rewind_sync_checkpoints(
+    tx: &Transaction<'_>,
+    block: BlockNumber,
+) -> anyhow::Result<()> {
+    match block.parent() {
+        Some(parent) => tx
+            .inner()
+            .execute(
+                "UPDATE sync_checkpoints SET block_number = ? WHERE block_number > ?",
+                params![&parent, &parent],
+            )
+            .context("Rewinding sync checkpoints")?,
+        None => tx
+            .inner()
+            .execute("DELETE FROM sync_checkpoints", [])
+            .context("Deleting sync checkpoints")?,
+    };
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Storage;
+
+    use super::*;
+
+    #[test]
+    fn empty_is_none() {
+        let storage = Storage::in_memory().unwrap();
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+
+        let result = sync_checkpoint(&tx, SyncStage::Headers).unwrap();
+        assert_eq!(result, None);
+    }
+
+    #[test]
+    fn update() {
+        let storage = Storage::in_memory().unwrap();
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+
+        update_sync_checkpoint(&tx, SyncStage::Transactions, BlockNumber::new_or_panic(5)).unwrap();
+        update_sync_checkpoint(&tx, SyncStage::Transactions, BlockNumber::new_or_panic(7)).unwrap();
+        update_sync_checkpoint(&tx, SyncStage::Receipts, BlockNumber::new_or_panic(3)).unwrap();
+
+        let result = sync_checkpoint(&tx, SyncStage::Transactions).unwrap();
+        assert_eq!(result, Some(BlockNumber::new_or_panic(7)));
+        let result = sync_checkpoint(&tx, SyncStage::Receipts).unwrap();
+        assert_eq!(result, Some(BlockNumber::new_or_panic(3)));
+        let result = sync_checkpoint(&tx, SyncStage::StateUpdates).unwrap();
+        assert_eq!(result, None);
+    }
+
+    #[test]
+    fn rewind() {
+        let storage = Storage::in_memory().unwrap();
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+
+        update_sync_checkpoint(&tx, SyncStage::Headers, BlockNumber::new_or_panic(10)).unwrap();
+        update_sync_checkpoint(&tx, SyncStage::Transactions, BlockNumber::new_or_panic(5)).unwrap();
+        update_sync_checkpoint(&tx, SyncStage::Receipts, BlockNumber::new_or_panic(3)).unwrap();
+
+        rewind_sync_checkpoints(&tx, BlockNumber::new_or_panic(5)).unwrap();
+
+        let result = sync_checkpoint(&tx, SyncStage::Headers).unwrap();
+        assert_eq!(result, Some(BlockNumber::new_or_panic(4)));
+        let result = sync_checkpoint(&tx, SyncStage::Transactions).unwrap();
+        assert_eq!(result, Some(BlockNumber::new_or_panic(4)));
+        let result = sync_checkpoint(&tx, SyncStage::Receipts).unwrap();
+        assert_eq!(result, Some(BlockNumber::new_or_panic(3)));
+
+        rewind_sync_checkpoints(&tx, BlockNumber::GENESIS).unwrap();
+
+        let result = sync_checkpoint(&tx, SyncStage::Receipts).unwrap();
+        assert_eq!(result, None);
+    }
+}
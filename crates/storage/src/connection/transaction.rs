@@ -4,6 +4,7 @@ use anyhow::Context;
 use pathfinder_common::receipt::Receipt;
 use pathfinder_common::transaction::Transaction as StarknetTransaction;
 use pathfinder_common::{BlockHash, BlockNumber, TransactionHash};
+use primitive_types::H256;
 
 use crate::{prelude::*, BlockId};
 
@@ -12,6 +13,13 @@ pub enum TransactionStatus {
     L2Accepted,
 }
 
+/// The maximum number of transactions inserted by a single statement.
+const INSERT_BATCH_SIZE: usize = 64;
+
+/// Blocks with at least this many events compute their Bloom filter on a separate thread, while
+/// the transactions are inserted. Below it, spawning the thread costs more than it saves.
+const PARALLEL_BLOOM_EVENT_THRESHOLD: usize = 1024;
+
 pub(super) fn insert_transactions(
     tx: &Transaction<'_>,
     block_hash: BlockHash,
@@ -22,11 +30,86 @@ pub(super) fn insert_transactions(
         return Ok(());
     }
 
+    let bloom = || {
+        let events = transaction_data
+            .iter()
+            .filter_map(|(_, receipt)| receipt.as_ref())
+            .flat_map(|receipt| &receipt.events);
+        super::event::block_bloom(events).to_compressed_bytes()
+    };
+
+    let event_count: usize = transaction_data
+        .iter()
+        .filter_map(|(_, receipt)| receipt.as_ref())
+        .map(|receipt| receipt.events.len())
+        .sum();
+
+    let bloom = if event_count >= PARALLEL_BLOOM_EVENT_THRESHOLD {
+        std::thread::scope(|scope| {
+            // The Bloom filter is computed while the transactions are serialized and inserted.
+            let bloom = scope.spawn(bloom);
+            insert_transaction_rows(tx, block_hash, block_number, transaction_data)?;
+            anyhow::Ok(
+                bloom
+                    .join()
+                    .unwrap_or_else(|panic| std::panic::resume_unwind(panic)),
+            )
+        })?
+    } else {
+        insert_transaction_rows(tx, block_hash, block_number, transaction_data)?;
+        bloom()
+    };
+
+    super::event::insert_block_bloom(tx, block_number, &bloom)
+        .context("Inserting events into Bloom filter")
+}
+
+fn insert_transaction_rows(
+    tx: &Transaction<'_>,
+    block_hash: BlockHash,
+    block_number: BlockNumber,
+    transaction_data: &[(StarknetTransaction, Option<Receipt>)],
+) -> anyhow::Result<()> {
+    let rows = serialize_transactions(transaction_data)?;
+
+    let mut rows = rows.as_slice();
+    while !rows.is_empty() {
+        // Batches are sized in powers of two, so that only a few distinct statements are
+        // cached.
+        let size = INSERT_BATCH_SIZE.min(1 << rows.len().ilog2());
+        let (batch, rest) = rows.split_at(size);
+        insert_transaction_batch(tx, block_hash, batch).context("Inserting transaction data")?;
+        rows = rest;
+    }
+
+    for receipt in transaction_data
+        .iter()
+        .filter_map(|(_, receipt)| receipt.as_ref())
+    {
+        insert_l2_to_l1_messages(tx, block_number, receipt)
+            .context("Inserting L2 to L1 messages")?;
+    }
+
+    Ok(())
+}
+
+/// A row of the `starknet_transactions` table, with its transaction and receipt serialized and
+/// compressed.
+struct TransactionRow {
+    hash: TransactionHash,
+    idx: i64,
+    transaction: Vec<u8>,
+    receipt: Option<Vec<u8>>,
+}
+
+fn serialize_transactions(
+    transaction_data: &[(StarknetTransaction, Option<Receipt>)],
+) -> anyhow::Result<Vec<TransactionRow>> {
     let mut compressor = zstd::bulk::Compressor::new(10).context("Create zstd compressor")?;
+
+    let mut rows = Vec::with_capacity(transaction_data.len());
     for (i, (transaction, receipt)) in transaction_data.iter().enumerate() {
-        // Serialize and compress transaction data.
         let transaction = dto::Transaction::from(transaction);
-
         let tx_data = serde_json::to_vec(&transaction).context("Serializing transaction")?;
         let tx_data = compressor
             .compress(&tx_data)
@@ -46,23 +129,48 @@ pub(super) fn insert_transactions(
             None => None,
         };
 
-        tx.inner().execute(r"INSERT OR REPLACE INTO starknet_transactions (hash,  idx,  block_hash,  tx,  receipt) 
-                                                                  VALUES (:hash, :idx, :block_hash, :tx, :receipt)",
-            named_params![
-            ":hash": &transaction.hash(),
-            ":idx": &i.try_into_sql_int()?,
-            ":block_hash": &block_hash,
-            ":tx": &tx_data,
-            ":receipt": &serialized_receipt,
-        ]).context("Inserting transaction data")?;
+        rows.push(TransactionRow {
+            hash: transaction.hash(),
+            idx: i.try_into_sql_int()?,
+            transaction: tx_data,
+            receipt: serialized_receipt,
+        });
     }
 
-    let events = transaction_data
-        .iter()
-        .filter_map(|(_, receipt)| receipt.as_ref())
-        .flat_map(|receipt| &receipt.events);
-    super::event::insert_block_events(tx, block_number, events)
-        .context("Inserting events into Bloom filter")?;
+    Ok(rows)
+}
+
+/// Inserts the rows with a single multi-row statement.
+fn insert_transaction_batch(
+    tx: &Transaction<'_>,
+    block_hash: BlockHash,
+    rows: &[TransactionRow],
+) -> anyhow::Result<()> {
+    use crate::params::ToSql;
+
+    let values = vec!["(?, ?, ?, ?, ?)"; rows.len()].join(", ");
+    let sql = format!(
+        "INSERT OR REPLACE INTO starknet_transactions (hash, idx, block_hash, tx, receipt) \
+        VALUES {values}"
+    );
+
+    let block_hash = block_hash.to_sql();
+    let params = rows.iter().flat_map(|row| {
+        [
+            row.hash.to_sql(),
+            row.idx.to_sql(),
+            block_hash.clone(),
+            row.transaction.to_sql(),
+            row.receipt.to_sql(),
+        ]
+    });
+
+    tx.inner()
+        .prepare_cached(&sql)
+        .context("Preparing statement")?
+        .execute(rusqlite::params_from_iter(params))
+        .context("Executing statement")?;
+
     Ok(())
 }
 
@@ -73,13 +181,13 @@ pub(super) fn update_receipt(
     receipt: &Receipt,
 ) -> anyhow::Result<()> {
     let mut compressor = zstd::bulk::Compressor::new(10).context("Create zstd compressor")?;
-    let receipt = dto::Receipt::from(receipt);
-    let serialized_receipt = serde_json::to_vec(&receipt).context("Serializing receipt")?;
+    let dto_receipt = dto::Receipt::from(receipt);
+    let serialized_receipt = serde_json::to_vec(&dto_receipt).context("Serializing receipt")?;
     let serialized_receipt = compressor
         .compress(&serialized_receipt)
         .context("Compressing receipt")?;
 
-    let execution_status = match receipt.execution_status {
+    let execution_status = match dto_receipt.execution_status {
         dto::ExecutionStatus::Succeeded => 0,
         dto::ExecutionStatus::Reverted { .. } => 1,
     };
@@ -99,16 +207,98 @@ pub(super) fn update_receipt(
         )
         .context("Inserting transaction data")?;
 
+    let block_number = tx
+        .inner()
+        .query_row(
+            "SELECT number FROM block_headers WHERE hash = ?",
+            params![&block_hash],
+            |row| row.get_block_number(0),
+        )
+        .optional()
+        .context("Querying block number")?;
+
+    // Messages are indexed by block number, which is only known once the header is stored.
+    if let Some(block_number) = block_number {
+        insert_l2_to_l1_messages(tx, block_number, receipt)
+            .context("Inserting L2 to L1 messages")?;
+    }
+
+    Ok(())
+}
+
+fn insert_l2_to_l1_messages(
+    tx: &Transaction<'_>,
+    block_number: BlockNumber,
+    receipt: &Receipt,
+) -> anyhow::Result<()> {
+    tx.inner()
+        .prepare_cached("DELETE FROM l2_to_l1_messages WHERE transaction_hash = ?")
+        .context("Preparing statement")?
+        .execute(params![&receipt.transaction_hash])
+        .context("Deleting stale messages")?;
+
+    let mut stmt = tx
+        .inner()
+        .prepare_cached(
+            r"INSERT INTO l2_to_l1_messages (message_hash, block_number, transaction_hash, idx)
+            VALUES (?, ?, ?, ?)",
+        )
+        .context("Preparing statement")?;
+
+    for (idx, message) in receipt.l2_to_l1_messages.iter().enumerate() {
+        let message_hash = message.calculate_message_hash();
+        stmt.execute(params![
+            &message_hash.as_bytes(),
+            &block_number,
+            &receipt.transaction_hash,
+            &idx.try_into_sql_int()?,
+        ])
+        .context("Inserting message")?;
+    }
+
     Ok(())
 }
 
+/// Returns the block number, transaction hash and message index of every indexed L2 to L1
+/// message with the given hash, oldest first.
+///
+/// A message hash is not unique, since the same message may be sent more than once.
+pub(super) fn l2_to_l1_messages(
+    tx: &Transaction<'_>,
+    message_hash: H256,
+) -> anyhow::Result<Vec<(BlockNumber, TransactionHash, usize)>> {
+    let mut stmt = tx
+        .inner()
+        .prepare_cached(
+            r"SELECT block_number, transaction_hash, idx FROM l2_to_l1_messages
+            WHERE message_hash = ? ORDER BY block_number, transaction_hash, idx",
+        )
+        .context("Preparing statement")?;
+
+    let rows = stmt
+        .query_map(params![&message_hash.as_bytes()], |row| {
+            let block_number = row.get_block_number(0)?;
+            let transaction_hash = row.get_transaction_hash(1)?;
+            let idx = row.get_i64(2)?;
+            Ok((block_number, transaction_hash, idx))
+        })
+        .context("Executing query")?;
+
+    rows.map(|row| {
+        let (block_number, transaction_hash, idx) = row?;
+        let idx = usize::try_from(idx).context("Message index out of range")?;
+        Ok((block_number, transaction_hash, idx))
+    })
+    .collect()
+}
+
 pub(super) fn transaction(
     tx: &Transaction<'_>,
     transaction: TransactionHash,
 ) -> anyhow::Result<Option<StarknetTransaction>> {
     let mut stmt = tx
         .inner()
-        .prepare("SELECT tx FROM starknet_transactions WHERE hash = ?")
+        .prepare_cached("SELECT tx FROM starknet_transactions WHERE hash = ?")
         .context("Preparing statement")?;
 
     let mut rows = stmt
@@ -134,7 +324,7 @@ pub(super) fn transaction_with_receipt(
 ) -> anyhow::Result<Option<(StarknetTransaction, Receipt, BlockHash)>> {
     let mut stmt = tx
         .inner()
-        .prepare("SELECT tx, receipt, block_hash FROM starknet_transactions WHERE hash = ?1")
+        .prepare_cached("SELECT tx, receipt, block_hash FROM starknet_transactions WHERE hash = ?1")
         .context("Preparing statement")?;
 
     let mut rows = stmt.query(params![&txn_hash]).context("Executing query")?;
@@ -174,7 +364,7 @@ pub(super) fn transaction_at_block(
 
     let mut stmt = tx
         .inner()
-        .prepare("SELECT tx FROM starknet_transactions WHERE block_hash = ? AND idx = ?")
+        .prepare_cached("SELECT tx FROM starknet_transactions WHERE block_hash = ? AND idx = ?")
         .context("Preparing statement")?;
 
     let mut rows = stmt
@@ -202,21 +392,19 @@ pub(super) fn transaction_count(tx: &Transaction<'_>, block: BlockId) -> anyhow:
     match block {
         BlockId::Number(number) => tx
             .inner()
-            .query_row(
+            .prepare_cached(
                 "SELECT COUNT(*) FROM starknet_transactions
                 JOIN block_headers ON starknet_transactions.block_hash = block_headers.hash
                 WHERE number = ?1",
-                params![&number],
-                |row| row.get(0),
             )
+            .context("Preparing statement")?
+            .query_row(params![&number], |row| row.get(0))
             .context("Counting transactions"),
         BlockId::Hash(hash) => tx
             .inner()
-            .query_row(
-                "SELECT COUNT(*) FROM starknet_transactions WHERE block_hash = ?1",
-                params![&hash],
-                |row| row.get(0),
-            )
+            .prepare_cached("SELECT COUNT(*) FROM starknet_transactions WHERE block_hash = ?1")
+            .context("Preparing statement")?
+            .query_row(params![&hash], |row| row.get(0))
             .context("Counting transactions"),
         BlockId::Latest => {
             // First get the latest block
@@ -240,7 +428,7 @@ pub(super) fn transaction_data_for_block(
 
     let mut stmt = tx
         .inner()
-        .prepare(
+        .prepare_cached(
             "SELECT tx, receipt FROM starknet_transactions WHERE block_hash = ? ORDER BY idx ASC",
         )
         .context("Preparing statement")?;
@@ -283,7 +471,9 @@ pub(super) fn transactions_for_block(
 
     let mut stmt = tx
         .inner()
-        .prepare("SELECT tx FROM starknet_transactions WHERE block_hash = ? ORDER BY idx ASC")
+        .prepare_cached(
+            "SELECT tx FROM starknet_transactions WHERE block_hash = ? ORDER BY idx ASC",
+        )
         .context("Preparing statement")?;
 
     let mut rows = stmt
@@ -316,7 +506,9 @@ pub(super) fn receipts_for_block(
 
     let mut stmt = tx
         .inner()
-        .prepare("SELECT receipt FROM starknet_transactions WHERE block_hash = ? ORDER BY idx ASC")
+        .prepare_cached(
+            "SELECT receipt FROM starknet_transactions WHERE block_hash = ? ORDER BY idx ASC",
+        )
         .context("Preparing statement")?;
 
     let mut rows = stmt
@@ -349,7 +541,9 @@ pub(super) fn transaction_hashes_for_block(
 
     let mut stmt = tx
         .inner()
-        .prepare("SELECT hash FROM starknet_transactions WHERE block_hash = ? ORDER BY idx ASC")
+        .prepare_cached(
+            "SELECT hash FROM starknet_transactions WHERE block_hash = ? ORDER BY idx ASC",
+        )
         .context("Preparing statement")?;
 
     let data = stmt
@@ -365,11 +559,9 @@ pub(super) fn transaction_block_hash(
     hash: TransactionHash,
 ) -> anyhow::Result<Option<BlockHash>> {
     tx.inner()
-        .query_row(
-            "SELECT block_hash FROM starknet_transactions WHERE hash = ?",
-            params![&hash],
-            |row| row.get_block_hash(0),
-        )
+        .prepare_cached("SELECT block_hash FROM starknet_transactions WHERE hash = ?")
+        .context("Preparing statement")?
+        .query_row(params![&hash], |row| row.get_block_hash(0))
         .optional()
         .map_err(|e| e.into())
 }
@@ -2212,6 +2404,69 @@ mod tests {
         assert_eq!(invalid_block, None);
     }
 
+    #[test]
+    fn insert_in_batches() {
+        use pathfinder_common::event::Event;
+        use pathfinder_common::ContractAddress;
+        use pathfinder_crypto::Felt;
+
+        // Spans a full batch and smaller batches for the remainder.
+        const COUNT: usize = INSERT_BATCH_SIZE * 3 / 2 + 3;
+
+        let (_, _, body) = setup();
+        let (template, _) = body.first().unwrap().clone();
+        let header = BlockHeader::builder().finalize_with_hash(block_hash_bytes!(b"block hash"));
+
+        let body = (0..COUNT)
+            .map(|i| {
+                let transaction = StarknetTransaction {
+                    hash: TransactionHash(Felt::from_u64(i as u64)),
+                    ..template.clone()
+                };
+                let receipt = Receipt {
+                    transaction_hash: transaction.hash,
+                    transaction_index: TransactionIndex::new_or_panic(i as u64),
+                    events: vec![Event {
+                        data: vec![],
+                        from_address: ContractAddress(Felt::from_u64(i as u64)),
+                        keys: vec![],
+                    }],
+                    ..Default::default()
+                };
+                (transaction, receipt)
+            })
+            .collect::<Vec<_>>();
+
+        let mut db = crate::Storage::in_memory().unwrap().connection().unwrap();
+        let tx = db.transaction().unwrap();
+        tx.insert_block_header(&header).unwrap();
+        tx.insert_transaction_data(
+            header.hash,
+            header.number,
+            &body
+                .iter()
+                .cloned()
+                .map(|(transaction, receipt)| (transaction, Some(receipt)))
+                .collect::<Vec<_>>(),
+        )
+        .unwrap();
+
+        let result = super::transaction_data_for_block(&tx, header.number.into()).unwrap();
+        assert_eq!(result, Some(body));
+
+        let report = tx
+            .verify_bloom_filters(header.number, header.number)
+            .unwrap();
+        assert_eq!(
+            report,
+            crate::BloomFilterReport {
+                checked: 1,
+                missing: 0,
+                mismatched: 0,
+            }
+        );
+    }
+
     #[test]
     fn transactions_for_block() {
         let (mut db, header, body) = setup();
@@ -2267,4 +2522,43 @@ mod tests {
             super::transaction_block_hash(&tx, transaction_hash_bytes!(b"invalid hash")).unwrap();
         assert_eq!(invalid, None);
     }
+
+    #[test]
+    fn l2_to_l1_messages() {
+        use pathfinder_common::receipt::L2ToL1Message;
+        use pathfinder_common::EthereumAddress;
+
+        let (mut db, header, body) = setup();
+        let tx = db.transaction().unwrap();
+
+        let idx = 2;
+        let message = L2ToL1Message {
+            from_address: contract_address_bytes!(b"message from"),
+            payload: vec![
+                l2_to_l1_message_payload_elem_bytes!(b"payload 0"),
+                l2_to_l1_message_payload_elem_bytes!(b"payload 1"),
+            ],
+            to_address: EthereumAddress(primitive_types::H160::from_low_u64_be(0xabcd)),
+        };
+        let receipt = Receipt {
+            l2_to_l1_messages: vec![message.clone(), message.clone()],
+            ..body[idx].1.clone()
+        };
+        super::update_receipt(&tx, header.hash, idx, &receipt).unwrap();
+
+        let result = super::l2_to_l1_messages(&tx, message.calculate_message_hash()).unwrap();
+        let transaction_hash = body[idx].0.hash;
+        assert_eq!(
+            result,
+            vec![
+                (header.number, transaction_hash, 0),
+                (header.number, transaction_hash, 1)
+            ]
+        );
+
+        // Replacing the receipt must also replace its indexed messages.
+        super::update_receipt(&tx, header.hash, idx, &body[idx].1).unwrap();
+        let result = super::l2_to_l1_messages(&tx, message.calculate_message_hash()).unwrap();
+        assert!(result.is_empty());
+    }
 }
@@ -1,9 +1,8 @@
 use std::collections::HashMap;
 
 use anyhow::Context;
-use bitvec::prelude::Msb0;
-use bitvec::vec::BitVec;
 use pathfinder_common::prelude::*;
+pub use pathfinder_common::trie::{Child, Node, StoredNode};
 use pathfinder_crypto::Felt;
 
 use crate::prelude::*;
@@ -12,16 +11,240 @@ macros::create_trie_fns!(trie_class);
 macros::create_trie_fns!(trie_contracts);
 macros::create_trie_fns!(trie_storage);
 
+/// How trie nodes are keyed in storage. This is chosen when the database is created.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TrieLayout {
+    /// Nodes are only addressed by their index. Each commit stores all of its new nodes, even
+    /// if an identical node is already stored for another block or contract.
+    #[default]
+    Indexed,
+    /// Nodes are looked up by their hash before being stored, so identical subtrees are stored
+    /// once and shared across blocks and contracts. This requires an index on the node hashes,
+    /// which makes every write more expensive.
+    HashKeyed,
+}
+
+impl TrieLayout {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            TrieLayout::Indexed => "indexed",
+            TrieLayout::HashKeyed => "hash_keyed",
+        }
+    }
+
+    fn from_str(layout: &str) -> Option<Self> {
+        match layout {
+            "indexed" => Some(TrieLayout::Indexed),
+            "hash_keyed" => Some(TrieLayout::HashKeyed),
+            _ => None,
+        }
+    }
+}
+
+const TRIE_TABLES: [&str; 3] = ["trie_class", "trie_contracts", "trie_storage"];
+
+/// The tries whose nodes are kept in a [TrieNodeStore].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TrieTable {
+    Class,
+    Contracts,
+    Storage,
+}
+
+/// Storage for the nodes of the class, contract and storage tries.
+///
+/// The trie node tables take the bulk of the writes during sync, so this is the extension point
+/// for keeping them in a key-value store better suited to that load than SQLite, such as RocksDB
+/// or libmdbx. Everything else, including the roots which tie nodes to blocks, remains in
+/// SQLite. Use [StorageManager::with_trie_store](crate::StorageManager::with_trie_store) to
+/// replace the default [SqliteTrieStore].
+///
+/// Nodes are addressed by an index which the store assigns on insertion and which is only
+/// meaningful within the same [TrieTable]. Nodes are reference counted: a node is referenced by
+/// each of its stored parents and by each root table entry pointing to it, and is deleted once
+/// it is released by all of them.
+///
+/// Every call is made as part of the given SQLite transaction. An implementation must only make
+/// its writes durable once that transaction commits, and discard them otherwise, so that the
+/// nodes never disagree with the roots referencing them.
+pub trait TrieNodeStore: Send + Sync {
+    /// Stores the nodes reachable from `root` which are not already stored and returns the index
+    /// of the root. The root itself is stored with no references.
+    fn insert(
+        &self,
+        tx: &Transaction<'_>,
+        table: TrieTable,
+        root: Felt,
+        nodes: &HashMap<Felt, Node>,
+    ) -> anyhow::Result<u64>;
+
+    /// Increments the reference count of the node.
+    fn reference(&self, tx: &Transaction<'_>, table: TrieTable, index: u64) -> anyhow::Result<()>;
+
+    /// Decrements the reference count of each node, deleting the nodes which are no longer
+    /// referenced along with their no longer referenced descendants.
+    ///
+    /// Returns the number of nodes deleted.
+    fn release(
+        &self,
+        tx: &Transaction<'_>,
+        table: TrieTable,
+        indices: &[u64],
+    ) -> anyhow::Result<usize>;
+
+    fn node(
+        &self,
+        tx: &Transaction<'_>,
+        table: TrieTable,
+        index: u64,
+    ) -> anyhow::Result<Option<StoredNode>>;
+
+    fn hash(
+        &self,
+        tx: &Transaction<'_>,
+        table: TrieTable,
+        index: u64,
+    ) -> anyhow::Result<Option<Felt>>;
+}
+
+/// The default [TrieNodeStore], which keeps the nodes in the SQLite database.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SqliteTrieStore;
+
+impl TrieNodeStore for SqliteTrieStore {
+    fn insert(
+        &self,
+        tx: &Transaction<'_>,
+        table: TrieTable,
+        root: Felt,
+        nodes: &HashMap<Felt, Node>,
+    ) -> anyhow::Result<u64> {
+        match table {
+            TrieTable::Class => trie_class::insert(tx, root, nodes),
+            TrieTable::Contracts => trie_contracts::insert(tx, root, nodes),
+            TrieTable::Storage => trie_storage::insert(tx, root, nodes),
+        }
+    }
+
+    fn reference(&self, tx: &Transaction<'_>, table: TrieTable, index: u64) -> anyhow::Result<()> {
+        match table {
+            TrieTable::Class => trie_class::reference(tx, index),
+            TrieTable::Contracts => trie_contracts::reference(tx, index),
+            TrieTable::Storage => trie_storage::reference(tx, index),
+        }
+    }
+
+    fn release(
+        &self,
+        tx: &Transaction<'_>,
+        table: TrieTable,
+        indices: &[u64],
+    ) -> anyhow::Result<usize> {
+        match table {
+            TrieTable::Class => trie_class::release(tx, indices),
+            TrieTable::Contracts => trie_contracts::release(tx, indices),
+            TrieTable::Storage => trie_storage::release(tx, indices),
+        }
+    }
+
+    fn node(
+        &self,
+        tx: &Transaction<'_>,
+        table: TrieTable,
+        index: u64,
+    ) -> anyhow::Result<Option<StoredNode>> {
+        match table {
+            TrieTable::Class => trie_class::node(tx, index),
+            TrieTable::Contracts => trie_contracts::node(tx, index),
+            TrieTable::Storage => trie_storage::node(tx, index),
+        }
+    }
+
+    fn hash(
+        &self,
+        tx: &Transaction<'_>,
+        table: TrieTable,
+        index: u64,
+    ) -> anyhow::Result<Option<Felt>> {
+        match table {
+            TrieTable::Class => trie_class::hash(tx, index),
+            TrieTable::Contracts => trie_contracts::hash(tx, index),
+            TrieTable::Storage => trie_storage::hash(tx, index),
+        }
+    }
+}
+
+pub(super) fn trie_layout(tx: &Transaction<'_>) -> anyhow::Result<TrieLayout> {
+    let layout: String = tx
+        .inner()
+        .prepare_cached("SELECT layout FROM trie_layout")
+        .context("Preparing statement")?
+        .query_row([], |row| row.get(0))
+        .context("Querying trie layout")?;
+
+    TrieLayout::from_str(&layout).with_context(|| format!("Unknown trie layout {layout}"))
+}
+
+/// Changes the [TrieLayout], which is only possible as long as no trie nodes have been stored.
+pub(super) fn set_trie_layout(tx: &Transaction<'_>, layout: TrieLayout) -> anyhow::Result<()> {
+    if trie_layout(tx)? == layout {
+        return Ok(());
+    }
+
+    for table in TRIE_TABLES {
+        let is_empty = tx
+            .inner()
+            .query_row(
+                &format!("SELECT NOT EXISTS(SELECT 1 FROM {table})"),
+                [],
+                |row| row.get::<_, bool>(0),
+            )
+            .with_context(|| format!("Checking whether {table} is empty"))?;
+        anyhow::ensure!(
+            is_empty,
+            "The trie layout can only be changed before any trie nodes have been stored"
+        );
+    }
+
+    for table in TRIE_TABLES {
+        // The tables may have been moved to a separate database file, in which case the index
+        // must be created there as well.
+        let schema: String = tx
+            .inner()
+            .query_row(
+                "SELECT schema FROM pragma_table_list WHERE name = ?",
+                [table],
+                |row| row.get(0),
+            )
+            .with_context(|| format!("Querying schema of {table}"))?;
+        let sql = match layout {
+            TrieLayout::Indexed => format!("DROP INDEX IF EXISTS {schema}.{table}_hash"),
+            TrieLayout::HashKeyed => {
+                format!("CREATE INDEX {schema}.{table}_hash ON {table}(hash)")
+            }
+        };
+        tx.inner()
+            .execute(&sql, [])
+            .with_context(|| format!("Updating hash index of {table}"))?;
+    }
+
+    tx.inner()
+        .execute("UPDATE trie_layout SET layout = ?", params![&layout])
+        .context("Updating trie layout")?;
+
+    Ok(())
+}
+
 pub(super) fn class_root_index(
     tx: &Transaction<'_>,
     block_number: BlockNumber,
 ) -> anyhow::Result<Option<u64>> {
     tx.inner()
-        .query_row(
+        .prepare_cached(
             "SELECT root_index FROM class_roots WHERE block_number <= ? ORDER BY block_number DESC LIMIT 1",
-            params![&block_number],
-            |row| row.get::<_, Option<u64>>(0),
         )
+        .context("Preparing statement")?
+        .query_row(params![&block_number], |row| row.get::<_, Option<u64>>(0))
         .optional()
         .map(|x| x.flatten())
         .map_err(Into::into)
@@ -32,11 +255,11 @@ pub(super) fn storage_root_index(
     block_number: BlockNumber,
 ) -> anyhow::Result<Option<u64>> {
     tx.inner()
-        .query_row(
+        .prepare_cached(
             "SELECT root_index FROM storage_roots WHERE block_number <= ? ORDER BY block_number DESC LIMIT 1",
-            params![&block_number],
-            |row| row.get::<_, Option<u64>>(0),
         )
+        .context("Preparing statement")?
+        .query_row(params![&block_number], |row| row.get::<_, Option<u64>>(0))
         .optional()
         .map(|x| x.flatten())
         .map_err(Into::into)
@@ -48,11 +271,11 @@ pub(super) fn contract_root_index(
     contract: ContractAddress,
 ) -> anyhow::Result<Option<u64>> {
     tx.inner()
-        .query_row(
+        .prepare_cached(
             "SELECT root_index FROM contract_roots WHERE contract_address = ? AND block_number <= ? ORDER BY block_number DESC LIMIT 1",
-            params![&contract, &block_number],
-            |row| row.get::<_, Option<u64>>(0),
         )
+        .context("Preparing statement")?
+        .query_row(params![&contract, &block_number], |row| row.get::<_, Option<u64>>(0))
         .optional()
         .map(|x| x.flatten())
         .map_err(Into::into)
@@ -64,13 +287,13 @@ pub(super) fn contract_root(
     contract: ContractAddress,
 ) -> anyhow::Result<Option<ContractRoot>> {
     tx.inner()
-        .query_row(
+        .prepare_cached(
             r"SELECT hash FROM trie_contracts WHERE idx = (
                 SELECT root_index FROM contract_roots WHERE block_number <= ? AND contract_address = ? ORDER BY block_number DESC LIMIT 1
             )",
-            params![&block_number, &contract],
-            |row| row.get_contract_root(0),
         )
+        .context("Preparing statement")?
+        .query_row(params![&block_number, &contract], |row| row.get_contract_root(0))
         .optional()
         .map_err(Into::into)
 }
@@ -80,10 +303,17 @@ pub(super) fn insert_class_root(
     block_number: BlockNumber,
     root: Option<u64>,
 ) -> anyhow::Result<()> {
-    tx.inner().execute(
-        "INSERT INTO class_roots (block_number, root_index) VALUES(?, ?)",
-        params![&block_number, &root],
-    )?;
+    tx.inner()
+        .prepare_cached("INSERT INTO class_roots (block_number, root_index) VALUES(?, ?)")
+        .context("Preparing statement")?
+        .execute(params![&block_number, &root])?;
+
+    if let Some(root) = root {
+        tx.trie_store
+            .reference(tx, TrieTable::Class, root)
+            .context("Referencing class root node")?;
+    }
+
     Ok(())
 }
 
@@ -93,8 +323,13 @@ pub(super) fn insert_contract_state_hash(
     contract: ContractAddress,
     state_hash: ContractStateHash,
 ) -> anyhow::Result<()> {
-    tx.inner().execute("INSERT INTO contract_state_hashes(block_number, contract_address, state_hash) VALUES(?,?,?)", 
-        params![&block_number, &contract, &state_hash])?;
+    tx.inner()
+        .prepare_cached(
+            r"INSERT INTO contract_state_hashes(block_number, contract_address, state_hash)
+            VALUES(?,?,?)",
+        )
+        .context("Preparing statement")?
+        .execute(params![&block_number, &contract, &state_hash])?;
 
     Ok(())
 }
@@ -105,11 +340,11 @@ pub(super) fn contract_state_hash(
     contract: ContractAddress,
 ) -> anyhow::Result<Option<ContractStateHash>> {
     tx.inner()
-        .query_row(
+        .prepare_cached(
             "SELECT state_hash FROM contract_state_hashes WHERE contract_address = ? AND block_number <= ? ORDER BY block_number DESC LIMIT 1",
-            params![&contract, &block_number],
-            |row| row.get_contract_state_hash(0),
         )
+        .context("Preparing statement")?
+        .query_row(params![&contract, &block_number], |row| row.get_contract_state_hash(0))
         .optional()
         .map_err(Into::into)
 }
@@ -119,10 +354,17 @@ pub(super) fn insert_storage_root(
     block_number: BlockNumber,
     root: Option<u64>,
 ) -> anyhow::Result<()> {
-    tx.inner().execute(
-        "INSERT INTO storage_roots (block_number, root_index) VALUES(?, ?)",
-        params![&block_number, &root],
-    )?;
+    tx.inner()
+        .prepare_cached("INSERT INTO storage_roots (block_number, root_index) VALUES(?, ?)")
+        .context("Preparing statement")?
+        .execute(params![&block_number, &root])?;
+
+    if let Some(root) = root {
+        tx.trie_store
+            .reference(tx, TrieTable::Storage, root)
+            .context("Referencing storage root node")?;
+    }
+
     Ok(())
 }
 
@@ -132,13 +374,152 @@ pub(super) fn insert_contract_root(
     contract: ContractAddress,
     root: Option<u64>,
 ) -> anyhow::Result<()> {
-    tx.inner().execute(
-        "INSERT INTO contract_roots (block_number, contract_address, root_index) VALUES(?, ?, ?)",
-        params![&block_number, &contract, &root],
-    )?;
+    tx.inner()
+        .prepare_cached(
+            r"INSERT INTO contract_roots (block_number, contract_address, root_index)
+            VALUES(?, ?, ?)",
+        )
+        .context("Preparing statement")?
+        .execute(params![&block_number, &contract, &root])?;
+
+    if let Some(root) = root {
+        tx.trie_store
+            .reference(tx, TrieTable::Contracts, root)
+            .context("Referencing contract root node")?;
+    }
+
+    Ok(())
+}
+
+/// Removes the trie roots of the given block and deletes all trie nodes which are no
+/// longer referenced as a result.
+pub(super) fn purge_roots(tx: &Transaction<'_>, block_number: BlockNumber) -> anyhow::Result<()> {
+    let roots = delete_roots(
+        tx,
+        "DELETE FROM class_roots WHERE block_number = ? RETURNING root_index",
+        block_number,
+    )
+    .context("Deleting block from class_roots table")?;
+    tx.trie_store
+        .release(tx, TrieTable::Class, &roots)
+        .context("Releasing class trie nodes")?;
+
+    let roots = delete_roots(
+        tx,
+        "DELETE FROM storage_roots WHERE block_number = ? RETURNING root_index",
+        block_number,
+    )
+    .context("Deleting block from storage_roots table")?;
+    tx.trie_store
+        .release(tx, TrieTable::Storage, &roots)
+        .context("Releasing storage trie nodes")?;
+
+    let roots = delete_roots(
+        tx,
+        "DELETE FROM contract_roots WHERE block_number = ? RETURNING root_index",
+        block_number,
+    )
+    .context("Deleting block from contract_roots table")?;
+    tx.trie_store
+        .release(tx, TrieTable::Contracts, &roots)
+        .context("Releasing contract trie nodes")?;
+
     Ok(())
 }
 
+/// Removes all trie roots which are superseded at `block_number`, and deletes the trie nodes
+/// which are no longer referenced as a result. The trie state at `block_number` and later blocks
+/// remains intact, but historical trie state before it is no longer available.
+///
+/// Returns the number of trie nodes deleted.
+pub(super) fn prune_tries(
+    tx: &Transaction<'_>,
+    block_number: BlockNumber,
+) -> anyhow::Result<usize> {
+    let roots = delete_roots(
+        tx,
+        r"DELETE FROM class_roots WHERE block_number < (
+            SELECT block_number FROM class_roots WHERE block_number <= ?1 ORDER BY block_number DESC LIMIT 1
+        ) RETURNING root_index",
+        block_number,
+    )
+    .context("Pruning class_roots table")?;
+    let mut deleted = tx
+        .trie_store
+        .release(tx, TrieTable::Class, &roots)
+        .context("Releasing class trie nodes")?;
+
+    let roots = delete_roots(
+        tx,
+        r"DELETE FROM storage_roots WHERE block_number < (
+            SELECT block_number FROM storage_roots WHERE block_number <= ?1 ORDER BY block_number DESC LIMIT 1
+        ) RETURNING root_index",
+        block_number,
+    )
+    .context("Pruning storage_roots table")?;
+    deleted += tx
+        .trie_store
+        .release(tx, TrieTable::Storage, &roots)
+        .context("Releasing storage trie nodes")?;
+
+    let roots = delete_roots(
+        tx,
+        r"DELETE FROM contract_roots WHERE block_number < ?1 AND EXISTS (
+            SELECT 1 FROM contract_roots AS newer
+            WHERE newer.contract_address = contract_roots.contract_address
+            AND newer.block_number > contract_roots.block_number
+            AND newer.block_number <= ?1
+        ) RETURNING root_index",
+        block_number,
+    )
+    .context("Pruning contract_roots table")?;
+    deleted += tx
+        .trie_store
+        .release(tx, TrieTable::Contracts, &roots)
+        .context("Releasing contract trie nodes")?;
+
+    tx.inner()
+        .execute(
+            r"INSERT INTO trie_history (id, oldest_block) VALUES (0, ?)
+            ON CONFLICT(id) DO UPDATE SET oldest_block = MAX(oldest_block, excluded.oldest_block)",
+            params![&block_number],
+        )
+        .context("Updating trie history")?;
+
+    Ok(deleted)
+}
+
+/// Returns the oldest block whose trie state is available, or [None] if the tries have never
+/// been pruned.
+pub(super) fn oldest_trie_block(tx: &Transaction<'_>) -> anyhow::Result<Option<BlockNumber>> {
+    tx.inner()
+        .query_row("SELECT oldest_block FROM trie_history", [], |row| {
+            row.get_block_number(0)
+        })
+        .optional()
+        .context("Querying trie history")
+}
+
+/// Executes a `DELETE .. RETURNING root_index` statement on a roots table and returns
+/// the root indices which are no longer referenced by the deleted rows.
+fn delete_roots(
+    tx: &Transaction<'_>,
+    sql: &str,
+    block_number: BlockNumber,
+) -> anyhow::Result<Vec<u64>> {
+    let mut stmt = tx
+        .inner()
+        .prepare_cached(sql)
+        .context("Preparing statement")?;
+    let roots = stmt
+        .query_map(params![&block_number], |row| row.get::<_, Option<u64>>(0))
+        .context("Executing statement")?
+        .filter_map(|root| root.transpose())
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(roots)
+}
+
 mod macros {
     /// Generates the `insert`, `node` and `hash` trie functions for the given table name, within
     /// a module with the table name.
@@ -148,6 +529,13 @@ mod macros {
                 use super::*;
 
                 /// Stores the node data for this trie and returns the index of the root.
+                ///
+                /// New nodes start with a reference count of zero and increment the count of
+                /// their children. The root only gains a reference once it is assigned to a
+                /// block via the relevant roots table.
+                ///
+                /// With the [TrieLayout::HashKeyed] layout, nodes which are already stored are
+                /// reused together with their subtrees instead of being stored again.
                 pub fn insert(
                     tx: &Transaction<'_>,
                     root: Felt,
@@ -158,10 +546,21 @@ mod macros {
                         .prepare_cached(concat!(
                             "INSERT INTO ",
                             stringify!($table),
-                            " (hash, data) VALUES(?, ?) RETURNING idx",
+                            " (hash, data, ref_count) VALUES(?, ?, 0) RETURNING idx",
                         ))
                         .context("Creating insert statement")?;
 
+                    let hash_keyed = trie_layout(tx)? == TrieLayout::HashKeyed;
+                    let mut find = tx
+                        .inner()
+                        .prepare_cached(concat!(
+                            "SELECT idx FROM ",
+                            stringify!($table),
+                            " WHERE hash = ? LIMIT 1",
+                        ))
+                        .context("Creating find statement")?;
+
+                    let mut indices = HashMap::new();
                     let mut to_insert = Vec::new();
                     let mut to_process = vec![Child::Hash(root)];
 
@@ -175,6 +574,20 @@ mod macros {
                             continue;
                         };
 
+                        if hash_keyed && !indices.contains_key(&hash) {
+                            let existing: Option<u64> = find
+                                .query_row(params![&hash.as_be_bytes().as_slice()], |row| {
+                                    row.get(0)
+                                })
+                                .optional()
+                                .context("Querying existing node")?;
+
+                            if let Some(idx) = existing {
+                                indices.insert(hash, idx);
+                                continue;
+                            }
+                        }
+
                         let node = nodes.get(&hash).context("New node data is missing")?;
                         to_insert.push(hash);
 
@@ -191,13 +604,16 @@ mod macros {
                         }
                     }
 
-                    let mut indices = HashMap::new();
-
                     // Reusable (and oversized) buffer for encoding.
                     let mut buffer = vec![0u8; 256];
 
                     // Insert nodes in reverse to ensure children always have an assigned index for the parent to use.
                     for hash in to_insert.into_iter().rev() {
+                        // Identical subtrees are only stored once and shared by their parents.
+                        if indices.contains_key(&hash) {
+                            continue;
+                        }
+
                         let node = nodes
                             .get(&hash)
                             .expect("Node must exist as hash is dependent on this");
@@ -213,6 +629,10 @@ mod macros {
                             )
                             .context("Inserting node")?;
 
+                        for child in node.children() {
+                            reference(tx, child).context("Referencing child node")?;
+                        }
+
                         indices.insert(hash, idx);
                     }
 
@@ -221,6 +641,76 @@ mod macros {
                         .expect("Root index must exist as we just inserted it"))
                 }
 
+                /// Increments the reference count of the node with the given index.
+                pub fn reference(tx: &Transaction<'_>, index: u64) -> anyhow::Result<()> {
+                    let mut stmt = tx
+                        .inner()
+                        .prepare_cached(concat!(
+                            "UPDATE ",
+                            stringify!($table),
+                            " SET ref_count = ref_count + 1 WHERE idx = ?",
+                        ))
+                        .context("Creating reference statement")?;
+
+                    stmt.execute(params![&index])
+                        .context("Incrementing reference count")?;
+
+                    Ok(())
+                }
+
+                /// Decrements the reference count of each of the given nodes, deleting any
+                /// node which is no longer referenced. This cascades to the children of the
+                /// deleted nodes.
+                ///
+                /// Nodes without a reference count (i.e. created before reference counting was
+                /// introduced) are never deleted.
+                ///
+                /// Returns the number of nodes deleted.
+                pub fn release(tx: &Transaction<'_>, indices: &[u64]) -> anyhow::Result<usize> {
+                    let mut decrement = tx
+                        .inner()
+                        .prepare_cached(concat!(
+                            "UPDATE ",
+                            stringify!($table),
+                            " SET ref_count = ref_count - 1 WHERE idx = ? RETURNING ref_count, data",
+                        ))
+                        .context("Creating release statement")?;
+
+                    let mut delete = tx
+                        .inner()
+                        .prepare_cached(concat!(
+                            "DELETE FROM ",
+                            stringify!($table),
+                            " WHERE idx = ?",
+                        ))
+                        .context("Creating delete statement")?;
+
+                    let mut to_release = indices.to_vec();
+                    let mut deleted = 0;
+
+                    while let Some(index) = to_release.pop() {
+                        let Some((ref_count, data)): Option<(Option<i64>, Vec<u8>)> = decrement
+                            .query_row(params![&index], |row| Ok((row.get(0)?, row.get(1)?)))
+                            .optional()
+                            .context("Decrementing reference count")?
+                        else {
+                            continue;
+                        };
+
+                        if ref_count != Some(0) {
+                            continue;
+                        }
+
+                        let node = StoredNode::decode(&data).context("Decoding node")?;
+                        delete.execute(params![&index]).context("Deleting node")?;
+                        deleted += 1;
+
+                        to_release.extend(node.children());
+                    }
+
+                    Ok(deleted)
+                }
+
                 /// Returns the node with the given index.
                 pub fn node(
                     tx: &Transaction<'_>,
@@ -273,36 +763,6 @@ mod macros {
     pub(super) use create_trie_fns;
 }
 
-#[derive(Clone, Debug)]
-pub enum Node {
-    Binary {
-        left: Child,
-        right: Child,
-    },
-    Edge {
-        child: Child,
-        path: BitVec<u8, Msb0>,
-    },
-    LeafBinary,
-    LeafEdge {
-        path: BitVec<u8, Msb0>,
-    },
-}
-
-#[derive(Clone, Debug)]
-pub enum Child {
-    Id(u64),
-    Hash(Felt),
-}
-
-#[derive(Clone, Debug, PartialEq)]
-pub enum StoredNode {
-    Binary { left: u64, right: u64 },
-    Edge { child: u64, path: BitVec<u8, Msb0> },
-    LeafBinary,
-    LeafEdge { path: BitVec<u8, Msb0> },
-}
-
 #[derive(Clone, Debug, bincode::Encode, bincode::BorrowDecode)]
 enum StoredSerde {
     Binary { left: u64, right: u64 },
@@ -311,10 +771,17 @@ enum StoredSerde {
     LeafEdge { path: Vec<u8> },
 }
 
-impl StoredNode {
+/// The encoding of [StoredNode] in the database.
+trait NodeCodec: Sized {
     const CODEC_CFG: bincode::config::Configuration = bincode::config::standard();
 
-    /// Writes the [StoredNode] into `buffer` and returns the number of bytes written.
+    /// Writes the node into `buffer` and returns the number of bytes written.
+    fn encode(&self, buffer: &mut [u8]) -> Result<usize, bincode::error::EncodeError>;
+
+    fn decode(data: &[u8]) -> Result<Self, bincode::error::DecodeError>;
+}
+
+impl NodeCodec for StoredNode {
     fn encode(&self, buffer: &mut [u8]) -> Result<usize, bincode::error::EncodeError> {
         let helper = match self {
             Self::Binary { left, right } => StoredSerde::Binary {
@@ -379,76 +846,64 @@ impl StoredNode {
     }
 }
 
+/// Exposes the node encoding to the fuzz targets in `fuzz/`.
+#[cfg(fuzzing)]
+pub fn fuzz_encode_node(
+    node: &StoredNode,
+    buffer: &mut [u8],
+) -> Result<usize, bincode::error::EncodeError> {
+    node.encode(buffer)
+}
+
+/// Exposes the node decoding to the fuzz targets in `fuzz/`.
+#[cfg(fuzzing)]
+pub fn fuzz_decode_node(data: &[u8]) -> Result<StoredNode, bincode::error::DecodeError> {
+    StoredNode::decode(data)
+}
+
 #[cfg(test)]
-impl StoredNode {
-    fn into_binary(self) -> Option<(u64, u64)> {
-        match self {
-            Self::Binary { left, right } => Some((left, right)),
-            _ => None,
-        }
-    }
+mod tests {
+    use super::*;
+    use bitvec::prelude::Msb0;
+    use bitvec::vec::BitVec;
+    use pathfinder_common::macro_prelude::*;
 
-    fn into_edge(self) -> Option<(u64, BitVec<u8, Msb0>)> {
-        match self {
-            Self::Edge { child, path } => Some((child, path)),
-            _ => None,
-        }
+    trait StoredNodeExt {
+        fn into_binary(self) -> Option<(u64, u64)>;
+        fn into_edge(self) -> Option<(u64, BitVec<u8, Msb0>)>;
+        fn into_binary_leaf(self) -> Option<()>;
+        fn into_edge_leaf(self) -> Option<BitVec<u8, Msb0>>;
     }
 
-    fn into_binary_leaf(self) -> Option<()> {
-        match self {
-            Self::LeafBinary => Some(()),
-            _ => None,
+    impl StoredNodeExt for StoredNode {
+        fn into_binary(self) -> Option<(u64, u64)> {
+            match self {
+                Self::Binary { left, right } => Some((left, right)),
+                _ => None,
+            }
         }
-    }
 
-    fn into_edge_leaf(self) -> Option<BitVec<u8, Msb0>> {
-        match self {
-            Self::LeafEdge { path } => Some(path),
-            _ => None,
+        fn into_edge(self) -> Option<(u64, BitVec<u8, Msb0>)> {
+            match self {
+                Self::Edge { child, path } => Some((child, path)),
+                _ => None,
+            }
         }
-    }
-}
 
-impl Node {
-    fn as_stored(&self, indices: &HashMap<Felt, u64>) -> anyhow::Result<StoredNode> {
-        let node = match self {
-            Node::Binary { left, right } => {
-                let left = match left {
-                    Child::Id(id) => *id,
-                    Child::Hash(hash) => *indices.get(hash).context("Left child index missing")?,
-                };
-
-                let right = match right {
-                    Child::Id(id) => *id,
-                    Child::Hash(hash) => *indices.get(hash).context("Right child index missing")?,
-                };
-
-                StoredNode::Binary { left, right }
+        fn into_binary_leaf(self) -> Option<()> {
+            match self {
+                Self::LeafBinary => Some(()),
+                _ => None,
             }
-            Node::Edge { child, path } => {
-                let child = match child {
-                    Child::Id(id) => id,
-                    Child::Hash(hash) => indices.get(hash).context("Child index missing")?,
-                };
+        }
 
-                StoredNode::Edge {
-                    child: *child,
-                    path: path.clone(),
-                }
+        fn into_edge_leaf(self) -> Option<BitVec<u8, Msb0>> {
+            match self {
+                Self::LeafEdge { path } => Some(path),
+                _ => None,
             }
-            Node::LeafEdge { path } => StoredNode::LeafEdge { path: path.clone() },
-            Node::LeafBinary => StoredNode::LeafBinary,
-        };
-
-        Ok(node)
+        }
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use pathfinder_common::macro_prelude::*;
 
     #[test]
     fn class_roots() {
@@ -645,10 +1100,15 @@ mod tests {
         fn setup_db() -> rusqlite::Connection {
             let db = rusqlite::Connection::open_in_memory().unwrap();
             db.execute(
-                "CREATE TABLE test_table (idx INTEGER PRIMARY KEY,hash BLOB NOT NULL,data BLOB) ",
+                "CREATE TABLE test_table (idx INTEGER PRIMARY KEY,hash BLOB NOT NULL,data BLOB,ref_count INTEGER) ",
                 [],
             )
             .unwrap();
+            db.execute_batch(
+                "CREATE TABLE trie_layout (id INTEGER PRIMARY KEY, layout TEXT NOT NULL);
+                INSERT INTO trie_layout (id, layout) VALUES (0, 'indexed');",
+            )
+            .unwrap();
 
             db
         }
@@ -831,6 +1291,244 @@ mod tests {
         }
     }
 
+    mod reference_counting {
+        use super::*;
+
+        /// Returns the number of nodes stored in the given trie table.
+        fn node_count(tx: &Transaction<'_>, table: &str) -> usize {
+            tx.inner()
+                .query_row(&format!("SELECT COUNT(1) FROM {table}"), [], |row| {
+                    row.get(0)
+                })
+                .unwrap()
+        }
+
+        /// Creates a tree with a root binary node connecting two edge leaves.
+        fn simple_tree(root: Felt, left: Child) -> HashMap<Felt, Node> {
+            let right = felt_bytes!(b"right");
+            let mut nodes = HashMap::new();
+            nodes.insert(
+                root,
+                Node::Binary {
+                    left,
+                    right: Child::Hash(right),
+                },
+            );
+            nodes.insert(
+                right,
+                Node::LeafEdge {
+                    path: bitvec::bitvec![u8, Msb0; 1, 0, 1],
+                },
+            );
+            nodes
+        }
+
+        #[test]
+        fn shared_nodes_are_kept_until_unreferenced() {
+            let mut db = crate::Storage::in_memory().unwrap().connection().unwrap();
+            let tx = db.transaction().unwrap();
+
+            let left = felt_bytes!(b"left");
+            let root0 = felt_bytes!(b"root 0");
+            let mut nodes = simple_tree(root0, Child::Hash(left));
+            nodes.insert(
+                left,
+                Node::LeafEdge {
+                    path: bitvec::bitvec![u8, Msb0; 0, 0, 1],
+                },
+            );
+            let root0_idx = trie_storage::insert(&tx, root0, &nodes).unwrap();
+            insert_storage_root(&tx, BlockNumber::GENESIS, Some(root0_idx)).unwrap();
+            assert_eq!(node_count(&tx, "trie_storage"), 3);
+
+            // The second tree re-uses the left child of the first tree.
+            let StoredNode::Binary {
+                left: shared_idx, ..
+            } = trie_storage::node(&tx, root0_idx).unwrap().unwrap()
+            else {
+                panic!("Root should be a binary node");
+            };
+            let root1 = felt_bytes!(b"root 1");
+            let nodes = simple_tree(root1, Child::Id(shared_idx));
+            let root1_idx = trie_storage::insert(&tx, root1, &nodes).unwrap();
+            insert_storage_root(&tx, BlockNumber::GENESIS + 1, Some(root1_idx)).unwrap();
+            assert_eq!(node_count(&tx, "trie_storage"), 5);
+
+            // Removing the first tree keeps the shared node.
+            let deleted = trie_storage::release(&tx, &[root0_idx]).unwrap();
+            assert_eq!(deleted, 2);
+            assert!(trie_storage::node(&tx, root0_idx).unwrap().is_none());
+            assert!(trie_storage::node(&tx, shared_idx).unwrap().is_some());
+
+            let deleted = trie_storage::release(&tx, &[root1_idx]).unwrap();
+            assert_eq!(deleted, 3);
+            assert_eq!(node_count(&tx, "trie_storage"), 0);
+        }
+
+        #[test]
+        fn hash_keyed_layout_shares_identical_nodes() {
+            let mut db = crate::Storage::in_memory().unwrap().connection().unwrap();
+            let tx = db.transaction().unwrap();
+            set_trie_layout(&tx, TrieLayout::HashKeyed).unwrap();
+
+            let left = felt_bytes!(b"left");
+            let tree = |root| {
+                let mut nodes = simple_tree(root, Child::Hash(left));
+                nodes.insert(
+                    left,
+                    Node::LeafEdge {
+                        path: bitvec::bitvec![u8, Msb0; 0, 0, 1],
+                    },
+                );
+                nodes
+            };
+
+            let root0 = felt_bytes!(b"root 0");
+            let root0_idx = trie_storage::insert(&tx, root0, &tree(root0)).unwrap();
+            insert_storage_root(&tx, BlockNumber::GENESIS, Some(root0_idx)).unwrap();
+            assert_eq!(node_count(&tx, "trie_storage"), 3);
+
+            // Only the new root is stored, both children are already present.
+            let root1 = felt_bytes!(b"root 1");
+            let root1_idx = trie_storage::insert(&tx, root1, &tree(root1)).unwrap();
+            insert_storage_root(&tx, BlockNumber::GENESIS + 1, Some(root1_idx)).unwrap();
+            assert_eq!(node_count(&tx, "trie_storage"), 4);
+
+            // Removing the first tree keeps the shared nodes.
+            let deleted = trie_storage::release(&tx, &[root0_idx]).unwrap();
+            assert_eq!(deleted, 1);
+
+            let deleted = trie_storage::release(&tx, &[root1_idx]).unwrap();
+            assert_eq!(deleted, 3);
+            assert_eq!(node_count(&tx, "trie_storage"), 0);
+        }
+
+        #[test]
+        fn untracked_nodes_are_never_deleted() {
+            let mut db = crate::Storage::in_memory().unwrap().connection().unwrap();
+            let tx = db.transaction().unwrap();
+
+            let root = felt_bytes!(b"root");
+            let mut nodes = HashMap::new();
+            nodes.insert(root, Node::LeafBinary);
+            let idx = trie_class::insert(&tx, root, &nodes).unwrap();
+
+            // Mimic a node created before reference counting was introduced.
+            tx.inner()
+                .execute("UPDATE trie_class SET ref_count = NULL", [])
+                .unwrap();
+            insert_class_root(&tx, BlockNumber::GENESIS, Some(idx)).unwrap();
+
+            purge_roots(&tx, BlockNumber::GENESIS).unwrap();
+            assert_eq!(class_root_index(&tx, BlockNumber::GENESIS).unwrap(), None);
+            assert!(trie_class::node(&tx, idx).unwrap().is_some());
+        }
+
+        #[test]
+        fn purge_roots_deletes_orphaned_nodes() {
+            let mut db = crate::Storage::in_memory().unwrap().connection().unwrap();
+            let tx = db.transaction().unwrap();
+
+            let contract = contract_address_bytes!(b"contract");
+            let mut nodes = HashMap::new();
+            let root0 = felt_bytes!(b"root 0");
+            nodes.insert(root0, Node::LeafBinary);
+            let idx0 = trie_contracts::insert(&tx, root0, &nodes).unwrap();
+            insert_contract_root(&tx, BlockNumber::GENESIS, contract, Some(idx0)).unwrap();
+
+            nodes.clear();
+            let root1 = felt_bytes!(b"root 1");
+            nodes.insert(root1, Node::LeafBinary);
+            let idx1 = trie_contracts::insert(&tx, root1, &nodes).unwrap();
+            insert_contract_root(&tx, BlockNumber::GENESIS + 1, contract, Some(idx1)).unwrap();
+
+            purge_roots(&tx, BlockNumber::GENESIS + 1).unwrap();
+
+            assert!(trie_contracts::node(&tx, idx1).unwrap().is_none());
+            assert!(trie_contracts::node(&tx, idx0).unwrap().is_some());
+            assert_eq!(
+                contract_root_index(&tx, BlockNumber::GENESIS + 1, contract).unwrap(),
+                Some(idx0)
+            );
+        }
+
+        #[test]
+        fn prune_keeps_state_at_target_block() {
+            let mut db = crate::Storage::in_memory().unwrap().connection().unwrap();
+            let tx = db.transaction().unwrap();
+
+            let c1 = contract_address_bytes!(b"first");
+            let c2 = contract_address_bytes!(b"second");
+
+            let insert = |table_root: &[u8], block: BlockNumber| {
+                let mut nodes = HashMap::new();
+                let root = Felt::from_be_slice(table_root).unwrap();
+                nodes.insert(root, Node::LeafBinary);
+
+                let storage_idx = trie_storage::insert(&tx, root, &nodes).unwrap();
+                insert_storage_root(&tx, block, Some(storage_idx)).unwrap();
+                let contract_idx = trie_contracts::insert(&tx, root, &nodes).unwrap();
+                insert_contract_root(&tx, block, c1, Some(contract_idx)).unwrap();
+
+                (storage_idx, contract_idx)
+            };
+
+            let (storage0, c1_0) = insert(b"root 0", BlockNumber::GENESIS);
+            let (storage1, c1_1) = insert(b"root 1", BlockNumber::GENESIS + 1);
+            let (storage2, c1_2) = insert(b"root 2", BlockNumber::GENESIS + 2);
+
+            // Second contract is only updated in genesis, so its root must remain.
+            let mut nodes = HashMap::new();
+            nodes.insert(felt_bytes!(b"c2"), Node::LeafBinary);
+            let c2_0 = trie_contracts::insert(&tx, felt_bytes!(b"c2"), &nodes).unwrap();
+            insert_contract_root(&tx, BlockNumber::GENESIS, c2, Some(c2_0)).unwrap();
+
+            assert_eq!(oldest_trie_block(&tx).unwrap(), None);
+
+            let deleted = prune_tries(&tx, BlockNumber::GENESIS + 1).unwrap();
+            assert_eq!(deleted, 2);
+            assert_eq!(
+                oldest_trie_block(&tx).unwrap(),
+                Some(BlockNumber::GENESIS + 1)
+            );
+
+            assert!(trie_storage::node(&tx, storage0).unwrap().is_none());
+            assert!(trie_contracts::node(&tx, c1_0).unwrap().is_none());
+            for idx in [storage1, storage2] {
+                assert!(trie_storage::node(&tx, idx).unwrap().is_some());
+            }
+            for idx in [c1_1, c1_2, c2_0] {
+                assert!(trie_contracts::node(&tx, idx).unwrap().is_some());
+            }
+
+            assert_eq!(
+                storage_root_index(&tx, BlockNumber::GENESIS + 1).unwrap(),
+                Some(storage1)
+            );
+            assert_eq!(
+                contract_root_index(&tx, BlockNumber::GENESIS + 1, c2).unwrap(),
+                Some(c2_0)
+            );
+        }
+    }
+
+    #[test]
+    fn trie_layout_is_fixed_once_nodes_are_stored() {
+        let mut db = crate::Storage::in_memory().unwrap().connection().unwrap();
+        let tx = db.transaction().unwrap();
+
+        assert_eq!(trie_layout(&tx).unwrap(), TrieLayout::Indexed);
+
+        let root = felt_bytes!(b"root");
+        let mut nodes = HashMap::new();
+        nodes.insert(root, Node::LeafBinary);
+        trie_class::insert(&tx, root, &nodes).unwrap();
+
+        set_trie_layout(&tx, TrieLayout::HashKeyed).unwrap_err();
+        // Setting the current layout is a no-op.
+        set_trie_layout(&tx, TrieLayout::Indexed).unwrap();
+    }
+
     #[test]
     fn contract_state_hash() {
         let mut db = crate::Storage::in_memory().unwrap().connection().unwrap();
@@ -1,6 +1,7 @@
 //! Local storage.
 //!
-//! Currently this consists of a Sqlite backend implementation.
+//! Currently this consists of a Sqlite backend implementation. The trie nodes can instead be kept
+//! in an alternative backend by implementing [TrieNodeStore].
 
 // This is intended for internal use only -- do not make public.
 mod prelude;
@@ -9,7 +10,9 @@ mod bloom;
 mod connection;
 pub mod fake;
 mod params;
+mod profile;
 mod schema;
+mod split;
 pub mod test_utils;
 
 use std::num::NonZeroU32;
@@ -17,6 +20,7 @@ use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 pub use connection::*;
+pub use split::SplitDatabases;
 
 use pathfinder_common::{BlockHash, BlockNumber};
 
@@ -27,6 +31,16 @@ use r2d2_sqlite::SqliteConnectionManager;
 /// Sqlite key used for the PRAGMA user version.
 const VERSION_KEY: &str = "user_version";
 
+/// The number of prepared statements cached by each connection. This must exceed the number of
+/// statements prepared with `prepare_cached` by the hot paths, as otherwise they evict each other.
+const STATEMENT_CACHE_CAPACITY: usize = 128;
+
+/// The flags of connections to a database opened with [Storage::open_read_only]. Databases
+/// attached to such a connection are read-only as well.
+const READ_ONLY_FLAGS: rusqlite::OpenFlags = rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY
+    .union(rusqlite::OpenFlags::SQLITE_OPEN_URI)
+    .union(rusqlite::OpenFlags::SQLITE_OPEN_NO_MUTEX);
+
 /// Specifies the [journal mode](https://sqlite.org/pragma.html#pragma_journal_mode)
 /// of the [Storage].
 #[derive(Clone, Copy)]
@@ -35,6 +49,55 @@ pub enum JournalMode {
     WAL,
 }
 
+/// The passphrase from which SQLCipher derives the database's encryption key.
+#[derive(Clone)]
+pub struct EncryptionKey(String);
+
+impl EncryptionKey {
+    pub fn new(passphrase: String) -> Self {
+        Self(passphrase)
+    }
+}
+
+impl std::fmt::Debug for EncryptionKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("EncryptionKey(..)")
+    }
+}
+
+/// Whether an existing database is migrated to the latest schema when it is opened.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Migration {
+    #[default]
+    Auto,
+    /// Opening a database which requires migrations fails with
+    /// [SchemaError::MigrationRequired], so that a production database is only migrated on
+    /// purpose. New databases are still created.
+    Off,
+}
+
+/// Why a database cannot be opened by this version of pathfinder.
+#[derive(Debug, Clone, thiserror::Error, PartialEq, Eq)]
+pub enum SchemaError {
+    #[error(
+        "Database schema version {version} needs to be migrated to version {latest}, but \
+        migrations are disabled"
+    )]
+    MigrationRequired { version: usize, latest: usize },
+    #[error(
+        "Database schema version {version} is too old to be migrated, the oldest supported \
+        version is {oldest}"
+    )]
+    TooOld { version: usize, oldest: usize },
+    #[error(
+        "Database schema version {version} is from a newer version of pathfinder, this version \
+        supports up to {latest}"
+    )]
+    TooNew { version: usize, latest: usize },
+    #[error("The file is not a pathfinder database, or it is encrypted with a different key")]
+    NotPathfinder,
+}
+
 /// Identifies a specific starknet block stored in the database.
 ///
 /// Note that this excludes the `Pending` variant since we never store pending data
@@ -89,19 +152,113 @@ struct Inner {
     database_path: Arc<PathBuf>,
     pool: Pool<SqliteConnectionManager>,
     bloom_filter_cache: Arc<bloom::Cache>,
+    encryption_key: Option<Arc<EncryptionKey>>,
+    split: SplitDatabases,
+    trie_store: Arc<dyn TrieNodeStore>,
+}
+
+/// The kind of [WAL checkpoint](Storage::checkpoint_wal) to perform.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WalCheckpointMode {
+    /// Copies as much of the WAL as possible without waiting for readers or writers.
+    Passive,
+    /// Copies all of the WAL and truncates the WAL file to zero bytes, waiting for readers to
+    /// finish with it. Writers are blocked while waiting.
+    Truncate,
+}
+
+/// The outcome of a [WAL checkpoint](Storage::checkpoint_wal).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WalCheckpoint {
+    /// The number of frames in the WAL.
+    pub wal_frames: u64,
+    /// The number of frames which have been copied into the database.
+    pub checkpointed_frames: u64,
+}
+
+impl WalCheckpoint {
+    /// Whether all frames of the WAL have been copied into the database.
+    pub fn is_complete(&self) -> bool {
+        self.checkpointed_frames == self.wal_frames
+    }
 }
 
 pub struct StorageManager {
     database_path: PathBuf,
     journal_mode: JournalMode,
     bloom_filter_cache: Arc<bloom::Cache>,
+    wal_autocheckpoint: bool,
+    encryption_key: Option<Arc<EncryptionKey>>,
+    split: SplitDatabases,
+    trie_store: Arc<dyn TrieNodeStore>,
+    profile: bool,
+    read_only: bool,
 }
 
 impl StorageManager {
+    /// Disables SQLite's automatic WAL checkpoints for the connections of pools created
+    /// afterwards.
+    ///
+    /// Automatic checkpoints run as part of the commit which pushes the WAL over its size
+    /// limit, stalling that commit for as long as the checkpoint takes. Without them the WAL
+    /// must instead be checkpointed periodically using [Storage::checkpoint_wal].
+    pub fn without_wal_autocheckpoint(self) -> Self {
+        Self {
+            wal_autocheckpoint: false,
+            ..self
+        }
+    }
+
+    /// Keeps the trie nodes of pools created afterwards in `store` instead of the SQLite
+    /// database.
+    ///
+    /// The same store must be used every time the database is opened, since the trie roots
+    /// stored in the database refer to nodes by their index within the store.
+    pub fn with_trie_store(self, store: Arc<dyn TrieNodeStore>) -> Self {
+        Self {
+            trie_store: store,
+            ..self
+        }
+    }
+
+    /// Times the statements run by connections of pools created afterwards, and logs the ones
+    /// taking at least `threshold`.
+    ///
+    /// The threshold applies to all connections of the process.
+    pub fn with_slow_query_threshold(self, threshold: std::time::Duration) -> Self {
+        profile::set_threshold(threshold);
+        Self {
+            profile: true,
+            ..self
+        }
+    }
+
     pub fn create_pool(&self, capacity: NonZeroU32) -> anyhow::Result<Storage> {
         let journal_mode = self.journal_mode;
-        let pool_manager = SqliteConnectionManager::file(&self.database_path)
-            .with_init(move |connection| setup_connection(connection, journal_mode));
+        let wal_autocheckpoint = self.wal_autocheckpoint;
+        let profile = self.profile;
+        let encryption_key = self.encryption_key.clone();
+        let split = self.split.clone();
+        let pool_manager =
+            SqliteConnectionManager::file(&self.database_path).with_init(move |connection| {
+                // The key must be set before the database is accessed.
+                if let Some(key) = &encryption_key {
+                    set_encryption_key(connection, key)?;
+                }
+                split::attach(connection, &split, journal_mode)?;
+                setup_connection(connection, journal_mode)?;
+                if !wal_autocheckpoint {
+                    connection.pragma_update(None, "wal_autocheckpoint", 0)?;
+                }
+                if profile {
+                    connection.profile(Some(profile::profile));
+                }
+                Ok(())
+            });
+        let pool_manager = match self.read_only {
+            true => pool_manager.with_flags(READ_ONLY_FLAGS),
+            false => pool_manager,
+        };
         let pool = Pool::builder()
             .max_size(capacity.get())
             .build(pool_manager)?;
@@ -110,6 +267,9 @@ impl StorageManager {
             database_path: Arc::new(self.database_path.clone()),
             pool,
             bloom_filter_cache: self.bloom_filter_cache.clone(),
+            encryption_key: self.encryption_key.clone(),
+            split: self.split.clone(),
+            trie_store: self.trie_store.clone(),
         }))
     }
 }
@@ -125,10 +285,91 @@ impl Storage {
         database_path: PathBuf,
         journal_mode: JournalMode,
         bloom_filter_cache_size: usize,
+    ) -> anyhow::Result<StorageManager> {
+        Self::migrate_split(
+            database_path,
+            journal_mode,
+            bloom_filter_cache_size,
+            SplitDatabases::default(),
+            Migration::Auto,
+        )
+    }
+
+    /// Same as [Storage::migrate] except that the tables selected by `split` are kept in their
+    /// own database files.
+    ///
+    /// Tables which are still in the main database are moved into their file, which can take a
+    /// long time for an existing database. Once moved, a table's file must be given every time
+    /// the database is opened.
+    ///
+    /// In WAL mode a transaction is only atomic within each file, so a crash during a commit can
+    /// leave the files out of step with each other.
+    ///
+    /// An existing database is only migrated if `migration` allows it. Databases which cannot be
+    /// opened by this version fail with a [SchemaError] before they are modified.
+    pub fn migrate_split(
+        database_path: PathBuf,
+        journal_mode: JournalMode,
+        bloom_filter_cache_size: usize,
+        split: SplitDatabases,
+        migration: Migration,
+    ) -> anyhow::Result<StorageManager> {
+        Self::migrate_inner(
+            database_path,
+            journal_mode,
+            bloom_filter_cache_size,
+            None,
+            split,
+            migration,
+        )
+    }
+
+    /// Same as [Storage::migrate_split] except that the database is encrypted with SQLCipher
+    /// using `key`. Separate database files are encrypted with the same key.
+    ///
+    /// A new database is created encrypted, while an existing database must have been created
+    /// with the same key. Unencrypted databases cannot be opened this way.
+    #[cfg(feature = "sqlcipher")]
+    pub fn migrate_encrypted(
+        database_path: PathBuf,
+        journal_mode: JournalMode,
+        bloom_filter_cache_size: usize,
+        key: EncryptionKey,
+        split: SplitDatabases,
+        migration: Migration,
+    ) -> anyhow::Result<StorageManager> {
+        Self::migrate_inner(
+            database_path,
+            journal_mode,
+            bloom_filter_cache_size,
+            Some(Arc::new(key)),
+            split,
+            migration,
+        )
+    }
+
+    fn migrate_inner(
+        database_path: PathBuf,
+        journal_mode: JournalMode,
+        bloom_filter_cache_size: usize,
+        encryption_key: Option<Arc<EncryptionKey>>,
+        split: SplitDatabases,
+        migration: Migration,
     ) -> anyhow::Result<StorageManager> {
         let mut connection =
             rusqlite::Connection::open(&database_path).context("Opening DB for migration")?;
 
+        // The key must be set before the database is accessed.
+        if let Some(key) = &encryption_key {
+            set_encryption_key(&connection, key).context("Setting encryption key")?;
+        }
+
+        // Checked before the journal mode is changed, which would already modify the file.
+        check_schema(&connection, migration).context("Checking database schema")?;
+
+        split::attach(&connection, &split, JournalMode::Rollback)
+            .context("Attaching database files")?;
+
         // Migration is done with rollback journal mode. Otherwise dropped tables
         // get copied into the WAL which is prohibitively expensive for large
         // tables.
@@ -138,6 +379,8 @@ impl Storage {
             .context("Setting up database connection")?;
 
         migrate_database(&mut connection).context("Migrate database")?;
+        split::relocate_tables(&mut connection, &split)
+            .context("Moving tables to separate database files")?;
 
         // Set the journal mode to the desired value.
         setup_journal_mode(&mut connection, journal_mode).context("Setting journal mode")?;
@@ -151,13 +394,54 @@ impl Storage {
             database_path,
             journal_mode,
             bloom_filter_cache: Arc::new(bloom::Cache::with_size(bloom_filter_cache_size)),
+            wal_autocheckpoint: true,
+            encryption_key,
+            split,
+            trie_store: Arc::new(SqliteTrieStore),
+            profile: false,
+            read_only: false,
+        })
+    }
+
+    /// Opens an existing database without modifying it, e.g. to export its data.
+    ///
+    /// Unlike [Storage::migrate_split] the database is neither created nor migrated, and its
+    /// journal mode is left as is. It must already be at the latest schema version, and the
+    /// files of `split` must exist. Connections of the pools created from it cannot write.
+    pub fn open_read_only(
+        database_path: PathBuf,
+        split: SplitDatabases,
+    ) -> anyhow::Result<StorageManager> {
+        let connection = rusqlite::Connection::open_with_flags(&database_path, READ_ONLY_FLAGS)
+            .context("Opening DB")?;
+        if schema_version(&connection)? == 0 {
+            return Err(SchemaError::NotPathfinder.into());
+        }
+        check_schema(&connection, Migration::Off).context("Checking database schema")?;
+        drop(connection);
+
+        Ok(StorageManager {
+            database_path,
+            // Only determines the durability of writes.
+            journal_mode: JournalMode::Rollback,
+            bloom_filter_cache: Arc::new(bloom::Cache::with_size(1)),
+            wal_autocheckpoint: true,
+            encryption_key: None,
+            split,
+            trie_store: Arc::new(SqliteTrieStore),
+            profile: false,
+            read_only: true,
         })
     }
 
     /// Returns a new Sqlite [Connection] to the database.
     pub fn connection(&self) -> anyhow::Result<Connection> {
         let conn = self.0.pool.get()?;
-        Ok(Connection::new(conn, self.0.bloom_filter_cache.clone()))
+        Ok(Connection::new(
+            conn,
+            self.0.bloom_filter_cache.clone(),
+            self.0.trie_store.clone(),
+        ))
     }
 
     /// Convenience function for tests to create an in-memory database.
@@ -191,6 +475,128 @@ impl Storage {
     pub fn path(&self) -> &Path {
         &self.0.database_path
     }
+
+    /// Copies the WAL into the database according to `mode`. The database file is synced to
+    /// disk once all frames have been copied.
+    ///
+    /// A [truncating](WalCheckpointMode::Truncate) checkpoint is preceded by a passive one, so
+    /// that writers are only blocked while the frames written in between are copied.
+    ///
+    /// Does nothing for databases which are not in WAL mode.
+    pub fn checkpoint_wal(&self, mode: WalCheckpointMode) -> anyhow::Result<WalCheckpoint> {
+        let conn = self.0.pool.get()?;
+        let checkpoint = |sql| {
+            conn.query_row(sql, [], |row| {
+                Ok((row.get::<_, i64>(1)?, row.get::<_, i64>(2)?))
+            })
+            .context("Checkpointing WAL")
+        };
+
+        let mut result = checkpoint("PRAGMA wal_checkpoint(PASSIVE)")?;
+        if mode == WalCheckpointMode::Truncate {
+            result = checkpoint("PRAGMA wal_checkpoint(TRUNCATE)")?;
+        }
+        let (wal_frames, checkpointed_frames) = result;
+
+        // Both are -1 if the database is not in WAL mode.
+        Ok(WalCheckpoint {
+            wal_frames: wal_frames.try_into().unwrap_or_default(),
+            checkpointed_frames: checkpointed_frames.try_into().unwrap_or_default(),
+        })
+    }
+
+    /// Writes a consistent snapshot of the database to `destination` using SQLite's online
+    /// backup API, replacing any existing file.
+    ///
+    /// The snapshot is copied within a single read transaction. In WAL mode this does not block
+    /// writers, while in rollback journal mode writers wait until the copy is done.
+    ///
+    /// Databases split across multiple files cannot be backed up this way.
+    pub fn backup(&self, destination: &Path) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            self.0.split.is_empty(),
+            "Backing up a database split across multiple files is not supported"
+        );
+
+        let source = self.0.pool.get()?;
+        let mut destination =
+            rusqlite::Connection::open(destination).context("Opening backup database")?;
+        // Backups of encrypted databases are encrypted with the same key.
+        if let Some(key) = &self.0.encryption_key {
+            set_encryption_key(&destination, key).context("Setting backup encryption key")?;
+        }
+
+        let backup =
+            rusqlite::backup::Backup::new(&source, &mut destination).context("Starting backup")?;
+        // Copying all pages in one step keeps the snapshot consistent. Copying in smaller steps
+        // would restart the backup whenever the database is written to in between.
+        loop {
+            match backup.step(-1).context("Copying database")? {
+                rusqlite::backup::StepResult::Done => break,
+                rusqlite::backup::StepResult::More => {}
+                // Busy or locked.
+                _ => std::thread::sleep(std::time::Duration::from_millis(100)),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The size of the WAL file in bytes, or zero if there is none.
+    pub fn wal_size(&self) -> u64 {
+        let mut path = self.0.database_path.as_os_str().to_owned();
+        path.push("-wal");
+
+        std::fs::metadata(path)
+            .map(|metadata| metadata.len())
+            .unwrap_or_default()
+    }
+
+    /// Writes the cached event Bloom filters to `path`, replacing any existing file, and returns
+    /// their number. They can be restored with [Storage::load_bloom_filter_cache].
+    pub fn save_bloom_filter_cache(&self, path: &Path) -> anyhow::Result<usize> {
+        let reorg_counter = self.reorg_counter()?;
+
+        // Written to a temporary file first, so that an interrupted write leaves no partial file.
+        let mut temporary = path.as_os_str().to_owned();
+        temporary.push(".tmp");
+        let file = std::fs::File::create(&temporary).context("Creating file")?;
+        let mut writer = std::io::BufWriter::new(file);
+
+        let count = self
+            .0
+            .bloom_filter_cache
+            .save(reorg_counter, &mut writer)
+            .context("Writing filters")?;
+        writer
+            .into_inner()
+            .map_err(|e| e.into_error())
+            .and_then(|file| file.sync_all())
+            .context("Flushing file")?;
+        std::fs::rename(&temporary, path).context("Replacing file")?;
+
+        Ok(count)
+    }
+
+    /// Adds the event Bloom filters saved by [Storage::save_bloom_filter_cache] to the cache and
+    /// returns their number. Filters saved before the most recent reorg are ignored.
+    pub fn load_bloom_filter_cache(&self, path: &Path) -> anyhow::Result<usize> {
+        let reorg_counter = self.reorg_counter()?;
+
+        let file = std::fs::File::open(path).context("Opening file")?;
+        let mut reader = std::io::BufReader::new(file);
+
+        self.0
+            .bloom_filter_cache
+            .load(reorg_counter, &mut reader)
+            .context("Reading filters")
+    }
+
+    fn reorg_counter(&self) -> anyhow::Result<ReorgCounter> {
+        let mut db = self.connection().context("Creating database connection")?;
+        let tx = db.transaction().context("Creating database transaction")?;
+        tx.reorg_counter()
+    }
 }
 
 fn setup_journal_mode(
@@ -222,29 +628,40 @@ fn setup_connection(
         true,
     )?;
 
-    match journal_mode {
-        JournalMode::Rollback => {
-            // According to the documentation FULL is the recommended setting for rollback mode.
-            connection.pragma_update(None, "synchronous", "full")?;
-        }
-        JournalMode::WAL => {
-            // According to the documentation NORMAL is a good choice for WAL mode.
-            connection.pragma_update(None, "synchronous", "normal")?;
-        }
-    };
+    connection.pragma_update(None, "synchronous", synchronous(journal_mode))?;
+
+    connection.set_prepared_statement_cache_capacity(STATEMENT_CACHE_CAPACITY);
 
     Ok(())
 }
 
+/// The `synchronous` setting to use with the journal mode.
+fn synchronous(journal_mode: JournalMode) -> &'static str {
+    match journal_mode {
+        // According to the documentation FULL is the recommended setting for rollback mode.
+        JournalMode::Rollback => "full",
+        // According to the documentation NORMAL is a good choice for WAL mode.
+        JournalMode::WAL => "normal",
+    }
+}
+
+/// Sets the SQLCipher passphrase of the connection.
+///
+/// Without SQLCipher the pragma is unknown and silently ignored, which would leave the database
+/// unencrypted, so keys are only accepted by `Storage::migrate_encrypted`.
+fn set_encryption_key(
+    connection: &rusqlite::Connection,
+    key: &EncryptionKey,
+) -> Result<(), rusqlite::Error> {
+    connection.pragma_update(None, "key", &key.0)
+}
+
 /// Migrates the database to the latest version. This __MUST__ be called
 /// at the beginning of the application.
 fn migrate_database(connection: &mut rusqlite::Connection) -> anyhow::Result<()> {
     let mut current_revision = schema_version(connection)?;
     let migrations = schema::migrations();
-
-    // The target version is the number of null migrations which have been replaced
-    // by the base schema + the new migrations built on top of that.
-    let latest_revision = schema::BASE_SCHEMA_REVISION + migrations.len();
+    let latest_revision = latest_schema_version();
 
     // Apply the base schema if the database is new.
     if current_revision == 0 {
@@ -267,23 +684,19 @@ fn migrate_database(connection: &mut rusqlite::Connection) -> anyhow::Result<()>
 
     // Check for database version compatibility.
     if current_revision < schema::BASE_SCHEMA_REVISION {
-        tracing::error!(
-            version=%current_revision,
-            limit=%schema::BASE_SCHEMA_REVISION,
-            "Database version is too old to migrate"
-        );
-        anyhow::bail!("Database version {current_revision} too old to migrate");
+        return Err(SchemaError::TooOld {
+            version: current_revision,
+            oldest: schema::BASE_SCHEMA_REVISION,
+        }
+        .into());
     }
 
     if current_revision > latest_revision {
-        tracing::error!(
-            version=%current_revision,
-            limit=%latest_revision,
-            "Database version is from a newer than this application expected"
-        );
-        anyhow::bail!(
-            "Database version {current_revision} is newer than this application expected {latest_revision}",
-        );
+        return Err(SchemaError::TooNew {
+            version: current_revision,
+            latest: latest_revision,
+        }
+        .into());
     }
 
     let amount = latest_revision - current_revision;
@@ -330,8 +743,67 @@ fn schema_version(connection: &rusqlite::Connection) -> anyhow::Result<usize> {
         &format!("SELECT {VERSION_KEY} FROM pragma_user_version;"),
         [],
         |row| row.get::<_, usize>(0),
-    )?;
-    Ok(version)
+    );
+
+    match version {
+        Ok(version) => Ok(version),
+        Err(rusqlite::Error::SqliteFailure(e, _))
+            if e.code == rusqlite::ErrorCode::NotADatabase =>
+        {
+            Err(SchemaError::NotPathfinder.into())
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// The schema version of a fully migrated database.
+fn latest_schema_version() -> usize {
+    // The target version is the number of null migrations which have been replaced
+    // by the base schema + the new migrations built on top of that.
+    schema::BASE_SCHEMA_REVISION + schema::migrations().len()
+}
+
+/// Checks that the database can be opened by this version of pathfinder, and that it is only
+/// migrated if `migration` allows it.
+fn check_schema(connection: &rusqlite::Connection, migration: Migration) -> anyhow::Result<()> {
+    let version = schema_version(connection)?;
+    let latest = latest_schema_version();
+
+    let exists = |sql: &str| -> anyhow::Result<bool> {
+        connection
+            .query_row(sql, [], |row| row.get(0))
+            .context("Querying tables")
+    };
+
+    // The tables of schemas before the base schema are not known to this version.
+    if version > 0 && version < schema::BASE_SCHEMA_REVISION {
+        return Err(SchemaError::TooOld {
+            version,
+            oldest: schema::BASE_SCHEMA_REVISION,
+        }
+        .into());
+    }
+
+    // A new database has no tables, while every later pathfinder schema has the block headers.
+    let is_pathfinder = match version {
+        0 => !exists("SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table')")?,
+        _ => exists("SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE name = 'block_headers')")?,
+    };
+    if !is_pathfinder {
+        return Err(SchemaError::NotPathfinder.into());
+    }
+
+    if version == 0 {
+        return Ok(());
+    }
+    if version > latest {
+        return Err(SchemaError::TooNew { version, latest }.into());
+    }
+    if version < latest && migration == Migration::Off {
+        return Err(SchemaError::MigrationRequired { version, latest }.into());
+    }
+
+    Ok(())
 }
 
 #[cfg(test)]
@@ -371,6 +843,139 @@ mod tests {
         migrate_database(&mut conn).unwrap_err();
     }
 
+    /// Returns the [SchemaError] which opening the database fails with, if any.
+    fn schema_error(path: &Path, migration: Migration) -> Option<SchemaError> {
+        let result = Storage::migrate_split(
+            path.to_owned(),
+            JournalMode::WAL,
+            1,
+            SplitDatabases::default(),
+            migration,
+        );
+
+        result
+            .err()?
+            .root_cause()
+            .downcast_ref::<SchemaError>()
+            .cloned()
+    }
+
+    fn set_schema_version(path: &Path, version: usize) {
+        let conn = rusqlite::Connection::open(path).unwrap();
+        conn.pragma_update(None, VERSION_KEY, version).unwrap();
+    }
+
+    #[test]
+    fn schema_compatibility() {
+        let db_dir = tempfile::TempDir::new().unwrap();
+        let db_path = db_dir.path().join("test.sqlite");
+        let latest = latest_schema_version();
+
+        // New databases are created even if migrations are disabled.
+        assert_eq!(schema_error(&db_path, Migration::Off), None);
+        // As are databases which are up to date.
+        assert_eq!(schema_error(&db_path, Migration::Off), None);
+
+        set_schema_version(&db_path, latest - 1);
+        assert_eq!(
+            schema_error(&db_path, Migration::Off),
+            Some(SchemaError::MigrationRequired {
+                version: latest - 1,
+                latest
+            })
+        );
+        // The database is left as is.
+        let conn = rusqlite::Connection::open(&db_path).unwrap();
+        assert_eq!(schema_version(&conn).unwrap(), latest - 1);
+        drop(conn);
+
+        set_schema_version(&db_path, latest + 1);
+        assert_eq!(
+            schema_error(&db_path, Migration::Auto),
+            Some(SchemaError::TooNew {
+                version: latest + 1,
+                latest
+            })
+        );
+
+        set_schema_version(&db_path, schema::BASE_SCHEMA_REVISION - 1);
+        assert_eq!(
+            schema_error(&db_path, Migration::Auto),
+            Some(SchemaError::TooOld {
+                version: schema::BASE_SCHEMA_REVISION - 1,
+                oldest: schema::BASE_SCHEMA_REVISION
+            })
+        );
+    }
+
+    #[test]
+    fn read_only() {
+        let db_dir = tempfile::TempDir::new().unwrap();
+        let db_path = db_dir.path().join("test.sqlite");
+
+        // Missing databases are not created.
+        Storage::open_read_only(db_path.clone(), SplitDatabases::default()).unwrap_err();
+        assert!(!db_path.exists());
+
+        Storage::migrate(db_path.clone(), JournalMode::WAL, 1).unwrap();
+        let storage = Storage::open_read_only(db_path.clone(), SplitDatabases::default())
+            .unwrap()
+            .create_pool(NonZeroU32::new(1).unwrap())
+            .unwrap();
+        let mut conn = storage.connection().unwrap();
+        let tx = conn.transaction().unwrap();
+        assert_eq!(tx.block_id(BlockId::Latest).unwrap(), None);
+        tx.insert_block_header(&pathfinder_common::BlockHeader::default())
+            .unwrap_err();
+        drop(tx);
+        drop(conn);
+        drop(storage);
+
+        // Databases which require migrations are not migrated.
+        let latest = latest_schema_version();
+        set_schema_version(&db_path, latest - 1);
+        let error = Storage::open_read_only(db_path.clone(), SplitDatabases::default())
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.root_cause().downcast_ref::<SchemaError>(),
+            Some(&SchemaError::MigrationRequired {
+                version: latest - 1,
+                latest
+            })
+        );
+        let conn = rusqlite::Connection::open(&db_path).unwrap();
+        assert_eq!(schema_version(&conn).unwrap(), latest - 1);
+    }
+
+    #[test]
+    fn not_a_pathfinder_database() {
+        let db_dir = tempfile::TempDir::new().unwrap();
+
+        let not_sqlite = db_dir.path().join("not_sqlite");
+        std::fs::write(&not_sqlite, [0xab; 4096]).unwrap();
+        assert_eq!(
+            schema_error(&not_sqlite, Migration::Auto),
+            Some(SchemaError::NotPathfinder)
+        );
+
+        let other = db_dir.path().join("other.sqlite");
+        let conn = rusqlite::Connection::open(&other).unwrap();
+        conn.execute("CREATE TABLE other (id INTEGER)", []).unwrap();
+        drop(conn);
+        assert_eq!(
+            schema_error(&other, Migration::Auto),
+            Some(SchemaError::NotPathfinder)
+        );
+
+        // Other applications may use the version as well.
+        set_schema_version(&other, latest_schema_version());
+        assert_eq!(
+            schema_error(&other, Migration::Auto),
+            Some(SchemaError::NotPathfinder)
+        );
+    }
+
     #[test]
     fn foreign_keys_are_enforced() {
         let conn = rusqlite::Connection::open_in_memory().unwrap();
@@ -410,6 +1015,113 @@ mod tests {
             .unwrap_err();
     }
 
+    #[test]
+    fn wal_checkpoint() {
+        let db_dir = tempfile::TempDir::new().unwrap();
+        let mut db_path = PathBuf::from(db_dir.path());
+        db_path.push("test.sqlite");
+
+        let storage = Storage::migrate(db_path, JournalMode::WAL, 1)
+            .unwrap()
+            .without_wal_autocheckpoint()
+            .create_pool(NonZeroU32::new(1).unwrap())
+            .unwrap();
+
+        let mut conn = storage.connection().unwrap();
+        let tx = conn.transaction().unwrap();
+        tx.insert_block_header(&pathfinder_common::BlockHeader::default())
+            .unwrap();
+        tx.commit().unwrap();
+        drop(conn);
+
+        assert!(storage.wal_size() > 0);
+
+        let checkpoint = storage.checkpoint_wal(WalCheckpointMode::Passive).unwrap();
+        assert!(checkpoint.wal_frames > 0);
+        assert!(checkpoint.is_complete());
+        // The WAL file is only reset by the next writer.
+        assert!(storage.wal_size() > 0);
+
+        let checkpoint = storage.checkpoint_wal(WalCheckpointMode::Truncate).unwrap();
+        assert!(checkpoint.is_complete());
+        assert_eq!(storage.wal_size(), 0);
+    }
+
+    #[test]
+    fn backup() {
+        let db_dir = tempfile::TempDir::new().unwrap();
+        let mut db_path = PathBuf::from(db_dir.path());
+        db_path.push("test.sqlite");
+        let mut backup_path = PathBuf::from(db_dir.path());
+        backup_path.push("backup.sqlite");
+
+        let storage = Storage::migrate(db_path, JournalMode::WAL, 1)
+            .unwrap()
+            .create_pool(NonZeroU32::new(1).unwrap())
+            .unwrap();
+
+        let header = pathfinder_common::BlockHeader::default();
+        let mut conn = storage.connection().unwrap();
+        let tx = conn.transaction().unwrap();
+        tx.insert_block_header(&header).unwrap();
+        tx.commit().unwrap();
+        drop(conn);
+
+        storage.backup(&backup_path).unwrap();
+
+        let backup = Storage::migrate(backup_path, JournalMode::WAL, 1)
+            .unwrap()
+            .create_pool(NonZeroU32::new(1).unwrap())
+            .unwrap();
+        let mut conn = backup.connection().unwrap();
+        let tx = conn.transaction().unwrap();
+        assert_eq!(
+            tx.block_id(BlockId::Latest).unwrap(),
+            Some((header.number, header.hash))
+        );
+    }
+
+    #[cfg(feature = "sqlcipher")]
+    #[test]
+    fn encryption() {
+        let db_dir = tempfile::TempDir::new().unwrap();
+        let mut db_path = PathBuf::from(db_dir.path());
+        db_path.push("test.sqlite");
+        let key = || EncryptionKey::new("secret".to_owned());
+
+        let storage = Storage::migrate_encrypted(db_path.clone(), JournalMode::WAL, 1, key())
+            .unwrap()
+            .create_pool(NonZeroU32::new(1).unwrap())
+            .unwrap();
+        let header = pathfinder_common::BlockHeader::default();
+        let mut conn = storage.connection().unwrap();
+        let tx = conn.transaction().unwrap();
+        tx.insert_block_header(&header).unwrap();
+        tx.commit().unwrap();
+        drop(conn);
+        drop(storage);
+
+        Storage::migrate(db_path.clone(), JournalMode::WAL, 1).unwrap_err();
+        Storage::migrate_encrypted(
+            db_path.clone(),
+            JournalMode::WAL,
+            1,
+            EncryptionKey::new("wrong".to_owned()),
+        )
+        .unwrap_err();
+
+        let storage = Storage::migrate_encrypted(db_path, JournalMode::WAL, 1, key())
+            .unwrap()
+            .create_pool(NonZeroU32::new(1).unwrap())
+            .unwrap();
+        let mut conn = storage.connection().unwrap();
+        let tx = conn.transaction().unwrap();
+        assert_eq!(
+            tx.block_id(BlockId::Latest).unwrap(),
+            Some((header.number, header.hash))
+        );
+    }
+
     #[test]
     fn rpc_test_db_is_migrated() {
         let mut source_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
@@ -427,4 +1139,183 @@ mod tests {
 
         assert_eq!(version, expected, "RPC database fixture needs migrating");
     }
+
+    #[test]
+    fn trie_store() {
+        use std::collections::HashMap;
+        use std::sync::Mutex;
+
+        use pathfinder_common::macro_prelude::*;
+        use pathfinder_crypto::Felt;
+
+        /// Records the tables accessed before delegating to SQLite.
+        #[derive(Default)]
+        struct RecordingStore(Mutex<Vec<TrieTable>>);
+
+        impl TrieNodeStore for RecordingStore {
+            fn insert(
+                &self,
+                tx: &Transaction<'_>,
+                table: TrieTable,
+                root: Felt,
+                nodes: &HashMap<Felt, Node>,
+            ) -> anyhow::Result<u64> {
+                self.0.lock().unwrap().push(table);
+                SqliteTrieStore.insert(tx, table, root, nodes)
+            }
+
+            fn reference(
+                &self,
+                tx: &Transaction<'_>,
+                table: TrieTable,
+                index: u64,
+            ) -> anyhow::Result<()> {
+                self.0.lock().unwrap().push(table);
+                SqliteTrieStore.reference(tx, table, index)
+            }
+
+            fn release(
+                &self,
+                tx: &Transaction<'_>,
+                table: TrieTable,
+                indices: &[u64],
+            ) -> anyhow::Result<usize> {
+                self.0.lock().unwrap().push(table);
+                SqliteTrieStore.release(tx, table, indices)
+            }
+
+            fn node(
+                &self,
+                tx: &Transaction<'_>,
+                table: TrieTable,
+                index: u64,
+            ) -> anyhow::Result<Option<StoredNode>> {
+                self.0.lock().unwrap().push(table);
+                SqliteTrieStore.node(tx, table, index)
+            }
+
+            fn hash(
+                &self,
+                tx: &Transaction<'_>,
+                table: TrieTable,
+                index: u64,
+            ) -> anyhow::Result<Option<Felt>> {
+                self.0.lock().unwrap().push(table);
+                SqliteTrieStore.hash(tx, table, index)
+            }
+        }
+
+        let db_dir = tempfile::TempDir::new().unwrap();
+        let mut db_path = PathBuf::from(db_dir.path());
+        db_path.push("test.sqlite");
+
+        let store = Arc::new(RecordingStore::default());
+        let storage = Storage::migrate(db_path, JournalMode::WAL, 1)
+            .unwrap()
+            .with_trie_store(store.clone())
+            .create_pool(NonZeroU32::new(1).unwrap())
+            .unwrap();
+
+        let mut conn = storage.connection().unwrap();
+        let tx = conn.transaction().unwrap();
+
+        let root = felt_bytes!(b"root");
+        let nodes = HashMap::from([(root, Node::LeafBinary)]);
+        let idx = tx
+            .insert_class_trie(pathfinder_common::ClassCommitment(root), &nodes)
+            .unwrap();
+        tx.insert_block_header(&pathfinder_common::BlockHeader::default())
+            .unwrap();
+        tx.insert_class_root(BlockNumber::GENESIS, Some(idx))
+            .unwrap();
+        assert_eq!(tx.class_trie_node_hash(idx).unwrap(), Some(root));
+        assert_eq!(
+            tx.class_trie_node(idx).unwrap(),
+            Some(StoredNode::LeafBinary)
+        );
+
+        assert_eq!(*store.0.lock().unwrap(), vec![TrieTable::Class; 4]);
+    }
+
+    #[test]
+    fn split_databases() {
+        use std::collections::HashMap;
+
+        use pathfinder_common::macro_prelude::*;
+
+        let db_dir = tempfile::TempDir::new().unwrap();
+        let db_path = db_dir.path().join("test.sqlite");
+        let split = SplitDatabases {
+            tries: Some(db_dir.path().join("tries.sqlite")),
+            transactions: Some(db_dir.path().join("transactions.sqlite")),
+        };
+
+        // Store a trie node before splitting, so that moving existing tables is covered.
+        let root = felt_bytes!(b"root");
+        let storage = Storage::migrate(db_path.clone(), JournalMode::WAL, 1)
+            .unwrap()
+            .create_pool(NonZeroU32::new(1).unwrap())
+            .unwrap();
+        let mut conn = storage.connection().unwrap();
+        let tx = conn.transaction().unwrap();
+        let idx = tx
+            .insert_class_trie(
+                pathfinder_common::ClassCommitment(root),
+                &HashMap::from([(root, Node::LeafBinary)]),
+            )
+            .unwrap();
+        tx.commit().unwrap();
+        drop(conn);
+        drop(storage);
+
+        let storage = Storage::migrate_split(
+            db_path.clone(),
+            JournalMode::WAL,
+            1,
+            split.clone(),
+            Migration::Auto,
+        )
+        .unwrap()
+        .create_pool(NonZeroU32::new(1).unwrap())
+        .unwrap();
+        let mut conn = storage.connection().unwrap();
+        let tx = conn.transaction().unwrap();
+        assert_eq!(tx.class_trie_node_hash(idx).unwrap(), Some(root));
+
+        drop(tx);
+        drop(conn);
+        drop(storage);
+
+        let tables = |path: &Path| -> Vec<String> {
+            let conn = rusqlite::Connection::open(path).unwrap();
+            let mut stmt = conn
+                .prepare("SELECT name FROM sqlite_master WHERE type = 'table' ORDER BY name")
+                .unwrap();
+            let tables = stmt
+                .query_map([], |row| row.get(0))
+                .unwrap()
+                .collect::<Result<_, _>>()
+                .unwrap();
+            tables
+        };
+        assert_eq!(
+            tables(split.tries.as_ref().unwrap()),
+            ["trie_class", "trie_contracts", "trie_storage"]
+        );
+        assert_eq!(
+            tables(split.transactions.as_ref().unwrap()),
+            ["starknet_events_filters", "starknet_transactions"]
+        );
+        let main = tables(&db_path);
+        assert!(main.iter().any(|x| x == "canonical_blocks"));
+        assert!(!main
+            .iter()
+            .any(|x| x == "trie_class" || x == "starknet_transactions"));
+
+        // The files must be given once tables have been moved to them.
+        let result = Storage::migrate(db_path.clone(), JournalMode::WAL, 1);
+        assert!(result.is_err());
+
+        Storage::migrate_split(db_path, JournalMode::WAL, 1, split, Migration::Auto).unwrap();
+    }
 }
@@ -43,6 +43,20 @@ impl ToSql for StarknetVersion {
     }
 }
 
+impl ToSql for crate::SyncStage {
+    fn to_sql(&self) -> ToSqlOutput<'_> {
+        use rusqlite::types::ValueRef;
+        ToSqlOutput::Borrowed(ValueRef::Text(self.as_str().as_bytes()))
+    }
+}
+
+impl ToSql for crate::TrieLayout {
+    fn to_sql(&self) -> ToSqlOutput<'_> {
+        use rusqlite::types::ValueRef;
+        ToSqlOutput::Borrowed(ValueRef::Text(self.as_str().as_bytes()))
+    }
+}
+
 impl ToSql for L1DataAvailabilityMode {
     fn to_sql(&self) -> ToSqlOutput<'_> {
         let value = match self {
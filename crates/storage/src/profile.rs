@@ -0,0 +1,159 @@
+//! Timing of the SQL statements run by connections, to diagnose latency spikes.
+//!
+//! SQLite reports the duration of each statement once it completes. Durations are recorded in
+//! the `storage_query_duration_seconds` histogram, labelled by the statement's family, which is
+//! its kind along with the table it targets, e.g. `SELECT block_headers`. Statements running for
+//! longer than the threshold are logged as well, within the span of their caller.
+//!
+//! SQLite only reports the statement's text, and not the values bound to its parameters. String
+//! and blob literals are redacted from the logged text as well.
+//!
+//! Only a function pointer can be registered with SQLite, so the threshold is shared by all
+//! connections of the process.
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+const METRIC_DURATION: &str = "storage_query_duration_seconds";
+
+/// The slow query threshold in microseconds.
+static THRESHOLD: AtomicU64 = AtomicU64::new(u64::MAX);
+
+pub(crate) fn set_threshold(threshold: Duration) {
+    let micros = u64::try_from(threshold.as_micros()).unwrap_or(u64::MAX);
+    THRESHOLD.store(micros, Ordering::Relaxed);
+}
+
+/// Registered with SQLite to be called on each completed statement.
+pub(crate) fn profile(sql: &str, duration: Duration) {
+    metrics::histogram!(METRIC_DURATION, duration.as_secs_f64(), "family" => family(sql));
+
+    if duration.as_micros() >= u128::from(THRESHOLD.load(Ordering::Relaxed)) {
+        tracing::warn!(sql=%redact(sql), ?duration, "Slow query");
+    }
+}
+
+/// Returns the kind of the statement along with the table it targets, if any.
+fn family(sql: &str) -> String {
+    let mut words = sql.split_whitespace();
+    let Some(kind) = words.next() else {
+        return String::new();
+    };
+    let kind = kind.to_ascii_uppercase();
+
+    let keyword = match kind.as_str() {
+        "SELECT" | "DELETE" => "FROM",
+        "INSERT" | "REPLACE" => "INTO",
+        "UPDATE" => return format!("UPDATE {}", words.next().unwrap_or_default()),
+        _ => return kind,
+    };
+
+    match words.find(|word| word.eq_ignore_ascii_case(keyword)) {
+        Some(_) => {
+            let table = words
+                .next()
+                .unwrap_or_default()
+                .split(['(', ')', ',', ';'])
+                .find(|part| !part.is_empty())
+                .unwrap_or_default();
+            format!("{kind} {table}")
+        }
+        None => kind,
+    }
+}
+
+/// Collapses the whitespace of the statement and replaces its string and blob literals with `?`.
+fn redact(sql: &str) -> String {
+    let mut redacted = String::with_capacity(sql.len());
+    let mut chars = sql
+        .split_whitespace()
+        .flat_map(|word| word.chars().chain([' ']));
+
+    while let Some(c) = chars.next() {
+        if c == '\'' {
+            // Skips to the closing quote, where quotes within the literal are doubled.
+            while let Some(c) = chars.next() {
+                if c == '\'' {
+                    let mut rest = chars.clone();
+                    if rest.next() != Some('\'') {
+                        break;
+                    }
+                    chars = rest;
+                }
+            }
+            // Blob literals are prefixed with an x.
+            if redacted.ends_with(['x', 'X']) {
+                redacted.pop();
+            }
+            redacted.push('?');
+        } else {
+            redacted.push(c);
+        }
+    }
+    redacted.truncate(redacted.trim_end().len());
+
+    redacted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn families() {
+        let cases = [
+            (
+                "SELECT hash FROM block_headers WHERE number = ?",
+                "SELECT block_headers",
+            ),
+            (
+                "select a, b\n  from\n    events WHERE x = 1",
+                "SELECT events",
+            ),
+            (
+                "INSERT INTO trie_storage (hash) VALUES (?)",
+                "INSERT trie_storage",
+            ),
+            (
+                "INSERT OR REPLACE INTO l1_state(a) VALUES (?)",
+                "INSERT l1_state",
+            ),
+            (
+                "UPDATE reorg_counter SET counter=counter+1",
+                "UPDATE reorg_counter",
+            ),
+            ("DELETE FROM peers", "DELETE peers"),
+            ("SELECT 1", "SELECT"),
+            ("PRAGMA user_version", "PRAGMA"),
+            ("", ""),
+        ];
+
+        for (sql, expected) in cases {
+            assert_eq!(family(sql), expected, "{sql}");
+        }
+    }
+
+    #[test]
+    fn redaction() {
+        let cases = [
+            ("SELECT a FROM t WHERE b = ?", "SELECT a FROM t WHERE b = ?"),
+            ("SELECT a\n    FROM t", "SELECT a FROM t"),
+            (
+                "SELECT a FROM t WHERE b = 'secret'",
+                "SELECT a FROM t WHERE b = ?",
+            ),
+            (
+                "SELECT a FROM t WHERE b = 'it''s'",
+                "SELECT a FROM t WHERE b = ?",
+            ),
+            (
+                "SELECT a FROM t WHERE b != x'0102'",
+                "SELECT a FROM t WHERE b != ?",
+            ),
+            ("SELECT 'a  b', 'c'", "SELECT ?, ?"),
+        ];
+
+        for (sql, expected) in cases {
+            assert_eq!(redact(sql), expected, "{sql}");
+        }
+    }
+}
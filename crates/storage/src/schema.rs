@@ -11,6 +11,16 @@ mod revision_0048;
 mod revision_0049;
 mod revision_0050;
 mod revision_0051;
+mod revision_0052;
+mod revision_0053;
+mod revision_0054;
+mod revision_0055;
+mod revision_0056;
+mod revision_0057;
+mod revision_0058;
+mod revision_0059;
+mod revision_0060;
+mod revision_0061;
 
 pub(crate) use base::base_schema;
 
@@ -30,6 +40,16 @@ pub fn migrations() -> &'static [MigrationFn] {
         revision_0049::migrate,
         revision_0050::migrate,
         revision_0051::migrate,
+        revision_0052::migrate,
+        revision_0053::migrate,
+        revision_0054::migrate,
+        revision_0055::migrate,
+        revision_0056::migrate,
+        revision_0057::migrate,
+        revision_0058::migrate,
+        revision_0059::migrate,
+        revision_0060::migrate,
+        revision_0061::migrate,
     ]
 }
 
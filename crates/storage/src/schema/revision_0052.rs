@@ -0,0 +1,19 @@
+use anyhow::Context;
+
+/// Adds a reference count to each trie node table, which allows nodes that are no longer
+/// reachable from any root to be deleted.
+///
+/// Existing nodes are left with a `NULL` reference count. These are considered untracked and
+/// are never deleted, as computing their counts would require decoding every node in the database.
+pub(crate) fn migrate(tx: &rusqlite::Transaction<'_>) -> anyhow::Result<()> {
+    tx.execute_batch(
+        r"
+ALTER TABLE trie_class ADD COLUMN ref_count INTEGER DEFAULT NULL;
+ALTER TABLE trie_contracts ADD COLUMN ref_count INTEGER DEFAULT NULL;
+ALTER TABLE trie_storage ADD COLUMN ref_count INTEGER DEFAULT NULL;
+",
+    )
+    .context("Adding ref_count columns to trie tables")?;
+
+    Ok(())
+}
@@ -0,0 +1,24 @@
+use anyhow::Context;
+
+/// Adds an index of L2 to L1 message hashes, which allows looking up the transaction that sent
+/// a message.
+///
+/// Messages sent in blocks stored prior to this migration are not indexed.
+pub(crate) fn migrate(tx: &rusqlite::Transaction<'_>) -> anyhow::Result<()> {
+    tx.execute_batch(
+        r"
+CREATE TABLE l2_to_l1_messages (
+    message_hash BLOB NOT NULL,
+    block_number INTEGER NOT NULL,
+    transaction_hash BLOB NOT NULL,
+    idx INTEGER NOT NULL
+);
+CREATE INDEX l2_to_l1_messages_message_hash ON l2_to_l1_messages(message_hash);
+CREATE INDEX l2_to_l1_messages_block_number ON l2_to_l1_messages(block_number);
+CREATE INDEX l2_to_l1_messages_transaction_hash ON l2_to_l1_messages(transaction_hash);
+",
+    )
+    .context("Creating l2_to_l1_messages table")?;
+
+    Ok(())
+}
@@ -0,0 +1,15 @@
+use anyhow::Context;
+
+/// Adds a table which tracks how far each stage of the sync pipeline has progressed.
+pub(crate) fn migrate(tx: &rusqlite::Transaction<'_>) -> anyhow::Result<()> {
+    tx.execute(
+        r"CREATE TABLE sync_checkpoints (
+    stage TEXT PRIMARY KEY,
+    block_number INTEGER NOT NULL
+)",
+        [],
+    )
+    .context("Creating sync_checkpoints table")?;
+
+    Ok(())
+}
@@ -0,0 +1,16 @@
+use anyhow::Context;
+
+/// Adds a table which records the oldest block whose trie state is still available after the
+/// tries have been pruned.
+pub(crate) fn migrate(tx: &rusqlite::Transaction<'_>) -> anyhow::Result<()> {
+    tx.execute(
+        r"CREATE TABLE trie_history (
+    id INTEGER PRIMARY KEY CHECK (id = 0),
+    oldest_block INTEGER NOT NULL
+)",
+        [],
+    )
+    .context("Creating trie_history table")?;
+
+    Ok(())
+}
@@ -0,0 +1,19 @@
+use anyhow::Context;
+
+/// Adds a table which records how trie nodes are keyed, see [TrieLayout](crate::TrieLayout).
+///
+/// Existing databases use the indexed layout.
+pub(crate) fn migrate(tx: &rusqlite::Transaction<'_>) -> anyhow::Result<()> {
+    tx.execute_batch(
+        r"
+CREATE TABLE trie_layout (
+    id INTEGER PRIMARY KEY CHECK (id = 0),
+    layout TEXT NOT NULL
+);
+INSERT INTO trie_layout (id, layout) VALUES (0, 'indexed');
+",
+    )
+    .context("Creating trie_layout table")?;
+
+    Ok(())
+}
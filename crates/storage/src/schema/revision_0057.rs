@@ -0,0 +1,15 @@
+use anyhow::Context;
+
+/// Adds a table which persists the reputation of P2P peers across restarts.
+pub(crate) fn migrate(tx: &rusqlite::Transaction<'_>) -> anyhow::Result<()> {
+    tx.execute(
+        r"CREATE TABLE p2p_peer_scores (
+    peer_id TEXT PRIMARY KEY,
+    score INTEGER NOT NULL
+)",
+        [],
+    )
+    .context("Creating p2p_peer_scores table")?;
+
+    Ok(())
+}
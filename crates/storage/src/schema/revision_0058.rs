@@ -0,0 +1,17 @@
+use anyhow::Context;
+
+/// Adds a table which persists known P2P peers across restarts, so that they can be redialed
+/// and banned peers stay banned.
+pub(crate) fn migrate(tx: &rusqlite::Transaction<'_>) -> anyhow::Result<()> {
+    tx.execute(
+        r"CREATE TABLE p2p_peers (
+    peer_id TEXT PRIMARY KEY,
+    address TEXT,
+    banned INTEGER NOT NULL DEFAULT 0
+)",
+        [],
+    )
+    .context("Creating p2p_peers table")?;
+
+    Ok(())
+}
@@ -0,0 +1,138 @@
+use std::time::{Duration, Instant};
+
+use anyhow::Context;
+use rusqlite::params;
+
+/// The number of blocks whose totals are computed by a single query.
+const BATCH_SIZE: u64 = 10_000;
+
+/// Adds a table of the chain's state totals as of each block, and fills it for existing blocks.
+///
+/// Declared classes are counted by their declaration block, which for old Cairo 0 classes is the
+/// block of their first deployment.
+pub(crate) fn migrate(tx: &rusqlite::Transaction<'_>) -> anyhow::Result<()> {
+    tx.execute(
+        r"CREATE TABLE state_stats (
+    block_number INTEGER PRIMARY KEY REFERENCES canonical_blocks(number) ON DELETE CASCADE,
+    contracts INTEGER NOT NULL,
+    declared_classes INTEGER NOT NULL,
+    storage_leaves INTEGER NOT NULL,
+    storage_diffs INTEGER NOT NULL,
+    nonce_updates INTEGER NOT NULL
+)",
+        [],
+    )
+    .context("Creating state_stats table")?;
+
+    let Some(latest): Option<u64> = tx
+        .query_row("SELECT MAX(number) FROM canonical_blocks", [], |row| {
+            row.get(0)
+        })
+        .context("Querying latest block")?
+    else {
+        return Ok(());
+    };
+
+    tracing::info!("Computing state statistics, this may take a while");
+
+    // The changes of each block in the range. Zero storage values are stored as empty blobs.
+    let mut changes = tx
+        .prepare(
+            r"WITH
+    deployed AS (
+        SELECT block_number, COUNT(1) AS n FROM contract_updates AS c
+        WHERE block_number BETWEEN ?1 AND ?2 AND NOT EXISTS (
+            SELECT 1 FROM contract_updates
+            WHERE contract_address = c.contract_address AND block_number < c.block_number
+        )
+        GROUP BY block_number
+    ),
+    declared AS (
+        SELECT block_number, COUNT(1) AS n FROM class_definitions
+        WHERE block_number BETWEEN ?1 AND ?2 GROUP BY block_number
+    ),
+    storage AS (
+        SELECT
+            block_number,
+            COUNT(1) AS diffs,
+            SUM(CASE
+                WHEN storage_value != x'' AND IFNULL(previous, x'') = x'' THEN 1
+                WHEN storage_value = x'' AND IFNULL(previous, x'') != x'' THEN -1
+                ELSE 0
+            END) AS leaves
+        FROM (
+            SELECT block_number, storage_value, (
+                SELECT storage_value FROM storage_updates
+                WHERE contract_address = s.contract_address
+                    AND storage_address = s.storage_address
+                    AND block_number < s.block_number
+                ORDER BY block_number DESC LIMIT 1
+            ) AS previous
+            FROM storage_updates AS s WHERE block_number BETWEEN ?1 AND ?2
+        ) GROUP BY block_number
+    ),
+    nonces AS (
+        SELECT block_number, COUNT(1) AS n FROM nonce_updates
+        WHERE block_number BETWEEN ?1 AND ?2 GROUP BY block_number
+    )
+SELECT
+    number,
+    IFNULL(deployed.n, 0),
+    IFNULL(declared.n, 0),
+    IFNULL(storage.leaves, 0),
+    IFNULL(storage.diffs, 0),
+    IFNULL(nonces.n, 0)
+FROM canonical_blocks
+    LEFT JOIN deployed ON deployed.block_number = number
+    LEFT JOIN declared ON declared.block_number = number
+    LEFT JOIN storage ON storage.block_number = number
+    LEFT JOIN nonces ON nonces.block_number = number
+WHERE number BETWEEN ?1 AND ?2
+ORDER BY number",
+        )
+        .context("Preparing state changes query")?;
+
+    let mut insert = tx
+        .prepare(
+            r"INSERT INTO state_stats (
+    block_number, contracts, declared_classes, storage_leaves, storage_diffs, nonce_updates
+) VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .context("Preparing state stats insert")?;
+
+    let mut totals = [0i64; 5];
+    let mut progress_logged = Instant::now();
+    const LOG_RATE: Duration = Duration::from_secs(10);
+
+    for from in (0..=latest).step_by(BATCH_SIZE as usize) {
+        if progress_logged.elapsed() > LOG_RATE {
+            tracing::info!(block=%from, %latest, "Computing state statistics");
+            progress_logged = Instant::now();
+        }
+
+        let to = latest.min(from + BATCH_SIZE - 1);
+        let mut rows = changes
+            .query(params![from, to])
+            .context("Querying state changes")?;
+
+        while let Some(row) = rows.next().context("Fetching state changes")? {
+            let block_number: u64 = row.get(0)?;
+            for (i, total) in totals.iter_mut().enumerate() {
+                *total += row.get::<_, i64>(i + 1)?;
+            }
+
+            insert
+                .execute(params![
+                    block_number,
+                    totals[0],
+                    totals[1],
+                    totals[2],
+                    totals[3],
+                    totals[4]
+                ])
+                .context("Inserting state stats")?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,20 @@
+use anyhow::Context;
+
+/// Adds a log of the reorgs performed by sync, for incident analysis.
+pub(crate) fn migrate(tx: &rusqlite::Transaction<'_>) -> anyhow::Result<()> {
+    tx.execute(
+        r"CREATE TABLE reorg_history (
+    id INTEGER PRIMARY KEY,
+    timestamp INTEGER NOT NULL,
+    old_head_number INTEGER NOT NULL,
+    old_head_hash BLOB NOT NULL,
+    new_head_number INTEGER,
+    new_head_hash BLOB,
+    depth INTEGER NOT NULL
+)",
+        [],
+    )
+    .context("Creating reorg_history table")?;
+
+    Ok(())
+}
@@ -0,0 +1,17 @@
+use anyhow::Context;
+
+/// Adds a table of the Ethereum gas prices observed by L1 sync when each block was stored.
+pub(crate) fn migrate(tx: &rusqlite::Transaction<'_>) -> anyhow::Result<()> {
+    tx.execute(
+        r"CREATE TABLE block_l1_gas_prices (
+    block_number INTEGER PRIMARY KEY REFERENCES canonical_blocks(number) ON DELETE CASCADE,
+    ethereum_block_number INTEGER NOT NULL,
+    base_fee BLOB NOT NULL,
+    blob_base_fee BLOB
+)",
+        [],
+    )
+    .context("Creating block_l1_gas_prices table")?;
+
+    Ok(())
+}
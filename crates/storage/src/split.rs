@@ -0,0 +1,193 @@
+//! Keeping groups of tables in database files separate from the main database.
+//!
+//! The separate files are [attached](https://sqlite.org/lang_attach.html) to every connection, so
+//! that queries keep referring to the tables without a schema name. Only tables without foreign
+//! keys can be moved, since SQLite does not enforce foreign keys across database files. This
+//! rules out the class definitions, which reference the canonical blocks.
+//!
+//! Existing tables are moved into their file when it is first configured. Moving tables back
+//! into the main database is not supported.
+//!
+//! Migrations which create indexes on these tables must qualify the index name with the schema
+//! of its table.
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+
+use crate::JournalMode;
+
+/// The database files holding groups of tables separately from the main database, so that they
+/// can be placed on different volumes.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SplitDatabases {
+    /// The file holding the Merkle trie nodes, which take the bulk of the writes during sync.
+    pub tries: Option<PathBuf>,
+    /// The file holding the transactions and receipts, including their events, along with the
+    /// event bloom filters.
+    pub transactions: Option<PathBuf>,
+}
+
+const TRIES: TableGroup = TableGroup {
+    schema: "tries",
+    tables: &["trie_class", "trie_contracts", "trie_storage"],
+    option: "--storage.trie-directory",
+};
+
+const TRANSACTIONS: TableGroup = TableGroup {
+    schema: "transactions",
+    tables: &["starknet_transactions", "starknet_events_filters"],
+    option: "--storage.transaction-directory",
+};
+
+struct TableGroup {
+    /// The schema name the file is attached as.
+    schema: &'static str,
+    tables: &'static [&'static str],
+    /// The pathfinder option configuring the file, for error messages.
+    option: &'static str,
+}
+
+impl SplitDatabases {
+    pub fn is_empty(&self) -> bool {
+        self.tries.is_none() && self.transactions.is_none()
+    }
+
+    fn groups(&self) -> [(&TableGroup, Option<&Path>); 2] {
+        [
+            (&TRIES, self.tries.as_deref()),
+            (&TRANSACTIONS, self.transactions.as_deref()),
+        ]
+    }
+}
+
+/// Attaches the configured database files to the connection, creating them if necessary.
+pub(crate) fn attach(
+    connection: &rusqlite::Connection,
+    split: &SplitDatabases,
+    journal_mode: JournalMode,
+) -> Result<(), rusqlite::Error> {
+    for (group, path) in split.groups() {
+        let Some(path) = path else {
+            continue;
+        };
+
+        // Attached files use the main database's encryption key, if any.
+        connection.execute(
+            &format!("ATTACH DATABASE ? AS {}", group.schema),
+            [path.to_string_lossy()],
+        )?;
+        connection.pragma_update(
+            Some(rusqlite::DatabaseName::Attached(group.schema)),
+            "synchronous",
+            crate::synchronous(journal_mode),
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Moves tables which are still in the main database into their configured files, and checks
+/// that no table has been moved to a file which is no longer configured.
+pub(crate) fn relocate_tables(
+    connection: &mut rusqlite::Connection,
+    split: &SplitDatabases,
+) -> anyhow::Result<()> {
+    let tx = connection
+        .transaction()
+        .context("Creating database transaction")?;
+
+    for (group, path) in split.groups() {
+        for table in group.tables {
+            let in_main = table_exists(&tx, "main", table)?;
+
+            let Some(path) = path else {
+                anyhow::ensure!(
+                    in_main,
+                    "Table {table} was moved to a separate database file, which must be \
+                     configured using {}",
+                    group.option
+                );
+                continue;
+            };
+
+            let in_file = table_exists(&tx, group.schema, table)?;
+            match (in_main, in_file) {
+                (true, false) => {
+                    tracing::info!(
+                        %table,
+                        file=%path.display(),
+                        "Moving table to separate database file"
+                    );
+                    move_table(&tx, group.schema, table)
+                        .with_context(|| format!("Moving {table} to its database file"))?;
+                }
+                (false, true) => {}
+                (true, true) => anyhow::bail!(
+                    "Table {table} exists in both the main database and the file configured using \
+                     {}",
+                    group.option
+                ),
+                (false, false) => anyhow::bail!(
+                    "Table {table} is missing from the file configured using {}",
+                    group.option
+                ),
+            }
+        }
+    }
+
+    tx.commit().context("Committing database transaction")
+}
+
+fn table_exists(tx: &rusqlite::Transaction<'_>, schema: &str, table: &str) -> anyhow::Result<bool> {
+    tx.query_row(
+        &format!(
+            "SELECT EXISTS(SELECT 1 FROM {schema}.sqlite_master WHERE type = 'table' AND name = ?)"
+        ),
+        [table],
+        |row| row.get(0),
+    )
+    .with_context(|| format!("Checking whether {schema}.{table} exists"))
+}
+
+fn move_table(tx: &rusqlite::Transaction<'_>, schema: &str, table: &str) -> anyhow::Result<()> {
+    let mut stmt = tx
+        .prepare(
+            "SELECT type, sql FROM main.sqlite_master WHERE tbl_name = ? AND sql IS NOT NULL
+            ORDER BY type = 'index'",
+        )
+        .context("Preparing schema query")?;
+    let definitions = stmt
+        .query_map([table], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })
+        .context("Querying table schema")?
+        .collect::<Result<Vec<_>, _>>()
+        .context("Iterating over table schema")?;
+
+    // SQLite normalizes the leading keywords of the stored definitions, so the object name
+    // always follows them directly.
+    for (kind, sql) in definitions {
+        let sql = match kind.as_str() {
+            "table" => sql.replacen("CREATE TABLE ", &format!("CREATE TABLE {schema}."), 1),
+            "index" if sql.starts_with("CREATE UNIQUE INDEX ") => sql.replacen(
+                "CREATE UNIQUE INDEX ",
+                &format!("CREATE UNIQUE INDEX {schema}."),
+                1,
+            ),
+            "index" => sql.replacen("CREATE INDEX ", &format!("CREATE INDEX {schema}."), 1),
+            other => anyhow::bail!("Tables with a {other} cannot be moved"),
+        };
+        tx.execute(&sql, [])
+            .context("Creating table in database file")?;
+    }
+
+    tx.execute(
+        &format!("INSERT INTO {schema}.{table} SELECT * FROM main.{table}"),
+        [],
+    )
+    .context("Copying rows")?;
+    tx.execute(&format!("DROP TABLE main.{table}"), [])
+        .context("Dropping table from main database")?;
+
+    Ok(())
+}
@@ -4,8 +4,8 @@ use super::Storage;
 use pathfinder_common::macro_prelude::*;
 use pathfinder_common::receipt::{ExecutionDataAvailability, ExecutionResources, Receipt};
 use pathfinder_common::transaction::{
-    DeclareTransactionV0V1, DeployTransaction, EntryPointType, InvokeTransactionV0, Transaction,
-    TransactionVariant,
+    DeclareTransactionV0V1, DeployTransaction, EntryPointType, InvokeTransactionV0,
+    InvokeTransactionV1, Transaction, TransactionVariant,
 };
 use pathfinder_common::*;
 use pathfinder_crypto::Felt;
@@ -217,3 +217,377 @@ pub fn setup_test_storage() -> (Storage, TestData) {
         },
     )
 }
+
+/// Computes the storage and class commitments of a block by applying its state update to the
+/// tries of its parent block, persisting the new trie nodes in the process.
+///
+/// This is provided by the caller as the trie implementation lives outside of this crate.
+pub type CommitmentsFn<'a> = dyn FnMut(
+        &crate::Transaction<'_>,
+        BlockNumber,
+        &StateUpdate,
+    ) -> anyhow::Result<(StorageCommitment, ClassCommitment)>
+    + 'a;
+
+/// Fabricates a deterministic, consistent chain for use in tests.
+///
+/// Each block of the chain:
+/// - links to its parent via its parent hash and parent state commitment,
+/// - declares a Cairo class and deploys [contracts](ChainBuilder::with_contracts_per_block)
+///   of that class, each with a [DeployTransaction],
+/// - contains [invoke transactions](ChainBuilder::with_invokes_per_block) sent by these
+///   contracts, each writing to its sender's storage, bumping its nonce and emitting an
+///   event,
+/// - has a receipt for every transaction, with matching transaction and event counts in its
+///   header.
+///
+/// Trie commitments are zero unless [commitments](ChainBuilder::with_commitments) are
+/// computed while [inserting](ChainBuilder::insert) the chain. Class definitions are
+/// placeholders which cannot be parsed.
+pub struct ChainBuilder<'a> {
+    num_blocks: usize,
+    contracts_per_block: usize,
+    invokes_per_block: usize,
+    commitments: Option<Box<CommitmentsFn<'a>>>,
+}
+
+impl<'a> ChainBuilder<'a> {
+    /// A chain of `num_blocks` blocks starting at genesis, with one deployed contract and
+    /// one invoke transaction per block.
+    pub fn new(num_blocks: usize) -> Self {
+        Self {
+            num_blocks,
+            contracts_per_block: 1,
+            invokes_per_block: 1,
+            commitments: None,
+        }
+    }
+
+    pub fn with_contracts_per_block(mut self, contracts_per_block: usize) -> Self {
+        self.contracts_per_block = contracts_per_block;
+        self
+    }
+
+    /// # Panics
+    ///
+    /// Building the chain panics if there are invoke transactions but no contracts to send
+    /// them.
+    pub fn with_invokes_per_block(mut self, invokes_per_block: usize) -> Self {
+        self.invokes_per_block = invokes_per_block;
+        self
+    }
+
+    /// Sets the function used to compute each block's commitments when the chain is
+    /// [inserted](ChainBuilder::insert).
+    pub fn with_commitments<F>(mut self, commitments: F) -> Self
+    where
+        F: FnMut(
+                &crate::Transaction<'_>,
+                BlockNumber,
+                &StateUpdate,
+            ) -> anyhow::Result<(StorageCommitment, ClassCommitment)>
+            + 'a,
+    {
+        self.commitments = Some(Box::new(commitments));
+        self
+    }
+
+    /// Creates the chain without inserting it into storage. Commitments are always zero.
+    pub fn build(&self) -> Vec<crate::fake::Block> {
+        assert!(
+            self.invokes_per_block == 0 || self.contracts_per_block > 0,
+            "Invoke transactions require at least one contract per block"
+        );
+
+        let mut nonces = std::collections::HashMap::<ContractAddress, u64>::new();
+        let mut blocks: Vec<crate::fake::Block> = Vec::with_capacity(self.num_blocks);
+
+        for i in 0..self.num_blocks as u64 {
+            let number = BlockNumber::new_or_panic(i);
+            let hash = BlockHash(derive_felt(Kind::Block, i, 0));
+            let parent_hash = blocks
+                .last()
+                .map(|parent| parent.header.header.hash)
+                .unwrap_or_default();
+
+            let class_hash = ClassHash(derive_felt(Kind::Class, i, 0));
+            let mut state_update = StateUpdate::default()
+                .with_block_hash(hash)
+                .with_declared_cairo_class(class_hash);
+            let mut transaction_data = Vec::new();
+
+            let contracts = (0..self.contracts_per_block as u64)
+                .map(|j| ContractAddress::new_or_panic(derive_felt(Kind::Contract, i, j)))
+                .collect::<Vec<_>>();
+
+            for (j, &contract_address) in contracts.iter().enumerate() {
+                state_update = state_update.with_deployed_contract(contract_address, class_hash);
+
+                let transaction = Transaction {
+                    hash: TransactionHash(derive_felt(Kind::DeployTransaction, i, j as u64)),
+                    variant: TransactionVariant::Deploy(DeployTransaction {
+                        contract_address,
+                        contract_address_salt: ContractAddressSalt(derive_felt(
+                            Kind::Salt,
+                            i,
+                            j as u64,
+                        )),
+                        class_hash,
+                        constructor_calldata: vec![],
+                        version: TransactionVersion::ZERO,
+                    }),
+                };
+                transaction_data.push((transaction, vec![]));
+            }
+
+            for j in 0..self.invokes_per_block as u64 {
+                let sender_address = contracts[j as usize % contracts.len()];
+                let nonce = nonces.entry(sender_address).or_default();
+                *nonce += 1;
+
+                state_update = state_update
+                    .with_storage_update(
+                        sender_address,
+                        StorageAddress::new_or_panic(derive_felt(Kind::StorageKey, i, j)),
+                        StorageValue(derive_felt(Kind::StorageValue, i, j)),
+                    )
+                    .with_contract_nonce(sender_address, ContractNonce(Felt::from_u64(*nonce)));
+
+                let transaction = Transaction {
+                    hash: TransactionHash(derive_felt(Kind::InvokeTransaction, i, j)),
+                    variant: TransactionVariant::InvokeV1(InvokeTransactionV1 {
+                        calldata: vec![CallParam(derive_felt(Kind::Calldata, i, j))],
+                        sender_address,
+                        max_fee: Fee::ZERO,
+                        signature: vec![],
+                        nonce: TransactionNonce(Felt::from_u64(*nonce - 1)),
+                    }),
+                };
+                let event = pathfinder_common::event::Event {
+                    from_address: sender_address,
+                    keys: vec![EventKey(derive_felt(Kind::EventKey, i, j))],
+                    data: vec![EventData(derive_felt(Kind::EventData, i, j))],
+                };
+                transaction_data.push((transaction, vec![event]));
+            }
+
+            let transaction_data = transaction_data
+                .into_iter()
+                .enumerate()
+                .map(|(idx, (transaction, events))| {
+                    let receipt = Receipt {
+                        events,
+                        transaction_hash: transaction.hash,
+                        transaction_index: TransactionIndex::new_or_panic(idx as u64),
+                        ..Default::default()
+                    };
+                    (transaction, receipt)
+                })
+                .collect::<Vec<_>>();
+
+            let header = BlockHeader::builder()
+                .with_number(number)
+                .with_parent_hash(parent_hash)
+                .with_timestamp(BlockTimestamp::new_or_panic(i + 1))
+                .with_calculated_state_commitment()
+                .with_transaction_count(transaction_data.len())
+                .with_event_count(
+                    transaction_data
+                        .iter()
+                        .map(|(_, receipt)| receipt.events.len())
+                        .sum(),
+                )
+                .finalize_with_hash(hash);
+
+            let parent_state_commitment = blocks
+                .last()
+                .map(|parent| parent.header.header.state_commitment)
+                .unwrap_or_default();
+            let state_update = state_update
+                .with_state_commitment(header.state_commitment)
+                .with_parent_state_commitment(parent_state_commitment);
+
+            blocks.push(crate::fake::Block {
+                header: SignedBlockHeader {
+                    header,
+                    state_update_counts: state_update.counts(),
+                    ..Default::default()
+                },
+                transaction_data,
+                cairo_defs: vec![(class_hash, format!("class {i}").into_bytes())],
+                state_update,
+                sierra_defs: vec![],
+            });
+        }
+
+        blocks
+    }
+
+    /// Creates the chain and inserts it into `storage`, computing each block's commitments if
+    /// [configured](ChainBuilder::with_commitments).
+    pub fn insert(mut self, storage: &Storage) -> anyhow::Result<Vec<crate::fake::Block>> {
+        use anyhow::Context;
+
+        let mut blocks = self.build();
+
+        let mut connection = storage.connection()?;
+        let tx = connection.transaction()?;
+
+        let mut parent_state_commitment = StateCommitment::ZERO;
+        for block in &mut blocks {
+            let header = &mut block.header.header;
+
+            if let Some(commitments) = self.commitments.as_mut() {
+                let (storage_commitment, class_commitment) =
+                    commitments(&tx, header.number, &block.state_update)
+                        .context("Computing commitments")?;
+                header.storage_commitment = storage_commitment;
+                header.class_commitment = class_commitment;
+                header.state_commitment =
+                    StateCommitment::calculate(storage_commitment, class_commitment);
+            }
+            block.state_update.state_commitment = header.state_commitment;
+            block.state_update.parent_state_commitment = parent_state_commitment;
+            parent_state_commitment = header.state_commitment;
+
+            tx.insert_block_header(header)
+                .context("Inserting block header")?;
+            tx.insert_transaction_data(
+                header.hash,
+                header.number,
+                &block
+                    .transaction_data
+                    .iter()
+                    .cloned()
+                    .map(|(transaction, receipt)| (transaction, Some(receipt)))
+                    .collect::<Vec<_>>(),
+            )
+            .context("Inserting transaction data")?;
+            tx.insert_state_update_counts(header.number, &block.header.state_update_counts)
+                .context("Inserting state update counts")?;
+            for (class_hash, definition) in &block.cairo_defs {
+                tx.insert_cairo_class(*class_hash, definition)
+                    .context("Inserting class definition")?;
+            }
+            tx.insert_state_update(header.number, &block.state_update)
+                .context("Inserting state update")?;
+        }
+
+        tx.commit().context("Committing database transaction")?;
+
+        Ok(blocks)
+    }
+}
+
+/// The kinds of values derived by [derive_felt], which keeps the values of different kinds
+/// distinct.
+#[derive(Copy, Clone)]
+enum Kind {
+    Block = 1,
+    Class,
+    Contract,
+    Salt,
+    DeployTransaction,
+    InvokeTransaction,
+    Calldata,
+    StorageKey,
+    StorageValue,
+    EventKey,
+    EventData,
+}
+
+/// Derives a distinct value for the `index`th item of the given kind in block `block`.
+///
+/// The kind, block and index are packed into non-overlapping bit fields, so `block` must fit
+/// in 32 bits and `index` in 24 bits for the values to remain distinct.
+fn derive_felt(kind: Kind, block: u64, index: u64) -> Felt {
+    assert!(block < 1 << 32, "block {block} does not fit the derived value");
+    assert!(index < 1 << 24, "index {index} does not fit the derived value");
+    Felt::from_u64(((kind as u64) << 56) | (block << 24) | index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chain_is_consistent() {
+        let blocks = ChainBuilder::new(3)
+            .with_contracts_per_block(2)
+            .with_invokes_per_block(3)
+            .build();
+
+        assert_eq!(blocks.len(), 3);
+        assert_eq!(blocks[0].header.header.parent_hash, BlockHash::ZERO);
+        for (parent, child) in blocks.iter().zip(blocks.iter().skip(1)) {
+            assert_eq!(child.header.header.parent_hash, parent.header.header.hash);
+            assert_eq!(child.header.header.number, parent.header.header.number + 1);
+        }
+
+        for block in &blocks {
+            assert_eq!(block.header.header.transaction_count, 5);
+            assert_eq!(block.header.header.event_count, 3);
+            assert_eq!(block.state_update.block_hash, block.header.header.hash);
+            assert_eq!(block.state_update.contract_updates.len(), 2);
+            for (idx, (transaction, receipt)) in block.transaction_data.iter().enumerate() {
+                assert_eq!(receipt.transaction_hash, transaction.hash);
+                assert_eq!(receipt.transaction_index.get(), idx as u64);
+            }
+        }
+
+        // The chain is deterministic.
+        assert_eq!(
+            blocks,
+            ChainBuilder::new(3)
+                .with_contracts_per_block(2)
+                .with_invokes_per_block(3)
+                .build()
+        );
+    }
+
+    #[test]
+    fn insert() {
+        let storage = Storage::in_memory().unwrap();
+
+        let mut calls = 0;
+        let blocks = ChainBuilder::new(2)
+            .with_commitments(|_, number, _| {
+                calls += 1;
+                Ok((
+                    StorageCommitment(Felt::from_u64(number.get() + 1)),
+                    ClassCommitment::ZERO,
+                ))
+            })
+            .insert(&storage)
+            .unwrap();
+        assert_eq!(calls, 2);
+
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+
+        for block in &blocks {
+            let header = tx
+                .block_header(block.header.header.number.into())
+                .unwrap()
+                .unwrap();
+            assert_eq!(header, block.header.header);
+            assert_eq!(
+                header.storage_commitment,
+                StorageCommitment(Felt::from_u64(header.number.get() + 1))
+            );
+
+            let state_update = tx.state_update(header.number.into()).unwrap().unwrap();
+            assert_eq!(state_update, block.state_update);
+        }
+        assert_eq!(
+            blocks[1].state_update.parent_state_commitment,
+            blocks[0].header.header.state_commitment
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "does not fit")]
+    fn derived_index_out_of_range() {
+        derive_felt(Kind::Calldata, 0, 1 << 24);
+    }
+}
@@ -25,4 +25,61 @@ rpc_call '{
         ]
     },
     "id": 0
-}'
\ No newline at end of file
+}'
+rpc_call '{
+    "jsonrpc": "2.0",
+    "method": "pathfinder_getStateDiff",
+    "params": {
+        "from_block_id": {"block_number": 100},
+        "to_block_id": "latest"
+    },
+    "id": 0
+}'
+
+rpc_call '{
+    "jsonrpc": "2.0",
+    "method": "pathfinder_getContractStateRoot",
+    "params": {
+        "block_id": "latest",
+        "contract_address": "0x23371b227eaecd8e8920cd429d2cd0f3fee6abaacca08d3ab82a7cdd"
+    },
+    "id": 0
+}'
+
+rpc_call '{
+    "jsonrpc": "2.0",
+    "method": "pathfinder_getContractHistory",
+    "params": {
+        "contract_address": "0x23371b227eaecd8e8920cd429d2cd0f3fee6abaacca08d3ab82a7cdd"
+    },
+    "id": 0
+}'
+
+rpc_call '{
+    "jsonrpc": "2.0",
+    "method": "pathfinder_getEventsByTransaction",
+    "params": {
+        "transaction_hash": "0x1"
+    },
+    "id": 0
+}'
+
+rpc_call '{
+    "jsonrpc": "2.0",
+    "method": "pathfinder_getBlockRange",
+    "params": {
+        "start": 100,
+        "end": 200,
+        "scope": "HEADERS"
+    },
+    "id": 0
+}'
+
+rpc_call '{
+    "jsonrpc": "2.0",
+    "method": "pathfinder_getL2ToL1MessageProof",
+    "params": {
+        "message_hash": "0x1"
+    },
+    "id": 0
+}'
@@ -0,0 +1,21 @@
+//! Parses arbitrary bytes and strings as felts, and checks that valid felts round-trip.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use pathfinder_crypto::Felt;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(felt) = Felt::from_be_slice(data) {
+        assert_eq!(Felt::from_be_bytes(felt.to_be_bytes()), Ok(felt));
+    }
+
+    if let Ok(bytes) = <[u8; 32]>::try_from(data) {
+        let _ = Felt::from_be_bytes(bytes);
+    }
+
+    if let Ok(hex_str) = std::str::from_utf8(data) {
+        if let Ok(felt) = Felt::from_hex_str(hex_str) {
+            assert_eq!(Felt::from_hex_str(&felt.to_hex_str()), Ok(felt));
+        }
+    }
+});
@@ -0,0 +1,14 @@
+//! Hashes pairs of felts built from arbitrary bytes.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use pathfinder_crypto::hash::pedersen_hash;
+use pathfinder_crypto::Felt;
+
+fuzz_target!(|data: [[u8; 32]; 2]| {
+    let (Ok(a), Ok(b)) = (Felt::from_be_bytes(data[0]), Felt::from_be_bytes(data[1])) else {
+        return;
+    };
+
+    let _ = pedersen_hash(a, b);
+});
@@ -0,0 +1,23 @@
+//! Hashes sequences of felts built from arbitrary bytes.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use pathfinder_crypto::hash::{poseidon_hash, poseidon_hash_many};
+use pathfinder_crypto::{Felt, MontFelt};
+
+fuzz_target!(|data: Vec<[u8; 32]>| {
+    let Ok(felts) = data
+        .into_iter()
+        .map(Felt::from_be_bytes)
+        .collect::<Result<Vec<_>, _>>()
+    else {
+        return;
+    };
+    let felts = felts.into_iter().map(MontFelt::from).collect::<Vec<_>>();
+
+    let _ = poseidon_hash_many(&felts);
+
+    if let [x, y, ..] = felts[..] {
+        let _ = poseidon_hash(x, y);
+    }
+});
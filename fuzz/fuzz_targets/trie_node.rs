@@ -0,0 +1,16 @@
+//! Decodes arbitrary bytes as stored trie nodes, and checks that decoded nodes round-trip.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use pathfinder_storage::{fuzz_decode_node, fuzz_encode_node};
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(node) = fuzz_decode_node(data) else {
+        return;
+    };
+
+    let mut buffer = vec![0u8; 256];
+    let length = fuzz_encode_node(&node, &mut buffer).expect("Encoding a decoded node");
+    let decoded = fuzz_decode_node(&buffer[..length]).expect("Decoding an encoded node");
+    assert_eq!(decoded, node);
+});